@@ -0,0 +1,10 @@
+//! Title extraction must not panic on malformed HTML
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webpage_save::fuzz;
+
+fuzz_target!(|html: &str| {
+    let _ = fuzz::extract_title(html);
+});
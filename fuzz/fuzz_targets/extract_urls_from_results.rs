@@ -0,0 +1,22 @@
+//! Search result extraction must not panic on arbitrary response text, and must only
+//! queue web addresses for conversion
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use webpage_save::fuzz;
+
+fuzz_target!(|search_results: &str| {
+    let Ok(results) = fuzz::extract_urls_from_results(search_results) else {
+        return;
+    };
+    for result in results {
+        let url = url::Url::parse(&result.url).expect("extracted URLs parse");
+        assert!(matches!(url.scheme(), "http" | "https"), "{}", result.url);
+        assert!(
+            !result.url.ends_with(['.', ',', ';', '"', '\'', '>', '*']),
+            "trailing punctuation in {}",
+            result.url
+        );
+    }
+});
@@ -0,0 +1,32 @@
+//! A sanitized title must name a single file inside the output directory
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::path::{Component, Path};
+use webpage_save::fuzz;
+
+fuzz_target!(|title: &str| {
+    let stem = fuzz::sanitize_filename(title);
+    assert!(!stem.contains(['/', '\\']), "separator in {:?}", stem);
+    assert!(
+        !stem.chars().any(char::is_control),
+        "control character in {:?}",
+        stem
+    );
+    assert!(
+        !stem.starts_with('.') && !stem.ends_with('.'),
+        "leading or trailing dot in {:?}",
+        stem
+    );
+
+    // Output files are named `<stem>.<extension>`
+    for file in [stem.clone(), format!("{}.md", stem)] {
+        let components: Vec<_> = Path::new(&file).components().collect();
+        assert!(
+            file.is_empty() || matches!(components[..], [Component::Normal(_)]),
+            "{:?} is not a single file name",
+            file
+        );
+    }
+});
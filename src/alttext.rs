@@ -0,0 +1,447 @@
+//! Alt text of images in Markdown output
+//!
+//! The HTML-to-Markdown conversion only keeps an image's `alt` attribute, and drops
+//! images inside links and `<picture>` elements altogether. Before conversion,
+//! [`normalize_images`] rewrites every image as a plain `<img>` with its source and the
+//! best description the page offers: the `alt` attribute, an ARIA label, the title, or
+//! the caption of its figure. Lazy-loaded images get their real source instead of the
+//! placeholder, and linked images become linked Markdown images.
+//!
+//! Images without any description can be described by a vision model: an
+//! [`AltTextClient`] asks an OpenAI-compatible `/chat/completions` endpoint for a short
+//! alt text for each of them. Images marked as decorative with an empty `alt`
+//! attribute are left without one.
+
+use crate::extract;
+use anyhow::Result;
+use reqwest::Client;
+use select::document::Document;
+use select::node::{Data, Node};
+use select::predicate::Name;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{info, warn};
+use url::Url;
+
+/// Default OpenAI-compatible API base URL
+pub const DEFAULT_ALT_TEXT_ENDPOINT: &str = "https://api.openai.com/v1";
+
+/// Default vision model
+pub const DEFAULT_ALT_TEXT_MODEL: &str = "gpt-4o-mini";
+
+/// Default number of images described per page
+pub const DEFAULT_MAX_IMAGES: usize = 20;
+
+/// Instruction sent with each image
+const PROMPT: &str = "Write alt text for this image from a web page: one short, factual \
+    sentence describing what it shows, for readers who cannot see it. Reply with the \
+    alt text only.";
+
+/// Attributes lazy-loading scripts keep the real image source in
+const LAZY_SOURCES: &[&str] = &["data-src", "data-lazy-src", "data-original"];
+
+/// Client for an OpenAI-compatible chat completions endpoint with a vision model
+#[derive(Clone)]
+pub struct AltTextClient {
+    client: Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    max_images: usize,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+impl AltTextClient {
+    /// Create a client for the API at `endpoint` using `model`
+    ///
+    /// `endpoint` is the API base URL (e.g. `https://api.openai.com/v1`);
+    /// `/chat/completions` is appended unless it is already present.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new(endpoint: &str, model: &str) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(60))
+            .user_agent("webpage-save-alt-text/1.0")
+            .build()?;
+
+        let endpoint = endpoint.trim_end_matches('/');
+        let endpoint = if endpoint.ends_with("/chat/completions") {
+            endpoint.to_string()
+        } else {
+            format!("{}/chat/completions", endpoint)
+        };
+
+        Ok(Self {
+            client,
+            endpoint,
+            model: model.to_string(),
+            api_key: None,
+            max_images: DEFAULT_MAX_IMAGES,
+        })
+    }
+
+    /// Authenticate requests with a bearer token
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Describe at most this many images per page
+    pub fn with_max_images(mut self, max_images: usize) -> Self {
+        self.max_images = max_images;
+        self
+    }
+
+    /// Generate alt text for an image
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, the endpoint returns an error status, or
+    /// the response has no text
+    pub async fn describe(&self, image_url: &str) -> Result<String> {
+        let body = json!({
+            "model": self.model,
+            "max_tokens": 100,
+            "messages": [{
+                "role": "user",
+                "content": [
+                    { "type": "text", "text": PROMPT },
+                    { "type": "image_url", "image_url": { "url": image_url } },
+                ],
+            }],
+        });
+        let mut request = self.client.post(&self.endpoint).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Alt text request failed with status {}: {}",
+                status,
+                body.chars().take(500).collect::<String>()
+            ));
+        }
+
+        let response = response.json::<ChatResponse>().await?;
+        let text = response
+            .choices
+            .into_iter()
+            .find_map(|choice| choice.message.content)
+            .map(|text| collapse_whitespace(text.trim().trim_matches('"')))
+            .unwrap_or_default();
+        if text.is_empty() {
+            return Err(anyhow::anyhow!("The alt text response has no text"));
+        }
+        Ok(text)
+    }
+
+    /// Generate alt text for the images of a page that have none, keyed by their source
+    /// as written in the page
+    ///
+    /// Images that cannot be described are left without alt text.
+    pub async fn describe_images(
+        &self,
+        html: &str,
+        base_url: Option<&str>,
+    ) -> HashMap<String, String> {
+        let base = base_url.and_then(|url| Url::parse(url).ok());
+        let mut descriptions = HashMap::new();
+        for source in images_lacking_alt(html).into_iter().take(self.max_images) {
+            let resolved = match &base {
+                Some(base) => base.join(&source).ok(),
+                None => Url::parse(&source).ok(),
+            };
+            // Only images the endpoint can fetch; data URIs are often placeholders
+            let Some(resolved) = resolved.filter(|url| matches!(url.scheme(), "http" | "https"))
+            else {
+                continue;
+            };
+            match self.describe(resolved.as_str()).await {
+                Ok(text) => {
+                    info!("Generated alt text for {}: {}", resolved, text);
+                    descriptions.insert(source, text);
+                }
+                Err(e) => warn!("Failed to generate alt text for {}: {}", resolved, e),
+            }
+        }
+        descriptions
+    }
+}
+
+/// The sources of the images in HTML that have no description, in page order without
+/// duplicates
+pub fn images_lacking_alt(html: &str) -> Vec<String> {
+    let document = Document::from(html);
+    let mut sources: Vec<String> = Vec::new();
+    for image in document.find(Name("img")) {
+        if image.attr("alt").is_some() || description(&image).is_some() {
+            continue;
+        }
+        if let Some(source) = source(&image) {
+            if !sources.contains(&source) {
+                sources.push(source);
+            }
+        }
+    }
+    sources
+}
+
+/// Rewrite the images in HTML so that their source and alt text survive the conversion
+/// to Markdown, using generated alt text for images without a description
+pub fn normalize_images(html: &str, generated: &HashMap<String, String>) -> String {
+    let document = Document::from(html);
+    let body = document.find(Name("body")).next();
+    let Some(body) = body.filter(|body| body.find(Name("img")).next().is_some()) else {
+        return html.to_string();
+    };
+    let mut out = String::with_capacity(html.len());
+    for child in body.children() {
+        write_node(&child, generated, false, &mut out);
+    }
+    out
+}
+
+/// Serialize a node as HTML, rewriting the images in it
+fn write_node(node: &Node, generated: &HashMap<String, String>, in_link: bool, out: &mut String) {
+    let name = node.name().unwrap_or_default();
+    match name {
+        "img" => return write_image(node, generated, in_link, out),
+        // Only the fallback image of a picture is kept, with the best source
+        "picture" => {
+            if let Some(image) = node.find(Name("img")).next() {
+                write_image(&image, generated, in_link, out);
+            }
+            return;
+        }
+        _ => {}
+    }
+    let has_images = matches!(node.data(), Data::Element(..))
+        && name != "noscript"
+        && node.find(Name("img")).next().is_some();
+    if !has_images {
+        return extract::write_html(node, &|_| false, out);
+    }
+
+    out.push('<');
+    out.push_str(name);
+    for (attr, value) in node.attrs() {
+        out.push_str(&format!(" {}=\"{}\"", attr, extract::escape(value, true)));
+    }
+    out.push('>');
+    for child in node.children() {
+        write_node(&child, generated, in_link || name == "a", out);
+    }
+    out.push_str(&format!("</{}>", name));
+}
+
+/// An image as a plain `<img>`, or as Markdown text inside links, which the conversion
+/// would otherwise drop
+fn write_image(image: &Node, generated: &HashMap<String, String>, in_link: bool, out: &mut String) {
+    let Some(source) = source(image) else {
+        return;
+    };
+    let alt = image
+        .attr("alt")
+        .map(collapse_whitespace)
+        .filter(|alt| !alt.is_empty())
+        .or_else(|| description(image))
+        .or_else(|| generated.get(&source).cloned())
+        .unwrap_or_default();
+    let alt = alt
+        .replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]");
+    let source = source
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29");
+
+    match in_link {
+        true => out.push_str(&extract::escape(&format!("![{}]({})", alt, source), false)),
+        false => out.push_str(&format!(
+            "<img src=\"{}\" alt=\"{}\">",
+            extract::escape(&source, true),
+            extract::escape(&alt, true)
+        )),
+    }
+}
+
+/// The source of an image: the real source of lazy-loaded images, otherwise its `src`,
+/// otherwise the first candidate of its or its picture's `srcset`
+pub(crate) fn source(image: &Node) -> Option<String> {
+    let src = image
+        .attr("src")
+        .map(str::trim)
+        .filter(|src| !src.is_empty());
+    let lazy = LAZY_SOURCES
+        .iter()
+        .filter_map(|attr| image.attr(attr))
+        .map(str::trim)
+        .find(|src| !src.is_empty());
+    let srcset = std::iter::once(*image)
+        .chain(
+            image
+                .parent()
+                .filter(|parent| parent.name() == Some("picture"))
+                .into_iter()
+                .flat_map(|picture| picture.find(Name("source")).collect::<Vec<_>>()),
+        )
+        .filter_map(|node| node.attr("srcset"))
+        .filter_map(|srcset| srcset.split(',').next()?.split_whitespace().next())
+        .next();
+    match (src, lazy) {
+        (Some(src), Some(lazy)) if src.starts_with("data:") => Some(lazy),
+        (Some(src), _) => Some(src),
+        (None, lazy) => lazy.or(srcset),
+    }
+    .map(str::to_string)
+}
+
+/// A description of an image other than its `alt` attribute: its ARIA label, its
+/// title, or the caption of the figure it is in
+fn description(image: &Node) -> Option<String> {
+    let attribute = ["aria-label", "title"]
+        .iter()
+        .filter_map(|attr| image.attr(attr))
+        .map(collapse_whitespace)
+        .find(|text| !text.is_empty());
+    attribute.or_else(|| {
+        let figure = std::iter::successors(image.parent(), Node::parent)
+            .find(|node| node.name() == Some("figure"))?;
+        // Only figures of a single image, so the caption describes this one
+        if figure.find(Name("img")).count() != 1 {
+            return None;
+        }
+        let caption = collapse_whitespace(figure.find(Name("figcaption")).next()?.text());
+        (!caption.is_empty()).then_some(caption)
+    })
+}
+
+fn collapse_whitespace(text: impl AsRef<str>) -> String {
+    text.as_ref()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const HTML: &str = r#"<article>
+        <p>Intro <img src="plain.png" alt="A [small] chart"></p>
+        <p><a href="/full.jpg"><img src="thumb.jpg" alt="Team photo"></a></p>
+        <picture><source srcset="hero.webp 1x, hero@2x.webp 2x"><img src="hero.jpg" title="Hero shot"></picture>
+        <figure><img src="data:image/gif;base64,R0lGOD" data-src="lazy map.png"><figcaption>Route  map</figcaption></figure>
+        <p><img src="/photos/cat.jpg"> <img src="spacer.gif" alt=""></p>
+    </article>"#;
+
+    #[test]
+    fn test_normalize_images() {
+        assert_eq!(images_lacking_alt(HTML), vec!["/photos/cat.jpg"]);
+
+        let generated = HashMap::from([(
+            "/photos/cat.jpg".to_string(),
+            "A cat asleep on a keyboard".to_string(),
+        )]);
+        let markdown = mdka::from_html(&normalize_images(HTML, &generated));
+        for expected in [
+            r"![A \[small\] chart](plain.png)",
+            "[![Team photo](thumb.jpg)](/full.jpg)",
+            "![Hero shot](hero.jpg)",
+            "![Route map](lazy%20map.png)",
+            "![A cat asleep on a keyboard](/photos/cat.jpg)",
+            "![](spacer.gif)",
+        ] {
+            assert!(
+                markdown.contains(expected),
+                "{} not in {}",
+                expected,
+                markdown
+            );
+        }
+
+        let text = "<p>No images &amp; nothing to change</p>";
+        assert_eq!(normalize_images(text, &HashMap::new()), text);
+    }
+
+    #[tokio::test]
+    async fn test_describe_images() -> Result<()> {
+        // Minimal OpenAI-compatible server answering a single request
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = format!("http://{}/v1", listener.local_addr()?);
+        let server = std::thread::spawn(move || -> std::io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            loop {
+                let read = stream.read(&mut buffer)?;
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                    let length = headers
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|length| length.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+
+            let body =
+                r#"{"choices":[{"message":{"content":"\"A cat asleep\non a keyboard.\""}}]}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )?;
+            Ok(String::from_utf8_lossy(&request).into_owned())
+        });
+
+        let client =
+            AltTextClient::new(&endpoint, "vision-model")?.with_api_key(Some("secret".to_string()));
+        let descriptions = client
+            .describe_images(HTML, Some("https://example.com/blog/post"))
+            .await;
+        assert_eq!(
+            descriptions,
+            HashMap::from([(
+                "/photos/cat.jpg".to_string(),
+                "A cat asleep on a keyboard.".to_string()
+            )])
+        );
+
+        let request = server.join().unwrap()?;
+        assert!(request.starts_with("POST /v1/chat/completions"));
+        assert!(request.contains("authorization: Bearer secret"));
+        assert!(request.contains("https://example.com/photos/cat.jpg"));
+        Ok(())
+    }
+}
@@ -0,0 +1,479 @@
+//! Single-file SQLite archive of captured pages
+//!
+//! This module stores captures (source URL, title, capture time, Markdown content,
+//! and the generated PDF either inline as a blob or as a path to a file) in one
+//! SQLite database. Markdown content is indexed with FTS5 so the archive can be
+//! searched without unpacking it, making it a portable alternative to a directory
+//! of loose output files. RFC 3161 timestamps of the stored content are kept
+//! alongside, so the time a capture existed by can be proven from the archive alone.
+
+use crate::atomic;
+use crate::timestamp::TimestampToken;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS captures (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    title TEXT,
+    captured_at TEXT NOT NULL,
+    markdown TEXT,
+    pdf BLOB,
+    pdf_path TEXT
+);
+CREATE INDEX IF NOT EXISTS captures_url ON captures (url);
+CREATE VIRTUAL TABLE IF NOT EXISTS captures_fts USING fts5 (
+    title, url, markdown, content = 'captures', content_rowid = 'id'
+);
+CREATE TABLE IF NOT EXISTS timestamps (
+    capture_id INTEGER NOT NULL REFERENCES captures (id),
+    part TEXT NOT NULL,
+    tsa_url TEXT NOT NULL,
+    sha256 TEXT NOT NULL,
+    gen_time TEXT,
+    reply BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS timestamps_capture ON timestamps (capture_id);
+";
+
+/// The PDF of a capture, stored in the database or as a separate file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PdfBlob {
+    /// PDF bytes stored in the database
+    Inline(Vec<u8>),
+    /// Path to a PDF file stored outside the database
+    Path(PathBuf),
+}
+
+/// A page capture to store in or read from the archive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    /// The URL (or local path) the page was captured from
+    pub url: String,
+    /// The page title, if known
+    pub title: Option<String>,
+    /// When the page was captured
+    pub captured_at: DateTime<Utc>,
+    /// The Markdown conversion of the page
+    pub markdown: Option<String>,
+    /// The PDF rendering of the page
+    pub pdf: Option<PdfBlob>,
+}
+
+impl Capture {
+    /// A capture of the given URL taken now, without content
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            title: None,
+            captured_at: Utc::now(),
+            markdown: None,
+            pdf: None,
+        }
+    }
+
+    /// Set the page title
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the Markdown content
+    pub fn with_markdown(mut self, markdown: impl Into<String>) -> Self {
+        self.markdown = Some(markdown.into());
+        self
+    }
+
+    /// Set the PDF rendering
+    pub fn with_pdf(mut self, pdf: PdfBlob) -> Self {
+        self.pdf = Some(pdf);
+        self
+    }
+}
+
+/// Summary of an archived capture, without its content
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveEntry {
+    /// The capture's ID in the archive
+    pub id: i64,
+    /// The URL (or local path) the page was captured from
+    pub url: String,
+    /// The page title, if known
+    pub title: Option<String>,
+    /// When the page was captured
+    pub captured_at: DateTime<Utc>,
+    /// Whether the capture has Markdown content
+    pub has_markdown: bool,
+    /// Whether the capture has a PDF
+    pub has_pdf: bool,
+}
+
+/// A full-text search match
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveHit {
+    /// The matching capture
+    pub entry: ArchiveEntry,
+    /// An excerpt of the matching content, with matches wrapped in `[` and `]`
+    pub snippet: String,
+}
+
+/// A trusted timestamp of part of a capture
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivedTimestamp {
+    /// Part of the capture timestamped, `markdown` or `pdf`
+    pub part: String,
+    /// URL of the authority that granted the timestamp
+    pub tsa_url: String,
+    /// The timestamp
+    pub token: TimestampToken,
+}
+
+/// A SQLite database of page captures with full-text search
+pub struct Archive {
+    conn: Connection,
+}
+
+impl Archive {
+    /// Open the archive at the given path, creating it if it does not exist
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or initialized
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Store a capture, returning its ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capture cannot be written
+    pub fn insert(&mut self, capture: &Capture) -> Result<i64> {
+        let (pdf, pdf_path) = match &capture.pdf {
+            Some(PdfBlob::Inline(data)) => (Some(data.as_slice()), None),
+            Some(PdfBlob::Path(path)) => (None, Some(path.to_string_lossy().into_owned())),
+            None => (None, None),
+        };
+
+        let tx = self.conn.transaction()?;
+        tx.execute(
+            "INSERT INTO captures (url, title, captured_at, markdown, pdf, pdf_path)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                capture.url,
+                capture.title,
+                capture.captured_at.to_rfc3339(),
+                capture.markdown,
+                pdf,
+                pdf_path
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        tx.execute(
+            "INSERT INTO captures_fts (rowid, title, url, markdown) VALUES (?1, ?2, ?3, ?4)",
+            params![id, capture.title, capture.url, capture.markdown],
+        )?;
+        tx.commit()?;
+        Ok(id)
+    }
+
+    /// List the most recent captures, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be read
+    pub fn list(&self, limit: usize) -> Result<Vec<ArchiveEntry>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, url, title, captured_at, markdown IS NOT NULL,
+                    pdf IS NOT NULL OR pdf_path IS NOT NULL
+             FROM captures ORDER BY captured_at DESC, id DESC LIMIT ?1",
+        )?;
+        let entries = statement
+            .query_map(params![limit as i64], entry_from_row)?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(entries)
+    }
+
+    /// The IDs of all captures, oldest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be read
+    pub fn ids(&self) -> Result<Vec<i64>> {
+        let mut statement = self.conn.prepare("SELECT id FROM captures ORDER BY id")?;
+        let ids = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(ids)
+    }
+
+    /// Whether a capture of the URL taken at the given time is already stored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be read
+    pub fn contains(&self, url: &str, captured_at: &DateTime<Utc>) -> Result<bool> {
+        let found = self
+            .conn
+            .query_row(
+                "SELECT 1 FROM captures WHERE url = ?1 AND captured_at = ?2",
+                params![url, captured_at.to_rfc3339()],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(found.is_some())
+    }
+
+    /// Search titles, URLs, and Markdown content using an FTS5 query, best matches first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is malformed or the database cannot be read
+    pub fn search(&self, query: &str, limit: usize) -> Result<Vec<ArchiveHit>> {
+        let mut statement = self.conn.prepare(
+            "SELECT c.id, c.url, c.title, c.captured_at, c.markdown IS NOT NULL,
+                    c.pdf IS NOT NULL OR c.pdf_path IS NOT NULL,
+                    snippet(captures_fts, -1, '[', ']', '…', 16)
+             FROM captures_fts JOIN captures c ON c.id = captures_fts.rowid
+             WHERE captures_fts MATCH ?1 ORDER BY rank LIMIT ?2",
+        )?;
+        let hits = statement
+            .query_map(params![query, limit as i64], |row| {
+                Ok(ArchiveHit {
+                    entry: entry_from_row(row)?,
+                    snippet: row.get(6)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(hits)
+    }
+
+    /// Read a capture with its content, or None if there is no capture with that ID
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be read
+    pub fn get(&self, id: i64) -> Result<Option<Capture>> {
+        let capture = self
+            .conn
+            .query_row(
+                "SELECT url, title, captured_at, markdown, pdf, pdf_path
+                 FROM captures WHERE id = ?1",
+                params![id],
+                |row| {
+                    let pdf: Option<Vec<u8>> = row.get(4)?;
+                    let pdf_path: Option<String> = row.get(5)?;
+                    Ok(Capture {
+                        url: row.get(0)?,
+                        title: row.get(1)?,
+                        captured_at: parse_timestamp(row.get(2)?, 2)?,
+                        markdown: row.get(3)?,
+                        pdf: pdf
+                            .map(PdfBlob::Inline)
+                            .or_else(|| pdf_path.map(|path| PdfBlob::Path(PathBuf::from(path)))),
+                    })
+                },
+            )
+            .optional()?;
+        Ok(capture)
+    }
+
+    /// Store a timestamp of part of a capture
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the timestamp cannot be written
+    pub fn add_timestamp(
+        &mut self,
+        id: i64,
+        part: &str,
+        tsa_url: &str,
+        token: &TimestampToken,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO timestamps (capture_id, part, tsa_url, sha256, gen_time, reply)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                id,
+                part,
+                tsa_url,
+                token.sha256,
+                token.gen_time.map(|time| time.to_rfc3339()),
+                token.reply
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The timestamps of a capture, in the order they were stored
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be read
+    pub fn timestamps(&self, id: i64) -> Result<Vec<ArchivedTimestamp>> {
+        let mut statement = self.conn.prepare(
+            "SELECT part, tsa_url, sha256, gen_time, reply FROM timestamps
+             WHERE capture_id = ?1 ORDER BY rowid",
+        )?;
+        let timestamps = statement
+            .query_map(params![id], |row| {
+                let gen_time: Option<String> = row.get(3)?;
+                Ok(ArchivedTimestamp {
+                    part: row.get(0)?,
+                    tsa_url: row.get(1)?,
+                    token: TimestampToken {
+                        sha256: row.get(2)?,
+                        reply: row.get(4)?,
+                        gen_time: gen_time.map(|time| parse_timestamp(time, 3)).transpose()?,
+                    },
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(timestamps)
+    }
+}
+
+fn entry_from_row(row: &rusqlite::Row) -> rusqlite::Result<ArchiveEntry> {
+    Ok(ArchiveEntry {
+        id: row.get(0)?,
+        url: row.get(1)?,
+        title: row.get(2)?,
+        captured_at: parse_timestamp(row.get(3)?, 3)?,
+        has_markdown: row.get(4)?,
+        has_pdf: row.get(5)?,
+    })
+}
+
+fn parse_timestamp(value: String, column: usize) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&value)
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(
+                column,
+                rusqlite::types::Type::Text,
+                Box::new(e),
+            )
+        })
+}
+
+/// Write a PDF into a blob directory, named after its content hash, returning its path
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub fn write_pdf_file(dir: &Path, data: &[u8]) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{:x}.pdf", Sha256::digest(data)));
+    atomic::write_blocking(&path, data)?;
+    Ok(path)
+}
+
+/// Extract the title from a Markdown document's first top-level heading
+pub fn markdown_title(markdown: &str) -> Option<String> {
+    markdown
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .map(|title| title.trim().to_string())
+        .filter(|title| !title.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Timelike;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_insert_and_get() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let mut archive = Archive::open(&temp_dir.path().join("nested/archive.db"))?;
+
+        let capture = Capture::new("https://example.com")
+            .with_title("Example")
+            .with_markdown("# Example\n\nHello world")
+            .with_pdf(PdfBlob::Inline(b"%PDF-1.4".to_vec()));
+        let id = archive.insert(&capture)?;
+
+        let stored = archive.get(id)?.unwrap();
+        assert_eq!(stored.url, capture.url);
+        assert_eq!(stored.markdown, capture.markdown);
+        assert_eq!(stored.pdf, capture.pdf);
+        assert_eq!(
+            stored.captured_at.timestamp(),
+            capture.captured_at.timestamp()
+        );
+        assert!(archive.get(id + 1)?.is_none());
+
+        let token = TimestampToken {
+            sha256: format!("{:x}", Sha256::digest(b"%PDF-1.4")),
+            reply: vec![0x30, 0x03, 0x02, 0x01, 0x00],
+            gen_time: Some(capture.captured_at.with_nanosecond(0).unwrap()),
+        };
+        archive.add_timestamp(id, "pdf", "https://tsa.example/tsr", &token)?;
+        let timestamps = archive.timestamps(id)?;
+        assert_eq!(timestamps.len(), 1);
+        assert_eq!(timestamps[0].part, "pdf");
+        assert_eq!(timestamps[0].token, token);
+        assert!(archive.timestamps(id + 1)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_and_list() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("archive.db");
+        let mut archive = Archive::open(&path)?;
+        archive.insert(
+            &Capture::new("https://a.example")
+                .with_title("Rust async")
+                .with_markdown("Futures and executors in Rust"),
+        )?;
+        archive.insert(
+            &Capture::new("https://b.example")
+                .with_title("Gardening")
+                .with_pdf(PdfBlob::Path(PathBuf::from("b.pdf"))),
+        )?;
+
+        // Reopening keeps the existing captures
+        let archive = Archive::open(&path)?;
+        let hits = archive.search("executors", 10)?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry.url, "https://a.example");
+        assert!(hits[0].snippet.contains("[executors]"));
+        assert!(archive.search("executors AND", 10).is_err());
+
+        let entries = archive.list(10)?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title.as_deref(), Some("Gardening"));
+        assert!(entries[0].has_pdf && !entries[0].has_markdown);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_pdf_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let first = write_pdf_file(temp_dir.path(), b"%PDF-1.4")?;
+        let second = write_pdf_file(temp_dir.path(), b"%PDF-1.4")?;
+        assert_eq!(first, second);
+        assert_eq!(std::fs::read(&first)?, b"%PDF-1.4");
+        Ok(())
+    }
+
+    #[test]
+    fn test_markdown_title() {
+        assert_eq!(
+            markdown_title("# Title\n\n## Sub").as_deref(),
+            Some("Title")
+        );
+        assert_eq!(markdown_title("no heading"), None);
+    }
+}
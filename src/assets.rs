@@ -0,0 +1,281 @@
+//! Local copies of the images of Markdown output
+//!
+//! Markdown keeps images as links to the site they came from, and those links break
+//! when the site moves things or goes away. [`MarkdownImages`] downloads the images a
+//! Markdown document shows and rewrites its image links, either to files in an images
+//! directory next to the document or to `data:` URIs inlined in it. Saved files are
+//! named after a hash of their content, so an image shown several times, or served
+//! under several URLs, is saved once. Images that cannot be downloaded keep their
+//! links.
+
+use crate::atomic;
+use crate::epub::image_type;
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use regex::{Captures, Regex};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use tracing::{debug, warn};
+use url::Url;
+
+/// Largest image downloaded unless another limit is given
+pub const DEFAULT_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Suffix of the images directory of a Markdown file: `page.md` keeps its images in
+/// `page_images/`
+pub const IMAGES_DIR_SUFFIX: &str = "_images";
+
+/// A Markdown image: alt text, source, and optional title
+static IMAGE_LINK: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"!\[((?:\\.|[^\]\\])*)\]\(([^)\s]+)((?:\s+"[^"]*")?)\)"#).expect("valid regex")
+});
+
+/// Where downloaded images are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageEmbedding {
+    /// As files in the images directory next to the Markdown file
+    Download,
+    /// In the Markdown itself, as base64 `data:` URIs
+    Inline,
+}
+
+/// Downloads the images of Markdown documents and points their links at the copies
+#[derive(Debug, Clone)]
+pub struct MarkdownImages {
+    embedding: ImageEmbedding,
+    max_bytes: u64,
+}
+
+impl MarkdownImages {
+    /// Keep images as chosen, up to [`DEFAULT_MAX_BYTES`] per image
+    pub fn new(embedding: ImageEmbedding) -> Self {
+        Self {
+            embedding,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+
+    /// Leave images larger than this many bytes as links
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Where images are kept
+    pub fn embedding(&self) -> ImageEmbedding {
+        self.embedding
+    }
+
+    /// The images directory of a Markdown file
+    pub fn images_dir(markdown_path: &Path) -> PathBuf {
+        let stem = markdown_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        markdown_path.with_file_name(format!("{}{}", stem, IMAGES_DIR_SUFFIX))
+    }
+
+    /// Download the images of a Markdown document and rewrite their links
+    ///
+    /// Relative sources are resolved against the page URL. Downloaded images need the
+    /// path the Markdown is saved to, and are left as links without one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an image cannot be saved; images that cannot be downloaded
+    /// are only logged
+    pub async fn localize(
+        &self,
+        client: &Client,
+        markdown: &str,
+        page_url: Option<&str>,
+        markdown_path: Option<&Path>,
+    ) -> Result<String> {
+        let dir = match (self.embedding, markdown_path) {
+            (ImageEmbedding::Download, Some(path)) => Some(Self::images_dir(path)),
+            (ImageEmbedding::Download, None) => return Ok(markdown.to_string()),
+            (ImageEmbedding::Inline, _) => None,
+        };
+        let base = page_url.and_then(|url| Url::parse(url).ok());
+
+        // Each source is fetched once, however often the document shows it
+        let mut targets: HashMap<String, Option<String>> = HashMap::new();
+        for captures in IMAGE_LINK.captures_iter(markdown) {
+            let source = &captures[2];
+            if targets.contains_key(source) {
+                continue;
+            }
+            let url = match &base {
+                Some(base) => base.join(source).ok(),
+                None => Url::parse(source).ok(),
+            }
+            .filter(|url| matches!(url.scheme(), "http" | "https"));
+            let target = match url {
+                Some(url) => match self.fetch(client, &url).await {
+                    Ok(data) => Some(self.store(data, dir.as_deref()).await?),
+                    Err(e) => {
+                        warn!("Keeping the link to image {}: {}", url, e);
+                        None
+                    }
+                },
+                None => None,
+            };
+            targets.insert(source.to_string(), target);
+        }
+
+        Ok(IMAGE_LINK
+            .replace_all(markdown, |captures: &Captures| {
+                match targets.get(&captures[2]) {
+                    Some(Some(target)) => {
+                        format!("![{}]({}{})", &captures[1], target, &captures[3])
+                    }
+                    _ => captures[0].to_string(),
+                }
+            })
+            .into_owned())
+    }
+
+    /// Download an image, giving up as soon as it turns out larger than the limit or
+    /// not an image
+    async fn fetch(&self, client: &Client, url: &Url) -> Result<Vec<u8>> {
+        let mut response = client.get(url.clone()).send().await?.error_for_status()?;
+        let too_large =
+            || anyhow::anyhow!("larger than {}", crate::space::format_size(self.max_bytes));
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_bytes)
+        {
+            return Err(too_large());
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (data.len() + chunk.len()) as u64 > self.max_bytes {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+        if image_type(&data).is_none() {
+            return Err(anyhow::anyhow!("Not a PNG, JPEG, GIF, WebP, or SVG image"));
+        }
+        Ok(data)
+    }
+
+    /// Keep an image in the directory or as a `data:` URI, returning its new link
+    async fn store(&self, data: Vec<u8>, dir: Option<&Path>) -> Result<String> {
+        let (media_type, extension) = image_type(&data).unwrap_or(("image/png", "png"));
+        let Some(dir) = dir else {
+            return Ok(format!(
+                "data:{};base64,{}",
+                media_type,
+                BASE64.encode(&data)
+            ));
+        };
+        let hash = format!("{:x}", Sha256::digest(&data));
+        let name = format!("{}.{}", &hash[..16], extension);
+        let path = dir.join(&name);
+        if tokio::fs::try_exists(&path).await? {
+            debug!("Image {} already saved", path.display());
+        } else {
+            tokio::fs::create_dir_all(dir).await?;
+            atomic::write(&path, &data).await?;
+        }
+        let dir_name = dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(format!("{}/{}", dir_name, name)
+            .replace(' ', "%20")
+            .replace('(', "%28")
+            .replace(')', "%29"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const PIXEL: &[u8] = b"GIF89a\x01\x00\x01\x00\x00\x00\x00;";
+
+    #[tokio::test]
+    async fn test_localize_images() -> Result<()> {
+        // Serves the same image under two paths, and nothing else
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let page_url = format!("http://{}/blog/post", listener.local_addr()?);
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    match stream.read(&mut buffer) {
+                        Ok(0) | Err(_) => break,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                let request = String::from_utf8_lossy(&request);
+                let (status, body) = match request.split_whitespace().nth(1) {
+                    Some("/img/dot.gif" | "/cdn/dot.gif") => ("200 OK", PIXEL),
+                    Some("/page.html") => ("200 OK", &b"<html></html>"[..]),
+                    _ => ("404 Not Found", &b""[..]),
+                };
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nconnection: close\r\ncontent-length: {}\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(body);
+            }
+        });
+
+        let markdown = "# Post\n\n![A dot](/img/dot.gif \"Dot\") and ![Same \\] dot](../cdn/dot.gif)\n\n\
+             [![Linked](missing.png)](/page.html) ![Page](/page.html) ![Inline](data:image/gif;base64,AA==)\n\n\
+             ![A dot](/img/dot.gif)\n";
+        let client = Client::new();
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("post.md");
+
+        let images = MarkdownImages::new(ImageEmbedding::Download);
+        let localized = images
+            .localize(&client, markdown, Some(&page_url), Some(&path))
+            .await?;
+        let name = format!("{:x}", Sha256::digest(PIXEL))[..16].to_string();
+        assert_eq!(
+            localized,
+            format!(
+                "# Post\n\n![A dot](post_images/{name}.gif \"Dot\") and ![Same \\] dot](post_images/{name}.gif)\n\n\
+                 [![Linked](missing.png)](/page.html) ![Page](/page.html) ![Inline](data:image/gif;base64,AA==)\n\n\
+                 ![A dot](post_images/{name}.gif)\n"
+            )
+        );
+        let saved: Vec<_> = std::fs::read_dir(dir.path().join("post_images"))?.collect();
+        assert_eq!(saved.len(), 1);
+        assert_eq!(
+            images
+                .localize(&client, markdown, Some(&page_url), None)
+                .await?,
+            markdown
+        );
+
+        let inlined = MarkdownImages::new(ImageEmbedding::Inline)
+            .localize(&client, "![A dot](/img/dot.gif)", Some(&page_url), None)
+            .await?;
+        assert_eq!(
+            inlined,
+            format!("![A dot](data:image/gif;base64,{})", BASE64.encode(PIXEL))
+        );
+
+        let limited = MarkdownImages::new(ImageEmbedding::Inline)
+            .with_max_bytes(4)
+            .localize(&client, "![A dot](/img/dot.gif)", Some(&page_url), None)
+            .await?;
+        assert_eq!(limited, "![A dot](/img/dot.gif)");
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+//! Crash-safe output file writes
+//!
+//! Output files are first written to a temporary file in the destination directory and
+//! then renamed over the destination, so an interrupted run leaves either the previous
+//! file or the complete new one, never a truncated file. The temporary file is removed
+//! if writing fails.
+
+use anyhow::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// Create a temporary file next to `path`, to be renamed over it with [`persist`]
+///
+/// # Errors
+///
+/// Returns an error if the path has no file name or the temporary file cannot be created
+pub fn temp_file_for(path: &Path) -> Result<NamedTempFile> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid output path: {}", path.display()))?;
+    let dir = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let prefix = format!(".{}.", file_name.to_string_lossy());
+    let mut builder = tempfile::Builder::new();
+    builder.prefix(&prefix).suffix(".tmp");
+    // Temporary files are private by default; outputs get the usual umask-based mode
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        builder.permissions(std::fs::Permissions::from_mode(0o666));
+    }
+    Ok(builder.tempfile_in(dir)?)
+}
+
+/// Flush a temporary file created by [`temp_file_for`] to disk and rename it to `path`
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be synced or renamed
+pub fn persist(file: NamedTempFile, path: &Path) -> Result<()> {
+    file.as_file().sync_all()?;
+    file.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+/// Atomically replace the contents of a file
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub fn write_blocking(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut file = temp_file_for(path)?;
+    file.write_all(contents)?;
+    persist(file, path)
+}
+
+/// Atomically replace the contents of a file without blocking the async runtime
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be written
+pub async fn write(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let path: PathBuf = path.to_path_buf();
+    let contents = contents.as_ref().to_vec();
+    tokio::task::spawn_blocking(move || write_blocking(&path, &contents)).await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_write_replaces_file() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("page.md");
+        std::fs::write(&path, "old")?;
+
+        write(&path, "new").await?;
+        assert_eq!(std::fs::read_to_string(&path)?, "new");
+        assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 1);
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_write_uses_default_mode() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("page.pdf");
+        let plain = temp_dir.path().join("plain.pdf");
+        write(&path, "data").await?;
+        std::fs::write(&plain, "data")?;
+
+        let mode =
+            |path: &Path| -> Result<u32> { Ok(std::fs::metadata(path)?.permissions().mode()) };
+        assert_eq!(mode(&path)?, mode(&plain)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abandoned_write_keeps_original() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("page.pdf");
+        std::fs::write(&path, "complete")?;
+
+        let mut file = temp_file_for(&path)?;
+        file.write_all(b"trunc")?;
+        drop(file);
+
+        assert_eq!(std::fs::read_to_string(&path)?, "complete");
+        assert_eq!(std::fs::read_dir(temp_dir.path())?.count(), 1);
+        Ok(())
+    }
+}
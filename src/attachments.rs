@@ -0,0 +1,301 @@
+//! Downloads of files linked from captured pages
+//!
+//! Articles often link the files they are about: the paper as a PDF, the dataset as a
+//! CSV or ZIP file. A [`LinkDownloader`] finds the links of a page whose path ends in
+//! one of the chosen extensions and saves the linked files into an attachments
+//! directory next to the capture, so they are archived together. Files larger than the
+//! size limit are skipped, as are links beyond the per-page limit. Downloaded images
+//! can be transcoded, scaled, and stripped of metadata by an [`ImagePolicy`].
+
+use crate::atomic;
+use crate::images::{ImageFormat, ImagePolicy};
+use crate::readlater::{checked, http_client};
+use anyhow::Result;
+use percent_encoding::percent_decode_str;
+use reqwest::Client;
+use select::document::Document;
+use select::predicate::Name;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use url::Url;
+
+/// Largest file downloaded unless another limit is given
+pub const DEFAULT_MAX_BYTES: u64 = 25 * 1024 * 1024;
+
+/// Most files downloaded per page unless another limit is given
+pub const DEFAULT_MAX_FILES: usize = 20;
+
+/// A linked file that was not downloaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkippedLink {
+    /// The link target
+    pub url: String,
+    /// Why it was skipped, e.g. `larger than 25.0 MB`
+    pub reason: String,
+}
+
+/// Files downloaded for one page
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Attachments {
+    /// The saved files
+    pub saved: Vec<PathBuf>,
+    /// The matching links that were not saved
+    pub skipped: Vec<SkippedLink>,
+}
+
+/// Downloads the files a page links to that have one of a set of extensions
+#[derive(Clone)]
+pub struct LinkDownloader {
+    client: Client,
+    extensions: Vec<String>,
+    max_bytes: u64,
+    max_files: usize,
+    image_policy: ImagePolicy,
+}
+
+impl LinkDownloader {
+    /// Create a downloader for links to files with these extensions (such as `pdf` or
+    /// `.csv`, case-insensitive), limited to [`DEFAULT_MAX_BYTES`] per file and
+    /// [`DEFAULT_MAX_FILES`] per page
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no extension is given or the HTTP client cannot be created
+    pub fn new(extensions: &[String]) -> Result<Self> {
+        let extensions: Vec<String> = extensions
+            .iter()
+            .map(|ext| ext.trim().trim_start_matches('.').to_lowercase())
+            .filter(|ext| !ext.is_empty())
+            .collect();
+        if extensions.is_empty() {
+            return Err(anyhow::anyhow!("No file extensions to download links for"));
+        }
+        Ok(Self {
+            client: http_client()?,
+            extensions,
+            max_bytes: DEFAULT_MAX_BYTES,
+            max_files: DEFAULT_MAX_FILES,
+            image_policy: ImagePolicy::new(),
+        })
+    }
+
+    /// Skip files larger than this many bytes
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// Download at most this many files per page
+    pub fn with_max_files(mut self, max_files: usize) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Apply this policy to downloaded images
+    pub fn with_image_policy(mut self, policy: ImagePolicy) -> Self {
+        self.image_policy = policy;
+        self
+    }
+
+    /// Fetch the HTML of a page to look for links in, when it is not cached
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be fetched
+    pub async fn fetch_page(&self, url: &str) -> Result<String> {
+        let response = self.client.get(url).send().await?;
+        Ok(checked(response, "Fetching page").await?.text().await?)
+    }
+
+    /// The HTTP(S) links of a page to files with one of the extensions, resolved against
+    /// the page URL, in page order without duplicates
+    pub fn links(&self, html: &str, page_url: &str) -> Vec<Url> {
+        let Ok(base) = Url::parse(page_url) else {
+            return Vec::new();
+        };
+        let document = Document::from(html);
+        let mut seen = HashSet::new();
+        document
+            .find(Name("a"))
+            .filter_map(|link| link.attr("href"))
+            .filter_map(|href| base.join(href.trim()).ok())
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .map(|mut url| {
+                url.set_fragment(None);
+                url
+            })
+            .filter(|url| extension(url).is_some_and(|ext| self.extensions.contains(&ext)))
+            .filter(|url| seen.insert(url.to_string()))
+            .collect()
+    }
+
+    /// Download the matching links of a page into a directory, created when there is
+    /// something to save
+    ///
+    /// Links that cannot be downloaded are reported as skipped rather than failing the
+    /// whole page.
+    pub async fn download(&self, html: &str, page_url: &str, dir: &Path) -> Attachments {
+        let mut attachments = Attachments::default();
+        let mut names = HashSet::new();
+        for (index, url) in self.links(html, page_url).into_iter().enumerate() {
+            if index >= self.max_files {
+                attachments.skipped.push(SkippedLink {
+                    url: url.to_string(),
+                    reason: format!("more than {} files linked", self.max_files),
+                });
+                continue;
+            }
+            match self.download_file(&url, dir, &mut names).await {
+                Ok(path) => {
+                    info!("Downloaded {} to {}", url, path.display());
+                    attachments.saved.push(path);
+                }
+                Err(e) => {
+                    warn!("Skipping linked file {}: {}", url, e);
+                    attachments.skipped.push(SkippedLink {
+                        url: url.to_string(),
+                        reason: e.to_string(),
+                    });
+                }
+            }
+        }
+        attachments
+    }
+
+    /// Download one file into a directory, giving up as soon as it turns out larger than
+    /// the limit
+    async fn download_file(
+        &self,
+        url: &Url,
+        dir: &Path,
+        names: &mut HashSet<String>,
+    ) -> Result<PathBuf> {
+        let response = self.client.get(url.clone()).send().await?;
+        let mut response = checked(response, "Download").await?;
+        let too_large =
+            || anyhow::anyhow!("larger than {}", crate::space::format_size(self.max_bytes));
+        if response
+            .content_length()
+            .is_some_and(|length| length > self.max_bytes)
+        {
+            return Err(too_large());
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await? {
+            if (data.len() + chunk.len()) as u64 > self.max_bytes {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        let mut format = None;
+        if !self.image_policy.is_empty() {
+            let image = self.image_policy.apply(data)?;
+            data = image.data;
+            format = image.format;
+        }
+        tokio::fs::create_dir_all(dir).await?;
+        let path = dir.join(unique_name(url, format, names));
+        atomic::write(&path, data).await?;
+        Ok(path)
+    }
+}
+
+/// The lowercase extension of the last path segment of a URL
+fn extension(url: &Url) -> Option<String> {
+    let name = url.path_segments()?.next_back()?;
+    let (stem, ext) = name.rsplit_once('.')?;
+    (!stem.is_empty()).then(|| ext.to_lowercase())
+}
+
+/// A safe file name for a linked file, not yet taken by another file of the page, with
+/// the extension of its image format if it was transcoded
+fn unique_name(url: &Url, format: Option<ImageFormat>, taken: &mut HashSet<String>) -> String {
+    let decoded = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|name| percent_decode_str(name).decode_utf8_lossy().into_owned())
+        .unwrap_or_default();
+    let name: String = decoded
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let mut name = match name.trim_start_matches('.') {
+        "" => "attachment".to_string(),
+        name => name.to_string(),
+    };
+
+    let (stem, mut ext) = match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => (stem.to_string(), format!(".{}", ext)),
+        _ => (name.clone(), String::new()),
+    };
+    if let Some(format) = format.filter(|format| ImageFormat::from_extension(&ext) != Some(*format))
+    {
+        ext = format!(".{}", format.extension());
+        name = format!("{}{}", stem, ext);
+    }
+    let mut candidate = name;
+    let mut counter = 2;
+    while !taken.insert(candidate.to_lowercase()) {
+        candidate = format!("{}-{}{}", stem, counter, ext);
+        counter += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_links_and_names() -> Result<()> {
+        let downloader = LinkDownloader::new(&["PDF".to_string(), ".csv".to_string()])?;
+        let html = r#"<article>
+            <a href="/papers/Result%20Tables.pdf">Paper</a>
+            <a href="https://data.example.org/v2/data.CSV?download=1#top">Data</a>
+            <a href="/papers/Result%20Tables.pdf#page=2">Paper again</a>
+            <a href="other/data.csv">Other data</a>
+            <a href="archive.zip">Archive</a>
+            <a href="/pdf">Not a file</a>
+            <a href="mailto:me@example.com?subject=x.pdf">Mail</a>
+        </article>"#;
+        let links: Vec<String> = downloader
+            .links(html, "https://example.com/blog/post.html")
+            .iter()
+            .map(Url::to_string)
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                "https://example.com/papers/Result%20Tables.pdf",
+                "https://data.example.org/v2/data.CSV?download=1",
+                "https://example.com/blog/other/data.csv",
+            ]
+        );
+
+        let mut taken = HashSet::new();
+        let names: Vec<String> = links
+            .iter()
+            .map(|link| unique_name(&Url::parse(link).unwrap(), None, &mut taken))
+            .collect();
+        assert_eq!(names, vec!["Result Tables.pdf", "data.CSV", "data-2.csv"]);
+        let photo = Url::parse("https://example.com/photo.webp?w=800")?;
+        assert_eq!(
+            unique_name(&photo, Some(ImageFormat::Png), &mut taken),
+            "photo.png"
+        );
+        let photo = Url::parse("https://example.com/img/photo.JPEG")?;
+        assert_eq!(
+            unique_name(&photo, Some(ImageFormat::Jpeg), &mut taken),
+            "photo.JPEG"
+        );
+
+        assert!(LinkDownloader::new(&[" ".to_string()]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,95 @@
+//! API key storage in the operating system keyring
+//!
+//! This module stores the Brave Search API key in the platform credential store
+//! (macOS Keychain, Secret Service on Linux, Windows Credential Manager) so that
+//! it does not have to live in shell history or environment files.
+
+use anyhow::Result;
+use keyring::Entry;
+
+/// Keyring service name under which credentials are stored
+pub const KEYRING_SERVICE: &str = "webpage-save";
+
+/// Keyring account name for the Brave Search API key
+pub const BRAVE_API_KEY_ACCOUNT: &str = "brave-api-key";
+
+/// Store the Brave API key in the OS keyring, replacing any existing key
+///
+/// # Errors
+///
+/// Returns an error if the key is empty or the keyring is unavailable
+pub fn store_api_key(api_key: &str) -> Result<()> {
+    let api_key = api_key.trim();
+    if api_key.is_empty() {
+        return Err(anyhow::anyhow!("API key must not be empty"));
+    }
+
+    brave_api_key_entry()?.set_password(api_key)?;
+    Ok(())
+}
+
+/// Load the Brave API key from the OS keyring
+///
+/// # Returns
+///
+/// Returns `None` if no key has been stored
+///
+/// # Errors
+///
+/// Returns an error if the keyring is unavailable
+pub fn load_api_key() -> Result<Option<String>> {
+    match brave_api_key_entry()?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Delete the Brave API key from the OS keyring
+///
+/// # Returns
+///
+/// Returns `true` if a key was deleted and `false` if none was stored
+///
+/// # Errors
+///
+/// Returns an error if the keyring is unavailable
+pub fn delete_api_key() -> Result<bool> {
+    match brave_api_key_entry()?.delete_credential() {
+        Ok(()) => Ok(true),
+        Err(keyring::Error::NoEntry) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Mask an API key for display, keeping only the last four characters
+pub fn mask_api_key(api_key: &str) -> String {
+    let chars: Vec<char> = api_key.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+
+    let visible: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}{}", "*".repeat(chars.len() - 4), visible)
+}
+
+fn brave_api_key_entry() -> Result<Entry> {
+    Ok(Entry::new(KEYRING_SERVICE, BRAVE_API_KEY_ACCOUNT)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_api_key() {
+        assert_eq!(mask_api_key("abcdefgh"), "****efgh");
+        assert_eq!(mask_api_key("abcd"), "****");
+        assert_eq!(mask_api_key(""), "");
+    }
+
+    #[test]
+    fn test_store_empty_api_key() {
+        assert!(store_api_key("   ").is_err());
+    }
+}
@@ -0,0 +1,256 @@
+//! Batch conversion of URL lists
+//!
+//! `webpage-save batch` reads a list of URLs, one per line, and converts them to PDF
+//! and/or Markdown concurrently: PDFs in up to a fixed number of tabs of one browser
+//! (see [`PdfGeneratorPool`]), and Markdown with the same limit on fetches in flight.
+//! The outcome of every URL is collected into a [`BatchReport`], which lists the files
+//! written and the errors of the formats that failed.
+
+use crate::markdown::MarkdownGenerator;
+use crate::pdf::{PdfGenerator, PdfGeneratorPool};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
+
+/// File name of the report written into the output directory by default
+pub const REPORT_FILE: &str = "batch_report.json";
+
+/// The URLs of a list: one per line, without blank lines, `#` comments, and repeats
+pub fn parse_url_list(text: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|line| seen.insert(line.to_string()))
+        .map(str::to_string)
+        .collect()
+}
+
+/// The outcome of converting one URL of a batch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchEntry {
+    /// The URL converted
+    pub url: String,
+    /// Files written for the URL
+    pub files: Vec<PathBuf>,
+    /// Errors of the formats that failed, e.g. `PDF: navigation timed out`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+    /// Time spent converting the URL, in milliseconds
+    pub elapsed_ms: u64,
+}
+
+impl BatchEntry {
+    /// Whether every requested format was converted
+    pub fn is_success(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// The outcome of a batch, in the order of its URL list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchReport {
+    /// When the batch finished
+    pub generated_at: DateTime<Utc>,
+    /// URLs converted to every requested format
+    pub succeeded: usize,
+    /// URLs that failed in at least one format
+    pub failed: usize,
+    /// The outcome of each URL
+    pub entries: Vec<BatchEntry>,
+}
+
+impl BatchReport {
+    /// Summarize the outcomes of a batch
+    pub fn new(entries: Vec<BatchEntry>) -> Self {
+        let succeeded = entries.iter().filter(|entry| entry.is_success()).count();
+        Self {
+            generated_at: Utc::now(),
+            succeeded,
+            failed: entries.len() - succeeded,
+            entries,
+        }
+    }
+
+    /// Write the report as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub async fn write_json(&self, path: &Path) -> Result<()> {
+        crate::atomic::write(path, serde_json::to_string_pretty(self)?).await
+    }
+}
+
+/// Converts lists of URLs to PDF and/or Markdown, up to a fixed number at a time
+pub struct BatchConverter {
+    pdf: Option<PdfGeneratorPool>,
+    markdown: Option<Arc<MarkdownGenerator>>,
+    concurrency: usize,
+}
+
+impl BatchConverter {
+    /// Create a converter working on at most `concurrency` URLs at a time
+    ///
+    /// No output is produced until a PDF or Markdown generator is added.
+    pub fn new(concurrency: usize) -> Self {
+        Self {
+            pdf: None,
+            markdown: None,
+            concurrency: concurrency.max(1),
+        }
+    }
+
+    /// Convert every URL to PDF with this generator
+    pub fn with_pdf_generator(mut self, generator: PdfGenerator) -> Self {
+        self.pdf = Some(PdfGeneratorPool::new(generator, self.concurrency));
+        self
+    }
+
+    /// Convert every URL to Markdown with this generator
+    pub fn with_markdown_generator(mut self, generator: MarkdownGenerator) -> Self {
+        self.markdown = Some(Arc::new(generator));
+        self
+    }
+
+    /// Convert URLs into files in `output_dir` named after each URL (see
+    /// [`crate::integration::filename_from_url`]), with a numeric suffix for URLs that
+    /// would share a name
+    ///
+    /// A URL failing in one format does not stop the others or the rest of the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the output directory cannot be created
+    pub async fn convert(&self, urls: &[String], output_dir: &Path) -> Result<BatchReport> {
+        tokio::fs::create_dir_all(output_dir).await?;
+        let permits = Arc::new(Semaphore::new(self.concurrency));
+        let total = urls.len();
+        let mut used_stems = HashSet::new();
+        let mut entries: Vec<Option<BatchEntry>> = urls.iter().map(|_| None).collect();
+        let mut tasks = JoinSet::new();
+        for (index, url) in urls.iter().enumerate() {
+            let stem = match crate::integration::filename_from_url(url) {
+                Ok(base) => {
+                    let mut stem = base.clone();
+                    let mut suffix = 1;
+                    while !used_stems.insert(stem.clone()) {
+                        suffix += 1;
+                        stem = format!("{}_{}", base, suffix);
+                    }
+                    stem
+                }
+                Err(e) => {
+                    entries[index] = Some(BatchEntry {
+                        url: url.clone(),
+                        files: Vec::new(),
+                        errors: vec![format!("Invalid URL: {}", e)],
+                        elapsed_ms: 0,
+                    });
+                    continue;
+                }
+            };
+            let pdf = self
+                .pdf
+                .clone()
+                .map(|pool| (pool, output_dir.join(format!("{}.pdf", stem))));
+            let markdown = self
+                .markdown
+                .clone()
+                .map(|generator| (generator, output_dir.join(format!("{}.md", stem))));
+            let permits = permits.clone();
+            let url = url.clone();
+            tasks.spawn(async move {
+                let _permit = permits.acquire_owned().await;
+                info!("Converting {} ({}/{})", url, index + 1, total);
+                let started = Instant::now();
+                let mut files = Vec::new();
+                let mut errors = Vec::new();
+                if let Some((pool, path)) = pdf {
+                    match pool.url_to_pdf(&url, Some(&path)).await {
+                        Ok(_) => files.push(path),
+                        Err(e) => errors.push(format!("PDF: {}", e)),
+                    }
+                }
+                if let Some((generator, path)) = markdown {
+                    match generator.url_to_markdown(&url, Some(&path)).await {
+                        Ok(_) => files.push(path),
+                        Err(e) => errors.push(format!("Markdown: {}", e)),
+                    }
+                }
+                let entry = BatchEntry {
+                    url,
+                    files,
+                    errors,
+                    elapsed_ms: elapsed_ms(started.elapsed()),
+                };
+                (index, entry)
+            });
+        }
+
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, entry)) => entries[index] = Some(entry),
+                Err(e) => warn!("Batch conversion task failed: {}", e),
+            }
+        }
+        let entries = entries
+            .into_iter()
+            .zip(urls)
+            .map(|(entry, url)| {
+                entry.unwrap_or_else(|| BatchEntry {
+                    url: url.clone(),
+                    files: Vec::new(),
+                    errors: vec!["Conversion was interrupted".to_string()],
+                    elapsed_ms: 0,
+                })
+            })
+            .collect();
+        Ok(BatchReport::new(entries))
+    }
+}
+
+fn elapsed_ms(elapsed: Duration) -> u64 {
+    elapsed.as_millis().try_into().unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_url_list_and_report() {
+        let list = "# Reading list\nhttps://example.com/a\n\n  https://example.com/b  \n\
+                    https://example.com/a\n# https://example.com/skipped\n";
+        assert_eq!(
+            parse_url_list(list),
+            vec!["https://example.com/a", "https://example.com/b"]
+        );
+
+        let report = BatchReport::new(vec![
+            BatchEntry {
+                url: "https://example.com/a".to_string(),
+                files: vec![PathBuf::from("out/example.com_a.pdf")],
+                errors: Vec::new(),
+                elapsed_ms: 1200,
+            },
+            BatchEntry {
+                url: "https://example.com/b".to_string(),
+                files: Vec::new(),
+                errors: vec!["PDF: timeout".to_string()],
+                elapsed_ms: 30000,
+            },
+        ]);
+        assert_eq!((report.succeeded, report.failed), (1, 1));
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json["entries"][0].get("errors").is_none());
+        assert_eq!(json["entries"][1]["errors"][0], "PDF: timeout");
+    }
+}
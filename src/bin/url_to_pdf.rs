@@ -2,515 +2,6854 @@
 //!
 //! This binary provides a command-line interface for converting web pages to PDF format
 //! using headless Chrome and for performing web, news, and local searches using Brave Search API.
+//!
+//! The CLI is organized into subcommands:
+//!
+//! - `convert` converts one or more URLs or local HTML files to PDF, Markdown, or EPUB
+//! - `batch` converts the URLs listed in a file concurrently and reports which failed
+//! - `search` performs a Brave search and prints the results
+//! - `search-to-pdf` performs a Brave search and converts the results
+//! - `render` re-renders saved HTML/MHTML snapshots to PDF and/or Markdown
+//! - `embed` computes embeddings for converted Markdown and writes them as JSONL
+//! - `archive` manages captures stored in a SQLite archive, and records WARC files
+//! - `read-later` archives articles saved in Pocket, Instapaper, or Readwise Reader
+//! - `bookmarks` archives bookmarks from Pinboard or Raindrop.io
+//! - `newsletter` backs up the posts of Substack, Ghost, or Buttondown newsletters
+//! - `thread` archives Hacker News or Reddit discussions
+//! - `crawl` saves a whole documentation site as one document
+//! - `docs` saves the docs.rs documentation of a crate
+//! - `login` saves a logged-in browser session for later captures
+//! - `redact` removes sensitive text from saved Markdown, text, and PDFs
+//! - `index` writes browsable indexes of an output directory
+//! - `price-history` shows how the prices of saved product pages changed
+//! - `changelog` checks a changelog page for new versions
+//! - `diff` shows how a page changed between two captures
+//! - `evidence` captures a page with a network log and a hashed capture report
+//! - `auth` manages the Brave API key stored in the OS keyring
+//!
+//! `webpage-save --help` and `webpage-save <SUBCOMMAND> --help` describe each in full.
+//!
+//! For backward compatibility, `webpage-save <URL>` without a subcommand is treated as `convert`.
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
-use tracing::{error, info};
-use webpage_save::integration::{NamingStrategy, OutputFormat as IntegrationOutputFormat, SearchToPdfClient, SearchToPdfConfig};
-use webpage_save::markdown::MarkdownGenerator;
-use webpage_save::pdf::PdfGenerator;
-use webpage_save::search::{BraveSearchClient, SearchConfig, SearchType};
+use clap::{Args, Parser, Subcommand};
+use headless_chrome::Browser;
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tracing::{error, info, warn};
+use webpage_save::alttext::{self, AltTextClient};
+use webpage_save::archive::{self, Archive, Capture, PdfBlob};
+use webpage_save::assets::{ImageEmbedding, MarkdownImages};
+use webpage_save::atomic;
+use webpage_save::attachments::{self, LinkDownloader};
+use webpage_save::auth;
+use webpage_save::batch::{self, BatchConverter};
+use webpage_save::blocklist::DomainBlocklist;
+use webpage_save::bookmarks::{BookmarkService, PinboardClient, RaindropClient};
+use webpage_save::branding::Branding;
+use webpage_save::bundle;
+use webpage_save::cache::{PageCache, SearchCache};
+use webpage_save::changelog::{self, ReleaseNote, ReleaseNotifier};
+use webpage_save::chunk::{self, ChunkConfig, ChunkUnit};
+use webpage_save::cookies::{BrowserCookies, CookieBrowser};
+use webpage_save::crawl::{self, DocsCrawler, DocsPreset};
+use webpage_save::delivery::{Delivered, Delivery};
+use webpage_save::deterministic::DeterministicRendering;
+use webpage_save::diff::CaptureDiff;
+use webpage_save::dns::{self, DnsSnapshot};
+use webpage_save::embedding::{self, EmbeddingClient};
+use webpage_save::epub::EpubGenerator;
+use webpage_save::evidence::{self, CaptureReport, EvidenceFile, NetworkLog};
+use webpage_save::extract::{ContentMode, ExtractConfig, Selector};
+use webpage_save::fonts::ReaderFont;
+use webpage_save::frontmatter::FrontMatterFormat;
+use webpage_save::highlight::{self, CodeHighlighter};
+use webpage_save::i18n::{Language, Message};
+use webpage_save::images::{ImageFormat, ImagePolicy};
+use webpage_save::index;
+use webpage_save::integration::{
+    FormatOutcome, NamingStrategy, OutputFormat as IntegrationOutputFormat, SearchToPdfClient,
+    SearchToPdfConfig, filename_from_url,
+};
+use webpage_save::manifest::{self, OutputManifest, OutputSlot};
+use webpage_save::markdown::{self, MarkdownConfig, MarkdownGenerator, MarkdownPage};
+use webpage_save::newsletter::{NewsletterClient, NewsletterPlatform};
+use webpage_save::paper::{Paper, PrintScale};
+use webpage_save::pdf::{self, Media, PdfConfig, PdfGenerator, ScreenshotFormat};
+use webpage_save::pii::PiiScanner;
+use webpage_save::places::{self, PlaceMaps};
+use webpage_save::printview::PrintViews;
+use webpage_save::products::{self, PricePoint};
+use webpage_save::profiles::SiteProfiles;
+use webpage_save::proxy::ProxyConfig;
+use webpage_save::reader::{ReaderMode, Typography, TypographyPreset};
+use webpage_save::readlater::{
+    self, Article, InstapaperClient, ListFilter, ReadLater, ReadwiseClient,
+};
+use webpage_save::recording::{HttpRecorder, RecordingMode};
+use webpage_save::redact::{self, Redactor};
+use webpage_save::render;
+use webpage_save::request::RequestOptions;
+use webpage_save::resolver::{DnsResolver, DnsServer, parse_override, parse_unix_socket};
+use webpage_save::search::{BraveSearchClient, KeyStatus, SearchConfig, SearchType};
+use webpage_save::security::SecurityPosture;
+use webpage_save::session::{self, LoginWindow, SessionStore};
+use webpage_save::site;
+use webpage_save::snippets;
+use webpage_save::social::SocialThreads;
+use webpage_save::space;
+use webpage_save::stats::RunStats;
+use webpage_save::threads::{ThreadClient, ThreadSource};
+use webpage_save::timeouts::{self, AdaptiveTimeouts};
+use webpage_save::timestamp::{TimestampAuthority, TimestampToken};
+use webpage_save::translate::{self, TranslationService, Translator};
+use webpage_save::versions;
+use webpage_save::viewport::Viewport;
+use webpage_save::virusscan::{self, Quarantined, ScanHook};
+use webpage_save::wait::WaitStrategy;
+use webpage_save::warc;
+use webpage_save::wikipedia::{Wikipedia, WikipediaUrl};
 
 #[derive(Parser)]
 #[command(name = "webpage-save")]
 #[command(about = "Convert URLs to PDF/Markdown using headless Chrome or perform Brave searches")]
 #[command(version)]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
     #[command(subcommand)]
     command: Option<Commands>,
 
-    /// URL to convert to PDF (when no subcommand is used)
-    #[arg(value_name = "URL")]
-    url: Option<String>,
-
-    /// Output file path (optional, defaults to hostname.pdf or hostname.md)
-    #[arg(short, long, value_name = "FILE")]
-    output: Option<PathBuf>,
-
-    /// Output format (pdf or markdown)
-    #[arg(short, long, value_enum, default_value = "pdf")]
-    format: OutputFormat,
+    /// Convert options used when no subcommand is given (`webpage-save <URL>`)
+    #[command(flatten)]
+    convert: ConvertArgs,
 
     /// Verbose output
-    #[arg(short, long)]
+    #[arg(short, long, global = true, env = "WEBPAGE_SAVE_VERBOSE")]
     verbose: bool,
 
-    /// Wait time in seconds before generating PDF (for dynamic content)
-    #[arg(short, long, default_value = "2")]
-    wait: u64,
+    /// Language of the conversion messages and of dates on cover pages and reader-mode
+    /// PDFs (defaults to the language of the locale, or English; other messages are in
+    /// English)
+    #[arg(long, global = true, value_enum, env = "WEBPAGE_SAVE_LANG")]
+    lang: Option<LanguageArg>,
+
+    /// Proxy to load pages and search through: http://, https://, socks5://, or
+    /// socks5h://, with optional user:password@ (defaults to HTTPS_PROXY or ALL_PROXY)
+    #[arg(long, global = true, value_name = "URL", env = "WEBPAGE_SAVE_PROXY")]
+    proxy: Option<String>,
+
+    /// Nameserver to resolve hosts with instead of the system's: an IP address with an
+    /// optional port, or an https:// DNS-over-HTTPS URL
+    #[arg(
+        long,
+        global = true,
+        value_name = "SERVER",
+        env = "WEBPAGE_SAVE_DNS_SERVER",
+        conflicts_with = "proxy"
+    )]
+    dns_server: Option<String>,
+
+    /// Resolve a host to a fixed address, like a hosts file entry, e.g.
+    /// staging.example.com:203.0.113.7 (repeatable; several addresses separated by
+    /// commas)
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOST:IP",
+        env = "WEBPAGE_SAVE_RESOLVE",
+        conflicts_with = "proxy"
+    )]
+    resolve: Vec<String>,
+
+    /// Serve a host from a Unix socket, e.g. dashboard.internal=/run/dashboard.sock, to
+    /// capture http://dashboard.internal/ (repeatable; only the hosts given are mapped)
+    #[arg(
+        long,
+        global = true,
+        value_name = "HOST=PATH",
+        env = "WEBPAGE_SAVE_UNIX_SOCKET",
+        conflicts_with = "proxy"
+    )]
+    unix_socket: Vec<String>,
+}
+
+/// Language chosen with `--lang` or detected from the locale, set once at startup
+static LANGUAGE: OnceLock<Language> = OnceLock::new();
+
+/// The language of messages and dates
+fn language() -> Language {
+    LANGUAGE.get().copied().unwrap_or_default()
+}
+
+/// Proxy chosen with `--proxy` or read from the environment, set once at startup
+static PROXY: OnceLock<Option<ProxyConfig>> = OnceLock::new();
+
+/// The proxy to load pages and search through, if any
+fn proxy() -> Option<&'static ProxyConfig> {
+    PROXY.get().and_then(Option::as_ref)
+}
+
+/// Resolver chosen with `--dns-server`, `--resolve`, or `--unix-socket`, set once at
+/// startup
+static RESOLVER: OnceLock<Option<DnsResolver>> = OnceLock::new();
+
+/// The resolver to look hosts up with, if not the system's
+fn resolver() -> Option<&'static DnsResolver> {
+    RESOLVER.get().and_then(Option::as_ref)
+}
+
+/// Proxy Chrome is launched with: the `--proxy`, or the resolver's local proxy
+static BROWSER_PROXY: OnceLock<Option<ProxyConfig>> = OnceLock::new();
+
+/// The proxy to launch Chrome with, if any
+fn browser_proxy() -> Option<&'static ProxyConfig> {
+    BROWSER_PROXY.get().and_then(Option::as_ref)
+}
+
+/// A message in the chosen language
+fn tr(message: Message) -> String {
+    message.text(language())
 }
 
 #[derive(Subcommand)]
 enum Commands {
+    /// Convert one or more URLs to PDF and/or Markdown
+    Convert(ConvertArgs),
+    /// Convert the URLs listed in a file (or stdin) concurrently and report which failed
+    Batch(BatchArgs),
     /// Perform a Brave search
-    Search {
-        /// Type of search to perform
-        #[arg(value_enum)]
-        search_type: SearchTypeArg,
+    Search(SearchArgs),
+    /// Search and convert results to PDF/Markdown
+    SearchToPdf(SearchToPdfArgs),
+    /// Re-render saved HTML/MHTML snapshots to PDF and/or Markdown
+    Render(RenderArgs),
+    /// Compute embeddings for converted Markdown and write them as JSONL
+    Embed(EmbedArgs),
+    /// List, search, and read captures stored in a SQLite archive, or record the HTTP
+    /// exchanges of loading a page into a WARC file
+    Archive(ArchiveArgs),
+    /// Archive articles saved in Pocket, Instapaper, or Readwise Reader, or save captures
+    /// back to them
+    #[command(subcommand)]
+    ReadLater(ReadLaterCommands),
+    /// Archive bookmarks from Pinboard or Raindrop.io, optionally noting the local copies
+    /// on them
+    Bookmarks(Box<BookmarksArgs>),
+    /// Back up every post of Substack, Ghost, or Buttondown newsletters, named by publish
+    /// date
+    Newsletter(Box<NewsletterArgs>),
+    /// Archive Hacker News or Reddit discussions as Markdown, along with the articles
+    /// they link to
+    Thread(Box<ThreadArgs>),
+    /// Save a whole mdBook, Docusaurus, Sphinx, or rustdoc documentation site as one
+    /// document, in sidebar order
+    Crawl(CrawlArgs),
+    /// Save the docs.rs documentation of a crate (`crate` or `crate@version`) as one
+    /// document
+    Docs(DocsArgs),
+    /// Log into a site in a visible browser (including two-factor steps) and save the
+    /// session, so later captures of the domain see its pages as the logged-in user
+    Login(LoginArgs),
+    /// Redact email addresses, API keys, names, or other patterns from saved Markdown and
+    /// text, and from PDFs rendered again from their snapshots
+    Redact(RedactArgs),
+    /// Write index.md and a filterable, sortable index.html of the pages saved in an
+    /// output directory
+    Index(IndexArgs),
+    /// Show how the prices of the product pages saved in an output directory changed
+    PriceHistory(PriceHistoryArgs),
+    /// Check a changelog or release notes page for new versions, saving only their
+    /// sections as a note and sending it to a webhook or by email
+    Changelog(ChangelogArgs),
+    /// Show how a page changed between two Markdown captures, or two `--versioned`
+    /// captures of a URL, optionally as HTML or PDF with the changes highlighted
+    Diff(DiffArgs),
+    /// Capture a page for evidentiary use: the PDF, the HTML and WARC as served, the
+    /// network log with addresses and TLS certificates, and a hashed capture report
+    Evidence(EvidenceArgs),
+    /// Manage the Brave API key stored in the OS keyring
+    #[command(subcommand)]
+    Auth(AuthCommands),
+}
 
-        /// Search query
-        query: String,
+#[derive(Subcommand)]
+enum AuthCommands {
+    /// Store the Brave API key in the OS keyring (prompts for it, or reads it from stdin)
+    SetKey,
+    /// Remove the Brave API key from the OS keyring
+    DeleteKey,
+    /// Show whether a Brave API key is stored in the OS keyring
+    Status,
+}
 
-        /// Number of results to return
-        #[arg(short, long)]
-        count: Option<usize>,
+/// Arguments for the `convert` subcommand
+#[derive(Args)]
+struct ConvertArgs {
+    /// URLs, local HTML files, directories, or glob patterns to convert
+    #[arg(value_name = "URL")]
+    urls: Vec<String>,
 
-        /// Pagination offset
-        #[arg(short = 'o', long)]
-        offset: Option<usize>,
+    /// Output file path, or output directory when converting multiple URLs
+    /// (optional, defaults to hostname.pdf or hostname.md)
+    #[arg(short, long, value_name = "PATH", env = "WEBPAGE_SAVE_OUTPUT")]
+    output: Option<PathBuf>,
 
-        /// Country code for news/local searches
-        #[arg(long)]
-        country: Option<String>,
+    /// Output format (pdf, markdown, both, screenshot, or epub)
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value = "pdf",
+        env = "WEBPAGE_SAVE_FORMAT"
+    )]
+    format: OutputFormat,
 
-        /// Language code for news searches
-        #[arg(short, long)]
-        language: Option<String>,
+    /// Wait time in seconds before generating PDF (for dynamic content, 0 disables)
+    #[arg(short, long, default_value = "2", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
 
-        /// Freshness filter for news searches (h, d, w, m, y)
-        #[arg(short, long)]
-        freshness: Option<String>,
+    /// Convert only from previously saved page snapshots, without network access
+    #[arg(long, conflicts_with = "no_cache", env = "WEBPAGE_SAVE_OFFLINE")]
+    offline: bool,
 
-        /// Brave API key (optional, can also use BRAVE_API_KEY environment variable)
-        #[arg(long)]
-        api_key: Option<String>,
-    },
-    /// Search and convert results to PDF/Markdown
-    SearchToPdf {
-        /// Type of search to perform
-        #[arg(value_enum)]
-        search_type: SearchTypeArg,
+    /// Do not save page snapshots for later offline use
+    #[arg(long, env = "WEBPAGE_SAVE_NO_CACHE")]
+    no_cache: bool,
 
-        /// Search query
-        query: String,
+    /// Combine all Markdown output into one document with a table of contents
+    /// (written to --output if it names a file, otherwise merged.md)
+    #[arg(long, env = "WEBPAGE_SAVE_MERGE")]
+    merge: bool,
 
-        /// Maximum number of results to convert to PDF
-        #[arg(short, long, default_value = "5")]
-        max_results: usize,
+    /// Convert URLs again even if the output directory already has them
+    #[arg(long, env = "WEBPAGE_SAVE_FORCE")]
+    force: bool,
 
-        /// Output directory for PDF files
-        #[arg(short, long, default_value = "./pdf_downloads")]
-        output_dir: PathBuf,
+    /// Skip checking for enough free disk space before converting
+    #[arg(long, env = "WEBPAGE_SAVE_NO_SPACE_CHECK")]
+    no_space_check: bool,
 
-        /// Output format (pdf, markdown, or both)
-        #[arg(long, value_enum, default_value = "pdf")]
-        format: OutputFormat,
+    /// Also save the per-domain statistics report of the run as JSON
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_STATS_JSON")]
+    stats_json: Option<PathBuf>,
 
-        /// File naming strategy
-        #[arg(long, value_enum, default_value = "title-domain")]
-        naming: NamingStrategyArg,
+    /// Scan captured pages for likely personal data (emails, phone numbers, national
+    /// IDs) and list the findings per document in the run report
+    #[arg(long, env = "WEBPAGE_SAVE_SCAN_PII")]
+    scan_pii: bool,
 
-        /// Number of search results to return
-        #[arg(short, long)]
-        count: Option<usize>,
+    /// Also download the files pages link to with these extensions (e.g. pdf,zip,csv)
+    /// into an attachments directory next to the output
+    #[arg(
+        long,
+        value_name = "EXTENSIONS",
+        value_delimiter = ',',
+        conflicts_with = "archive",
+        env = "WEBPAGE_SAVE_DOWNLOAD_LINKS"
+    )]
+    download_links: Vec<String>,
 
-        /// Pagination offset
-        #[arg(long)]
-        offset: Option<usize>,
+    /// Largest linked file downloaded with --download-links, in megabytes
+    #[arg(
+        long,
+        value_name = "MB",
+        default_value = "25",
+        env = "WEBPAGE_SAVE_MAX_DOWNLOAD_MB"
+    )]
+    max_download_mb: u64,
 
-        /// Country code for news/local searches
-        #[arg(long)]
-        country: Option<String>,
+    /// Most linked files downloaded per page with --download-links
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = attachments::DEFAULT_MAX_FILES,
+        env = "WEBPAGE_SAVE_MAX_DOWNLOADS"
+    )]
+    max_downloads: usize,
 
-        /// Language code for news searches
-        #[arg(short, long)]
-        language: Option<String>,
+    /// Transcode images downloaded with --download-links (e.g. WebP) into this format
+    #[arg(
+        long,
+        value_enum,
+        requires = "download_links",
+        env = "WEBPAGE_SAVE_IMAGE_FORMAT"
+    )]
+    image_format: Option<ImageFormatArg>,
 
-        /// Freshness filter for news searches (h, d, w, m, y)
-        #[arg(short, long)]
-        freshness: Option<String>,
+    /// Scale down images downloaded with --download-links that are wider or taller
+    /// than this many pixels
+    #[arg(
+        long,
+        value_name = "PX",
+        requires = "download_links",
+        value_parser = clap::value_parser!(u32).range(1..),
+        env = "WEBPAGE_SAVE_MAX_IMAGE_DIMENSION"
+    )]
+    max_image_dimension: Option<u32>,
 
-        /// Brave API key (optional, can also use BRAVE_API_KEY environment variable)
-        #[arg(long)]
-        api_key: Option<String>,
-    },
+    /// Remove EXIF, XMP, and text metadata from images downloaded with --download-links
+    #[arg(
+        long,
+        requires = "download_links",
+        env = "WEBPAGE_SAVE_STRIP_IMAGE_METADATA"
+    )]
+    strip_image_metadata: bool,
+
+    /// Longest page load timeout in seconds for consistently slow sites; timeouts start
+    /// at 30 seconds and double per slow site up to this limit
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value = "120",
+        env = "WEBPAGE_SAVE_MAX_TIMEOUT"
+    )]
+    max_timeout: u64,
+
+    /// Follow "next page" links (rel=next, ?page=2, /page/2/) of paginated articles for
+    /// up to N further pages, stitching all parts into one document
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "0",
+        env = "WEBPAGE_SAVE_FOLLOW_PAGINATION"
+    )]
+    follow_pagination: usize,
+
+    /// Render pages with JavaScript in headless Chrome before converting them to
+    /// Markdown, for single-page applications that are empty without it
+    #[arg(long, env = "WEBPAGE_SAVE_RENDER_JS")]
+    render_js: bool,
+
+    /// Also convert the articles linked from the "See also" sections of Wikipedia
+    /// articles (one level deep)
+    #[arg(long, env = "WEBPAGE_SAVE_WIKIPEDIA_SEE_ALSO")]
+    wikipedia_see_also: bool,
+
+    /// Record every HTTP response of the conversion into this file, for replaying it
+    /// later with --replay
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["offline", "replay"],
+        env = "WEBPAGE_SAVE_RECORD"
+    )]
+    record: Option<PathBuf>,
+
+    /// Answer every HTTP request from a file written by --record instead of the network,
+    /// failing requests that were not recorded
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "offline",
+        env = "WEBPAGE_SAVE_REPLAY"
+    )]
+    replay: Option<PathBuf>,
+
+    /// Write each capture into a timestamped directory per URL, with a `latest` pointer
+    /// to the newest one (uses --output, or the current directory, as the output directory)
+    #[arg(long, conflicts_with_all = ["merge", "archive"], env = "WEBPAGE_SAVE_VERSIONED")]
+    versioned: bool,
+
+    /// Store captures in a single SQLite database instead of writing output files
+    #[arg(
+        long,
+        value_name = "DB",
+        conflicts_with_all = ["output", "merge", "chunk_size"],
+        env = "WEBPAGE_SAVE_ARCHIVE"
+    )]
+    archive: Option<PathBuf>,
+
+    /// Keep archived PDFs as files in this directory, storing only their paths in the database
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "archive",
+        env = "WEBPAGE_SAVE_ARCHIVE_PDF_DIR"
+    )]
+    archive_pdf_dir: Option<PathBuf>,
+
+    /// RFC 3161 timestamp authority to timestamp each saved file with, e.g.
+    /// https://freetsa.org/tsr; the reply is saved next to the file as <file>.tsr, or
+    /// with the capture in the --archive database
+    #[arg(long, value_name = "URL", env = "WEBPAGE_SAVE_TSA_URL")]
+    tsa_url: Option<String>,
+
+    /// Record the TLS certificate chain, HSTS policy, and security headers of each
+    /// captured origin in the output manifest, and flag expired or invalid certificates
+    /// in the run report (needs a format loading pages in Chrome: pdf, both, or
+    /// screenshot)
+    #[arg(long, env = "WEBPAGE_SAVE_SECURITY_REPORT")]
+    security_report: bool,
+
+    /// Record the CNAME chain and A/AAAA addresses each captured host resolves to, at
+    /// capture time, in the output manifest (asking the --dns-server if one is given)
+    #[arg(long, env = "WEBPAGE_SAVE_DNS_SNAPSHOT")]
+    dns_snapshot: bool,
+
+    #[command(flatten)]
+    branding: BrandingArgs,
+
+    /// Start Markdown output with YAML or TOML front matter holding the title, source
+    /// URL, fetch time, author, description, image, word count, and language
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        env = "WEBPAGE_SAVE_FRONT_MATTER"
+    )]
+    front_matter: Option<FrontMatterArg>,
+
+    /// Keep only the text of links in Markdown output, dropping their targets
+    #[arg(long, env = "WEBPAGE_SAVE_STRIP_LINKS")]
+    strip_links: bool,
+
+    #[command(flatten)]
+    chunking: ChunkArgs,
+
+    #[command(flatten)]
+    extraction: ExtractArgs,
+
+    #[command(flatten)]
+    reader: ReaderArgs,
+
+    #[command(flatten)]
+    device: DeviceArgs,
+
+    #[command(flatten)]
+    rendering: RenderingArgs,
+
+    #[command(flatten)]
+    sites: SiteArgs,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[command(flatten)]
+    alt_text: AltTextArgs,
+
+    #[command(flatten)]
+    images: MarkdownImageArgs,
+
+    #[command(flatten)]
+    translation: TranslateArgs,
+
+    /// File names (without extension) for particular URLs in an output directory, set by
+    /// subcommands that know better names than the URL gives
+    #[arg(skip)]
+    stems: HashMap<String, String>,
 }
 
-#[derive(clap::ValueEnum, Clone)]
-enum OutputFormat {
-    Pdf,
-    Markdown,
-    Both,
+/// Arguments for the `search` subcommand
+#[derive(Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct SearchArgs {
+    #[command(subcommand)]
+    command: Option<SearchCommands>,
+
+    /// Type of search to perform
+    #[arg(value_enum, required = true)]
+    search_type: Option<SearchTypeArg>,
+
+    /// Search query
+    #[arg(required = true)]
+    query: Option<String>,
+
+    /// Number of results to return
+    #[arg(short, long, env = "WEBPAGE_SAVE_COUNT")]
+    count: Option<usize>,
+
+    /// Pagination offset
+    #[arg(short = 'o', long, env = "WEBPAGE_SAVE_OFFSET")]
+    offset: Option<usize>,
+
+    /// Country code for news/local searches
+    #[arg(long, env = "WEBPAGE_SAVE_COUNTRY")]
+    country: Option<String>,
+
+    /// Language code for news searches
+    #[arg(short, long, env = "WEBPAGE_SAVE_LANGUAGE")]
+    language: Option<String>,
+
+    /// Freshness filter for news searches (h, d, w, m, y)
+    #[arg(short, long, env = "WEBPAGE_SAVE_FRESHNESS")]
+    freshness: Option<String>,
+
+    /// Print the results as a JSON array of objects with title, url, and description
+    #[arg(long, env = "WEBPAGE_SAVE_SEARCH_JSON")]
+    json: bool,
+
+    /// Brave API key (optional, can also use BRAVE_API_KEY environment variable)
+    #[arg(long, env = "BRAVE_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    #[command(flatten)]
+    cache: CacheArgs,
 }
 
-#[derive(clap::ValueEnum, Clone)]
-enum SearchTypeArg {
-    Web,
-    News,
-    Local,
+/// Search response cache options shared by search commands
+#[derive(Args)]
+struct CacheArgs {
+    /// Bypass the on-disk search response cache and page snapshots
+    #[arg(long, env = "WEBPAGE_SAVE_NO_CACHE")]
+    no_cache: bool,
+
+    /// Use only cached search responses and page snapshots, without network access
+    #[arg(long, conflicts_with = "no_cache", env = "WEBPAGE_SAVE_OFFLINE")]
+    offline: bool,
+
+    /// Time-to-live in seconds for cached search responses
+    #[arg(long, default_value = "86400", env = "WEBPAGE_SAVE_CACHE_TTL")]
+    cache_ttl: u64,
 }
 
-impl From<SearchTypeArg> for SearchType {
-    fn from(arg: SearchTypeArg) -> Self {
-        match arg {
-            SearchTypeArg::Web => SearchType::Web,
-            SearchTypeArg::News => SearchType::News,
-            SearchTypeArg::Local => SearchType::Local,
+impl CacheArgs {
+    /// The page snapshot cache to use, or None when caching is disabled or unavailable
+    fn page_cache(&self) -> Option<PageCache> {
+        page_cache(self.no_cache)
+    }
+
+    /// The search cache to use, or None when caching is disabled or unavailable
+    fn search_cache(&self) -> Option<SearchCache> {
+        if self.no_cache {
+            return None;
+        }
+
+        match SearchCache::in_default_dir(Duration::from_secs(self.cache_ttl)) {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                warn!("Search cache disabled: {}", e);
+                None
+            }
         }
     }
 }
 
-#[derive(clap::ValueEnum, Clone)]
-enum NamingStrategyArg {
-    Title,
-    Domain,
-    Sequential,
-    #[value(name = "title-domain")]
-    TitleDomain,
+/// PDF cover page and header options shared by converting commands
+#[derive(Args)]
+struct BrandingArgs {
+    /// Prepend a cover page with the page title, URL, and capture time to each PDF
+    #[arg(long, env = "WEBPAGE_SAVE_COVER")]
+    cover: bool,
+
+    /// Tera template for the cover page (implies --cover)
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_COVER_TEMPLATE")]
+    cover_template: Option<PathBuf>,
+
+    /// Print a header with the project name, page title, and page numbers on each PDF page
+    #[arg(long, env = "WEBPAGE_SAVE_PAGE_HEADER")]
+    page_header: bool,
+
+    /// Tera template for the page header (implies --page-header)
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_HEADER_TEMPLATE")]
+    header_template: Option<PathBuf>,
+
+    /// Project name shown on the cover page and in the page header
+    #[arg(long, env = "WEBPAGE_SAVE_PROJECT_NAME")]
+    project_name: Option<String>,
+
+    /// Logo image shown on the cover page and in the page header
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_LOGO")]
+    logo: Option<PathBuf>,
 }
 
-impl From<NamingStrategyArg> for NamingStrategy {
-    fn from(arg: NamingStrategyArg) -> Self {
-        match arg {
-            NamingStrategyArg::Title => NamingStrategy::Title,
-            NamingStrategyArg::Domain => NamingStrategy::Domain,
-            NamingStrategyArg::Sequential => NamingStrategy::Sequential,
-            NamingStrategyArg::TitleDomain => NamingStrategy::TitleDomain,
+impl BrandingArgs {
+    /// The branding to apply, or None when neither a cover page nor a header is requested
+    fn branding(&self) -> Result<Option<Branding>> {
+        let cover = self.cover || self.cover_template.is_some();
+        let header = self.page_header || self.header_template.is_some();
+        if !cover && !header {
+            return Ok(None);
         }
+
+        let mut branding = Branding::new().with_language(language());
+        if let Some(project_name) = &self.project_name {
+            branding = branding.with_project_name(project_name);
+        }
+        if let Some(logo) = &self.logo {
+            branding = branding.with_logo(logo)?;
+        }
+        if cover {
+            let template = read_template(self.cover_template.as_deref())?;
+            branding = branding.with_cover(template.as_deref())?;
+        }
+        if header {
+            let template = read_template(self.header_template.as_deref())?;
+            branding = branding.with_header(template.as_deref())?;
+        }
+        Ok(Some(branding))
     }
 }
 
-impl From<OutputFormat> for IntegrationOutputFormat {
-    fn from(arg: OutputFormat) -> Self {
-        match arg {
-            OutputFormat::Pdf => IntegrationOutputFormat::Pdf,
-            OutputFormat::Markdown => IntegrationOutputFormat::Markdown,
-            OutputFormat::Both => IntegrationOutputFormat::Both,
+/// Reader-mode PDF options shared by converting commands
+#[derive(Args)]
+struct ReaderArgs {
+    /// Render PDFs from the extracted main content in a clean article template instead
+    /// of printing the page as the site lays it out
+    #[arg(long, env = "WEBPAGE_SAVE_READER_PDF")]
+    reader_pdf: bool,
+
+    /// Tera template for reader-mode PDFs (implies --reader-pdf)
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_READER_TEMPLATE")]
+    reader_template: Option<PathBuf>,
+
+    /// Font for reader-mode PDFs: a .ttf/.otf/.woff/.woff2 file to embed, or the name of
+    /// an installed font (repeatable; later fonts are fallbacks, e.g. for CJK text;
+    /// implies --reader-pdf)
+    #[arg(long, value_name = "FONT", env = "WEBPAGE_SAVE_READER_FONT")]
+    reader_font: Vec<String>,
+
+    /// Typography preset for reader-mode PDFs (implies --reader-pdf)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "PRESET",
+        env = "WEBPAGE_SAVE_READER_PRESET"
+    )]
+    reader_preset: Option<ReaderPresetArg>,
+
+    /// Body text size of reader-mode PDFs in points, overriding the preset
+    #[arg(long, value_name = "PT", env = "WEBPAGE_SAVE_READER_FONT_SIZE")]
+    reader_font_size: Option<f64>,
+
+    /// Maximum line length of reader-mode PDFs in ems, overriding the preset
+    #[arg(long, value_name = "EM", env = "WEBPAGE_SAVE_READER_LINE_WIDTH")]
+    reader_line_width: Option<f64>,
+
+    /// Justify reader-mode text (--reader-justify=false for ragged right), overriding the
+    /// preset
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        env = "WEBPAGE_SAVE_READER_JUSTIFY"
+    )]
+    reader_justify: Option<bool>,
+
+    /// Hyphenate reader-mode text (--reader-hyphenate=false to turn off), overriding the
+    /// preset
+    #[arg(
+        long,
+        value_name = "BOOL",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        env = "WEBPAGE_SAVE_READER_HYPHENATE"
+    )]
+    reader_hyphenate: Option<bool>,
+
+    /// Color theme for highlighting code blocks in reader-mode PDFs, or none to keep the
+    /// code as extracted (default: InspiredGitHub; implies --reader-pdf)
+    #[arg(long, value_name = "THEME", env = "WEBPAGE_SAVE_READER_CODE_THEME")]
+    reader_code_theme: Option<String>,
+}
+
+impl ReaderArgs {
+    /// The reader mode to apply, or None when reader-mode PDFs are not requested
+    fn reader(
+        &self,
+        extraction: ExtractConfig,
+        paper: Option<Paper>,
+    ) -> Result<Option<ReaderMode>> {
+        let typography_set = self.reader_preset.is_some()
+            || self.reader_font_size.is_some()
+            || self.reader_line_width.is_some()
+            || self.reader_justify.is_some()
+            || self.reader_hyphenate.is_some();
+        if !self.reader_pdf
+            && self.reader_template.is_none()
+            && self.reader_font.is_empty()
+            && self.reader_code_theme.is_none()
+            && !typography_set
+        {
+            return Ok(None);
+        }
+        // Comments are only moved into an appendix for Markdown output
+        let mut reader = ReaderMode::new()
+            .with_language(language())
+            .with_extraction(extraction.with_include_comments(false));
+        if let Some(template) = read_template(self.reader_template.as_deref())? {
+            reader = reader.with_template(&template)?;
+        }
+        if !self.reader_font.is_empty() {
+            let fonts: Vec<ReaderFont> = self
+                .reader_font
+                .iter()
+                .map(|font| ReaderFont::parse(font))
+                .collect();
+            reader = reader.with_fonts(&fonts)?;
+        }
+        match self.reader_code_theme.as_deref() {
+            Some("none") => {}
+            theme => {
+                let highlighter = CodeHighlighter::new(theme.unwrap_or(highlight::DEFAULT_THEME))?;
+                reader = reader.with_highlighter(highlighter);
+            }
+        }
+        let mut typography = Typography::preset(
+            self.reader_preset
+                .clone()
+                .map(Into::into)
+                .unwrap_or_default(),
+        );
+        if let Some(font_size) = self.reader_font_size {
+            typography = typography.with_font_size(font_size);
+        }
+        if let Some(line_width) = self.reader_line_width {
+            typography = typography.with_line_width(line_width);
+        }
+        if let Some(justify) = self.reader_justify {
+            typography = typography.with_justify(justify);
         }
+        if let Some(hyphenate) = self.reader_hyphenate {
+            typography = typography.with_hyphenate(hyphenate);
+        }
+        if let Some(paper) = paper {
+            typography = typography.with_paper(paper);
+        }
+        Ok(Some(reader.with_typography(typography)?))
     }
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// Markdown chunking options shared by converting commands
+#[derive(Args)]
+struct ChunkArgs {
+    /// Split Markdown output into numbered files of at most this size, for LLM/RAG ingestion
+    #[arg(long, value_name = "SIZE", env = "WEBPAGE_SAVE_CHUNK_SIZE")]
+    chunk_size: Option<usize>,
 
-    // Initialize logging
-    if cli.verbose {
-        tracing_subscriber::fmt().with_env_filter("debug").init();
-    } else {
-        tracing_subscriber::fmt().with_env_filter("info").init();
+    /// Unit of --chunk-size and --chunk-overlap (tokens are approximated as 4 characters)
+    #[arg(
+        long,
+        value_enum,
+        default_value = "tokens",
+        env = "WEBPAGE_SAVE_CHUNK_UNIT"
+    )]
+    chunk_unit: ChunkUnitArg,
+
+    /// Amount of text repeated at the start of each chunk from the end of the previous one
+    #[arg(long, default_value = "0", env = "WEBPAGE_SAVE_CHUNK_OVERLAP")]
+    chunk_overlap: usize,
+}
+
+impl ChunkArgs {
+    /// The chunking to apply, or None when --chunk-size is not given
+    fn chunk_config(&self) -> Result<Option<ChunkConfig>> {
+        self.chunk_size
+            .map(|size| {
+                ChunkConfig::new(size, self.chunk_unit.clone().into())?
+                    .with_overlap(self.chunk_overlap)
+            })
+            .transpose()
     }
+}
 
-    match cli.command {
-        Some(Commands::Search {
-            search_type,
-            query,
-            count,
-            offset,
-            country,
-            language,
-            freshness,
-            api_key,
-        }) => {
-            // Handle search command
-            info!(
-                "Performing {} search for: {}",
-                SearchType::from(search_type.clone()),
-                query
-            );
+/// Main-content extraction options shared by converting commands
+#[derive(Args)]
+struct ExtractArgs {
+    /// CSS selector of the main content, e.g. "article.post", tried before the built-in
+    /// ones; pages where none matches fail (repeatable; comma-separated lists allowed)
+    #[arg(long, value_name = "SELECTOR", env = "WEBPAGE_SAVE_CONTENT_SELECTOR")]
+    content_selector: Vec<String>,
 
-            // Create search client
-            let client = match BraveSearchClient::new(api_key) {
-                Ok(client) => client,
-                Err(e) => {
-                    error!("Failed to initialize Brave search client: {}", e);
-                    eprintln!("✗ Failed to initialize Brave search client: {}", e);
-                    eprintln!(
-                        "  Make sure to set BRAVE_API_KEY environment variable or use --api-key"
-                    );
-                    std::process::exit(1);
-                }
-            };
+    /// CSS selector of elements to remove from the extracted content, such as nav, .ads,
+    /// or footer (repeatable; comma-separated lists allowed)
+    #[arg(long, value_name = "SELECTOR", env = "WEBPAGE_SAVE_STRIP_SELECTOR")]
+    strip_selector: Vec<String>,
 
-            // Create search configuration
-            let config = SearchConfig {
-                count,
-                offset,
-                country,
-                language,
-                freshness,
-            };
+    /// Keep navigation, share buttons, newsletter signups, related posts, and other
+    /// boilerplate in the extracted content
+    #[arg(long, env = "WEBPAGE_SAVE_KEEP_BOILERPLATE")]
+    keep_boilerplate: bool,
 
-            // Perform search
-            match client
-                .search(search_type.into(), &query, Some(config))
-                .await
-            {
-                Ok(results) => {
-                    println!("Search Results:");
-                    println!("==============");
-                    println!("{}", results);
-                }
-                Err(e) => {
-                    error!("Search failed: {}", e);
-                    eprintln!("✗ Search failed: {}", e);
-                    std::process::exit(1);
-                }
+    /// Append comment threads (Hacker News, Reddit, Disqus, WordPress, schema.org) to
+    /// Markdown output as an appendix
+    #[arg(long, env = "WEBPAGE_SAVE_INCLUDE_COMMENTS")]
+    include_comments: bool,
+
+    /// What pages are converted to Markdown as: auto (the schema.org recipe of recipe
+    /// pages, the main content of others), page (always the main content), or recipe
+    /// (always the recipe, failing for pages without one)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        default_value = "auto",
+        env = "WEBPAGE_SAVE_MODE"
+    )]
+    mode: ContentModeArg,
+}
+
+impl ExtractArgs {
+    /// The extraction settings given on the command line
+    fn extract_config(&self) -> Result<ExtractConfig> {
+        let parse = |selectors: &[String]| -> Result<Vec<Selector>> {
+            let mut parsed = Vec::new();
+            for selector in selectors {
+                parsed.extend(Selector::parse_list(selector)?);
             }
-        }
-        Some(Commands::SearchToPdf {
-            search_type,
-            query,
-            max_results,
-            output_dir,
-            format,
-            naming,
-            count,
-            offset,
-            country,
-            language,
-            freshness,
-            api_key,
-        }) => {
-            // Handle search-to-PDF command
-            info!(
-                "Performing {} search-to-PDF for: {} (max results: {})",
-                SearchType::from(search_type.clone()),
-                query,
-                max_results
-            );
+            Ok(parsed)
+        };
+        Ok(ExtractConfig::new()
+            .with_content_selectors(parse(&self.content_selector)?)
+            .with_require_content(true)
+            .with_strip_selectors(parse(&self.strip_selector)?)
+            .with_keep_boilerplate(self.keep_boilerplate)
+            .with_include_comments(self.include_comments)
+            .with_mode(self.mode.into()))
+    }
+}
 
-            // Create search-to-PDF client
-            let client = match SearchToPdfClient::new(api_key).await {
-                Ok(client) => client,
-                Err(e) => {
-                    error!("Failed to initialize search-to-PDF client: {}", e);
-                    eprintln!("✗ Failed to initialize search-to-PDF client: {}", e);
-                    eprintln!(
-                        "  Make sure to set BRAVE_API_KEY environment variable or use --api-key"
-                    );
-                    std::process::exit(1);
-                }
-            };
+/// Per-site options shared by commands that load pages from the network
+#[derive(Args)]
+struct SiteArgs {
+    /// JSON file of per-domain site profiles, e.g. {"example.com": {"print_view": "?print=1"}}
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_SITE_PROFILES")]
+    site_profiles: Option<PathBuf>,
 
-            // Create search configuration
-            let search_config = SearchConfig {
-                count,
-                offset,
-                country,
-                language,
-                freshness,
-            };
+    /// Try the print view of pages (?print=1, /print/) on sites without a profile, falling
+    /// back to the page itself when there is none
+    #[arg(long, env = "WEBPAGE_SAVE_PREFER_PRINT_VIEW")]
+    prefer_print_view: bool,
 
-            // Create PDF configuration
-            let pdf_config = SearchToPdfConfig {
-                max_results,
-                output_dir,
-                include_metadata: true,
-                naming_strategy: naming.into(),
-                output_format: format.into(),
-            };
+    /// Print Twitter/X and Mastodon post pages as they are, instead of capturing their
+    /// threads through the platform APIs
+    #[arg(long, env = "WEBPAGE_SAVE_NO_SOCIAL_THREADS")]
+    no_social_threads: bool,
 
-            // Perform search and convert to PDF
-            match client
-                .search_and_convert_to_pdf(
-                    search_type.into(),
-                    &query,
-                    Some(search_config),
-                    pdf_config,
-                )
-                .await
-            {
-                Ok(output_files) => {
-                    println!("✓ Successfully converted {} URLs:", output_files.len());
-                    for (index, output_path) in output_files.iter().enumerate() {
-                        println!("  {}. {}", index + 1, output_path.display());
-                    }
-                }
-                Err(e) => {
-                    error!("Search-to-format operation failed: {}", e);
-                    eprintln!("✗ Search-to-format operation failed: {}", e);
-                    std::process::exit(1);
-                }
-            }
-        }
-        None => {
-            // Handle URL to PDF conversion (legacy behavior)
-            let url = match cli.url {
-                Some(url) => url,
-                None => {
-                    eprintln!("✗ No URL provided for PDF conversion");
-                    eprintln!("  Use 'webpage-save <URL>' or 'webpage-save search <type> <query>'");
-                    std::process::exit(1);
-                }
-            };
+    /// Print Wikipedia articles as they are, instead of exporting them through the REST
+    /// API with linked footnotes and image credits
+    #[arg(long, env = "WEBPAGE_SAVE_NO_WIKIPEDIA")]
+    no_wikipedia: bool,
 
-            // Check if output path was provided
-            let output_provided = cli.output.is_some();
-            
-            // Generate output filename if not provided
-            let output_path = match cli.output {
-                Some(path) => path,
-                None => {
-                    let parsed_url = url::Url::parse(&url)?;
-                    let host = parsed_url.host_str().unwrap_or("unknown");
-                    let extension = match cli.format {
-                        OutputFormat::Pdf => "pdf",
-                        OutputFormat::Markdown => "md",
-                        OutputFormat::Both => "pdf", // Default to PDF for primary filename
-                    };
-                    let filename = format!("{}.{}", host, extension);
-                    PathBuf::from(filename)
-                }
-            };
+    /// Chrome profile directory to load pages with, keeping the sessions of sites logged
+    /// into with the `login` subcommand
+    #[arg(long, value_name = "DIR", env = "WEBPAGE_SAVE_PROFILE_DIR")]
+    profile_dir: Option<PathBuf>,
 
-            match cli.format {
-                OutputFormat::Pdf => {
-                    info!("Converting URL to PDF: {}", url);
-                    info!("Output file: {}", output_path.display());
-                    info!("Wait time: {} seconds", cli.wait);
-
-                    // Create PDF generator
-                    let generator = match PdfGenerator::new().await {
-                        Ok(generator) => {
-                            info!("PDF generator initialized successfully");
-                            generator
-                        }
-                        Err(e) => {
-                            error!("Failed to initialize PDF generator: {}", e);
-                            eprintln!("✗ Failed to initialize PDF generator: {}", e);
-                            std::process::exit(1);
-                        }
-                    };
+    /// Load pages with the cookies of the default profile of a locally installed browser,
+    /// so pages logged into there are captured as the logged-in user
+    #[arg(
+        long,
+        value_enum,
+        value_name = "BROWSER",
+        env = "WEBPAGE_SAVE_COOKIES_FROM_BROWSER"
+    )]
+    cookies_from_browser: Option<CookieBrowserArg>,
 
-                    // Convert URL to PDF
-                    match generator.url_to_pdf(&url, Some(&output_path)).await {
-                        Ok(pdf_data) => {
-                            info!("PDF generated successfully ({} bytes)", pdf_data.len());
-                            println!("✓ Successfully generated PDF ({} bytes)", pdf_data.len());
-                            println!("✓ Saved to: {}", output_path.display());
-                        }
-                        Err(e) => {
-                            error!("Failed to generate PDF: {}", e);
-                            eprintln!("✗ Failed to generate PDF: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-                OutputFormat::Markdown => {
-                    info!("Converting URL to Markdown: {}", url);
-                    info!("Output file: {}", output_path.display());
-
-                    // Create Markdown generator
-                    let generator = match MarkdownGenerator::new().await {
-                        Ok(generator) => {
-                            info!("Markdown generator initialized successfully");
-                            generator
-                        }
-                        Err(e) => {
-                            error!("Failed to initialize Markdown generator: {}", e);
-                            eprintln!("✗ Failed to initialize Markdown generator: {}", e);
-                            std::process::exit(1);
-                        }
-                    };
+    /// Load pages with the cookies of a Netscape cookies.txt or JSON cookie file, as
+    /// exported by curl or browser extensions
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_COOKIES_FILE")]
+    cookies_file: Option<PathBuf>,
 
-                    // Convert URL to Markdown
-                    match generator.url_to_markdown(&url, Some(&output_path)).await {
-                        Ok(markdown_data) => {
-                            info!(
-                                "Markdown generated successfully ({} chars)",
-                                markdown_data.len()
-                            );
-                            println!(
-                                "✓ Successfully generated Markdown ({} chars)",
-                                markdown_data.len()
-                            );
-                            println!("✓ Saved to: {}", output_path.display());
-                        }
-                        Err(e) => {
-                            error!("Failed to generate Markdown: {}", e);
-                            eprintln!("✗ Failed to generate Markdown: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-                OutputFormat::Both => {
-                    info!("Converting URL to both PDF and Markdown: {}", url);
-                    
-                    // Generate PDF path
-                    let pdf_path = if output_provided {
-                        // If output is specified, use that for PDF and generate MD path
-                        output_path.clone()
-                    } else {
-                        let parsed_url = url::Url::parse(&url)?;
-                        let host = parsed_url.host_str().unwrap_or("unknown");
-                        PathBuf::from(format!("{}.pdf", host))
-                    };
-                    
-                    // Generate Markdown path
-                    let md_path = if output_provided {
-                        // If output is specified, change extension to .md
-                        output_path.with_extension("md")
-                    } else {
-                        let parsed_url = url::Url::parse(&url)?;
-                        let host = parsed_url.host_str().unwrap_or("unknown");
-                        PathBuf::from(format!("{}.md", host))
-                    };
+    /// Do not send the cookies of sessions saved with the `login` subcommand
+    #[arg(long, env = "WEBPAGE_SAVE_NO_SAVED_SESSIONS")]
+    no_saved_sessions: bool,
 
-                    // Create PDF generator
-                    let pdf_generator = match PdfGenerator::new().await {
-                        Ok(generator) => {
-                            info!("PDF generator initialized successfully");
-                            generator
-                        }
-                        Err(e) => {
-                            error!("Failed to initialize PDF generator: {}", e);
-                            eprintln!("✗ Failed to initialize PDF generator: {}", e);
-                            std::process::exit(1);
-                        }
-                    };
+    /// Extra header sent with page requests, e.g. "Authorization: Bearer TOKEN" or
+    /// "X-API-Key: KEY" (repeatable)
+    #[arg(long, value_name = "NAME: VALUE", env = "WEBPAGE_SAVE_HEADER")]
+    header: Vec<String>,
+}
 
-                    // Create Markdown generator
-                    let md_generator = match MarkdownGenerator::new().await {
-                        Ok(generator) => {
-                            info!("Markdown generator initialized successfully");
-                            generator
-                        }
-                        Err(e) => {
-                            error!("Failed to initialize Markdown generator: {}", e);
-                            eprintln!("✗ Failed to initialize Markdown generator: {}", e);
-                            std::process::exit(1);
-                        }
-                    };
+impl SiteArgs {
+    /// The print-view preference given on the command line, or None when unused
+    async fn print_views(&self) -> Result<Option<PrintViews>> {
+        let profiles = match &self.site_profiles {
+            Some(path) => SiteProfiles::load(path).await?,
+            None => SiteProfiles::new(),
+        };
+        if profiles.is_empty() && !self.prefer_print_view {
+            return Ok(None);
+        }
+        Ok(Some(PrintViews::new(profiles, self.prefer_print_view)?))
+    }
 
-                    // Convert URL to PDF
-                    match pdf_generator.url_to_pdf(&url, Some(&pdf_path)).await {
-                        Ok(pdf_data) => {
-                            info!("PDF generated successfully ({} bytes)", pdf_data.len());
-                            println!("✓ Successfully generated PDF ({} bytes)", pdf_data.len());
-                            println!("✓ Saved to: {}", pdf_path.display());
-                        }
-                        Err(e) => {
-                            error!("Failed to generate PDF: {}", e);
-                            eprintln!("✗ Failed to generate PDF: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
+    /// The social thread capture, or None when disabled
+    fn social_threads(&self) -> Result<Option<SocialThreads>> {
+        match self.no_social_threads {
+            true => Ok(None),
+            false => Ok(Some(SocialThreads::new()?)),
+        }
+    }
 
-                    // Convert URL to Markdown
-                    match md_generator.url_to_markdown(&url, Some(&md_path)).await {
-                        Ok(markdown_data) => {
-                            info!(
-                                "Markdown generated successfully ({} chars)",
-                                markdown_data.len()
-                            );
-                            println!(
-                                "✓ Successfully generated Markdown ({} chars)",
-                                markdown_data.len()
-                            );
-                            println!("✓ Saved to: {}", md_path.display());
-                        }
-                        Err(e) => {
-                            error!("Failed to generate Markdown: {}", e);
-                            eprintln!("✗ Failed to generate Markdown: {}", e);
-                            std::process::exit(1);
-                        }
-                    }
-                }
-            }
+    /// The Wikipedia article export, or None when disabled
+    fn wikipedia(&self) -> Result<Option<Wikipedia>> {
+        match self.no_wikipedia {
+            true => Ok(None),
+            false => Ok(Some(Wikipedia::new()?)),
         }
     }
 
+    /// The cookies of saved login sessions, of the browser given with
+    /// --cookies-from-browser, and of --cookies-file, or None when there are none
+    fn cookies(&self) -> Result<Option<BrowserCookies>> {
+        let mut saved = match self.no_saved_sessions {
+            true => Vec::new(),
+            false => SessionStore::open_default()?.load_all()?,
+        };
+        if let Some(path) = &self.cookies_file {
+            saved.extend(BrowserCookies::from_file(path)?.into_cookies());
+        }
+        let cookies = match self.cookies_from_browser {
+            Some(browser) => BrowserCookies::load(browser.into())?,
+            None if saved.is_empty() => return Ok(None),
+            None => BrowserCookies::default(),
+        };
+        Ok(Some(cookies.with_cookies(saved)))
+    }
+
+    /// The extra request headers, or None when none are given
+    fn request_options(&self) -> Result<Option<RequestOptions>> {
+        let options = RequestOptions::from_header_lines(&self.header)?;
+        Ok((!options.is_empty()).then_some(options))
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum CookieBrowserArg {
+    Chrome,
+    Firefox,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum ImageFormatArg {
+    Png,
+    Jpeg,
+}
+
+impl From<ImageFormatArg> for ImageFormat {
+    fn from(format: ImageFormatArg) -> Self {
+        match format {
+            ImageFormatArg::Png => ImageFormat::Png,
+            ImageFormatArg::Jpeg => ImageFormat::Jpeg,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum TranslationServiceArg {
+    Deepl,
+    Openai,
+}
+
+impl From<TranslationServiceArg> for TranslationService {
+    fn from(service: TranslationServiceArg) -> Self {
+        match service {
+            TranslationServiceArg::Deepl => TranslationService::DeepL,
+            TranslationServiceArg::Openai => TranslationService::OpenAi,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum LanguageArg {
+    En,
+    Ja,
+}
+
+impl From<LanguageArg> for Language {
+    fn from(language: LanguageArg) -> Self {
+        match language {
+            LanguageArg::En => Language::English,
+            LanguageArg::Ja => Language::Japanese,
+        }
+    }
+}
+
+impl From<CookieBrowserArg> for CookieBrowser {
+    fn from(browser: CookieBrowserArg) -> Self {
+        match browser {
+            CookieBrowserArg::Chrome => CookieBrowser::Chrome,
+            CookieBrowserArg::Firefox => CookieBrowser::Firefox,
+        }
+    }
+}
+
+/// Page-size and e-reader delivery options shared by PDF-generating commands
+#[derive(Args)]
+struct DeviceArgs {
+    /// Page size of PDFs: a4 (the default), a5, a3, letter, legal, or the screen of an
+    /// e-reader, with matching margins and text scaling
+    #[arg(
+        long,
+        alias = "paper-size",
+        value_enum,
+        value_name = "PAPER",
+        env = "WEBPAGE_SAVE_PAPER"
+    )]
+    paper: Option<PaperArg>,
+
+    /// Copy every saved PDF into this directory, e.g. the documents folder of an e-reader
+    /// mounted over USB
+    #[arg(long, value_name = "DIR", env = "WEBPAGE_SAVE_DEVICE_DIR")]
+    device_dir: Option<PathBuf>,
+
+    /// Email every saved PDF to this Send to Kindle address (requires --mail-from)
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        requires = "mail_from",
+        env = "WEBPAGE_SAVE_SEND_TO_KINDLE"
+    )]
+    send_to_kindle: Option<String>,
+
+    /// Sender of --send-to-kindle email, which must be on the Kindle account's approved
+    /// sender list
+    #[arg(long, value_name = "ADDRESS", env = "WEBPAGE_SAVE_MAIL_FROM")]
+    mail_from: Option<String>,
+
+    /// sendmail-compatible command that sends --send-to-kindle email
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        default_value = "sendmail",
+        env = "WEBPAGE_SAVE_SENDMAIL"
+    )]
+    sendmail: String,
+}
+
+impl DeviceArgs {
+    /// The paper preset given on the command line
+    fn paper(&self) -> Option<Paper> {
+        self.paper.clone().map(Into::into)
+    }
+
+    /// Where to deliver saved PDFs
+    fn delivery(&self) -> Result<Delivery> {
+        let mut delivery = Delivery::new();
+        if let Some(dir) = &self.device_dir {
+            delivery = delivery.with_device_dir(dir.clone())?;
+        }
+        if let (Some(to), Some(from)) = (&self.send_to_kindle, &self.mail_from) {
+            delivery = delivery.with_kindle_email(to, from, &self.sendmail)?;
+        }
+        Ok(delivery)
+    }
+}
+
+/// Rendering options shared by PDF-generating commands
+#[derive(Args)]
+struct RenderingArgs {
+    /// CSS media type pages are laid out for: print (Chrome's default, using print
+    /// stylesheets) or screen (the page as it looks in a browser window)
+    #[arg(long, value_enum, value_name = "MEDIA", env = "WEBPAGE_SAVE_MEDIA")]
+    media: Option<MediaArg>,
+
+    /// Print scale from 0.1 to 2, or auto to shrink pages whose tables, code blocks, or
+    /// images are wider than the page (default: the scale of the paper preset)
+    #[arg(long, value_name = "SCALE", env = "WEBPAGE_SAVE_SCALE")]
+    scale: Option<PrintScale>,
+
+    /// Print pages mostly taken up by tables or code blocks wider than the page in
+    /// landscape, instead of warning about them
+    #[arg(long, env = "WEBPAGE_SAVE_AUTO_LANDSCAPE")]
+    auto_landscape: bool,
+
+    /// Print every page in landscape
+    #[arg(long, env = "WEBPAGE_SAVE_LANDSCAPE")]
+    landscape: bool,
+
+    /// Margin on every side of PDF pages in inches (default: the margin of the paper
+    /// preset)
+    #[arg(long, value_name = "INCHES", env = "WEBPAGE_SAVE_MARGINS")]
+    margins: Option<f64>,
+
+    /// HTML of a header printed on every page, where Chrome fills in elements with the
+    /// date, title, url, pageNumber, and totalPages classes (--page-header replaces it)
+    #[arg(long, value_name = "HTML", env = "WEBPAGE_SAVE_PRINT_HEADER")]
+    print_header: Option<String>,
+
+    /// HTML of a footer printed on every page, e.g. '<span class="pageNumber"></span>'
+    #[arg(long, value_name = "HTML", env = "WEBPAGE_SAVE_PRINT_FOOTER")]
+    print_footer: Option<String>,
+
+    /// Pages to print, e.g. 1-5,8 or 2- (default: all)
+    #[arg(
+        long,
+        value_name = "RANGES",
+        value_parser = pdf::parse_page_ranges,
+        env = "WEBPAGE_SAVE_PAGE_RANGES"
+    )]
+    page_ranges: Option<String>,
+
+    /// Leave background colors and images out of PDFs
+    #[arg(long, env = "WEBPAGE_SAVE_NO_BACKGROUND")]
+    no_background: bool,
+
+    /// Image format of full-page screenshots (with --format screenshot)
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "png",
+        env = "WEBPAGE_SAVE_SCREENSHOT_FORMAT"
+    )]
+    screenshot_format: ScreenshotFormatArg,
+
+    /// Viewport pages are rendered in, as WIDTHxHEIGHT (e.g. 1920x1080) or a preset
+    /// (desktop, laptop, tablet, mobile); responsive pages lay out for its width
+    #[arg(long, value_name = "SIZE", env = "WEBPAGE_SAVE_VIEWPORT")]
+    viewport: Option<Viewport>,
+
+    /// Render reproducibly: freeze the page clock, seed Math.random, disable animations,
+    /// hide ads, consent banners and chat widgets, and date PDFs with the frozen time, so
+    /// captures of unchanged pages give identical PDFs
+    #[arg(long, env = "WEBPAGE_SAVE_DETERMINISTIC")]
+    deterministic: bool,
+
+    /// Time the page clock is frozen at with --deterministic, as RFC 3339
+    /// (default: 2000-01-01T00:00:00Z)
+    #[arg(
+        long,
+        value_name = "TIME",
+        requires = "deterministic",
+        env = "WEBPAGE_SAVE_FREEZE_TIME"
+    )]
+    freeze_time: Option<chrono::DateTime<chrono::Utc>>,
+
+    /// Seed of Math.random with --deterministic (default: 1)
+    #[arg(
+        long,
+        value_name = "SEED",
+        requires = "deterministic",
+        env = "WEBPAGE_SAVE_RANDOM_SEED"
+    )]
+    random_seed: Option<u32>,
+
+    /// Capture pages once an element matches this CSS selector (e.g. "#content"),
+    /// instead of after the --wait delay
+    #[arg(
+        long,
+        value_name = "SELECTOR",
+        conflicts_with = "wait_network_idle",
+        env = "WEBPAGE_SAVE_WAIT_FOR_SELECTOR"
+    )]
+    wait_for_selector: Option<String>,
+
+    /// Capture pages once no request has been in flight for half a second, instead of
+    /// after the --wait delay
+    #[arg(long, env = "WEBPAGE_SAVE_WAIT_NETWORK_IDLE")]
+    wait_network_idle: bool,
+
+    /// Longest wait in seconds for --wait-for-selector or --wait-network-idle, after
+    /// which the page is captured as it is
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value = "30",
+        env = "WEBPAGE_SAVE_WAIT_TIMEOUT"
+    )]
+    wait_timeout: u64,
+}
+
+impl RenderingArgs {
+    /// The page layout of PDFs on a paper preset
+    fn pdf_config(&self, paper: Option<Paper>) -> PdfConfig {
+        let mut config = PdfConfig::new()
+            .with_paper(paper.unwrap_or_default())
+            .with_landscape(self.landscape)
+            .with_auto_landscape(self.auto_landscape)
+            .with_print_background(!self.no_background);
+        if let Some(margins) = self.margins {
+            config = config.with_margins(margins);
+        }
+        if let Some(scale) = self.scale {
+            config = config.with_scale(scale);
+        }
+        if let Some(template) = &self.print_header {
+            config = config.with_header_template(template);
+        }
+        if let Some(template) = &self.print_footer {
+            config = config.with_footer_template(template);
+        }
+        config.page_ranges = self.page_ranges.clone();
+        config
+    }
+
+    /// The image format pages are captured in, when the output format is screenshot
+    fn screenshot(&self, format: &OutputFormat) -> Option<ScreenshotFormat> {
+        matches!(format, OutputFormat::Screenshot).then(|| self.screenshot_format.clone().into())
+    }
+
+    /// The readiness strategy replacing the --wait delay, or None when not requested
+    fn wait_strategy(&self) -> Option<WaitStrategy> {
+        let timeout = Duration::from_secs(self.wait_timeout);
+        match (&self.wait_for_selector, self.wait_network_idle) {
+            (Some(selector), _) => Some(WaitStrategy::selector(selector, timeout)),
+            (None, true) => Some(WaitStrategy::network_idle(timeout)),
+            (None, false) => None,
+        }
+    }
+
+    /// Deterministic rendering settings, or None when not requested
+    fn deterministic(&self) -> Option<DeterministicRendering> {
+        if !self.deterministic {
+            return None;
+        }
+        let mut deterministic = DeterministicRendering::new();
+        if let Some(time) = self.freeze_time {
+            deterministic = deterministic.with_time(time);
+        }
+        if let Some(seed) = self.random_seed {
+            deterministic = deterministic.with_seed(seed);
+        }
+        Some(deterministic)
+    }
+}
+
+/// Alt-text generation options shared by commands converting pages to Markdown
+#[derive(Args)]
+struct AltTextArgs {
+    /// Describe images without alt text in Markdown output with a vision model
+    #[arg(long, env = "WEBPAGE_SAVE_GENERATE_ALT_TEXT")]
+    generate_alt_text: bool,
+
+    /// OpenAI-compatible API base URL for --generate-alt-text
+    #[arg(
+        long,
+        default_value = alttext::DEFAULT_ALT_TEXT_ENDPOINT,
+        env = "WEBPAGE_SAVE_ALT_TEXT_ENDPOINT"
+    )]
+    alt_text_endpoint: String,
+
+    /// Vision model for --generate-alt-text
+    #[arg(
+        long,
+        default_value = alttext::DEFAULT_ALT_TEXT_MODEL,
+        env = "WEBPAGE_SAVE_ALT_TEXT_MODEL"
+    )]
+    alt_text_model: String,
+
+    /// API key for the alt text endpoint (optional for local servers)
+    #[arg(long, env = "OPENAI_API_KEY", hide_env_values = true)]
+    alt_text_api_key: Option<String>,
+
+    /// Most images described per page with --generate-alt-text
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = alttext::DEFAULT_MAX_IMAGES,
+        env = "WEBPAGE_SAVE_MAX_ALT_TEXT_IMAGES"
+    )]
+    max_alt_text_images: usize,
+}
+
+/// Image options shared by commands saving Markdown files
+#[derive(Args)]
+struct MarkdownImageArgs {
+    /// Download the images of Markdown output into a <name>_images directory next to
+    /// it and link them from there
+    #[arg(long, env = "WEBPAGE_SAVE_DOWNLOAD_IMAGES")]
+    download_images: bool,
+
+    /// Inline the images of Markdown output as base64 data: URIs
+    #[arg(
+        long,
+        conflicts_with = "download_images",
+        env = "WEBPAGE_SAVE_INLINE_IMAGES"
+    )]
+    inline_images: bool,
+
+    /// Keep links to images larger than this many megabytes instead of copies
+    #[arg(
+        long,
+        value_name = "MB",
+        default_value = "10",
+        env = "WEBPAGE_SAVE_MAX_IMAGE_MB"
+    )]
+    max_image_mb: u64,
+}
+
+impl MarkdownImageArgs {
+    /// The chosen image handling, or None to keep links to the images
+    fn markdown_images(&self) -> Option<MarkdownImages> {
+        let embedding = match (self.download_images, self.inline_images) {
+            (true, _) => ImageEmbedding::Download,
+            (false, true) => ImageEmbedding::Inline,
+            (false, false) => return None,
+        };
+        Some(MarkdownImages::new(embedding).with_max_bytes(self.max_image_mb * 1024 * 1024))
+    }
+}
+
+/// Create the alt text client, or None unless --generate-alt-text is set, exiting the
+/// process on failure
+fn init_alt_text(args: &AltTextArgs) -> Option<AltTextClient> {
+    if !args.generate_alt_text {
+        return None;
+    }
+    match AltTextClient::new(&args.alt_text_endpoint, &args.alt_text_model) {
+        Ok(client) => Some(
+            client
+                .with_api_key(args.alt_text_api_key.clone())
+                .with_max_images(args.max_alt_text_images),
+        ),
+        Err(e) => {
+            error!("Failed to create alt text client: {}", e);
+            eprintln!("✗ Failed to create alt text client: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Place card options for local searches
+#[derive(Args)]
+struct PlaceMapArgs {
+    /// Save a card with the address, links, and a street map of every place found by a
+    /// local search, as <name>.card.md/.pdf
+    #[arg(long, env = "WEBPAGE_SAVE_PLACE_CARDS")]
+    place_cards: bool,
+
+    /// Zoom level of place card maps, from 0 (the world) to 19 (buildings)
+    #[arg(
+        long,
+        value_name = "ZOOM",
+        default_value_t = places::DEFAULT_MAP_ZOOM,
+        value_parser = clap::value_parser!(u8).range(0..=places::MAX_MAP_ZOOM as i64),
+        env = "WEBPAGE_SAVE_MAP_ZOOM"
+    )]
+    map_zoom: u8,
+
+    /// Nominatim server that geocodes the addresses of places without coordinates
+    #[arg(
+        long,
+        value_name = "URL",
+        default_value = places::DEFAULT_GEOCODER_ENDPOINT,
+        env = "WEBPAGE_SAVE_GEOCODER_ENDPOINT"
+    )]
+    geocoder_endpoint: String,
+
+    /// URL template of map tiles, with {z}, {x}, and {y} placeholders
+    #[arg(
+        long,
+        value_name = "URL",
+        default_value = places::DEFAULT_TILE_URL,
+        env = "WEBPAGE_SAVE_TILE_URL"
+    )]
+    tile_url: String,
+}
+
+/// Create the geocoder and tile source of place cards, or None unless --place-cards is
+/// set, exiting the process on failure
+fn init_place_maps(args: &PlaceMapArgs) -> Option<PlaceMaps> {
+    if !args.place_cards {
+        return None;
+    }
+    match PlaceMaps::new() {
+        Ok(maps) => Some(
+            maps.with_geocoder(&args.geocoder_endpoint)
+                .with_tile_url(&args.tile_url)
+                .with_zoom(args.map_zoom),
+        ),
+        Err(e) => {
+            error!("Failed to create the place map client: {}", e);
+            eprintln!("✗ Failed to create the place map client: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Machine translation options for Markdown output
+#[derive(Args)]
+struct TranslateArgs {
+    /// Also save Markdown output translated into this language (e.g. de, ja, pt-BR) as
+    /// <name>.<lang>.md
+    #[arg(long, value_name = "LANG", env = "WEBPAGE_SAVE_TRANSLATE_TO")]
+    translate_to: Option<String>,
+
+    /// Translation service for --translate-to
+    #[arg(
+        long,
+        value_enum,
+        default_value = "deepl",
+        env = "WEBPAGE_SAVE_TRANSLATION_SERVICE"
+    )]
+    translation_service: TranslationServiceArg,
+
+    /// API base URL of the translation service (default: the DeepL free API, or the
+    /// OpenAI API)
+    #[arg(long, env = "WEBPAGE_SAVE_TRANSLATION_ENDPOINT")]
+    translation_endpoint: Option<String>,
+
+    /// Chat model for --translation-service openai
+    #[arg(
+        long,
+        default_value = translate::DEFAULT_TRANSLATION_MODEL,
+        env = "WEBPAGE_SAVE_TRANSLATION_MODEL"
+    )]
+    translation_model: String,
+
+    /// API key for the translation service (default: DEEPL_AUTH_KEY or OPENAI_API_KEY)
+    #[arg(long, env = "WEBPAGE_SAVE_TRANSLATION_API_KEY", hide_env_values = true)]
+    translation_api_key: Option<String>,
+}
+
+/// Create the translator, or None unless --translate-to is set, exiting the process on
+/// failure
+fn init_translator(args: &TranslateArgs) -> Option<Translator> {
+    let target_lang = args.translate_to.as_deref()?;
+    let service = TranslationService::from(args.translation_service);
+    let endpoint = args
+        .translation_endpoint
+        .as_deref()
+        .unwrap_or(service.default_endpoint());
+    let api_key = args.translation_api_key.clone().or_else(|| {
+        let fallback = match service {
+            TranslationService::DeepL => "DEEPL_AUTH_KEY",
+            TranslationService::OpenAi => "OPENAI_API_KEY",
+        };
+        std::env::var(fallback).ok().filter(|key| !key.is_empty())
+    });
+    match Translator::new(service, endpoint, target_lang) {
+        Ok(translator) => Some(
+            translator
+                .with_api_key(api_key)
+                .with_model(&args.translation_model),
+        ),
+        Err(e) => {
+            error!("Failed to create translator: {}", e);
+            eprintln!("✗ Failed to create translator: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Write the translations of Markdown files, reporting each one
+///
+/// # Returns
+///
+/// Returns whether every file was translated
+async fn save_translations(translator: &Translator, files: &[PathBuf]) -> bool {
+    let mut translated = true;
+    for md_path in files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|extension| extension == "md"))
+    {
+        match translator.translate_file(md_path).await {
+            Ok(path) => println!("✓ {}", tr(Message::SavedTo(&path))),
+            Err(e) => {
+                error!("Failed to translate {}: {}", md_path.display(), e);
+                eprintln!("✗ Failed to translate {}: {}", md_path.display(), e);
+                translated = false;
+            }
+        }
+    }
+    translated
+}
+
+/// Create the timestamp authority client, exiting the process on failure
+fn init_timestamp_authority(url: &str) -> TimestampAuthority {
+    match TimestampAuthority::new(url) {
+        Ok(tsa) => tsa,
+        Err(e) => {
+            error!("Invalid timestamp authority: {}", e);
+            eprintln!("✗ Invalid timestamp authority: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Timestamp saved files, writing each reply next to its file, and report each one
+///
+/// # Returns
+///
+/// Returns whether every file was timestamped
+async fn timestamp_files(tsa: &TimestampAuthority, files: &[PathBuf]) -> bool {
+    let mut timestamped = true;
+    for file in files {
+        match tsa.timestamp_file(file).await {
+            Ok((path, token)) => {
+                report_timestamp(&file.display().to_string(), &token);
+                println!("  {}", tr(Message::SavedTo(&path)));
+            }
+            Err(e) => {
+                error!("Failed to timestamp {}: {}", file.display(), e);
+                eprintln!("✗ Failed to timestamp {}: {}", file.display(), e);
+                timestamped = false;
+            }
+        }
+    }
+    timestamped
+}
+
+/// Print one line for a granted timestamp
+fn report_timestamp(what: &str, token: &TimestampToken) {
+    match token.gen_time {
+        Some(time) => println!(
+            "✓ Timestamped {} at {}",
+            what,
+            time.format("%Y-%m-%d %H:%M:%S UTC")
+        ),
+        None => println!("✓ Timestamped {}", what),
+    }
+}
+
+/// Virus-scan options shared by commands saving content from the web
+#[derive(Args)]
+struct ScanArgs {
+    /// Command scanning each saved file before it is delivered or archived, e.g.
+    /// "clamdscan --no-summary"; the file path replaces {} or is appended. Exit status 0
+    /// means clean, 1 infected
+    #[arg(long, value_name = "COMMAND", env = "WEBPAGE_SAVE_SCAN_COMMAND")]
+    scan_command: Option<String>,
+
+    /// Directory infected files are moved into (default: quarantine in the output
+    /// directory)
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "scan_command",
+        env = "WEBPAGE_SAVE_QUARANTINE_DIR"
+    )]
+    quarantine_dir: Option<PathBuf>,
+}
+
+/// Create the virus-scan hook, quarantining into `output_dir` unless another directory
+/// is given, or None when no scan command is set, exiting the process on failure
+fn init_scan_hook(args: &ScanArgs, output_dir: &Path) -> Option<ScanHook> {
+    let command = args.scan_command.as_deref()?;
+    match ScanHook::new(command) {
+        Ok(hook) => Some(
+            hook.with_quarantine_dir(
+                args.quarantine_dir
+                    .clone()
+                    .unwrap_or_else(|| output_dir.join(virusscan::DEFAULT_QUARANTINE_DIR)),
+            ),
+        ),
+        Err(e) => {
+            error!("Invalid virus scan command: {}", e);
+            eprintln!("✗ Invalid virus scan command: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print the files a virus scan moved into quarantine
+fn report_quarantined(files: &[Quarantined]) {
+    for file in files {
+        eprintln!(
+            "⚠ Quarantined {} ({}): {}",
+            file.file.display(),
+            file.finding,
+            file.quarantined.display()
+        );
+    }
+}
+
+/// Create the downloader for --download-links, or None when no extensions are given,
+/// exiting the process on failure
+///
+/// Images are transcoded and scaled in the browser of the PDF generator, or in a new
+/// one without it.
+fn init_link_downloader(
+    args: &ConvertArgs,
+    pdf_generator: Option<&PdfGenerator>,
+) -> Option<LinkDownloader> {
+    if args.download_links.is_empty() {
+        return None;
+    }
+    let downloader = match LinkDownloader::new(&args.download_links) {
+        Ok(downloader) => downloader
+            .with_max_bytes(args.max_download_mb * 1024 * 1024)
+            .with_max_files(args.max_downloads),
+        Err(e) => {
+            error!("Invalid --download-links: {}", e);
+            eprintln!("✗ Invalid --download-links: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut policy = ImagePolicy::new().with_strip_metadata(args.strip_image_metadata);
+    if let Some(format) = args.image_format {
+        policy = policy.with_format(format.into());
+    }
+    if let Some(max_dimension) = args.max_image_dimension {
+        policy = policy.with_max_dimension(max_dimension);
+    }
+    if policy.needs_browser() {
+        let browser = match pdf_generator {
+            Some(generator) => generator.browser().clone(),
+            None => match session::launch_browser(true, None, None) {
+                Ok(browser) => browser,
+                Err(e) => {
+                    error!("Failed to launch Chrome for image conversion: {}", e);
+                    eprintln!("✗ Failed to launch Chrome for image conversion: {}", e);
+                    std::process::exit(1);
+                }
+            },
+        };
+        policy = policy.with_browser(browser);
+    }
+    Some(downloader.with_image_policy(policy))
+}
+
+/// Download the files a captured page links to into a directory, scanning each one
+///
+/// # Returns
+///
+/// Returns the downloaded files the virus scan moved into quarantine
+async fn save_linked_files(
+    downloader: &LinkDownloader,
+    page_cache: Option<&PageCache>,
+    scan_hook: Option<&ScanHook>,
+    url: &str,
+    dir: &Path,
+) -> Vec<Quarantined> {
+    let cached = match page_cache {
+        Some(cache) => cache.get(url).await,
+        None => None,
+    };
+    let html = match cached {
+        Some(html) => html,
+        None => match downloader.fetch_page(url).await {
+            Ok(html) => html,
+            Err(e) => {
+                warn!("Failed to fetch {} for linked files: {}", url, e);
+                eprintln!("⚠ Skipped linked files of {}: {}", url, e);
+                return Vec::new();
+            }
+        },
+    };
+
+    let attachments = downloader.download(&html, url, dir).await;
+    for skipped in &attachments.skipped {
+        eprintln!("⚠ Skipped linked file {} ({})", skipped.url, skipped.reason);
+    }
+    let mut quarantined = Vec::new();
+    let mut saved = 0;
+    for file in &attachments.saved {
+        match scan_hook.map(|hook| hook.screen(file)) {
+            Some(Ok(Some(infected))) => quarantined.push(infected),
+            Some(Err(e)) => {
+                error!("Virus scan failed on {}: {}", file.display(), e);
+                eprintln!("✗ Virus scan failed on {}: {}", file.display(), e);
+                saved += 1;
+            }
+            _ => saved += 1,
+        }
+    }
+    if saved > 0 {
+        println!("✓ Saved {} linked file(s) to {}", saved, dir.display());
+    }
+    quarantined
+}
+
+/// Read an optional template file
+fn read_template(path: Option<&Path>) -> Result<Option<String>> {
+    path.map(|path| {
+        std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read template {}: {}", path.display(), e))
+    })
+    .transpose()
+}
+
+/// The default page snapshot cache, or None when disabled or unavailable
+fn page_cache(no_cache: bool) -> Option<PageCache> {
+    if no_cache {
+        return None;
+    }
+
+    match PageCache::in_default_dir() {
+        Ok(cache) => Some(cache),
+        Err(e) => {
+            warn!("Page snapshot cache disabled: {}", e);
+            None
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum SearchCommands {
+    /// Validate the Brave API key and report plan/quota information
+    Check {
+        /// Brave API key (optional, can also use BRAVE_API_KEY environment variable)
+        #[arg(long, env = "BRAVE_API_KEY", hide_env_values = true)]
+        api_key: Option<String>,
+    },
+}
+
+/// Arguments for the `search-to-pdf` subcommand
+#[derive(Args)]
+struct SearchToPdfArgs {
+    /// Type of search to perform
+    #[arg(value_enum)]
+    search_type: SearchTypeArg,
+
+    /// Search query
+    query: String,
+
+    /// Maximum number of results to convert to PDF
+    #[arg(short, long, default_value = "5", env = "WEBPAGE_SAVE_MAX_RESULTS")]
+    max_results: usize,
+
+    /// Output directory for PDF files
+    #[arg(
+        short,
+        long,
+        default_value = "./pdf_downloads",
+        env = "WEBPAGE_SAVE_OUTPUT_DIR"
+    )]
+    output_dir: PathBuf,
+
+    /// Output format (pdf, markdown, both, screenshot, or epub)
+    #[arg(long, value_enum, default_value = "pdf", env = "WEBPAGE_SAVE_FORMAT")]
+    format: OutputFormat,
+
+    /// File naming strategy
+    #[arg(
+        long,
+        value_enum,
+        default_value = "title-domain",
+        env = "WEBPAGE_SAVE_NAMING"
+    )]
+    naming: NamingStrategyArg,
+
+    /// Combine all Markdown output into one document named after the query
+    #[arg(long, env = "WEBPAGE_SAVE_MERGE")]
+    merge: bool,
+
+    /// Convert results again even if the output directory already has them
+    #[arg(long, env = "WEBPAGE_SAVE_FORCE")]
+    force: bool,
+
+    /// Skip checking for enough free disk space before converting
+    #[arg(long, env = "WEBPAGE_SAVE_NO_SPACE_CHECK")]
+    no_space_check: bool,
+
+    /// Passages most relevant to the query kept per result in the output manifest and
+    /// index (0 for none)
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = snippets::DEFAULT_MAX_SNIPPETS,
+        env = "WEBPAGE_SAVE_MAX_SNIPPETS"
+    )]
+    max_snippets: usize,
+
+    /// Most results converted to PDF at a time, each in its own browser tab
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = pdf::DEFAULT_CONCURRENCY,
+        env = "WEBPAGE_SAVE_CONCURRENCY"
+    )]
+    concurrency: usize,
+
+    /// Save a compact Markdown brief of each result (title, relevant passages, and key
+    /// facts) and a combined brief named after the query, instead of converting the
+    /// results
+    #[arg(long, conflicts_with = "merge", env = "WEBPAGE_SAVE_EXTRACT_ONLY")]
+    extract_only: bool,
+
+    /// Also capture a full-page screenshot of each result next to its PDF or Markdown
+    /// (see --screenshot-format)
+    #[arg(
+        long,
+        conflicts_with = "extract_only",
+        env = "WEBPAGE_SAVE_SCREENSHOTS"
+    )]
+    screenshots: bool,
+
+    /// Also save the schema.org events described on each result (conference sessions,
+    /// concerts, meetups) as an importable iCalendar file
+    #[arg(long, env = "WEBPAGE_SAVE_CALENDAR")]
+    calendar: bool,
+
+    /// Also save local search places, and the businesses and people described on each
+    /// result, as vCard files for address books
+    #[arg(long, env = "WEBPAGE_SAVE_CONTACTS")]
+    contacts: bool,
+
+    /// Also save the per-domain statistics report of the run as JSON
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_STATS_JSON")]
+    stats_json: Option<PathBuf>,
+
+    /// Scan captured pages for likely personal data (emails, phone numbers, national
+    /// IDs) and list the findings per document in the run report
+    #[arg(long, env = "WEBPAGE_SAVE_SCAN_PII")]
+    scan_pii: bool,
+
+    /// Domain blocklist file (one domain per line, hosts file, or Adblock `||domain^`
+    /// rules); results on a listed domain or its subdomains are skipped and reported
+    /// (repeatable)
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_BLOCKLIST")]
+    blocklist: Vec<PathBuf>,
+
+    /// Longest page load timeout in seconds for consistently slow sites; timeouts start
+    /// at 30 seconds and double per slow site up to this limit
+    #[arg(
+        long,
+        value_name = "SECS",
+        default_value = "120",
+        env = "WEBPAGE_SAVE_MAX_TIMEOUT"
+    )]
+    max_timeout: u64,
+
+    /// Follow "next page" links (rel=next, ?page=2, /page/2/) of paginated articles for
+    /// up to N further pages, stitching all parts into one document
+    #[arg(
+        long,
+        value_name = "N",
+        default_value = "0",
+        env = "WEBPAGE_SAVE_FOLLOW_PAGINATION"
+    )]
+    follow_pagination: usize,
+
+    /// Render pages with JavaScript in headless Chrome before converting them to
+    /// Markdown, for single-page applications that are empty without it
+    #[arg(long, env = "WEBPAGE_SAVE_RENDER_JS")]
+    render_js: bool,
+
+    /// Number of search results to return
+    #[arg(short, long, env = "WEBPAGE_SAVE_COUNT")]
+    count: Option<usize>,
+
+    /// Pagination offset
+    #[arg(long, env = "WEBPAGE_SAVE_OFFSET")]
+    offset: Option<usize>,
+
+    /// Country code for news/local searches
+    #[arg(long, env = "WEBPAGE_SAVE_COUNTRY")]
+    country: Option<String>,
+
+    /// Language code for news searches
+    #[arg(short, long, env = "WEBPAGE_SAVE_LANGUAGE")]
+    language: Option<String>,
+
+    /// Freshness filter for news searches (h, d, w, m, y)
+    #[arg(short, long, env = "WEBPAGE_SAVE_FRESHNESS")]
+    freshness: Option<String>,
+
+    /// Brave API key (optional, can also use BRAVE_API_KEY environment variable)
+    #[arg(long, env = "BRAVE_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    #[command(flatten)]
+    cache: CacheArgs,
+
+    /// Wait time in seconds before generating each PDF (for dynamic content, 0 disables)
+    #[arg(short, long, default_value = "2", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
+
+    #[command(flatten)]
+    branding: BrandingArgs,
+
+    /// Start Markdown output with YAML or TOML front matter holding the title, source
+    /// URL, fetch time, author, description, image, word count, and language
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        env = "WEBPAGE_SAVE_FRONT_MATTER"
+    )]
+    front_matter: Option<FrontMatterArg>,
+
+    /// Keep only the text of links in Markdown output, dropping their targets
+    #[arg(long, env = "WEBPAGE_SAVE_STRIP_LINKS")]
+    strip_links: bool,
+
+    #[command(flatten)]
+    chunking: ChunkArgs,
+
+    #[command(flatten)]
+    extraction: ExtractArgs,
+
+    #[command(flatten)]
+    reader: ReaderArgs,
+
+    #[command(flatten)]
+    device: DeviceArgs,
+
+    #[command(flatten)]
+    rendering: RenderingArgs,
+
+    #[command(flatten)]
+    sites: SiteArgs,
+
+    #[command(flatten)]
+    scan: ScanArgs,
+
+    #[command(flatten)]
+    alt_text: AltTextArgs,
+
+    #[command(flatten)]
+    images: MarkdownImageArgs,
+
+    #[command(flatten)]
+    place_maps: PlaceMapArgs,
+}
+
+/// Arguments for the `render` subcommand
+#[derive(Args)]
+struct RenderArgs {
+    /// Saved .html/.mhtml files, or directories searched recursively for them
+    #[arg(value_name = "PATH", required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output directory, mirroring the input directory structure
+    /// (optional, defaults to writing next to each snapshot)
+    #[arg(short, long, env = "WEBPAGE_SAVE_OUTPUT_DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Output format (pdf, markdown, both, screenshot, or epub)
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value = "pdf",
+        env = "WEBPAGE_SAVE_FORMAT"
+    )]
+    format: OutputFormat,
+
+    /// Wait time in seconds before generating each PDF (0 disables)
+    #[arg(short, long, default_value = "0", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
+
+    /// Skip checking for enough free disk space before rendering
+    #[arg(long, env = "WEBPAGE_SAVE_NO_SPACE_CHECK")]
+    no_space_check: bool,
+
+    #[command(flatten)]
+    branding: BrandingArgs,
+
+    /// Start Markdown output with YAML or TOML front matter holding the title, source
+    /// URL, fetch time, author, description, image, word count, and language
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        env = "WEBPAGE_SAVE_FRONT_MATTER"
+    )]
+    front_matter: Option<FrontMatterArg>,
+
+    /// Keep only the text of links in Markdown output, dropping their targets
+    #[arg(long, env = "WEBPAGE_SAVE_STRIP_LINKS")]
+    strip_links: bool,
+
+    #[command(flatten)]
+    chunking: ChunkArgs,
+
+    #[command(flatten)]
+    extraction: ExtractArgs,
+
+    #[command(flatten)]
+    reader: ReaderArgs,
+
+    #[command(flatten)]
+    device: DeviceArgs,
+
+    #[command(flatten)]
+    rendering: RenderingArgs,
+}
+
+/// Arguments for the `embed` subcommand
+#[derive(Args)]
+struct EmbedArgs {
+    /// Markdown files (e.g. chunk files), or directories searched recursively for them
+    #[arg(value_name = "PATH", required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Output JSONL file
+    #[arg(
+        short,
+        long,
+        default_value = "embeddings.jsonl",
+        env = "WEBPAGE_SAVE_EMBEDDINGS_OUTPUT"
+    )]
+    output: PathBuf,
+
+    /// OpenAI-compatible API base URL
+    #[arg(
+        long,
+        default_value = embedding::DEFAULT_EMBEDDING_ENDPOINT,
+        env = "WEBPAGE_SAVE_EMBEDDING_ENDPOINT"
+    )]
+    endpoint: String,
+
+    /// Embedding model
+    #[arg(
+        long,
+        default_value = embedding::DEFAULT_EMBEDDING_MODEL,
+        env = "WEBPAGE_SAVE_EMBEDDING_MODEL"
+    )]
+    model: String,
+
+    /// API key for the embeddings endpoint (optional for local servers)
+    #[arg(long, env = "OPENAI_API_KEY", hide_env_values = true)]
+    api_key: Option<String>,
+
+    /// Number of texts sent per embeddings request
+    #[arg(long, default_value = "64", env = "WEBPAGE_SAVE_EMBEDDING_BATCH_SIZE")]
+    batch_size: usize,
+}
+
+/// Arguments for the `archive` subcommand
+#[derive(Args)]
+struct ArchiveArgs {
+    /// SQLite archive database (required by all but `publish`)
+    #[arg(short, long, value_name = "DB", env = "WEBPAGE_SAVE_ARCHIVE")]
+    database: Option<PathBuf>,
+
+    /// URL to load in Chrome, recording its HTTP exchanges into the --warc file
+    #[arg(value_name = "URL", requires = "warc")]
+    url: Option<String>,
+
+    /// WARC file to record every request and response of loading URL into, including
+    /// the page's sub-resources (gzip-compressed if it ends in .gz)
+    #[arg(long, value_name = "PATH", requires = "url")]
+    warc: Option<PathBuf>,
+
+    /// Wait time in seconds after URL loads, for resources fetched by scripts
+    #[arg(short, long, default_value = "2", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
+
+    #[command(subcommand)]
+    command: Option<ArchiveCommands>,
+}
+
+#[derive(Subcommand)]
+enum ArchiveCommands {
+    /// List the most recent captures
+    List {
+        /// Maximum number of captures to list
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Search titles, URLs, and Markdown content (FTS5 query syntax)
+    Query {
+        /// Full-text search query
+        query: String,
+
+        /// Maximum number of matches to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Print a capture's Markdown, or write its PDF to a file
+    Show {
+        /// Capture ID, as shown by `list` and `query`
+        id: i64,
+
+        /// Write the capture's PDF to this file instead of printing its Markdown
+        #[arg(long, value_name = "PATH")]
+        pdf: Option<PathBuf>,
+    },
+    /// Package every capture into a bundle (.tar.zst) with integrity checksums
+    Export {
+        /// Bundle file to write
+        bundle: PathBuf,
+    },
+    /// Verify a bundle created by `export` and add its captures to the archive
+    Import {
+        /// Bundle file to read
+        bundle: PathBuf,
+
+        /// Keep imported PDFs as files in this directory, storing only their paths
+        #[arg(long, value_name = "DIR", env = "WEBPAGE_SAVE_ARCHIVE_PDF_DIR")]
+        pdf_dir: Option<PathBuf>,
+    },
+    /// Publish the Markdown captures of an output directory as a static website, with
+    /// tag pages and full-text search
+    Publish {
+        /// Output directory the pages were converted into
+        #[arg(value_name = "DIR")]
+        dir: PathBuf,
+
+        /// Directory to write the website into
+        #[arg(short, long, value_name = "SITE", env = "WEBPAGE_SAVE_SITE_DIR")]
+        output_dir: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+enum ReadLaterCommands {
+    /// Convert every article saved in a read-it-later service
+    Pull(Box<ReadLaterPullArgs>),
+    /// Save URLs to a read-it-later service, with the content of their page snapshots
+    Push(ReadLaterPushArgs),
+}
+
+/// Arguments for the `read-later pull` subcommand
+#[derive(Args)]
+struct ReadLaterPullArgs {
+    /// Service to pull saved articles from
+    #[arg(value_enum)]
+    service: ReadLaterServiceArg,
+
+    /// Read the saved articles from the service's CSV export instead of its API
+    /// (required for Pocket, whose API has shut down)
+    #[arg(long, value_name = "CSV", env = "WEBPAGE_SAVE_READ_LATER_EXPORT")]
+    export: Option<PathBuf>,
+
+    /// Only pull articles with this tag
+    #[arg(long, env = "WEBPAGE_SAVE_READ_LATER_TAG")]
+    tag: Option<String>,
+
+    /// Only pull articles in this folder (Instapaper: unread, starred, archive, or a folder
+    /// ID; Readwise: new, later, shortlist, archive, feed; exports: the status column)
+    #[arg(long, env = "WEBPAGE_SAVE_READ_LATER_FOLDER")]
+    folder: Option<String>,
+
+    /// Pull at most this many articles
+    #[arg(long, env = "WEBPAGE_SAVE_READ_LATER_LIMIT")]
+    limit: Option<usize>,
+
+    /// Print the saved articles instead of converting them
+    #[arg(long)]
+    list: bool,
+
+    #[command(flatten)]
+    credentials: ReadLaterCredentials,
+
+    /// Conversion options; URLs given here are converted along with the saved articles
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+/// Arguments for the `read-later push` subcommand
+#[derive(Args)]
+struct ReadLaterPushArgs {
+    /// Service to save the URLs to
+    #[arg(value_enum)]
+    service: ReadLaterServiceArg,
+
+    /// URLs to save
+    #[arg(required = true, value_name = "URL")]
+    urls: Vec<String>,
+
+    /// Tag to add to the saved articles (Readwise only; repeatable)
+    #[arg(long, env = "WEBPAGE_SAVE_READ_LATER_TAG")]
+    tag: Vec<String>,
+
+    /// Save only the URLs, without the captured content of their page snapshots
+    #[arg(long, env = "WEBPAGE_SAVE_READ_LATER_URL_ONLY")]
+    url_only: bool,
+
+    #[command(flatten)]
+    credentials: ReadLaterCredentials,
+}
+
+/// Read-it-later service credentials
+#[derive(Args)]
+struct ReadLaterCredentials {
+    /// Readwise access token (from https://readwise.io/access_token)
+    #[arg(long, env = "READWISE_TOKEN", hide_env_values = true)]
+    readwise_token: Option<String>,
+
+    /// Instapaper API consumer key
+    #[arg(long, env = "INSTAPAPER_CONSUMER_KEY", hide_env_values = true)]
+    instapaper_consumer_key: Option<String>,
+
+    /// Instapaper API consumer secret
+    #[arg(long, env = "INSTAPAPER_CONSUMER_SECRET", hide_env_values = true)]
+    instapaper_consumer_secret: Option<String>,
+
+    /// Instapaper account username or email address
+    #[arg(long, env = "INSTAPAPER_USERNAME")]
+    instapaper_username: Option<String>,
+
+    /// Instapaper account password (omit for accounts without one)
+    #[arg(long, env = "INSTAPAPER_PASSWORD", hide_env_values = true)]
+    instapaper_password: Option<String>,
+}
+
+impl ReadLaterCredentials {
+    /// Connect to a service's API
+    async fn connect(&self, service: &ReadLaterServiceArg) -> Result<ReadLater> {
+        match service {
+            ReadLaterServiceArg::Readwise => {
+                let token = self
+                    .readwise_token
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Set READWISE_TOKEN or use --readwise-token"))?;
+                Ok(ReadLater::Readwise(ReadwiseClient::new(token)?))
+            }
+            ReadLaterServiceArg::Instapaper => {
+                let (Some(key), Some(secret), Some(username)) = (
+                    &self.instapaper_consumer_key,
+                    &self.instapaper_consumer_secret,
+                    &self.instapaper_username,
+                ) else {
+                    return Err(anyhow::anyhow!(
+                        "Set INSTAPAPER_CONSUMER_KEY, INSTAPAPER_CONSUMER_SECRET, and \
+                         INSTAPAPER_USERNAME (or use the --instapaper-* options)"
+                    ));
+                };
+                let password = self.instapaper_password.as_deref().unwrap_or_default();
+                let client = InstapaperClient::login(key, secret, username, password).await?;
+                Ok(ReadLater::Instapaper(client))
+            }
+            ReadLaterServiceArg::Pocket => Err(anyhow::anyhow!(
+                "Pocket has shut down its API; pull from a Pocket export with --export"
+            )),
+        }
+    }
+}
+
+/// Arguments for the `bookmarks` subcommand
+#[derive(Args)]
+struct BookmarksArgs {
+    /// Bookmark service to archive
+    #[arg(value_enum)]
+    service: BookmarkServiceArg,
+
+    /// Only archive bookmarks with this tag
+    #[arg(long, env = "WEBPAGE_SAVE_BOOKMARKS_TAG")]
+    tag: Option<String>,
+
+    /// Archive at most this many bookmarks
+    #[arg(long, env = "WEBPAGE_SAVE_BOOKMARKS_LIMIT")]
+    limit: Option<usize>,
+
+    /// Print the bookmarks instead of converting them
+    #[arg(long)]
+    list: bool,
+
+    /// Record the paths of the local copies in each bookmark's note (requires an output
+    /// directory, and cannot be combined with --archive, --versioned, or --merge)
+    #[arg(long, env = "WEBPAGE_SAVE_BOOKMARKS_WRITE_BACK")]
+    write_back: bool,
+
+    /// Pinboard API token, as user:TOKEN (from https://pinboard.in/settings/password)
+    #[arg(long, env = "PINBOARD_TOKEN", hide_env_values = true)]
+    pinboard_token: Option<String>,
+
+    /// Raindrop.io access token (the test token of an app in the integration settings)
+    #[arg(long, env = "RAINDROP_TOKEN", hide_env_values = true)]
+    raindrop_token: Option<String>,
+
+    /// Conversion options; URLs given here are converted along with the bookmarks
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+impl BookmarksArgs {
+    /// Connect to the bookmark service's API
+    fn connect(&self) -> Result<BookmarkService> {
+        match self.service {
+            BookmarkServiceArg::Pinboard => {
+                let token = self
+                    .pinboard_token
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Set PINBOARD_TOKEN or use --pinboard-token"))?;
+                Ok(BookmarkService::Pinboard(PinboardClient::new(token)?))
+            }
+            BookmarkServiceArg::Raindrop => {
+                let token = self
+                    .raindrop_token
+                    .as_deref()
+                    .ok_or_else(|| anyhow::anyhow!("Set RAINDROP_TOKEN or use --raindrop-token"))?;
+                Ok(BookmarkService::Raindrop(RaindropClient::new(token)?))
+            }
+        }
+    }
+}
+
+/// Arguments for the `thread` subcommand
+///
+/// The URLs are Hacker News item or Reddit post URLs, and the output path is the
+/// directory the threads and articles are saved in (the current directory by default).
+#[derive(Args)]
+struct ThreadArgs {
+    /// Only save the discussions, not the articles they link to
+    #[arg(long, env = "WEBPAGE_SAVE_THREAD_NO_ARTICLE")]
+    no_article: bool,
+
+    /// Conversion options for the linked articles
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+/// Arguments for the `newsletter` subcommand
+///
+/// The URLs are newsletter home pages, and the output path is the directory the posts
+/// are saved in (the current directory by default).
+#[derive(Args)]
+struct NewsletterArgs {
+    /// Newsletter platform (detected from the URL or home page by default)
+    #[arg(long, value_enum, env = "WEBPAGE_SAVE_NEWSLETTER_PLATFORM")]
+    platform: Option<NewsletterPlatformArg>,
+
+    /// Only save posts published on or after this date (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", env = "WEBPAGE_SAVE_NEWSLETTER_SINCE")]
+    since: Option<chrono::NaiveDate>,
+
+    /// Save at most this many of the newest posts of each newsletter
+    #[arg(long, env = "WEBPAGE_SAVE_NEWSLETTER_LIMIT")]
+    limit: Option<usize>,
+
+    /// Print the posts instead of converting them
+    #[arg(long)]
+    list: bool,
+
+    /// Conversion options for the posts
+    #[command(flatten)]
+    convert: ConvertArgs,
+}
+
+/// Arguments for the `crawl` subcommand
+#[derive(Args)]
+struct CrawlArgs {
+    /// A page of the documentation site (the home page for Sphinx sites, whose sidebar
+    /// shows only nearby pages elsewhere)
+    url: String,
+
+    /// Documentation generator (detected from the page by default)
+    #[arg(long, value_enum, env = "WEBPAGE_SAVE_CRAWL_PRESET")]
+    preset: Option<DocsPresetArg>,
+
+    /// Output file path (optional, defaults to the site title, e.g. the-book.pdf)
+    #[arg(short, long, value_name = "PATH", env = "WEBPAGE_SAVE_OUTPUT")]
+    output: Option<PathBuf>,
+
+    /// Output format (pdf, markdown, or both)
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value = "pdf",
+        env = "WEBPAGE_SAVE_FORMAT"
+    )]
+    format: OutputFormat,
+
+    /// Save at most this many pages
+    #[arg(long, default_value_t = crawl::DEFAULT_MAX_PAGES, env = "WEBPAGE_SAVE_CRAWL_MAX_PAGES")]
+    max_pages: usize,
+
+    /// Domain blocklist file (one domain per line, hosts file, or Adblock `||domain^`
+    /// rules); sites and pages on a listed domain are not crawled (repeatable)
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_BLOCKLIST")]
+    blocklist: Vec<PathBuf>,
+
+    /// Print the pages in sidebar order instead of saving them
+    #[arg(long)]
+    list: bool,
+}
+
+/// Arguments for the `docs` subcommand
+#[derive(Args)]
+struct DocsArgs {
+    /// Crate name, optionally with a version (`serde`, `tokio@1.37.0`, or `rand@^0.8`)
+    #[arg(value_name = "CRATE[@VERSION]")]
+    spec: String,
+
+    /// Output file path (optional, defaults to the crate name, e.g. serde-documentation.pdf)
+    #[arg(short, long, value_name = "PATH", env = "WEBPAGE_SAVE_OUTPUT")]
+    output: Option<PathBuf>,
+
+    /// Output format (pdf, markdown, or both)
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value = "pdf",
+        env = "WEBPAGE_SAVE_FORMAT"
+    )]
+    format: OutputFormat,
+
+    /// Save at most this many pages
+    #[arg(long, default_value_t = crawl::DEFAULT_MAX_PAGES, env = "WEBPAGE_SAVE_CRAWL_MAX_PAGES")]
+    max_pages: usize,
+
+    /// Print the pages in order instead of saving them
+    #[arg(long)]
+    list: bool,
+}
+
+/// Arguments for the `login` subcommand
+#[derive(Args)]
+struct LoginArgs {
+    /// Domain to log into (opening its home page), or the URL of its login page
+    #[arg(value_name = "DOMAIN", required_unless_present = "list")]
+    target: Option<String>,
+
+    /// Also keep the session in this Chrome profile directory (created if missing)
+    #[arg(long, value_name = "DIR", env = "WEBPAGE_SAVE_PROFILE_DIR")]
+    profile_dir: Option<PathBuf>,
+
+    /// Delete the saved session of the domain instead of logging in
+    #[arg(long)]
+    logout: bool,
+
+    /// List the domains with a saved session
+    #[arg(long, conflicts_with_all = ["target", "logout"])]
+    list: bool,
+}
+
+/// Arguments for the `redact` subcommand
+#[derive(Args)]
+#[command(group(clap::ArgGroup::new("destination").required(true).args(["output_dir", "in_place"])))]
+struct RedactArgs {
+    /// Markdown/text files, or directories searched recursively for them
+    #[arg(value_name = "PATH", required = true)]
+    paths: Vec<PathBuf>,
+
+    /// Write redacted copies into this directory, mirroring the input directory structure
+    #[arg(short, long, env = "WEBPAGE_SAVE_OUTPUT_DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Overwrite the files with their redacted versions
+    #[arg(long)]
+    in_place: bool,
+
+    /// Name or other literal text to redact, ignoring case (repeatable)
+    #[arg(long, value_name = "TEXT", env = "WEBPAGE_SAVE_REDACT_NAME")]
+    name: Vec<String>,
+
+    /// Regular expression of text to redact (repeatable)
+    #[arg(long, value_name = "REGEX", env = "WEBPAGE_SAVE_REDACT_PATTERN")]
+    pattern: Vec<String>,
+
+    /// Keep email addresses, which are redacted by default
+    #[arg(long, env = "WEBPAGE_SAVE_REDACT_KEEP_EMAILS")]
+    keep_emails: bool,
+
+    /// Keep API keys and tokens, which are redacted by default
+    #[arg(long, env = "WEBPAGE_SAVE_REDACT_KEEP_API_KEYS")]
+    keep_api_keys: bool,
+
+    /// Text replacing redacted matches in Markdown and text
+    #[arg(
+        long,
+        value_name = "TEXT",
+        default_value = redact::DEFAULT_REPLACEMENT,
+        env = "WEBPAGE_SAVE_REDACT_REPLACEMENT"
+    )]
+    replacement: String,
+
+    /// Also render the .html/.mhtml snapshots under the paths to PDFs with the matches
+    /// blacked out, replacing the unredacted PDFs next to them
+    #[arg(long, env = "WEBPAGE_SAVE_REDACT_PDF")]
+    pdf: bool,
+
+    /// Wait time in seconds before printing each redacted PDF (0 disables)
+    #[arg(short, long, default_value = "0", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
+
+    /// Page size of redacted PDFs (default: a4)
+    #[arg(long, value_enum, value_name = "PAPER", env = "WEBPAGE_SAVE_PAPER")]
+    paper: Option<PaperArg>,
+}
+
+/// Arguments for the `index` subcommand
+#[derive(Args)]
+struct IndexArgs {
+    /// Output directory to index
+    #[arg(value_name = "DIR", env = "WEBPAGE_SAVE_OUTPUT")]
+    dir: PathBuf,
+}
+
+/// Arguments for the `evidence` subcommand
+#[derive(Args)]
+struct EvidenceArgs {
+    /// URL to capture
+    #[arg(value_name = "URL")]
+    url: String,
+
+    /// Directory to write the evidence bundle into (defaults to
+    /// evidence-<host>-<timestamp>)
+    #[arg(short, long, value_name = "DIR")]
+    output: Option<PathBuf>,
+
+    /// NTP server to check the local clock against before the capture
+    #[arg(
+        long,
+        value_name = "HOST",
+        default_value = evidence::DEFAULT_NTP_SERVER,
+        env = "WEBPAGE_SAVE_NTP_SERVER"
+    )]
+    ntp_server: String,
+
+    /// RFC 3161 timestamp authority to timestamp SHA256SUMS with, proving when the
+    /// bundle existed; the reply is saved as SHA256SUMS.tsr
+    #[arg(long, value_name = "URL", env = "WEBPAGE_SAVE_TSA_URL")]
+    tsa_url: Option<String>,
+
+    /// Wait time in seconds after the page loads, for resources fetched by scripts
+    #[arg(short, long, default_value = "2", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
+}
+
+/// Arguments for the `price-history` subcommand
+#[derive(Args)]
+struct PriceHistoryArgs {
+    /// Output directory the product pages were saved into
+    #[arg(value_name = "DIR", env = "WEBPAGE_SAVE_OUTPUT")]
+    dir: PathBuf,
+
+    /// Only show the history of this URL
+    #[arg(value_name = "URL")]
+    url: Option<String>,
+
+    /// Print the history as a JSON array of objects with url and prices
+    #[arg(long)]
+    json: bool,
+}
+
+/// Arguments for the `changelog` subcommand
+#[derive(Args)]
+struct ChangelogArgs {
+    /// URL of the changelog or release notes page
+    #[arg(value_name = "URL")]
+    url: String,
+
+    /// Output directory, which keeps the versions seen at earlier checks
+    #[arg(short, long, default_value = ".", env = "WEBPAGE_SAVE_OUTPUT_DIR")]
+    output_dir: PathBuf,
+
+    /// Load the page in headless Chrome and read the DOM once its scripts have run
+    #[arg(long, env = "WEBPAGE_SAVE_RENDER_JS")]
+    render_js: bool,
+
+    /// POST new releases as JSON to this URL (e.g. a Slack or chat webhook)
+    #[arg(long, value_name = "URL", env = "WEBPAGE_SAVE_WEBHOOK")]
+    webhook: Option<String>,
+
+    /// Email new releases to this address (requires --mail-from)
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        requires = "mail_from",
+        env = "WEBPAGE_SAVE_NOTIFY_EMAIL"
+    )]
+    notify_email: Option<String>,
+
+    /// Sender of --notify-email email
+    #[arg(long, value_name = "ADDRESS", env = "WEBPAGE_SAVE_MAIL_FROM")]
+    mail_from: Option<String>,
+
+    /// sendmail-compatible command that sends --notify-email email
+    #[arg(
+        long,
+        value_name = "COMMAND",
+        default_value = "sendmail",
+        env = "WEBPAGE_SAVE_SENDMAIL"
+    )]
+    sendmail: String,
+}
+
+impl ChangelogArgs {
+    /// Where to send notes of new releases
+    fn notifier(&self) -> Result<ReleaseNotifier> {
+        let mut notifier = ReleaseNotifier::new()?;
+        if let Some(webhook) = &self.webhook {
+            notifier = notifier.with_webhook(webhook)?;
+        }
+        if let (Some(to), Some(from)) = (&self.notify_email, &self.mail_from) {
+            notifier = notifier.with_email(to, from, &self.sendmail)?;
+        }
+        Ok(notifier)
+    }
+}
+
+/// Arguments for the `diff` subcommand
+#[derive(Args)]
+struct DiffArgs {
+    /// Old Markdown capture, or the URL of a page captured with --versioned
+    #[arg(value_name = "OLD|URL")]
+    old: String,
+
+    /// New Markdown capture (omitted when comparing the captures of a URL)
+    #[arg(value_name = "NEW")]
+    new: Option<PathBuf>,
+
+    /// Directory the URL was captured into with --versioned
+    #[arg(
+        long,
+        value_name = "DIR",
+        default_value = ".",
+        env = "WEBPAGE_SAVE_OUTPUT_DIR"
+    )]
+    dir: PathBuf,
+
+    /// Compare from the last capture of the URL taken on or before this date
+    /// (YYYY-MM-DD; defaults to the capture before --to)
+    #[arg(long, value_name = "DATE")]
+    from: Option<chrono::NaiveDate>,
+
+    /// Compare to the last capture of the URL taken on or before this date (YYYY-MM-DD;
+    /// defaults to the latest capture)
+    #[arg(long, value_name = "DATE")]
+    to: Option<chrono::NaiveDate>,
+
+    /// Write the diff as an HTML page with the changes highlighted, or as a PDF when the
+    /// output file ends in .pdf, instead of printing it
+    #[arg(long)]
+    render: bool,
+
+    /// Output file of --render
+    #[arg(
+        short,
+        long,
+        value_name = "PATH",
+        default_value = "diff.html",
+        requires = "render"
+    )]
+    output: PathBuf,
+}
+
+/// Arguments for the `batch` subcommand
+#[derive(Args)]
+struct BatchArgs {
+    /// File with one URL per line (blank lines and # comments are skipped), or - to read
+    /// the list from stdin
+    #[arg(value_name = "FILE")]
+    input: PathBuf,
+
+    /// Output format (pdf, markdown, or both)
+    #[arg(
+        short,
+        long,
+        value_enum,
+        default_value = "pdf",
+        env = "WEBPAGE_SAVE_FORMAT"
+    )]
+    format: OutputFormat,
+
+    /// Output directory
+    #[arg(short, long, default_value = ".", env = "WEBPAGE_SAVE_OUTPUT_DIR")]
+    output_dir: PathBuf,
+
+    /// Most URLs converted at a time, each PDF in its own browser tab
+    #[arg(
+        long,
+        value_name = "N",
+        default_value_t = pdf::DEFAULT_CONCURRENCY,
+        env = "WEBPAGE_SAVE_CONCURRENCY"
+    )]
+    concurrency: usize,
+
+    /// Wait time in seconds before generating each PDF (for dynamic content, 0 disables)
+    #[arg(short, long, default_value = "2", env = "WEBPAGE_SAVE_WAIT")]
+    wait: u64,
+
+    /// Render pages with JavaScript in headless Chrome before converting them to
+    /// Markdown, for single-page applications that are empty without it
+    #[arg(long, env = "WEBPAGE_SAVE_RENDER_JS")]
+    render_js: bool,
+
+    /// Where to write the JSON report of the batch (default: batch_report.json in the
+    /// output directory)
+    #[arg(long, value_name = "PATH", env = "WEBPAGE_SAVE_BATCH_REPORT")]
+    report: Option<PathBuf>,
+}
+
+impl RedactArgs {
+    /// The redactor for the rules given on the command line
+    fn redactor(&self) -> Result<Redactor> {
+        let mut redactor = Redactor::new().with_replacement(self.replacement.clone());
+        if !self.keep_emails {
+            redactor = redactor.with_emails();
+        }
+        if !self.keep_api_keys {
+            redactor = redactor.with_api_keys();
+        }
+        for name in self.name.iter().filter(|name| !name.trim().is_empty()) {
+            redactor = redactor.with_name(name);
+        }
+        for pattern in &self.pattern {
+            redactor = redactor.with_pattern(pattern)?;
+        }
+        if redactor.is_empty() {
+            return Err(anyhow::anyhow!("No redaction rules left to apply"));
+        }
+        Ok(redactor)
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum DocsPresetArg {
+    Mdbook,
+    Docusaurus,
+    Sphinx,
+    Rustdoc,
+}
+
+impl From<DocsPresetArg> for DocsPreset {
+    fn from(preset: DocsPresetArg) -> Self {
+        match preset {
+            DocsPresetArg::Mdbook => Self::MdBook,
+            DocsPresetArg::Docusaurus => Self::Docusaurus,
+            DocsPresetArg::Sphinx => Self::Sphinx,
+            DocsPresetArg::Rustdoc => Self::Rustdoc,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum NewsletterPlatformArg {
+    Substack,
+    Ghost,
+    Buttondown,
+}
+
+impl From<NewsletterPlatformArg> for NewsletterPlatform {
+    fn from(platform: NewsletterPlatformArg) -> Self {
+        match platform {
+            NewsletterPlatformArg::Substack => Self::Substack,
+            NewsletterPlatformArg::Ghost => Self::Ghost,
+            NewsletterPlatformArg::Buttondown => Self::Buttondown,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum BookmarkServiceArg {
+    Pinboard,
+    Raindrop,
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum ReadLaterServiceArg {
+    Pocket,
+    Instapaper,
+    Readwise,
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum OutputFormat {
+    Pdf,
+    Markdown,
+    Both,
+    Screenshot,
+    Epub,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum FrontMatterArg {
+    Yaml,
+    Toml,
+}
+
+impl From<FrontMatterArg> for FrontMatterFormat {
+    fn from(arg: FrontMatterArg) -> Self {
+        match arg {
+            FrontMatterArg::Yaml => FrontMatterFormat::Yaml,
+            FrontMatterArg::Toml => FrontMatterFormat::Toml,
+        }
+    }
+}
+
+/// The Markdown output options of a front matter choice and --strip-links
+fn markdown_config(front_matter: Option<FrontMatterArg>, strip_links: bool) -> MarkdownConfig {
+    MarkdownConfig {
+        front_matter: front_matter.map(Into::into).unwrap_or_default(),
+        strip_links,
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum ChunkUnitArg {
+    Tokens,
+    Chars,
+}
+
+impl From<ChunkUnitArg> for ChunkUnit {
+    fn from(arg: ChunkUnitArg) -> Self {
+        match arg {
+            ChunkUnitArg::Tokens => ChunkUnit::Tokens,
+            ChunkUnitArg::Chars => ChunkUnit::Chars,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum ReaderPresetArg {
+    Screen,
+    Print,
+    Ereader,
+}
+
+impl From<ReaderPresetArg> for TypographyPreset {
+    fn from(arg: ReaderPresetArg) -> Self {
+        match arg {
+            ReaderPresetArg::Screen => TypographyPreset::Screen,
+            ReaderPresetArg::Print => TypographyPreset::Print,
+            ReaderPresetArg::Ereader => TypographyPreset::Ereader,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum PaperArg {
+    A4,
+    A5,
+    A3,
+    Letter,
+    Legal,
+    Kindle,
+    Kobo,
+    Remarkable,
+}
+
+impl From<PaperArg> for Paper {
+    fn from(arg: PaperArg) -> Self {
+        match arg {
+            PaperArg::A4 => Paper::A4,
+            PaperArg::A5 => Paper::A5,
+            PaperArg::A3 => Paper::A3,
+            PaperArg::Letter => Paper::Letter,
+            PaperArg::Legal => Paper::Legal,
+            PaperArg::Kindle => Paper::Kindle,
+            PaperArg::Kobo => Paper::Kobo,
+            PaperArg::Remarkable => Paper::Remarkable,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum ContentModeArg {
+    Auto,
+    Page,
+    Recipe,
+}
+
+impl From<ContentModeArg> for ContentMode {
+    fn from(arg: ContentModeArg) -> Self {
+        match arg {
+            ContentModeArg::Auto => ContentMode::Auto,
+            ContentModeArg::Page => ContentMode::Page,
+            ContentModeArg::Recipe => ContentMode::Recipe,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy)]
+enum MediaArg {
+    Screen,
+    Print,
+}
+
+impl From<MediaArg> for Media {
+    fn from(arg: MediaArg) -> Self {
+        match arg {
+            MediaArg::Screen => Media::Screen,
+            MediaArg::Print => Media::Print,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum ScreenshotFormatArg {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl From<ScreenshotFormatArg> for ScreenshotFormat {
+    fn from(arg: ScreenshotFormatArg) -> Self {
+        match arg {
+            ScreenshotFormatArg::Png => ScreenshotFormat::Png,
+            ScreenshotFormatArg::Jpeg => ScreenshotFormat::Jpeg,
+            ScreenshotFormatArg::Webp => ScreenshotFormat::Webp,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum SearchTypeArg {
+    Web,
+    News,
+    Local,
+}
+
+impl From<SearchTypeArg> for SearchType {
+    fn from(arg: SearchTypeArg) -> Self {
+        match arg {
+            SearchTypeArg::Web => SearchType::Web,
+            SearchTypeArg::News => SearchType::News,
+            SearchTypeArg::Local => SearchType::Local,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone)]
+enum NamingStrategyArg {
+    Title,
+    Domain,
+    Sequential,
+    #[value(name = "title-domain")]
+    TitleDomain,
+}
+
+impl From<NamingStrategyArg> for NamingStrategy {
+    fn from(arg: NamingStrategyArg) -> Self {
+        match arg {
+            NamingStrategyArg::Title => NamingStrategy::Title,
+            NamingStrategyArg::Domain => NamingStrategy::Domain,
+            NamingStrategyArg::Sequential => NamingStrategy::Sequential,
+            NamingStrategyArg::TitleDomain => NamingStrategy::TitleDomain,
+        }
+    }
+}
+
+impl From<OutputFormat> for IntegrationOutputFormat {
+    fn from(arg: OutputFormat) -> Self {
+        match arg {
+            OutputFormat::Pdf => IntegrationOutputFormat::Pdf,
+            OutputFormat::Markdown => IntegrationOutputFormat::Markdown,
+            OutputFormat::Both => IntegrationOutputFormat::Both,
+            OutputFormat::Screenshot => IntegrationOutputFormat::Screenshot,
+            OutputFormat::Epub => IntegrationOutputFormat::Epub,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    LANGUAGE
+        .set(cli.lang.map_or_else(Language::detect, Into::into))
+        .ok();
+
+    // Initialize logging
+    if cli.verbose {
+        tracing_subscriber::fmt().with_env_filter("debug").init();
+    } else {
+        tracing_subscriber::fmt().with_env_filter("info").init();
+    }
+
+    // A proxy resolves the hosts sent through it, so an explicit resolver replaces
+    // the proxy of the environment
+    let resolver = match init_resolver(cli.dns_server.as_deref(), &cli.resolve, &cli.unix_socket) {
+        Ok(resolver) => resolver,
+        Err(e) => {
+            error!("Invalid DNS settings: {}", e);
+            eprintln!("✗ Invalid DNS settings: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(server) = resolver.as_ref().and_then(DnsResolver::server) {
+        info!("Resolving hosts with {}", server);
+    }
+    for (host, addresses) in resolver.iter().flat_map(DnsResolver::overrides) {
+        info!("Resolving {} to {:?}", host, addresses);
+    }
+    for (host, path) in resolver.iter().flat_map(DnsResolver::unix_sockets) {
+        info!("Serving {} from Unix socket {}", host, path.display());
+    }
+
+    // Hosts served from Unix sockets have no addresses, so every client goes through
+    // the resolver's proxy to reach them. An upstream proxy resolves hosts itself, so
+    // the resolver's proxy cannot be chained to it; clap rejects the combination, and
+    // it is refused here too rather than silently bypassing the resolver in Chrome.
+    let proxy = match (&cli.proxy, &resolver) {
+        (Some(_), Some(_)) => Err(anyhow::anyhow!(
+            "--dns-server, --resolve, and --unix-socket cannot be combined with --proxy"
+        )),
+        (Some(url), None) => ProxyConfig::new(url).map(Some),
+        (None, Some(resolver)) if !resolver.unix_sockets().is_empty() => {
+            resolver.browser_proxy().map(Some)
+        }
+        (None, Some(_)) => Ok(None),
+        (None, None) => ProxyConfig::from_env(),
+    };
+    let proxy = proxy.and_then(|proxy| {
+        let browser = match (&proxy, &resolver) {
+            (Some(proxy), _) => Some(proxy.clone()),
+            (None, Some(resolver)) => Some(resolver.browser_proxy()?),
+            (None, None) => None,
+        };
+        Ok((proxy, browser))
+    });
+    match proxy {
+        Ok((proxy, browser)) => {
+            if let Some(proxy) = &proxy {
+                info!("Using proxy {}", proxy);
+            }
+            PROXY.set(proxy).ok();
+            RESOLVER.set(resolver).ok();
+            BROWSER_PROXY.set(browser).ok();
+        }
+        Err(e) => {
+            error!("Invalid proxy: {}", e);
+            eprintln!("✗ Invalid proxy: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // A bare `webpage-save <URL>` is shorthand for `webpage-save convert <URL>`
+    match cli.command.unwrap_or(Commands::Convert(cli.convert)) {
+        Commands::Convert(args) => run_convert(args).await,
+        Commands::Batch(args) => run_batch(args).await,
+        Commands::Search(args) => run_search(args).await,
+        Commands::SearchToPdf(args) => run_search_to_pdf(args).await,
+        Commands::Render(args) => run_render(args).await,
+        Commands::Embed(args) => run_embed(args).await,
+        Commands::Archive(args) => run_archive(args).await,
+        Commands::ReadLater(command) => run_read_later(command).await,
+        Commands::Bookmarks(args) => run_bookmarks(*args).await,
+        Commands::Thread(args) => run_thread(*args).await,
+        Commands::Newsletter(args) => run_newsletter(*args).await,
+        Commands::Crawl(args) => run_crawl(args).await,
+        Commands::Docs(args) => run_docs(args).await,
+        Commands::Login(args) => run_login(args).await,
+        Commands::Redact(args) => run_redact(args).await,
+        Commands::Index(args) => run_index(args).await,
+        Commands::PriceHistory(args) => run_price_history(args).await,
+        Commands::Changelog(args) => run_changelog(args).await,
+        Commands::Diff(args) => run_diff(args).await,
+        Commands::Evidence(args) => run_evidence(args).await,
+        Commands::Auth(command) => run_auth(command),
+    }
+}
+
+/// Handle the `auth` subcommands
+fn run_auth(command: AuthCommands) -> Result<()> {
+    let result = match command {
+        AuthCommands::SetKey => read_api_key().and_then(|key| {
+            auth::store_api_key(&key)?;
+            println!("✓ Brave API key stored in the OS keyring");
+            Ok(())
+        }),
+        AuthCommands::DeleteKey => auth::delete_api_key().map(|deleted| {
+            if deleted {
+                println!("✓ Brave API key removed from the OS keyring");
+            } else {
+                println!("No Brave API key stored in the OS keyring");
+            }
+        }),
+        AuthCommands::Status => auth::load_api_key().map(|key| match key {
+            Some(key) => println!(
+                "✓ Brave API key stored in the OS keyring ({})",
+                auth::mask_api_key(&key)
+            ),
+            None => println!("No Brave API key stored in the OS keyring"),
+        }),
+    };
+
+    if let Err(e) = result {
+        error!("Keyring operation failed: {}", e);
+        eprintln!("✗ Keyring operation failed: {}", e);
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Read the API key from a hidden prompt, or from stdin when it is not a terminal
+fn read_api_key() -> Result<String> {
+    if std::io::stdin().is_terminal() {
+        Ok(rpassword::prompt_password("Brave API key: ")?)
+    } else {
+        let mut key = String::new();
+        std::io::stdin().read_line(&mut key)?;
+        Ok(key.trim().to_string())
+    }
+}
+
+/// Handle the `search` subcommand
+async fn run_search(args: SearchArgs) -> Result<()> {
+    if let Some(SearchCommands::Check { api_key }) = args.command {
+        return run_search_check(api_key).await;
+    }
+    let (Some(search_type), Some(query)) = (args.search_type, args.query) else {
+        return Err(anyhow::anyhow!("Search type and query are required"));
+    };
+
+    info!(
+        "Performing {} search for: {}",
+        SearchType::from(search_type.clone()),
+        query
+    );
+
+    // Create search client
+    let client = match BraveSearchClient::new(args.api_key) {
+        Ok(client) => match (proxy(), resolver()) {
+            (Some(proxy), _) => client.with_proxy(proxy.clone()),
+            (None, Some(resolver)) => client.with_resolver(resolver.clone()),
+            (None, None) => client,
+        },
+        Err(e) => {
+            error!("Failed to initialize Brave search client: {}", e);
+            eprintln!("✗ Failed to initialize Brave search client: {}", e);
+            eprintln!(
+                "  Set BRAVE_API_KEY, use --api-key, or store a key with 'webpage-save auth set-key'"
+            );
+            std::process::exit(1);
+        }
+    };
+    let client = match args.cache.search_cache() {
+        Some(cache) => client.with_cache(cache),
+        None => client,
+    }
+    .with_offline(args.cache.offline);
+
+    // Create search configuration
+    let config = SearchConfig {
+        count: args.count,
+        offset: args.offset,
+        country: args.country,
+        language: args.language,
+        freshness: args.freshness,
+    };
+
+    if args.json {
+        match client
+            .search_structured(search_type.into(), &query, Some(config))
+            .await
+        {
+            Ok(results) => println!("{}", serde_json::to_string_pretty(&results)?),
+            Err(e) => {
+                error!("Search failed: {}", e);
+                eprintln!("✗ Search failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
+    // Perform search
+    match client
+        .search(search_type.into(), &query, Some(config))
+        .await
+    {
+        Ok(results) => {
+            println!("Search Results:");
+            println!("==============");
+            println!("{}", results);
+        }
+        Err(e) => {
+            error!("Search failed: {}", e);
+            eprintln!("✗ Search failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// Handle the `search check` subcommand
+async fn run_search_check(api_key: Option<String>) -> Result<()> {
+    let client = match BraveSearchClient::new(api_key) {
+        Ok(client) => match (proxy(), resolver()) {
+            (Some(proxy), _) => client.with_proxy(proxy.clone()),
+            (None, Some(resolver)) => client.with_resolver(resolver.clone()),
+            (None, None) => client,
+        },
+        Err(e) => {
+            error!("Failed to initialize Brave search client: {}", e);
+            eprintln!("✗ No Brave API key configured: {}", e);
+            eprintln!(
+                "  Set BRAVE_API_KEY, use --api-key, or store a key with 'webpage-save auth set-key'"
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let check = match client.check_api_key().await {
+        Ok(check) => check,
+        Err(e) => {
+            error!("API key check failed: {}", e);
+            eprintln!("✗ {}", e);
+            eprintln!("  Check your network connection and proxy settings");
+            std::process::exit(1);
+        }
+    };
+
+    match check.status {
+        KeyStatus::Valid => println!("✓ Brave API key is valid"),
+        KeyStatus::InvalidKey => eprintln!(
+            "✗ Brave API key was rejected (HTTP {}); verify the key in your Brave Search API dashboard",
+            check.http_status
+        ),
+        KeyStatus::QuotaExceeded => eprintln!(
+            "✗ Brave API key is valid but its rate limit or quota is exhausted (HTTP {}); wait for the reset or upgrade your plan",
+            check.http_status
+        ),
+        KeyStatus::Unexpected(status) => eprintln!(
+            "✗ Unexpected response from the Brave Search API (HTTP {})",
+            status
+        ),
+    }
+
+    let quota = &check.quota;
+    for (label, value) in [
+        ("Rate limit", &quota.limit),
+        ("Remaining", &quota.remaining),
+        ("Reset (seconds)", &quota.reset),
+        ("Policy", &quota.policy),
+    ] {
+        if let Some(value) = value {
+            println!("  {}: {}", label, value);
+        }
+    }
+
+    if check.status != KeyStatus::Valid {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Handle the `search-to-pdf` subcommand
+async fn run_search_to_pdf(args: SearchToPdfArgs) -> Result<()> {
+    info!(
+        "Performing {} search-to-PDF for: {} (max results: {})",
+        SearchType::from(args.search_type.clone()),
+        args.query,
+        args.max_results
+    );
+
+    // Create search-to-PDF client
+    let delivery = init_delivery(&args.device);
+    let timeouts = AdaptiveTimeouts::new(
+        timeouts::DEFAULT_TIMEOUT,
+        Duration::from_secs(args.max_timeout),
+    );
+    let pii_scanner = args.scan_pii.then(PiiScanner::new);
+    let client = match (&args.sites.profile_dir, proxy(), resolver()) {
+        (profile_dir, Some(proxy), _) => {
+            SearchToPdfClient::new_with_proxy(args.api_key, profile_dir.as_deref(), proxy.clone())
+                .await
+        }
+        (profile_dir, None, Some(resolver)) => {
+            SearchToPdfClient::new_with_resolver(
+                args.api_key,
+                profile_dir.as_deref(),
+                resolver.clone(),
+            )
+            .await
+        }
+        (Some(dir), None, None) => SearchToPdfClient::new_with_profile(args.api_key, dir).await,
+        (None, None, None) => SearchToPdfClient::new(args.api_key).await,
+    };
+    let client = match client {
+        Ok(client) => {
+            let mut client = client
+                .with_wait(Duration::from_secs(args.wait))
+                .with_adaptive_timeouts(timeouts.clone())
+                .with_follow_pagination(args.follow_pagination)
+                .with_render_js(args.render_js)
+                .with_extraction(init_extraction(&args.extraction))
+                .with_pdf_config(args.rendering.pdf_config(args.device.paper()));
+            if let Some(wait) = args.rendering.wait_strategy() {
+                client = client.with_wait_strategy(wait);
+            }
+            if let Some(branding) = init_branding(&args.branding) {
+                client = client.with_branding(branding);
+            }
+            if let Some(reader) = init_reader(&args.reader, &args.extraction, &args.device) {
+                client = client.with_reader(reader);
+            }
+            if let Some(deterministic) = args.rendering.deterministic() {
+                client = client.with_deterministic(deterministic);
+            }
+            if let Some(media) = args.rendering.media {
+                client = client.with_media(media.into());
+            }
+            if let Some(viewport) = args.rendering.viewport {
+                client = client.with_viewport(viewport);
+            }
+            if let Some(scanner) = &pii_scanner {
+                client = client.with_pii_scanner(scanner.clone());
+            }
+            if let Some(alt_text) = init_alt_text(&args.alt_text) {
+                client = client.with_alt_text(alt_text);
+            }
+            if let Some(images) = args.images.markdown_images() {
+                client = client.with_markdown_images(images);
+            }
+            client =
+                client.with_markdown_config(markdown_config(args.front_matter, args.strip_links));
+            if let Some(blocklist) = init_blocklist(&args.blocklist).await {
+                client = client.with_blocklist(blocklist);
+            }
+            if let Some(maps) = init_place_maps(&args.place_maps) {
+                client = client.with_place_maps(maps);
+            }
+            if let Some(hook) = init_scan_hook(&args.scan, &args.output_dir) {
+                client = client.with_scan_hook(hook);
+            }
+            if let Some(cache) = args.cache.search_cache() {
+                client = client.with_search_cache(cache);
+            }
+            if let Some(cache) = args.cache.page_cache() {
+                client = client.with_page_cache(cache);
+            }
+            if let Some(print_views) = init_print_views(&args.sites).await {
+                client = client.with_print_views(print_views);
+            }
+            if let Some(social_threads) = init_social_threads(&args.sites) {
+                client = client.with_social_threads(social_threads);
+            }
+            if let Some(wikipedia) = init_wikipedia(&args.sites) {
+                client = client.with_wikipedia(wikipedia);
+            }
+            if !args.cache.offline {
+                if let Some(cookies) = init_cookies(&args.sites) {
+                    client = client.with_cookies(cookies);
+                }
+                if let Some(options) = init_request_options(&args.sites) {
+                    client = client.with_request_options(options);
+                }
+            }
+            client.with_offline(args.cache.offline)
+        }
+        Err(e) => {
+            error!("Failed to initialize search-to-PDF client: {}", e);
+            eprintln!("✗ Failed to initialize search-to-PDF client: {}", e);
+            eprintln!(
+                "  Set BRAVE_API_KEY, use --api-key, or store a key with 'webpage-save auth set-key'"
+            );
+            std::process::exit(1);
+        }
+    };
+
+    // Create search configuration
+    let search_config = SearchConfig {
+        count: args.count,
+        offset: args.offset,
+        country: args.country,
+        language: args.language,
+        freshness: args.freshness,
+    };
+
+    // Create PDF configuration
+    let pdf_config = SearchToPdfConfig {
+        max_results: args.max_results,
+        output_dir: args.output_dir,
+        include_metadata: true,
+        naming_strategy: args.naming.into(),
+        output_format: args.format.into(),
+        merge_markdown: args.merge,
+        chunking: init_chunking(&args.chunking),
+        force: args.force,
+        check_space: !args.no_space_check,
+        max_snippets: args.max_snippets,
+        concurrency: args.concurrency,
+        extract_only: args.extract_only,
+        screenshots: args.screenshots,
+        screenshot_format: args.rendering.screenshot_format.into(),
+        calendar: args.calendar,
+        contacts: args.contacts,
+    };
+
+    // Perform search and convert to PDF
+    let mut stats = RunStats::new();
+    let converted = client
+        .search_and_convert_with_stats(
+            args.search_type.into(),
+            &args.query,
+            Some(search_config),
+            pdf_config,
+            &mut stats,
+        )
+        .await;
+    report_stats(
+        &stats,
+        &timeouts,
+        pii_scanner.as_ref(),
+        args.stats_json.as_deref(),
+    )
+    .await;
+    match converted {
+        Ok(output_files) => {
+            println!("✓ Successfully converted {} URLs:", output_files.len());
+            for (index, output_path) in output_files.iter().enumerate() {
+                println!("  {}. {}", index + 1, output_path.display());
+            }
+            let mut undelivered = 0;
+            for pdf_path in output_files
+                .iter()
+                .filter(|path| path.extension().is_some_and(|ext| ext == "pdf"))
+            {
+                if deliver_pdf(&delivery, pdf_path).await.is_err() {
+                    undelivered += 1;
+                }
+            }
+            if undelivered > 0 {
+                eprintln!("✗ {} PDFs could not be delivered", undelivered);
+                std::process::exit(1);
+            }
+        }
+        Err(e) => {
+            error!("Search-to-format operation failed: {}", e);
+            eprintln!("✗ Search-to-format operation failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    Ok(())
+}
+
+/// A single `convert` input: a URL or a local HTML file
+enum ConvertInput {
+    Url(String),
+    File(render::LocalFile),
+}
+
+/// Handle the `convert` subcommand (and the bare-URL shortcut)
+async fn run_convert(args: ConvertArgs) -> Result<()> {
+    let run = convert_inputs(args).await?;
+    if run.failed > 0 {
+        eprintln!(
+            "✗ {}",
+            tr(Message::InputsFailed {
+                failed: run.failed,
+                total: run.total
+            })
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Number of inputs in a conversion run, and how many of them failed
+struct ConvertRun {
+    total: usize,
+    failed: usize,
+}
+
+/// Convert every input given to `convert`, reporting the outcome of each
+async fn convert_inputs(mut args: ConvertArgs) -> Result<ConvertRun> {
+    if args.urls.is_empty() {
+        eprintln!("✗ {}", tr(Message::NoUrl));
+        eprintln!("  Use 'webpage-save convert <URL>...' or 'webpage-save search <type> <query>'");
+        std::process::exit(1);
+    }
+    if args.archive.is_some()
+        && matches!(args.format, OutputFormat::Screenshot | OutputFormat::Epub)
+    {
+        eprintln!(
+            "✗ Screenshots and EPUB books cannot be stored in an archive; use --format pdf or markdown"
+        );
+        std::process::exit(1);
+    }
+    if args.merge && matches!(args.format, OutputFormat::Epub) {
+        eprintln!("✗ --merge combines Markdown output; use --format markdown or both");
+        std::process::exit(1);
+    }
+    let recorder = init_recorder(args.record.as_deref(), args.replay.as_deref());
+    if args.wikipedia_see_also && !args.offline && recorder.is_none() {
+        add_see_also(&mut args.urls).await?;
+    }
+
+    // Local paths, directories, and glob patterns expand to the HTML files they match
+    let mut failed = 0;
+    let mut inputs = Vec::new();
+    for input in &args.urls {
+        if !render::is_local_input(input) {
+            inputs.push(ConvertInput::Url(input.clone()));
+            continue;
+        }
+        match render::expand_local_input(input) {
+            Ok(files) => inputs.extend(files.into_iter().map(ConvertInput::File)),
+            Err(e) => {
+                error!("Invalid input {}: {}", input, e);
+                eprintln!("✗ {}", tr(Message::InvalidInput { input, error: &e }));
+                failed += 1;
+            }
+        }
+    }
+    let total = inputs.len() + failed;
+
+    // When archiving, output is written to a temporary directory and then stored in the database
+    let mut archive = args.archive.as_deref().map(init_archive);
+    let archive_dir = match &archive {
+        Some(_) => Some(tempfile::TempDir::new()?),
+        None => None,
+    };
+
+    // Multiple inputs, or an output path that names a directory, switch to per-input filenames.
+    // When merging, an output file names the merged document and other output goes beside it.
+    let merge_path = args.merge.then(|| match &args.output {
+        Some(path) if !is_directory_path(path) => path.clone(),
+        Some(dir) => dir.join("merged.md"),
+        None => PathBuf::from("merged.md"),
+    });
+    let output_dir = match (&args.output, &merge_path) {
+        _ if archive_dir.is_some() => archive_dir.as_ref().map(|dir| dir.path().to_path_buf()),
+        (output, _) if args.versioned => Some(output.clone().unwrap_or_else(|| PathBuf::from("."))),
+        (_, Some(merge_path)) => Some(
+            merge_path
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| PathBuf::from(".")),
+        ),
+        (Some(path), None) if total > 1 || is_directory_path(path) => Some(path.clone()),
+        (None, None) if total > 1 => Some(PathBuf::from(".")),
+        _ => None,
+    };
+    if let Some(dir) = &output_dir {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    if !args.no_space_check {
+        let destination = match (&args.archive, &output_dir, &args.output) {
+            (Some(archive), _, _) => archive.as_path(),
+            (None, Some(dir), _) => dir.as_path(),
+            (None, None, Some(output)) => output.as_path(),
+            (None, None, None) => Path::new("."),
+        };
+        check_disk_space(destination, inputs.len(), &args.format);
+    }
+
+    let page_cache = page_cache(args.no_cache);
+    let print_views = init_print_views(&args.sites).await;
+    // API-based captures bypass the browser, so recordings cover only the pages themselves
+    let (social_threads, wikipedia) = match recorder {
+        Some(_) => (None, None),
+        None => (
+            init_social_threads(&args.sites),
+            init_wikipedia(&args.sites),
+        ),
+    };
+    let (cookies, request_options) = match args.offline || args.replay.is_some() {
+        true => (None, None),
+        false => (init_cookies(&args.sites), init_request_options(&args.sites)),
+    };
+    let delivery = init_delivery(&args.device);
+    let timeouts = AdaptiveTimeouts::new(
+        timeouts::DEFAULT_TIMEOUT,
+        Duration::from_secs(args.max_timeout),
+    );
+    let pii_scanner = args.scan_pii.then(PiiScanner::new);
+    // Archived captures are converted into a temporary directory, so quarantine beside it
+    let scan_hook = init_scan_hook(
+        &args.scan,
+        match (&archive_dir, &output_dir) {
+            (None, Some(dir)) => dir.as_path(),
+            _ => Path::new("."),
+        },
+    );
+    let tsa = args.tsa_url.as_deref().map(init_timestamp_authority);
+    let network_log = args.security_report.then(NetworkLog::new);
+    let screenshot = args.rendering.screenshot(&args.format);
+    let pdf_generator = match args.format {
+        OutputFormat::Pdf | OutputFormat::Both | OutputFormat::Screenshot => {
+            let mut generator = init_pdf_generator(args.sites.profile_dir.as_deref())
+                .await
+                .with_wait(Duration::from_secs(args.wait))
+                .with_offline(args.offline)
+                .with_adaptive_timeouts(timeouts.clone())
+                .with_follow_pagination(args.follow_pagination)
+                .with_config(args.rendering.pdf_config(args.device.paper()));
+            if let Some(cache) = &page_cache {
+                generator = generator.with_page_cache(cache.clone());
+            }
+            if let Some(print_views) = &print_views {
+                generator = generator.with_print_views(print_views.clone());
+            }
+            if let Some(social_threads) = &social_threads {
+                generator = generator.with_social_threads(social_threads.clone());
+            }
+            if let Some(wikipedia) = &wikipedia {
+                generator = generator.with_wikipedia(wikipedia.clone());
+            }
+            if let Some(cookies) = &cookies {
+                generator = generator.with_cookies(cookies.clone());
+            }
+            if let Some(options) = &request_options {
+                generator = generator.with_request_options(options.clone());
+            }
+            if let Some(recorder) = &recorder {
+                generator = generator.with_recorder(recorder.clone());
+            }
+            if let Some(log) = &network_log {
+                generator = generator.with_network_log(log.clone());
+            }
+            if let Some(branding) = init_branding(&args.branding) {
+                generator = generator.with_branding(branding);
+            }
+            if let Some(reader) = init_reader(&args.reader, &args.extraction, &args.device) {
+                generator = generator.with_reader(reader);
+            }
+            if let Some(deterministic) = args.rendering.deterministic() {
+                generator = generator.with_deterministic(deterministic);
+            }
+            if let Some(wait) = args.rendering.wait_strategy() {
+                generator = generator.with_wait_strategy(wait);
+            }
+            if let Some(media) = args.rendering.media {
+                generator = generator.with_media(media.into());
+            }
+            if let Some(viewport) = args.rendering.viewport {
+                generator = generator.with_viewport(viewport);
+            }
+            if let Some(scanner) = &pii_scanner {
+                generator = generator.with_pii_scanner(scanner.clone());
+            }
+            Some(generator)
+        }
+        OutputFormat::Markdown | OutputFormat::Epub => None,
+    };
+    let md_generator = match args.format {
+        OutputFormat::Markdown | OutputFormat::Both | OutputFormat::Epub => {
+            let mut generator = init_markdown_generator()
+                .await
+                .with_offline(args.offline)
+                .with_adaptive_timeouts(timeouts.clone())
+                .with_follow_pagination(args.follow_pagination)
+                .with_extraction(init_extraction(&args.extraction))
+                .with_config(markdown_config(args.front_matter, args.strip_links));
+            if let Some(cache) = &page_cache {
+                generator = generator.with_page_cache(cache.clone());
+            }
+            if let Some(print_views) = &print_views {
+                generator = generator.with_print_views(print_views.clone());
+            }
+            if let Some(social_threads) = &social_threads {
+                generator = generator.with_social_threads(social_threads.clone());
+            }
+            if let Some(wikipedia) = &wikipedia {
+                generator = generator.with_wikipedia(wikipedia.clone());
+            }
+            if let Some(cookies) = &cookies {
+                generator = generator.with_cookies(cookies.clone());
+            }
+            if let Some(options) = &request_options {
+                generator = generator.with_request_options(options.clone());
+            }
+            if let Some(recorder) = &recorder {
+                generator = generator.with_recorder(recorder.clone());
+            }
+            if let Some(wait) = args.rendering.wait_strategy() {
+                generator = generator.with_wait_strategy(wait);
+            }
+            if args.render_js {
+                // Pages are rendered in the browser that prints the PDFs, if there is one
+                if let Some(pdf_generator) = &pdf_generator {
+                    generator = generator.with_browser(pdf_generator.browser().clone());
+                }
+                generator = generator.with_render_js(true);
+            }
+            if let Some(dir) = &args.sites.profile_dir {
+                let browser = match &pdf_generator {
+                    Some(pdf_generator) => pdf_generator.browser().clone(),
+                    None => init_profile_browser(dir),
+                };
+                generator = generator.with_browser(browser);
+            }
+            if let Some(scanner) = &pii_scanner {
+                generator = generator.with_pii_scanner(scanner.clone());
+            }
+            if let Some(alt_text) = init_alt_text(&args.alt_text) {
+                generator = generator.with_alt_text(alt_text);
+            }
+            if let Some(images) = args.images.markdown_images() {
+                generator = generator.with_images(images);
+            }
+            Some(generator)
+        }
+        OutputFormat::Pdf | OutputFormat::Screenshot => None,
+    };
+    // Books are made from the main content the Markdown generator extracts
+    let (md_generator, epub_generator) = match args.format {
+        OutputFormat::Epub => (None, md_generator.map(EpubGenerator::new)),
+        _ => (md_generator, None),
+    };
+    let link_downloader = init_link_downloader(&args, pdf_generator.as_ref());
+    match args.rendering.wait_strategy() {
+        Some(WaitStrategy::Selector { selector, timeout }) => info!(
+            "Waiting up to {} seconds for '{}'",
+            timeout.as_secs(),
+            selector
+        ),
+        Some(_) => info!(
+            "Waiting up to {} seconds for network idle",
+            args.rendering.wait_timeout
+        ),
+        None => info!("Wait time: {} seconds", args.wait),
+    }
+
+    let chunking = init_chunking(&args.chunking);
+    let translator = match args.format {
+        OutputFormat::Pdf | OutputFormat::Screenshot | OutputFormat::Epub => None,
+        _ => init_translator(&args.translation),
+    };
+
+    // URLs already saved in an output directory are skipped, except when the output is
+    // stored elsewhere (archive) or collected into one document (merge)
+    let mut manifest = match (&output_dir, &archive, &merge_path) {
+        (Some(dir), None, None) if !args.versioned => match OutputManifest::load(dir).await {
+            Ok(manifest) => Some(manifest),
+            Err(e) => {
+                warn!("Ignoring output manifest: {}", e);
+                Some(OutputManifest::default())
+            }
+        },
+        _ => None,
+    };
+    // Screenshots are captured by the PDF generator, in place of the PDF
+    let page_extension = screenshot.map_or("pdf", |format| format.extension());
+    let outputs = |stem: &str| {
+        let Some(dir) = &output_dir else {
+            return Vec::new();
+        };
+        let stem = dir.join(stem);
+        let mut files = Vec::new();
+        if pdf_generator.is_some() {
+            files.push(output_file(&stem, page_extension));
+        }
+        if md_generator.is_some() {
+            let md_path = output_file(&stem, "md");
+            files.push(match chunking {
+                Some(_) => chunk::chunk_path(&md_path, 1),
+                None => md_path,
+            });
+        }
+        if epub_generator.is_some() {
+            files.push(output_file(&stem, "epub"));
+        }
+        files
+    };
+
+    let mut merged_pages = Vec::new();
+    let mut used_stems = HashSet::new();
+    let mut run_stats = RunStats::new();
+    let mut partial = Vec::new();
+    for input in &inputs {
+        let stem = match input {
+            ConvertInput::Url(url) => {
+                let slot = output_slot(
+                    url,
+                    args.stems.get(url).map(String::as_str),
+                    output_dir.as_deref(),
+                    manifest.as_ref(),
+                    args.force,
+                    outputs,
+                    &mut used_stems,
+                );
+                match slot {
+                    Ok(OutputSlot::Free(stem)) => match &output_dir {
+                        Some(dir) => dir.join(stem),
+                        None => PathBuf::from(stem),
+                    },
+                    Ok(OutputSlot::Saved(files)) => {
+                        println!("✓ {}", tr(Message::AlreadySaved(url)));
+                        for file in files {
+                            println!("  {}", file.display());
+                        }
+                        continue;
+                    }
+                    // An invalid URL, or no free output name for it
+                    Err(e) => {
+                        error!("Cannot save {}: {}", url, e);
+                        eprintln!("✗ Cannot save {}: {}", url, e);
+                        run_stats.record_failure(url, Duration::ZERO, &e);
+                        failed += 1;
+                        continue;
+                    }
+                }
+            }
+            // Local files mirror their directory structure under an explicit output
+            // directory, and are otherwise written next to the source file, in place of
+            // its extension
+            ConvertInput::File(file) => match (&args.output, &output_dir) {
+                (Some(_), Some(dir)) => dir.join(&file.relative),
+                (None, Some(dir)) if args.versioned || archive.is_some() => {
+                    dir.join(&file.relative)
+                }
+                _ => file.path.clone(),
+            }
+            .with_extension(""),
+        };
+
+        // Versioned captures go into a new timestamped directory under the per-URL directory
+        let (stem, version_dir) = if args.versioned {
+            match versions::create_version_dir(&stem, chrono::Utc::now()) {
+                Ok(version_dir) => {
+                    let name = stem.file_name().unwrap_or_default().to_os_string();
+                    (version_dir.join(name), Some(version_dir))
+                }
+                Err(e) => {
+                    error!(
+                        "Failed to create capture directory in {}: {}",
+                        stem.display(),
+                        e
+                    );
+                    eprintln!(
+                        "✗ Failed to create capture directory in {}: {}",
+                        stem.display(),
+                        e
+                    );
+                    failed += 1;
+                    continue;
+                }
+            }
+        } else {
+            (stem, None)
+        };
+
+        // An explicit output file is used as-is for the primary format
+        let explicit_output = args.output.as_ref().filter(|_| output_dir.is_none());
+        let pdf_path = explicit_output
+            .cloned()
+            .unwrap_or_else(|| output_file(&stem, page_extension));
+        let md_path = match (&args.format, explicit_output) {
+            (OutputFormat::Markdown, Some(path)) => path.clone(),
+            (_, Some(path)) => path.with_extension("md"),
+            (_, None) => output_file(&stem, "md"),
+        };
+        let epub_path = match (&args.format, explicit_output) {
+            (OutputFormat::Epub, Some(path)) => path.clone(),
+            _ => output_file(&stem, "epub"),
+        };
+        let epub = epub_generator
+            .as_ref()
+            .map(|generator| (generator, epub_path.as_path()));
+
+        let md_target = match (&merge_path, &chunking) {
+            (Some(_), _) => MarkdownTarget::Merge(&mut merged_pages),
+            (None, Some(chunking)) => MarkdownTarget::Chunks(&md_path, chunking),
+            (None, None) => MarkdownTarget::File(&md_path),
+        };
+        // Resolved before the page loads, so the snapshot shows where it was served from
+        let dns = match (
+            args.dns_snapshot && !args.offline && args.replay.is_none(),
+            input,
+        ) {
+            (true, ConvertInput::Url(url)) => resolve_host(url).await,
+            _ => None,
+        };
+        let started = Instant::now();
+        let mut outcome = match input {
+            ConvertInput::Url(url) => {
+                convert_url(
+                    pdf_generator.as_ref(),
+                    md_generator.as_ref(),
+                    url,
+                    &pdf_path,
+                    screenshot,
+                    md_target,
+                    epub,
+                )
+                .await
+            }
+            ConvertInput::File(file) => {
+                if let Some(parent) = pdf_path.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                convert_file(
+                    pdf_generator.as_ref(),
+                    md_generator.as_ref(),
+                    &file.path,
+                    &pdf_path,
+                    screenshot,
+                    md_target,
+                    epub,
+                )
+                .await
+            }
+        };
+        let source = match input {
+            ConvertInput::Url(url) => url.clone(),
+            ConvertInput::File(file) => file.path.display().to_string(),
+        };
+        if let Some(hook) = &scan_hook {
+            let quarantined = outcome.screen(hook);
+            report_quarantined(&quarantined);
+            run_stats.record_quarantined(quarantined);
+        }
+        let posture = network_log.as_ref().and_then(|log| {
+            let (entries, chains) = log.take();
+            SecurityPosture::from_network(&entries, &chains)
+        });
+        if let Some(posture) = &posture {
+            for issue in &posture.issues {
+                eprintln!("⚠ {}: {}", posture.origin, issue);
+            }
+            run_stats.record_security(posture);
+        }
+        run_stats.record_outcome(&source, started.elapsed(), &outcome);
+        if !outcome.is_complete() {
+            failed += 1;
+            if outcome.is_partial() {
+                partial.push(format!("{}: {}", source, outcome.failure_summary()));
+            }
+        }
+        let disk_full = outcome.failed.iter().any(|(_, e)| stop_if_disk_full(e));
+        if outcome.converted.is_empty() {
+            if let Some(version_dir) = &version_dir {
+                let _ = std::fs::remove_dir_all(version_dir);
+            }
+            if disk_full {
+                break;
+            }
+            continue;
+        }
+        if disk_full {
+            break;
+        }
+        // Archived captures are timestamped in the database instead
+        if let (Some(tsa), None) = (&tsa, &archive_dir) {
+            if !timestamp_files(tsa, &outcome.files).await {
+                failed += 1;
+            }
+        }
+        if let Some(translator) = &translator {
+            if !save_translations(translator, &outcome.files).await {
+                failed += 1;
+            }
+        }
+        if let (Some(downloader), ConvertInput::Url(url)) = (&link_downloader, input) {
+            let dir = match &version_dir {
+                Some(version_dir) => version_dir.join("attachments"),
+                None => PathBuf::from(format!(
+                    "{}_attachments",
+                    pdf_path.with_extension("").display()
+                )),
+            };
+            let quarantined = save_linked_files(
+                downloader,
+                page_cache.as_ref(),
+                scan_hook.as_ref(),
+                url,
+                &dir,
+            )
+            .await;
+            report_quarantined(&quarantined);
+            run_stats.record_quarantined(quarantined);
+        }
+        if outcome.converted.contains(&IntegrationOutputFormat::Pdf)
+            && deliver_pdf(&delivery, &pdf_path).await.is_err()
+        {
+            failed += 1;
+        }
+
+        // Partial captures are kept, but not made the latest one or recorded as saved
+        if let Some(version_dir) = version_dir.as_ref().filter(|_| outcome.is_complete()) {
+            match versions::update_latest(version_dir) {
+                Ok(latest) => println!("✓ Latest capture: {}", latest.display()),
+                Err(e) => {
+                    error!("Failed to update latest pointer: {}", e);
+                    eprintln!("✗ Failed to update latest pointer: {}", e);
+                    failed += 1;
+                }
+            }
+        }
+
+        if let (Some(manifest), Some(dir), ConvertInput::Url(url), true) =
+            (manifest.as_mut(), &output_dir, input, outcome.is_complete())
+        {
+            let files = outputs(&stem.file_name().unwrap_or_default().to_string_lossy());
+            manifest.record(dir, url, &files);
+            if let Some(posture) = posture {
+                manifest.set_security(url, posture);
+            }
+            if let Some(snapshot) = dns {
+                manifest.set_dns(url, snapshot);
+            }
+            if let Some(point) = product_price(page_cache.as_ref(), url).await {
+                if manifest.record_price(url, point.clone()) {
+                    println!("✓ Price: {}", describe_price(&point));
+                }
+            }
+            if let Err(e) = manifest.save(dir).await {
+                warn!("Failed to update the output manifest: {}", e);
+            }
+        }
+
+        if let Some(archive) = archive.as_mut() {
+            let stored = archive_capture(
+                archive,
+                &source,
+                outcome
+                    .converted
+                    .contains(&IntegrationOutputFormat::Pdf)
+                    .then_some(pdf_path.as_path()),
+                outcome
+                    .converted
+                    .contains(&IntegrationOutputFormat::Markdown)
+                    .then_some(md_path.as_path()),
+                args.archive_pdf_dir.as_deref(),
+                tsa.as_ref(),
+            )
+            .await;
+            if stored.is_err() {
+                failed += 1;
+            }
+        }
+    }
+
+    if let Some(path) = merge_path.filter(|_| !merged_pages.is_empty()) {
+        let merged = markdown::merge_pages(&merged_pages);
+        println!("✓ {}", tr(Message::MergedPages(merged_pages.len())));
+        match &chunking {
+            Some(chunking) => {
+                let paths = chunk::write_chunks(&path, &merged, None, None, chunking).await?;
+                report_chunks(&paths);
+                if let Some(translator) = &translator {
+                    if !save_translations(translator, &paths).await {
+                        failed += 1;
+                    }
+                }
+            }
+            None => {
+                atomic::write(&path, merged).await?;
+                println!("✓ {}", tr(Message::SavedTo(&path)));
+                if let Some(translator) = &translator {
+                    if !save_translations(translator, &[path]).await {
+                        failed += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    if total > 1 || args.stats_json.is_some() || pii_scanner.is_some() || network_log.is_some() {
+        report_stats(
+            &run_stats,
+            &timeouts,
+            pii_scanner.as_ref(),
+            args.stats_json.as_deref(),
+        )
+        .await;
+    }
+
+    if let Some(recorder) = recorder.filter(|r| r.mode() == RecordingMode::Record) {
+        match recorder.save() {
+            Ok(()) => println!(
+                "✓ Recorded {} responses to: {}",
+                recorder.len(),
+                recorder.path().display()
+            ),
+            Err(e) => {
+                error!(
+                    "Failed to save recording {}: {}",
+                    recorder.path().display(),
+                    e
+                );
+                eprintln!(
+                    "✗ Failed to save recording {}: {}",
+                    recorder.path().display(),
+                    e
+                );
+                failed += 1;
+            }
+        }
+    }
+
+    report_partial(&partial);
+    Ok(ConvertRun { total, failed })
+}
+
+/// Handle the `render` subcommand
+async fn run_render(args: RenderArgs) -> Result<()> {
+    // Pair each snapshot with its output path (without extension)
+    let mut jobs = Vec::new();
+    for root in &args.paths {
+        let files = match render::local_files(root) {
+            Ok(files) => files,
+            Err(e) => {
+                error!("Failed to read {}: {}", root.display(), e);
+                eprintln!("✗ Failed to read {}: {}", root.display(), e);
+                std::process::exit(1);
+            }
+        };
+
+        for file in files {
+            let output = match &args.output_dir {
+                Some(dir) => dir.join(&file.relative),
+                None => file.path.clone(),
+            };
+            jobs.push((file.path, output));
+        }
+    }
+
+    if jobs.is_empty() {
+        eprintln!("✗ No .html or .mhtml snapshots found");
+        std::process::exit(1);
+    }
+    if !args.no_space_check {
+        let destination = args.output_dir.as_deref().unwrap_or(&args.paths[0]);
+        check_disk_space(destination, jobs.len(), &args.format);
+    }
+
+    let delivery = init_delivery(&args.device);
+    let screenshot = args.rendering.screenshot(&args.format);
+    let pdf_generator = match args.format {
+        OutputFormat::Pdf | OutputFormat::Both | OutputFormat::Screenshot => {
+            let mut generator = init_pdf_generator(None)
+                .await
+                .with_wait(Duration::from_secs(args.wait))
+                .with_config(args.rendering.pdf_config(args.device.paper()));
+            if let Some(branding) = init_branding(&args.branding) {
+                generator = generator.with_branding(branding);
+            }
+            if let Some(reader) = init_reader(&args.reader, &args.extraction, &args.device) {
+                generator = generator.with_reader(reader);
+            }
+            if let Some(deterministic) = args.rendering.deterministic() {
+                generator = generator.with_deterministic(deterministic);
+            }
+            if let Some(wait) = args.rendering.wait_strategy() {
+                generator = generator.with_wait_strategy(wait);
+            }
+            if let Some(media) = args.rendering.media {
+                generator = generator.with_media(media.into());
+            }
+            if let Some(viewport) = args.rendering.viewport {
+                generator = generator.with_viewport(viewport);
+            }
+            Some(generator)
+        }
+        OutputFormat::Markdown | OutputFormat::Epub => None,
+    };
+    let md_generator = match args.format {
+        OutputFormat::Markdown | OutputFormat::Both | OutputFormat::Epub => Some(
+            init_markdown_generator()
+                .await
+                .with_extraction(init_extraction(&args.extraction))
+                .with_config(markdown_config(args.front_matter, args.strip_links)),
+        ),
+        OutputFormat::Pdf | OutputFormat::Screenshot => None,
+    };
+    let (md_generator, epub_generator) = match args.format {
+        OutputFormat::Epub => (None, md_generator.map(EpubGenerator::new)),
+        _ => (md_generator, None),
+    };
+
+    let chunking = init_chunking(&args.chunking);
+    let mut failed = 0;
+    let mut partial = Vec::new();
+    for (file, output) in &jobs {
+        if let Some(parent) = output.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        let md_path = output.with_extension("md");
+        let epub_path = output.with_extension("epub");
+        let pdf_path = output.with_extension(screenshot.map_or("pdf", |format| format.extension()));
+        let outcome = convert_file(
+            pdf_generator.as_ref(),
+            md_generator.as_ref(),
+            file,
+            &pdf_path,
+            screenshot,
+            match &chunking {
+                Some(chunking) => MarkdownTarget::Chunks(&md_path, chunking),
+                None => MarkdownTarget::File(&md_path),
+            },
+            epub_generator
+                .as_ref()
+                .map(|generator| (generator, epub_path.as_path())),
+        )
+        .await;
+        if !outcome.is_complete() {
+            failed += 1;
+            if outcome.is_partial() {
+                partial.push(format!("{}: {}", file.display(), outcome.failure_summary()));
+            }
+        }
+        if outcome.failed.iter().any(|(_, e)| stop_if_disk_full(e)) {
+            break;
+        }
+        if outcome.converted.contains(&IntegrationOutputFormat::Pdf)
+            && deliver_pdf(&delivery, &pdf_path).await.is_err()
+        {
+            failed += 1;
+        }
+    }
+
+    report_partial(&partial);
+    if failed > 0 {
+        eprintln!("✗ {} of {} snapshots failed to render", failed, jobs.len());
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+/// Handle the `embed` subcommand
+async fn run_embed(args: EmbedArgs) -> Result<()> {
+    let mut files = Vec::new();
+    for path in &args.paths {
+        match render::collect_files(path, embedding::is_markdown_file) {
+            Ok(found) => files.extend(found),
+            Err(e) => {
+                error!("Failed to read {}: {}", path.display(), e);
+                eprintln!("✗ Failed to read {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+    if files.is_empty() {
+        eprintln!("✗ No Markdown files found");
+        std::process::exit(1);
+    }
+
+    let client = EmbeddingClient::new(&args.endpoint, &args.model)?
+        .with_api_key(args.api_key)
+        .with_batch_size(args.batch_size);
+
+    match embedding::embed_files(&client, &files, &args.output).await {
+        Ok(count) => {
+            println!("✓ Embedded {} Markdown files with {}", count, args.model);
+            println!("✓ {}", tr(Message::SavedTo(&args.output)));
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to compute embeddings: {}", e);
+            eprintln!("✗ Failed to compute embeddings: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Handle the `read-later` subcommands
+async fn run_read_later(command: ReadLaterCommands) -> Result<()> {
+    match command {
+        ReadLaterCommands::Pull(args) => run_read_later_pull(*args).await,
+        ReadLaterCommands::Push(args) => run_read_later_push(args).await,
+    }
+}
+
+/// Handle the `read-later pull` subcommand
+async fn run_read_later_pull(mut args: ReadLaterPullArgs) -> Result<()> {
+    let filter = ListFilter {
+        tag: args.tag.clone(),
+        folder: args.folder.clone(),
+        limit: args.limit,
+    };
+    let items = match &args.export {
+        Some(path) => tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))
+            .and_then(|data| readlater::parse_export(&data))
+            .map(|items| filter.apply(items)),
+        None => match args.credentials.connect(&args.service).await {
+            Ok(service) => service.list(&filter).await,
+            Err(e) => Err(e),
+        },
+    };
+    let items = match items {
+        Ok(items) => items,
+        Err(e) => {
+            error!("Failed to list saved articles: {}", e);
+            eprintln!("✗ Failed to list saved articles: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    println!("✓ Found {} saved articles", items.len());
+    if args.list {
+        for item in &items {
+            match &item.title {
+                Some(title) => println!("  {} ({})", item.url, title),
+                None => println!("  {}", item.url),
+            }
+        }
+        return Ok(());
+    }
+    if items.is_empty() && args.convert.urls.is_empty() {
+        return Ok(());
+    }
+    let output = args.convert.output.clone();
+    let tagged: Vec<(String, Vec<String>)> = items
+        .iter()
+        .map(|item| (item.url.clone(), item.tags.clone()))
+        .collect();
+    args.convert
+        .urls
+        .extend(items.into_iter().map(|item| item.url));
+    let run = convert_inputs(args.convert).await?;
+    record_tags(output.as_deref(), &tagged).await;
+    if run.failed > 0 {
+        eprintln!(
+            "✗ {}",
+            tr(Message::InputsFailed {
+                failed: run.failed,
+                total: run.total
+            })
+        );
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Record the tags URLs have in a bookmark or read-later service in the manifest of the
+/// output directory, for its index
+async fn record_tags(output: Option<&Path>, tagged: &[(String, Vec<String>)]) {
+    let Some(dir) = output.filter(|dir| dir.is_dir()) else {
+        return;
+    };
+    let Ok(mut manifest) = OutputManifest::load(dir).await else {
+        return;
+    };
+    let mut changed = false;
+    for (url, tags) in tagged {
+        changed |= manifest.tag(url, tags);
+    }
+    if changed {
+        if let Err(e) = manifest.save(dir).await {
+            warn!("Failed to record tags in the output manifest: {}", e);
+        }
+    }
+}
+
+/// Handle the `read-later push` subcommand
+async fn run_read_later_push(args: ReadLaterPushArgs) -> Result<()> {
+    let service = match args.credentials.connect(&args.service).await {
+        Ok(service) => service,
+        Err(e) => {
+            error!("Failed to connect to the read-it-later service: {}", e);
+            eprintln!("✗ Failed to connect to the read-it-later service: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let page_cache = page_cache(args.url_only);
+
+    let mut failed = 0;
+    for url in &args.urls {
+        let html = match &page_cache {
+            Some(cache) => cache.get(url).await,
+            None => None,
+        };
+        let article = Article::new(url, html).with_tags(args.tag.clone());
+        match service.save(&article).await {
+            Ok(()) if article.html.is_some() => {
+                println!("✓ Saved {} with its captured content", url)
+            }
+            Ok(()) => println!("✓ Saved {}", url),
+            Err(e) => {
+                error!("Failed to save {}: {}", url, e);
+                eprintln!("✗ Failed to save {}: {}", url, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("✗ {} of {} URLs failed to save", failed, args.urls.len());
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handle the `bookmarks` subcommand
+async fn run_bookmarks(mut args: BookmarksArgs) -> Result<()> {
+    // Local copies are looked up in the output directory's manifest
+    let write_back_dir = match &args.convert.output {
+        _ if !args.write_back => None,
+        Some(dir)
+            if args.convert.archive.is_none() && !args.convert.versioned && !args.convert.merge =>
+        {
+            Some(dir.clone())
+        }
+        _ => {
+            eprintln!(
+                "✗ --write-back needs an output directory (-o DIR) and cannot be combined with --archive, --versioned, or --merge"
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let service = match args.connect() {
+        Ok(service) => service,
+        Err(e) => {
+            error!("Failed to connect to the bookmark service: {}", e);
+            eprintln!("✗ Failed to connect to the bookmark service: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let mut bookmarks = match service.list(args.tag.as_deref()).await {
+        Ok(bookmarks) => bookmarks,
+        Err(e) => {
+            error!("Failed to list bookmarks: {}", e);
+            eprintln!("✗ Failed to list bookmarks: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Some(limit) = args.limit {
+        bookmarks.truncate(limit);
+    }
+
+    println!("✓ Found {} bookmarks", bookmarks.len());
+    if args.list {
+        for bookmark in &bookmarks {
+            match &bookmark.title {
+                Some(title) => println!("  {} ({})", bookmark.url, title),
+                None => println!("  {}", bookmark.url),
+            }
+        }
+        return Ok(());
+    }
+    if bookmarks.is_empty() && args.convert.urls.is_empty() {
+        return Ok(());
+    }
+
+    if let Some(dir) = &write_back_dir {
+        tokio::fs::create_dir_all(dir).await?;
+    }
+    args.convert
+        .urls
+        .extend(bookmarks.iter().map(|bookmark| bookmark.url.clone()));
+    let output = args.convert.output.clone();
+    let run = convert_inputs(args.convert).await?;
+    let tagged: Vec<(String, Vec<String>)> = bookmarks
+        .iter()
+        .map(|bookmark| (bookmark.url.clone(), bookmark.tags.clone()))
+        .collect();
+    record_tags(output.as_deref(), &tagged).await;
+
+    let mut failed_notes = 0;
+    if let Some(dir) = &write_back_dir {
+        let manifest = OutputManifest::load(dir).await?;
+        let mut noted = 0;
+        for bookmark in &bookmarks {
+            let Some(files) = manifest.saved_files(dir, &bookmark.url) else {
+                continue;
+            };
+            match service.note_archive(bookmark, &files).await {
+                Ok(true) => noted += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("Failed to update bookmark {}: {}", bookmark.url, e);
+                    eprintln!("✗ Failed to update bookmark {}: {}", bookmark.url, e);
+                    failed_notes += 1;
+                }
+            }
+        }
+        println!("✓ Noted the local copies on {} bookmarks", noted);
+    }
+
+    if run.failed > 0 {
+        eprintln!(
+            "✗ {}",
+            tr(Message::InputsFailed {
+                failed: run.failed,
+                total: run.total
+            })
+        );
+    }
+    if failed_notes > 0 {
+        eprintln!("✗ {} bookmarks could not be updated", failed_notes);
+    }
+    if run.failed > 0 || failed_notes > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handle the `newsletter` subcommand
+async fn run_newsletter(mut args: NewsletterArgs) -> Result<()> {
+    let newsletters = std::mem::take(&mut args.convert.urls);
+    if newsletters.is_empty() {
+        eprintln!("✗ No newsletter URLs given");
+        std::process::exit(1);
+    }
+    let client = NewsletterClient::new()?;
+
+    let mut failed = 0;
+    for url in &newsletters {
+        let posts = match client.resolve(url, args.platform.map(Into::into)).await {
+            Ok(newsletter) => client
+                .posts(&newsletter)
+                .await
+                .map(|posts| (newsletter.platform, posts)),
+            Err(e) => Err(e),
+        };
+        let (platform, mut posts) = match posts {
+            Ok(posts) => posts,
+            Err(e) => {
+                error!("Failed to list the posts of {}: {}", url, e);
+                eprintln!("✗ Failed to list the posts of {}: {}", url, e);
+                failed += 1;
+                continue;
+            }
+        };
+        if let Some(since) = args.since {
+            posts.retain(|post| {
+                post.published
+                    .is_some_and(|date| date.date_naive() >= since)
+            });
+        }
+        if let Some(limit) = args.limit {
+            posts.truncate(limit);
+        }
+
+        println!(
+            "✓ Found {} posts in {} ({})",
+            posts.len(),
+            url,
+            platform.name()
+        );
+        for post in posts {
+            if args.list {
+                let date = post
+                    .published
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_else(|| "----------".to_string());
+                match &post.title {
+                    Some(title) => println!("  {} {} ({})", date, post.url, title),
+                    None => println!("  {} {}", date, post.url),
+                }
+                continue;
+            }
+            args.convert
+                .stems
+                .insert(post.url.clone(), post.file_stem());
+            args.convert.urls.push(post.url);
+        }
+    }
+
+    if !args.convert.urls.is_empty() {
+        let dir = args
+            .convert
+            .output
+            .take()
+            .unwrap_or_else(|| PathBuf::from("."));
+        tokio::fs::create_dir_all(&dir).await?;
+        args.convert.output = Some(dir);
+        let run = convert_inputs(args.convert).await?;
+        if run.failed > 0 {
+            eprintln!("✗ {} of {} posts failed to convert", run.failed, run.total);
+        }
+        failed += run.failed;
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handle the `thread` subcommand
+async fn run_thread(mut args: ThreadArgs) -> Result<()> {
+    let urls = std::mem::take(&mut args.convert.urls);
+    if urls.is_empty() {
+        eprintln!("✗ No Hacker News or Reddit URLs given");
+        std::process::exit(1);
+    }
+    let dir = args
+        .convert
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("."));
+    tokio::fs::create_dir_all(&dir).await?;
+    let client = ThreadClient::new()?;
+
+    let mut failed = 0;
+    let mut articles = Vec::new();
+    for url in &urls {
+        let Some(source) = ThreadSource::parse(url) else {
+            eprintln!("✗ Not a Hacker News item or Reddit post URL: {}", url);
+            failed += 1;
+            continue;
+        };
+        let thread = match client.fetch(&source).await {
+            Ok(thread) => thread,
+            Err(e) => {
+                error!("Failed to fetch thread {}: {}", url, e);
+                eprintln!("✗ Failed to fetch thread {}: {}", url, e);
+                failed += 1;
+                continue;
+            }
+        };
+        let path = dir.join(format!("{}.md", source.file_stem()));
+        atomic::write(&path, thread.to_markdown()).await?;
+        println!(
+            "✓ Thread saved with {} comments: {}",
+            thread.comments.len(),
+            path.display()
+        );
+        if let Some(link) = thread.link.filter(|_| !args.no_article) {
+            articles.push(link);
+        }
+    }
+
+    if !articles.is_empty() {
+        args.convert.urls = articles;
+        args.convert.output = Some(dir);
+        failed += convert_inputs(args.convert).await?.failed;
+    }
+    if failed > 0 {
+        eprintln!("✗ {} threads or articles failed to save", failed);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handle the `crawl` subcommand
+async fn run_crawl(args: CrawlArgs) -> Result<()> {
+    let mut crawler = DocsCrawler::new()?.with_max_pages(args.max_pages);
+    if let Some(preset) = args.preset {
+        crawler = crawler.with_preset(preset.into());
+    }
+    if let Some(blocklist) = init_blocklist(&args.blocklist).await {
+        crawler = crawler.with_blocklist(blocklist);
+    }
+    match args.list {
+        true => list_docs(&crawler, &args.url).await,
+        false => save_docs(&crawler, &args.url, args.output, &args.format).await,
+    }
+}
+
+/// Handle the `docs` subcommand
+async fn run_docs(args: DocsArgs) -> Result<()> {
+    let url = match crawl::docs_rs_url(&args.spec) {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+    let crawler = DocsCrawler::new()?
+        .with_preset(DocsPreset::Rustdoc)
+        .with_max_pages(args.max_pages);
+    match args.list {
+        true => list_docs(&crawler, &url).await,
+        false => save_docs(&crawler, &url, args.output, &args.format).await,
+    }
+}
+
+/// Handle the `login` subcommand
+async fn run_login(args: LoginArgs) -> Result<()> {
+    let store = match SessionStore::open_default() {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.list {
+        for domain in store.domains()? {
+            println!("{}", domain);
+        }
+        return Ok(());
+    }
+
+    let target = args.target.unwrap_or_default();
+    let (domain, url) = match session::login_target(&target) {
+        Ok(target) => target,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.logout {
+        match store.delete(&domain)? {
+            true => println!("✓ Deleted the saved session of {}", domain),
+            false => println!("No saved session of {}", domain),
+        }
+        return Ok(());
+    }
+
+    let window = match LoginWindow::open(&url, args.profile_dir.as_deref(), browser_proxy()) {
+        Ok(window) => window,
+        Err(e) => {
+            error!("Failed to open {}: {}", url, e);
+            eprintln!("✗ Failed to open {}: {}", url, e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "Log in to {} in the browser window, including any two-factor step, then press \
+         Enter here or close the window to save the session",
+        domain
+    );
+
+    // A plain thread, since a blocking task would keep the runtime waiting on stdin
+    // after the window is closed
+    let (entered, enter) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let mut line = String::new();
+        let _ = std::io::stdin().read_line(&mut line);
+        let _ = entered.send(());
+    });
+
+    // Keep the latest cookies while waiting, since a closed window cannot be read
+    let mut cookies = Vec::new();
+    while window.is_open() {
+        if let Ok(latest) = window.cookies(&domain) {
+            cookies = latest;
+        }
+        if enter.try_recv().is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+    window.close().await;
+
+    if cookies.is_empty() {
+        eprintln!(
+            "✗ {} set no cookies, so there is no session to save",
+            domain
+        );
+        std::process::exit(1);
+    }
+    let path = store.save(&domain, &cookies)?;
+    println!(
+        "✓ Saved the session of {} ({} cookies) to: {}",
+        domain,
+        cookies.len(),
+        path.display()
+    );
+    if let Some(dir) = &args.profile_dir {
+        println!("✓ Kept the session in the profile: {}", dir.display());
+    }
+    Ok(())
+}
+
+/// Print the pages of a documentation site in order, exiting the process on failure
+async fn list_docs(crawler: &DocsCrawler, url: &str) -> Result<()> {
+    let (preset, title, urls) = match crawler.list(url).await {
+        Ok(listing) => listing,
+        Err(e) => {
+            error!("Failed to list the pages of {}: {}", url, e);
+            eprintln!("✗ Failed to list the pages of {}: {}", url, e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "✓ Found {} pages in {} ({})",
+        urls.len(),
+        title,
+        preset.name()
+    );
+    for url in &urls {
+        println!("  {}", url);
+    }
+    Ok(())
+}
+
+/// Crawl a documentation site and save it as one document, exiting the process on failure
+async fn save_docs(
+    crawler: &DocsCrawler,
+    url: &str,
+    output: Option<PathBuf>,
+    format: &OutputFormat,
+) -> Result<()> {
+    if matches!(format, OutputFormat::Screenshot | OutputFormat::Epub) {
+        eprintln!(
+            "✗ Crawled sites cannot be saved as a screenshot or EPUB; use --format pdf or markdown"
+        );
+        std::process::exit(1);
+    }
+    let site = match crawler.crawl(url).await {
+        Ok(site) => site,
+        Err(e) => {
+            error!("Failed to crawl {}: {}", url, e);
+            eprintln!("✗ Failed to crawl {}: {}", url, e);
+            std::process::exit(1);
+        }
+    };
+    println!(
+        "✓ Fetched {} pages of {} ({})",
+        site.pages.len(),
+        site.title,
+        site.preset.name()
+    );
+    for failed in &site.failed {
+        eprintln!("✗ Failed to fetch {}", failed);
+    }
+    for blocked in &site.blocked {
+        println!(
+            "⚠ Skipped {} ({} on {})",
+            blocked.url, blocked.domain, blocked.list
+        );
+    }
+    if site.pages.is_empty() {
+        std::process::exit(1);
+    }
+
+    let base = output.unwrap_or_else(|| PathBuf::from(site.file_stem()));
+    if let Some(parent) = base
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let mut failed = !site.failed.is_empty();
+    if matches!(format, OutputFormat::Markdown | OutputFormat::Both) {
+        let md_path = base.with_extension("md");
+        let merged = markdown::merge_pages(&site.markdown_pages());
+        atomic::write(&md_path, &merged).await?;
+        println!("✓ {}", tr(Message::SavedTo(&md_path)));
+    }
+    if matches!(format, OutputFormat::Pdf | OutputFormat::Both) {
+        let pdf_path = base.with_extension("pdf");
+        let generator = init_pdf_generator(None).await;
+        let result = match site.to_html() {
+            Ok(html) => generator.html_to_pdf(&html, Some(&pdf_path)).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(pdf_data) => {
+                println!("✓ {}", tr(Message::GeneratedPdf(pdf_data.len())));
+                println!("✓ {}", tr(Message::SavedTo(&pdf_path)));
+            }
+            Err(e) => {
+                error!("Failed to generate PDF for {}: {}", url, e);
+                eprintln!(
+                    "✗ {}",
+                    tr(Message::PdfFailed {
+                        source: &url,
+                        error: &e
+                    })
+                );
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Redact the Markdown and text files under the given paths, and optionally render
+/// their snapshots to redacted PDFs
+async fn run_redact(args: RedactArgs) -> Result<()> {
+    let redactor = match args.redactor() {
+        Ok(redactor) => redactor,
+        Err(e) => {
+            error!("Invalid redaction options: {}", e);
+            eprintln!("✗ Invalid redaction options: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // Pair each file with its relative path, to mirror it into the output directory
+    let mut text_files = Vec::new();
+    let mut snapshots = Vec::new();
+    let mut pdfs = 0;
+    for root in &args.paths {
+        let base = if root.is_file() {
+            root.parent().unwrap_or(Path::new(""))
+        } else {
+            root.as_path()
+        };
+        let files = match render::collect_files(root, |path| {
+            redact::is_text_file(path)
+                || render::is_snapshot_file(path)
+                || redact::is_pdf_file(path)
+        }) {
+            Ok(files) => files,
+            Err(e) => {
+                error!("Failed to read {}: {}", root.display(), e);
+                eprintln!("✗ Failed to read {}: {}", root.display(), e);
+                std::process::exit(1);
+            }
+        };
+        for file in files {
+            let relative = file
+                .strip_prefix(base)
+                .ok()
+                .filter(|relative| !relative.as_os_str().is_empty())
+                .map(Path::to_path_buf)
+                .or_else(|| file.file_name().map(PathBuf::from))
+                .unwrap_or_else(|| file.clone());
+            let output = match &args.output_dir {
+                Some(dir) => dir.join(&relative),
+                None => file.clone(),
+            };
+            if redact::is_text_file(&file) {
+                text_files.push((file, output));
+            } else if render::is_snapshot_file(&file) {
+                snapshots.push((file, output));
+            } else {
+                pdfs += 1;
+            }
+        }
+    }
+    if text_files.is_empty() && (snapshots.is_empty() || !args.pdf) {
+        eprintln!("✗ No Markdown or text files found");
+        std::process::exit(1);
+    }
+
+    let mut failed = 0;
+    let mut redacted_total = 0;
+    for (file, output) in &text_files {
+        let result = async {
+            let text = tokio::fs::read_to_string(file).await?;
+            let (redacted, count) = redactor.redact_text(&text);
+            if count > 0 || output != file {
+                if let Some(parent) = output.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                atomic::write(output, &redacted).await?;
+            }
+            Ok::<_, anyhow::Error>(count)
+        }
+        .await;
+        match result {
+            Ok(0) => info!("Nothing to redact in {}", file.display()),
+            Ok(count) => {
+                redacted_total += count;
+                println!("✓ Redacted {} match(es) in {}", count, output.display());
+            }
+            Err(e) => {
+                failed += 1;
+                error!("Failed to redact {}: {}", file.display(), e);
+                eprintln!("✗ Failed to redact {}: {}", file.display(), e);
+            }
+        }
+    }
+
+    if args.pdf && !snapshots.is_empty() {
+        let generator = init_pdf_generator(None)
+            .await
+            .with_wait(Duration::from_secs(args.wait))
+            .with_paper(args.paper.clone().map(Into::into).unwrap_or_default())
+            .with_redactor(redactor.clone());
+        for (snapshot, output) in &snapshots {
+            let pdf_path = output.with_extension("pdf");
+            if let Some(parent) = pdf_path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            if render_to_pdf(&generator, snapshot, &pdf_path)
+                .await
+                .is_err()
+            {
+                failed += 1;
+            }
+        }
+    } else if pdfs > 0 {
+        println!(
+            "{} PDF(s) left unredacted; pass --pdf to render their .html/.mhtml snapshots \
+             again with the matches blacked out",
+            pdfs
+        );
+    }
+
+    println!(
+        "✓ Redacted {} match(es) in {} text file(s)",
+        redacted_total,
+        text_files.len()
+    );
+    if failed > 0 {
+        eprintln!("✗ {} file(s) failed to redact", failed);
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handle the `index` subcommand
+async fn run_index(args: IndexArgs) -> Result<()> {
+    if !args.dir.join(manifest::MANIFEST_FILE).is_file() {
+        eprintln!(
+            "✗ {} has no record of saved pages; convert URLs into it with -o first",
+            args.dir.display()
+        );
+        std::process::exit(1);
+    }
+    match index::write_index(&args.dir).await {
+        Ok(count) => {
+            println!(
+                "✓ Indexed {} page(s) in {} and {}",
+                count,
+                args.dir.join(index::INDEX_MARKDOWN).display(),
+                args.dir.join(index::INDEX_HTML).display()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to write the index of {}: {}", args.dir.display(), e);
+            eprintln!(
+                "✗ Failed to write the index of {}: {}",
+                args.dir.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The product price on the cached snapshot of a page, if it is a product page
+async fn product_price(page_cache: Option<&PageCache>, url: &str) -> Option<PricePoint> {
+    let html = page_cache?.get(url).await?;
+    products::extract_price(&html, chrono::Utc::now())
+}
+
+/// A price with its availability, e.g. `19.99 USD (InStock)`
+fn describe_price(point: &PricePoint) -> String {
+    match &point.availability {
+        Some(availability) => format!("{} ({})", point.display_price(), availability),
+        None => point.display_price(),
+    }
+}
+
+/// Handle the `price-history` subcommand
+async fn run_price_history(args: PriceHistoryArgs) -> Result<()> {
+    if !args.dir.join(manifest::MANIFEST_FILE).is_file() {
+        eprintln!(
+            "✗ {} has no record of saved pages; convert URLs into it with -o first",
+            args.dir.display()
+        );
+        std::process::exit(1);
+    }
+    let manifest = match OutputManifest::load(&args.dir).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+    let entries: Vec<_> = manifest
+        .entries
+        .iter()
+        .filter(|entry| !entry.prices.is_empty())
+        .filter(|entry| args.url.as_ref().is_none_or(|url| &entry.url == url))
+        .collect();
+    if args.json {
+        let histories: Vec<_> = entries
+            .iter()
+            .map(|entry| serde_json::json!({ "url": entry.url, "prices": entry.prices }))
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&histories)?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        match &args.url {
+            Some(url) => eprintln!("✗ No prices recorded for {}", url),
+            None => eprintln!("✗ No prices recorded in {}", args.dir.display()),
+        }
+        std::process::exit(1);
+    }
+    for entry in entries {
+        match entry
+            .prices
+            .iter()
+            .rev()
+            .find_map(|point| point.name.as_deref())
+        {
+            Some(name) => println!("{} ({})", name, entry.url),
+            None => println!("{}", entry.url),
+        }
+        let mut previous: Option<&PricePoint> = None;
+        for point in &entry.prices {
+            let change = previous
+                .filter(|previous| previous.currency == point.currency)
+                .map(|previous| point.price - previous.price)
+                .filter(|change| *change != 0.0)
+                .map(|change| format!("  {:+.2}", change))
+                .unwrap_or_default();
+            println!(
+                "  {}  {}{}",
+                point.checked_at.format("%Y-%m-%d %H:%M"),
+                describe_price(point),
+                change
+            );
+            previous = Some(point);
+        }
+    }
+    Ok(())
+}
+
+/// Handle the `changelog` subcommand
+///
+/// The first check saves the whole page and remembers its versions; later checks save
+/// and send a note only when versions appeared that were not seen before.
+async fn run_changelog(args: ChangelogArgs) -> Result<()> {
+    let notifier = match args.notifier() {
+        Ok(notifier) => notifier,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+    let stem = match filename_from_url(&args.url) {
+        Ok(stem) => stem,
+        Err(e) => {
+            eprintln!("✗ Invalid URL {}: {}", args.url, e);
+            std::process::exit(1);
+        }
+    };
+    tokio::fs::create_dir_all(&args.output_dir).await?;
+    let dir = args.output_dir.as_path();
+    let mut manifest = match OutputManifest::load(dir).await {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            eprintln!("✗ {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let generator = init_markdown_generator()
+        .await
+        .with_render_js(args.render_js);
+    let page = match generator.url_to_page(&args.url).await {
+        Ok(page) => page,
+        Err(e) => {
+            error!("Failed to fetch {}: {}", args.url, e);
+            eprintln!("✗ Failed to fetch {}: {}", args.url, e);
+            std::process::exit(1);
+        }
+    };
+    let releases = changelog::releases(&page.content);
+    if releases.is_empty() {
+        eprintln!("✗ No version headings found on {}", args.url);
+        std::process::exit(1);
+    }
+    let versions: Vec<String> = releases
+        .iter()
+        .map(|release| release.version.clone())
+        .collect();
+
+    let known = manifest.versions(&args.url).to_vec();
+    if known.is_empty() {
+        let path = dir.join(format!("{}.md", stem));
+        atomic::write(&path, page.to_document()).await?;
+        manifest.record(dir, &args.url, std::slice::from_ref(&path));
+        manifest.add_versions(&args.url, &versions);
+        manifest.save(dir).await?;
+        println!(
+            "✓ Saved {} with {} release(s) to {}; later checks report new ones",
+            args.url,
+            versions.len(),
+            path.display()
+        );
+        return Ok(());
+    }
+
+    let new = changelog::new_releases(releases, &known);
+    if new.is_empty() {
+        println!("No new releases on {}", args.url);
+        return Ok(());
+    }
+    let note = ReleaseNote {
+        title: page.title.clone(),
+        url: args.url.clone(),
+        releases: new,
+    };
+    let version: String = note.releases[0]
+        .version
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || matches!(c, '.' | '-') {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+    let path = dir.join(format!("{}_{}.md", stem, version));
+    atomic::write(&path, note.to_markdown()).await?;
+    let mut files = manifest.saved_files(dir, &args.url).unwrap_or_default();
+    files.push(path.clone());
+    manifest.record(dir, &args.url, &files);
+    manifest.add_versions(&args.url, &versions);
+    manifest.save(dir).await?;
+    println!("✓ {}", note.summary());
+    println!("✓ {}", tr(Message::SavedTo(&path)));
+
+    if let Err(e) = notifier.notify(&note).await {
+        error!("Failed to send the note of {}: {}", args.url, e);
+        eprintln!("✗ Failed to send the note of {}: {}", args.url, e);
+        std::process::exit(1);
+    }
+    if !notifier.is_empty() {
+        println!("✓ Sent the note of {}", note.versions());
+    }
+    Ok(())
+}
+
+/// Handle the `diff` subcommand
+async fn run_diff(args: DiffArgs) -> Result<()> {
+    let ((old_label, old), (new_label, new)) = match &args.new {
+        Some(new) => {
+            let old = PathBuf::from(&args.old);
+            let read = |path: &Path| match std::fs::read_to_string(path) {
+                Ok(text) => (path.display().to_string(), text),
+                Err(e) => {
+                    eprintln!("✗ Failed to read {}: {}", path.display(), e);
+                    std::process::exit(1);
+                }
+            };
+            (read(&old), read(new))
+        }
+        None => match versioned_captures(&args) {
+            Ok(captures) => captures,
+            Err(e) => {
+                eprintln!("✗ {}", e);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let title = new
+        .lines()
+        .next()
+        .and_then(|line| line.strip_prefix("# "))
+        .unwrap_or_default()
+        .to_string();
+    let diff = CaptureDiff::new(&old, &new)
+        .with_title(title)
+        .with_labels(old_label, new_label);
+    if !args.render {
+        match diff.is_empty() {
+            true => println!("No changes"),
+            false => print!("{}", diff.to_text()),
+        }
+        return Ok(());
+    }
+
+    let html = diff.to_html();
+    let is_pdf = args
+        .output
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("pdf"));
+    let written = match is_pdf {
+        true => init_pdf_generator(None)
+            .await
+            .html_to_pdf(&html, Some(&args.output))
+            .await
+            .map(|_| ()),
+        false => atomic::write(&args.output, &html).await,
+    };
+    if let Err(e) = written {
+        error!("Failed to write {}: {}", args.output.display(), e);
+        eprintln!("✗ Failed to write {}: {}", args.output.display(), e);
+        std::process::exit(1);
+    }
+    let (added, removed) = diff.counts();
+    println!(
+        "✓ {} line(s) added, {} removed: {}",
+        added,
+        removed,
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// The labels and Markdown of the two `--versioned` captures of a URL picked by the
+/// dates of `diff`
+fn versioned_captures(args: &DiffArgs) -> Result<((String, String), (String, String))> {
+    let stem = filename_from_url(&args.old)
+        .map_err(|e| anyhow::anyhow!("{} is neither a file nor a valid URL: {}", args.old, e))?;
+    let file_name = format!("{}.md", stem);
+    let captures: Vec<_> = versions::list_captures(&args.dir.join(&stem))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|(_, dir)| dir.join(&file_name).is_file())
+        .collect();
+    if captures.is_empty() {
+        return Err(anyhow::anyhow!(
+            "No Markdown captures of {} in {}; capture it with convert -f markdown --versioned -o {}",
+            args.old,
+            args.dir.display(),
+            args.dir.display()
+        ));
+    }
+    let on_or_before = |date: chrono::NaiveDate| {
+        captures
+            .iter()
+            .rposition(|(time, _)| time.date_naive() <= date)
+            .ok_or_else(|| anyhow::anyhow!("No capture of {} on or before {}", args.old, date))
+    };
+    let to = match args.to {
+        Some(date) => on_or_before(date)?,
+        None => captures.len() - 1,
+    };
+    let from = match args.from {
+        Some(date) => on_or_before(date)?,
+        None => to
+            .checked_sub(1)
+            .ok_or_else(|| anyhow::anyhow!("No earlier capture of {} to compare with", args.old))?,
+    };
+    let read = |index: usize| -> Result<(String, String)> {
+        let (time, dir) = &captures[index];
+        let text = std::fs::read_to_string(dir.join(&file_name))?;
+        Ok((time.format("%Y-%m-%d %H:%M UTC").to_string(), text))
+    };
+    Ok((read(from)?, read(to)?))
+}
+
+/// Handle the `batch` subcommand
+async fn run_batch(args: BatchArgs) -> Result<()> {
+    if matches!(args.format, OutputFormat::Screenshot | OutputFormat::Epub) {
+        eprintln!("✗ batch saves PDF and/or Markdown; use convert for screenshots and EPUB");
+        std::process::exit(1);
+    }
+    let list = match args.input.as_os_str() == "-" {
+        true => tokio::task::spawn_blocking(|| std::io::read_to_string(std::io::stdin())).await?,
+        false => tokio::fs::read_to_string(&args.input).await,
+    };
+    let urls = match list {
+        Ok(list) => batch::parse_url_list(&list),
+        Err(e) => {
+            eprintln!(
+                "✗ Failed to read the URL list {}: {}",
+                args.input.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    };
+    if urls.is_empty() {
+        eprintln!("✗ No URLs listed in {}", args.input.display());
+        std::process::exit(1);
+    }
+
+    let mut converter = BatchConverter::new(args.concurrency);
+    let mut browser = None;
+    if matches!(args.format, OutputFormat::Pdf | OutputFormat::Both) {
+        let generator = init_pdf_generator(None)
+            .await
+            .with_wait(Duration::from_secs(args.wait));
+        browser = Some(generator.browser().clone());
+        converter = converter.with_pdf_generator(generator);
+    }
+    if matches!(args.format, OutputFormat::Markdown | OutputFormat::Both) {
+        let mut generator = init_markdown_generator().await;
+        if args.render_js {
+            // Pages are rendered in the browser that prints the PDFs, if there is one
+            if let Some(browser) = browser {
+                generator = generator.with_browser(browser);
+            }
+            generator = generator.with_render_js(true);
+        }
+        converter = converter.with_markdown_generator(generator);
+    }
+
+    println!(
+        "Converting {} URL(s) into {}, {} at a time",
+        urls.len(),
+        args.output_dir.display(),
+        args.concurrency.max(1)
+    );
+    let report = converter.convert(&urls, &args.output_dir).await?;
+    for entry in report.entries.iter().filter(|entry| !entry.is_success()) {
+        eprintln!("✗ {}: {}", entry.url, entry.errors.join("; "));
+    }
+    println!(
+        "✓ Converted {} of {} URL(s) ({} failed)",
+        report.succeeded,
+        report.entries.len(),
+        report.failed
+    );
+
+    // Converted URLs are recorded, so that the output directory can be indexed
+    match OutputManifest::load(&args.output_dir).await {
+        Ok(mut manifest) => {
+            for entry in report.entries.iter().filter(|entry| entry.is_success()) {
+                manifest.record(&args.output_dir, &entry.url, &entry.files);
+            }
+            if let Err(e) = manifest.save(&args.output_dir).await {
+                warn!("Failed to update the output manifest: {}", e);
+            }
+        }
+        Err(e) => warn!("Ignoring output manifest: {}", e),
+    }
+
+    let report_path = args
+        .report
+        .unwrap_or_else(|| args.output_dir.join(batch::REPORT_FILE));
+    match report.write_json(&report_path).await {
+        Ok(()) => println!("✓ Saved the batch report to {}", report_path.display()),
+        Err(e) => {
+            error!("Failed to save the batch report: {}", e);
+            eprintln!(
+                "✗ Failed to save the batch report to {}: {}",
+                report_path.display(),
+                e
+            );
+        }
+    }
+    if report.failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// Handle the `archive` subcommand
+async fn run_archive(args: ArchiveArgs) -> Result<()> {
+    let command = match (args.command, &args.url, &args.warc) {
+        (Some(command), _, _) => command,
+        (None, Some(url), Some(warc)) => return run_archive_warc(url, warc, args.wait).await,
+        (None, _, _) => {
+            eprintln!("✗ Give an archive subcommand, or a URL to record with --warc");
+            std::process::exit(1);
+        }
+    };
+    let database = match (&command, &args.database) {
+        (ArchiveCommands::Publish { dir, output_dir }, _) => {
+            return run_archive_publish(dir, output_dir).await;
+        }
+        (_, Some(database)) => database,
+        (_, None) => {
+            eprintln!("✗ No archive database given; pass --database or set WEBPAGE_SAVE_ARCHIVE");
+            std::process::exit(1);
+        }
+    };
+    let mut archive = init_archive(database);
+    match command {
+        ArchiveCommands::List { limit } => {
+            let entries = archive.list(limit)?;
+            if entries.is_empty() {
+                println!("No captures in {}", database.display());
+            }
+            for entry in &entries {
+                print_archive_entry(entry);
+            }
+        }
+        ArchiveCommands::Query { query, limit } => {
+            let hits = match archive.search(&query, limit) {
+                Ok(hits) => hits,
+                Err(e) => {
+                    error!("Archive query failed: {}", e);
+                    eprintln!("✗ Archive query failed: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            if hits.is_empty() {
+                println!("No captures match '{}'", query);
+            }
+            for hit in &hits {
+                print_archive_entry(&hit.entry);
+                println!("      {}", hit.snippet.replace('\n', " "));
+            }
+        }
+        ArchiveCommands::Show { id, pdf } => {
+            let Some(capture) = archive.get(id)? else {
+                eprintln!("✗ No capture with ID {} in {}", id, database.display());
+                std::process::exit(1);
+            };
+            match (pdf, capture.pdf, capture.markdown) {
+                (Some(path), Some(blob), _) => {
+                    let data = match blob {
+                        PdfBlob::Inline(data) => data,
+                        PdfBlob::Path(stored) => std::fs::read(stored)?,
+                    };
+                    atomic::write_blocking(&path, &data)?;
+                    println!("✓ {}", tr(Message::SavedTo(&path)));
+                }
+                (None, _, Some(markdown)) => println!("{}", markdown),
+                (Some(_), None, _) => {
+                    eprintln!("✗ Capture {} has no PDF", id);
+                    std::process::exit(1);
+                }
+                (None, _, None) => {
+                    eprintln!(
+                        "✗ Capture {} has no Markdown (use --pdf to extract its PDF)",
+                        id
+                    );
+                    std::process::exit(1);
+                }
+            }
+        }
+        ArchiveCommands::Export { bundle: path } => match bundle::export_bundle(&archive, &path) {
+            Ok(count) => {
+                println!("✓ Exported {} captures", count);
+                println!("✓ {}", tr(Message::SavedTo(&path)));
+            }
+            Err(e) => {
+                error!("Failed to export archive: {}", e);
+                eprintln!("✗ Failed to export archive: {}", e);
+                std::process::exit(1);
+            }
+        },
+        ArchiveCommands::Import {
+            bundle: path,
+            pdf_dir,
+        } => match bundle::import_bundle(&mut archive, &path, pdf_dir.as_deref()) {
+            Ok(summary) => {
+                println!(
+                    "✓ Imported {} captures into {}",
+                    summary.imported,
+                    database.display()
+                );
+                if summary.skipped > 0 {
+                    println!(
+                        "  Skipped {} captures already in the archive",
+                        summary.skipped
+                    );
+                }
+            }
+            Err(e) => {
+                error!("Failed to import {}: {}", path.display(), e);
+                eprintln!("✗ Failed to import {}: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        },
+        // Needs no database; handled above
+        ArchiveCommands::Publish { .. } => {}
+    }
+    Ok(())
+}
+
+/// Handle the `archive publish` subcommand
+/// Handle `archive <URL> --warc <PATH>`: load the URL in Chrome, recording every HTTP
+/// exchange into a WARC file
+async fn run_archive_warc(url: &str, path: &Path, wait: u64) -> Result<()> {
+    let recorder = HttpRecorder::record(path);
+    let generator = init_pdf_generator(None)
+        .await
+        .with_wait(Duration::from_secs(wait))
+        .with_recorder(recorder.clone());
+    if let Err(e) = generator.load_url(url).await {
+        error!("Failed to load {}: {}", url, e);
+        eprintln!("✗ Failed to load {}: {}", url, e);
+        std::process::exit(1);
+    }
+    match warc::write_warc(path, &recorder.responses()).await {
+        Ok(count) => {
+            println!(
+                "✓ Recorded {} HTTP exchange(s) of {} to {}",
+                count,
+                url,
+                path.display()
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to write {}: {}", path.display(), e);
+            eprintln!("✗ Failed to write {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Files of an evidence bundle, in the order they are listed in its report
+const EVIDENCE_FILES: &[&str] = &["page.pdf", "page.html", "capture.warc.gz", "network.json"];
+
+async fn run_evidence(args: EvidenceArgs) -> Result<()> {
+    let host = match url::Url::parse(&args.url) {
+        Ok(url) if matches!(url.scheme(), "http" | "https") && url.host_str().is_some() => url,
+        _ => {
+            eprintln!("✗ Not an http(s) URL: {}", args.url);
+            std::process::exit(1);
+        }
+    };
+    let started_at = chrono::Utc::now();
+    let dir = args.output.clone().unwrap_or_else(|| {
+        PathBuf::from(format!(
+            "evidence-{}-{}",
+            host.host_str().unwrap_or_default(),
+            started_at.format("%Y%m%dT%H%M%SZ")
+        ))
+    });
+    std::fs::create_dir_all(&dir)?;
+    let tsa = args.tsa_url.as_deref().map(init_timestamp_authority);
+    let mut report = CaptureReport::new(&args.url, started_at);
+
+    let server = args.ntp_server.clone();
+    let ntp =
+        tokio::task::spawn_blocking(move || evidence::check_ntp(&server, Duration::from_secs(5)))
+            .await?;
+    match ntp {
+        Ok(ntp) => report.ntp = Some(ntp),
+        Err(e) => {
+            warn!("Clock check failed: {}", e);
+            eprintln!("⚠ Clock check failed, capture times are unverified: {}", e);
+            report.ntp_error = Some(e.to_string());
+        }
+    }
+    if let Some(snapshot) = resolve_host(&args.url).await {
+        report.resolved_addresses = snapshot
+            .addresses()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        report.cnames = snapshot.cnames;
+    }
+
+    let recorder = HttpRecorder::record(dir.join("recording.json"));
+    let log = NetworkLog::new();
+    let generator = init_pdf_generator(None)
+        .await
+        .with_wait(Duration::from_secs(args.wait))
+        .with_recorder(recorder.clone())
+        .with_network_log(log.clone());
+    if let Err(e) = generator
+        .url_to_pdf(&args.url, Some(&dir.join("page.pdf")))
+        .await
+    {
+        error!("Failed to capture {}: {}", args.url, e);
+        eprintln!("✗ Failed to capture {}: {}", args.url, e);
+        std::process::exit(1);
+    }
+    let entries = log.entries();
+    report = report.with_network(&entries);
+
+    let written: Result<()> = async {
+        let responses = recorder.responses();
+        let page_url = report
+            .document
+            .as_ref()
+            .map_or(args.url.as_str(), |entry| entry.url.as_str());
+        let page = responses
+            .iter()
+            .find(|response| response.url == page_url)
+            .or_else(|| responses.first());
+        let html = match page {
+            Some(page) => page.body_bytes()?,
+            None => Vec::new(),
+        };
+        atomic::write(&dir.join("page.html"), html).await?;
+        warc::write_warc(&dir.join("capture.warc.gz"), &responses).await?;
+        atomic::write(
+            &dir.join("network.json"),
+            serde_json::to_vec_pretty(&entries)?,
+        )
+        .await?;
+
+        report.finished_at = chrono::Utc::now();
+        report.files = EVIDENCE_FILES
+            .iter()
+            .map(|name| EvidenceFile::hash(&dir, name))
+            .collect::<Result<_>>()?;
+        atomic::write(
+            &dir.join("report.json"),
+            serde_json::to_vec_pretty(&report)?,
+        )
+        .await?;
+        generator
+            .html_to_pdf(&report.to_html(), Some(&dir.join("report.pdf")))
+            .await?;
+        let mut files = report.files.clone();
+        for name in ["report.json", "report.pdf"] {
+            files.push(EvidenceFile::hash(&dir, name)?);
+        }
+        atomic::write(&dir.join("SHA256SUMS"), evidence::sha256sums(&files)).await
+    }
+    .await;
+    if let Err(e) = written {
+        error!("Failed to write the evidence of {}: {}", args.url, e);
+        eprintln!("✗ Failed to write the evidence of {}: {}", args.url, e);
+        std::process::exit(1);
+    }
+
+    if let Some(tsa) = &tsa {
+        if !timestamp_files(tsa, &[dir.join("SHA256SUMS")]).await {
+            std::process::exit(1);
+        }
+    }
+
+    println!(
+        "✓ Captured {} with {} response(s)",
+        args.url, report.responses
+    );
+    if let Some(ntp) = &report.ntp {
+        println!(
+            "  Local clock is {} ms off {} (round trip {} ms)",
+            ntp.offset_ms, ntp.server, ntp.round_trip_ms
+        );
+    }
+    println!("✓ {}", tr(Message::SavedTo(&dir)));
+    Ok(())
+}
+
+async fn run_archive_publish(dir: &Path, site_dir: &Path) -> Result<()> {
+    if !dir.join(manifest::MANIFEST_FILE).is_file() {
+        eprintln!(
+            "✗ {} has no record of saved pages; convert URLs into it with -o first",
+            dir.display()
+        );
+        std::process::exit(1);
+    }
+    match site::publish(dir, site_dir).await {
+        Ok(published) => {
+            println!(
+                "✓ Published {} page(s) and {} tag page(s)",
+                published.pages, published.tags
+            );
+            if published.skipped > 0 {
+                println!(
+                    "  Skipped {} page(s) saved without Markdown (convert with -f markdown or -f both)",
+                    published.skipped
+                );
+            }
+            println!("✓ {}", tr(Message::SavedTo(&site_dir.join("index.html"))));
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to publish {}: {}", dir.display(), e);
+            eprintln!("✗ Failed to publish {}: {}", dir.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print one line describing an archived capture
+fn print_archive_entry(entry: &archive::ArchiveEntry) {
+    let formats: Vec<&str> = [(entry.has_pdf, "pdf"), (entry.has_markdown, "markdown")]
+        .into_iter()
+        .filter_map(|(present, name)| present.then_some(name))
+        .collect();
+    println!(
+        "#{} {} [{}] {}",
+        entry.id,
+        entry.captured_at.format("%Y-%m-%d %H:%M"),
+        formats.join(", "),
+        entry.title.as_deref().unwrap_or(&entry.url)
+    );
+    if entry.title.is_some() {
+        println!("      {}", entry.url);
+    }
+}
+
+/// Store the output files of a converted input in the archive and report the outcome
+async fn archive_capture(
+    archive: &mut Archive,
+    source: &str,
+    pdf_path: Option<&Path>,
+    md_path: Option<&Path>,
+    pdf_dir: Option<&Path>,
+    tsa: Option<&TimestampAuthority>,
+) -> Result<()> {
+    let mut capture = Capture::new(source);
+    let mut pdf_data = None;
+    let result = async {
+        if let Some(md_path) = md_path {
+            let markdown = tokio::fs::read_to_string(md_path).await?;
+            if let Some(title) = archive::markdown_title(&markdown) {
+                capture = capture.with_title(title);
+            }
+            capture = capture.with_markdown(markdown);
+        }
+        if let Some(pdf_path) = pdf_path {
+            let data = tokio::fs::read(pdf_path).await?;
+            let blob = match pdf_dir {
+                Some(dir) => PdfBlob::Path(archive::write_pdf_file(dir, &data)?),
+                None => PdfBlob::Inline(data.clone()),
+            };
+            pdf_data = Some(data);
+            capture = capture.with_pdf(blob);
+        }
+        archive.insert(&capture).map(|id| (id, capture))
+    }
+    .await;
+
+    match result {
+        Ok((id, capture)) => {
+            println!("✓ Archived {} as #{}", source, id);
+            let Some(tsa) = tsa else {
+                return Ok(());
+            };
+            let parts = [
+                ("markdown", capture.markdown.as_deref().map(str::as_bytes)),
+                ("pdf", pdf_data.as_deref()),
+            ];
+            for (part, data) in parts {
+                let Some(data) = data else {
+                    continue;
+                };
+                let stored = match tsa.timestamp(data).await {
+                    Ok(token) => archive
+                        .add_timestamp(id, part, tsa.url(), &token)
+                        .map(|_| token),
+                    Err(e) => Err(e),
+                };
+                match stored {
+                    Ok(token) => report_timestamp(&format!("#{} ({})", id, part), &token),
+                    Err(e) => {
+                        error!("Failed to timestamp #{} ({}): {}", id, part, e);
+                        eprintln!("✗ Failed to timestamp #{} ({}): {}", id, part, e);
+                        return Err(e);
+                    }
+                }
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to archive {}: {}", source, e);
+            eprintln!("✗ Failed to archive {}: {}", source, e);
+            Err(e)
+        }
+    }
+}
+
+/// Convert a local HTML/MHTML file to PDF and report the outcome
+async fn render_to_pdf(generator: &PdfGenerator, path: &Path, pdf_path: &Path) -> Result<()> {
+    match render::file_url(path) {
+        Ok(file_url) => convert_to_pdf(generator, &file_url, pdf_path).await,
+        Err(e) => {
+            error!("Failed to generate PDF for {}: {}", path.display(), e);
+            eprintln!(
+                "✗ {}",
+                tr(Message::PdfFailed {
+                    source: &path.display(),
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Capture a full-page screenshot of a local HTML/MHTML file and report the outcome
+async fn render_to_screenshot(
+    generator: &PdfGenerator,
+    path: &Path,
+    format: ScreenshotFormat,
+    image_path: &Path,
+) -> Result<()> {
+    match render::file_url(path) {
+        Ok(file_url) => capture_screenshot(generator, &file_url, format, image_path).await,
+        Err(e) => {
+            error!("Failed to capture screenshot of {}: {}", path.display(), e);
+            eprintln!(
+                "✗ {}",
+                tr(Message::ScreenshotFailed {
+                    source: &path.display(),
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Convert a local HTML/MHTML file to Markdown and report the outcome
+async fn render_to_markdown(
+    generator: &MarkdownGenerator,
+    snapshot_path: &Path,
+    md_path: &Path,
+) -> Result<()> {
+    let result = async {
+        let snapshot = render::read_snapshot(snapshot_path).await?;
+        let markdown_data = generator
+            .html_to_markdown(&snapshot.html, snapshot.source_url.as_deref())
+            .await?;
+        atomic::write(md_path, &markdown_data).await?;
+        Ok::<_, anyhow::Error>(markdown_data)
+    }
+    .await;
+
+    match result {
+        Ok(markdown_data) => {
+            println!("✓ {}", tr(Message::GeneratedMarkdown(markdown_data.len())));
+            println!("✓ {}", tr(Message::SavedTo(md_path)));
+            Ok(())
+        }
+        Err(e) => {
+            error!(
+                "Failed to generate Markdown for {}: {}",
+                snapshot_path.display(),
+                e
+            );
+            eprintln!(
+                "✗ {}",
+                tr(Message::MarkdownFailed {
+                    source: &snapshot_path.display(),
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Where the Markdown conversion of an input goes
+enum MarkdownTarget<'a> {
+    /// A standalone Markdown file
+    File(&'a Path),
+    /// Numbered chunk files derived from a Markdown file path
+    Chunks(&'a Path, &'a ChunkConfig),
+    /// A page collected for the merged document
+    Merge(&'a mut Vec<MarkdownPage>),
+}
+
+/// Convert a URL to each requested format
+///
+/// With `screenshot`, the PDF generator captures a screenshot into `pdf_path` instead of
+/// a PDF. With `epub`, an EPUB book is written to the given path. Each format is
+/// attempted even if another one failed. Failures are reported as they occur and
+/// returned in the outcome for the caller to inspect.
+async fn convert_url(
+    pdf_generator: Option<&PdfGenerator>,
+    md_generator: Option<&MarkdownGenerator>,
+    url: &str,
+    pdf_path: &Path,
+    screenshot: Option<ScreenshotFormat>,
+    md_target: MarkdownTarget<'_>,
+    epub: Option<(&EpubGenerator, &Path)>,
+) -> FormatOutcome {
+    let mut outcome = FormatOutcome::default();
+    if let Some(generator) = pdf_generator {
+        let (format, result) = match screenshot {
+            Some(format) => {
+                info!("Capturing a screenshot of URL: {}", url);
+                let result = capture_screenshot(generator, url, format, pdf_path).await;
+                (IntegrationOutputFormat::Screenshot, result)
+            }
+            None => {
+                info!("Converting URL to PDF: {}", url);
+                let result = convert_to_pdf(generator, url, pdf_path).await;
+                (IntegrationOutputFormat::Pdf, result)
+            }
+        };
+        outcome.record(format, result.map(|()| vec![pdf_path.to_path_buf()]));
+    }
+    if let Some(generator) = md_generator {
+        info!("Converting URL to Markdown: {}", url);
+        let result = match md_target {
+            MarkdownTarget::File(md_path) => convert_to_markdown(generator, url, md_path)
+                .await
+                .map(|()| vec![md_path.to_path_buf()]),
+            MarkdownTarget::Chunks(md_path, chunking) => {
+                write_page_chunks(generator.url_to_page(url).await, url, md_path, chunking).await
+            }
+            MarkdownTarget::Merge(pages) => {
+                collect_page(generator.url_to_page(url).await, url, pages).map(|()| Vec::new())
+            }
+        };
+        outcome.record(IntegrationOutputFormat::Markdown, result);
+    }
+    if let Some((generator, epub_path)) = epub {
+        info!("Converting URL to EPUB: {}", url);
+        let result = convert_to_epub(generator, url, epub_path).await;
+        outcome.record(
+            IntegrationOutputFormat::Epub,
+            result.map(|()| vec![epub_path.to_path_buf()]),
+        );
+    }
+    outcome
+}
+
+/// Convert a local HTML file to each requested format
+///
+/// With `screenshot`, the PDF generator captures a screenshot into `pdf_path` instead of
+/// a PDF. With `epub`, an EPUB book is written to the given path. Each format is
+/// attempted even if another one failed. Failures are reported as they occur and
+/// returned in the outcome for the caller to inspect.
+async fn convert_file(
+    pdf_generator: Option<&PdfGenerator>,
+    md_generator: Option<&MarkdownGenerator>,
+    path: &Path,
+    pdf_path: &Path,
+    screenshot: Option<ScreenshotFormat>,
+    md_target: MarkdownTarget<'_>,
+    epub: Option<(&EpubGenerator, &Path)>,
+) -> FormatOutcome {
+    let mut outcome = FormatOutcome::default();
+    if let Some(generator) = pdf_generator {
+        let (format, result) = match screenshot {
+            Some(format) => {
+                info!("Capturing a screenshot of file: {}", path.display());
+                let result = render_to_screenshot(generator, path, format, pdf_path).await;
+                (IntegrationOutputFormat::Screenshot, result)
+            }
+            None => {
+                info!("Converting file to PDF: {}", path.display());
+                let result = render_to_pdf(generator, path, pdf_path).await;
+                (IntegrationOutputFormat::Pdf, result)
+            }
+        };
+        outcome.record(format, result.map(|()| vec![pdf_path.to_path_buf()]));
+    }
+    if let Some(generator) = md_generator {
+        info!("Converting file to Markdown: {}", path.display());
+        let source = path.display().to_string();
+        let result = match md_target {
+            MarkdownTarget::File(md_path) => render_to_markdown(generator, path, md_path)
+                .await
+                .map(|()| vec![md_path.to_path_buf()]),
+            MarkdownTarget::Chunks(md_path, chunking) => {
+                let page = file_to_page(generator, path).await;
+                write_page_chunks(page, &source, md_path, chunking).await
+            }
+            MarkdownTarget::Merge(pages) => {
+                let page = file_to_page(generator, path).await;
+                collect_page(page, &source, pages).map(|()| Vec::new())
+            }
+        };
+        outcome.record(IntegrationOutputFormat::Markdown, result);
+    }
+    if let Some((generator, epub_path)) = epub {
+        info!("Converting file to EPUB: {}", path.display());
+        let result = render_to_epub(generator, path, epub_path).await;
+        outcome.record(
+            IntegrationOutputFormat::Epub,
+            result.map(|()| vec![epub_path.to_path_buf()]),
+        );
+    }
+    outcome
+}
+
+/// Convert a local HTML/MHTML file to a Markdown page
+async fn file_to_page(generator: &MarkdownGenerator, path: &Path) -> Result<MarkdownPage> {
+    let snapshot = render::read_snapshot(path).await?;
+    generator.html_to_page(&snapshot.html, snapshot.source_url.as_deref())
+}
+
+/// Write a converted page as numbered chunk files and report the outcome
+async fn write_page_chunks(
+    page: Result<MarkdownPage>,
+    source: &str,
+    md_path: &Path,
+    chunking: &ChunkConfig,
+) -> Result<Vec<PathBuf>> {
+    let result = match page {
+        Ok(page) => {
+            chunk::write_chunks(
+                md_path,
+                &page.to_document(),
+                Some(&page.title),
+                page.url.as_deref(),
+                chunking,
+            )
+            .await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(paths) => {
+            report_chunks(&paths);
+            Ok(paths)
+        }
+        Err(e) => {
+            error!("Failed to generate Markdown chunks for {}: {}", source, e);
+            eprintln!("✗ Failed to generate Markdown chunks for {}: {}", source, e);
+            Err(e)
+        }
+    }
+}
+
+/// Print the chunk files written for a document
+fn report_chunks(paths: &[PathBuf]) {
+    println!("✓ Split Markdown into {} chunks", paths.len());
+    for path in paths {
+        println!("✓ {}", tr(Message::SavedTo(path)));
+    }
+}
+
+/// Add a converted page to the merged document and report the outcome
+fn collect_page(
+    page: Result<MarkdownPage>,
+    source: &str,
+    pages: &mut Vec<MarkdownPage>,
+) -> Result<()> {
+    match page {
+        Ok(page) => {
+            println!("✓ Converted {} for the merged document", source);
+            pages.push(page);
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to generate Markdown for {}: {}", source, e);
+            eprintln!(
+                "✗ {}",
+                tr(Message::MarkdownFailed {
+                    source: &source,
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Print the per-domain statistics of a run and any personal data found, and save them
+/// as JSON if requested
+async fn report_stats(
+    stats: &RunStats,
+    timeouts: &AdaptiveTimeouts,
+    pii_scanner: Option<&PiiScanner>,
+    json_path: Option<&Path>,
+) {
+    let mut report = stats.report().with_timeouts(timeouts);
+    if !stats.is_empty() {
+        println!("\n{}", tr(Message::DomainStatistics));
+        print!("{}", report.to_table());
+    }
+    if !report.blocked.is_empty() {
+        println!("\n⚠ Skipped {} blocked URL(s):", report.blocked.len());
+        for blocked in &report.blocked {
+            println!("  {} ({} on {})", blocked.url, blocked.domain, blocked.list);
+        }
+    }
+    if !report.quarantined.is_empty() {
+        println!(
+            "\n⚠ Quarantined {} infected file(s):",
+            report.quarantined.len()
+        );
+        for file in &report.quarantined {
+            println!(
+                "  {} ({}) -> {}",
+                file.file.display(),
+                file.finding,
+                file.quarantined.display()
+            );
+        }
+    }
+    let security = report.security_summary();
+    if !security.is_empty() {
+        println!("\n⚠ Security issues:");
+        print!("{}", security);
+    }
+    if let Some(scanner) = pii_scanner {
+        report = report.with_pii(scanner);
+        match report.pii.len() {
+            0 => println!("\n✓ No personal data found in the captured pages"),
+            documents => {
+                println!(
+                    "\n⚠ Possible personal data found in {} document(s):",
+                    documents
+                );
+                print!("{}", report.pii_summary());
+            }
+        }
+    }
+    if let Some(path) = json_path {
+        match report.write_json(path).await {
+            Ok(()) => println!("✓ {}", tr(Message::SavedStatistics(path))),
+            Err(e) => {
+                error!("Failed to save statistics to {}: {}", path.display(), e);
+                eprintln!("✗ Failed to save statistics to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// List the inputs for which only some of the requested formats were converted
+fn report_partial(partial: &[String]) {
+    if partial.is_empty() {
+        return;
+    }
+    eprintln!("✗ {}", tr(Message::PartiallyConverted(partial.len())));
+    for line in partial {
+        eprintln!("  {}", line);
+    }
+}
+
+/// Report and return whether an error means the disk is full, so a batch should stop
+fn stop_if_disk_full(error: &anyhow::Error) -> bool {
+    if !space::is_disk_full(error) {
+        return false;
+    }
+    error!("Disk full, stopping: {}", error);
+    eprintln!("✗ {}", tr(Message::DiskFull));
+    true
+}
+
+/// Fail early when the output location lacks space for the estimated output
+///
+/// Exits the process if the space is insufficient. If the free space cannot be
+/// determined, the check is skipped with a warning.
+fn check_disk_space(path: &Path, count: usize, format: &OutputFormat) {
+    // Books embed their images, so they are estimated like PDFs
+    let required = space::estimate(
+        count,
+        matches!(
+            format,
+            OutputFormat::Pdf | OutputFormat::Both | OutputFormat::Screenshot | OutputFormat::Epub
+        ),
+        matches!(format, OutputFormat::Markdown | OutputFormat::Both),
+    );
+    if let Err(e) = space::ensure_space(path, required) {
+        if space::available_space(path).is_err() {
+            warn!("Skipping disk space check: {}", e);
+            return;
+        }
+        error!("{}", e);
+        eprintln!("✗ {}", e);
+        eprintln!("  Free up space, or use --no-space-check to convert anyway");
+        std::process::exit(1);
+    }
+}
+
+/// Whether an output path refers to a directory rather than a file
+fn is_directory_path(path: &Path) -> bool {
+    path.is_dir()
+        || path
+            .as_os_str()
+            .to_string_lossy()
+            .ends_with(std::path::MAIN_SEPARATOR)
+}
+
+/// Compute the output filename (without extension) for a URL
+///
+/// Outside directory mode the stem is the hostname, preserving the single-URL
+/// behavior. In directory mode the stem includes the URL path, unless a stem is given,
+/// and is made unique within the run so that URLs on the same host do not overwrite
+/// each other. With a manifest, URLs already saved in the directory are reported as
+/// such, and filenames belonging to other URLs are not reused.
+fn output_slot(
+    url: &str,
+    stem: Option<&str>,
+    output_dir: Option<&Path>,
+    manifest: Option<&OutputManifest>,
+    force: bool,
+    outputs: impl Fn(&str) -> Vec<PathBuf>,
+    used_stems: &mut HashSet<String>,
+) -> Result<OutputSlot> {
+    let Some(dir) = output_dir else {
+        let parsed = url::Url::parse(url)?;
+        return Ok(OutputSlot::Free(
+            parsed.host_str().unwrap_or("unknown").to_string(),
+        ));
+    };
+
+    let base = match stem {
+        Some(stem) => stem.to_string(),
+        None => filename_from_url(url)?,
+    };
+    match manifest {
+        Some(manifest) => manifest.resolve_output(dir, url, &base, force, outputs, used_stems),
+        None => {
+            OutputManifest::default().resolve_output(dir, url, &base, true, outputs, used_stems)
+        }
+    }
+}
+
+/// The path of an output file for a stem, keeping any dots in the stem, so that
+/// `example.com` becomes `example.com.pdf`
+fn output_file(stem: &Path, extension: &str) -> PathBuf {
+    let mut path = stem.as_os_str().to_os_string();
+    path.push(".");
+    path.push(extension);
+    PathBuf::from(path)
+}
+
+/// Create a PDF generator, on the given Chrome profile if any, exiting the process on
+/// failure
+async fn init_pdf_generator(profile_dir: Option<&Path>) -> PdfGenerator {
+    let generator = match (profile_dir, browser_proxy()) {
+        (profile_dir, Some(proxy)) => {
+            PdfGenerator::new_with_proxy(profile_dir, proxy.clone()).await
+        }
+        (Some(dir), None) => PdfGenerator::new_with_profile(dir).await,
+        (None, None) => PdfGenerator::new().await,
+    };
+    match generator {
+        Ok(generator) => {
+            info!("PDF generator initialized successfully");
+            generator
+        }
+        Err(e) => {
+            error!("Failed to initialize PDF generator: {}", e);
+            eprintln!("✗ Failed to initialize PDF generator: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Start recording responses, or load a recording to replay, exiting the process on
+/// failure
+fn init_recorder(record: Option<&Path>, replay: Option<&Path>) -> Option<HttpRecorder> {
+    match (record, replay) {
+        (Some(path), _) => Some(HttpRecorder::record(path)),
+        (None, Some(path)) => match HttpRecorder::replay(path) {
+            Ok(recorder) => {
+                info!("Replaying {} recorded responses", recorder.len());
+                Some(recorder)
+            }
+            Err(e) => {
+                error!("Failed to load recording: {}", e);
+                eprintln!("✗ Failed to load recording: {}", e);
+                std::process::exit(1);
+            }
+        },
+        (None, None) => None,
+    }
+}
+
+/// Launch headless Chrome on a profile directory, exiting the process on failure
+fn init_profile_browser(profile_dir: &Path) -> Browser {
+    match session::launch_browser(true, Some(profile_dir), browser_proxy()) {
+        Ok(browser) => browser,
+        Err(e) => {
+            error!(
+                "Failed to launch Chrome on {}: {}",
+                profile_dir.display(),
+                e
+            );
+            eprintln!(
+                "✗ Failed to launch Chrome on {}: {}",
+                profile_dir.display(),
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Open a capture archive, exiting the process on failure
+fn init_archive(path: &Path) -> Archive {
+    match Archive::open(path) {
+        Ok(archive) => archive,
+        Err(e) => {
+            error!("Failed to open archive {}: {}", path.display(), e);
+            eprintln!("✗ Failed to open archive {}: {}", path.display(), e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Build the PDF branding from the command-line options, exiting the process on failure
+fn init_branding(args: &BrandingArgs) -> Option<Branding> {
+    match args.branding() {
+        Ok(branding) => branding,
+        Err(e) => {
+            error!("Invalid cover page or header options: {}", e);
+            eprintln!("✗ Invalid cover page or header options: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Build the reader mode from the command-line options, exiting the process on failure
+fn init_reader(
+    args: &ReaderArgs,
+    extraction: &ExtractArgs,
+    device: &DeviceArgs,
+) -> Option<ReaderMode> {
+    match args.reader(init_extraction(extraction), device.paper()) {
+        Ok(reader) => reader,
+        Err(e) => {
+            error!("Invalid reader-mode options: {}", e);
+            eprintln!("✗ Invalid reader-mode options: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Build the e-reader delivery from the command-line options, exiting the process on failure
+fn init_delivery(args: &DeviceArgs) -> Delivery {
+    match args.delivery() {
+        Ok(delivery) => delivery,
+        Err(e) => {
+            error!("Invalid delivery options: {}", e);
+            eprintln!("✗ Invalid delivery options: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Deliver a saved PDF to the e-reader destinations, reporting failures
+async fn deliver_pdf(delivery: &Delivery, pdf_path: &Path) -> Result<()> {
+    match delivery.deliver(pdf_path).await {
+        Ok(delivered) => {
+            for destination in delivered {
+                match destination {
+                    Delivered::Device(target) => {
+                        println!("✓ Copied to device: {}", target.display())
+                    }
+                    Delivered::Kindle(address) => println!("✓ Sent to Kindle: {}", address),
+                }
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to deliver {}: {}", pdf_path.display(), e);
+            eprintln!("✗ Failed to deliver {}: {}", pdf_path.display(), e);
+            Err(e)
+        }
+    }
+}
+
+/// Build the content extraction settings from the command-line options, exiting the process on failure
+fn init_extraction(args: &ExtractArgs) -> ExtractConfig {
+    match args.extract_config() {
+        Ok(extraction) => extraction,
+        Err(e) => {
+            error!("Invalid extraction options: {}", e);
+            eprintln!("✗ Invalid extraction options: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Load the domain blocklist files, or None when there are none, exiting the process
+/// on failure
+async fn init_blocklist(paths: &[PathBuf]) -> Option<DomainBlocklist> {
+    if paths.is_empty() {
+        return None;
+    }
+    match DomainBlocklist::load(paths).await {
+        Ok(blocklist) => {
+            info!("Loaded {} blocked domains", blocklist.len());
+            Some(blocklist)
+        }
+        Err(e) => {
+            error!("Invalid blocklist: {}", e);
+            eprintln!("✗ Invalid blocklist: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Load the site profiles from the command-line options, exiting the process on failure
+async fn init_print_views(args: &SiteArgs) -> Option<PrintViews> {
+    match args.print_views().await {
+        Ok(print_views) => print_views,
+        Err(e) => {
+            error!("Invalid site options: {}", e);
+            eprintln!("✗ Invalid site options: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Create the Wikipedia article export unless disabled, exiting the process on failure
+fn init_wikipedia(args: &SiteArgs) -> Option<Wikipedia> {
+    match args.wikipedia() {
+        Ok(wikipedia) => wikipedia,
+        Err(e) => {
+            error!("Failed to initialize Wikipedia export: {}", e);
+            eprintln!("✗ Failed to initialize Wikipedia export: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Read the cookies of saved login sessions and of the browser given with
+/// --cookies-from-browser, exiting the process on failure
+fn init_cookies(args: &SiteArgs) -> Option<BrowserCookies> {
+    match args.cookies() {
+        Ok(cookies) => cookies,
+        Err(e) => {
+            error!("Failed to read cookies: {}", e);
+            eprintln!("✗ Failed to read cookies: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn init_request_options(args: &SiteArgs) -> Option<RequestOptions> {
+    match args.request_options() {
+        Ok(options) => options,
+        Err(e) => {
+            error!("Invalid request header: {}", e);
+            eprintln!("✗ Invalid request header: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// The resolver of `--dns-server`, `--resolve`, and `--unix-socket`, or None to use the
+/// system's
+fn init_resolver(
+    server: Option<&str>,
+    overrides: &[String],
+    sockets: &[String],
+) -> Result<Option<DnsResolver>> {
+    if server.is_none() && overrides.is_empty() && sockets.is_empty() {
+        return Ok(None);
+    }
+    let mut resolver = match server {
+        Some(server) => DnsResolver::new(DnsServer::new(server)?),
+        None => DnsResolver::system(),
+    };
+    for spec in overrides {
+        let (host, addresses) = parse_override(spec)?;
+        resolver = resolver.with_override(&host, addresses);
+    }
+    for spec in sockets {
+        let (host, path) = parse_unix_socket(spec)?;
+        resolver = resolver.with_unix_socket(&host, path);
+    }
+    Ok(Some(resolver))
+}
+
+/// Snapshot how the host of a URL resolves, warning when it cannot be resolved
+async fn resolve_host(url: &str) -> Option<DnsSnapshot> {
+    let host = url::Url::parse(url).ok()?.host_str()?.to_string();
+    let name = host.clone();
+    let resolved = match resolver() {
+        Some(resolver) => Ok(resolver.snapshot(&host).await),
+        None => {
+            tokio::task::spawn_blocking(move || DnsSnapshot::resolve(&name, dns::DEFAULT_TIMEOUT))
+                .await
+        }
+    };
+    match resolved {
+        Ok(Ok(snapshot)) => {
+            info!(
+                "Resolved {} via [{}] to {:?}",
+                host,
+                snapshot.cnames.join(", "),
+                snapshot.addresses()
+            );
+            Some(snapshot)
+        }
+        Ok(Err(e)) => {
+            warn!("Failed to resolve {}: {}", host, e);
+            eprintln!("⚠ Failed to resolve {}: {}", host, e);
+            None
+        }
+        Err(e) => {
+            warn!("Failed to resolve {}: {}", host, e);
+            None
+        }
+    }
+}
+
+/// Add the articles linked from the "See also" sections of the Wikipedia articles among
+/// the URLs, after them
+async fn add_see_also(urls: &mut Vec<String>) -> Result<()> {
+    let wikipedia = Wikipedia::new()?;
+    let mut seen: HashSet<String> = urls.iter().cloned().collect();
+    let articles: Vec<String> = urls
+        .iter()
+        .filter(|url| WikipediaUrl::parse(url).is_some())
+        .cloned()
+        .collect();
+    for url in articles {
+        match wikipedia.see_also(&url).await {
+            Ok(links) => {
+                let links: Vec<String> = links
+                    .into_iter()
+                    .filter(|link| seen.insert(link.clone()))
+                    .collect();
+                println!("✓ Found {} \"See also\" articles in {}", links.len(), url);
+                urls.extend(links);
+            }
+            Err(e) => {
+                warn!("Failed to read the \"See also\" section of {}: {}", url, e);
+            }
+        }
+    }
     Ok(())
 }
+
+/// Create the social thread capture unless disabled, exiting the process on failure
+fn init_social_threads(args: &SiteArgs) -> Option<SocialThreads> {
+    match args.social_threads() {
+        Ok(social_threads) => social_threads,
+        Err(e) => {
+            error!("Failed to initialize social thread capture: {}", e);
+            eprintln!("✗ Failed to initialize social thread capture: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Build the Markdown chunking from the command-line options, exiting the process on failure
+fn init_chunking(args: &ChunkArgs) -> Option<ChunkConfig> {
+    match args.chunk_config() {
+        Ok(chunking) => chunking,
+        Err(e) => {
+            error!("Invalid chunking options: {}", e);
+            eprintln!("✗ Invalid chunking options: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Create a Markdown generator, exiting the process on failure
+async fn init_markdown_generator() -> MarkdownGenerator {
+    let generator = MarkdownGenerator::new().await;
+    let generator = match (proxy(), resolver()) {
+        (Some(proxy), _) => generator.and_then(|generator| generator.with_proxy(proxy.clone())),
+        (None, Some(resolver)) => {
+            generator.and_then(|generator| generator.with_resolver(resolver.clone()))
+        }
+        (None, None) => generator,
+    };
+    match generator {
+        Ok(generator) => {
+            info!("Markdown generator initialized successfully");
+            generator
+        }
+        Err(e) => {
+            error!("Failed to initialize Markdown generator: {}", e);
+            eprintln!("✗ Failed to initialize Markdown generator: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Convert a URL to PDF and report the outcome
+async fn convert_to_pdf(generator: &PdfGenerator, url: &str, pdf_path: &Path) -> Result<()> {
+    match generator.url_to_pdf(url, Some(pdf_path)).await {
+        Ok(pdf_data) => {
+            info!("PDF generated successfully ({} bytes)", pdf_data.len());
+            println!("✓ {}", tr(Message::GeneratedPdf(pdf_data.len())));
+            println!("✓ {}", tr(Message::SavedTo(pdf_path)));
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to generate PDF for {}: {}", url, e);
+            eprintln!(
+                "✗ {}",
+                tr(Message::PdfFailed {
+                    source: &url,
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Capture a full-page screenshot of a URL and report the outcome
+async fn capture_screenshot(
+    generator: &PdfGenerator,
+    url: &str,
+    format: ScreenshotFormat,
+    image_path: &Path,
+) -> Result<()> {
+    match generator
+        .url_to_screenshot(url, format, Some(image_path))
+        .await
+    {
+        Ok(image_data) => {
+            info!(
+                "{} screenshot captured successfully ({} bytes)",
+                format,
+                image_data.len()
+            );
+            println!("✓ {}", tr(Message::CapturedScreenshot(image_data.len())));
+            println!("✓ {}", tr(Message::SavedTo(image_path)));
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to capture screenshot of {}: {}", url, e);
+            eprintln!(
+                "✗ {}",
+                tr(Message::ScreenshotFailed {
+                    source: &url,
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Convert a URL to Markdown and report the outcome
+async fn convert_to_markdown(
+    generator: &MarkdownGenerator,
+    url: &str,
+    md_path: &Path,
+) -> Result<()> {
+    match generator.url_to_markdown(url, Some(md_path)).await {
+        Ok(markdown_data) => {
+            info!(
+                "Markdown generated successfully ({} chars)",
+                markdown_data.len()
+            );
+            println!("✓ {}", tr(Message::GeneratedMarkdown(markdown_data.len())));
+            println!("✓ {}", tr(Message::SavedTo(md_path)));
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to generate Markdown for {}: {}", url, e);
+            eprintln!(
+                "✗ {}",
+                tr(Message::MarkdownFailed {
+                    source: &url,
+                    error: &e
+                })
+            );
+            Err(e)
+        }
+    }
+}
+
+/// Convert a URL to an EPUB book and report the outcome
+async fn convert_to_epub(generator: &EpubGenerator, url: &str, epub_path: &Path) -> Result<()> {
+    let result = generator.url_to_epub(url, Some(epub_path)).await;
+    report_epub(result, &url, epub_path)
+}
+
+/// Convert a local HTML/MHTML file to an EPUB book and report the outcome
+async fn render_to_epub(
+    generator: &EpubGenerator,
+    snapshot_path: &Path,
+    epub_path: &Path,
+) -> Result<()> {
+    let result = async {
+        let snapshot = render::read_snapshot(snapshot_path).await?;
+        generator
+            .html_to_epub(
+                &snapshot.html,
+                snapshot.source_url.as_deref(),
+                Some(epub_path),
+            )
+            .await
+    }
+    .await;
+    report_epub(result, &snapshot_path.display(), epub_path)
+}
+
+/// Report the outcome of writing an EPUB book
+fn report_epub(
+    result: Result<Vec<u8>>,
+    source: &dyn std::fmt::Display,
+    epub_path: &Path,
+) -> Result<()> {
+    match result {
+        Ok(epub_data) => {
+            info!("EPUB generated successfully ({} bytes)", epub_data.len());
+            println!("✓ {}", tr(Message::GeneratedEpub(epub_data.len())));
+            println!("✓ {}", tr(Message::SavedTo(epub_path)));
+            Ok(())
+        }
+        Err(e) => {
+            error!("Failed to generate EPUB for {}: {}", source, e);
+            eprintln!("✗ {}", tr(Message::EpubFailed { source, error: &e }));
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolver_options_conflict_with_proxy() {
+        for option in [
+            ["--dns-server", "10.0.0.53"],
+            ["--resolve", "example.com:203.0.113.7"],
+            ["--unix-socket", "example.internal=/run/example.sock"],
+        ] {
+            let args = ["webpage-save", "--proxy", "socks5://127.0.0.1:1080"];
+            let error = Cli::try_parse_from(args.into_iter().chain(option)).err();
+            assert_eq!(
+                error.map(|error| error.kind()),
+                Some(clap::error::ErrorKind::ArgumentConflict),
+                "{} was accepted with --proxy",
+                option[0]
+            );
+        }
+    }
+
+    #[test]
+    fn test_output_files_on_one_host() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let outputs = |stem: &str| {
+            let stem = dir.path().join(stem);
+            vec![output_file(&stem, "pdf"), output_file(&stem, "md")]
+        };
+
+        let mut used_stems = HashSet::new();
+        let mut files = Vec::new();
+        for url in ["https://example.com/a", "https://example.com/b"] {
+            let slot = output_slot(
+                url,
+                None,
+                Some(dir.path()),
+                None,
+                false,
+                outputs,
+                &mut used_stems,
+            )?;
+            match slot {
+                OutputSlot::Free(stem) => files.push(outputs(&stem)),
+                OutputSlot::Saved(_) => panic!("{} is not saved yet", url),
+            }
+        }
+        assert_eq!(
+            files,
+            [
+                vec![
+                    dir.path().join("example.com_a.pdf"),
+                    dir.path().join("example.com_a.md")
+                ],
+                vec![
+                    dir.path().join("example.com_b.pdf"),
+                    dir.path().join("example.com_b.md")
+                ],
+            ]
+        );
+
+        // Without an output directory, a page is named after its host
+        let slot = output_slot(
+            "https://example.com/a",
+            None,
+            None,
+            None,
+            false,
+            outputs,
+            &mut HashSet::new(),
+        )?;
+        let OutputSlot::Free(stem) = slot else {
+            panic!("no output directory to be saved in");
+        };
+        assert_eq!(
+            output_file(Path::new(&stem), "pdf"),
+            PathBuf::from("example.com.pdf")
+        );
+        Ok(())
+    }
+}
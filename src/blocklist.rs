@@ -0,0 +1,181 @@
+//! Domain blocklists screening URLs before conversion
+//!
+//! Automated runs such as search-to-PDF and documentation crawls convert whatever URLs
+//! they come across. A [`DomainBlocklist`] loads local lists of unwanted domains (malware
+//! hosts, adult content, or anything else) and flags the URLs on them, so they are
+//! skipped and reported instead of archived. A listed domain also blocks its subdomains.
+//!
+//! Lists are text files with one domain per line. Hosts files (`0.0.0.0 example.com`)
+//! and Adblock-style domain rules (`||example.com^`) are read as well, so published
+//! lists can be used as they are. Lines starting with `#` or `!` are comments.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Host names in hosts files that are not blocked domains
+const HOSTS_FILE_NAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "0.0.0.0",
+];
+
+/// A URL skipped because its domain is on a blocklist
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockedUrl {
+    /// The skipped URL
+    pub url: String,
+    /// The listed domain the URL belongs to
+    pub domain: String,
+    /// Name of the list the domain is on
+    pub list: String,
+}
+
+/// Blocked domains, with the name of the list each one came from
+#[derive(Debug, Clone, Default)]
+pub struct DomainBlocklist {
+    domains: HashMap<String, String>,
+}
+
+impl DomainBlocklist {
+    /// Create an empty blocklist
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load blocklist files, each named after its file name
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read
+    pub async fn load(paths: &[impl AsRef<Path>]) -> Result<Self> {
+        let mut blocklist = Self::new();
+        for path in paths {
+            let path = path.as_ref();
+            let text = tokio::fs::read_to_string(path)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+            let name = path.file_name().map_or_else(
+                || path.display().to_string(),
+                |name| name.to_string_lossy().into_owned(),
+            );
+            blocklist = blocklist.with_list(&name, &text);
+        }
+        Ok(blocklist)
+    }
+
+    /// Add the domains of a list in any of the supported formats
+    pub fn with_list(mut self, name: &str, text: &str) -> Self {
+        for domain in text.lines().filter_map(parse_line) {
+            self.domains
+                .entry(domain)
+                .or_insert_with(|| name.to_string());
+        }
+        self
+    }
+
+    /// Number of blocked domains
+    pub fn len(&self) -> usize {
+        self.domains.len()
+    }
+
+    /// Whether no domains are blocked
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// The block of a URL whose domain, or a parent domain of it, is listed
+    pub fn check(&self, url: &str) -> Option<BlockedUrl> {
+        let host = url::Url::parse(url).ok()?.host_str()?.to_lowercase();
+        let mut domain = host.trim_end_matches('.');
+        loop {
+            if let Some(list) = self.domains.get(domain) {
+                return Some(BlockedUrl {
+                    url: url.to_string(),
+                    domain: domain.to_string(),
+                    list: list.clone(),
+                });
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+}
+
+/// The domain listed on a line, or None for comments, blank lines, and other rules
+fn parse_line(line: &str) -> Option<String> {
+    let line = line.split('#').next()?.trim();
+    if line.is_empty() || line.starts_with('!') {
+        return None;
+    }
+
+    // Adblock domain rules, without options such as `$third-party`
+    let domain = match line.strip_prefix("||") {
+        Some(rule) => rule.split(['^', '$', '/']).next()?,
+        None => {
+            // Hosts files map an address to one or more names
+            let mut fields = line.split_whitespace();
+            let first = fields.next()?;
+            match first.parse::<std::net::IpAddr>() {
+                Ok(_) => fields.next()?,
+                Err(_) => first,
+            }
+        }
+    };
+    let domain = domain
+        .trim_start_matches("*.")
+        .trim_end_matches('.')
+        .to_lowercase();
+    let valid = domain.contains('.')
+        && !HOSTS_FILE_NAMES.contains(&domain.as_str())
+        && domain
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_'));
+    valid.then_some(domain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_urls() {
+        let blocklist = DomainBlocklist::new()
+            .with_list(
+                "malware.txt",
+                "# Malware hosts\n\
+                 0.0.0.0 bad.example\n\
+                 127.0.0.1 localhost\n\
+                 evil.example.org # reported 2026-01\n",
+            )
+            .with_list(
+                "ads.txt",
+                "! Adblock list\n||ads.example.net^$third-party\n*.tracker.example\n",
+            );
+        assert_eq!(blocklist.len(), 4);
+
+        assert_eq!(
+            blocklist.check("https://www.Bad.example/page"),
+            Some(BlockedUrl {
+                url: "https://www.Bad.example/page".to_string(),
+                domain: "bad.example".to_string(),
+                list: "malware.txt".to_string(),
+            })
+        );
+        assert_eq!(
+            blocklist
+                .check("http://cdn.ads.example.net/x.js")
+                .map(|blocked| blocked.list),
+            Some("ads.txt".to_string())
+        );
+        assert!(blocklist.check("https://pixel.tracker.example/").is_some());
+        assert!(blocklist.check("https://example.org/").is_none());
+        assert!(blocklist.check("https://notbad.example/").is_none());
+        assert!(blocklist.check("http://localhost/").is_none());
+        assert!(blocklist.check("not a url").is_none());
+    }
+}
@@ -0,0 +1,366 @@
+//! Bookmark service integration
+//!
+//! This module fetches bookmarks from Pinboard and Raindrop.io, so every bookmarked page
+//! can be archived locally, and notes the paths of the local copies on the bookmarks.
+//! The note is a single `Archived copy:` line in the bookmark's description (Pinboard)
+//! or note (Raindrop), replaced when the copy moves and left alone otherwise.
+
+use crate::readlater::{checked, http_client, send_with_retry};
+use anyhow::Result;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::debug;
+
+/// Pinboard API base URL
+pub const PINBOARD_API: &str = "https://api.pinboard.in/v1";
+
+/// Raindrop.io API base URL
+pub const RAINDROP_API: &str = "https://api.raindrop.io/rest/v1";
+
+/// Prefix of the note line that records where a bookmark is archived
+pub const ARCHIVE_NOTE_PREFIX: &str = "Archived copy:";
+
+/// Pinboard asks clients to wait this long between API calls
+const PINBOARD_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Most bookmarks Raindrop returns per page
+const RAINDROP_PAGE_SIZE: usize = 50;
+
+/// A bookmark in a bookmark service
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    /// The bookmarked URL
+    pub url: String,
+    /// Title of the bookmark
+    pub title: Option<String>,
+    /// Tags of the bookmark
+    pub tags: Vec<String>,
+    /// Free-text note (Pinboard's extended description)
+    pub note: String,
+    /// Service-specific fields needed to update the bookmark
+    source: BookmarkSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BookmarkSource {
+    Pinboard {
+        time: String,
+        shared: String,
+        toread: String,
+    },
+    Raindrop {
+        id: i64,
+    },
+}
+
+/// The note line recording the local copies of a bookmark
+pub fn archive_note(paths: &[PathBuf]) -> String {
+    let paths: Vec<String> = paths
+        .iter()
+        .map(|path| {
+            std::fs::canonicalize(path)
+                .unwrap_or_else(|_| path.clone())
+                .display()
+                .to_string()
+        })
+        .collect();
+    format!("{} {}", ARCHIVE_NOTE_PREFIX, paths.join(", "))
+}
+
+/// A note with its archive line set to `line`, or None if it already has that line
+///
+/// An earlier archive line is replaced; otherwise the line is appended.
+pub fn with_archive_note(note: &str, line: &str) -> Option<String> {
+    if note.lines().any(|existing| existing.trim() == line) {
+        return None;
+    }
+    let mut lines: Vec<&str> = note
+        .lines()
+        .filter(|existing| !existing.trim_start().starts_with(ARCHIVE_NOTE_PREFIX))
+        .collect();
+    while lines.last().is_some_and(|last| last.trim().is_empty()) {
+        lines.pop();
+    }
+    lines.push(line);
+    Some(lines.join("\n"))
+}
+
+/// A connected bookmark service
+pub enum BookmarkService {
+    /// Pinboard
+    Pinboard(PinboardClient),
+    /// Raindrop.io
+    Raindrop(RaindropClient),
+}
+
+impl BookmarkService {
+    /// All bookmarks, or those with a tag
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the service cannot be reached or rejects the request
+    pub async fn list(&self, tag: Option<&str>) -> Result<Vec<Bookmark>> {
+        match self {
+            Self::Pinboard(client) => client.list(tag).await,
+            Self::Raindrop(client) => client.list(tag).await,
+        }
+    }
+
+    /// Record the local copies of a bookmark in its note, returning whether the note
+    /// changed
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the service cannot be reached or rejects the update
+    pub async fn note_archive(&self, bookmark: &Bookmark, paths: &[PathBuf]) -> Result<bool> {
+        let Some(note) = with_archive_note(&bookmark.note, &archive_note(paths)) else {
+            return Ok(false);
+        };
+        match (self, &bookmark.source) {
+            (Self::Pinboard(client), BookmarkSource::Pinboard { .. }) => {
+                client.update_note(bookmark, &note).await?
+            }
+            (Self::Raindrop(client), BookmarkSource::Raindrop { id }) => {
+                client.update_note(*id, &note).await?
+            }
+            _ => return Err(anyhow::anyhow!("Bookmark belongs to another service")),
+        }
+        Ok(true)
+    }
+}
+
+/// Client for the Pinboard API
+pub struct PinboardClient {
+    client: Client,
+    token: String,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct PinboardPost {
+    href: String,
+    description: String,
+    extended: String,
+    time: String,
+    shared: String,
+    toread: String,
+    tags: String,
+}
+
+impl PinboardClient {
+    /// Create a client authenticated with a Pinboard API token (`user:TOKEN`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is malformed or the HTTP client cannot be created
+    pub fn new(token: &str) -> Result<Self> {
+        if !token.trim().contains(':') {
+            return Err(anyhow::anyhow!(
+                "Pinboard API token must have the form user:TOKEN"
+            ));
+        }
+        Ok(Self {
+            client: http_client()?,
+            token: token.trim().to_string(),
+            endpoint: PINBOARD_API.to_string(),
+        })
+    }
+
+    /// All bookmarks, or those with a tag
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API cannot be reached or rejects the request
+    pub async fn list(&self, tag: Option<&str>) -> Result<Vec<Bookmark>> {
+        let mut query = vec![("auth_token", self.token.as_str()), ("format", "json")];
+        if let Some(tag) = tag {
+            query.push(("tag", tag));
+        }
+        let url = format!("{}/posts/all", self.endpoint);
+        let response = send_with_retry(|| self.client.get(&url).query(&query)).await?;
+        let posts: Vec<PinboardPost> = checked(response, "Pinboard list").await?.json().await?;
+        debug!("Listed {} Pinboard bookmarks", posts.len());
+        Ok(posts
+            .into_iter()
+            .map(|post| Bookmark {
+                url: post.href,
+                title: Some(post.description).filter(|title| !title.is_empty()),
+                tags: post.tags.split_whitespace().map(str::to_string).collect(),
+                note: post.extended,
+                source: BookmarkSource::Pinboard {
+                    time: post.time,
+                    shared: post.shared,
+                    toread: post.toread,
+                },
+            })
+            .collect())
+    }
+
+    /// Replace a bookmark's extended description, keeping its other fields
+    async fn update_note(&self, bookmark: &Bookmark, note: &str) -> Result<()> {
+        let BookmarkSource::Pinboard {
+            time,
+            shared,
+            toread,
+        } = &bookmark.source
+        else {
+            return Err(anyhow::anyhow!("Not a Pinboard bookmark"));
+        };
+        tokio::time::sleep(PINBOARD_INTERVAL).await;
+        let tags = bookmark.tags.join(" ");
+        let query = [
+            ("auth_token", self.token.as_str()),
+            ("format", "json"),
+            ("url", bookmark.url.as_str()),
+            (
+                "description",
+                bookmark.title.as_deref().unwrap_or(&bookmark.url),
+            ),
+            ("extended", note),
+            ("tags", tags.as_str()),
+            ("dt", time.as_str()),
+            ("shared", shared.as_str()),
+            ("toread", toread.as_str()),
+            ("replace", "yes"),
+        ];
+        let url = format!("{}/posts/add", self.endpoint);
+        let response = send_with_retry(|| self.client.get(&url).query(&query)).await?;
+        let result: serde_json::Value = checked(response, "Pinboard update").await?.json().await?;
+        match result["result_code"].as_str() {
+            Some("done") => Ok(()),
+            code => Err(anyhow::anyhow!(
+                "Pinboard update failed: {}",
+                code.unwrap_or("unexpected response")
+            )),
+        }
+    }
+}
+
+/// Client for the Raindrop.io API
+pub struct RaindropClient {
+    client: Client,
+    token: String,
+    endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct RaindropPage {
+    items: Vec<RaindropItem>,
+    #[serde(default)]
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct RaindropItem {
+    #[serde(rename = "_id")]
+    id: i64,
+    link: String,
+    title: Option<String>,
+    #[serde(default)]
+    note: String,
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl RaindropClient {
+    /// Create a client authenticated with a Raindrop access token (such as the test
+    /// token of an app created in the Raindrop integration settings)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the token is empty or the HTTP client cannot be created
+    pub fn new(token: &str) -> Result<Self> {
+        if token.trim().is_empty() {
+            return Err(anyhow::anyhow!("Raindrop access token must not be empty"));
+        }
+        Ok(Self {
+            client: http_client()?,
+            token: token.trim().to_string(),
+            endpoint: RAINDROP_API.to_string(),
+        })
+    }
+
+    /// All bookmarks in all collections, or those with a tag
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API cannot be reached or rejects the request
+    pub async fn list(&self, tag: Option<&str>) -> Result<Vec<Bookmark>> {
+        // Collection 0 holds every bookmark outside the trash
+        let url = format!("{}/raindrops/0", self.endpoint);
+        let search = tag.map(|tag| format!("#\"{}\"", tag.replace('"', "")));
+        let mut bookmarks = Vec::new();
+        let per_page = RAINDROP_PAGE_SIZE.to_string();
+        for page_number in 0.. {
+            let page_number = page_number.to_string();
+            let mut query = vec![
+                ("page", page_number.as_str()),
+                ("perpage", per_page.as_str()),
+            ];
+            if let Some(search) = &search {
+                query.push(("search", search));
+            }
+            let response =
+                send_with_retry(|| self.client.get(&url).bearer_auth(&self.token).query(&query))
+                    .await?;
+            let page: RaindropPage = checked(response, "Raindrop list").await?.json().await?;
+            let received = page.items.len();
+            bookmarks.extend(page.items.into_iter().map(|item| Bookmark {
+                url: item.link,
+                title: item.title.filter(|title| !title.is_empty()),
+                tags: item.tags,
+                note: item.note,
+                source: BookmarkSource::Raindrop { id: item.id },
+            }));
+            if received < RAINDROP_PAGE_SIZE || bookmarks.len() >= page.count {
+                break;
+            }
+        }
+        debug!("Listed {} Raindrop bookmarks", bookmarks.len());
+        Ok(bookmarks)
+    }
+
+    /// Replace a bookmark's note
+    async fn update_note(&self, id: i64, note: &str) -> Result<()> {
+        let url = format!("{}/raindrop/{}", self.endpoint, id);
+        let body = json!({ "note": note });
+        let response =
+            send_with_retry(|| self.client.put(&url).bearer_auth(&self.token).json(&body)).await?;
+        checked(response, "Raindrop update").await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_note() {
+        let line = "Archived copy: /data/a.pdf";
+        assert_eq!(
+            with_archive_note("Read later\n", line).as_deref(),
+            Some("Read later\nArchived copy: /data/a.pdf")
+        );
+        assert_eq!(with_archive_note("", line).as_deref(), Some(line));
+        // An up-to-date note is left alone, and an outdated line is replaced
+        assert_eq!(
+            with_archive_note("Read later\nArchived copy: /data/a.pdf", line),
+            None
+        );
+        assert_eq!(
+            with_archive_note("Archived copy: /old/a.pdf\nRead later", line).as_deref(),
+            Some("Read later\nArchived copy: /data/a.pdf")
+        );
+        assert_eq!(
+            archive_note(&[
+                PathBuf::from("/no/such/a.pdf"),
+                PathBuf::from("/no/such/a.md")
+            ]),
+            "Archived copy: /no/such/a.pdf, /no/such/a.md"
+        );
+    }
+}
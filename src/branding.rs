@@ -0,0 +1,238 @@
+//! Cover pages and page headers for branded PDF output
+//!
+//! This module renders user-supplied (or built-in) Tera templates into HTML for an
+//! optional cover page, which is prepended to the page before printing, and for the
+//! page header that Chrome draws on every PDF page. Templates can use the project
+//! name, logo, and capture metadata (URL, title, capture time), with the capture time
+//! written in the configured [`Language`].
+
+use crate::i18n::Language;
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::path::Path;
+use tera::{Context, Tera};
+
+const COVER_TEMPLATE: &str = "cover.html";
+const HEADER_TEMPLATE: &str = "header.html";
+
+/// Built-in cover page template
+pub const DEFAULT_COVER_TEMPLATE: &str = r#"<div style="page-break-after: always; height: 95vh; display: flex; flex-direction: column; justify-content: center; align-items: center; text-align: center; font-family: sans-serif;">
+  {% if logo %}<img src="{{ logo }}" style="max-width: 50%; max-height: 120px; margin-bottom: 2em;">{% endif %}
+  {% if project_name %}<div style="font-size: 14pt; color: #555; margin-bottom: 1em;">{{ project_name }}</div>{% endif %}
+  <h1 style="font-size: 24pt; margin: 0 0 0.5em;">{{ title }}</h1>
+  <div style="font-size: 10pt; word-break: break-all;">{{ url }}</div>
+  <div style="font-size: 10pt; color: #555; margin-top: 0.5em;">{{ captured }}</div>
+</div>"#;
+
+/// Built-in page header template
+///
+/// Chrome fills elements with the `pageNumber` and `totalPages` classes.
+pub const DEFAULT_HEADER_TEMPLATE: &str = r#"<div style="font-size: 8px; width: 100%; margin: 0 0.4in; display: flex; justify-content: space-between; color: #555; font-family: sans-serif;">
+  <span>{% if logo %}<img src="{{ logo }}" style="height: 10px; vertical-align: middle;"> {% endif %}{{ project_name }}</span>
+  <span>{{ title }}</span>
+  <span><span class="pageNumber"></span> / <span class="totalPages"></span></span>
+</div>"#;
+
+/// Metadata about a captured page, available to templates
+#[derive(Debug, Clone, Serialize)]
+pub struct CaptureMetadata {
+    /// The URL of the captured page
+    pub url: String,
+    /// The page title
+    pub title: String,
+    /// When the page was captured
+    pub captured_at: DateTime<Utc>,
+}
+
+impl CaptureMetadata {
+    /// Metadata for a page captured now
+    pub fn new(url: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            title: title.into(),
+            captured_at: Utc::now(),
+        }
+    }
+}
+
+/// Branding applied to generated PDFs: an optional cover page and page header
+#[derive(Debug, Clone, Default)]
+pub struct Branding {
+    tera: Tera,
+    project_name: Option<String>,
+    logo: Option<String>,
+    cover: bool,
+    header: bool,
+    language: Language,
+}
+
+impl Branding {
+    /// Create branding with neither a cover page nor a page header
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the project name shown on the cover page and in the header
+    pub fn with_project_name(mut self, project_name: impl Into<String>) -> Self {
+        self.project_name = Some(project_name.into());
+        self
+    }
+
+    /// Write the capture time in this language
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Set the logo image, embedded into the output as a data URI
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image cannot be read
+    pub fn with_logo(mut self, path: &Path) -> Result<Self> {
+        let data = std::fs::read(path)?;
+        self.logo = Some(format!(
+            "data:{};base64,{}",
+            image_mime_type(path),
+            base64::engine::general_purpose::STANDARD.encode(data)
+        ));
+        Ok(self)
+    }
+
+    /// Prepend a cover page, using the given template or the built-in one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed
+    pub fn with_cover(mut self, template: Option<&str>) -> Result<Self> {
+        self.tera
+            .add_raw_template(COVER_TEMPLATE, template.unwrap_or(DEFAULT_COVER_TEMPLATE))?;
+        self.cover = true;
+        Ok(self)
+    }
+
+    /// Print a header on every page, using the given template or the built-in one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed
+    pub fn with_header(mut self, template: Option<&str>) -> Result<Self> {
+        self.tera
+            .add_raw_template(HEADER_TEMPLATE, template.unwrap_or(DEFAULT_HEADER_TEMPLATE))?;
+        self.header = true;
+        Ok(self)
+    }
+
+    /// Render the cover page HTML, or None if no cover page is configured
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template fails to render
+    pub fn render_cover(&self, capture: &CaptureMetadata) -> Result<Option<String>> {
+        if !self.cover {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.tera.render(COVER_TEMPLATE, &self.context(capture))?,
+        ))
+    }
+
+    /// Render the page header HTML, or None if no page header is configured
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template fails to render
+    pub fn render_header(&self, capture: &CaptureMetadata) -> Result<Option<String>> {
+        if !self.header {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.tera.render(HEADER_TEMPLATE, &self.context(capture))?,
+        ))
+    }
+
+    fn context(&self, capture: &CaptureMetadata) -> Context {
+        let mut context = Context::new();
+        context.insert("project_name", &self.project_name.as_deref().unwrap_or(""));
+        context.insert("logo", &self.logo.as_deref().unwrap_or(""));
+        context.insert("url", &capture.url);
+        context.insert("title", &capture.title);
+        context.insert(
+            "captured_at",
+            &self.language.format_datetime(&capture.captured_at),
+        );
+        context.insert("captured", &self.language.captured(&capture.captured_at));
+        context.insert("lang", self.language.code());
+        context
+    }
+}
+
+fn image_mime_type(path: &Path) -> &'static str {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .as_deref()
+    {
+        Some("svg") => "image/svg+xml",
+        Some("jpg" | "jpeg") => "image/jpeg",
+        Some("gif") => "image/gif",
+        Some("webp") => "image/webp",
+        _ => "image/png",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn capture() -> CaptureMetadata {
+        CaptureMetadata::new("https://example.com/a?x=1&y=2", "Example <Title>")
+    }
+
+    #[test]
+    fn test_default_templates() -> Result<()> {
+        let branding = Branding::new()
+            .with_project_name("Acme Research")
+            .with_cover(None)?
+            .with_header(None)?;
+
+        let cover = branding.render_cover(&capture())?.unwrap();
+        assert!(cover.contains("Acme Research"));
+        assert!(cover.contains("Example &lt;Title&gt;"));
+        assert!(cover.contains("page-break-after"));
+
+        let header = branding.render_header(&capture())?.unwrap();
+        assert!(header.contains("pageNumber"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_template_and_logo() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let logo = temp_dir.path().join("logo.svg");
+        std::fs::write(&logo, "<svg></svg>")?;
+
+        let branding = Branding::new()
+            .with_logo(&logo)?
+            .with_cover(Some("<p>{{ project_name }}|{{ logo }}</p>"))?;
+        let cover = branding.render_cover(&capture())?.unwrap();
+        assert!(cover.starts_with("<p>|data:image&#x2F;svg+xml;base64,"));
+        assert!(branding.render_header(&capture())?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_template() {
+        assert!(Branding::new().with_cover(Some("{% if %}")).is_err());
+    }
+
+    #[test]
+    fn test_no_branding() -> Result<()> {
+        assert!(Branding::new().render_cover(&capture())?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+//! Compact Markdown briefs of search results
+//!
+//! In extract-only mode, `search-to-pdf` does not save whole pages. Each result is
+//! reduced to its title, the passages most relevant to the query, and its key facts
+//! (see [`crate::snippets`]), and the briefs of all results are combined into one
+//! document named after the query.
+
+use crate::markdown::MarkdownPage;
+use crate::snippets;
+
+/// Default number of key facts kept per brief
+pub const DEFAULT_MAX_FACTS: usize = 5;
+
+/// The brief of a page for a search query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Brief {
+    /// The page title
+    pub title: String,
+    /// The URL the page came from, if known
+    pub url: Option<String>,
+    /// A one-line summary, such as the search result's description
+    pub description: Option<String>,
+    /// The passages most relevant to the query, best first
+    pub passages: Vec<String>,
+    /// Sentences with figures or dates, those mentioning the query first
+    pub facts: Vec<String>,
+}
+
+impl Brief {
+    /// Extract the brief of a page, with up to `max_passages` passages and `max_facts`
+    /// key facts
+    ///
+    /// Facts that are already part of a passage are left out.
+    pub fn from_page(
+        page: &MarkdownPage,
+        query: &str,
+        max_passages: usize,
+        max_facts: usize,
+    ) -> Self {
+        let passages = snippets::relevant_passages([page.content.as_str()], query, max_passages);
+        let facts = snippets::key_facts([page.content.as_str()], query, max_facts + passages.len())
+            .into_iter()
+            .filter(|fact| {
+                !passages
+                    .iter()
+                    .any(|passage| passage.contains(fact.as_str()))
+            })
+            .take(max_facts)
+            .collect();
+        Self {
+            title: page.title.clone(),
+            url: page.url.clone(),
+            description: None,
+            passages,
+            facts,
+        }
+    }
+
+    /// Set the one-line summary shown under the title
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        let description = description.into();
+        self.description = Some(description.trim().to_string()).filter(|d| !d.is_empty());
+        self
+    }
+
+    /// Render the brief as a standalone Markdown document
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n", self.title);
+        if let Some(url) = &self.url {
+            markdown.push_str(&format!("*Source: [{}]({})*\n\n", url, url));
+        }
+        if let Some(description) = &self.description {
+            markdown.push_str(&format!("{}\n\n", description));
+        }
+        if !self.passages.is_empty() {
+            markdown.push_str("## Relevant passages\n\n");
+            for passage in &self.passages {
+                markdown.push_str(&format!("> {}\n\n", passage));
+            }
+        }
+        if !self.facts.is_empty() {
+            markdown.push_str("## Key facts\n\n");
+            for fact in &self.facts {
+                markdown.push_str(&format!("- {}\n", fact));
+            }
+            markdown.push('\n');
+        }
+        format!("{}\n", markdown.trim_end())
+    }
+}
+
+/// Combine the Markdown of briefs into one document titled with the query
+///
+/// The headings of each brief are demoted by one level, so that the briefs become
+/// sections of the combined document.
+pub fn combine<S: AsRef<str>>(query: &str, briefs: &[S]) -> String {
+    let sections: Vec<String> = briefs
+        .iter()
+        .map(|brief| {
+            brief
+                .as_ref()
+                .trim()
+                .lines()
+                .map(|line| match line.starts_with('#') {
+                    true => format!("#{}", line),
+                    false => line.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .collect();
+    format!("# {}\n\n{}\n", query, sections.join("\n\n---\n\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frontmatter::PageMetadata;
+
+    #[test]
+    fn test_brief() {
+        let page = MarkdownPage {
+            title: "Rust 2026".to_string(),
+            url: Some("https://example.com/rust".to_string()),
+            content: "Rust is a systems language. The borrow checker got 30% faster in 2026.\n\n\
+                      The compiler now ships 4 new lints for async code.\n\n\
+                      Unrelated text about gardening."
+                .to_string(),
+            metadata: PageMetadata::default(),
+        };
+        let brief = Brief::from_page(&page, "borrow checker", 3, 5)
+            .with_description("  What is new in Rust  ");
+        assert_eq!(
+            brief.passages,
+            vec!["Rust is a systems language. The borrow checker got 30% faster in 2026."]
+        );
+        assert_eq!(
+            brief.facts,
+            vec!["The compiler now ships 4 new lints for async code."]
+        );
+
+        let markdown = brief.to_markdown();
+        assert_eq!(
+            markdown,
+            "# Rust 2026\n\n*Source: [https://example.com/rust](https://example.com/rust)*\n\n\
+             What is new in Rust\n\n## Relevant passages\n\n\
+             > Rust is a systems language. The borrow checker got 30% faster in 2026.\n\n\
+             ## Key facts\n\n- The compiler now ships 4 new lints for async code.\n"
+        );
+
+        let combined = combine("borrow checker", &[markdown.as_str(), "# Other\n\nText\n"]);
+        assert!(combined.starts_with("# borrow checker\n\n## Rust 2026\n"));
+        assert!(combined.contains("### Key facts"));
+        assert!(combined.ends_with("---\n\n## Other\n\nText\n"));
+    }
+}
@@ -0,0 +1,340 @@
+//! Portable bundles for moving capture archives between machines
+//!
+//! A bundle is a zstd-compressed tar file holding the Markdown and PDF files of every
+//! capture in an archive, followed by a JSON manifest with each capture's metadata and
+//! the SHA-256 digest and size of each of its files. Imports verify every file against
+//! the manifest before anything is written to the destination archive.
+
+use crate::archive::{self, Archive, Capture, PdfBlob};
+use crate::atomic;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::{Component, Path, PathBuf};
+
+/// Format identifier written to bundle manifests
+pub const BUNDLE_FORMAT: &str = "webpage-save-archive";
+
+/// Current bundle manifest version
+pub const BUNDLE_VERSION: u32 = 1;
+
+const MANIFEST_PATH: &str = "manifest.json";
+
+/// Contents of a bundle's manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    /// Always `BUNDLE_FORMAT`
+    pub format: String,
+    /// Manifest version
+    pub version: u32,
+    /// When the bundle was created
+    pub exported_at: DateTime<Utc>,
+    /// The bundled captures, oldest first
+    pub captures: Vec<BundleCapture>,
+}
+
+/// Metadata of a bundled capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleCapture {
+    /// The URL (or local path) the page was captured from
+    pub url: String,
+    /// The page title, if known
+    pub title: Option<String>,
+    /// When the page was captured
+    pub captured_at: DateTime<Utc>,
+    /// The capture's Markdown file
+    pub markdown: Option<BundleFile>,
+    /// The capture's PDF file
+    pub pdf: Option<BundleFile>,
+}
+
+/// A file in a bundle with its integrity information
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleFile {
+    /// Path of the file within the bundle
+    pub path: String,
+    /// Hex-encoded SHA-256 digest of the file
+    pub sha256: String,
+    /// Size of the file in bytes
+    pub size: u64,
+}
+
+impl BundleFile {
+    fn new(path: String, data: &[u8]) -> Self {
+        Self {
+            path,
+            sha256: format!("{:x}", Sha256::digest(data)),
+            size: data.len() as u64,
+        }
+    }
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Captures added to the archive
+    pub imported: usize,
+    /// Captures skipped because the archive already contained them
+    pub skipped: usize,
+}
+
+/// Write every capture in the archive to a bundle, returning the number of captures
+///
+/// PDFs stored as paths are read from disk and included in the bundle.
+///
+/// # Errors
+///
+/// Returns an error if the archive or a PDF file cannot be read, or the bundle cannot be written
+pub fn export_bundle(archive: &Archive, path: &Path) -> Result<usize> {
+    let encoder = zstd::Encoder::new(atomic::temp_file_for(path)?, 0)?;
+    let mut builder = tar::Builder::new(encoder);
+    let mut manifest = BundleManifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        exported_at: Utc::now(),
+        captures: Vec::new(),
+    };
+
+    for (index, id) in archive.ids()?.into_iter().enumerate() {
+        let Some(capture) = archive.get(id)? else {
+            continue;
+        };
+        let dir = format!("captures/{:06}", index + 1);
+        let mtime = capture.captured_at.timestamp().max(0) as u64;
+
+        let markdown = match &capture.markdown {
+            Some(markdown) => {
+                let file = BundleFile::new(format!("{}/page.md", dir), markdown.as_bytes());
+                append_file(&mut builder, &file.path, markdown.as_bytes(), mtime)?;
+                Some(file)
+            }
+            None => None,
+        };
+        let pdf = match &capture.pdf {
+            Some(blob) => {
+                let data = match blob {
+                    PdfBlob::Inline(data) => data.clone(),
+                    PdfBlob::Path(pdf_path) => std::fs::read(pdf_path).map_err(|e| {
+                        anyhow::anyhow!(
+                            "Failed to read PDF {} of capture #{}: {}",
+                            pdf_path.display(),
+                            id,
+                            e
+                        )
+                    })?,
+                };
+                let file = BundleFile::new(format!("{}/page.pdf", dir), &data);
+                append_file(&mut builder, &file.path, &data, mtime)?;
+                Some(file)
+            }
+            None => None,
+        };
+
+        manifest.captures.push(BundleCapture {
+            url: capture.url,
+            title: capture.title,
+            captured_at: capture.captured_at,
+            markdown,
+            pdf,
+        });
+    }
+
+    let manifest_json = serde_json::to_vec_pretty(&manifest)?;
+    append_file(
+        &mut builder,
+        MANIFEST_PATH,
+        &manifest_json,
+        manifest.exported_at.timestamp().max(0) as u64,
+    )?;
+    atomic::persist(builder.into_inner()?.finish()?, path)?;
+
+    Ok(manifest.captures.len())
+}
+
+/// Verify a bundle and add its captures to the archive
+///
+/// Captures already in the archive (same URL and capture time) are skipped, so importing
+/// a bundle twice is harmless. PDFs are stored inline unless `pdf_dir` is given.
+///
+/// # Errors
+///
+/// Returns an error if the bundle cannot be read, its manifest is missing or unsupported,
+/// or any file is missing or does not match its size and checksum. Nothing is imported
+/// in that case.
+pub fn import_bundle(
+    archive: &mut Archive,
+    path: &Path,
+    pdf_dir: Option<&Path>,
+) -> Result<ImportSummary> {
+    let unpacked = tempfile::TempDir::new()?;
+    tar::Archive::new(zstd::Decoder::new(File::open(path)?)?).unpack(unpacked.path())?;
+
+    let manifest_path = unpacked.path().join(MANIFEST_PATH);
+    if !manifest_path.exists() {
+        return Err(anyhow::anyhow!(
+            "{} is not an archive bundle (no manifest)",
+            path.display()
+        ));
+    }
+    let manifest: BundleManifest = serde_json::from_slice(&std::fs::read(manifest_path)?)?;
+    if manifest.format != BUNDLE_FORMAT || manifest.version > BUNDLE_VERSION {
+        return Err(anyhow::anyhow!(
+            "Unsupported bundle format {} version {}",
+            manifest.format,
+            manifest.version
+        ));
+    }
+
+    // Verify everything before touching the archive
+    for capture in &manifest.captures {
+        for file in capture.markdown.iter().chain(capture.pdf.iter()) {
+            read_verified(unpacked.path(), file)?;
+        }
+    }
+
+    let mut summary = ImportSummary::default();
+    for bundled in &manifest.captures {
+        if archive.contains(&bundled.url, &bundled.captured_at)? {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let mut capture = Capture::new(bundled.url.clone());
+        capture.title = bundled.title.clone();
+        capture.captured_at = bundled.captured_at;
+        if let Some(file) = &bundled.markdown {
+            capture.markdown = Some(String::from_utf8(read_verified(unpacked.path(), file)?)?);
+        }
+        if let Some(file) = &bundled.pdf {
+            let data = read_verified(unpacked.path(), file)?;
+            capture.pdf = Some(match pdf_dir {
+                Some(dir) => PdfBlob::Path(archive::write_pdf_file(dir, &data)?),
+                None => PdfBlob::Inline(data),
+            });
+        }
+        archive.insert(&capture)?;
+        summary.imported += 1;
+    }
+
+    Ok(summary)
+}
+
+fn append_file<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    path: &str,
+    data: &[u8],
+    mtime: u64,
+) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(mtime);
+    builder.append_data(&mut header, path, data)?;
+    Ok(())
+}
+
+/// Read a bundled file, checking its size and checksum against the manifest
+fn read_verified(root: &Path, file: &BundleFile) -> Result<Vec<u8>> {
+    let relative = PathBuf::from(&file.path);
+    if !relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(anyhow::anyhow!("Invalid path in bundle: {}", file.path));
+    }
+
+    let data = std::fs::read(root.join(&relative))
+        .map_err(|_| anyhow::anyhow!("Bundle is missing {}", file.path))?;
+    if data.len() as u64 != file.size || format!("{:x}", Sha256::digest(&data)) != file.sha256 {
+        return Err(anyhow::anyhow!(
+            "Bundle file {} does not match its checksum",
+            file.path
+        ));
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample_archive(path: &Path, pdf_dir: &Path) -> Result<Archive> {
+        let mut archive = Archive::open(path)?;
+        archive.insert(
+            &Capture::new("https://a.example")
+                .with_title("Alpha")
+                .with_markdown("# Alpha\n\nFirst page")
+                .with_pdf(PdfBlob::Inline(b"%PDF-alpha".to_vec())),
+        )?;
+        archive.insert(&Capture::new("https://b.example").with_pdf(PdfBlob::Path(
+            archive::write_pdf_file(pdf_dir, b"%PDF-beta")?,
+        )))?;
+        Ok(archive)
+    }
+
+    #[test]
+    fn test_export_import_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let source = sample_archive(
+            &temp_dir.path().join("source.db"),
+            &temp_dir.path().join("pdfs"),
+        )?;
+        let bundle = temp_dir.path().join("bundle.tar.zst");
+        assert_eq!(export_bundle(&source, &bundle)?, 2);
+
+        let mut target = Archive::open(&temp_dir.path().join("target.db"))?;
+        let summary = import_bundle(&mut target, &bundle, None)?;
+        assert_eq!(
+            summary,
+            ImportSummary {
+                imported: 2,
+                skipped: 0
+            }
+        );
+
+        for (source_id, target_id) in source.ids()?.into_iter().zip(target.ids()?) {
+            let original = source.get(source_id)?.unwrap();
+            let imported = target.get(target_id)?.unwrap();
+            assert_eq!(imported.url, original.url);
+            assert_eq!(imported.title, original.title);
+            assert_eq!(imported.captured_at, original.captured_at);
+            assert_eq!(imported.markdown, original.markdown);
+        }
+        let beta = target.get(target.ids()?[1])?.unwrap();
+        assert_eq!(beta.pdf, Some(PdfBlob::Inline(b"%PDF-beta".to_vec())));
+
+        // Importing again skips existing captures
+        let summary = import_bundle(&mut target, &bundle, None)?;
+        assert_eq!(summary.skipped, 2);
+        assert_eq!(target.ids()?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_rejects_tampered_bundle() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let source = sample_archive(
+            &temp_dir.path().join("source.db"),
+            &temp_dir.path().join("pdfs"),
+        )?;
+        let bundle = temp_dir.path().join("bundle.tar.zst");
+        export_bundle(&source, &bundle)?;
+
+        // Rewrite the bundle with one file's content altered
+        let unpacked = temp_dir.path().join("unpacked");
+        tar::Archive::new(zstd::Decoder::new(File::open(&bundle)?)?).unpack(&unpacked)?;
+        std::fs::write(unpacked.join("captures/000001/page.md"), "# Tampered")?;
+        let mut builder = tar::Builder::new(zstd::Encoder::new(File::create(&bundle)?, 0)?);
+        builder.append_dir_all(".", &unpacked)?;
+        builder.into_inner()?.finish()?;
+
+        let mut target = Archive::open(&temp_dir.path().join("target.db"))?;
+        let error = import_bundle(&mut target, &bundle, None).unwrap_err();
+        assert!(error.to_string().contains("checksum"));
+        assert!(target.ids()?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,265 @@
+//! On-disk caching of search responses and page snapshots
+//!
+//! This module stores Brave search responses on disk keyed by search type, query,
+//! and search parameters, so repeated runs within the TTL do not consume API quota.
+//! It also keeps HTML snapshots of fetched pages, which offline mode converts from
+//! without touching the network.
+
+use crate::atomic;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+
+/// Default time-to-live for cached search responses
+pub const DEFAULT_SEARCH_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A cached search response as stored on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    /// Unix timestamp (seconds) when the entry was written
+    created_at: u64,
+    /// The cached response body
+    response: String,
+}
+
+/// On-disk cache for search responses with a fixed TTL
+#[derive(Debug, Clone)]
+pub struct SearchCache {
+    dir: PathBuf,
+    ttl: Duration,
+}
+
+impl SearchCache {
+    /// Create a cache storing entries in `dir`
+    pub fn new(dir: impl Into<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl,
+        }
+    }
+
+    /// Create a cache in the platform cache directory (e.g. `~/.cache/webpage-save/search`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform has no cache directory
+    pub fn in_default_dir(ttl: Duration) -> Result<Self> {
+        let dir = default_cache_dir()?.join("search");
+        Ok(Self::new(dir, ttl))
+    }
+
+    /// The directory holding cache entries
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Build a stable cache key from the parts identifying a request
+    ///
+    /// The parts are serialized to JSON and hashed, so any serializable value can be used.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the parts cannot be serialized
+    pub fn key<T: Serialize>(parts: &T) -> Result<String> {
+        let serialized = serde_json::to_vec(parts)?;
+        Ok(format!("{:x}", Sha256::digest(&serialized)))
+    }
+
+    /// Look up a cached response, ignoring entries older than the TTL
+    ///
+    /// Missing, expired, or unreadable entries are all treated as a cache miss.
+    pub async fn get(&self, key: &str) -> Option<String> {
+        let entry = self.read_entry(key).await?;
+
+        let age = unix_now().saturating_sub(entry.created_at);
+        if age > self.ttl.as_secs() {
+            return None;
+        }
+
+        Some(entry.response)
+    }
+
+    /// Look up a cached response regardless of its age
+    ///
+    /// Used in offline mode, where a stale response is better than none.
+    pub async fn get_stale(&self, key: &str) -> Option<String> {
+        self.read_entry(key).await.map(|entry| entry.response)
+    }
+
+    /// Store a response in the cache
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory or entry cannot be written
+    pub async fn put(&self, key: &str, response: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+
+        let entry = CacheEntry {
+            created_at: unix_now(),
+            response: response.to_string(),
+        };
+        atomic::write(&self.entry_path(key), serde_json::to_vec(&entry)?).await?;
+        Ok(())
+    }
+
+    async fn read_entry(&self, key: &str) -> Option<CacheEntry> {
+        let data = fs::read(self.entry_path(key)).await.ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+/// On-disk store of HTML snapshots for fetched pages, keyed by URL
+#[derive(Debug, Clone)]
+pub struct PageCache {
+    dir: PathBuf,
+}
+
+impl PageCache {
+    /// Create a page cache storing snapshots in `dir`
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Create a page cache in the platform cache directory (e.g. `~/.cache/webpage-save/pages`)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform has no cache directory
+    pub fn in_default_dir() -> Result<Self> {
+        Ok(Self::new(default_cache_dir()?.join("pages")))
+    }
+
+    /// The directory holding page snapshots
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Look up the snapshot for a URL
+    pub async fn get(&self, url: &str) -> Option<String> {
+        fs::read_to_string(self.snapshot_path(url).ok()?).await.ok()
+    }
+
+    /// Look up the snapshot for a URL, failing clearly when there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no snapshot of the URL has been saved
+    pub async fn require(&self, url: &str) -> Result<String> {
+        self.get(url).await.ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} is not in the page cache; fetch it once without --offline first",
+                url
+            )
+        })
+    }
+
+    /// Save the snapshot for a URL, replacing any previous one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the snapshot cannot be written
+    pub async fn put(&self, url: &str, html: &str) -> Result<()> {
+        fs::create_dir_all(&self.dir).await?;
+        atomic::write(&self.snapshot_path(url)?, html).await?;
+        Ok(())
+    }
+
+    fn snapshot_path(&self, url: &str) -> Result<PathBuf> {
+        Ok(self.dir.join(format!("{}.html", SearchCache::key(&url)?)))
+    }
+}
+
+/// The platform cache directory for webpage-save
+///
+/// # Errors
+///
+/// Returns an error if the platform has no cache directory
+pub fn default_cache_dir() -> Result<PathBuf> {
+    dirs::cache_dir()
+        .map(|dir| dir.join("webpage-save"))
+        .ok_or_else(|| anyhow::anyhow!("Could not determine the platform cache directory"))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_cache_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = SearchCache::new(temp_dir.path(), Duration::from_secs(60));
+        let key = SearchCache::key(&("web", "rust"))?;
+
+        assert!(cache.get(&key).await.is_none());
+        cache.put(&key, "results").await?;
+        assert_eq!(cache.get(&key).await.as_deref(), Some("results"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_expired_entry() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = SearchCache::new(temp_dir.path(), Duration::from_secs(60));
+        let key = SearchCache::key(&"query")?;
+
+        let stale = CacheEntry {
+            created_at: unix_now() - 120,
+            response: "old".to_string(),
+        };
+        std::fs::write(cache.entry_path(&key), serde_json::to_vec(&stale)?)?;
+        assert!(cache.get(&key).await.is_none());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cache_get_stale_ignores_ttl() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = SearchCache::new(temp_dir.path(), Duration::ZERO);
+        let key = SearchCache::key(&"query")?;
+
+        let stale = CacheEntry {
+            created_at: unix_now() - 120,
+            response: "old".to_string(),
+        };
+        std::fs::write(cache.entry_path(&key), serde_json::to_vec(&stale)?)?;
+        assert!(cache.get(&key).await.is_none());
+        assert_eq!(cache.get_stale(&key).await.as_deref(), Some("old"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_page_cache_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let cache = PageCache::new(temp_dir.path());
+
+        assert!(cache.require("https://example.com").await.is_err());
+        cache.put("https://example.com", "<html></html>").await?;
+        assert_eq!(cache.require("https://example.com").await?, "<html></html>");
+        assert!(cache.get("https://example.org").await.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_parts() -> Result<()> {
+        let a = SearchCache::key(&("web", "rust", Some(5)))?;
+        let b = SearchCache::key(&("web", "rust", Some(10)))?;
+        assert_ne!(a, b);
+        assert_eq!(a, SearchCache::key(&("web", "rust", Some(5)))?);
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+//! Monitoring of changelogs and release notes
+//!
+//! A changelog or release notes page lists its releases under headings naming their
+//! versions (`## [1.4.0] - 2026-03-02`, `v1.4.0`, `Release 1.4`). Checking such a page
+//! splits its Markdown into one [`Release`] per version heading, so that the releases
+//! not seen at an earlier check can be saved as a note of their own and sent to a
+//! webhook or by email, instead of the whole page.
+
+use crate::delivery::{check_address, sendmail};
+use crate::readlater::{checked, http_client};
+use anyhow::Result;
+use base64::Engine;
+use regex::Regex;
+use reqwest::Client;
+use serde_json::json;
+use std::sync::LazyLock;
+use tracing::info;
+
+/// A version number in a heading, e.g. `1.4.0`, `v2.0.0-rc.1`, or `2026.3`
+static VERSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[vV]?(\d+(?:\.\d+)+(?:-[0-9A-Za-z]+(?:\.[0-9A-Za-z]+)*)?)\b").unwrap()
+});
+
+/// One release of a changelog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// The version, without a `v` prefix
+    pub version: String,
+    /// The Markdown of the release, starting with its heading
+    pub content: String,
+}
+
+/// The releases of a changelog in Markdown, in page order
+///
+/// A release runs from a heading with a version number to the next heading of the same
+/// or a higher level, or the next version heading, so the `### Added` and `### Fixed`
+/// subsections of a release stay with it. Headings in code blocks are ignored.
+pub fn releases(markdown: &str) -> Vec<Release> {
+    let mut releases: Vec<Release> = Vec::new();
+    // The level of the open release's heading
+    let mut open: Option<usize> = None;
+    let mut in_code = false;
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+        }
+        let heading = (!in_code).then(|| heading(line)).flatten();
+        if let Some((level, text)) = heading {
+            if let Some(version) = VERSION
+                .captures(text)
+                .map(|captures| captures[1].to_string())
+            {
+                if !releases.iter().any(|release| release.version == version) {
+                    releases.push(Release {
+                        version,
+                        content: String::new(),
+                    });
+                    open = Some(level);
+                } else {
+                    open = None;
+                }
+            } else if open.is_some_and(|open| level <= open) {
+                open = None;
+            }
+        }
+        if let (Some(_), Some(release)) = (open, releases.last_mut()) {
+            release.content.push_str(line);
+            release.content.push('\n');
+        }
+    }
+    for release in &mut releases {
+        release.content = release.content.trim_end().to_string();
+    }
+    releases
+}
+
+/// The level and text of an ATX heading line
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+    (1..=6)
+        .contains(&level)
+        .then(|| (level, text.trim().trim_end_matches('#').trim()))
+}
+
+/// The releases of a changelog that appeared since it was last checked
+///
+/// `known` are the versions seen at earlier checks.
+pub fn new_releases(releases: Vec<Release>, known: &[String]) -> Vec<Release> {
+    releases
+        .into_iter()
+        .filter(|release| !known.contains(&release.version))
+        .collect()
+}
+
+/// A note of the releases new on a changelog page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseNote {
+    /// The changelog page title
+    pub title: String,
+    /// The changelog page URL
+    pub url: String,
+    /// The new releases, newest first as on most changelogs
+    pub releases: Vec<Release>,
+}
+
+impl ReleaseNote {
+    /// The new versions, e.g. `1.4.0, 1.3.2`
+    pub fn versions(&self) -> String {
+        self.releases
+            .iter()
+            .map(|release| release.version.as_str())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// A one-line summary, e.g. `New release of Tokio changelog: 1.4.0`
+    pub fn summary(&self) -> String {
+        let releases = match self.releases.len() {
+            1 => "release",
+            _ => "releases",
+        };
+        format!("New {} of {}: {}", releases, self.title, self.versions())
+    }
+
+    /// The note as a standalone Markdown document
+    pub fn to_markdown(&self) -> String {
+        let releases: Vec<&str> = self
+            .releases
+            .iter()
+            .map(|release| release.content.as_str())
+            .collect();
+        format!(
+            "# {}\n\n*Source: [{}]({})*\n\n---\n\n{}\n",
+            self.summary(),
+            self.url,
+            self.url,
+            releases.join("\n\n")
+        )
+    }
+}
+
+/// Where notes of new releases are sent
+pub struct ReleaseNotifier {
+    client: Client,
+    webhook: Option<String>,
+    email: Option<ReleaseMail>,
+}
+
+struct ReleaseMail {
+    to: String,
+    from: String,
+    sendmail: String,
+}
+
+impl ReleaseNotifier {
+    /// Create a notifier that sends notes nowhere
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            webhook: None,
+            email: None,
+        })
+    }
+
+    /// POST every note as JSON to a webhook URL
+    ///
+    /// The JSON has the page `url` and `title`, the new `versions`, the note as
+    /// `markdown`, and a one-line summary as `text`, which chat services such as Slack
+    /// post as the message.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not an HTTP or HTTPS URL
+    pub fn with_webhook(mut self, url: &str) -> Result<Self> {
+        let parsed = url::Url::parse(url)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(anyhow::anyhow!("Webhook URL must be an HTTP or HTTPS URL"));
+        }
+        self.webhook = Some(url.to_string());
+        Ok(self)
+    }
+
+    /// Email every note, using a sendmail-compatible command
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either address is not a valid email address
+    pub fn with_email(mut self, to: &str, from: &str, sendmail: &str) -> Result<Self> {
+        check_address(to)?;
+        check_address(from)?;
+        self.email = Some(ReleaseMail {
+            to: to.to_string(),
+            from: from.to_string(),
+            sendmail: sendmail.to_string(),
+        });
+        Ok(self)
+    }
+
+    /// Whether notes are sent anywhere
+    pub fn is_empty(&self) -> bool {
+        self.webhook.is_none() && self.email.is_none()
+    }
+
+    /// Send a note to every configured destination
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the webhook request or the email fails
+    pub async fn notify(&self, note: &ReleaseNote) -> Result<()> {
+        if let Some(webhook) = &self.webhook {
+            let versions: Vec<&str> = note
+                .releases
+                .iter()
+                .map(|release| release.version.as_str())
+                .collect();
+            let payload = json!({
+                "url": note.url,
+                "title": note.title,
+                "versions": versions,
+                "markdown": note.to_markdown(),
+                "text": format!("{} {}", note.summary(), note.url),
+            });
+            let response = self.client.post(webhook).json(&payload).send().await?;
+            checked(response, "Webhook notification").await?;
+            info!("Notified {} of {}", webhook, note.versions());
+        }
+        if let Some(email) = &self.email {
+            let message = release_message(&email.to, &email.from, note);
+            let command = email.sendmail.clone();
+            tokio::task::spawn_blocking(move || sendmail(&command, &message)).await??;
+            info!("Emailed {} to {}", note.versions(), email.to);
+        }
+        Ok(())
+    }
+}
+
+/// A plain-text email of a note
+fn release_message(to: &str, from: &str, note: &ReleaseNote) -> String {
+    let summary = note.summary().replace(['\r', '\n'], " ");
+    let subject = match summary.is_ascii() {
+        true => summary,
+        false => format!(
+            "=?UTF-8?B?{}?=",
+            base64::engine::general_purpose::STANDARD.encode(summary)
+        ),
+    };
+    format!(
+        "From: {from}\nTo: {to}\nSubject: {subject}\nMIME-Version: 1.0\n\
+         Content-Type: text/plain; charset=utf-8\nContent-Transfer-Encoding: 8bit\n\n{}",
+        note.to_markdown()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_releases_note() {
+        let markdown = "# Changelog\n\nAll notable changes.\n\n## [Unreleased]\n\n- Work in progress\n\n\
+                        ## [1.4.0] - 2026-03-02\n\n### Added\n\n- `--watch`\n\n```\n# 2.0 is not a heading\n```\n\n\
+                        ## v1.3.2\n\n- Fixed a crash\n\n## License\n\nMIT\n";
+        let releases = releases(markdown);
+        assert_eq!(
+            releases
+                .iter()
+                .map(|release| release.version.as_str())
+                .collect::<Vec<_>>(),
+            vec!["1.4.0", "1.3.2"]
+        );
+        assert!(releases[0].content.starts_with("## [1.4.0] - 2026-03-02\n"));
+        assert!(releases[0].content.contains("### Added"));
+        assert!(releases[0].content.ends_with("```"));
+        assert_eq!(releases[1].content, "## v1.3.2\n\n- Fixed a crash");
+
+        let new = new_releases(releases, &["1.3.2".to_string()]);
+        let note = ReleaseNote {
+            title: "Changelog".to_string(),
+            url: "https://example.com/changelog".to_string(),
+            releases: new,
+        };
+        assert_eq!(note.summary(), "New release of Changelog: 1.4.0");
+        let document = note.to_markdown();
+        assert!(document.starts_with("# New release of Changelog: 1.4.0\n"));
+        assert!(!document.contains("Fixed a crash"));
+
+        let message = release_message("me@example.com", "bot@example.com", &note);
+        assert!(message.contains("\nSubject: New release of Changelog: 1.4.0\n"));
+    }
+}
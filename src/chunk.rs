@@ -0,0 +1,327 @@
+//! Splitting Markdown into overlapping chunks for LLM context windows
+//!
+//! This module splits converted Markdown into chunks of a bounded size, preferring
+//! paragraph boundaries, and writes them as numbered files with a metadata header so
+//! they can be fed directly into embedding and RAG ingestion pipelines.
+
+use crate::atomic;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+/// Approximate number of characters per token, used to size token-based chunks
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Unit in which chunk sizes are measured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkUnit {
+    /// Tokens, approximated as `CHARS_PER_TOKEN` characters each
+    Tokens,
+    /// Unicode characters
+    Chars,
+}
+
+impl ChunkUnit {
+    /// The size of a text in this unit
+    pub fn measure(self, text: &str) -> usize {
+        let chars = text.chars().count();
+        match self {
+            ChunkUnit::Tokens => chars.div_ceil(CHARS_PER_TOKEN),
+            ChunkUnit::Chars => chars,
+        }
+    }
+
+    /// The name of the unit as used in chunk metadata
+    pub fn name(self) -> &'static str {
+        match self {
+            ChunkUnit::Tokens => "tokens",
+            ChunkUnit::Chars => "chars",
+        }
+    }
+
+    fn chars_per_unit(self) -> usize {
+        match self {
+            ChunkUnit::Tokens => CHARS_PER_TOKEN,
+            ChunkUnit::Chars => 1,
+        }
+    }
+}
+
+/// Configuration for chunking Markdown output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkConfig {
+    /// Maximum size of a chunk
+    pub size: usize,
+    /// Amount of text repeated from the end of one chunk at the start of the next
+    pub overlap: usize,
+    /// Unit of `size` and `overlap`
+    pub unit: ChunkUnit,
+}
+
+impl ChunkConfig {
+    /// Create a chunk configuration without overlap
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size is zero
+    pub fn new(size: usize, unit: ChunkUnit) -> Result<Self> {
+        if size == 0 {
+            return Err(anyhow::anyhow!("Chunk size must be greater than zero"));
+        }
+        Ok(Self {
+            size,
+            overlap: 0,
+            unit,
+        })
+    }
+
+    /// Repeat up to `overlap` units from the end of each chunk at the start of the next
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the overlap is not smaller than the chunk size
+    pub fn with_overlap(mut self, overlap: usize) -> Result<Self> {
+        if overlap >= self.size {
+            return Err(anyhow::anyhow!(
+                "Chunk overlap ({}) must be smaller than the chunk size ({})",
+                overlap,
+                self.size
+            ));
+        }
+        self.overlap = overlap;
+        Ok(self)
+    }
+}
+
+/// A piece of text that is never split further, with the separator that precedes it
+struct Piece<'a> {
+    text: &'a str,
+    separator: &'static str,
+}
+
+/// Split Markdown into chunks no larger than the configured size
+///
+/// Chunks break at paragraph boundaries where possible, then at word boundaries,
+/// and only split words that are themselves larger than a chunk.
+pub fn split_markdown(markdown: &str, config: &ChunkConfig) -> Vec<String> {
+    let max_chars = config.size * config.unit.chars_per_unit();
+    let overlap_chars = config.overlap * config.unit.chars_per_unit();
+    let pieces = split_pieces(markdown, max_chars);
+
+    let mut chunks = Vec::new();
+    let mut current: Vec<&Piece> = Vec::new();
+    for piece in &pieces {
+        if !current.is_empty() && joined_len(&current) + joined_piece_len(piece) > max_chars {
+            chunks.push(join(&current));
+
+            // Carry trailing pieces over as overlap, as long as the new piece still fits
+            let mut carried = Vec::new();
+            for &previous in current.iter().rev() {
+                let mut candidate = vec![previous];
+                candidate.extend(carried.iter().copied());
+                if joined_len(&candidate) > overlap_chars
+                    || joined_len(&candidate) + joined_piece_len(piece) > max_chars
+                {
+                    break;
+                }
+                carried = candidate;
+            }
+            current = carried;
+        }
+        current.push(piece);
+    }
+    if !current.is_empty() {
+        chunks.push(join(&current));
+    }
+
+    chunks
+}
+
+/// Split Markdown and write each chunk as a numbered file with a metadata header
+///
+/// Chunks of `output.md` are written as `output_001.md`, `output_002.md`, and so on.
+///
+/// # Errors
+///
+/// Returns an error if a chunk file cannot be written
+pub async fn write_chunks(
+    output_path: &Path,
+    markdown: &str,
+    title: Option<&str>,
+    source: Option<&str>,
+    config: &ChunkConfig,
+) -> Result<Vec<PathBuf>> {
+    let chunks = split_markdown(markdown, config);
+    let mut paths = Vec::with_capacity(chunks.len());
+    for (index, chunk) in chunks.iter().enumerate() {
+        // YAML front matter; strings are written as JSON, which is valid YAML
+        let mut content = String::from("---\n");
+        if let Some(title) = title {
+            content.push_str(&format!("title: {}\n", serde_json::to_string(title)?));
+        }
+        if let Some(source) = source {
+            content.push_str(&format!("source: {}\n", serde_json::to_string(source)?));
+        }
+        content.push_str(&format!(
+            "chunk: {}\nchunks: {}\nunit: {}\nsize: {}\n---\n\n{}\n",
+            index + 1,
+            chunks.len(),
+            config.unit.name(),
+            config.unit.measure(chunk),
+            chunk
+        ));
+
+        let path = chunk_path(output_path, index + 1);
+        atomic::write(&path, content).await?;
+        paths.push(path);
+    }
+
+    Ok(paths)
+}
+
+/// The path of the numbered chunk file (starting at 1) derived from a Markdown file path
+pub fn chunk_path(output_path: &Path, number: usize) -> PathBuf {
+    let stem = output_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "chunk".to_string());
+    output_path.with_file_name(format!("{}_{:03}.md", stem, number))
+}
+
+/// Break Markdown into paragraphs, splitting oversized paragraphs at word boundaries
+fn split_pieces(markdown: &str, max_chars: usize) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    for paragraph in markdown
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|paragraph| !paragraph.is_empty())
+    {
+        if paragraph.chars().count() <= max_chars {
+            pieces.push(Piece {
+                text: paragraph,
+                separator: "\n\n",
+            });
+            continue;
+        }
+
+        let mut separator = "\n\n";
+        for word in paragraph.split_whitespace() {
+            for text in split_long_word(word, max_chars) {
+                pieces.push(Piece { text, separator });
+                separator = " ";
+            }
+        }
+    }
+    pieces
+}
+
+/// Split a word into slices of at most `max_chars` characters
+fn split_long_word(word: &str, max_chars: usize) -> Vec<&str> {
+    let mut slices = Vec::new();
+    let mut start = 0;
+    let mut count = 0;
+    for (offset, _) in word.char_indices() {
+        if count == max_chars {
+            slices.push(&word[start..offset]);
+            start = offset;
+            count = 0;
+        }
+        count += 1;
+    }
+    slices.push(&word[start..]);
+    slices
+}
+
+fn joined_piece_len(piece: &Piece) -> usize {
+    piece.separator.len() + piece.text.chars().count()
+}
+
+fn joined_len(pieces: &[&Piece]) -> usize {
+    let total: usize = pieces.iter().map(|piece| joined_piece_len(piece)).sum();
+    total.saturating_sub(pieces.first().map_or(0, |piece| piece.separator.len()))
+}
+
+fn join(pieces: &[&Piece]) -> String {
+    let mut joined = String::new();
+    for (index, piece) in pieces.iter().enumerate() {
+        if index > 0 {
+            joined.push_str(piece.separator);
+        }
+        joined.push_str(piece.text);
+    }
+    joined
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_split_at_paragraphs() -> Result<()> {
+        let config = ChunkConfig::new(12, ChunkUnit::Chars)?;
+        let chunks = split_markdown("# Title\n\nfirst para\n\nsecond one", &config);
+        assert_eq!(chunks, vec!["# Title", "first para", "second one"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_long_paragraph_with_overlap() -> Result<()> {
+        let config = ChunkConfig::new(11, ChunkUnit::Chars)?.with_overlap(5)?;
+        let chunks = split_markdown("one two three four five", &config);
+        assert_eq!(
+            chunks,
+            vec!["one two", "two three", "three four", "four five"]
+        );
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= 11));
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_long_word() -> Result<()> {
+        let config = ChunkConfig::new(1, ChunkUnit::Tokens)?;
+        let chunks = split_markdown("abcdefghij", &config);
+        assert_eq!(chunks, vec!["abcd", "efgh", "ij"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(ChunkConfig::new(0, ChunkUnit::Tokens).is_err());
+        assert!(
+            ChunkConfig::new(10, ChunkUnit::Tokens)
+                .and_then(|config| config.with_overlap(10))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_write_chunks() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let output = temp_dir.path().join("page.md");
+        let config = ChunkConfig::new(5, ChunkUnit::Chars)?;
+
+        let paths = write_chunks(
+            &output,
+            "alpha\n\nbeta",
+            Some("A \"quoted\" title"),
+            Some("https://example.com"),
+            &config,
+        )
+        .await?;
+        assert_eq!(
+            paths,
+            vec![
+                temp_dir.path().join("page_001.md"),
+                temp_dir.path().join("page_002.md")
+            ]
+        );
+
+        let first = std::fs::read_to_string(&paths[0])?;
+        assert!(first.starts_with("---\n"));
+        assert!(first.contains("title: \"A \\\"quoted\\\" title\"\n"));
+        assert!(first.contains("source: \"https://example.com\"\n"));
+        assert!(first.contains("chunk: 1\nchunks: 2\n"));
+        assert!(first.ends_with("---\n\nalpha\n"));
+        Ok(())
+    }
+}
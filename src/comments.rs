@@ -0,0 +1,303 @@
+//! Comment thread extraction
+//!
+//! Discussions are often the most valuable part of a page, but they are removed from the
+//! main content as boilerplate. With comments enabled, Markdown output gets an appendix
+//! with the page's comment threads, found with the markup of common comment systems:
+//! Hacker News, Reddit (new and old), static Disqus, WordPress, and schema.org `Comment`
+//! items in microdata or JSON-LD.
+
+use crate::extract::Selector;
+use select::document::Document;
+use select::node::Node;
+use select::predicate::Attr;
+use std::sync::LazyLock;
+
+/// A comment, with its depth in the reply thread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// Name of the commenter, if the page shows it
+    pub author: Option<String>,
+    /// The comment text as Markdown
+    pub content: String,
+    /// Reply depth, 0 for top-level comments
+    pub depth: usize,
+}
+
+/// Markup of a comment system
+struct Schema {
+    /// Elements holding one comment each, including its replies when they are nested
+    comment: Selector,
+    /// The commenter's name, the first match inside the comment
+    author: Selector,
+    /// The comment text, the first match inside the comment
+    body: Selector,
+    /// Element and attribute holding the depth, for flat threads that indent replies
+    indent: Option<(Selector, &'static str)>,
+}
+
+impl Schema {
+    fn new(comment: &str, author: &str, body: &str, indent: Option<(&str, &'static str)>) -> Self {
+        let parse = |selector| Selector::parse(selector).expect("built-in selectors are valid");
+        Self {
+            comment: parse(comment),
+            author: parse(author),
+            body: parse(body),
+            indent: indent.map(|(selector, attr)| (parse(selector), attr)),
+        }
+    }
+
+    fn extract(&self, document: &Document) -> Vec<Comment> {
+        document
+            .find(|node: &Node| self.comment.matches(node))
+            .filter_map(|node| {
+                let content = self
+                    .own_match(&node, &self.body)
+                    .map(|body| mdka::from_html(&body.html()).trim().to_string())
+                    .filter(|content| !content.is_empty())?;
+                let author = self
+                    .own_match(&node, &self.author)
+                    .map(|author| author.text())
+                    .or_else(|| node.attr("author").map(str::to_string))
+                    .map(|author| author.trim().to_string())
+                    .filter(|author| !author.is_empty());
+                Some(Comment {
+                    author,
+                    content,
+                    depth: self.depth(&node),
+                })
+            })
+            .collect()
+    }
+
+    fn depth(&self, node: &Node) -> usize {
+        if let Some((selector, attr)) = &self.indent {
+            return self
+                .own_match(node, selector)
+                .and_then(|indent| indent.attr(attr)?.parse().ok())
+                .unwrap_or(0);
+        }
+        ancestors(node)
+            .filter(|ancestor| self.comment.matches(ancestor))
+            .count()
+    }
+
+    /// The first element inside a comment matching a selector, skipping nested replies
+    fn own_match<'a>(&self, comment: &Node<'a>, selector: &Selector) -> Option<Node<'a>> {
+        comment.descendants().find(|node| {
+            selector.matches(node)
+                && ancestors(node)
+                    .find(|ancestor| self.comment.matches(ancestor))
+                    .is_some_and(|ancestor| ancestor.index() == comment.index())
+        })
+    }
+}
+
+static SCHEMAS: LazyLock<Vec<Schema>> = LazyLock::new(|| {
+    vec![
+        // Hacker News: a flat table whose rows are indented by depth
+        Schema::new(
+            "tr.comtr",
+            "a.hnuser",
+            ".commtext",
+            Some(("td.ind", "indent")),
+        ),
+        // Reddit, whose comment elements carry the author as an attribute
+        Schema::new(
+            "shreddit-comment",
+            "[data-testid=comment_author_link]",
+            "[slot=comment]",
+            None,
+        ),
+        // Old Reddit
+        Schema::new("div.comment", "a.author", "div.md", None),
+        // Disqus static HTML
+        Schema::new("li.post", ".post-byline .author", ".post-message", None),
+        // WordPress and other blogs using its markup
+        Schema::new(
+            "li.comment",
+            ".comment-author .fn",
+            ".comment-content",
+            None,
+        ),
+        // schema.org microdata
+        Schema::new(
+            "[itemprop=comment]",
+            "[itemprop=author]",
+            "[itemprop=text]",
+            None,
+        ),
+    ]
+});
+
+/// Extract the comments of a page, in thread order
+///
+/// The first comment system found on the page is used, so comments marked up for
+/// several systems are not repeated. JSON-LD is only read if no markup is found.
+pub fn extract_comments(document: &Document) -> Vec<Comment> {
+    SCHEMAS
+        .iter()
+        .map(|schema| schema.extract(document))
+        .find(|comments| !comments.is_empty())
+        .unwrap_or_else(|| json_ld_comments(document))
+}
+
+/// Whether an element holds a comment of one of the known comment systems
+pub fn is_comment(node: &Node) -> bool {
+    SCHEMAS.iter().any(|schema| schema.comment.matches(node))
+}
+
+/// Render comments as a Markdown appendix, or an empty string if there are none
+///
+/// Replies are nested list items under the comment they answer.
+pub fn comments_markdown(comments: &[Comment]) -> String {
+    if comments.is_empty() {
+        return String::new();
+    }
+    let mut markdown = format!("## Comments ({})\n", comments.len());
+    for comment in comments {
+        let indent = "  ".repeat(comment.depth);
+        let author = comment.author.as_deref().unwrap_or("Anonymous");
+        markdown.push_str(&format!("\n{}- **{}**\n", indent, author));
+        for line in comment.content.lines() {
+            match line.is_empty() {
+                true => markdown.push('\n'),
+                false => markdown.push_str(&format!("\n{}  {}", indent, line)),
+            }
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+fn ancestors<'a>(node: &Node<'a>) -> impl Iterator<Item = Node<'a>> {
+    std::iter::successors(node.parent(), |parent| parent.parent())
+}
+
+/// Comments from schema.org JSON-LD, e.g. a `DiscussionForumPosting` with a `comment` list
+fn json_ld_comments(document: &Document) -> Vec<Comment> {
+    let mut comments = Vec::new();
+    for script in document.find(Attr("type", "application/ld+json")) {
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&script.text()) {
+            collect_json_ld(&value, 0, &mut comments);
+        }
+    }
+    comments
+}
+
+fn collect_json_ld(value: &serde_json::Value, depth: usize, comments: &mut Vec<Comment>) {
+    match value {
+        serde_json::Value::Array(values) => {
+            for value in values {
+                collect_json_ld(value, depth, comments);
+            }
+        }
+        serde_json::Value::Object(object) => {
+            let is_comment = match object.get("@type") {
+                Some(serde_json::Value::String(kind)) => kind == "Comment",
+                Some(serde_json::Value::Array(kinds)) => kinds.iter().any(|kind| kind == "Comment"),
+                _ => false,
+            };
+            let text = object.get("text").and_then(|text| text.as_str());
+            match (is_comment, text) {
+                (true, Some(text)) if !text.trim().is_empty() => {
+                    let author = match object.get("author") {
+                        Some(serde_json::Value::String(name)) => Some(name.clone()),
+                        Some(author) => author
+                            .get("name")
+                            .and_then(|name| name.as_str())
+                            .map(str::to_string),
+                        None => None,
+                    };
+                    comments.push(Comment {
+                        author,
+                        content: text.trim().to_string(),
+                        depth,
+                    });
+                    if let Some(replies) = object.get("comment") {
+                        collect_json_ld(replies, depth + 1, comments);
+                    }
+                }
+                _ => {
+                    for value in object.values() {
+                        collect_json_ld(value, depth, comments);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hacker_news_comments() {
+        let html = r#"<table class="comment-tree">
+            <tr class="athing comtr"><td><table><tr>
+                <td class="ind" indent="0"></td>
+                <td><a class="hnuser">pg</a><div class="commtext">Top <i>level</i></div></td>
+            </tr></table></td></tr>
+            <tr class="athing comtr"><td><table><tr>
+                <td class="ind" indent="1"></td>
+                <td><a class="hnuser">dang</a><div class="commtext">A reply</div></td>
+            </tr></table></td></tr>
+        </table>"#;
+        let comments = extract_comments(&Document::from(html));
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].author.as_deref(), Some("pg"));
+        assert_eq!(comments[0].content, "Top _level_");
+        assert_eq!(comments[1].depth, 1);
+
+        let markdown = comments_markdown(&comments);
+        assert!(markdown.starts_with("## Comments (2)\n"));
+        assert!(markdown.contains("\n- **pg**\n\n  Top _level_\n"));
+        assert!(markdown.contains("\n  - **dang**\n\n    A reply\n"));
+    }
+
+    #[test]
+    fn test_nested_comments() {
+        let html = r#"<ol class="comment-list">
+            <li class="comment"><div class="comment-author"><b class="fn">Ann</b></div>
+                <div class="comment-content"><p>First</p></div>
+                <ol class="children"><li class="comment">
+                    <div class="comment-content"><p>Anonymous reply</p></div>
+                </li></ol>
+            </li>
+        </ol>"#;
+        let comments = extract_comments(&Document::from(html));
+        assert_eq!(
+            comments,
+            vec![
+                Comment {
+                    author: Some("Ann".into()),
+                    content: "First".into(),
+                    depth: 0
+                },
+                Comment {
+                    author: None,
+                    content: "Anonymous reply".into(),
+                    depth: 1
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_json_ld_comments() {
+        let html = r#"<script type="application/ld+json">{
+            "@type": "DiscussionForumPosting",
+            "comment": [{
+                "@type": "Comment", "text": "Question", "author": {"name": "Bo"},
+                "comment": [{"@type": "Comment", "text": "Answer", "author": "Cy"}]
+            }]
+        }</script>"#;
+        let comments = extract_comments(&Document::from(html));
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].author.as_deref(), Some("Bo"));
+        assert_eq!(comments[1].author.as_deref(), Some("Cy"));
+        assert_eq!(comments[1].depth, 1);
+        assert!(comments_markdown(&[]).is_empty());
+    }
+}
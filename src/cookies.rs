@@ -0,0 +1,759 @@
+//! Cookie import from the cookie stores of locally installed browsers, and from cookie
+//! files
+//!
+//! Pages behind a login can be captured with the session of the browser the user
+//! normally reads them in, or with cookies exported to a file: a Netscape
+//! `cookies.txt` (as written by curl, wget, and browser extensions) or a JSON array of
+//! cookies (as exported by Cookie-Editor and similar extensions, or the `cookies` of a
+//! Playwright storage state). Firefox keeps its cookies in plain SQLite. Chrome encrypts
+//! cookie values with a key derived from a password in the OS keychain: the Secret
+//! Service on Linux (or a fixed password when no keyring is running) and the login
+//! Keychain on macOS. Chrome's cookies cannot be read on Windows, where the key is
+//! protected with DPAPI.
+
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use anyhow::{Context, Result, bail};
+use hmac::{Hmac, Mac};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+use url::Url;
+
+/// Seconds between 1601-01-01, the epoch of Chrome's timestamps, and the Unix epoch
+const CHROME_EPOCH_OFFSET: i64 = 11_644_473_600;
+
+/// Salt of the PBKDF2 derivation of Chrome's cookie key
+const CHROME_SALT: &[u8] = b"saltysalt";
+
+/// Cookie database version from which Chrome prefixes values with a hash of the domain
+const CHROME_DOMAIN_HASH_VERSION: i64 = 24;
+
+/// Browser whose cookie store is read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CookieBrowser {
+    /// Google Chrome, or Chromium when Chrome is not installed
+    Chrome,
+    /// Mozilla Firefox
+    Firefox,
+}
+
+impl fmt::Display for CookieBrowser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CookieBrowser::Chrome => write!(f, "Chrome"),
+            CookieBrowser::Firefox => write!(f, "Firefox"),
+        }
+    }
+}
+
+/// A cookie read from a browser's cookie store
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cookie {
+    /// Cookie name
+    pub name: String,
+    /// Decrypted cookie value
+    pub value: String,
+    /// Domain, with a leading dot when the cookie is sent to subdomains too
+    pub domain: String,
+    /// Path prefix of the URLs the cookie is sent to
+    pub path: String,
+    /// Whether the cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Whether the cookie is hidden from page scripts
+    pub http_only: bool,
+    /// Expiry as a Unix timestamp, or None for session cookies
+    pub expires: Option<i64>,
+}
+
+impl Cookie {
+    /// Whether a browser would send the cookie with a request for the URL
+    pub fn matches(&self, url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let domain_matches = match self.domain.strip_prefix('.') {
+            Some(domain) => {
+                host == domain
+                    || host
+                        .strip_suffix(domain)
+                        .is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            None => host == self.domain,
+        };
+        let path_matches = url.path().starts_with(&self.path)
+            && (self.path.ends_with('/')
+                || url.path().len() == self.path.len()
+                || url.path()[self.path.len()..].starts_with('/'));
+        domain_matches && path_matches && (!self.secure || url.scheme() == "https")
+    }
+
+    /// Whether the cookie has expired at the given Unix time
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires.is_some_and(|expires| expires <= now)
+    }
+}
+
+/// Cookies imported from a browser, handed out per URL
+#[derive(Debug, Clone, Default)]
+pub struct BrowserCookies {
+    cookies: Vec<Cookie>,
+}
+
+impl BrowserCookies {
+    /// Create a cookie set from already read cookies
+    pub fn new(cookies: Vec<Cookie>) -> Self {
+        Self { cookies }
+    }
+
+    /// Read the unexpired cookies of the default profile of a browser
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the browser's cookie store cannot be found or read, or the
+    /// key to decrypt Chrome's cookies cannot be obtained
+    pub fn load(browser: CookieBrowser) -> Result<Self> {
+        let cookies = match browser {
+            CookieBrowser::Chrome => read_chrome_cookies()?,
+            CookieBrowser::Firefox => read_firefox_cookies()?,
+        };
+        let now = chrono::Utc::now().timestamp();
+        let cookies: Vec<Cookie> = cookies.into_iter().filter(|c| !c.is_expired(now)).collect();
+        info!("Read {} cookies from {}", cookies.len(), browser);
+        Ok(Self { cookies })
+    }
+
+    /// Read the unexpired cookies of a Netscape `cookies.txt` or JSON cookie file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is in neither format
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cookie file {}", path.display()))?;
+        let cookies = parse_cookie_file(&text)
+            .with_context(|| format!("Failed to parse cookie file {}", path.display()))?;
+        let now = chrono::Utc::now().timestamp();
+        let cookies: Vec<Cookie> = cookies.into_iter().filter(|c| !c.is_expired(now)).collect();
+        info!("Read {} cookies from {}", cookies.len(), path.display());
+        Ok(Self { cookies })
+    }
+
+    /// Add cookies read elsewhere, such as saved login sessions
+    pub fn with_cookies(mut self, cookies: Vec<Cookie>) -> Self {
+        self.cookies.extend(cookies);
+        self
+    }
+
+    /// The cookies, for merging into another set
+    pub fn into_cookies(self) -> Vec<Cookie> {
+        self.cookies
+    }
+
+    /// Number of cookies
+    pub fn len(&self) -> usize {
+        self.cookies.len()
+    }
+
+    /// Whether no cookies were read
+    pub fn is_empty(&self) -> bool {
+        self.cookies.is_empty()
+    }
+
+    /// The cookies sent with a request for the URL
+    pub fn for_url(&self, url: &str) -> Vec<&Cookie> {
+        match Url::parse(url) {
+            Ok(url) => self.cookies.iter().filter(|c| c.matches(&url)).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// The `Cookie` header for a request for the URL, or None when no cookie applies
+    pub fn header(&self, url: &str) -> Option<String> {
+        let cookies = self.for_url(url);
+        if cookies.is_empty() {
+            return None;
+        }
+        let pairs: Vec<String> = cookies
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        Some(pairs.join("; "))
+    }
+}
+
+/// Parse a cookie file, as JSON when it starts with `[` or `{` and in the Netscape
+/// format otherwise
+fn parse_cookie_file(text: &str) -> Result<Vec<Cookie>> {
+    let trimmed = text.trim_start();
+    if !trimmed.starts_with(['[', '{']) {
+        return parse_netscape_cookies(text);
+    }
+    let value: serde_json::Value = serde_json::from_str(trimmed)?;
+    let entries = match &value {
+        serde_json::Value::Array(entries) => entries,
+        serde_json::Value::Object(state) => match state.get("cookies") {
+            Some(serde_json::Value::Array(entries)) => entries,
+            _ => bail!("Expected an array of cookies, or an object with a \"cookies\" array"),
+        },
+        _ => bail!("Expected an array of cookies"),
+    };
+    entries.iter().map(json_cookie).collect()
+}
+
+/// A cookie of a JSON export, with the field names of Cookie-Editor, EditThisCookie,
+/// Playwright, or Chrome DevTools
+fn json_cookie(entry: &serde_json::Value) -> Result<Cookie> {
+    let text = |key: &str| entry.get(key).and_then(|value| value.as_str());
+    let flag = |key: &str| entry.get(key).and_then(|value| value.as_bool());
+    let (Some(name), Some(domain)) = (text("name"), text("domain")) else {
+        bail!("Cookie without a name or domain: {}", entry);
+    };
+    // Exports mark cookies for subdomains with a leading dot, or with hostOnly false
+    let domain = match flag("hostOnly") {
+        Some(false) if !domain.starts_with('.') => format!(".{}", domain),
+        _ => domain.to_string(),
+    };
+    let expires = ["expirationDate", "expires"]
+        .iter()
+        .find_map(|key| entry.get(*key).and_then(|value| value.as_f64()))
+        .filter(|expires| *expires > 0.0)
+        .map(|expires| expires as i64);
+    Ok(Cookie {
+        name: name.to_string(),
+        value: text("value").unwrap_or_default().to_string(),
+        domain,
+        path: text("path").unwrap_or("/").to_string(),
+        secure: flag("secure").unwrap_or(false),
+        http_only: flag("httpOnly").unwrap_or(false),
+        expires,
+    })
+}
+
+/// Parse a Netscape `cookies.txt`: tab-separated domain, subdomain flag, path, secure
+/// flag, expiry, name, and value, with `#HttpOnly_` before the domain of HTTP-only
+/// cookies
+fn parse_netscape_cookies(text: &str) -> Result<Vec<Cookie>> {
+    let mut cookies = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+        let [domain, subdomains, path, secure, expires, name, value] = fields[..] else {
+            bail!("Line {} does not have 7 tab-separated fields", number + 1);
+        };
+        let domain = match subdomains.eq_ignore_ascii_case("TRUE") && !domain.starts_with('.') {
+            true => format!(".{}", domain),
+            false => domain.to_string(),
+        };
+        let expires: i64 = expires
+            .parse()
+            .with_context(|| format!("Line {} has an invalid expiry", number + 1))?;
+        cookies.push(Cookie {
+            name: name.to_string(),
+            value: value.to_string(),
+            domain,
+            path: path.to_string(),
+            secure: secure.eq_ignore_ascii_case("TRUE"),
+            http_only,
+            expires: (expires > 0).then_some(expires),
+        });
+    }
+    Ok(cookies)
+}
+
+/// Open a copy of a browser's cookie database, which the running browser keeps locked
+///
+/// The write-ahead log is copied along, so recent changes not yet in the database
+/// file are read as well.
+fn open_copy(path: &Path) -> Result<(tempfile::TempDir, Connection)> {
+    let dir = tempfile::tempdir()?;
+    let copy = dir.path().join("cookies.sqlite");
+    std::fs::copy(path, &copy)?;
+    let wal = PathBuf::from(format!("{}-wal", path.display()));
+    if wal.exists() {
+        std::fs::copy(&wal, dir.path().join("cookies.sqlite-wal"))?;
+    }
+    let conn = Connection::open_with_flags(&copy, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    Ok((dir, conn))
+}
+
+/// Chrome flavours in the order they are looked for: user-data directory below the
+/// config directory, Secret Service application name, and Keychain service and account
+#[cfg(not(target_os = "macos"))]
+const CHROME_INSTALLS: &[(&str, &str, &str)] = &[
+    ("google-chrome", "chrome", "Chrome"),
+    ("chromium", "chromium", "Chromium"),
+];
+#[cfg(target_os = "macos")]
+const CHROME_INSTALLS: &[(&str, &str, &str)] = &[
+    ("Google/Chrome", "chrome", "Chrome"),
+    ("Chromium", "chromium", "Chromium"),
+];
+
+/// Read and decrypt the cookies of the default Chrome profile
+fn read_chrome_cookies() -> Result<Vec<Cookie>> {
+    let config = dirs::config_dir()
+        .ok_or_else(|| anyhow::anyhow!("Cannot determine the config directory"))?;
+    let (database, app, keychain_name) = CHROME_INSTALLS
+        .iter()
+        .find_map(|(dir, app, keychain_name)| {
+            let profile = config.join(dir).join("Default");
+            [
+                profile.join("Network").join("Cookies"),
+                profile.join("Cookies"),
+            ]
+            .into_iter()
+            .find(|path| path.exists())
+            .map(|path| (path, *app, *keychain_name))
+        })
+        .ok_or_else(|| {
+            anyhow::anyhow!("No Chrome cookie database found in {}", config.display())
+        })?;
+    info!("Reading Chrome cookies from {}", database.display());
+
+    let keys = chrome_keys(app, keychain_name)?;
+    let (_dir, conn) = open_copy(&database)?;
+    let version: i64 = conn
+        .query_row("SELECT value FROM meta WHERE key = 'version'", [], |row| {
+            row.get::<_, String>(0)
+        })
+        .map(|value| value.parse().unwrap_or(0))
+        .unwrap_or(0);
+
+    let mut stmt = conn.prepare(
+        "SELECT host_key, name, value, encrypted_value, path, expires_utc, is_secure, \
+         is_httponly FROM cookies",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, Vec<u8>>(3)?,
+            row.get::<_, String>(4)?,
+            row.get::<_, i64>(5)?,
+            row.get::<_, bool>(6)?,
+            row.get::<_, bool>(7)?,
+        ))
+    })?;
+
+    let mut cookies = Vec::new();
+    let mut undecryptable = 0;
+    for row in rows {
+        let (domain, name, value, encrypted, path, expires_utc, secure, http_only) = row?;
+        let value = match encrypted.is_empty() {
+            true => value,
+            false => {
+                match decrypt_chrome_value(&encrypted, &keys, version >= CHROME_DOMAIN_HASH_VERSION)
+                {
+                    Some(value) => value,
+                    None => {
+                        undecryptable += 1;
+                        continue;
+                    }
+                }
+            }
+        };
+        cookies.push(Cookie {
+            name,
+            value,
+            domain,
+            path,
+            secure,
+            http_only,
+            expires: (expires_utc > 0).then(|| expires_utc / 1_000_000 - CHROME_EPOCH_OFFSET),
+        });
+    }
+    if undecryptable > 0 {
+        warn!(
+            "Skipped {} Chrome cookies that could not be decrypted",
+            undecryptable
+        );
+    }
+    Ok(cookies)
+}
+
+/// AES keys for the two encryption schemes of Chrome's cookie values
+struct ChromeKeys {
+    /// Key of `v10` values
+    v10: [u8; 16],
+    /// Key of `v11` values, encrypted with a password from the Secret Service
+    v11: Option<[u8; 16]>,
+}
+
+/// Derive the keys for Chrome's cookie values on Linux
+///
+/// `v10` values use a fixed password. `v11` values use the password Chrome stores in
+/// the Secret Service, which may be missing when no keyring was running.
+#[cfg(target_os = "linux")]
+fn chrome_keys(app: &str, _keychain_name: &str) -> Result<ChromeKeys> {
+    let v11 = match secret_service_password(app) {
+        Ok(password) => password.map(|password| pbkdf2_sha1(&password, CHROME_SALT, 1)),
+        Err(e) => {
+            warn!(
+                "Failed to read the {} password from the Secret Service: {}",
+                app, e
+            );
+            None
+        }
+    };
+    Ok(ChromeKeys {
+        v10: pbkdf2_sha1(b"peanuts", CHROME_SALT, 1),
+        v11,
+    })
+}
+
+/// Look up the password Chrome stores in the Secret Service
+#[cfg(target_os = "linux")]
+fn secret_service_password(app: &str) -> Result<Option<Vec<u8>>> {
+    use secret_service::EncryptionType;
+    use secret_service::blocking::SecretService;
+    use std::collections::HashMap;
+
+    let service = SecretService::connect(EncryptionType::Dh)?;
+    let search = service.search_items(HashMap::from([("application", app)]))?;
+    let Some(item) = search.unlocked.first().or(search.locked.first()) else {
+        return Ok(None);
+    };
+    item.ensure_unlocked()?;
+    Ok(Some(item.get_secret()?))
+}
+
+/// Derive the key for Chrome's cookie values on macOS from the Keychain password
+///
+/// Reading the password shows a Keychain prompt unless access was allowed before.
+#[cfg(target_os = "macos")]
+fn chrome_keys(_app: &str, keychain_name: &str) -> Result<ChromeKeys> {
+    let entry = keyring::Entry::new(&format!("{} Safe Storage", keychain_name), keychain_name)?;
+    let password = entry.get_password().map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to read the {} Safe Storage password from the Keychain: {}",
+            keychain_name,
+            e
+        )
+    })?;
+    Ok(ChromeKeys {
+        v10: pbkdf2_sha1(password.as_bytes(), CHROME_SALT, 1003),
+        v11: None,
+    })
+}
+
+/// Chrome's cookie key is protected with DPAPI on Windows, which is not supported
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn chrome_keys(_app: &str, _keychain_name: &str) -> Result<ChromeKeys> {
+    Err(anyhow::anyhow!(
+        "Reading Chrome cookies is not supported on this platform; use Firefox cookies \
+         or a logged-in --profile-dir instead"
+    ))
+}
+
+/// Decrypt a `v10` or `v11` Chrome cookie value
+///
+/// Returns None when the value uses another scheme, its key is unavailable, or it
+/// does not decrypt to text.
+fn decrypt_chrome_value(encrypted: &[u8], keys: &ChromeKeys, domain_hash: bool) -> Option<String> {
+    let (key, ciphertext) = match encrypted.split_at_checked(3)? {
+        (b"v10", ciphertext) => (keys.v10, ciphertext),
+        (b"v11", ciphertext) => (keys.v11?, ciphertext),
+        _ => return None,
+    };
+    let mut buffer = ciphertext.to_vec();
+    let plaintext = cbc::Decryptor::<aes::Aes128>::new(&key.into(), &[b' '; 16].into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buffer)
+        .ok()?;
+    let plaintext = match domain_hash {
+        true => plaintext.get(32..)?,
+        false => plaintext,
+    };
+    String::from_utf8(plaintext.to_vec()).ok()
+}
+
+/// PBKDF2-HMAC-SHA1 with a 16-byte output, as Chrome derives its cookie key
+fn pbkdf2_sha1(password: &[u8], salt: &[u8], iterations: u32) -> [u8; 16] {
+    let mac = Hmac::<Sha1>::new_from_slice(password).expect("HMAC accepts keys of any length");
+    let mut block = mac.clone();
+    block.update(salt);
+    block.update(&1u32.to_be_bytes());
+    let mut u = block.finalize().into_bytes();
+    let mut result = u;
+    for _ in 1..iterations {
+        let mut round = mac.clone();
+        round.update(&u);
+        u = round.finalize().into_bytes();
+        for (r, b) in result.iter_mut().zip(u.iter()) {
+            *r ^= b;
+        }
+    }
+    let mut key = [0; 16];
+    key.copy_from_slice(&result[..16]);
+    key
+}
+
+/// Read the cookies of the default Firefox profile
+fn read_firefox_cookies() -> Result<Vec<Cookie>> {
+    let profile = firefox_profile()?;
+    let database = profile.join("cookies.sqlite");
+    if !database.exists() {
+        return Err(anyhow::anyhow!(
+            "No Firefox cookie database in {}",
+            profile.display()
+        ));
+    }
+    info!("Reading Firefox cookies from {}", database.display());
+
+    let (_dir, conn) = open_copy(&database)?;
+    let mut stmt = conn
+        .prepare("SELECT host, name, value, path, expiry, isSecure, isHttpOnly FROM moz_cookies")?;
+    let rows = stmt.query_map([], |row| {
+        let expiry: i64 = row.get(4)?;
+        Ok(Cookie {
+            domain: row.get(0)?,
+            name: row.get(1)?,
+            value: row.get(2)?,
+            path: row.get(3)?,
+            // Recent Firefox versions store the expiry in milliseconds
+            expires: Some(match expiry > 100_000_000_000 {
+                true => expiry / 1000,
+                false => expiry,
+            }),
+            secure: row.get(5)?,
+            http_only: row.get(6)?,
+        })
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// The directory of the default Firefox profile, as named in `profiles.ini`
+fn firefox_profile() -> Result<PathBuf> {
+    #[cfg(target_os = "linux")]
+    let root = dirs::home_dir().map(|home| home.join(".mozilla").join("firefox"));
+    #[cfg(target_os = "macos")]
+    let root = dirs::config_dir().map(|config| config.join("Firefox"));
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    let root = dirs::config_dir().map(|config| config.join("Mozilla").join("Firefox"));
+
+    let root = root.ok_or_else(|| anyhow::anyhow!("Cannot determine the Firefox directory"))?;
+    let ini = std::fs::read_to_string(root.join("profiles.ini")).map_err(|e| {
+        anyhow::anyhow!(
+            "Failed to read {}: {}",
+            root.join("profiles.ini").display(),
+            e
+        )
+    })?;
+    let (path, relative) = default_firefox_profile(&ini).ok_or_else(|| {
+        anyhow::anyhow!(
+            "No default profile in {}",
+            root.join("profiles.ini").display()
+        )
+    })?;
+    Ok(match relative {
+        true => root.join(path),
+        false => PathBuf::from(path),
+    })
+}
+
+/// The path of the default profile in a `profiles.ini` file, and whether it is relative
+///
+/// The profile an `[Install…]` section names as default wins over a `[Profile…]`
+/// section with `Default=1`, which older versions use.
+fn default_firefox_profile(ini: &str) -> Option<(String, bool)> {
+    let mut sections: Vec<(String, Vec<(String, String)>)> = Vec::new();
+    for line in ini.lines().map(str::trim) {
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            sections.push((name.to_string(), Vec::new()));
+        } else if let (Some((key, value)), Some((_, entries))) =
+            (line.split_once('='), sections.last_mut())
+        {
+            entries.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+    let get = |entries: &[(String, String)], key: &str| {
+        entries
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+    };
+
+    let profiles = sections
+        .iter()
+        .filter(|(name, _)| name.starts_with("Profile"));
+    if let Some(path) = sections
+        .iter()
+        .filter(|(name, _)| name.starts_with("Install"))
+        .find_map(|(_, entries)| get(entries, "Default"))
+    {
+        let relative = profiles
+            .clone()
+            .find(|(_, entries)| get(entries, "Path").as_deref() == Some(&path))
+            .is_none_or(|(_, entries)| get(entries, "IsRelative").as_deref() != Some("0"));
+        return Some((path, relative));
+    }
+    profiles
+        .clone()
+        .find(|(_, entries)| get(entries, "Default").as_deref() == Some("1"))
+        .or_else(|| profiles.clone().next())
+        .and_then(|(_, entries)| {
+            let relative = get(entries, "IsRelative").as_deref() != Some("0");
+            get(entries, "Path").map(|path| (path, relative))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockEncryptMut;
+
+    fn cookie(domain: &str, path: &str, secure: bool) -> Cookie {
+        Cookie {
+            name: "session".to_string(),
+            value: "abc".to_string(),
+            domain: domain.to_string(),
+            path: path.to_string(),
+            secure,
+            http_only: true,
+            expires: None,
+        }
+    }
+
+    #[test]
+    fn test_cookie_matches() {
+        let url = |s: &str| Url::parse(s).unwrap();
+        let domain = cookie(".example.com", "/", true);
+        assert!(domain.matches(&url("https://example.com/a")));
+        assert!(domain.matches(&url("https://www.example.com/a")));
+        assert!(!domain.matches(&url("https://badexample.com/a")));
+        assert!(!domain.matches(&url("http://example.com/a")));
+
+        let host = cookie("www.example.com", "/news", false);
+        assert!(host.matches(&url("http://www.example.com/news/1")));
+        assert!(host.matches(&url("http://www.example.com/news")));
+        assert!(!host.matches(&url("http://www.example.com/newsletter")));
+        assert!(!host.matches(&url("http://sub.www.example.com/news")));
+
+        let cookies = BrowserCookies::new(vec![domain, cookie("other.org", "/", false)]);
+        assert_eq!(
+            cookies.header("https://www.example.com/"),
+            Some("session=abc".to_string())
+        );
+        assert_eq!(cookies.header("https://example.net/"), None);
+    }
+
+    #[test]
+    fn test_parse_cookie_file() {
+        let netscape = "# Netscape HTTP Cookie File\n\n\
+                        .example.com\tTRUE\t/\tTRUE\t0\ttheme\tdark\n\
+                        #HttpOnly_www.example.com\tFALSE\t/account\tFALSE\t1900000000\tsession\tabc\n";
+        let cookies = parse_cookie_file(netscape).unwrap();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0].domain, ".example.com");
+        assert!(cookies[0].secure && !cookies[0].http_only);
+        assert_eq!(cookies[0].expires, None);
+        assert_eq!(
+            cookies[1],
+            Cookie {
+                expires: Some(1_900_000_000),
+                ..cookie("www.example.com", "/account", false)
+            }
+        );
+        assert!(parse_cookie_file("example.com\tTRUE\t/\n").is_err());
+
+        let json = r#"[
+            {"name": "session", "value": "abc", "domain": "example.com", "hostOnly": false,
+             "path": "/", "secure": true, "httpOnly": true, "expirationDate": 1900000000.5},
+            {"name": "theme", "value": "dark", "domain": "www.example.com", "hostOnly": true}
+        ]"#;
+        let cookies = parse_cookie_file(json).unwrap();
+        assert_eq!(
+            cookies[0],
+            Cookie {
+                expires: Some(1_900_000_000),
+                ..cookie(".example.com", "/", true)
+            }
+        );
+        assert_eq!(cookies[1].domain, "www.example.com");
+        assert_eq!(cookies[1].path, "/");
+
+        let state = r#"{"cookies": [{"name": "session", "value": "abc", "domain": ".example.com",
+            "path": "/", "expires": -1, "httpOnly": true, "secure": true}], "origins": []}"#;
+        assert_eq!(
+            parse_cookie_file(state).unwrap(),
+            vec![cookie(".example.com", "/", true)]
+        );
+    }
+
+    #[test]
+    fn test_pbkdf2_sha1() {
+        // RFC 6070 test vectors, truncated to 16 bytes
+        assert_eq!(
+            pbkdf2_sha1(b"password", b"salt", 1),
+            [
+                0x0c, 0x60, 0xc8, 0x0f, 0x96, 0x1f, 0x0e, 0x71, 0xf3, 0xa9, 0xb5, 0x24, 0xaf, 0x60,
+                0x12, 0x06
+            ]
+        );
+        assert_eq!(
+            pbkdf2_sha1(b"password", b"salt", 2),
+            [
+                0xea, 0x6c, 0x01, 0x4d, 0xc7, 0x2d, 0x6f, 0x8c, 0xcd, 0x1e, 0xd9, 0x2a, 0xce, 0x1d,
+                0x41, 0xf0
+            ]
+        );
+    }
+
+    #[test]
+    fn test_decrypt_chrome_value() {
+        let keys = ChromeKeys {
+            v10: pbkdf2_sha1(b"peanuts", CHROME_SALT, 1),
+            v11: None,
+        };
+        let encrypt = |plaintext: &[u8]| {
+            let mut buffer = plaintext.to_vec();
+            buffer.resize(plaintext.len() + 16, 0);
+            let ciphertext =
+                cbc::Encryptor::<aes::Aes128>::new(&keys.v10.into(), &[b' '; 16].into())
+                    .encrypt_padded_mut::<Pkcs7>(&mut buffer, plaintext.len())
+                    .unwrap()
+                    .to_vec();
+            [b"v10".as_slice(), &ciphertext].concat()
+        };
+
+        let encrypted = encrypt(b"token-123");
+        assert_eq!(
+            decrypt_chrome_value(&encrypted, &keys, false),
+            Some("token-123".to_string())
+        );
+        let hashed = encrypt(&[[7u8; 32].as_slice(), b"token-123"].concat());
+        assert_eq!(
+            decrypt_chrome_value(&hashed, &keys, true),
+            Some("token-123".to_string())
+        );
+        assert_eq!(decrypt_chrome_value(b"v11abc", &keys, false), None);
+    }
+
+    #[test]
+    fn test_default_firefox_profile() {
+        let ini = "[Install4F96D1932A9F858E]\nDefault=Profiles/abc.default-release\nLocked=1\n\n\
+                   [Profile1]\nName=default\nIsRelative=1\nPath=Profiles/xyz.default\nDefault=1\n\n\
+                   [Profile0]\nName=default-release\nIsRelative=1\nPath=Profiles/abc.default-release\n";
+        assert_eq!(
+            default_firefox_profile(ini),
+            Some(("Profiles/abc.default-release".to_string(), true))
+        );
+
+        let old = "[General]\nStartWithLastProfile=1\n\n\
+                   [Profile0]\nName=default\nIsRelative=0\nPath=/home/me/ff\nDefault=1\n";
+        assert_eq!(
+            default_firefox_profile(old),
+            Some(("/home/me/ff".to_string(), false))
+        );
+    }
+}
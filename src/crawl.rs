@@ -0,0 +1,766 @@
+//! Documentation site crawling
+//!
+//! Documentation generators lay a whole site out in a sidebar, in reading order. This
+//! module recognizes the common generators, lists their pages in sidebar order, fetches
+//! them, and merges their main content into one document, so a documentation set can be
+//! saved as a single PDF or Markdown file.
+//!
+//! - **mdBook**: the chapters of the sidebar (`toc.html` in newer books).
+//! - **Docusaurus**: the links of the docs sidebar.
+//! - **Sphinx**: the global table of contents of the Read the Docs, Furo, PyData, and
+//!   classic themes.
+//! - **rustdoc**: the crate root, then every item listed on the crate's `all.html`.
+//!
+//! Pages are fetched directly, without a browser, since all of these generators write
+//! their pages and navigation as static HTML.
+
+use crate::blocklist::{BlockedUrl, DomainBlocklist};
+use crate::extract::{self, ExtractConfig, Selector};
+use crate::frontmatter::PageMetadata;
+use crate::markdown::MarkdownPage;
+use crate::newsletter::slugify;
+use crate::readlater::{checked, http_client, send_with_retry};
+use anyhow::Result;
+use regex::Regex;
+use reqwest::Client;
+use select::document::Document;
+use select::predicate::Attr;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use std::time::Duration;
+use tera::{Context, Tera};
+use tracing::{info, warn};
+use url::Url;
+
+/// docs.rs, which hosts the documentation of every crate published on crates.io
+pub const DOCS_RS: &str = "https://docs.rs";
+
+/// Most pages saved from one site unless configured otherwise
+pub const DEFAULT_MAX_PAGES: usize = 500;
+
+/// Pause between page requests, to go easy on documentation hosts
+const CRAWL_INTERVAL: Duration = Duration::from_millis(250);
+
+static LINK_ATTR: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"\s(href|src|id)="([^"]*)""#).expect("valid regex"));
+
+static PATH_TO_ROOT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"path_to_root\s*=\s*"([^"]*)""#).expect("valid regex"));
+
+const SITE_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{ title }}</title>
+<style>
+  body { margin: 0 auto; max-width: 46em; font-family: system-ui, -apple-system, "Segoe UI", "Noto Sans", sans-serif; line-height: 1.5; color: #111; }
+  .source { color: #666; font-size: 0.85em; word-break: break-all; }
+  .contents ol { padding-left: 1.5em; }
+  .contents a { color: inherit; text-decoration: none; }
+  section.page { page-break-before: always; }
+  pre { white-space: pre-wrap; word-break: break-word; background: #f5f5f5; padding: 0.6em; border-radius: 4px; font-size: 0.85em; }
+  code { font-family: ui-monospace, "SFMono-Regular", Menlo, Consolas, monospace; }
+  img, svg, video { max-width: 100%; height: auto; }
+  table { border-collapse: collapse; }
+  th, td { border: 1px solid #ccc; padding: 0.3em 0.5em; }
+  a { color: #1a4f8b; }
+</style>
+</head>
+<body>
+<h1>{{ title }}</h1>
+<p class="source"><a href="{{ url }}">{{ url }}</a></p>
+<nav class="contents">
+<h2>Contents</h2>
+<ol>
+{% for page in pages %}<li><a href="#{{ page.anchor }}">{{ page.title }}</a></li>
+{% endfor %}</ol>
+</nav>
+{% for page in pages %}<section class="page" id="{{ page.anchor }}">
+<p class="source"><a href="{{ page.url }}">{{ page.url }}</a></p>
+{{ page.content | safe }}
+</section>
+{% endfor %}</body>
+</html>
+"##;
+
+/// A documentation generator whose navigation is understood
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsPreset {
+    /// mdBook
+    MdBook,
+    /// Docusaurus
+    Docusaurus,
+    /// Sphinx
+    Sphinx,
+    /// rustdoc
+    Rustdoc,
+}
+
+impl DocsPreset {
+    /// Name of the generator
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MdBook => "mdBook",
+            Self::Docusaurus => "Docusaurus",
+            Self::Sphinx => "Sphinx",
+            Self::Rustdoc => "rustdoc",
+        }
+    }
+
+    /// The generator that produced a page, recognized from its markup
+    pub fn detect(html: &str) -> Option<Self> {
+        let document = Document::from(html);
+        let generator = document
+            .find(Attr("name", "generator"))
+            .filter_map(|meta| meta.attr("content"))
+            .map(str::to_ascii_lowercase)
+            .collect::<Vec<_>>()
+            .join(" ");
+        if generator.contains("rustdoc") || document.find(Attr("name", "rustdoc-vars")).count() > 0
+        {
+            Some(Self::Rustdoc)
+        } else if generator.contains("docusaurus") {
+            Some(Self::Docusaurus)
+        } else if generator.contains("sphinx") || html.contains("documentation_options.js") {
+            Some(Self::Sphinx)
+        } else if html.contains("Book generated using mdBook") || html.contains("book.js\"") {
+            Some(Self::MdBook)
+        } else {
+            None
+        }
+    }
+
+    /// Selectors of the sidebar links, tried in order until one matches
+    fn nav_selectors(self) -> &'static str {
+        match self {
+            Self::MdBook => "ol.chapter a",
+            Self::Docusaurus => "nav.menu a.menu__link, .theme-doc-sidebar-menu a.menu__link",
+            Self::Sphinx => {
+                ".wy-menu-vertical a.reference.internal, .sidebar-tree a.reference.internal, \
+                 .bd-docs-nav a.reference.internal, .sphinxsidebarwrapper a.reference.internal, \
+                 .toctree-wrapper a.reference.internal"
+            }
+            Self::Rustdoc => "ul.all-items a",
+        }
+    }
+
+    /// Selectors of the page's main content
+    fn content_selectors(self) -> &'static str {
+        match self {
+            Self::MdBook => "main",
+            Self::Docusaurus => ".theme-doc-markdown, article",
+            Self::Sphinx => "[role=main], article.bd-article, div.body",
+            Self::Rustdoc => "#main-content",
+        }
+    }
+
+    /// Selectors of the page controls left out of the content
+    fn strip_selectors(self) -> &'static str {
+        match self {
+            Self::MdBook => ".nav-wrapper, .nav-chapters, .mobile-nav-chapters",
+            Self::Docusaurus => {
+                ".hash-link, .theme-doc-toc-mobile, .theme-doc-breadcrumbs, \
+                 .theme-doc-footer, .pagination-nav"
+            }
+            Self::Sphinx => ".headerlink, .rst-footer-buttons, .prev-next-area, .related",
+            Self::Rustdoc => ".out-of-band, #copy-path, a.anchor, a.src, rustdoc-toolbar",
+        }
+    }
+
+    /// Content extraction for the generator's pages
+    pub fn extraction(self) -> ExtractConfig {
+        let parse = |source| Selector::parse_list(source).expect("preset selectors are valid");
+        ExtractConfig::new()
+            .with_content_selectors(parse(self.content_selectors()))
+            .with_strip_selectors(parse(self.strip_selectors()))
+    }
+}
+
+/// A fetched documentation page
+#[derive(Debug, Clone)]
+pub struct DocsPage {
+    /// The page URL
+    pub url: String,
+    /// The page HTML
+    pub html: String,
+}
+
+/// A documentation site, with its pages in sidebar order
+#[derive(Debug, Clone)]
+pub struct DocsSite {
+    /// The generator of the site
+    pub preset: DocsPreset,
+    /// The URL the crawl started from
+    pub url: String,
+    /// Title of the documentation set
+    pub title: String,
+    /// The fetched pages
+    pub pages: Vec<DocsPage>,
+    /// URLs of the pages that could not be fetched
+    pub failed: Vec<String>,
+    /// Pages skipped because their domain is on the blocklist
+    pub blocked: Vec<BlockedUrl>,
+}
+
+#[derive(Serialize)]
+struct SitePage {
+    anchor: String,
+    title: String,
+    url: String,
+    content: String,
+}
+
+impl DocsSite {
+    /// Render the site as one HTML document: a table of contents, then the main content
+    /// of every page
+    ///
+    /// Links between the pages point into the document, and other links and images are
+    /// made absolute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be rendered
+    pub fn to_html(&self) -> Result<String> {
+        let extraction = self.preset.extraction();
+        let anchors: HashMap<String, usize> = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(index, page)| (page.url.clone(), index))
+            .collect();
+        let pages: Vec<SitePage> = self
+            .pages
+            .iter()
+            .enumerate()
+            .map(|(index, page)| {
+                let (title, content) = page_content(&page.html, &extraction);
+                SitePage {
+                    anchor: format!("page-{}", index),
+                    title,
+                    url: page.url.clone(),
+                    content: rewrite_links(&content, &page.url, Some((index, &anchors))),
+                }
+            })
+            .collect();
+
+        let mut context = Context::new();
+        context.insert("title", &self.title);
+        context.insert("url", &self.url);
+        context.insert("pages", &pages);
+        Ok(Tera::one_off(SITE_TEMPLATE, &context, true)?)
+    }
+
+    /// The pages converted to Markdown, for merging with `markdown::merge_pages`
+    pub fn markdown_pages(&self) -> Vec<MarkdownPage> {
+        let extraction = self.preset.extraction();
+        self.pages
+            .iter()
+            .map(|page| {
+                let (title, content) = page_content(&page.html, &extraction);
+                MarkdownPage {
+                    title,
+                    url: Some(page.url.clone()),
+                    content: mdka::from_html(&rewrite_links(&content, &page.url, None)),
+                    metadata: PageMetadata::default(),
+                }
+            })
+            .collect()
+    }
+
+    /// File name stem for the merged document, from the site title
+    pub fn file_stem(&self) -> String {
+        match slugify(&self.title) {
+            stem if stem.is_empty() => "docs".to_string(),
+            stem => stem,
+        }
+    }
+}
+
+/// Crawler listing and fetching the pages of documentation sites
+pub struct DocsCrawler {
+    client: Client,
+    preset: Option<DocsPreset>,
+    max_pages: usize,
+    blocklist: Option<DomainBlocklist>,
+}
+
+impl DocsCrawler {
+    /// Create a crawler that detects the generator and saves at most
+    /// [`DEFAULT_MAX_PAGES`] pages
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            preset: None,
+            max_pages: DEFAULT_MAX_PAGES,
+            blocklist: None,
+        })
+    }
+
+    /// Use this preset instead of detecting the generator
+    pub fn with_preset(mut self, preset: DocsPreset) -> Self {
+        self.preset = Some(preset);
+        self
+    }
+
+    /// Save at most this many pages
+    pub fn with_max_pages(mut self, max_pages: usize) -> Self {
+        self.max_pages = max_pages.max(1);
+        self
+    }
+
+    /// Refuse sites, and skip pages, whose domain is on a blocklist
+    pub fn with_blocklist(mut self, blocklist: DomainBlocklist) -> Self {
+        self.blocklist = Some(blocklist);
+        self
+    }
+
+    /// List the pages of the site a page belongs to, in sidebar order, along with the
+    /// generator and the site title
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be fetched, or its generator is not recognized
+    pub async fn list(&self, url: &str) -> Result<(DocsPreset, String, Vec<String>)> {
+        Url::parse(url)?;
+        if let Some(blocked) = self.blocklist.as_ref().and_then(|list| list.check(url)) {
+            return Err(anyhow::anyhow!(
+                "{} is on blocklist {}",
+                blocked.domain,
+                blocked.list
+            ));
+        }
+        let response = send_with_retry(|| self.client.get(url)).await?;
+        // Links are relative to the page the request was redirected to
+        let start = response.url().clone();
+        let html = checked(response, "Page request").await?.text().await?;
+        let preset = match self.preset.or_else(|| DocsPreset::detect(&html)) {
+            Some(preset) => preset,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "{} is not a recognized mdBook, Docusaurus, Sphinx, or rustdoc page; pass a preset",
+                    url
+                ));
+            }
+        };
+
+        let document = Document::from(html.as_str());
+        let title = site_title(preset, &document);
+        let mut urls = match preset {
+            DocsPreset::Rustdoc => self.rustdoc_pages(&start, &document).await?,
+            DocsPreset::MdBook => {
+                let links = nav_links(preset, &document, &start);
+                match links.is_empty() {
+                    true => self.mdbook_toc(&start, &html).await?,
+                    false => links,
+                }
+            }
+            DocsPreset::Docusaurus | DocsPreset::Sphinx => {
+                let mut links = nav_links(preset, &document, &start);
+                let start_url = without_fragment(&start);
+                if !links.contains(&start_url) {
+                    links.insert(0, start_url);
+                }
+                links
+            }
+        };
+        if urls.len() > self.max_pages {
+            warn!(
+                "{} has {} pages; saving the first {}",
+                url,
+                urls.len(),
+                self.max_pages
+            );
+            urls.truncate(self.max_pages);
+        }
+        info!("Found {} {} pages at {}", urls.len(), preset.name(), url);
+        Ok((preset, title, urls))
+    }
+
+    /// Fetch every page of the site a page belongs to, in sidebar order
+    ///
+    /// Pages that cannot be fetched are recorded in [`DocsSite::failed`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pages cannot be listed
+    pub async fn crawl(&self, url: &str) -> Result<DocsSite> {
+        let (preset, title, urls) = self.list(url).await?;
+        let mut pages = Vec::with_capacity(urls.len());
+        let mut failed = Vec::new();
+        let mut blocked = Vec::new();
+        let urls = urls.into_iter().filter(|page_url| {
+            match self
+                .blocklist
+                .as_ref()
+                .and_then(|list| list.check(page_url))
+            {
+                Some(block) => {
+                    warn!(
+                        "Skipping {}: {} is on blocklist {}",
+                        page_url, block.domain, block.list
+                    );
+                    blocked.push(block);
+                    false
+                }
+                None => true,
+            }
+        });
+        for (index, page_url) in urls.enumerate() {
+            if index > 0 {
+                tokio::time::sleep(CRAWL_INTERVAL).await;
+            }
+            info!("Fetching page {}: {}", index + 1, page_url);
+            match self.fetch(&page_url).await {
+                Ok(html) => pages.push(DocsPage {
+                    url: page_url,
+                    html,
+                }),
+                Err(e) => {
+                    warn!("Failed to fetch {}: {}", page_url, e);
+                    failed.push(page_url);
+                }
+            }
+        }
+        Ok(DocsSite {
+            preset,
+            url: url.to_string(),
+            title,
+            pages,
+            failed,
+            blocked,
+        })
+    }
+
+    async fn fetch(&self, url: &str) -> Result<String> {
+        let response = send_with_retry(|| self.client.get(url)).await?;
+        Ok(checked(response, "Page request").await?.text().await?)
+    }
+
+    /// The chapters of an mdBook whose sidebar is loaded by a script from `toc.html`
+    async fn mdbook_toc(&self, start: &Url, html: &str) -> Result<Vec<String>> {
+        let root = PATH_TO_ROOT
+            .captures(html)
+            .map(|captures| captures[1].to_string())
+            .unwrap_or_default();
+        let toc_url = start.join(&root)?.join("toc.html")?;
+        let toc = self.fetch(toc_url.as_str()).await?;
+        Ok(nav_links(
+            DocsPreset::MdBook,
+            &Document::from(toc.as_str()),
+            &toc_url,
+        ))
+    }
+
+    /// The crate root, then every item listed on the crate's `all.html`
+    async fn rustdoc_pages(&self, start: &Url, document: &Document) -> Result<Vec<String>> {
+        let vars = document.find(Attr("name", "rustdoc-vars")).next();
+        let crate_name = vars.and_then(|vars| vars.attr("data-current-crate"));
+        let crate_dir = match (
+            vars.and_then(|vars| vars.attr("data-root-path")),
+            crate_name,
+        ) {
+            (Some(root), Some(crate_name)) => {
+                start.join(root)?.join(&format!("{}/", crate_name))?
+            }
+            _ => start.join("./")?,
+        };
+        let all_url = crate_dir.join("all.html")?;
+        let all = self.fetch(all_url.as_str()).await?;
+        let mut pages = vec![crate_dir.join("index.html")?.to_string()];
+        for link in nav_links(DocsPreset::Rustdoc, &Document::from(all.as_str()), &all_url) {
+            if !pages.contains(&link) {
+                pages.push(link);
+            }
+        }
+        Ok(pages)
+    }
+}
+
+/// The docs.rs URL of a crate's documentation, from `crate` or `crate@version`
+///
+/// The version may be anything docs.rs accepts, such as `1.0.197`, a requirement like
+/// `^1.0`, or `latest` (the default). docs.rs redirects the URL to the crate's root module.
+///
+/// # Errors
+///
+/// Returns an error if the crate name or version is not valid
+pub fn docs_rs_url(spec: &str) -> Result<String> {
+    let (name, version) = spec
+        .trim()
+        .split_once('@')
+        .unwrap_or((spec.trim(), "latest"));
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(anyhow::anyhow!("Invalid crate name: {}", name));
+    }
+    let version = match version.trim() {
+        "" => "latest",
+        version => version,
+    };
+    if version.contains(['/', '?', '#']) || version.chars().any(char::is_whitespace) {
+        return Err(anyhow::anyhow!("Invalid crate version: {}", version));
+    }
+    let mut url = Url::parse(DOCS_RS)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Invalid docs.rs URL"))?
+        .extend([name, version, ""]);
+    Ok(url.to_string())
+}
+
+/// The sidebar links of a page, in order, without fragments or duplicates, and limited to
+/// HTML pages on the page's own site
+pub fn nav_links(preset: DocsPreset, document: &Document, page_url: &Url) -> Vec<String> {
+    let selectors =
+        Selector::parse_list(preset.nav_selectors()).expect("preset selectors are valid");
+    for selector in &selectors {
+        let mut seen = HashSet::new();
+        let links: Vec<String> = document
+            .find(|node: &select::node::Node| selector.matches(node))
+            .filter_map(|link| link.attr("href"))
+            .filter_map(|href| page_url.join(href).ok())
+            .filter(|link| link.origin() == page_url.origin() && is_html_page(link))
+            .map(|link| without_fragment(&link))
+            .filter(|link| seen.insert(link.clone()))
+            .collect();
+        if !links.is_empty() {
+            return links;
+        }
+    }
+    Vec::new()
+}
+
+/// Whether a URL looks like an HTML page rather than a download
+fn is_html_page(url: &Url) -> bool {
+    let last = url.path().rsplit('/').next().unwrap_or_default();
+    match last.rsplit_once('.') {
+        Some((_, extension)) => matches!(extension, "html" | "htm"),
+        None => true,
+    }
+}
+
+fn without_fragment(url: &Url) -> String {
+    let mut url = url.clone();
+    url.set_fragment(None);
+    url.to_string()
+}
+
+/// Title of the documentation set: the site name, crate name, or start page title
+fn site_title(preset: DocsPreset, document: &Document) -> String {
+    let meta = |name: &str, attr: &str| {
+        document
+            .find(Attr(name, attr))
+            .find_map(|meta| meta.attr("content"))
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+    };
+    if let Some(site_name) = meta("property", "og:site_name") {
+        return site_name;
+    }
+    if preset == DocsPreset::Rustdoc {
+        if let Some(crate_name) = document
+            .find(Attr("name", "rustdoc-vars"))
+            .find_map(|vars| vars.attr("data-current-crate"))
+        {
+            return format!("{} documentation", crate_name);
+        }
+    }
+    let title = document
+        .find(select::predicate::Name("title"))
+        .next()
+        .map(|title| title.text().trim().to_string())
+        .or_else(|| extract::find_title(document))
+        .unwrap_or_else(|| "Documentation".to_string());
+    // Page titles usually end with the site name, e.g. "Installation - The Book"
+    [" | ", " — ", " - "]
+        .iter()
+        .find_map(|separator| title.rsplit_once(separator))
+        .map(|(_, site)| site.trim().to_string())
+        .filter(|site| !site.is_empty())
+        .unwrap_or(title)
+}
+
+/// The title and main content HTML of a page, with collapsed sections opened for print
+fn page_content(html: &str, extraction: &ExtractConfig) -> (String, String) {
+    let document = Document::from(html);
+    let title = extract::find_title(&document).unwrap_or_else(|| "Untitled".to_string());
+    let content = match extraction.find_main(&document) {
+        Some(main) => extraction.html(&main),
+        None => html.to_string(),
+    };
+    (
+        title,
+        content
+            .replace("<details ", "<details open ")
+            .replace("<details>", "<details open>"),
+    )
+}
+
+/// Make the links and images of a page's content absolute
+///
+/// When `anchors` holds the pages of a merged document and the page's index in it, links
+/// to those pages point into the document instead, and element ids are prefixed with the
+/// page's anchor so that they stay unique.
+fn rewrite_links(
+    html: &str,
+    page_url: &str,
+    anchors: Option<(usize, &HashMap<String, usize>)>,
+) -> String {
+    let Ok(base) = Url::parse(page_url) else {
+        return html.to_string();
+    };
+    LINK_ATTR
+        .replace_all(html, |captures: &regex::Captures| {
+            let attr = &captures[1];
+            let value = captures[2].replace("&amp;", "&");
+            let rewritten = match (attr, anchors) {
+                ("id", Some((index, _))) => format!("page-{}-{}", index, value),
+                ("id", None) => value,
+                (_, _) if value.starts_with("data:") || value.starts_with("javascript:") => value,
+                ("href", Some((index, _))) if value.starts_with('#') => {
+                    format!("#page-{}-{}", index, &value[1..])
+                }
+                (_, _) if value.starts_with('#') => value,
+                (_, anchors) => match base.join(&value) {
+                    Ok(target) => {
+                        let page = anchors.and_then(|(_, anchors)| {
+                            anchors.get(&without_fragment(&target)).copied()
+                        });
+                        match (attr, page, target.fragment()) {
+                            ("href", Some(page), Some(fragment)) => {
+                                format!("#page-{}-{}", page, fragment)
+                            }
+                            ("href", Some(page), None) => format!("#page-{}", page),
+                            _ => target.to_string(),
+                        }
+                    }
+                    Err(_) => value,
+                },
+            };
+            format!(" {}=\"{}\"", attr, rewritten.replace('&', "&amp;"))
+        })
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_preset() {
+        assert_eq!(
+            DocsPreset::detect(r#"<head><meta name="generator" content="rustdoc"></head>"#),
+            Some(DocsPreset::Rustdoc)
+        );
+        assert_eq!(
+            DocsPreset::detect(
+                r#"<head><meta name="generator" content="Docusaurus v3.1.0"></head>"#
+            ),
+            Some(DocsPreset::Docusaurus)
+        );
+        assert_eq!(
+            DocsPreset::detect(
+                r#"<head><script src="_static/documentation_options.js"></script></head>"#
+            ),
+            Some(DocsPreset::Sphinx)
+        );
+        assert_eq!(
+            DocsPreset::detect("<body><!-- Book generated using mdBook --></body>"),
+            Some(DocsPreset::MdBook)
+        );
+        assert_eq!(DocsPreset::detect("<p>Plain page</p>"), None);
+    }
+
+    #[test]
+    fn test_nav_links() {
+        let html = r#"
+            <nav id="sidebar"><ol class="chapter">
+                <li><a href="intro.html">Introduction</a></li>
+                <li><a href="guide/setup.html#install">Setup</a></li>
+                <li><a href="guide/setup.html">Setup again</a></li>
+                <li><a href="https://github.com/org/book">Repository</a></li>
+                <li><a href="book.pdf">PDF</a></li>
+                <li><a href="../reference/">Reference</a></li>
+            </ol></nav>"#;
+        let url = Url::parse("https://example.com/book/intro.html").unwrap();
+        assert_eq!(
+            nav_links(DocsPreset::MdBook, &Document::from(html), &url),
+            [
+                "https://example.com/book/intro.html",
+                "https://example.com/book/guide/setup.html",
+                "https://example.com/reference/",
+            ]
+        );
+
+        // The first sidebar that has links is used, not the page's local contents
+        let html = r#"
+            <div class="wy-menu-vertical"><a class="reference internal" href="usage.html">Usage</a></div>
+            <div class="toctree-wrapper"><a class="reference internal" href="api.html">API</a></div>"#;
+        let url = Url::parse("https://docs.example.com/en/latest/").unwrap();
+        assert_eq!(
+            nav_links(DocsPreset::Sphinx, &Document::from(html), &url),
+            ["https://docs.example.com/en/latest/usage.html"]
+        );
+    }
+
+    #[test]
+    fn test_docs_rs_url() {
+        assert_eq!(
+            docs_rs_url("serde").unwrap(),
+            "https://docs.rs/serde/latest/"
+        );
+        assert_eq!(
+            docs_rs_url("tokio@1.37.0").unwrap(),
+            "https://docs.rs/tokio/1.37.0/"
+        );
+        assert_eq!(
+            docs_rs_url("rand_core@^0.6").unwrap(),
+            "https://docs.rs/rand_core/^0.6/"
+        );
+        assert!(docs_rs_url("../etc").is_err());
+        assert!(docs_rs_url("serde@1.0/../x").is_err());
+    }
+
+    #[test]
+    fn test_merged_links() {
+        let site = DocsSite {
+            preset: DocsPreset::MdBook,
+            url: "https://example.com/book/".to_string(),
+            title: "The Book".to_string(),
+            pages: vec![
+                DocsPage {
+                    url: "https://example.com/book/a.html".to_string(),
+                    html: r##"<main><h1 id="a">A</h1><p><a href="b.html#usage">Usage</a> <a href="#a">top</a> <img src="img/x.png"></p></main>"##.to_string(),
+                },
+                DocsPage {
+                    url: "https://example.com/book/b.html".to_string(),
+                    html: r#"<main><h1>B</h1><h2 id="usage">Usage</h2><a href="https://rust-lang.org/?a=1&amp;b=2">Rust</a></main>"#.to_string(),
+                },
+            ],
+            failed: Vec::new(),
+            blocked: Vec::new(),
+        };
+        let html = site.to_html().unwrap();
+        assert!(html.contains(r##"<a href="#page-1-usage">Usage</a>"##));
+        assert!(html.contains(r##"<a href="#page-0-a">top</a>"##));
+        assert!(html.contains(r#"<h2 id="page-1-usage">"#));
+        assert!(html.contains(r#"src="https://example.com/book/img/x.png""#));
+        assert!(html.contains(r#"href="https://rust-lang.org/?a=1&amp;b=2""#));
+        assert!(html.contains(r##"<a href="#page-1">B</a>"##));
+
+        let pages = site.markdown_pages();
+        assert_eq!(pages[1].title, "B");
+        assert!(
+            pages[0]
+                .content
+                .contains("https://example.com/book/b.html#usage")
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! Delivery of PDFs to e-readers
+//!
+//! Saved PDFs can be copied to a directory, such as the `documents` folder of an
+//! e-reader mounted over USB, and emailed to a Kindle's Send to Kindle address. Email is
+//! handed to a sendmail-compatible command (sendmail, msmtp, or the like), so it goes
+//! out through whatever mail setup the machine already has.
+
+use crate::atomic;
+use anyhow::Result;
+use base64::Engine;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::info;
+
+/// Largest attachment Send to Kindle accepts
+pub const KINDLE_MAX_BYTES: u64 = 50 * 1024 * 1024;
+
+const BOUNDARY: &str = "webpage-save-attachment";
+
+/// Where saved PDFs are delivered
+#[derive(Debug, Clone, Default)]
+pub struct Delivery {
+    device_dir: Option<PathBuf>,
+    kindle: Option<KindleMail>,
+}
+
+/// A destination a PDF was delivered to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Delivered {
+    /// Copied to this path on the device
+    Device(PathBuf),
+    /// Emailed to this Send to Kindle address
+    Kindle(String),
+}
+
+#[derive(Debug, Clone)]
+struct KindleMail {
+    to: String,
+    from: String,
+    sendmail: String,
+}
+
+impl Delivery {
+    /// Create a delivery that sends PDFs nowhere
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy PDFs into a directory, such as the documents folder of a mounted e-reader
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory does not exist, e.g. because the device is not
+    /// mounted
+    pub fn with_device_dir(mut self, dir: PathBuf) -> Result<Self> {
+        if !dir.is_dir() {
+            return Err(anyhow::anyhow!(
+                "Device directory {} does not exist (is the device mounted?)",
+                dir.display()
+            ));
+        }
+        self.device_dir = Some(dir);
+        Ok(self)
+    }
+
+    /// Email PDFs to a Send to Kindle address from an approved sender address, using a
+    /// sendmail-compatible command
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either address is not a valid email address
+    pub fn with_kindle_email(mut self, to: &str, from: &str, sendmail: &str) -> Result<Self> {
+        for address in [to, from] {
+            check_address(address)?;
+        }
+        self.kindle = Some(KindleMail {
+            to: to.to_string(),
+            from: from.to_string(),
+            sendmail: sendmail.to_string(),
+        });
+        Ok(self)
+    }
+
+    /// Whether PDFs are delivered anywhere
+    pub fn is_empty(&self) -> bool {
+        self.device_dir.is_none() && self.kindle.is_none()
+    }
+
+    /// Deliver a saved PDF to every configured destination, returning where it went
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDF cannot be read, copied, or sent
+    pub async fn deliver(&self, pdf_path: &Path) -> Result<Vec<Delivered>> {
+        let mut delivered = Vec::new();
+        if self.is_empty() {
+            return Ok(delivered);
+        }
+        let data = tokio::fs::read(pdf_path).await?;
+        let file_name = pdf_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| "document.pdf".to_string());
+
+        if let Some(dir) = &self.device_dir {
+            let target = dir.join(&file_name);
+            atomic::write(&target, &data).await?;
+            info!("Copied {} to {}", pdf_path.display(), target.display());
+            delivered.push(Delivered::Device(target));
+        }
+
+        if let Some(kindle) = &self.kindle {
+            if data.len() as u64 > KINDLE_MAX_BYTES {
+                return Err(anyhow::anyhow!(
+                    "{} is too large for Send to Kindle ({} bytes, limit {})",
+                    pdf_path.display(),
+                    data.len(),
+                    KINDLE_MAX_BYTES
+                ));
+            }
+            let message = kindle_message(&kindle.to, &kindle.from, &file_name, &data);
+            let command = kindle.sendmail.clone();
+            tokio::task::spawn_blocking(move || sendmail(&command, &message)).await??;
+            info!("Emailed {} to {}", pdf_path.display(), kindle.to);
+            delivered.push(Delivered::Kindle(kindle.to.clone()));
+        }
+        Ok(delivered)
+    }
+}
+
+/// Check that an email address is plausible and safe to put in a header
+///
+/// # Errors
+///
+/// Returns an error if the address is not a valid email address
+pub(crate) fn check_address(address: &str) -> Result<()> {
+    let valid = address
+        .split_once('@')
+        .is_some_and(|(user, domain)| !user.is_empty() && domain.contains('.'))
+        && !address.chars().any(|c| c.is_whitespace() || c.is_control());
+    match valid {
+        true => Ok(()),
+        false => Err(anyhow::anyhow!("Invalid email address '{}'", address)),
+    }
+}
+
+/// A MIME email carrying a PDF attachment
+fn kindle_message(to: &str, from: &str, file_name: &str, data: &[u8]) -> String {
+    // Header parameters are kept to plain ASCII without quotes
+    let file_name: String = file_name
+        .chars()
+        .map(|c| {
+            if (c.is_ascii_graphic() && !matches!(c, '"' | '\\')) || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let subject = file_name.trim_end_matches(".pdf");
+    let encoded = base64::engine::general_purpose::STANDARD.encode(data);
+    let mut body = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for line in encoded.as_bytes().chunks(76) {
+        body.push_str(&String::from_utf8_lossy(line));
+        body.push('\n');
+    }
+    format!(
+        "From: {from}\nTo: {to}\nSubject: {subject}\nMIME-Version: 1.0\n\
+         Content-Type: multipart/mixed; boundary=\"{BOUNDARY}\"\n\n\
+         --{BOUNDARY}\nContent-Type: text/plain; charset=utf-8\n\nSent by webpage-save\n\n\
+         --{BOUNDARY}\nContent-Type: application/pdf; name=\"{file_name}\"\n\
+         Content-Disposition: attachment; filename=\"{file_name}\"\n\
+         Content-Transfer-Encoding: base64\n\n{body}--{BOUNDARY}--\n"
+    )
+}
+
+/// Send a message through a sendmail-compatible command, taking recipients from its
+/// headers
+pub(crate) fn sendmail(command: &str, message: &str) -> Result<()> {
+    let mut child = Command::new(command)
+        .args(["-t", "-i"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(message.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "{} failed ({}): {}",
+            command,
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kindle_message() {
+        let message = kindle_message(
+            "reader@kindle.com",
+            "me@example.com",
+            "Café \"notes\".pdf",
+            b"%PDF-1.4",
+        );
+        assert!(message.starts_with("From: me@example.com\nTo: reader@kindle.com\n"));
+        assert!(message.contains("Subject: Caf_ _notes_\n"));
+        assert!(message.contains("filename=\"Caf_ _notes_.pdf\""));
+        assert!(message.contains("Content-Transfer-Encoding: base64\n\nJVBERi0xLjQ=\n--"));
+        assert!(message.ends_with("--webpage-save-attachment--\n"));
+
+        assert!(
+            Delivery::new()
+                .with_kindle_email("reader@kindle.com", "me@example.com", "sendmail")
+                .is_ok()
+        );
+        assert!(
+            Delivery::new()
+                .with_kindle_email(
+                    "reader@kindle.com\nBcc: x@y.z",
+                    "me@example.com",
+                    "sendmail"
+                )
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_deliver_to_device() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let device = temp_dir.path().join("documents");
+        assert!(Delivery::new().with_device_dir(device.clone()).is_err());
+
+        std::fs::create_dir(&device)?;
+        let pdf = temp_dir.path().join("page.pdf");
+        std::fs::write(&pdf, b"%PDF-1.4")?;
+        let delivered = Delivery::new()
+            .with_device_dir(device.clone())?
+            .deliver(&pdf)
+            .await?;
+        assert_eq!(delivered, [Delivered::Device(device.join("page.pdf"))]);
+        assert_eq!(std::fs::read(device.join("page.pdf"))?, b"%PDF-1.4");
+        Ok(())
+    }
+}
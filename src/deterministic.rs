@@ -0,0 +1,255 @@
+//! Deterministic rendering for reproducible PDF captures
+//!
+//! Capturing the same content twice normally gives different PDFs: clocks move on,
+//! `Math.random` picks other ads and layouts, animations are caught mid-frame, and
+//! Chrome stamps each PDF with its creation time and a random document ID. In
+//! deterministic mode, the page runs on virtual time starting at a fixed instant,
+//! `Math.random` is seeded, animations and transitions are disabled, common dynamic
+//! widgets (ads, consent banners, chat bubbles) are hidden, and the PDF dates and ID
+//! are derived from the frozen time, so identical content gives identical PDFs.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use headless_chrome::Tab;
+use headless_chrome::protocol::cdp::Emulation::{SetVirtualTimePolicy, VirtualTimePolicy};
+use headless_chrome::protocol::cdp::Page::AddScriptToEvaluateOnNewDocument;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// Instant the page clock starts at unless another is given
+pub const DEFAULT_TIME: &str = "2000-01-01T00:00:00Z";
+
+/// Seed of `Math.random` unless another is given
+pub const DEFAULT_SEED: u32 = 1;
+
+/// Virtual time pages may run for (timers, intervals) before their clock stops
+const VIRTUAL_TIME_BUDGET: Duration = Duration::from_secs(5);
+
+/// Dynamic widgets hidden before printing
+const DYNAMIC_WIDGETS: &[&str] = &[
+    // Ads
+    "ins.adsbygoogle",
+    "[id^='google_ads']",
+    "[id^='div-gpt-ad']",
+    "iframe[src*='doubleclick.net']",
+    "iframe[src*='googlesyndication.com']",
+    // Cookie consent banners
+    "#onetrust-consent-sdk",
+    "#CybotCookiebotDialog",
+    ".cc-window",
+    "#didomi-host",
+    ".fc-consent-root",
+    // Chat bubbles
+    "#intercom-container",
+    ".intercom-lightweight-app",
+    "#hubspot-messages-iframe-container",
+    ".drift-frame-controller",
+    // Live regions such as tickers and toasts
+    "[aria-live='assertive']",
+];
+
+/// CSS stopping motion on the page
+const MOTION_CSS: &str = "*, *::before, *::after { animation: none !important; \
+     transition: none !important; caret-color: transparent !important; } \
+     html { scroll-behavior: auto !important; }";
+
+/// Settings that make repeated captures of the same content print the same PDF
+#[derive(Debug, Clone)]
+pub struct DeterministicRendering {
+    time: DateTime<Utc>,
+    seed: u32,
+    hidden: Vec<String>,
+}
+
+impl Default for DeterministicRendering {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeterministicRendering {
+    /// Deterministic rendering starting at [`DEFAULT_TIME`] with [`DEFAULT_SEED`]
+    pub fn new() -> Self {
+        Self {
+            time: DateTime::parse_from_rfc3339(DEFAULT_TIME)
+                .expect("DEFAULT_TIME is valid RFC 3339")
+                .with_timezone(&Utc),
+            seed: DEFAULT_SEED,
+            hidden: DYNAMIC_WIDGETS.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    /// Start the page clock at this instant, which also dates the PDF
+    pub fn with_time(mut self, time: DateTime<Utc>) -> Self {
+        self.time = time;
+        self
+    }
+
+    /// Seed `Math.random` with this value
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Also hide the elements matching these CSS selectors
+    pub fn with_hidden(mut self, selectors: Vec<String>) -> Self {
+        self.hidden.extend(selectors);
+        self
+    }
+
+    /// The instant the page clock starts at
+    pub fn time(&self) -> DateTime<Utc> {
+        self.time
+    }
+
+    /// Prepare a tab before it loads the page: start virtual time at the frozen instant
+    /// and install the seeded `Math.random` and motion-free stylesheet
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the DevTools calls fail
+    pub fn prepare(&self, tab: &Tab) -> Result<()> {
+        tab.call_method(AddScriptToEvaluateOnNewDocument {
+            source: self.script(),
+            world_name: None,
+            include_command_line_api: None,
+            run_immediately: None,
+        })?;
+        tab.call_method(SetVirtualTimePolicy {
+            policy: VirtualTimePolicy::PauseIfNetworkFetchesPending,
+            budget: Some(VIRTUAL_TIME_BUDGET.as_millis() as f64),
+            max_virtual_time_task_starvation_count: None,
+            initial_virtual_time: Some(self.time.timestamp() as f64),
+        })?;
+        Ok(())
+    }
+
+    /// The script run in every document before its own scripts
+    pub fn script(&self) -> String {
+        let css = format!(
+            "{} {} {{ display: none !important; }}",
+            MOTION_CSS,
+            self.hidden.join(", ")
+        );
+        format!(
+            r#"(() => {{
+  let state = {seed} >>> 0;
+  Math.random = function () {{
+    state = (state + 0x6D2B79F5) >>> 0;
+    let t = state;
+    t = Math.imul(t ^ (t >>> 15), t | 1);
+    t ^= t + Math.imul(t ^ (t >>> 7), t | 61);
+    return ((t ^ (t >>> 14)) >>> 0) / 4294967296;
+  }};
+  const addStyle = () => {{
+    const style = document.createElement('style');
+    style.textContent = {css};
+    (document.head || document.documentElement).appendChild(style);
+  }};
+  if (document.readyState === 'loading') {{
+    document.addEventListener('DOMContentLoaded', addStyle);
+  }} else {{
+    addStyle();
+  }}
+}})();"#,
+            seed = self.seed,
+            css = serde_json::to_string(&css).unwrap_or_default(),
+        )
+    }
+
+    /// Replace the creation and modification dates and the document ID Chrome writes
+    /// into a PDF with values derived from the frozen time
+    ///
+    /// Replacements keep the length of the original values, so the cross-reference
+    /// table stays valid. Values in an unexpected format are left alone.
+    pub fn normalize_pdf(&self, pdf: &mut [u8]) {
+        let date = self.time.format("D:%Y%m%d%H%M%S+00'00'").to_string();
+        for key in [&b"/CreationDate ("[..], &b"/ModDate ("[..]] {
+            replace_values(pdf, key, b')', |value| {
+                (value.len() == date.len()).then(|| date.as_bytes().to_vec())
+            });
+        }
+
+        let id = hex(&Sha256::digest(date.as_bytes()));
+        replace_values(pdf, b"/ID [<", b']', |value| {
+            // `HEX> <HEX>`, both of the same length
+            let half = value.len().checked_sub(4)? / 2;
+            let fits = value.len() == half * 2 + 4 && half <= id.len();
+            fits.then(|| format!("{0}> <{0}>", &id[..half]).into_bytes())
+        });
+    }
+}
+
+/// Replace every value following `key` up to `end` with the output of `replace`
+fn replace_values(
+    data: &mut [u8],
+    key: &[u8],
+    end: u8,
+    replace: impl Fn(&[u8]) -> Option<Vec<u8>>,
+) {
+    let mut from = 0;
+    while let Some(offset) = find(&data[from..], key) {
+        let start = from + offset + key.len();
+        let Some(len) = data[start..].iter().position(|&b| b == end) else {
+            break;
+        };
+        if let Some(value) = replace(&data[start..start + len]).filter(|v| v.len() == len) {
+            data[start..start + len].copy_from_slice(&value);
+        }
+        from = start + len;
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    use std::fmt::Write;
+    bytes.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{:02X}", b);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_pdf() {
+        let rendering = DeterministicRendering::new();
+        let pdf = b"%PDF-1.4\n<< /Creator (Chromium) /CreationDate (D:20261016135905+00'00') \
+                    /ModDate (D:20261016135905+00'00') >>\ntrailer << /Size 9 \
+                    /ID [<0123456789ABCDEF0123456789ABCDEF> <FEDCBA9876543210FEDCBA9876543210>] >>";
+        let mut first = pdf.to_vec();
+        rendering.normalize_pdf(&mut first);
+        let text = String::from_utf8(first.clone()).unwrap();
+        assert_eq!(first.len(), pdf.len());
+        assert!(text.contains("/CreationDate (D:20000101000000+00'00')"));
+        assert!(text.contains("/ModDate (D:20000101000000+00'00')"));
+        assert!(!text.contains("0123456789ABCDEF"));
+
+        // Another capture with other dates and ID normalizes to the same bytes
+        let mut second = String::from_utf8(pdf.to_vec())
+            .unwrap()
+            .replace("20261016135905", "20271231235959")
+            .replace("0123456789ABCDEF", "AAAAAAAAAAAAAAAA")
+            .into_bytes();
+        rendering.normalize_pdf(&mut second);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_script() {
+        let script = DeterministicRendering::new()
+            .with_seed(42)
+            .with_hidden(vec![".ticker".to_string()])
+            .script();
+        assert!(script.contains("let state = 42 >>> 0;"));
+        assert!(script.contains("animation: none !important"));
+        assert!(script.contains(".ticker { display: none !important; }"));
+    }
+}
@@ -0,0 +1,395 @@
+//! Visual diffs between two captures of a page
+//!
+//! Two Markdown captures of the same URL are compared line by line, and lines that were
+//! changed rather than added or removed are compared again word by word, so a reworded
+//! sentence shows just the words that changed. The result is rendered as a standalone
+//! HTML page (which can be printed to PDF) with additions and deletions highlighted,
+//! and long unchanged stretches folded away.
+
+use crate::extract::escape;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Unchanged lines shown around each change
+pub const CONTEXT_LINES: usize = 3;
+
+/// Largest number of line or word pairs compared exactly; beyond it changed stretches
+/// are shown as removed and added as a whole
+const MAX_COMPARISONS: usize = 25_000_000;
+
+/// Words, runs of whitespace, and single punctuation characters
+static TOKEN: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\s+|\w+|[^\w\s]").unwrap());
+
+/// A piece of a changed line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Span {
+    /// Text in both captures
+    Same(String),
+    /// Text only in the new capture
+    Added(String),
+    /// Text only in the old capture
+    Removed(String),
+}
+
+/// A line of a diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// A line in both captures
+    Same(String),
+    /// A line only in the new capture
+    Added(String),
+    /// A line only in the old capture
+    Removed(String),
+    /// A line reworded between the captures, word by word
+    Changed(Vec<Span>),
+}
+
+/// The differences between two captures of a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptureDiff {
+    /// The page title
+    pub title: String,
+    /// What the old capture is, e.g. its file name or capture time
+    pub old_label: String,
+    /// What the new capture is
+    pub new_label: String,
+    /// The lines of both captures, in order
+    pub lines: Vec<DiffLine>,
+}
+
+/// One step of an edit script turning one sequence into another
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Same(usize, usize),
+    Removed(usize),
+    Added(usize),
+}
+
+impl CaptureDiff {
+    /// Compare the Markdown of an old and a new capture
+    pub fn new(old: &str, new: &str) -> Self {
+        let old_lines: Vec<&str> = old.lines().collect();
+        let new_lines: Vec<&str> = new.lines().collect();
+        let mut lines = Vec::new();
+        let mut removed: Vec<&str> = Vec::new();
+        let mut added: Vec<&str> = Vec::new();
+        for edit in edit_script(&old_lines, &new_lines) {
+            match edit {
+                Edit::Removed(i) => removed.push(old_lines[i]),
+                Edit::Added(j) => added.push(new_lines[j]),
+                Edit::Same(i, _) => {
+                    flush_changes(&mut removed, &mut added, &mut lines);
+                    lines.push(DiffLine::Same(old_lines[i].to_string()));
+                }
+            }
+        }
+        flush_changes(&mut removed, &mut added, &mut lines);
+        Self {
+            title: String::new(),
+            old_label: "old".to_string(),
+            new_label: "new".to_string(),
+            lines,
+        }
+    }
+
+    /// Name the page the captures are of
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Name the old and the new capture
+    pub fn with_labels(mut self, old: impl Into<String>, new: impl Into<String>) -> Self {
+        self.old_label = old.into();
+        self.new_label = new.into();
+        self
+    }
+
+    /// Whether the captures have the same text
+    pub fn is_empty(&self) -> bool {
+        self.lines
+            .iter()
+            .all(|line| matches!(line, DiffLine::Same(_)))
+    }
+
+    /// The number of lines added and removed, counting a changed line as both
+    pub fn counts(&self) -> (usize, usize) {
+        self.lines
+            .iter()
+            .fold((0, 0), |(added, removed), line| match line {
+                DiffLine::Same(_) => (added, removed),
+                DiffLine::Added(_) => (added + 1, removed),
+                DiffLine::Removed(_) => (added, removed + 1),
+                DiffLine::Changed(_) => (added + 1, removed + 1),
+            })
+    }
+
+    /// The diff in the style of `diff -u`, without line numbers
+    pub fn to_text(&self) -> String {
+        let mut text = format!("--- {}\n+++ {}\n", self.old_label, self.new_label);
+        for item in self.visible_lines() {
+            match item {
+                Some(DiffLine::Same(line)) => text.push_str(&format!(" {}\n", line)),
+                Some(DiffLine::Added(line)) => text.push_str(&format!("+{}\n", line)),
+                Some(DiffLine::Removed(line)) => text.push_str(&format!("-{}\n", line)),
+                Some(DiffLine::Changed(spans)) => {
+                    let side = |keep: fn(&Span) -> Option<&str>| {
+                        spans.iter().filter_map(keep).collect::<String>()
+                    };
+                    text.push_str(&format!(
+                        "-{}\n+{}\n",
+                        side(|span| match span {
+                            Span::Same(text) | Span::Removed(text) => Some(text),
+                            Span::Added(_) => None,
+                        }),
+                        side(|span| match span {
+                            Span::Same(text) | Span::Added(text) => Some(text),
+                            Span::Removed(_) => None,
+                        })
+                    ));
+                }
+                None => text.push_str("@@\n"),
+            }
+        }
+        text
+    }
+
+    /// The diff as a standalone HTML page
+    pub fn to_html(&self) -> String {
+        let (added, removed) = self.counts();
+        let mut body = String::new();
+        for item in self.visible_lines() {
+            let line = match item {
+                Some(DiffLine::Same(line)) => {
+                    format!("<div class=\"same\">{}</div>", text_or_blank(line))
+                }
+                Some(DiffLine::Added(line)) => {
+                    format!(
+                        "<div class=\"added\"><ins>{}</ins></div>",
+                        text_or_blank(line)
+                    )
+                }
+                Some(DiffLine::Removed(line)) => {
+                    format!(
+                        "<div class=\"removed\"><del>{}</del></div>",
+                        text_or_blank(line)
+                    )
+                }
+                Some(DiffLine::Changed(spans)) => {
+                    let spans: String = spans
+                        .iter()
+                        .map(|span| match span {
+                            Span::Same(text) => escape(text, false),
+                            Span::Added(text) => format!("<ins>{}</ins>", escape(text, false)),
+                            Span::Removed(text) => format!("<del>{}</del>", escape(text, false)),
+                        })
+                        .collect();
+                    format!("<div class=\"changed\">{}</div>", spans)
+                }
+                None => "<div class=\"fold\">⋯</div>".to_string(),
+            };
+            body.push_str(&line);
+            body.push('\n');
+        }
+        if self.is_empty() {
+            body = "<p>No changes.</p>".to_string();
+        }
+        let title = match self.title.is_empty() {
+            true => "Changes".to_string(),
+            false => format!("Changes to {}", self.title),
+        };
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title>\
+             <style>body{{font-family:sans-serif;margin:2em}}\
+             .lines{{font-family:monospace;white-space:pre-wrap;border:1px solid #ddd}}\
+             .lines div{{padding:0 .5em}}.added{{background:#e6ffec}}\
+             .removed{{background:#ffebe9}}.changed{{background:#fff8c5}}\
+             ins{{background:#abf2bc;text-decoration:none}}\
+             del{{background:#ffc1c0}}.fold{{color:#888;background:#f6f8fa}}\
+             .summary{{color:#555}}</style></head><body><h1>{title}</h1>\
+             <p class=\"summary\"><del>{old}</del> → <ins>{new}</ins>: \
+             {added} line(s) added, {removed} line(s) removed</p>\
+             <div class=\"lines\">\n{body}</div></body></html>",
+            title = escape(&title, false),
+            old = escape(&self.old_label, false),
+            new = escape(&self.new_label, false),
+        )
+    }
+
+    /// The lines to show: the changes with [`CONTEXT_LINES`] around them, and None for
+    /// each stretch of unchanged lines left out
+    fn visible_lines(&self) -> Vec<Option<&DiffLine>> {
+        let changed: Vec<usize> = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !matches!(line, DiffLine::Same(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let near_change = |i: usize| {
+            let at = changed.partition_point(|&c| c + CONTEXT_LINES < i);
+            changed.get(at).is_some_and(|&c| c <= i + CONTEXT_LINES)
+        };
+        let mut visible = Vec::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if near_change(i) {
+                visible.push(Some(line));
+            } else if visible.last().is_none_or(Option::is_some) {
+                visible.push(None);
+            }
+        }
+        visible
+    }
+}
+
+/// Escaped text, or a non-breaking space to keep an empty line's height
+fn text_or_blank(text: &str) -> String {
+    match text.is_empty() {
+        true => "&nbsp;".to_string(),
+        false => escape(text, false),
+    }
+}
+
+/// Add the lines removed and added since the last unchanged line, pairing them up as
+/// changed lines when they are equally many
+fn flush_changes<'a>(
+    removed: &mut Vec<&'a str>,
+    added: &mut Vec<&'a str>,
+    lines: &mut Vec<DiffLine>,
+) {
+    if removed.len() == added.len() {
+        for (old, new) in removed.iter().zip(added.iter()) {
+            lines.push(DiffLine::Changed(word_diff(old, new)));
+        }
+    } else {
+        lines.extend(
+            removed
+                .iter()
+                .map(|line| DiffLine::Removed(line.to_string())),
+        );
+        lines.extend(added.iter().map(|line| DiffLine::Added(line.to_string())));
+    }
+    removed.clear();
+    added.clear();
+}
+
+/// The word-level differences of two versions of a line
+fn word_diff(old: &str, new: &str) -> Vec<Span> {
+    let old_tokens: Vec<&str> = TOKEN.find_iter(old).map(|m| m.as_str()).collect();
+    let new_tokens: Vec<&str> = TOKEN.find_iter(new).map(|m| m.as_str()).collect();
+    let mut spans: Vec<Span> = Vec::new();
+    for edit in edit_script(&old_tokens, &new_tokens) {
+        let span = match edit {
+            Edit::Same(i, _) => Span::Same(old_tokens[i].to_string()),
+            Edit::Removed(i) => Span::Removed(old_tokens[i].to_string()),
+            Edit::Added(j) => Span::Added(new_tokens[j].to_string()),
+        };
+        // Merge with the previous span of the same kind
+        match (spans.last_mut(), span) {
+            (Some(Span::Same(last)), Span::Same(text))
+            | (Some(Span::Added(last)), Span::Added(text))
+            | (Some(Span::Removed(last)), Span::Removed(text)) => last.push_str(&text),
+            (_, span) => spans.push(span),
+        }
+    }
+    spans
+}
+
+/// The shortest edit script between two sequences, from their longest common
+/// subsequence, with removals before additions at each change
+fn edit_script<T: PartialEq>(old: &[T], new: &[T]) -> Vec<Edit> {
+    // Common prefix and suffix are matched up front
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Same(i, i)).collect();
+    if a.len().saturating_mul(b.len()) > MAX_COMPARISONS {
+        edits.extend((0..a.len()).map(|i| Edit::Removed(prefix + i)));
+        edits.extend((0..b.len()).map(|j| Edit::Added(prefix + j)));
+    } else {
+        // lengths[i][j] is the length of the common subsequence of a[i..] and b[j..]
+        let width = b.len() + 1;
+        let mut lengths = vec![0u32; (a.len() + 1) * width];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lengths[i * width + j] = match a[i] == b[j] {
+                    true => lengths[(i + 1) * width + j + 1] + 1,
+                    false => lengths[(i + 1) * width + j].max(lengths[i * width + j + 1]),
+                };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        let mut added = Vec::new();
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                edits.append(&mut added);
+                edits.push(Edit::Same(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if j < b.len()
+                && (i == a.len() || lengths[i * width + j + 1] >= lengths[(i + 1) * width + j])
+            {
+                added.push(Edit::Added(prefix + j));
+                j += 1;
+            } else {
+                edits.push(Edit::Removed(prefix + i));
+                i += 1;
+            }
+        }
+        edits.append(&mut added);
+    }
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    edits.extend((0..suffix).map(|k| Edit::Same(old_end + k, new_end + k)));
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_diff() {
+        let old = "# Pricing\n\nThe basic plan costs $10 a month.\n\nContact sales.\n\n\
+                   a\nb\nc\nd\ne\nf\ng\nh\nFooter";
+        let new = "# Pricing\n\nThe basic plan costs $12 a month.\n\nContact sales.\n\nNew: a team plan.\n\n\
+                   a\nb\nc\nd\ne\nf\ng\nh\nFooter";
+        let diff = CaptureDiff::new(old, new).with_labels("2026-01-01", "2026-02-01");
+        assert!(!diff.is_empty());
+        assert_eq!(
+            diff.lines[2],
+            DiffLine::Changed(vec![
+                Span::Same("The basic plan costs $".to_string()),
+                Span::Removed("10".to_string()),
+                Span::Added("12".to_string()),
+                Span::Same(" a month.".to_string()),
+            ])
+        );
+        assert_eq!(diff.counts(), (3, 1));
+
+        let text = diff.to_text();
+        assert!(text.starts_with("--- 2026-01-01\n+++ 2026-02-01\n"));
+        assert!(
+            text.contains(
+                "-The basic plan costs $10 a month.\n+The basic plan costs $12 a month.\n"
+            )
+        );
+        assert!(text.contains("+New: a team plan.\n"));
+        // Unchanged lines far from the changes are folded
+        assert!(!text.contains(" e\n"));
+        assert!(text.ends_with("@@\n"));
+
+        let html = diff.to_html();
+        assert!(html.contains("<del>10</del><ins>12</ins>"));
+        assert!(html.contains("<div class=\"added\"><ins>New: a team plan.</ins></div>"));
+        assert!(CaptureDiff::new(old, old).is_empty());
+    }
+}
@@ -0,0 +1,337 @@
+//! DNS resolution snapshots of captured hosts
+//!
+//! Domains change hands: a site captured today may be served by someone else next
+//! year, and the addresses a host resolved to at capture time help show who served
+//! the archived content. A [`DnsSnapshot`] records the CNAME chain and the A and AAAA
+//! addresses of a host, queried directly from the system's nameserver so the chain of
+//! aliases is seen, not only the final addresses.
+
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::Path;
+use std::time::Duration;
+
+/// How long to wait for each answer
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where the system's nameservers are configured on Unix
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+/// Record types queried or read from answers
+pub(crate) const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+pub(crate) const TYPE_AAAA: u16 = 28;
+
+/// The resolution of a host at a point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DnsSnapshot {
+    /// Host name resolved
+    pub host: String,
+    /// When the host was resolved
+    pub resolved_at: DateTime<Utc>,
+    /// Nameserver queried, as an address or a DNS-over-HTTPS URL, `override` for hosts
+    /// mapped to fixed addresses (see [`crate::resolver`]), or None when the system
+    /// resolver was used, which does not report aliases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nameserver: Option<String>,
+    /// Aliases from the host to its canonical name, in the order they were followed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cnames: Vec<String>,
+    /// IPv4 addresses (A records)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ipv4: Vec<Ipv4Addr>,
+    /// IPv6 addresses (AAAA records)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ipv6: Vec<Ipv6Addr>,
+}
+
+impl DnsSnapshot {
+    /// Resolve a host, through the first nameserver of `/etc/resolv.conf` when there is
+    /// one and through the system resolver otherwise
+    ///
+    /// This blocks for up to twice `timeout`; call it from a blocking task.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host does not exist or the nameserver does not answer
+    pub fn resolve(host: &str, timeout: Duration) -> Result<Self> {
+        let mut snapshot = Self::new(host);
+        if snapshot.host.parse::<IpAddr>().is_ok() {
+            return Ok(snapshot);
+        }
+        let host = snapshot.host.clone();
+        let host = host.as_str();
+
+        let nameserver = std::fs::read_to_string(Path::new(RESOLV_CONF))
+            .ok()
+            .and_then(|conf| nameserver(&conf));
+        let Some(nameserver) = nameserver else {
+            let addresses = (host, 0)
+                .to_socket_addrs()
+                .with_context(|| format!("Failed to resolve {}", host))?;
+            addresses.for_each(|address| snapshot.add_address(address.ip()));
+            return Ok(snapshot);
+        };
+
+        snapshot.nameserver = Some(nameserver.to_string());
+        let server = SocketAddr::new(nameserver, 53);
+        for record_type in [TYPE_A, TYPE_AAAA] {
+            snapshot.add_answer(query(server, host, record_type, timeout)?);
+        }
+        Ok(snapshot)
+    }
+
+    /// A snapshot of a host without records, taken now; an IP address resolves to
+    /// itself
+    pub(crate) fn new(host: &str) -> Self {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let mut snapshot = Self {
+            host: host.to_string(),
+            resolved_at: Utc::now(),
+            nameserver: None,
+            cnames: Vec::new(),
+            ipv4: Vec::new(),
+            ipv6: Vec::new(),
+        };
+        if let Ok(address) = host.parse::<IpAddr>() {
+            snapshot.add_address(address);
+        }
+        snapshot
+    }
+
+    /// Add the records of an answer, taking the aliases from the first answer with any
+    pub(crate) fn add_answer(&mut self, answer: Answer) {
+        if self.cnames.is_empty() {
+            self.cnames = answer.cname_chain(&self.host);
+        }
+        answer
+            .addresses
+            .into_iter()
+            .for_each(|address| self.add_address(address));
+    }
+
+    /// All resolved addresses, IPv4 first
+    pub fn addresses(&self) -> Vec<IpAddr> {
+        let ipv4 = self.ipv4.iter().copied().map(IpAddr::V4);
+        ipv4.chain(self.ipv6.iter().copied().map(IpAddr::V6))
+            .collect()
+    }
+
+    pub(crate) fn add_address(&mut self, address: IpAddr) {
+        match address {
+            IpAddr::V4(address) if !self.ipv4.contains(&address) => self.ipv4.push(address),
+            IpAddr::V6(address) if !self.ipv6.contains(&address) => self.ipv6.push(address),
+            _ => {}
+        }
+    }
+}
+
+/// The first nameserver of a resolv.conf file
+fn nameserver(conf: &str) -> Option<IpAddr> {
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("nameserver"))
+        .find_map(|address| {
+            // Link-local IPv6 nameservers carry a zone, e.g. fe80::1%eth0
+            let address = address.trim();
+            address.split('%').next()?.parse().ok()
+        })
+}
+
+/// The records of an answer to a query
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct Answer {
+    /// CNAME records, as (alias, target)
+    cnames: Vec<(String, String)>,
+    /// Addresses of A and AAAA records
+    addresses: Vec<IpAddr>,
+}
+
+impl Answer {
+    /// The aliases followed from `host`, guarding against loops
+    fn cname_chain(&self, host: &str) -> Vec<String> {
+        let mut chain: Vec<String> = Vec::new();
+        let mut current = host;
+        while let Some((_, target)) = self
+            .cnames
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(current))
+        {
+            if chain.len() >= self.cnames.len() {
+                break;
+            }
+            chain.push(target.clone());
+            current = target;
+        }
+        chain
+    }
+}
+
+/// Ask a nameserver for the records of one type of a host
+pub(crate) fn query(
+    server: SocketAddr,
+    host: &str,
+    record_type: u16,
+    timeout: Duration,
+) -> Result<Answer> {
+    let bind = match server {
+        SocketAddr::V4(_) => "0.0.0.0:0",
+        SocketAddr::V6(_) => "[::]:0",
+    };
+    let socket = UdpSocket::bind(bind)?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+    socket.connect(server)?;
+
+    let id = Utc::now().timestamp_subsec_nanos() as u16;
+    socket.send(&request(id, host, record_type)?)?;
+    let mut reply = [0u8; 4096];
+    let len = socket
+        .recv(&mut reply)
+        .with_context(|| format!("No reply from nameserver {}", server.ip()))?;
+    parse_reply(&reply[..len], id, host)
+}
+
+/// A recursive query for the records of one type of a host
+pub(crate) fn request(id: u16, host: &str, record_type: u16) -> Result<Vec<u8>> {
+    let mut message = Vec::with_capacity(host.len() + 18);
+    message.extend_from_slice(&id.to_be_bytes());
+    // Recursion desired, one question
+    message.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!("Invalid host name {}", host);
+        }
+        message.push(label.len() as u8);
+        message.extend_from_slice(label.as_bytes());
+    }
+    message.push(0);
+    message.extend_from_slice(&record_type.to_be_bytes());
+    // Class IN
+    message.extend_from_slice(&[0, 1]);
+    Ok(message)
+}
+
+/// Read the CNAME and address records of the answer to a query
+pub(crate) fn parse_reply(reply: &[u8], id: u16, host: &str) -> Result<Answer> {
+    if reply.len() < 12 || reply[..2] != id.to_be_bytes() || reply[2] & 0x80 == 0 {
+        bail!("Unexpected reply to the DNS query for {}", host);
+    }
+    match reply[3] & 0x0f {
+        0 => {}
+        3 => bail!("Host {} does not exist", host),
+        code => bail!("DNS query for {} failed with code {}", host, code),
+    }
+    let questions = u16::from_be_bytes([reply[4], reply[5]]);
+    let answers = u16::from_be_bytes([reply[6], reply[7]]);
+
+    let mut offset = 12;
+    for _ in 0..questions {
+        offset = read_name(reply, offset)?.1 + 4;
+    }
+    let mut answer = Answer::default();
+    for _ in 0..answers {
+        let (name, end) = read_name(reply, offset)?;
+        let header = reply.get(end..end + 10).context("Truncated DNS reply")?;
+        let record_type = u16::from_be_bytes([header[0], header[1]]);
+        let len = u16::from_be_bytes([header[8], header[9]]) as usize;
+        let data = reply
+            .get(end + 10..end + 10 + len)
+            .context("Truncated DNS reply")?;
+        match (record_type, len) {
+            (TYPE_A, 4) => answer
+                .addresses
+                .push(IpAddr::from(<[u8; 4]>::try_from(data)?)),
+            (TYPE_AAAA, 16) => answer
+                .addresses
+                .push(IpAddr::from(<[u8; 16]>::try_from(data)?)),
+            (TYPE_CNAME, _) => answer.cnames.push((name, read_name(reply, end + 10)?.0)),
+            _ => {}
+        }
+        offset = end + 10 + len;
+    }
+    Ok(answer)
+}
+
+/// Read a possibly compressed name at `offset`, returning it and the offset after it
+fn read_name(message: &[u8], offset: usize) -> Result<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut position = offset;
+    let mut end = None;
+    // Each pointer must go back, so a message can hold at most this many
+    for _ in 0..message.len() {
+        let len = *message.get(position).context("Truncated DNS name")? as usize;
+        match len {
+            0 => {
+                return Ok((labels.join("."), end.unwrap_or(position + 1)));
+            }
+            0xc0.. => {
+                let low = *message.get(position + 1).context("Truncated DNS name")? as usize;
+                end.get_or_insert(position + 2);
+                let target = ((len & 0x3f) << 8) | low;
+                if target >= position {
+                    bail!("Invalid DNS name pointer");
+                }
+                position = target;
+            }
+            1..=63 => {
+                let label = message
+                    .get(position + 1..position + 1 + len)
+                    .context("Truncated DNS name")?;
+                labels.push(String::from_utf8_lossy(label).to_lowercase());
+                position += 1 + len;
+            }
+            _ => bail!("Invalid DNS name label"),
+        }
+    }
+    bail!("Invalid DNS name")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reply() {
+        let conf = "# generated\nsearch lan\nnameserver fe80::1%eth0\nnameserver 10.0.0.1\n";
+        assert_eq!(nameserver(conf), Some("fe80::1".parse().unwrap()));
+        assert_eq!(nameserver("search lan\n"), None);
+
+        let query = request(0x1234, "www.example.com", TYPE_A).unwrap();
+        assert_eq!(query.len(), 12 + 17 + 4);
+        assert_eq!(&query[12..16], b"\x03www");
+        assert!(request(1, "a..b", TYPE_A).is_err());
+
+        // www.example.com CNAME edge.cdn.net (pointing at the question's "www"), then
+        // edge.cdn.net A 192.0.2.7
+        let mut reply = query.clone();
+        reply[2] = 0x81;
+        reply[3] = 0x80;
+        reply[7] = 2;
+        reply.extend_from_slice(&[0xc0, 12, 0, 5, 0, 1, 0, 0, 0, 60, 0, 14]);
+        let cname_at = reply.len();
+        reply.extend_from_slice(b"\x04edge\x03cdn\x03net\x00");
+        reply.extend_from_slice(&[0xc0, cname_at as u8, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4]);
+        reply.extend_from_slice(&[192, 0, 2, 7]);
+
+        let answer = parse_reply(&reply, 0x1234, "www.example.com").unwrap();
+        assert_eq!(
+            answer.cnames,
+            [("www.example.com".to_string(), "edge.cdn.net".to_string())]
+        );
+        assert_eq!(answer.addresses, [IpAddr::from([192, 0, 2, 7])]);
+        assert_eq!(answer.cname_chain("WWW.example.com"), ["edge.cdn.net"]);
+        assert!(answer.cname_chain("example.com").is_empty());
+
+        assert!(parse_reply(&reply, 0x4321, "www.example.com").is_err());
+        reply[3] = 0x83;
+        let error = parse_reply(&reply, 0x1234, "www.example.com").unwrap_err();
+        assert!(error.to_string().contains("does not exist"));
+
+        let snapshot = DnsSnapshot::resolve("[::1]", DEFAULT_TIMEOUT).unwrap();
+        assert_eq!(snapshot.host, "::1");
+        assert_eq!(snapshot.addresses(), [IpAddr::V6(Ipv6Addr::LOCALHOST)]);
+        assert_eq!(snapshot.nameserver, None);
+    }
+}
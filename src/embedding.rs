@@ -0,0 +1,404 @@
+//! Text embedding generation for converted Markdown
+//!
+//! This module reads converted Markdown files (including chunk files with their
+//! metadata headers), computes embeddings through an OpenAI-compatible `/embeddings`
+//! endpoint, and writes one JSON record per file to a JSONL file, ready to be loaded
+//! into a vector store.
+
+use crate::atomic;
+use crate::frontmatter;
+use anyhow::Result;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tracing::info;
+
+/// Default OpenAI-compatible API base URL
+pub const DEFAULT_EMBEDDING_ENDPOINT: &str = "https://api.openai.com/v1";
+
+/// Default embedding model
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Default number of texts sent per embeddings request
+pub const DEFAULT_EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// Client for an OpenAI-compatible embeddings endpoint
+pub struct EmbeddingClient {
+    client: Client,
+    endpoint: String,
+    model: String,
+    api_key: Option<String>,
+    batch_size: usize,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+impl EmbeddingClient {
+    /// Create a client for the API at `endpoint` using `model`
+    ///
+    /// `endpoint` is the API base URL (e.g. `https://api.openai.com/v1`); `/embeddings`
+    /// is appended unless it is already present.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new(endpoint: &str, model: &str) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(60))
+            .user_agent("webpage-save-embeddings/1.0")
+            .build()?;
+
+        let endpoint = endpoint.trim_end_matches('/');
+        let endpoint = if endpoint.ends_with("/embeddings") {
+            endpoint.to_string()
+        } else {
+            format!("{}/embeddings", endpoint)
+        };
+
+        Ok(Self {
+            client,
+            endpoint,
+            model: model.to_string(),
+            api_key: None,
+            batch_size: DEFAULT_EMBEDDING_BATCH_SIZE,
+        })
+    }
+
+    /// Authenticate requests with a bearer token
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Set the number of texts sent per request (at least one)
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The model used for embeddings
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    /// Compute embeddings for texts, returned in the same order
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails, the endpoint returns an error status, or the
+    /// response does not contain one embedding per input
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            embeddings.extend(self.embed_batch(batch).await?);
+        }
+        Ok(embeddings)
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut request = self.client.post(&self.endpoint).json(&EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+        });
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!(
+                "Embeddings request failed with status {}: {}",
+                status,
+                body.chars().take(500).collect::<String>()
+            ));
+        }
+
+        let mut data = response.json::<EmbeddingResponse>().await?.data;
+        if data.len() != texts.len() {
+            return Err(anyhow::anyhow!(
+                "Expected {} embeddings but received {}",
+                texts.len(),
+                data.len()
+            ));
+        }
+        data.sort_by_key(|item| item.index);
+        Ok(data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+/// A Markdown file prepared for embedding
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddingInput {
+    /// The Markdown file the text was read from
+    pub file: PathBuf,
+    /// The page title, if recorded
+    pub title: Option<String>,
+    /// The URL the page came from, if recorded
+    pub source: Option<String>,
+    /// Position of the chunk within its document (1-based), for chunk files
+    pub chunk: Option<u64>,
+    /// Number of chunks in the document, for chunk files
+    pub chunks: Option<u64>,
+    /// The text to embed, without the metadata header
+    pub text: String,
+}
+
+impl EmbeddingInput {
+    /// Parse a converted Markdown document or chunk file
+    ///
+    /// Chunk files carry their metadata in YAML front matter, as do documents saved
+    /// with `--front-matter` (YAML or TOML); other documents record the source in their
+    /// `*Source: [...](...)*` line.
+    pub fn parse(file: impl Into<PathBuf>, content: &str) -> Self {
+        let mut input = Self {
+            file: file.into(),
+            title: None,
+            source: None,
+            chunk: None,
+            chunks: None,
+            text: content.trim().to_string(),
+        };
+
+        if let Some((front_matter, body)) = frontmatter::split_front_matter(content) {
+            for (key, value) in frontmatter::fields(front_matter) {
+                let value = serde_json::from_str::<serde_json::Value>(value)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+                match key {
+                    "title" => input.title = value.as_str().map(str::to_string),
+                    "source" => input.source = value.as_str().map(str::to_string),
+                    "chunk" => input.chunk = value.as_u64(),
+                    "chunks" => input.chunks = value.as_u64(),
+                    _ => {}
+                }
+            }
+            input.text = body.trim().to_string();
+        } else {
+            let source_line =
+                regex::Regex::new(r"(?m)^\*Source: \[[^\]]*\]\(([^)]+)\)\*$").unwrap();
+            input.source = source_line
+                .captures(content)
+                .map(|captures| captures[1].to_string());
+            input.title = content
+                .lines()
+                .next()
+                .and_then(|line| line.strip_prefix("# "))
+                .map(|title| title.trim().to_string());
+        }
+
+        input
+    }
+
+    /// Read and parse a Markdown file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read
+    pub async fn read(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).await?;
+        Ok(Self::parse(path, &content))
+    }
+}
+
+/// An embedded text with references back to the file and chunk it came from
+#[derive(Debug, Clone, Serialize)]
+pub struct EmbeddingRecord {
+    /// Stable identifier: the file path, plus the chunk number for chunk files
+    pub id: String,
+    /// The Markdown file the text was read from
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<u64>,
+    /// The embedding model
+    pub model: String,
+    /// The embedded text
+    pub text: String,
+    /// The embedding vector
+    pub embedding: Vec<f32>,
+}
+
+/// Whether a path is a Markdown file
+pub fn is_markdown_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("md"))
+        .unwrap_or(false)
+}
+
+/// Embed Markdown files and write the records to a JSONL file
+///
+/// Empty files are skipped.
+///
+/// # Returns
+///
+/// Returns the number of records written
+///
+/// # Errors
+///
+/// Returns an error if a file cannot be read, the embeddings request fails, or the
+/// output cannot be written
+pub async fn embed_files(
+    client: &EmbeddingClient,
+    files: &[PathBuf],
+    output_path: &Path,
+) -> Result<usize> {
+    let mut inputs = Vec::with_capacity(files.len());
+    for file in files {
+        let input = EmbeddingInput::read(file).await?;
+        if !input.text.is_empty() {
+            inputs.push(input);
+        }
+    }
+
+    info!(
+        "Embedding {} Markdown files with {}",
+        inputs.len(),
+        client.model()
+    );
+    let texts: Vec<String> = inputs.iter().map(|input| input.text.clone()).collect();
+    let embeddings = client.embed(&texts).await?;
+
+    let mut jsonl = String::new();
+    for (input, embedding) in inputs.into_iter().zip(embeddings) {
+        let file = input.file.display().to_string();
+        let record = EmbeddingRecord {
+            id: match input.chunk {
+                Some(chunk) => format!("{}#{}", file, chunk),
+                None => file.clone(),
+            },
+            file,
+            title: input.title,
+            source: input.source,
+            chunk: input.chunk,
+            chunks: input.chunks,
+            model: client.model().to_string(),
+            text: input.text,
+            embedding,
+        };
+        jsonl.push_str(&serde_json::to_string(&record)?);
+        jsonl.push('\n');
+    }
+
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    atomic::write(output_path, jsonl).await?;
+    Ok(texts.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_parse_chunk_file() {
+        let content = "---\ntitle: \"A: title\"\nsource: \"https://example.com\"\nchunk: 2\nchunks: 3\nunit: tokens\nsize: 5\n---\n\nBody text\n";
+        let input = EmbeddingInput::parse("page_002.md", content);
+        assert_eq!(input.title.as_deref(), Some("A: title"));
+        assert_eq!(input.source.as_deref(), Some("https://example.com"));
+        assert_eq!(input.chunk, Some(2));
+        assert_eq!(input.chunks, Some(3));
+        assert_eq!(input.text, "Body text");
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let content =
+            "# Title\n\n*Source: [https://example.com/a](https://example.com/a)*\n\n---\n\nBody";
+        let input = EmbeddingInput::parse("page.md", content);
+        assert_eq!(input.title.as_deref(), Some("Title"));
+        assert_eq!(input.source.as_deref(), Some("https://example.com/a"));
+        assert_eq!(input.chunk, None);
+        assert_eq!(input.text, content);
+    }
+
+    #[tokio::test]
+    async fn test_embed_files() -> Result<()> {
+        // Minimal OpenAI-compatible server answering a single request
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = format!("http://{}/v1", listener.local_addr()?);
+        let server = std::thread::spawn(move || -> std::io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            loop {
+                let read = stream.read(&mut buffer)?;
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                    let length = headers
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|length| length.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+
+            let body = r#"{"data":[{"index":1,"embedding":[0.5]},{"index":0,"embedding":[0.25]}]}"#;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )?;
+            Ok(String::from_utf8_lossy(&request).into_owned())
+        });
+
+        let temp_dir = TempDir::new()?;
+        let first = temp_dir.path().join("a_001.md");
+        let second = temp_dir.path().join("a_002.md");
+        std::fs::write(&first, "---\nchunk: 1\nchunks: 2\n---\n\nfirst")?;
+        std::fs::write(&second, "---\nchunk: 2\nchunks: 2\n---\n\nsecond")?;
+        let output = temp_dir.path().join("vectors.jsonl");
+
+        let client =
+            EmbeddingClient::new(&endpoint, "test-model")?.with_api_key(Some("secret".to_string()));
+        let count = embed_files(&client, &[first, second], &output).await?;
+        assert_eq!(count, 2);
+
+        let request = server.join().unwrap()?;
+        assert!(request.starts_with("POST /v1/embeddings"));
+        assert!(request.contains("authorization: Bearer secret"));
+
+        let lines: Vec<serde_json::Value> = std::fs::read_to_string(&output)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["text"], "first");
+        assert_eq!(lines[0]["embedding"][0], 0.25);
+        assert_eq!(lines[1]["chunk"], 2);
+        assert!(lines[1]["id"].as_str().unwrap().ends_with("a_002.md#2"));
+        Ok(())
+    }
+}
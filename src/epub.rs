@@ -0,0 +1,786 @@
+//! EPUB books of articles
+//!
+//! Long articles read better on an e-reader that reflows the text than as a printed
+//! page. The EPUB generator extracts the main content of a page as Markdown conversion
+//! does, rewrites it as XHTML, and packages it as an EPUB 3 book with the page title,
+//! author, language, and URL as metadata and the images of the article embedded. The
+//! cover is the page's Open Graph image, or a title page drawn as SVG when it has none.
+//!
+//! An EPUB file is a ZIP archive whose first entry is the `mimetype` file, stored
+//! uncompressed so readers can identify the file by its first bytes. Every other entry
+//! is deflated.
+
+use crate::alttext;
+use crate::atomic;
+use crate::extract;
+use crate::images::ImageFormat;
+use crate::markdown::MarkdownGenerator;
+use crate::warc;
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use percent_encoding::percent_decode_str;
+use select::document::Document;
+use select::node::{Data, Node};
+use select::predicate::Name;
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use tracing::{info, warn};
+use url::Url;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+/// Images larger than this are left out of books
+pub const DEFAULT_MAX_IMAGE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// At most this many images are embedded in a book
+pub const DEFAULT_MAX_IMAGES: usize = 200;
+
+/// The content of the `mimetype` entry
+const MIMETYPE: &str = "application/epub+zip";
+
+/// Elements left out of books: scripts, embedded content, and forms
+const DROPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "iframe", "object", "embed", "video", "audio",
+    "canvas", "svg", "math", "source", "track", "form", "input", "button", "select", "textarea",
+    "link", "meta", "base", "head", "title",
+];
+
+/// Attributes left out of books, besides event handlers
+const DROPPED_ATTRIBUTES: &[&str] = &["srcset", "sizes", "loading", "decoding", "xmlns"];
+
+const CONTAINER_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="EPUB/package.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+const STYLESHEET: &str = "body { margin: 0 5%; line-height: 1.5; }
+h1 { line-height: 1.2; }
+img { max-width: 100%; height: auto; }
+pre { white-space: pre-wrap; font-size: 0.85em; }
+blockquote { margin: 1em 1.5em; font-style: italic; }
+p.source { font-size: 0.8em; word-break: break-all; }
+div.cover { text-align: center; }
+div.cover img { max-height: 95vh; }
+";
+
+/// EPUB generator that converts the main content of pages to books
+///
+/// Pages are fetched and their main content extracted by a [`MarkdownGenerator`], so
+/// its page cache, offline mode, browser rendering, and extraction settings apply. In
+/// offline mode, only images embedded in the page as `data:` URLs are included.
+pub struct EpubGenerator {
+    markdown: MarkdownGenerator,
+    max_image_bytes: u64,
+    max_images: usize,
+}
+
+/// An image embedded in a book
+struct BookImage {
+    /// The file name under `EPUB/images/`
+    name: String,
+    media_type: &'static str,
+    data: Vec<u8>,
+}
+
+/// A book before it is packaged
+struct Book {
+    identifier: String,
+    title: String,
+    language: String,
+    author: Option<String>,
+    description: Option<String>,
+    published: Option<String>,
+    url: Option<String>,
+    /// The XHTML body of the article
+    content: String,
+    /// The ids and titles of the article's sections, for the table of contents
+    sections: Vec<(String, String)>,
+    images: Vec<BookImage>,
+    /// The cover image, which is not shown in the article
+    cover: BookImage,
+}
+
+impl EpubGenerator {
+    /// Create an EPUB generator fetching and extracting pages with `markdown`
+    pub fn new(markdown: MarkdownGenerator) -> Self {
+        Self {
+            markdown,
+            max_image_bytes: DEFAULT_MAX_IMAGE_BYTES,
+            max_images: DEFAULT_MAX_IMAGES,
+        }
+    }
+
+    /// Leave out images larger than this many bytes
+    pub fn with_max_image_bytes(mut self, max_image_bytes: u64) -> Self {
+        self.max_image_bytes = max_image_bytes;
+        self
+    }
+
+    /// Embed at most this many images per book
+    pub fn with_max_images(mut self, max_images: usize) -> Self {
+        self.max_images = max_images;
+        self
+    }
+
+    /// Convert a URL to an EPUB book
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to convert
+    /// * `output_path` - Optional output file path. If None, returns the book without saving
+    ///
+    /// # Returns
+    ///
+    /// Returns the EPUB file as bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The URL is invalid or cannot be accessed
+    /// - The HTTP request fails
+    /// - File I/O operations fail
+    pub async fn url_to_epub(&self, url: &str, output_path: Option<&Path>) -> Result<Vec<u8>> {
+        let parsed_url = Url::parse(url)?;
+        if !matches!(parsed_url.scheme(), "http" | "https") {
+            return Err(anyhow::anyhow!("Only HTTP and HTTPS URLs are supported"));
+        }
+        let html_content = self.markdown.url_to_html(url).await?;
+        self.html_to_epub(&html_content, Some(url), output_path)
+            .await
+    }
+
+    /// Convert HTML content to an EPUB book
+    ///
+    /// Relative links and images are resolved against `url`, or the page's own
+    /// `<base href>`. Images that cannot be fetched are left out.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if HTML parsing fails or the file cannot be written
+    pub async fn html_to_epub(
+        &self,
+        html_content: &str,
+        url: Option<&str>,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        let book = self.book(html_content, url).await?;
+        let data = book.to_epub(Utc::now())?;
+        if let Some(path) = output_path {
+            atomic::write(path, &data).await?;
+        }
+        Ok(data)
+    }
+
+    /// Extract the article of a page and fetch its images
+    async fn book(&self, html_content: &str, url: Option<&str>) -> Result<Book> {
+        let document = Document::from(html_content);
+        let meta = |key: &str| {
+            document
+                .find(Name("meta"))
+                .find(|meta| meta.attr("name") == Some(key) || meta.attr("property") == Some(key))
+                .and_then(|meta| meta.attr("content"))
+                .map(|content| content.trim().to_string())
+                .filter(|content| !content.is_empty())
+        };
+        let base = url
+            .map(str::to_string)
+            .or_else(|| {
+                document
+                    .find(Name("base"))
+                    .find_map(|base| base.attr("href").map(str::to_string))
+            })
+            .and_then(|base| Url::parse(&base).ok());
+
+        let title = self
+            .markdown
+            .extract_title(html_content)
+            .unwrap_or_else(|| "Untitled".to_string());
+        let language = document
+            .find(Name("html"))
+            .find_map(|html| html.attr("lang").map(str::trim))
+            .filter(|lang| {
+                !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            })
+            .unwrap_or("en")
+            .to_string();
+        // Profile URLs are not names
+        let author = meta("author")
+            .or_else(|| meta("article:author"))
+            .filter(|author| Url::parse(author).is_err());
+
+        let main_content = self.markdown.extract_main_content(html_content)?;
+        let article = Document::from(main_content.as_str());
+        let mut images = Vec::new();
+        let mut image_names = HashMap::new();
+        for image in article.find(Name("img")) {
+            if images.len() >= self.max_images {
+                warn!("Embedding only the first {} images", self.max_images);
+                break;
+            }
+            let Some(source) = alttext::source(&image) else {
+                continue;
+            };
+            let Some(image_url) = resolve(base.as_ref(), &source) else {
+                continue;
+            };
+            if image_names.contains_key(image_url.as_str()) {
+                continue;
+            }
+            let name = format!("image-{}", images.len() + 1);
+            match self.fetch_image(&image_url, &name).await {
+                Ok(image) => {
+                    image_names.insert(image_url.to_string(), image.name.clone());
+                    images.push(image);
+                }
+                Err(e) => warn!("Leaving out image {}: {}", short_url(&image_url), e),
+            }
+        }
+
+        let cover_url = meta("og:image").and_then(|image| resolve(base.as_ref(), &image));
+        let cover = match cover_url {
+            Some(cover_url) => match self.fetch_image(&cover_url, "cover").await {
+                Ok(cover) => Some(cover),
+                Err(e) => {
+                    warn!(
+                        "Drawing a cover instead of {}: {}",
+                        short_url(&cover_url),
+                        e
+                    );
+                    None
+                }
+            },
+            None => None,
+        };
+        let cover = cover.unwrap_or_else(|| BookImage {
+            name: "cover.svg".to_string(),
+            media_type: "image/svg+xml",
+            data: cover_svg(&title, base.as_ref().and_then(Url::host_str)).into_bytes(),
+        });
+
+        let mut xhtml = Xhtml {
+            base: base.as_ref(),
+            images: &image_names,
+            content: String::new(),
+            sections: Vec::new(),
+        };
+        if let Some(body) = article.find(Name("body")).next() {
+            for child in body.children() {
+                xhtml.write(&child);
+            }
+        }
+        // The title is shown above the article unless it starts with its own
+        let show_title = article
+            .find(Name("h1"))
+            .next()
+            .is_none_or(|heading| heading.text().trim() != title);
+        let mut content = String::new();
+        if show_title {
+            content.push_str(&format!("<h1>{}</h1>\n", xml_escape(&title)));
+        }
+        if let Some(url) = url {
+            content.push_str(&format!(
+                "<p class=\"source\"><a href=\"{}\">{}</a></p>\n",
+                xml_escape(url),
+                xml_escape(url)
+            ));
+        }
+        content.push_str(&xhtml.content);
+
+        info!("Embedding {} images in {}", images.len(), title);
+        Ok(Book {
+            identifier: warc::uuid_urn(url.unwrap_or(html_content)),
+            title,
+            language,
+            author,
+            description: meta("description").or_else(|| meta("og:description")),
+            published: meta("article:published_time"),
+            url: url.map(str::to_string),
+            content,
+            sections: xhtml.sections,
+            images,
+            cover,
+        })
+    }
+
+    /// Download an image, or decode it from a `data:` URL, naming it after its format
+    async fn fetch_image(&self, url: &Url, stem: &str) -> Result<BookImage> {
+        let data = match url.scheme() {
+            "data" => {
+                let (header, payload) = url
+                    .as_str()
+                    .split_once(',')
+                    .ok_or_else(|| anyhow::anyhow!("Malformed data URL"))?;
+                match header.ends_with(";base64") {
+                    true => base64::engine::general_purpose::STANDARD.decode(payload.trim())?,
+                    false => percent_decode_str(payload).collect(),
+                }
+            }
+            "http" | "https" if !self.markdown.is_offline() => {
+                let response = self
+                    .markdown
+                    .client()
+                    .get(url.as_str())
+                    .send()
+                    .await?
+                    .error_for_status()?;
+                if response
+                    .content_length()
+                    .is_some_and(|length| length > self.max_image_bytes)
+                {
+                    return Err(anyhow::anyhow!(
+                        "Larger than {} bytes",
+                        self.max_image_bytes
+                    ));
+                }
+                response.bytes().await?.to_vec()
+            }
+            _ => return Err(anyhow::anyhow!("Not available offline")),
+        };
+        if data.len() as u64 > self.max_image_bytes {
+            return Err(anyhow::anyhow!(
+                "Larger than {} bytes",
+                self.max_image_bytes
+            ));
+        }
+        let (media_type, extension) = image_type(&data)
+            .ok_or_else(|| anyhow::anyhow!("Not a PNG, JPEG, GIF, WebP, or SVG image"))?;
+        Ok(BookImage {
+            name: format!("{}.{}", stem, extension),
+            media_type,
+            data,
+        })
+    }
+}
+
+impl Book {
+    /// Package the book as an EPUB file, marked as modified at `modified`
+    fn to_epub(&self, modified: DateTime<Utc>) -> Result<Vec<u8>> {
+        // MS-DOS dates of ZIP entries run from 1980 to 2107
+        let modified_time = zip::DateTime::from_date_and_time(
+            modified.year().clamp(1980, 2107) as u16,
+            modified.month() as u8,
+            modified.day() as u8,
+            modified.hour() as u8,
+            modified.minute() as u8,
+            modified.second() as u8,
+        )
+        .map_err(|_| anyhow::anyhow!("Invalid modification time: {}", modified))?;
+        let stored = SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Stored)
+            .last_modified_time(modified_time);
+        let deflated = stored.compression_method(CompressionMethod::Deflated);
+
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        let mut add = |name: &str, contents: &[u8], options: SimpleFileOptions| -> Result<()> {
+            zip.start_file(name, options)?;
+            zip.write_all(contents)?;
+            Ok(())
+        };
+        add("mimetype", MIMETYPE.as_bytes(), stored)?;
+        add("META-INF/container.xml", CONTAINER_XML.as_bytes(), deflated)?;
+        add(
+            "EPUB/package.opf",
+            self.package(modified).as_bytes(),
+            deflated,
+        )?;
+        add("EPUB/nav.xhtml", self.nav().as_bytes(), deflated)?;
+        add("EPUB/style.css", STYLESHEET.as_bytes(), deflated)?;
+        let cover = format!(
+            "<div class=\"cover\"><img src=\"images/{}\" alt=\"{}\"/></div>",
+            self.cover.name,
+            xml_escape(&self.title)
+        );
+        add(
+            "EPUB/cover.xhtml",
+            self.xhtml_page(&cover, " epub:type=\"cover\"").as_bytes(),
+            deflated,
+        )?;
+        add(
+            "EPUB/article.xhtml",
+            self.xhtml_page(&self.content, "").as_bytes(),
+            deflated,
+        )?;
+        for image in std::iter::once(&self.cover).chain(&self.images) {
+            // Compressed image formats gain nothing from deflating
+            let options = match image.media_type == "image/svg+xml" {
+                true => deflated,
+                false => stored,
+            };
+            add(&format!("EPUB/images/{}", image.name), &image.data, options)?;
+        }
+        Ok(zip.finish()?.into_inner())
+    }
+
+    /// The package document, with the metadata, files, and reading order of the book
+    fn package(&self, modified: DateTime<Utc>) -> String {
+        let mut metadata = format!(
+            "    <dc:identifier id=\"book-id\">{}</dc:identifier>\n    \
+             <dc:title>{}</dc:title>\n    <dc:language>{}</dc:language>\n",
+            xml_escape(&self.identifier),
+            xml_escape(&self.title),
+            self.language
+        );
+        let optional = [
+            ("dc:creator", &self.author),
+            ("dc:description", &self.description),
+            ("dc:date", &self.published),
+            ("dc:source", &self.url),
+        ];
+        for (element, value) in optional {
+            if let Some(value) = value {
+                metadata.push_str(&format!(
+                    "    <{element}>{}</{element}>\n",
+                    xml_escape(value)
+                ));
+            }
+        }
+        metadata.push_str(&format!(
+            "    <meta property=\"dcterms:modified\">{}</meta>\n    \
+             <meta name=\"cover\" content=\"cover-image\"/>\n",
+            modified.format("%Y-%m-%dT%H:%M:%SZ")
+        ));
+
+        let mut manifest = String::from(
+            "    <item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n    \
+             <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n    \
+             <item id=\"cover\" href=\"cover.xhtml\" media-type=\"application/xhtml+xml\"/>\n    \
+             <item id=\"article\" href=\"article.xhtml\" media-type=\"application/xhtml+xml\"/>\n",
+        );
+        manifest.push_str(&format!(
+            "    <item id=\"cover-image\" href=\"images/{}\" media-type=\"{}\" properties=\"cover-image\"/>\n",
+            self.cover.name, self.cover.media_type
+        ));
+        for (index, image) in self.images.iter().enumerate() {
+            manifest.push_str(&format!(
+                "    <item id=\"image-{}\" href=\"images/{}\" media-type=\"{}\"/>\n",
+                index + 1,
+                image.name,
+                image.media_type
+            ));
+        }
+
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{}\">\n  \
+             <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{}  </metadata>\n  \
+             <manifest>\n{}  </manifest>\n  \
+             <spine>\n    <itemref idref=\"cover\" linear=\"no\"/>\n    <itemref idref=\"article\"/>\n  </spine>\n\
+             </package>\n",
+            self.language, metadata, manifest
+        )
+    }
+
+    /// The navigation document: the article and its sections
+    fn nav(&self) -> String {
+        let mut sections = String::new();
+        if !self.sections.is_empty() {
+            sections.push_str("\n<ol>\n");
+            for (id, title) in &self.sections {
+                sections.push_str(&format!(
+                    "<li><a href=\"article.xhtml#{}\">{}</a></li>\n",
+                    xml_escape(id),
+                    xml_escape(title)
+                ));
+            }
+            sections.push_str("</ol>\n");
+        }
+        let toc = format!(
+            "<nav epub:type=\"toc\" id=\"toc\">\n<ol>\n<li><a href=\"article.xhtml\">{}</a>{}</li>\n</ol>\n</nav>",
+            xml_escape(&self.title),
+            sections
+        );
+        self.xhtml_page(&toc, "")
+    }
+
+    /// An XHTML document of the book with the given body
+    fn xhtml_page(&self, body: &str, body_attributes: &str) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<!DOCTYPE html>\n\
+             <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" \
+             xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+             <head>\n<meta charset=\"UTF-8\"/>\n<title>{}</title>\n\
+             <link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+             <body{}>\n{}\n</body>\n</html>\n",
+            xml_escape(&self.title),
+            body_attributes,
+            body,
+            lang = self.language
+        )
+    }
+}
+
+/// Serializes article HTML as XHTML, with images pointing at their embedded copies
+struct Xhtml<'a> {
+    base: Option<&'a Url>,
+    /// The file names of embedded images, by image URL
+    images: &'a HashMap<String, String>,
+    content: String,
+    sections: Vec<(String, String)>,
+}
+
+impl Xhtml<'_> {
+    fn write(&mut self, node: &Node) {
+        let name = match node.data() {
+            Data::Text(text) => {
+                self.content.push_str(&xml_escape(text));
+                return;
+            }
+            Data::Comment(_) => return,
+            Data::Element(..) => node.name().unwrap_or_default(),
+        };
+        if DROPPED_ELEMENTS.contains(&name) || !is_xml_name(name) {
+            return;
+        }
+
+        let mut attributes: Vec<(&str, String)> = Vec::new();
+        if name == "img" {
+            // Images that were not embedded are left out
+            let embedded = alttext::source(node)
+                .and_then(|source| resolve(self.base, &source))
+                .and_then(|url| self.images.get(url.as_str()));
+            let Some(file_name) = embedded else {
+                return;
+            };
+            attributes.push(("src", format!("images/{}", file_name)));
+            attributes.push(("alt", node.attr("alt").unwrap_or_default().to_string()));
+        }
+        for (attribute, value) in node.attrs() {
+            let dropped = attribute.starts_with("on")
+                || attribute.starts_with("data-")
+                || DROPPED_ATTRIBUTES.contains(&attribute)
+                || !is_xml_name(attribute)
+                || attributes.iter().any(|(name, _)| *name == attribute);
+            if dropped || (name == "img" && attribute == "src") {
+                continue;
+            }
+            let value = match attribute {
+                // Links go back to the page, except those within the article
+                "href" if !value.starts_with('#') => {
+                    match resolve(self.base, value)
+                        .filter(|url| matches!(url.scheme(), "http" | "https" | "mailto"))
+                    {
+                        Some(url) => url.to_string(),
+                        None => continue,
+                    }
+                }
+                _ => value.to_string(),
+            };
+            attributes.push((attribute, value));
+        }
+        // Sections are listed in the table of contents, so they need an id to link to
+        if name == "h2" {
+            let title = node.text().split_whitespace().collect::<Vec<_>>().join(" ");
+            if !title.is_empty() {
+                let id = match attributes.iter().find(|(name, _)| *name == "id") {
+                    Some((_, id)) => id.clone(),
+                    None => {
+                        let id = format!("section-{}", self.sections.len() + 1);
+                        attributes.push(("id", id.clone()));
+                        id
+                    }
+                };
+                self.sections.push((id, title));
+            }
+        }
+
+        self.content.push('<');
+        self.content.push_str(name);
+        for (attribute, value) in attributes {
+            self.content
+                .push_str(&format!(" {}=\"{}\"", attribute, xml_escape(&value)));
+        }
+        if extract::VOID_ELEMENTS.contains(&name) {
+            self.content.push_str("/>");
+            return;
+        }
+        self.content.push('>');
+        for child in node.children() {
+            self.write(&child);
+        }
+        self.content.push_str(&format!("</{}>", name));
+    }
+}
+
+/// A cover page showing the title and the site, for pages without an image
+fn cover_svg(title: &str, site: Option<&str>) -> String {
+    const LINE_LENGTH: usize = 22;
+    const MAX_LINES: usize = 8;
+    let mut lines: Vec<String> = Vec::new();
+    for word in title.split_whitespace() {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= LINE_LENGTH => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    if lines.len() > MAX_LINES {
+        lines.truncate(MAX_LINES);
+        lines[MAX_LINES - 1].push('…');
+    }
+
+    let top = 400 - lines.len() as i32 * 26;
+    let mut text = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        text.push_str(&format!(
+            "<text x=\"300\" y=\"{}\" font-family=\"Georgia, serif\" font-size=\"40\" text-anchor=\"middle\" fill=\"#222\">{}</text>\n",
+            top + index as i32 * 52,
+            xml_escape(line)
+        ));
+    }
+    if let Some(site) = site {
+        text.push_str(&format!(
+            "<text x=\"300\" y=\"720\" font-family=\"sans-serif\" font-size=\"22\" text-anchor=\"middle\" fill=\"#555\">{}</text>\n",
+            xml_escape(site.trim_start_matches("www."))
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <svg xmlns=\"http://www.w3.org/2000/svg\" width=\"600\" height=\"800\" viewBox=\"0 0 600 800\">\n\
+         <rect width=\"600\" height=\"800\" fill=\"#f4f1ea\"/>\n\
+         <rect x=\"40\" y=\"40\" width=\"520\" height=\"720\" fill=\"none\" stroke=\"#333\" stroke-width=\"2\"/>\n\
+         {}</svg>\n",
+        text
+    )
+}
+
+/// The media type and file extension of image data, from its content
+pub(crate) fn image_type(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    if let Some(format) = ImageFormat::detect(data) {
+        return Some((format.mime(), format.extension()));
+    }
+    if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        return Some(("image/gif", "gif"));
+    }
+    let start = String::from_utf8_lossy(&data[..data.len().min(1024)]).to_lowercase();
+    let start = start.trim_start_matches('\u{feff}').trim_start();
+    (start.starts_with('<') && start.contains("<svg")).then_some(("image/svg+xml", "svg"))
+}
+
+/// Resolve a link or image source against the page URL
+fn resolve(base: Option<&Url>, reference: &str) -> Option<Url> {
+    let reference = reference.trim();
+    match base {
+        Some(base) => base.join(reference).ok(),
+        None => Url::parse(reference).ok(),
+    }
+}
+
+/// A URL shortened for log messages, since `data:` URLs can be very long
+fn short_url(url: &Url) -> String {
+    match url.scheme() {
+        "data" => "data: URL".to_string(),
+        _ => url.to_string(),
+    }
+}
+
+/// Whether a name is a valid XML element or attribute name without a namespace prefix
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Escape text for XML, dropping the characters XML does not allow
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() || matches!(c, '\u{fffe}' | '\u{ffff}') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    /// The entries of a ZIP archive, by name, in archive order, with whether each is
+    /// stored uncompressed
+    fn unzip(data: &[u8]) -> Vec<(String, Vec<u8>, bool)> {
+        let mut archive = zip::ZipArchive::new(Cursor::new(data)).unwrap();
+        (0..archive.len())
+            .map(|index| {
+                let mut file = archive.by_index(index).unwrap();
+                let mut contents = Vec::new();
+                file.read_to_end(&mut contents).unwrap();
+                let stored = file.compression() == CompressionMethod::Stored;
+                (file.name().to_string(), contents, stored)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_html_to_epub() -> Result<()> {
+        let pixel = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNk+M9QDwADhgGAWjR9awAAAABJRU5ErkJggg==";
+        let html = format!(
+            r#"<html lang="de"><head><title>Tales &amp; Notes</title>
+            <meta name="author" content="Ada"></head><body>
+            <nav>Home</nav>
+            <article><h1>Tales &amp; Notes</h1><p>First<br>line&nbsp;<a href="/next" onclick="x()">next</a></p>
+            <h2>Part two</h2><p><img src="data:image/png;base64,{pixel}" alt="Dot" srcset="a.png 2x">
+            <img src="missing.png"></p><script>alert(1)</script></article>
+            </body></html>"#
+        );
+        let generator = EpubGenerator::new(MarkdownGenerator::new().await?.with_offline(true));
+        let data = generator
+            .html_to_epub(&html, Some("https://example.com/tales"), None)
+            .await?;
+
+        // The uncompressed mimetype entry comes first, where readers look for it
+        assert!(data[30..].starts_with(b"mimetypeapplication/epub+zip"));
+        let entries = unzip(&data);
+        let file = |name: &str| {
+            let (_, contents, _) = entries.iter().find(|(entry, ..)| entry == name).unwrap();
+            String::from_utf8_lossy(contents).into_owned()
+        };
+        assert_eq!(
+            entries[0],
+            (
+                "mimetype".to_string(),
+                b"application/epub+zip".to_vec(),
+                true
+            )
+        );
+        assert!(!entries[1].2);
+
+        let package = file("EPUB/package.opf");
+        assert!(package.contains("<dc:title>Tales &amp; Notes</dc:title>"));
+        assert!(package.contains("<dc:language>de</dc:language>"));
+        assert!(package.contains("<dc:creator>Ada</dc:creator>"));
+        assert!(package.contains("<dc:source>https://example.com/tales</dc:source>"));
+        assert!(package.contains("href=\"images/image-1.png\" media-type=\"image/png\""));
+        assert!(package.contains("href=\"images/cover.svg\" media-type=\"image/svg+xml\""));
+
+        let article = file("EPUB/article.xhtml");
+        assert!(article.contains("<h1>Tales &amp; Notes</h1><p>First<br/>line\u{a0}"));
+        assert!(article.contains("<a href=\"https://example.com/next\">next</a>"));
+        assert!(article.contains("<h2 id=\"section-1\">Part two</h2>"));
+        assert!(article.contains("<img src=\"images/image-1.png\" alt=\"Dot\"/>"));
+        assert!(!article.contains("missing.png"));
+        assert!(!article.contains("alert"));
+        assert!(!article.contains("Home"));
+        assert!(
+            file("EPUB/nav.xhtml").contains("<a href=\"article.xhtml#section-1\">Part two</a>")
+        );
+        assert!(file("EPUB/images/cover.svg").contains(">Tales &amp; Notes</text>"));
+        assert_eq!(entries.len(), 9);
+        Ok(())
+    }
+}
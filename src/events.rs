@@ -0,0 +1,327 @@
+//! Calendar export of events on captured pages
+//!
+//! Pages of conferences, concerts, and meetups usually describe their events for search
+//! engines with schema.org `Event` items in JSON-LD. These are read into [`Event`]s,
+//! including the sessions of a schedule listed as `subEvent`s, and written as an
+//! iCalendar (`.ics`) file that calendar applications import.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
+use select::document::Document;
+use select::predicate::Attr;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+
+/// Longest iCalendar content line in bytes, longer ones are folded
+const MAX_LINE_BYTES: usize = 75;
+
+/// The start or end of an event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventTime {
+    /// A whole day
+    Date(NaiveDate),
+    /// A time without a UTC offset, in the local time of wherever the event is held
+    Local(NaiveDateTime),
+    /// A time with a UTC offset
+    Utc(DateTime<Utc>),
+}
+
+impl EventTime {
+    /// Parse an ISO 8601 date or date-time, such as `2026-03-10`, `2026-03-10T09:00`, or
+    /// `2026-03-10T09:00:00+09:00`
+    pub fn parse(source: &str) -> Option<Self> {
+        let source = source.trim();
+        if let Ok(time) = DateTime::parse_from_rfc3339(source) {
+            return Some(EventTime::Utc(time.with_timezone(&Utc)));
+        }
+        for format in [
+            "%Y-%m-%dT%H:%M:%S%:z",
+            "%Y-%m-%dT%H:%M%:z",
+            "%Y-%m-%dT%H:%M%z",
+        ] {
+            if let Ok(time) = DateTime::parse_from_str(source, format) {
+                return Some(EventTime::Utc(time.with_timezone(&Utc)));
+            }
+        }
+        for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"] {
+            if let Ok(time) = NaiveDateTime::parse_from_str(source, format) {
+                return Some(EventTime::Local(time));
+            }
+        }
+        NaiveDate::parse_from_str(source, "%Y-%m-%d")
+            .ok()
+            .map(EventTime::Date)
+    }
+
+    /// The iCalendar property of this time, e.g. `DTSTART;VALUE=DATE:20260310`
+    fn property(&self, name: &str) -> String {
+        match self {
+            EventTime::Date(date) => format!("{};VALUE=DATE:{}", name, date.format("%Y%m%d")),
+            EventTime::Local(time) => format!("{}:{}", name, time.format("%Y%m%dT%H%M%S")),
+            EventTime::Utc(time) => format!("{}:{}", name, time.format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+/// An event described on a page
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The event name
+    pub name: String,
+    /// When the event starts
+    pub start: EventTime,
+    /// When the event ends, if the page says
+    pub end: Option<EventTime>,
+    /// The venue and its address, or the URL of an online event
+    pub location: Option<String>,
+    /// The page of the event
+    pub url: Option<String>,
+    /// A description of the event
+    pub description: Option<String>,
+}
+
+/// The schema.org events in the JSON-LD of a page, in page order
+///
+/// Events without a name or a readable start are skipped. `page_url` is the URL of
+/// events that do not name their own.
+pub fn extract_events(html_content: &str, page_url: Option<&str>) -> Vec<Event> {
+    let document = Document::from(html_content);
+    let mut events = Vec::new();
+    for script in document.find(Attr("type", "application/ld+json")) {
+        if let Ok(value) = serde_json::from_str::<Value>(&script.text()) {
+            collect_events(&value, page_url, &mut events);
+        }
+    }
+    let mut seen = HashSet::new();
+    events.retain(|event| seen.insert((event.name.clone(), event.start)));
+    events
+}
+
+fn collect_events(value: &Value, page_url: Option<&str>, events: &mut Vec<Event>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_events(value, page_url, events);
+            }
+        }
+        Value::Object(object) => {
+            let is_event = match object.get("@type") {
+                Some(Value::String(kind)) => is_event_type(kind),
+                Some(Value::Array(kinds)) => kinds
+                    .iter()
+                    .any(|kind| kind.as_str().is_some_and(is_event_type)),
+                _ => false,
+            };
+            if !is_event {
+                for value in object.values() {
+                    collect_events(value, page_url, events);
+                }
+                return;
+            }
+
+            let text = |key: &str| {
+                object
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty())
+            };
+            let name = text("name");
+            let start = text("startDate").and_then(EventTime::parse);
+            if let (Some(name), Some(start)) = (name, start) {
+                events.push(Event {
+                    name: name.to_string(),
+                    start,
+                    end: text("endDate").and_then(EventTime::parse),
+                    location: object.get("location").and_then(location),
+                    url: text("url").or(page_url).map(str::to_string),
+                    description: text("description").map(str::to_string),
+                });
+            }
+            if let Some(sub_events) = object.get("subEvent").or(object.get("subEvents")) {
+                collect_events(sub_events, page_url, events);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Whether a schema.org type is `Event` or one of its subtypes, such as `MusicEvent`
+fn is_event_type(kind: &str) -> bool {
+    let kind = kind.rsplit('/').next().unwrap_or(kind);
+    kind.ends_with("Event") || matches!(kind, "Festival" | "Hackathon")
+}
+
+/// A schema.org location as one line: a `Place` with its name and address, a
+/// `PostalAddress`, a `VirtualLocation` URL, or text
+fn location(value: &Value) -> Option<String> {
+    let parts: Vec<String> = match value {
+        Value::String(text) => vec![text.trim().to_string()],
+        Value::Array(values) => values.iter().filter_map(location).collect(),
+        Value::Object(object) => {
+            let field = |key: &str| object.get(key).and_then(Value::as_str).map(str::trim);
+            let mut parts: Vec<String> = [
+                "name",
+                "streetAddress",
+                "addressLocality",
+                "addressRegion",
+                "postalCode",
+            ]
+            .iter()
+            .filter_map(|key| field(key))
+            .map(str::to_string)
+            .collect();
+            match object.get("addressCountry") {
+                Some(Value::String(country)) => parts.push(country.trim().to_string()),
+                Some(country) => parts.extend(country.get("name").and_then(location)),
+                None => {}
+            }
+            parts.extend(object.get("address").and_then(location));
+            if parts.is_empty() {
+                parts.extend(field("url").map(str::to_string));
+            }
+            parts
+        }
+        _ => Vec::new(),
+    };
+    let parts: Vec<String> = parts.into_iter().filter(|part| !part.is_empty()).collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
+
+/// Write events as an iCalendar document, stamped with the time of the capture
+///
+/// Each event gets a UID derived from its name, start, and URL, so that importing a
+/// later capture of the same page updates the events instead of duplicating them.
+pub fn to_ics(events: &[Event], stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//webpage-save//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+    ];
+    for event in events {
+        lines.push("BEGIN:VEVENT".to_string());
+        lines.push(format!("UID:{}@webpage-save", uid(event)));
+        lines.push(format!("DTSTAMP:{}", stamp.format("%Y%m%dT%H%M%SZ")));
+        lines.push(event.start.property("DTSTART"));
+        // All-day events end at the start of the day after their last day
+        match event.end {
+            Some(EventTime::Date(date)) => {
+                let after = date.checked_add_signed(Duration::days(1)).unwrap_or(date);
+                lines.push(EventTime::Date(after).property("DTEND"));
+            }
+            Some(end) => lines.push(end.property("DTEND")),
+            None => {}
+        }
+        lines.push(format!("SUMMARY:{}", escape(&event.name)));
+        if let Some(location) = &event.location {
+            lines.push(format!("LOCATION:{}", escape(location)));
+        }
+        if let Some(description) = &event.description {
+            lines.push(format!("DESCRIPTION:{}", escape(description)));
+        }
+        if let Some(url) = &event.url {
+            lines.push(format!("URL:{}", url));
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    lines.iter().map(|line| fold(line)).collect::<String>()
+}
+
+/// A stable identifier of an event
+fn uid(event: &Event) -> String {
+    let key = format!(
+        "{}\n{}\n{}",
+        event.name,
+        event.start.property(""),
+        event.url.as_deref().unwrap_or("")
+    );
+    format!("{:x}", Sha256::digest(key.as_bytes()))[..32].to_string()
+}
+
+/// Escape text for an iCalendar or vCard property value
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// A content line ending in CRLF, folded into lines of at most [`MAX_LINE_BYTES`]
+/// without splitting characters, as iCalendar and vCard require
+pub(crate) fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + 8);
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > MAX_LINE_BYTES {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_events_to_ics() {
+        let html = r#"<html><head><script type="application/ld+json">{
+            "@context": "https://schema.org",
+            "@graph": [{
+                "@type": "BusinessEvent",
+                "name": "RustConf 2026",
+                "startDate": "2026-09-08",
+                "endDate": "2026-09-10",
+                "location": {
+                    "@type": "Place",
+                    "name": "Convention Center",
+                    "address": {"@type": "PostalAddress", "addressLocality": "Seattle", "addressCountry": "US"}
+                },
+                "subEvent": [{
+                    "@type": "Event",
+                    "name": "Keynote; welcome",
+                    "startDate": "2026-09-08T09:00:00-07:00",
+                    "endDate": "2026-09-08T10:00:00-07:00",
+                    "url": "https://rustconf.com/keynote"
+                }, {"@type": "Event", "name": "No date"}]
+            }]
+        }</script></head><body></body></html>"#;
+
+        let events = extract_events(html, Some("https://rustconf.com/"));
+        assert_eq!(events.len(), 2);
+        assert_eq!(
+            events[0].location.as_deref(),
+            Some("Convention Center, Seattle, US")
+        );
+        assert_eq!(events[0].url.as_deref(), Some("https://rustconf.com/"));
+        assert_eq!(
+            events[1].start,
+            EventTime::Utc(Utc.with_ymd_and_hms(2026, 9, 8, 16, 0, 0).unwrap())
+        );
+        assert_eq!(
+            EventTime::parse("2026-09-08T09:00"),
+            NaiveDate::from_ymd_opt(2026, 9, 8)
+                .and_then(|date| date.and_hms_opt(9, 0, 0))
+                .map(EventTime::Local)
+        );
+        assert!(extract_events("<p>No events</p>", None).is_empty());
+
+        let ics = to_ics(&events, Utc.with_ymd_and_hms(2026, 1, 1, 0, 0, 0).unwrap());
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains("\r\nDTSTART;VALUE=DATE:20260908\r\nDTEND;VALUE=DATE:20260911\r\n"));
+        assert!(ics.contains("\r\nDTSTART:20260908T160000Z\r\n"));
+        assert!(ics.contains("\r\nSUMMARY:Keynote\\; welcome\r\n"));
+        assert!(ics.contains("\r\nLOCATION:Convention Center\\, Seattle\\, US\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.split("\r\n").all(|line| line.len() <= MAX_LINE_BYTES));
+    }
+}
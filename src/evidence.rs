@@ -0,0 +1,664 @@
+//! Evidence captures with a chain-of-custody report
+//!
+//! For captures that may be used as evidence, everything needed to show what a page
+//! served and when is kept next to the capture: the request and response headers of
+//! every exchange, the IP addresses the host resolved to and that Chrome connected to,
+//! the TLS certificate the server presented, the offset of the local clock from an NTP
+//! server, and the SHA-256 digest of each file. A [`NetworkLog`] collects the exchanges
+//! from the DevTools Network domain while the page loads, [`check_ntp`] queries the
+//! clock, and a [`CaptureReport`] ties them together as JSON and as an HTML page to
+//! print to PDF.
+
+use crate::extract::escape;
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use chrono::{DateTime, TimeDelta, Utc};
+use headless_chrome::Tab;
+use headless_chrome::protocol::cdp::Network::events::ResponseReceivedEventParams;
+use headless_chrome::protocol::cdp::Network::{GetCertificate, Headers, SecurityDetails};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::debug;
+
+/// NTP server queried when none is given
+pub const DEFAULT_NTP_SERVER: &str = "pool.ntp.org";
+
+/// Seconds from the NTP epoch (1900) to the Unix epoch (1970)
+const NTP_UNIX_OFFSET: f64 = 2_208_988_800.0;
+
+/// Name the response handler is registered under
+const HANDLER_NAME: &str = "webpage-save-evidence";
+
+/// The offset of the local clock from an NTP server
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NtpCheck {
+    /// Server queried
+    pub server: String,
+    /// Server time when the reply was received
+    pub server_time: DateTime<Utc>,
+    /// How far the local clock is behind the server, in milliseconds
+    pub offset_ms: i64,
+    /// Round-trip time of the query, in milliseconds
+    pub round_trip_ms: i64,
+}
+
+impl NtpCheck {
+    /// A local clock time corrected by the measured offset
+    pub fn corrected(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        time + TimeDelta::milliseconds(self.offset_ms)
+    }
+}
+
+/// Query an NTP server (SNTP, RFC 4330) for the offset of the local clock
+///
+/// This blocks for up to `timeout`; call it from a blocking task.
+pub fn check_ntp(server: &str, timeout: Duration) -> Result<NtpCheck> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_read_timeout(Some(timeout))?;
+    socket.set_write_timeout(Some(timeout))?;
+    socket
+        .connect((server, 123))
+        .with_context(|| format!("Failed to resolve NTP server {}", server))?;
+
+    let mut request = [0u8; 48];
+    // Leap indicator 0, version 4, mode 3 (client)
+    request[0] = 0x23;
+    let sent = Utc::now();
+    request[40..48].copy_from_slice(&to_ntp(sent));
+    socket.send(&request)?;
+
+    let mut reply = [0u8; 48];
+    let len = socket
+        .recv(&mut reply)
+        .with_context(|| format!("No reply from NTP server {}", server))?;
+    let received = Utc::now();
+    if len < 48 {
+        bail!("Short reply from NTP server {}", server);
+    }
+    parse_reply(server, &reply, sent, received)
+}
+
+/// Work out the clock offset from an NTP reply and the local send and receive times
+fn parse_reply(
+    server: &str,
+    reply: &[u8; 48],
+    sent: DateTime<Utc>,
+    received: DateTime<Utc>,
+) -> Result<NtpCheck> {
+    if reply[0] & 0x07 != 4 {
+        bail!("Unexpected reply from NTP server {}", server);
+    }
+    if reply[1] == 0 {
+        bail!("NTP server {} refused the query", server);
+    }
+    let t1 = unix_seconds(sent);
+    let t2 = from_ntp(&reply[32..40]);
+    let t3 = from_ntp(&reply[40..48]);
+    let t4 = unix_seconds(received);
+    let offset = ((t2 - t1) + (t3 - t4)) / 2.0;
+    let round_trip = (t4 - t1) - (t3 - t2);
+    Ok(NtpCheck {
+        server: server.to_string(),
+        server_time: received + TimeDelta::milliseconds((offset * 1000.0).round() as i64),
+        offset_ms: (offset * 1000.0).round() as i64,
+        round_trip_ms: (round_trip * 1000.0).round().max(0.0) as i64,
+    })
+}
+
+fn unix_seconds(time: DateTime<Utc>) -> f64 {
+    time.timestamp() as f64 + f64::from(time.timestamp_subsec_nanos()) / 1e9
+}
+
+fn to_ntp(time: DateTime<Utc>) -> [u8; 8] {
+    let seconds = unix_seconds(time) + NTP_UNIX_OFFSET;
+    let whole = seconds.trunc() as u32;
+    let fraction = (seconds.fract() * 4_294_967_296.0) as u32;
+    let mut bytes = [0u8; 8];
+    bytes[..4].copy_from_slice(&whole.to_be_bytes());
+    bytes[4..].copy_from_slice(&fraction.to_be_bytes());
+    bytes
+}
+
+fn from_ntp(bytes: &[u8]) -> f64 {
+    let whole = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    let fraction = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    f64::from(whole) - NTP_UNIX_OFFSET + f64::from(fraction) / 4_294_967_296.0
+}
+
+/// The TLS connection a response was received over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsDetails {
+    /// TLS version
+    pub protocol: String,
+    /// Cipher suite
+    pub cipher: String,
+    /// Key exchange
+    pub key_exchange: String,
+    /// Certificate subject
+    pub subject: String,
+    /// Certificate issuer
+    pub issuer: String,
+    /// Names the certificate is valid for
+    pub san: Vec<String>,
+    /// Start of the certificate's validity
+    pub valid_from: Option<DateTime<Utc>>,
+    /// End of the certificate's validity
+    pub valid_to: Option<DateTime<Utc>>,
+    /// Whether the certificate complies with Certificate Transparency
+    pub certificate_transparency: String,
+}
+
+impl From<SecurityDetails> for TlsDetails {
+    fn from(details: SecurityDetails) -> Self {
+        let key_exchange = match details.key_exchange_group {
+            Some(group) if details.key_exchange.is_empty() => group,
+            Some(group) => format!("{} ({})", details.key_exchange, group),
+            None => details.key_exchange,
+        };
+        Self {
+            protocol: details.protocol,
+            cipher: details.cipher,
+            key_exchange,
+            subject: details.subject_name,
+            issuer: details.issuer,
+            san: details.san_list,
+            valid_from: DateTime::from_timestamp(details.valid_from as i64, 0),
+            valid_to: DateTime::from_timestamp(details.valid_to as i64, 0),
+            certificate_transparency: format!("{:?}", details.certificate_transparency_compliance),
+        }
+    }
+}
+
+/// A response Chrome received while loading a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NetworkEntry {
+    /// Response URL
+    pub url: String,
+    /// Resource type, such as `Document` or `Script`
+    pub resource_type: String,
+    /// Response status code
+    pub status: u32,
+    /// Response status text
+    pub status_text: String,
+    /// Protocol, such as `h2` or `http/1.1`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    /// MIME type of the body
+    pub mime_type: String,
+    /// IP address Chrome connected to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_ip: Option<String>,
+    /// Port Chrome connected to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_port: Option<u32>,
+    /// Request headers as sent, when Chrome reports them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_headers: Vec<(String, String)>,
+    /// Response headers
+    pub response_headers: Vec<(String, String)>,
+    /// Whether the response came from the disk cache
+    #[serde(default)]
+    pub from_cache: bool,
+    /// TLS connection, for secure responses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsDetails>,
+    /// How Chrome rated the security of the connection, such as `Secure` or `Insecure`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_state: Option<String>,
+    /// When the response was received
+    pub received_at: DateTime<Utc>,
+}
+
+impl NetworkEntry {
+    fn from_event(params: ResponseReceivedEventParams) -> Self {
+        let response = params.response;
+        Self {
+            url: response.url,
+            resource_type: format!("{:?}", params.Type),
+            status: response.status,
+            status_text: response.status_text,
+            protocol: response.protocol,
+            mime_type: response.mime_type,
+            remote_ip: response.remote_ip_address.filter(|ip| !ip.is_empty()),
+            remote_port: response.remote_port,
+            request_headers: response
+                .request_headers
+                .as_ref()
+                .map(header_pairs)
+                .unwrap_or_default(),
+            response_headers: header_pairs(&response.headers),
+            from_cache: response.from_disk_cache.unwrap_or(false),
+            tls: response.security_details.map(TlsDetails::from),
+            security_state: Some(format!("{:?}", response.security_state)),
+            received_at: Utc::now(),
+        }
+    }
+}
+
+fn header_pairs(headers: &Headers) -> Vec<(String, String)> {
+    match &headers.0 {
+        Some(serde_json::Value::Object(headers)) => headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Responses received while loading pages, collected from the DevTools Network domain
+///
+/// Unlike an [`HttpRecorder`](crate::recording::HttpRecorder), which sees responses
+/// before Chrome handles them, the Network domain reports how they arrived: the
+/// address connected to, the protocol, and the TLS certificate.
+#[derive(Clone, Default)]
+pub struct NetworkLog {
+    entries: Arc<Mutex<Vec<NetworkEntry>>>,
+    certificates: Arc<Mutex<HashMap<String, Vec<Vec<u8>>>>>,
+}
+
+impl NetworkLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Collect the responses the tab receives from now on
+    pub fn attach(&self, tab: &Tab) -> Result<()> {
+        let entries = self.entries.clone();
+        tab.register_response_handling(
+            HANDLER_NAME,
+            Box::new(move |params, _| {
+                let entry = NetworkEntry::from_event(params);
+                entries.lock().unwrap().push(entry);
+            }),
+        )?;
+        Ok(())
+    }
+
+    /// Fetch the certificate chains of the secure origins of the pages loaded so far,
+    /// while the tab still has their connections
+    pub fn fetch_certificates(&self, tab: &Tab) {
+        let origins: Vec<String> = self
+            .entries()
+            .iter()
+            .filter(|entry| entry.resource_type == "Document" && entry.tls.is_some())
+            .filter_map(|entry| origin_of(&entry.url))
+            .collect();
+        for origin in origins {
+            if self.certificates.lock().unwrap().contains_key(&origin) {
+                continue;
+            }
+            let chain = match tab.call_method(GetCertificate {
+                origin: origin.clone(),
+            }) {
+                Ok(chain) => chain
+                    .table_names
+                    .iter()
+                    .filter_map(|der| base64::engine::general_purpose::STANDARD.decode(der).ok())
+                    .collect(),
+                Err(e) => {
+                    debug!("Failed to get the certificate of {}: {}", origin, e);
+                    Vec::new()
+                }
+            };
+            self.certificates.lock().unwrap().insert(origin, chain);
+        }
+    }
+
+    /// The responses collected so far, in the order they were received
+    pub fn entries(&self) -> Vec<NetworkEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    /// Take the responses and certificate chains collected so far, leaving the log
+    /// empty for the next page
+    pub fn take(&self) -> (Vec<NetworkEntry>, HashMap<String, Vec<Vec<u8>>>) {
+        (
+            std::mem::take(&mut *self.entries.lock().unwrap()),
+            std::mem::take(&mut *self.certificates.lock().unwrap()),
+        )
+    }
+}
+
+/// The origin of a URL, such as `https://example.com` or `https://example.com:8443`
+pub fn origin_of(url: &str) -> Option<String> {
+    let url = url::Url::parse(url).ok()?;
+    match url.origin() {
+        origin @ url::Origin::Tuple(..) => Some(origin.ascii_serialization()),
+        url::Origin::Opaque(_) => None,
+    }
+}
+
+/// A file of an evidence bundle and its digest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvidenceFile {
+    /// File name, relative to the bundle
+    pub name: String,
+    /// Hex SHA-256 digest of the contents
+    pub sha256: String,
+    /// Size in bytes
+    pub size: u64,
+}
+
+impl EvidenceFile {
+    /// Hash a file of the bundle
+    pub fn hash(dir: &Path, name: &str) -> Result<Self> {
+        let contents = std::fs::read(dir.join(name))
+            .with_context(|| format!("Failed to read {}", dir.join(name).display()))?;
+        Ok(Self {
+            name: name.to_string(),
+            sha256: format!("{:x}", Sha256::digest(&contents)),
+            size: contents.len() as u64,
+        })
+    }
+}
+
+/// Contents of a `SHA256SUMS` file listing the files, readable by `sha256sum -c`
+pub fn sha256sums(files: &[EvidenceFile]) -> String {
+    let mut sums = String::new();
+    for file in files {
+        sums.push_str(&file.sha256);
+        sums.push_str("  ");
+        sums.push_str(&file.name);
+        sums.push('\n');
+    }
+    sums
+}
+
+/// The chain-of-custody report of a capture
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureReport {
+    /// URL captured
+    pub url: String,
+    /// Tool and version that made the capture
+    pub tool: String,
+    /// Local clock time the capture started
+    pub started_at: DateTime<Utc>,
+    /// Local clock time the capture finished
+    pub finished_at: DateTime<Utc>,
+    /// Clock check made before the capture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ntp: Option<NtpCheck>,
+    /// Why the clock could not be checked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ntp_error: Option<String>,
+    /// Addresses the host name resolved to before the capture
+    pub resolved_addresses: Vec<String>,
+    /// Aliases the host name resolved through before the capture
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cnames: Vec<String>,
+    /// Response of the page itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document: Option<NetworkEntry>,
+    /// Number of responses received while loading the page
+    pub responses: usize,
+    /// Distinct addresses the responses came from
+    pub remote_addresses: Vec<String>,
+    /// Files of the bundle
+    pub files: Vec<EvidenceFile>,
+}
+
+impl CaptureReport {
+    /// Start a report of the capture of `url`
+    pub fn new(url: &str, started_at: DateTime<Utc>) -> Self {
+        Self {
+            url: url.to_string(),
+            tool: format!("webpage-save {}", env!("CARGO_PKG_VERSION")),
+            started_at,
+            finished_at: started_at,
+            ntp: None,
+            ntp_error: None,
+            resolved_addresses: Vec::new(),
+            cnames: Vec::new(),
+            document: None,
+            responses: 0,
+            remote_addresses: Vec::new(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Record the responses of the page load: the first document response is taken to
+    /// be the page, following any redirects to it
+    pub fn with_network(mut self, entries: &[NetworkEntry]) -> Self {
+        self.document = entries
+            .iter()
+            .find(|entry| entry.resource_type == "Document" && !(300..400).contains(&entry.status))
+            .or_else(|| {
+                entries
+                    .iter()
+                    .find(|entry| entry.resource_type == "Document")
+            })
+            .cloned();
+        self.responses = entries.len();
+        let mut addresses: Vec<String> = entries
+            .iter()
+            .filter_map(|entry| entry.remote_ip.clone())
+            .collect();
+        addresses.sort();
+        addresses.dedup();
+        self.remote_addresses = addresses;
+        self
+    }
+
+    /// The report as an HTML page, to print to PDF
+    pub fn to_html(&self) -> String {
+        let mut body = String::new();
+        let row = |body: &mut String, name: &str, value: &str| {
+            body.push_str(&format!(
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(name, false),
+                escape(value, false)
+            ));
+        };
+        let time = |time: DateTime<Utc>| time.format("%Y-%m-%d %H:%M:%S%.3f UTC").to_string();
+
+        body.push_str("<h2>Capture</h2><table>");
+        row(&mut body, "URL", &self.url);
+        row(&mut body, "Tool", &self.tool);
+        row(&mut body, "Started (local clock)", &time(self.started_at));
+        row(&mut body, "Finished (local clock)", &time(self.finished_at));
+        body.push_str("</table><h2>Clock</h2><table>");
+        match (&self.ntp, &self.ntp_error) {
+            (Some(ntp), _) => {
+                row(&mut body, "NTP server", &ntp.server);
+                row(&mut body, "Server time", &time(ntp.server_time));
+                row(
+                    &mut body,
+                    "Local clock offset",
+                    &format!("{} ms", ntp.offset_ms),
+                );
+                row(
+                    &mut body,
+                    "Round trip",
+                    &format!("{} ms", ntp.round_trip_ms),
+                );
+                row(
+                    &mut body,
+                    "Started (corrected)",
+                    &time(ntp.corrected(self.started_at)),
+                );
+                row(
+                    &mut body,
+                    "Finished (corrected)",
+                    &time(ntp.corrected(self.finished_at)),
+                );
+            }
+            (None, error) => row(
+                &mut body,
+                "Unverified",
+                error.as_deref().unwrap_or("The clock was not checked"),
+            ),
+        }
+        body.push_str("</table><h2>Network</h2><table>");
+        row(
+            &mut body,
+            "Resolved addresses",
+            &self.resolved_addresses.join(", "),
+        );
+        if !self.cnames.is_empty() {
+            row(&mut body, "CNAME chain", &self.cnames.join(" → "));
+        }
+        row(&mut body, "Responses", &self.responses.to_string());
+        row(
+            &mut body,
+            "Remote addresses",
+            &self.remote_addresses.join(", "),
+        );
+        body.push_str("</table>");
+
+        if let Some(document) = &self.document {
+            body.push_str("<h2>Page response</h2><table>");
+            row(&mut body, "URL", &document.url);
+            row(
+                &mut body,
+                "Status",
+                &format!("{} {}", document.status, document.status_text),
+            );
+            if let Some(protocol) = &document.protocol {
+                row(&mut body, "Protocol", protocol);
+            }
+            if let Some(ip) = &document.remote_ip {
+                let address = match document.remote_port {
+                    Some(port) => format!("{}:{}", ip, port),
+                    None => ip.clone(),
+                };
+                row(&mut body, "Remote address", &address);
+            }
+            row(&mut body, "Received", &time(document.received_at));
+            body.push_str("</table>");
+            if let Some(tls) = &document.tls {
+                body.push_str("<h2>TLS certificate</h2><table>");
+                row(&mut body, "Subject", &tls.subject);
+                row(&mut body, "Issuer", &tls.issuer);
+                row(&mut body, "Names", &tls.san.join(", "));
+                if let (Some(from), Some(to)) = (tls.valid_from, tls.valid_to) {
+                    row(
+                        &mut body,
+                        "Valid",
+                        &format!("{} to {}", time(from), time(to)),
+                    );
+                }
+                row(
+                    &mut body,
+                    "Connection",
+                    &format!("{}, {}", tls.protocol, tls.cipher),
+                );
+                row(&mut body, "Key exchange", &tls.key_exchange);
+                row(
+                    &mut body,
+                    "Certificate Transparency",
+                    &tls.certificate_transparency,
+                );
+                body.push_str("</table>");
+            }
+            for (title, headers) in [
+                ("Request headers", &document.request_headers),
+                ("Response headers", &document.response_headers),
+            ] {
+                if headers.is_empty() {
+                    continue;
+                }
+                body.push_str(&format!("<h2>{}</h2><table class=\"headers\">", title));
+                for (name, value) in headers {
+                    row(&mut body, name, value);
+                }
+                body.push_str("</table>");
+            }
+        }
+
+        body.push_str("<h2>Files</h2><table class=\"files\"><tr><th>File</th><th>Bytes</th><th>SHA-256</th></tr>");
+        for file in &self.files {
+            body.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td><code>{}</code></td></tr>",
+                escape(&file.name, false),
+                file.size,
+                file.sha256
+            ));
+        }
+        body.push_str("</table>");
+
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+             <title>Capture report</title>\
+             <style>body{{font-family:sans-serif;margin:2em;font-size:11pt}}\
+             table{{border-collapse:collapse;width:100%;margin-bottom:1em}}\
+             th,td{{border:1px solid #ddd;padding:.3em .5em;text-align:left;\
+             vertical-align:top;word-break:break-all}}th{{background:#f6f8fa;width:30%}}\
+             .files th{{width:auto}}code{{font-size:9pt}}</style></head><body>\
+             <h1>Capture report</h1><p>{}</p>{}</body></html>",
+            escape(&self.url, false),
+            body
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ntp_offset_and_report() {
+        let sent = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let received = sent + TimeDelta::milliseconds(100);
+        // The server clock is two seconds ahead, and took 20 ms to answer
+        let mut reply = [0u8; 48];
+        reply[0] = 0x24;
+        reply[1] = 2;
+        reply[32..40].copy_from_slice(&to_ntp(sent + TimeDelta::milliseconds(2040)));
+        reply[40..48].copy_from_slice(&to_ntp(sent + TimeDelta::milliseconds(2060)));
+        let ntp = parse_reply("ntp.example", &reply, sent, received).unwrap();
+        assert_eq!(ntp.offset_ms, 2000);
+        assert_eq!(ntp.round_trip_ms, 80);
+        assert_eq!(ntp.corrected(sent), sent + TimeDelta::seconds(2));
+
+        reply[0] = 0x23;
+        assert!(parse_reply("ntp.example", &reply, sent, received).is_err());
+
+        let entry = |url: &str, status: u32, resource_type: &str| NetworkEntry {
+            url: url.to_string(),
+            resource_type: resource_type.to_string(),
+            status,
+            status_text: String::new(),
+            protocol: Some("h2".to_string()),
+            mime_type: "text/html".to_string(),
+            remote_ip: Some("93.184.215.14".to_string()),
+            remote_port: Some(443),
+            request_headers: Vec::new(),
+            response_headers: vec![("server".to_string(), "<nginx>".to_string())],
+            from_cache: false,
+            tls: None,
+            security_state: None,
+            received_at: received,
+        };
+        let mut report = CaptureReport::new("http://example.com/", sent).with_network(&[
+            entry("http://example.com/", 301, "Document"),
+            entry("https://example.com/", 200, "Document"),
+            entry("https://example.com/a.css", 200, "Stylesheet"),
+        ]);
+        report.ntp = Some(ntp);
+        report.files = vec![EvidenceFile {
+            name: "page.pdf".to_string(),
+            sha256: "ab".repeat(32),
+            size: 10,
+        }];
+        assert_eq!(
+            report.document.as_ref().unwrap().url,
+            "https://example.com/"
+        );
+        assert_eq!(report.responses, 3);
+        assert_eq!(report.remote_addresses, vec!["93.184.215.14"]);
+        assert_eq!(
+            sha256sums(&report.files),
+            format!("{}  page.pdf\n", "ab".repeat(32))
+        );
+
+        let html = report.to_html();
+        assert!(html.contains("<td>&lt;nginx&gt;</td>"));
+        assert!(html.contains("2023-11-14 22:13:22.000 UTC"));
+        assert!(html.contains("<td>2000 ms</td>"));
+    }
+}
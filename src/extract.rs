@@ -0,0 +1,708 @@
+//! Main-content extraction settings and simple CSS selectors
+//!
+//! Markdown conversion extracts the main content of a page before converting it. The
+//! selectors tried for the content, and the elements removed from it, can be configured
+//! with [`ExtractConfig`]. Selectors support the common subset of CSS: type, `*`,
+//! `.class`, `#id`, `[attr]`, and `[attr=value]`, combined into compound selectors and
+//! joined with descendant (space) or child (`>`) combinators. Comma-separated lists
+//! match any of their selectors.
+//!
+//! Unless disabled, boilerplate such as navigation, share buttons, newsletter signups,
+//! cookie banners, and related-post lists is removed from the content as well. It is
+//! recognized by element type and role ([`BOILERPLATE_SELECTORS`]) and by words in class
+//! names and ids ([`BOILERPLATE_WORDS`]).
+
+use crate::comments;
+use anyhow::{Result, bail};
+use select::document::Document;
+use select::node::{Data, Node};
+use select::predicate::{Attr, Name};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::LazyLock;
+
+/// Selectors of elements removed from the content as boilerplate
+pub const BOILERPLATE_SELECTORS: &[&str] = &[
+    "nav",
+    "aside",
+    "footer",
+    "form",
+    "button",
+    "dialog",
+    "noscript",
+    "iframe",
+    "[role=navigation]",
+    "[role=complementary]",
+    "[role=contentinfo]",
+    "[role=dialog]",
+    "[aria-hidden=true]",
+];
+
+/// Words that mark an element as boilerplate when they appear in its class names or id,
+/// e.g. `share-buttons` or `newsletter_signup`
+pub const BOILERPLATE_WORDS: &[&str] = &[
+    "ad",
+    "ads",
+    "advert",
+    "advertisement",
+    "banner-ad",
+    "breadcrumb",
+    "breadcrumbs",
+    "comments",
+    "consent",
+    "cookie",
+    "cookies",
+    "disqus",
+    "gdpr",
+    "newsletter",
+    "popup",
+    "promo",
+    "recommended",
+    "related",
+    "share",
+    "sharing",
+    "sidebar",
+    "signup",
+    "social",
+    "sponsored",
+    "subscribe",
+    "subscription",
+];
+
+/// Selectors tried, in order, to find the main content when no configured one matches
+pub const CONTENT_SELECTORS: &[&str] = &[
+    "main",
+    "article",
+    "body",
+    ".main-content",
+    ".content",
+    ".post-content",
+    ".entry-content",
+    ".article-content",
+    "#content",
+];
+
+static CONTENT: LazyLock<Vec<Selector>> = LazyLock::new(|| {
+    CONTENT_SELECTORS
+        .iter()
+        .map(|selector| Selector::parse(selector).expect("built-in selectors are valid"))
+        .collect()
+});
+
+static BOILERPLATE: LazyLock<Vec<Selector>> = LazyLock::new(|| {
+    BOILERPLATE_SELECTORS
+        .iter()
+        .map(|selector| Selector::parse(selector).expect("built-in selectors are valid"))
+        .collect()
+});
+
+/// A simple CSS selector, e.g. `div.post-body`, `#comments > ol`, or `[role=navigation]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    source: String,
+    /// Compound selectors from the leftmost one, with the combinator joining each to the next
+    steps: Vec<(Compound, Combinator)>,
+    last: Compound,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attrs: Vec<(String, Option<String>)>,
+}
+
+impl Compound {
+    fn matches(&self, node: &Node) -> bool {
+        let Some(name) = node.name() else {
+            return false;
+        };
+        if self.tag.as_deref().is_some_and(|tag| tag != name) {
+            return false;
+        }
+        if self
+            .id
+            .as_deref()
+            .is_some_and(|id| node.attr("id") != Some(id))
+        {
+            return false;
+        }
+        let classes = node.attr("class").unwrap_or_default();
+        if !self
+            .classes
+            .iter()
+            .all(|class| classes.split_whitespace().any(|c| c == class))
+        {
+            return false;
+        }
+        self.attrs.iter().all(|(attr, value)| match value {
+            Some(value) => node.attr(attr) == Some(value.as_str()),
+            None => node.attr(attr).is_some(),
+        })
+    }
+}
+
+impl Selector {
+    /// Parse a single selector
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the selector is empty or uses unsupported syntax
+    pub fn parse(source: &str) -> Result<Self> {
+        let invalid = |reason: &str| anyhow::anyhow!("Invalid selector '{}': {}", source, reason);
+        let mut steps = Vec::new();
+        let mut current = Compound::default();
+        let mut empty = true;
+        let mut pending: Option<Combinator> = None;
+        let mut chars = source.trim().chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                ' ' | '\t' | '\n' | '>' => {
+                    if !empty {
+                        steps.push((std::mem::take(&mut current), Combinator::Descendant));
+                        empty = true;
+                    }
+                    if c == '>' {
+                        if pending == Some(Combinator::Child) || steps.is_empty() {
+                            return Err(invalid("misplaced '>'"));
+                        }
+                        pending = Some(Combinator::Child);
+                    } else if pending.is_none() {
+                        pending = Some(Combinator::Descendant);
+                    }
+                    continue;
+                }
+                '.' | '#' => {
+                    let name = take_ident(&mut chars);
+                    if name.is_empty() {
+                        return Err(invalid("missing class or id name"));
+                    }
+                    match c {
+                        '.' => current.classes.push(name),
+                        _ => current.id = Some(name),
+                    }
+                }
+                '[' => {
+                    let mut attr = String::new();
+                    for c in chars.by_ref() {
+                        if c == ']' {
+                            break;
+                        }
+                        attr.push(c);
+                    }
+                    let (name, value) = match attr.split_once('=') {
+                        Some((name, value)) => {
+                            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+                            (name.trim(), Some(value.to_string()))
+                        }
+                        None => (attr.trim(), None),
+                    };
+                    if name.is_empty() || name.ends_with(['~', '|', '^', '$', '*']) {
+                        return Err(invalid("unsupported attribute selector"));
+                    }
+                    current.attrs.push((name.to_ascii_lowercase(), value));
+                }
+                '*' if empty => {}
+                c if c.is_ascii_alphanumeric() && empty => {
+                    let mut tag = c.to_string();
+                    tag.push_str(&take_ident(&mut chars));
+                    current.tag = Some(tag.to_ascii_lowercase());
+                }
+                c => return Err(invalid(&format!("unsupported character '{}'", c))),
+            }
+
+            // The first part of a new compound selector fixes the pending combinator
+            if empty {
+                if let (Some(combinator), Some(step)) = (pending.take(), steps.last_mut()) {
+                    step.1 = combinator;
+                }
+                empty = false;
+            }
+        }
+
+        if empty {
+            return Err(invalid(match steps.is_empty() {
+                true => "empty selector",
+                false => "dangling combinator",
+            }));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            steps,
+            last: current,
+        })
+    }
+
+    /// Parse a comma-separated list of selectors
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any selector is invalid
+    pub fn parse_list(source: &str) -> Result<Vec<Self>> {
+        source.split(',').map(Self::parse).collect()
+    }
+
+    /// Whether a node matches the selector
+    pub fn matches(&self, node: &Node) -> bool {
+        self.last.matches(node) && Self::matches_ancestors(&self.steps, node)
+    }
+
+    fn matches_ancestors(steps: &[(Compound, Combinator)], node: &Node) -> bool {
+        let Some(((compound, combinator), rest)) = steps.split_last() else {
+            return true;
+        };
+        let mut ancestor = node.parent();
+        while let Some(candidate) = ancestor {
+            if compound.matches(&candidate) && Self::matches_ancestors(rest, &candidate) {
+                return true;
+            }
+            if *combinator == Combinator::Child {
+                return false;
+            }
+            ancestor = candidate.parent();
+        }
+        false
+    }
+
+    /// The first node in the document matching the selector
+    pub fn find_first<'a>(&self, document: &'a Document) -> Option<Node<'a>> {
+        document.find(|node: &Node| self.matches(node)).next()
+    }
+}
+
+impl FromStr for Selector {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn take_ident(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) -> String {
+    let mut ident = String::new();
+    while let Some(&c) = chars.peek() {
+        if !(c.is_alphanumeric() || c == '-' || c == '_') {
+            break;
+        }
+        ident.push(c);
+        chars.next();
+    }
+    ident
+}
+
+/// What a page is converted to Markdown as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ContentMode {
+    /// The recipe of pages with a schema.org recipe (see [`crate::recipe`]), and the
+    /// main content of other pages
+    #[default]
+    Auto,
+    /// The main content of every page
+    Page,
+    /// The recipe of every page, failing for pages without one
+    Recipe,
+}
+
+/// Main-content extraction settings
+#[derive(Debug, Clone, Default)]
+pub struct ExtractConfig {
+    /// Selectors tried, in order, before the built-in ones to find the main content
+    pub content_selectors: Vec<Selector>,
+    /// Fail when none of the content selectors matches, instead of falling back to the
+    /// built-in ones
+    pub require_content: bool,
+    /// Selectors of elements removed from the extracted content
+    pub strip_selectors: Vec<Selector>,
+    /// Keep boilerplate such as navigation and share buttons in the extracted content
+    pub keep_boilerplate: bool,
+    /// Move comment threads out of the content into an appendix
+    pub include_comments: bool,
+    /// Whether pages are converted as recipes
+    pub mode: ContentMode,
+}
+
+impl ExtractConfig {
+    /// Create settings that use only the built-in extraction
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Try these selectors, in order, before the built-in ones
+    pub fn with_content_selectors(mut self, selectors: Vec<Selector>) -> Self {
+        self.content_selectors.extend(selectors);
+        self
+    }
+
+    /// Fail to extract pages where none of the content selectors matches, instead of
+    /// falling back to the built-in ones
+    pub fn with_require_content(mut self, require_content: bool) -> Self {
+        self.require_content = require_content;
+        self
+    }
+
+    /// Remove elements matching these selectors from the extracted content
+    pub fn with_strip_selectors(mut self, selectors: Vec<Selector>) -> Self {
+        self.strip_selectors.extend(selectors);
+        self
+    }
+
+    /// Keep boilerplate, such as navigation and share buttons, in the extracted content
+    pub fn with_keep_boilerplate(mut self, keep_boilerplate: bool) -> Self {
+        self.keep_boilerplate = keep_boilerplate;
+        self
+    }
+
+    /// Append the page's comment threads to the Markdown output
+    ///
+    /// Comments are then removed from the extracted content, so they appear only once.
+    pub fn with_include_comments(mut self, include_comments: bool) -> Self {
+        self.include_comments = include_comments;
+        self
+    }
+
+    /// Convert pages as recipes when they have one, always, or never
+    pub fn with_mode(mut self, mode: ContentMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// The first element matching one of the configured content selectors
+    pub fn find_content<'a>(&self, document: &'a Document) -> Option<Node<'a>> {
+        self.content_selectors
+            .iter()
+            .find_map(|selector| selector.find_first(document))
+    }
+
+    /// The main content of a page: the first element matching one of the configured
+    /// content selectors, or else one of the built-in ones
+    pub fn find_main<'a>(&self, document: &'a Document) -> Option<Node<'a>> {
+        self.find_content(document).or_else(|| {
+            CONTENT
+                .iter()
+                .find_map(|selector| selector.find_first(document))
+        })
+    }
+
+    /// The main content of a page like [`Self::find_main`], without falling back to the
+    /// built-in selectors when content selectors are required
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the selectors when they are required and none matches
+    pub fn require_main<'a>(&self, document: &'a Document) -> Result<Option<Node<'a>>> {
+        if !self.require_content || self.content_selectors.is_empty() {
+            return Ok(self.find_main(document));
+        }
+        match self.find_content(document) {
+            Some(content) => Ok(Some(content)),
+            None => {
+                let selectors: Vec<String> = self
+                    .content_selectors
+                    .iter()
+                    .map(|selector| format!("'{}'", selector))
+                    .collect();
+                bail!(
+                    "No element on the page matches the content selector {}",
+                    selectors.join(", ")
+                )
+            }
+        }
+    }
+
+    /// The HTML of a node, without the elements matching the strip selectors and,
+    /// unless it is kept, without boilerplate
+    pub fn html(&self, node: &Node) -> String {
+        if self.strip_selectors.is_empty() && self.keep_boilerplate && !self.include_comments {
+            return node.html();
+        }
+        let root = node.index();
+        let root_text = node.text().trim().len();
+        let mut html = String::new();
+        write_html(
+            node,
+            &|candidate: &Node| {
+                candidate.index() != root
+                    && (self.is_stripped(candidate)
+                        || (self.include_comments && comments::is_comment(candidate))
+                        || (!self.keep_boilerplate && is_boilerplate(candidate, root_text)))
+            },
+            &mut html,
+        );
+        html
+    }
+
+    fn is_stripped(&self, node: &Node) -> bool {
+        self.strip_selectors
+            .iter()
+            .any(|selector| selector.matches(node))
+    }
+}
+
+/// The title of a page: its first heading, `<title>`, Open Graph or Twitter title, or
+/// title-classed element, whichever is found first
+pub fn find_title(document: &Document) -> Option<String> {
+    // Try various title selectors
+    let tag_selectors = ["h1", "title"];
+    let class_selectors = ["title", "post-title", "entry-title", "article-title"];
+
+    // Try tag selectors first
+    for &selector in &tag_selectors {
+        if let Some(element) = document.find(Name(selector)).next() {
+            let text = element.text().trim().to_string();
+            if !text.is_empty() {
+                return Some(text);
+            }
+        }
+    }
+
+    // Try Open Graph meta tag
+    if let Some(element) = document.find(Attr("property", "og:title")).next() {
+        if let Some(content) = element.attr("content") {
+            return Some(content.to_string());
+        }
+    }
+
+    // Try Twitter meta tag
+    if let Some(element) = document.find(Attr("name", "twitter:title")).next() {
+        if let Some(content) = element.attr("content") {
+            return Some(content.to_string());
+        }
+    }
+
+    // Try class selectors
+    for &class_name in &class_selectors {
+        if let Some(element) = document.find(Attr("class", class_name)).next() {
+            let text = element.text().trim().to_string();
+            if !text.is_empty() {
+                return Some(text);
+            }
+        }
+    }
+
+    None
+}
+
+/// Whether an element inside content with `content_len` bytes of text is boilerplate
+///
+/// Elements holding at least half of the content's text are never considered
+/// boilerplate, so a wrapper with an unlucky class name does not remove the article.
+pub fn is_boilerplate(node: &Node, content_len: usize) -> bool {
+    let marked = BOILERPLATE.iter().any(|selector| selector.matches(node))
+        || node
+            .attr("class")
+            .into_iter()
+            .chain(node.attr("id"))
+            .flat_map(str::split_whitespace)
+            .any(is_boilerplate_name);
+    marked && node.text().trim().len() * 2 < content_len
+}
+
+/// Whether a class name or id marks boilerplate, as a whole (`banner-ad`) or through
+/// one of its hyphen- or underscore-separated words (`share-buttons`)
+fn is_boilerplate_name(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    BOILERPLATE_WORDS.contains(&name.as_str())
+        || name
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .any(|word| BOILERPLATE_WORDS.contains(&word))
+}
+
+pub(crate) const VOID_ELEMENTS: [&str; 14] = [
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/// Serialize a node as HTML, skipping the subtrees of nodes matching `skip`
+pub fn write_html(node: &Node, skip: &dyn Fn(&Node) -> bool, out: &mut String) {
+    match node.data() {
+        Data::Text(text) => {
+            let raw = node
+                .parent()
+                .and_then(|parent| parent.name())
+                .is_some_and(|name| matches!(name, "script" | "style"));
+            match raw {
+                true => out.push_str(text),
+                false => out.push_str(&escape(text, false)),
+            }
+        }
+        Data::Comment(_) => {}
+        Data::Element(..) => {
+            if skip(node) {
+                return;
+            }
+            let name = node.name().unwrap_or_default();
+            out.push('<');
+            out.push_str(name);
+            for (attr, value) in node.attrs() {
+                out.push_str(&format!(" {}=\"{}\"", attr, escape(value, true)));
+            }
+            out.push('>');
+            if VOID_ELEMENTS.contains(&name) {
+                return;
+            }
+            for child in node.children() {
+                write_html(&child, skip, out);
+            }
+            out.push_str(&format!("</{}>", name));
+        }
+    }
+}
+
+/// Escape text for HTML, as an attribute value or as element content
+pub(crate) fn escape(text: &str, attribute: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' if !attribute => escaped.push_str("&lt;"),
+            '>' if !attribute => escaped.push_str("&gt;"),
+            '"' if attribute => escaped.push_str("&quot;"),
+            '\u{a0}' => escaped.push_str("&nbsp;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = r#"
+        <html><body>
+            <nav class="menu top">Menu</nav>
+            <div id="main">
+                <div class="post-body" data-kind="article">
+                    <p>Kept &amp; <b>bold</b></p>
+                    <div class="ads">Buy now</div>
+                    <ul><li>One</li></ul>
+                </div>
+            </div>
+        </body></html>
+    "#;
+
+    fn first_name(document: &Document, selector: &str) -> Option<String> {
+        let selector = Selector::parse(selector).unwrap();
+        selector
+            .find_first(document)
+            .map(|node| node.name().unwrap_or_default().to_string())
+    }
+
+    #[test]
+    fn test_selector_matching() {
+        let document = Document::from(HTML);
+        assert_eq!(first_name(&document, "div.post-body"), Some("div".into()));
+        assert_eq!(first_name(&document, ".menu.top"), Some("nav".into()));
+        assert_eq!(
+            first_name(&document, "#main > .post-body ul li"),
+            Some("li".into())
+        );
+        assert_eq!(
+            first_name(&document, "[data-kind=\"article\"] p"),
+            Some("p".into())
+        );
+        assert_eq!(first_name(&document, "body > .post-body"), None);
+        assert_eq!(first_name(&document, ".menu.bottom"), None);
+    }
+
+    #[test]
+    fn test_selector_errors() {
+        assert!(Selector::parse("").is_err());
+        assert!(Selector::parse("div >").is_err());
+        assert!(Selector::parse("a:hover").is_err());
+        assert!(Selector::parse("[href^=http]").is_err());
+        assert_eq!(Selector::parse_list("nav, .ads").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_boilerplate_removal() {
+        let html = r#"
+            <article>
+                <h1>Title</h1>
+                <div class="share-buttons">Share on X</div>
+                <p>A long enough paragraph of real article text to outweigh the rest.</p>
+                <div id="newsletter_signup">Subscribe!</div>
+                <section class="related posts">Related: other posts</section>
+                <div class="shared-memory">Kept: not a boilerplate word</div>
+                <nav>Previous / Next</nav>
+            </article>
+        "#;
+        let document = Document::from(html);
+        let article = Selector::parse("article")
+            .unwrap()
+            .find_first(&document)
+            .unwrap();
+
+        let stripped = ExtractConfig::new().html(&article);
+        assert!(stripped.contains("real article text"));
+        assert!(stripped.contains("Kept: not a boilerplate word"));
+        for boilerplate in ["Share on X", "Subscribe!", "Related:", "Previous / Next"] {
+            assert!(!stripped.contains(boilerplate), "{}", boilerplate);
+        }
+
+        let kept = ExtractConfig::new()
+            .with_keep_boilerplate(true)
+            .html(&article);
+        assert!(kept.contains("Share on X"));
+    }
+
+    #[test]
+    fn test_boilerplate_never_removes_most_content() {
+        let html = r#"<div id="main"><div class="post sidebar-layout"><p>The whole article</p></div></div>"#;
+        let document = Document::from(html);
+        let main = Selector::parse("#main")
+            .unwrap()
+            .find_first(&document)
+            .unwrap();
+        assert!(
+            ExtractConfig::new()
+                .html(&main)
+                .contains("The whole article")
+        );
+    }
+
+    #[test]
+    fn test_extract_and_strip() {
+        let document = Document::from(HTML);
+        let config = ExtractConfig::new()
+            .with_content_selectors(Selector::parse_list("article, div.post-body").unwrap())
+            .with_strip_selectors(vec![Selector::parse(".ads").unwrap()]);
+
+        let content = config.find_content(&document).unwrap();
+        let html = config.html(&content);
+        assert!(html.starts_with("<div class=\"post-body\""));
+        assert!(html.contains("<p>Kept &amp; <b>bold</b></p>"));
+        assert!(!html.contains("Buy now"));
+
+        let config = ExtractConfig::new()
+            .with_content_selectors(Selector::parse_list("article.post, #missing div").unwrap())
+            .with_require_content(true);
+        let error = config.require_main(&document).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "No element on the page matches the content selector 'article.post', '#missing div'"
+        );
+        assert!(config.find_main(&document).is_some());
+        assert!(
+            ExtractConfig::new()
+                .with_require_content(true)
+                .require_main(&document)
+                .unwrap()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! Fonts for reader-mode PDFs
+//!
+//! Reader-mode templates can use custom fonts, given as font files or as the names of
+//! installed fonts. Font files are embedded into the rendered HTML as data URIs, so they
+//! are used without being installed. Chrome embeds the fonts a page uses into the PDF,
+//! so the PDF looks the same on machines without them.
+//!
+//! Font files are grouped into families by file name: `NotoSerif-Regular.ttf`,
+//! `NotoSerif-Bold.ttf`, and `NotoSerif-BoldItalic.ttf` form the `NotoSerif` family,
+//! with the weight and style taken from the rest of the name.
+
+use anyhow::Result;
+use base64::Engine;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// File extensions of supported font files
+pub const FONT_EXTENSIONS: &[&str] = &["ttf", "otf", "woff", "woff2"];
+
+/// A font for reader-mode PDFs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReaderFont {
+    /// A font file embedded into the document
+    File(PathBuf),
+    /// An installed font, by family name
+    System(String),
+}
+
+impl ReaderFont {
+    /// Interpret a command-line font: a path with a font file extension is a file,
+    /// anything else the name of an installed font
+    pub fn parse(source: &str) -> Self {
+        let path = Path::new(source);
+        match font_extension(path) {
+            Some(_) => Self::File(path.to_path_buf()),
+            None => Self::System(source.trim().to_string()),
+        }
+    }
+
+    /// The CSS family name of the font
+    pub fn family(&self) -> String {
+        let name = match self {
+            Self::File(path) => file_family(path),
+            Self::System(name) => name.clone(),
+        };
+        name.replace(['"', '\\'], "")
+    }
+}
+
+/// The CSS for a list of fonts, to be used in that order with later fonts as fallbacks
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FontCss {
+    /// `@font-face` rules embedding the font files
+    pub font_faces: String,
+    /// The quoted family names, comma-separated, for a `font-family` declaration
+    pub font_family: String,
+}
+
+/// Build the CSS for reader fonts
+///
+/// # Errors
+///
+/// Returns an error if a font file cannot be read, or an installed font is not found
+pub fn font_css(fonts: &[ReaderFont]) -> Result<FontCss> {
+    let mut css = FontCss::default();
+    let mut families: Vec<String> = Vec::new();
+    for font in fonts {
+        match font {
+            ReaderFont::File(path) => {
+                let data = std::fs::read(path).map_err(|e| {
+                    anyhow::anyhow!("Failed to read font {}: {}", path.display(), e)
+                })?;
+                let (mime, format) = match font_extension(path) {
+                    Some("otf") => ("font/otf", "opentype"),
+                    Some("woff") => ("font/woff", "woff"),
+                    Some("woff2") => ("font/woff2", "woff2"),
+                    _ => ("font/ttf", "truetype"),
+                };
+                let (weight, style) = file_weight_and_style(path);
+                css.font_faces.push_str(&format!(
+                    "@font-face {{ font-family: \"{}\"; src: url(data:{};base64,{}) format(\"{}\"); font-weight: {}; font-style: {}; }}\n",
+                    font.family(),
+                    mime,
+                    base64::engine::general_purpose::STANDARD.encode(data),
+                    format,
+                    weight,
+                    style
+                ));
+            }
+            ReaderFont::System(name) => {
+                if is_installed(name) == Some(false) {
+                    return Err(anyhow::anyhow!(
+                        "Font '{}' is not installed (see fc-list for installed fonts)",
+                        name
+                    ));
+                }
+            }
+        }
+        let family = font.family();
+        if !families.contains(&family) {
+            families.push(family);
+        }
+    }
+    css.font_family = families
+        .iter()
+        .map(|family| format!("\"{}\"", family))
+        .collect::<Vec<_>>()
+        .join(", ");
+    Ok(css)
+}
+
+/// Whether a font family is installed, or None if fontconfig is not available to tell
+pub fn is_installed(name: &str) -> Option<bool> {
+    let output = Command::new("fc-list")
+        .args([":", "family"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .flat_map(|line| line.split(','))
+            .any(|family| family.trim().eq_ignore_ascii_case(name.trim())),
+    )
+}
+
+fn font_extension(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    FONT_EXTENSIONS
+        .iter()
+        .find(|known| **known == extension)
+        .copied()
+}
+
+/// Words in font file names that name a weight, with its CSS value
+const WEIGHTS: &[(&str, u16)] = &[
+    ("thin", 100),
+    ("extralight", 200),
+    ("light", 300),
+    ("regular", 400),
+    ("medium", 500),
+    ("semibold", 600),
+    ("bold", 700),
+    ("extrabold", 800),
+    ("black", 900),
+];
+
+/// The family of a font file: its name without the weight and style suffix
+fn file_family(path: &Path) -> String {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match stem.rsplit_once(['-', '_']) {
+        Some((family, suffix)) if is_style_suffix(suffix) => family.to_string(),
+        _ => stem,
+    }
+}
+
+fn is_style_suffix(suffix: &str) -> bool {
+    let suffix = suffix.to_ascii_lowercase();
+    let rest = suffix
+        .strip_suffix("italic")
+        .or_else(|| suffix.strip_suffix("oblique"))
+        .unwrap_or(&suffix);
+    rest.is_empty() || WEIGHTS.iter().any(|(name, _)| *name == rest)
+}
+
+fn file_weight_and_style(path: &Path) -> (u16, &'static str) {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let Some((_, suffix)) = stem
+        .rsplit_once(['-', '_'])
+        .filter(|(_, s)| is_style_suffix(s))
+    else {
+        return (400, "normal");
+    };
+    let (rest, style) = match suffix
+        .strip_suffix("italic")
+        .or_else(|| suffix.strip_suffix("oblique"))
+    {
+        Some(rest) => (rest, "italic"),
+        None => (suffix, "normal"),
+    };
+    let weight = WEIGHTS
+        .iter()
+        .find(|(name, _)| *name == rest)
+        .map_or(400, |(_, weight)| *weight);
+    (weight, style)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fonts() {
+        assert_eq!(
+            ReaderFont::parse("fonts/NotoSerif-Bold.TTF"),
+            ReaderFont::File(PathBuf::from("fonts/NotoSerif-Bold.TTF"))
+        );
+        assert_eq!(
+            ReaderFont::parse("Noto Sans CJK JP"),
+            ReaderFont::System("Noto Sans CJK JP".to_string())
+        );
+        assert_eq!(
+            ReaderFont::parse("a/NotoSerif-BoldItalic.ttf").family(),
+            "NotoSerif"
+        );
+        assert_eq!(ReaderFont::parse("a/Inter.woff2").family(), "Inter");
+        assert_eq!(
+            file_weight_and_style(Path::new("NotoSerif-BoldItalic.ttf")),
+            (700, "italic")
+        );
+        assert_eq!(
+            file_weight_and_style(Path::new("Inter-Light.ttf")),
+            (300, "normal")
+        );
+        assert_eq!(
+            file_weight_and_style(Path::new("Source-Code.ttf")),
+            (400, "normal")
+        );
+    }
+
+    #[test]
+    fn test_font_css() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let regular = temp_dir.path().join("Serif-Regular.ttf");
+        let bold = temp_dir.path().join("Serif-Bold.otf");
+        std::fs::write(&regular, b"font")?;
+        std::fs::write(&bold, b"font")?;
+
+        let css = font_css(&[ReaderFont::File(regular), ReaderFont::File(bold)])?;
+        assert_eq!(css.font_family, "\"Serif\"");
+        assert_eq!(css.font_faces.matches("@font-face").count(), 2);
+        assert!(
+            css.font_faces
+                .contains("url(data:font/ttf;base64,Zm9udA==)")
+        );
+        assert!(
+            css.font_faces
+                .contains("format(\"opentype\"); font-weight: 700")
+        );
+
+        let missing = ReaderFont::File(temp_dir.path().join("Missing.ttf"));
+        assert!(font_css(&[missing]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,234 @@
+//! Front matter of Markdown output
+//!
+//! Note-taking apps and static site generators read a document's metadata from a
+//! block at its top: YAML between `---` lines (Obsidian, Hugo, Jekyll) or TOML between
+//! `+++` lines (Hugo, Zola). With a [`FrontMatterFormat`] chosen, saved Markdown starts
+//! with the page title, source URL, fetch time, author, description, preview image,
+//! word count, and language, so captures can go straight into a vault or a site.
+
+use anyhow::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use select::document::Document;
+use select::predicate::Name;
+use url::Url;
+
+/// Syntax of the front matter block
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrontMatterFormat {
+    /// No front matter
+    #[default]
+    None,
+    /// YAML between `---` lines
+    Yaml,
+    /// TOML between `+++` lines
+    Toml,
+}
+
+/// Metadata of a page beyond its title and URL, read from its HTML
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PageMetadata {
+    /// The author's name
+    pub author: Option<String>,
+    /// The page's summary of itself
+    pub description: Option<String>,
+    /// Absolute URL of the preview image (`og:image`)
+    pub image: Option<String>,
+    /// Language tag of the page, e.g. `en` or `de-CH`
+    pub language: Option<String>,
+}
+
+impl PageMetadata {
+    /// Read the metadata of a page, resolving the image against the page URL
+    pub fn from_html(html: &str, url: Option<&str>) -> Self {
+        let document = Document::from(html);
+        let meta = |key: &str| {
+            document
+                .find(Name("meta"))
+                .find(|meta| meta.attr("name") == Some(key) || meta.attr("property") == Some(key))
+                .and_then(|meta| meta.attr("content"))
+                .map(|content| content.split_whitespace().collect::<Vec<_>>().join(" "))
+                .filter(|content| !content.is_empty())
+        };
+        let base = url.and_then(|url| Url::parse(url).ok());
+        Self {
+            // Profile URLs are not names
+            author: meta("author")
+                .or_else(|| meta("article:author"))
+                .filter(|author| Url::parse(author).is_err()),
+            description: meta("description").or_else(|| meta("og:description")),
+            image: meta("og:image").and_then(|image| match &base {
+                Some(base) => base.join(&image).ok().map(String::from),
+                None => Url::parse(&image).ok().map(String::from),
+            }),
+            language: document
+                .find(Name("html"))
+                .find_map(|html| html.attr("lang").map(str::trim))
+                .filter(|lang| {
+                    !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                })
+                .map(str::to_string),
+        }
+    }
+}
+
+/// The front matter block of a document, ending with its closing line, or an empty
+/// string for [`FrontMatterFormat::None`]
+///
+/// # Errors
+///
+/// Returns an error if a value cannot be serialized
+pub fn front_matter(
+    format: FrontMatterFormat,
+    title: &str,
+    source: Option<&str>,
+    fetched: DateTime<Utc>,
+    metadata: &PageMetadata,
+    word_count: usize,
+) -> Result<String> {
+    let (fence, separator) = match format {
+        FrontMatterFormat::None => return Ok(String::new()),
+        FrontMatterFormat::Yaml => ("---", ": "),
+        FrontMatterFormat::Toml => ("+++", " = "),
+    };
+    // Strings are written as JSON, which is valid YAML and a valid TOML basic string,
+    // and the time as RFC 3339, which both read as a timestamp
+    let mut fields = vec![("title", serde_json::to_string(title)?)];
+    if let Some(source) = source {
+        fields.push(("source", serde_json::to_string(source)?));
+    }
+    fields.push((
+        "fetched",
+        fetched.to_rfc3339_opts(SecondsFormat::Secs, true),
+    ));
+    for (key, value) in [
+        ("author", &metadata.author),
+        ("description", &metadata.description),
+        ("image", &metadata.image),
+    ] {
+        if let Some(value) = value {
+            fields.push((key, serde_json::to_string(value)?));
+        }
+    }
+    fields.push(("word_count", word_count.to_string()));
+    if let Some(language) = &metadata.language {
+        fields.push(("language", serde_json::to_string(language)?));
+    }
+
+    let mut block = format!("{}\n", fence);
+    for (key, value) in fields {
+        block.push_str(&format!("{}{}{}\n", key, separator, value));
+    }
+    block.push_str(fence);
+    block.push('\n');
+    Ok(block)
+}
+
+/// Split a document into the lines of its YAML or TOML front matter and the rest, or
+/// None if it has no front matter
+pub fn split_front_matter(markdown: &str) -> Option<(&str, &str)> {
+    ["---", "+++"].into_iter().find_map(|fence| {
+        markdown
+            .strip_prefix(fence)?
+            .strip_prefix('\n')?
+            .split_once(&format!("\n{}\n", fence))
+    })
+}
+
+/// The keys and raw values of front matter lines, in either format
+pub fn fields(front_matter: &str) -> impl Iterator<Item = (&str, &str)> {
+    front_matter.lines().filter_map(|line| {
+        line.split_once(": ")
+            .filter(|(key, _)| !key.contains(' '))
+            .or_else(|| line.split_once(" = "))
+    })
+}
+
+/// The number of words of Markdown text
+pub fn word_count(markdown: &str) -> usize {
+    markdown
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_front_matter() -> Result<()> {
+        let html = r#"<html lang="de-CH"><head>
+            <meta name="author" content="Ada  Muster">
+            <meta property="og:description" content="Ein &quot;kurzer&quot; Text">
+            <meta property="og:image" content="/img/cover.png">
+            </head><body></body></html>"#;
+        let metadata = PageMetadata::from_html(html, Some("https://example.ch/blog/post"));
+        assert_eq!(
+            metadata,
+            PageMetadata {
+                author: Some("Ada Muster".to_string()),
+                description: Some("Ein \"kurzer\" Text".to_string()),
+                image: Some("https://example.ch/img/cover.png".to_string()),
+                language: Some("de-CH".to_string()),
+            }
+        );
+        let fetched = Utc.with_ymd_and_hms(2024, 5, 2, 9, 30, 0).unwrap();
+        let words = word_count("# Title\n\nOne two — three.\n\n- four");
+        assert_eq!(words, 5);
+
+        let yaml = front_matter(
+            FrontMatterFormat::Yaml,
+            "Post: \"Part 1\"",
+            Some("https://example.ch/blog/post"),
+            fetched,
+            &metadata,
+            words,
+        )?;
+        assert_eq!(
+            yaml,
+            "---\ntitle: \"Post: \\\"Part 1\\\"\"\nsource: \"https://example.ch/blog/post\"\n\
+             fetched: 2024-05-02T09:30:00Z\nauthor: \"Ada Muster\"\n\
+             description: \"Ein \\\"kurzer\\\" Text\"\nimage: \"https://example.ch/img/cover.png\"\n\
+             word_count: 5\nlanguage: \"de-CH\"\n---\n"
+        );
+
+        let toml = front_matter(
+            FrontMatterFormat::Toml,
+            "Post",
+            None,
+            fetched,
+            &PageMetadata::default(),
+            0,
+        )?;
+        assert_eq!(
+            toml,
+            "+++\ntitle = \"Post\"\nfetched = 2024-05-02T09:30:00Z\nword_count = 0\n+++\n"
+        );
+        assert_eq!(
+            front_matter(FrontMatterFormat::None, "Post", None, fetched, &metadata, 0)?,
+            ""
+        );
+
+        let document = format!("{}# Post\n\nBody\n", toml);
+        let (block, body) = split_front_matter(&document).unwrap();
+        assert_eq!(body, "# Post\n\nBody\n");
+        assert_eq!(
+            fields(block).collect::<Vec<_>>(),
+            [
+                ("title", "\"Post\""),
+                ("fetched", "2024-05-02T09:30:00Z"),
+                ("word_count", "0")
+            ]
+        );
+        let document = format!("{}Body", yaml);
+        let (block, body) = split_front_matter(&document).unwrap();
+        assert_eq!(body, "Body");
+        assert_eq!(
+            fields(block).nth(2),
+            Some(("fetched", "2024-05-02T09:30:00Z"))
+        );
+        assert_eq!(split_front_matter("# Post\n\n---\n\nBody"), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,48 @@
+//! Entry points for the fuzz targets in `fuzz/`
+//!
+//! The parsers that see untrusted input (page HTML, search responses, and the titles
+//! that become filenames) are crate-private. This module exposes them, with the
+//! `fuzzing` feature only, so `cargo fuzz` can feed them malformed and adversarial
+//! input. It is not part of the public API.
+
+use crate::integration;
+use crate::markdown::MarkdownGenerator;
+use crate::search::SearchResult;
+use anyhow::Result;
+
+thread_local! {
+    /// A generator with the default extraction settings, built once per fuzzing thread
+    static GENERATOR: MarkdownGenerator = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("failed to build a Tokio runtime")
+        .block_on(MarkdownGenerator::new())
+        .expect("failed to create a Markdown generator");
+}
+
+/// Extract the main content of an HTML page, as Markdown conversion does
+///
+/// # Errors
+///
+/// Returns an error if a configured content selector matches nothing
+pub fn extract_main_content(html: &str) -> Result<String> {
+    GENERATOR.with(|generator| generator.extract_main_content(html))
+}
+
+/// Extract the title of an HTML page, as Markdown conversion does
+pub fn extract_title(html: &str) -> Option<String> {
+    GENERATOR.with(|generator| generator.extract_title(html))
+}
+
+/// Sanitize a search result title or host for use as a filename stem
+pub fn sanitize_filename(filename: &str) -> String {
+    integration::sanitize_filename(filename)
+}
+
+/// Extract the search results of a text search response
+///
+/// # Errors
+///
+/// Returns an error if the search results cannot be parsed
+pub fn extract_urls_from_results(search_results: &str) -> Result<Vec<SearchResult>> {
+    integration::extract_urls_from_results(search_results)
+}
@@ -0,0 +1,157 @@
+//! Syntax highlighting of code blocks in reader-mode PDFs
+//!
+//! Many sites highlight code with JavaScript that never runs on extracted content, or
+//! that ran with a dark theme that prints poorly. [`CodeHighlighter`] re-highlights the
+//! `<pre>` blocks of extracted HTML with [syntect](https://github.com/trishume/syntect),
+//! using inline styles so the colors survive printing. The language comes from the
+//! `language-*`/`lang-*` class of the block or its `<code>` element, or is guessed from
+//! the first line (such as a shebang); blocks in unknown languages are left as they are.
+
+use crate::extract;
+use anyhow::Result;
+use regex::Regex;
+use select::document::Document;
+use select::predicate::Name;
+use std::sync::LazyLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::{IncludeBackground, styled_line_to_highlighted_html};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+/// Theme used unless another is chosen, readable on white paper
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+static PRE_BLOCK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<pre(?:\s[^>]*)?>.*?</pre>").unwrap());
+static LANGUAGE_CLASS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)(?:language|lang|highlight-source|sourceCode)-([\w+#.-]+)").unwrap()
+});
+
+/// The names of the built-in themes, sorted
+pub fn theme_names() -> Vec<&'static str> {
+    let mut names: Vec<&str> = THEMES.themes.keys().map(String::as_str).collect();
+    names.sort_unstable();
+    names
+}
+
+/// Highlights the code blocks of HTML content with a color theme
+#[derive(Debug, Clone)]
+pub struct CodeHighlighter {
+    theme: &'static Theme,
+}
+
+impl Default for CodeHighlighter {
+    fn default() -> Self {
+        Self::new(DEFAULT_THEME).expect("default theme is built in")
+    }
+}
+
+impl CodeHighlighter {
+    /// Create a highlighter with one of the built-in themes (see [`theme_names`])
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no built-in theme of that name
+    pub fn new(theme: &str) -> Result<Self> {
+        let theme = THEMES.themes.get(theme).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown code theme '{}': expected one of {}",
+                theme,
+                theme_names().join(", ")
+            )
+        })?;
+        Ok(Self { theme })
+    }
+
+    /// Replace the `<pre>` blocks of an HTML fragment with highlighted ones
+    pub fn highlight_html(&self, html: &str) -> String {
+        PRE_BLOCK
+            .replace_all(html, |caps: &regex::Captures| {
+                self.highlight_block(&caps[0])
+                    .unwrap_or_else(|| caps[0].to_string())
+            })
+            .into_owned()
+    }
+
+    /// Highlight one `<pre>` block, or None when its language is unknown
+    fn highlight_block(&self, block: &str) -> Option<String> {
+        let document = Document::from(block);
+        let pre = document.find(Name("pre")).next()?;
+        let code = pre.text();
+        let classes = std::iter::once(pre)
+            .chain(pre.find(Name("code")))
+            .filter_map(|node| node.attr("class"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let syntax =
+            language_syntax(&classes).or_else(|| SYNTAXES.find_syntax_by_first_line(&code))?;
+
+        let mut highlighter = HighlightLines::new(syntax, self.theme);
+        let mut lines = String::new();
+        for line in LinesWithEndings::from(&code) {
+            let regions = highlighter.highlight_line(line, &SYNTAXES).ok()?;
+            lines.push_str(&styled_line_to_highlighted_html(&regions, IncludeBackground::No).ok()?);
+        }
+        let style = match self.theme.settings.background {
+            Some(color) => format!(
+                " style=\"background-color: #{:02x}{:02x}{:02x};\"",
+                color.r, color.g, color.b
+            ),
+            None => String::new(),
+        };
+        let lang = extract::escape(syntax.file_extensions.first().unwrap_or(&syntax.name), true);
+        Some(format!(
+            "<pre class=\"highlighted\" data-lang=\"{}\"{}><code>{}</code></pre>",
+            lang, style, lines
+        ))
+    }
+}
+
+/// The syntax named by a `language-*` style class, if any
+fn language_syntax(classes: &str) -> Option<&'static SyntaxReference> {
+    LANGUAGE_CLASS.captures_iter(classes).find_map(|caps| {
+        let token = caps[1].to_lowercase();
+        SYNTAXES.find_syntax_by_token(match token.as_str() {
+            // Common names syntect knows under other tokens
+            "shell" | "console" | "zsh" => "bash",
+            "js" | "jsx" | "typescript" | "ts" | "tsx" => "javascript",
+            "py" | "python3" => "python",
+            "yml" => "yaml",
+            "c++" => "cpp",
+            "c#" | "csharp" => "cs",
+            other => other,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_html() -> Result<()> {
+        let highlighter = CodeHighlighter::default();
+        let html = r#"<p>Example:</p>
+<pre><code class="language-rust">fn main() {
+    println!("&lt;hi&gt;");
+}</code></pre>
+<pre>plain text, no language</pre>"#;
+        let highlighted = highlighter.highlight_html(html);
+
+        assert!(highlighted.starts_with("<p>Example:</p>"));
+        assert!(highlighted.contains("<pre class=\"highlighted\" data-lang=\"rs\""));
+        assert!(highlighted.contains("<span style=\""));
+        assert!(highlighted.contains("&lt;hi&gt;"));
+        assert!(highlighted.contains("<pre>plain text, no language</pre>"));
+
+        let shebang = highlighter.highlight_html("<pre>#!/bin/bash\necho hi</pre>");
+        assert!(shebang.contains("data-lang=\"sh\""));
+
+        assert!(CodeHighlighter::new("base16-ocean.dark").is_ok());
+        assert!(CodeHighlighter::new("Nope").is_err());
+        Ok(())
+    }
+}
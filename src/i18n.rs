@@ -0,0 +1,272 @@
+//! Language of command-line messages and generated dates
+//!
+//! The messages printed by the common conversion steps, and the capture dates on cover
+//! pages and reader-mode PDFs, are available in English and Japanese. The language is
+//! chosen explicitly, or detected from the locale environment variables (`LC_ALL`,
+//! `LC_MESSAGES`, then `LANG`) with English as the fallback.
+
+use chrono::{DateTime, Utc};
+use std::fmt::Display;
+use std::path::Path;
+
+/// A language for messages and dates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    Japanese,
+}
+
+impl Language {
+    /// The language of a language tag or locale name such as `ja`, `ja-JP`, or
+    /// `en_US.UTF-8`, if supported
+    pub fn parse(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['-', '_', '.', '@'])
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Self::English),
+            "ja" | "jp" => Some(Self::Japanese),
+            _ => None,
+        }
+    }
+
+    /// The language of the user's locale, or English if it is not supported
+    pub fn detect() -> Self {
+        Self::from_locale(|name| std::env::var(name).ok())
+    }
+
+    /// The language of the first locale variable that is set, like `setlocale` does
+    fn from_locale(var: impl Fn(&str) -> Option<String>) -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .into_iter()
+            .filter_map(var)
+            .find(|value| !value.is_empty())
+            .and_then(|value| Self::parse(&value))
+            .unwrap_or_default()
+    }
+
+    /// The ISO 639-1 code of the language
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::English => "en",
+            Self::Japanese => "ja",
+        }
+    }
+
+    /// A date and time, e.g. `March 5, 2026 14:30 UTC` or `2026年3月5日 14:30 UTC`
+    pub fn format_datetime(self, time: &DateTime<Utc>) -> String {
+        match self {
+            Self::English => time.format("%B %-d, %Y %H:%M UTC").to_string(),
+            Self::Japanese => time.format("%Y年%-m月%-d日 %H:%M UTC").to_string(),
+        }
+    }
+
+    /// When a page was captured, as a phrase for the cover page or document header
+    pub fn captured(self, time: &DateTime<Utc>) -> String {
+        match self {
+            Self::English => format!("Captured {}", self.format_datetime(time)),
+            Self::Japanese => format!("{} 取得", self.format_datetime(time)),
+        }
+    }
+}
+
+/// A message printed by the command-line tool
+#[derive(Clone, Copy)]
+pub enum Message<'a> {
+    /// An output file was written
+    SavedTo(&'a Path),
+    /// A PDF of this many bytes was generated
+    GeneratedPdf(usize),
+    /// Markdown of this many characters was generated
+    GeneratedMarkdown(usize),
+    /// A screenshot of this many bytes was captured
+    CapturedScreenshot(usize),
+    /// An EPUB book of this many bytes was generated
+    GeneratedEpub(usize),
+    /// Generating the PDF of a source failed
+    PdfFailed {
+        source: &'a dyn Display,
+        error: &'a dyn Display,
+    },
+    /// Generating the Markdown of a source failed
+    MarkdownFailed {
+        source: &'a dyn Display,
+        error: &'a dyn Display,
+    },
+    /// Capturing the screenshot of a source failed
+    ScreenshotFailed {
+        source: &'a dyn Display,
+        error: &'a dyn Display,
+    },
+    /// Generating the EPUB book of a source failed
+    EpubFailed {
+        source: &'a dyn Display,
+        error: &'a dyn Display,
+    },
+    /// An input of `convert` is not a valid URL, file, or pattern
+    InvalidInput {
+        input: &'a str,
+        error: &'a dyn Display,
+    },
+    /// A URL was saved into the output directory before
+    AlreadySaved(&'a str),
+    /// Some inputs of a run failed
+    InputsFailed { failed: usize, total: usize },
+    /// Some inputs were only converted to some of the requested formats
+    PartiallyConverted(usize),
+    /// This many pages were merged into one document
+    MergedPages(usize),
+    /// `convert` was run without inputs
+    NoUrl,
+    /// The disk filled up during a batch
+    DiskFull,
+    /// Heading of the per-domain statistics table
+    DomainStatistics,
+    /// The run statistics were written to a file
+    SavedStatistics(&'a Path),
+}
+
+impl Message<'_> {
+    /// The message in a language
+    pub fn text(&self, language: Language) -> String {
+        match language {
+            Language::English => self.english(),
+            Language::Japanese => self.japanese(),
+        }
+    }
+
+    fn english(&self) -> String {
+        match self {
+            Self::SavedTo(path) => format!("Saved to: {}", path.display()),
+            Self::GeneratedPdf(bytes) => format!("Successfully generated PDF ({} bytes)", bytes),
+            Self::GeneratedMarkdown(chars) => {
+                format!("Successfully generated Markdown ({} chars)", chars)
+            }
+            Self::CapturedScreenshot(bytes) => {
+                format!("Successfully captured screenshot ({} bytes)", bytes)
+            }
+            Self::GeneratedEpub(bytes) => format!("Successfully generated EPUB ({} bytes)", bytes),
+            Self::PdfFailed { source, error } => {
+                format!("Failed to generate PDF for {}: {}", source, error)
+            }
+            Self::MarkdownFailed { source, error } => {
+                format!("Failed to generate Markdown for {}: {}", source, error)
+            }
+            Self::ScreenshotFailed { source, error } => {
+                format!("Failed to capture screenshot of {}: {}", source, error)
+            }
+            Self::EpubFailed { source, error } => {
+                format!("Failed to generate EPUB for {}: {}", source, error)
+            }
+            Self::InvalidInput { input, error } => format!("Invalid input {}: {}", input, error),
+            Self::AlreadySaved(url) => {
+                format!("Already saved {} (use --force to convert again)", url)
+            }
+            Self::InputsFailed { failed, total } => {
+                format!("{} of {} inputs failed to convert", failed, total)
+            }
+            Self::PartiallyConverted(count) => {
+                format!("{} inputs were only partially converted:", count)
+            }
+            Self::MergedPages(count) => format!("Merged {} pages into one document", count),
+            Self::NoUrl => "No URL provided for conversion".to_string(),
+            Self::DiskFull => "The disk is full; stopping before the remaining inputs".to_string(),
+            Self::DomainStatistics => "Per-domain statistics:".to_string(),
+            Self::SavedStatistics(path) => format!("Saved statistics to: {}", path.display()),
+        }
+    }
+
+    fn japanese(&self) -> String {
+        match self {
+            Self::SavedTo(path) => format!("保存先: {}", path.display()),
+            Self::GeneratedPdf(bytes) => format!("PDF を生成しました ({} バイト)", bytes),
+            Self::GeneratedMarkdown(chars) => format!("Markdown を生成しました ({} 文字)", chars),
+            Self::CapturedScreenshot(bytes) => {
+                format!("スクリーンショットを撮影しました ({} バイト)", bytes)
+            }
+            Self::GeneratedEpub(bytes) => format!("EPUB を生成しました ({} バイト)", bytes),
+            Self::PdfFailed { source, error } => {
+                format!("{} の PDF を生成できませんでした: {}", source, error)
+            }
+            Self::MarkdownFailed { source, error } => {
+                format!("{} の Markdown を生成できませんでした: {}", source, error)
+            }
+            Self::ScreenshotFailed { source, error } => {
+                format!(
+                    "{} のスクリーンショットを撮影できませんでした: {}",
+                    source, error
+                )
+            }
+            Self::EpubFailed { source, error } => {
+                format!("{} の EPUB を生成できませんでした: {}", source, error)
+            }
+            Self::InvalidInput { input, error } => format!("無効な入力 {}: {}", input, error),
+            Self::AlreadySaved(url) => {
+                format!("{} は保存済みです (再変換するには --force を指定)", url)
+            }
+            Self::InputsFailed { failed, total } => {
+                format!("{} 件中 {} 件の変換に失敗しました", total, failed)
+            }
+            Self::PartiallyConverted(count) => {
+                format!("{} 件は一部の形式にしか変換できませんでした:", count)
+            }
+            Self::MergedPages(count) => format!("{} ページを 1 つの文書にまとめました", count),
+            Self::NoUrl => "変換する URL が指定されていません".to_string(),
+            Self::DiskFull => "ディスクがいっぱいのため、残りの入力の変換を中止します".to_string(),
+            Self::DomainStatistics => "ドメイン別の統計:".to_string(),
+            Self::SavedStatistics(path) => format!("統計の保存先: {}", path.display()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_detect_and_format() {
+        assert_eq!(Language::parse("ja_JP.UTF-8"), Some(Language::Japanese));
+        assert_eq!(Language::parse("en-GB"), Some(Language::English));
+        assert_eq!(Language::parse("de_DE"), None);
+
+        let locale = |vars: &'static [(&'static str, &'static str)]| {
+            Language::from_locale(move |name| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| value.to_string())
+            })
+        };
+        assert_eq!(locale(&[("LANG", "ja_JP.UTF-8")]), Language::Japanese);
+        assert_eq!(
+            locale(&[("LC_ALL", "C"), ("LANG", "ja_JP.UTF-8")]),
+            Language::English
+        );
+        assert_eq!(
+            locale(&[("LC_ALL", ""), ("LC_MESSAGES", "ja_JP")]),
+            Language::Japanese
+        );
+        assert_eq!(locale(&[("LANG", "fr_FR.UTF-8")]), Language::English);
+
+        let time = Utc.with_ymd_and_hms(2026, 3, 5, 14, 30, 0).unwrap();
+        assert_eq!(
+            Language::English.captured(&time),
+            "Captured March 5, 2026 14:30 UTC"
+        );
+        assert_eq!(
+            Language::Japanese.captured(&time),
+            "2026年3月5日 14:30 UTC 取得"
+        );
+        assert_eq!(
+            Message::InputsFailed {
+                failed: 2,
+                total: 5
+            }
+            .text(Language::Japanese),
+            "5 件中 2 件の変換に失敗しました"
+        );
+    }
+}
@@ -0,0 +1,449 @@
+//! Format and size policy for downloaded images
+//!
+//! Images saved with a capture should open anywhere and not give away more than the
+//! page did. An [`ImagePolicy`] transcodes images into PNG or JPEG (WebP is not
+//! supported by every viewer), scales down images larger than a maximum width or
+//! height, and strips EXIF, XMP, and text metadata, which can carry camera serial
+//! numbers, GPS positions, and editing history.
+//!
+//! Transcoding and scaling decode the image in Chrome and encode it again from a
+//! canvas, which also drops every kind of metadata. Images that only need their
+//! metadata removed are rewritten without the metadata segments, leaving the image data
+//! untouched. GIF and SVG images are left as they are.
+
+use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use headless_chrome::Browser;
+
+/// JPEG quality images are encoded with
+const JPEG_QUALITY: f64 = 0.9;
+
+/// PNG chunks holding metadata rather than image data
+const PNG_METADATA_CHUNKS: &[&[u8; 4]] = &[b"eXIf", b"tEXt", b"zTXt", b"iTXt", b"tIME"];
+
+/// WebP chunks holding metadata rather than image data
+const WEBP_METADATA_CHUNKS: &[&[u8; 4]] = &[b"EXIF", b"XMP "];
+
+/// Raster image format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// PNG
+    Png,
+    /// JPEG
+    Jpeg,
+    /// WebP
+    Webp,
+}
+
+impl ImageFormat {
+    /// The format of image data, from its signature
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some(ImageFormat::Jpeg)
+        } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+            Some(ImageFormat::Webp)
+        } else {
+            None
+        }
+    }
+
+    /// The format usually stored in files with this extension, with or without the dot
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension.trim_start_matches('.').to_lowercase().as_str() {
+            "png" => Some(ImageFormat::Png),
+            "jpg" | "jpeg" => Some(ImageFormat::Jpeg),
+            "webp" => Some(ImageFormat::Webp),
+            _ => None,
+        }
+    }
+
+    /// The MIME type
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+        }
+    }
+
+    /// The usual file extension
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Png => "png",
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Webp => "webp",
+        }
+    }
+}
+
+/// An image after the policy was applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolicyImage {
+    /// The image data
+    pub data: Vec<u8>,
+    /// The image format, or None for data that is not a supported raster image
+    pub format: Option<ImageFormat>,
+}
+
+/// How downloaded images are transcoded, scaled, and stripped
+#[derive(Clone, Default)]
+pub struct ImagePolicy {
+    format: Option<ImageFormat>,
+    max_dimension: Option<u32>,
+    strip_metadata: bool,
+    browser: Option<Browser>,
+}
+
+impl ImagePolicy {
+    /// Create a policy keeping images as they are
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Transcode images in other raster formats into this one
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Scale down images wider or taller than this many pixels, keeping their aspect
+    /// ratio
+    pub fn with_max_dimension(mut self, max_dimension: u32) -> Self {
+        self.max_dimension = Some(max_dimension);
+        self
+    }
+
+    /// Remove EXIF, XMP, and text metadata
+    pub fn with_strip_metadata(mut self, strip_metadata: bool) -> Self {
+        self.strip_metadata = strip_metadata;
+        self
+    }
+
+    /// Transcode and scale images in this browser, required for those options
+    pub fn with_browser(mut self, browser: Browser) -> Self {
+        self.browser = Some(browser);
+        self
+    }
+
+    /// Whether the policy changes nothing
+    pub fn is_empty(&self) -> bool {
+        self.format.is_none() && self.max_dimension.is_none() && !self.strip_metadata
+    }
+
+    /// Whether the policy transcodes or scales images, which needs a browser
+    pub fn needs_browser(&self) -> bool {
+        self.format.is_some() || self.max_dimension.is_some()
+    }
+
+    /// Apply the policy to image data; data in other formats is returned unchanged
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the image needs transcoding or scaling but there is no
+    /// browser, or the browser cannot decode it
+    pub fn apply(&self, data: Vec<u8>) -> Result<PolicyImage> {
+        let Some(format) = ImageFormat::detect(&data) else {
+            return Ok(PolicyImage { data, format: None });
+        };
+        let target = self.format.unwrap_or(format);
+        let oversized = match (self.max_dimension, dimensions(format, &data)) {
+            (Some(max), Some((width, height))) => width.max(height) > max,
+            _ => false,
+        };
+
+        if target != format || oversized {
+            let browser = self
+                .browser
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Transcoding and scaling images needs a browser"))?;
+            let data = reencode(browser, &data, format, target, self.max_dimension)?;
+            return Ok(PolicyImage {
+                data,
+                format: Some(target),
+            });
+        }
+        let data = match self.strip_metadata {
+            true => strip_metadata(format, &data)?,
+            false => data,
+        };
+        Ok(PolicyImage {
+            data,
+            format: Some(format),
+        })
+    }
+}
+
+/// Decode an image in the browser and encode it from a canvas, scaled down to fit
+/// the maximum dimension
+fn reencode(
+    browser: &Browser,
+    data: &[u8],
+    format: ImageFormat,
+    target: ImageFormat,
+    max_dimension: Option<u32>,
+) -> Result<Vec<u8>> {
+    let script = format!(
+        r#"(async () => {{
+            const image = new Image();
+            image.src = {source};
+            await image.decode();
+            const largest = Math.max(image.naturalWidth, image.naturalHeight);
+            const scale = Math.min(1, {max} / largest);
+            const canvas = document.createElement('canvas');
+            canvas.width = Math.max(1, Math.round(image.naturalWidth * scale));
+            canvas.height = Math.max(1, Math.round(image.naturalHeight * scale));
+            const context = canvas.getContext('2d');
+            // JPEG has no transparency; show transparent areas on white, not black
+            if ({mime} === 'image/jpeg') {{
+                context.fillStyle = '#fff';
+                context.fillRect(0, 0, canvas.width, canvas.height);
+            }}
+            context.imageSmoothingQuality = 'high';
+            context.drawImage(image, 0, 0, canvas.width, canvas.height);
+            return canvas.toDataURL({mime}, {quality}).split(',')[1];
+        }})()"#,
+        source = serde_json::to_string(&format!(
+            "data:{};base64,{}",
+            format.mime(),
+            BASE64.encode(data)
+        ))?,
+        max = max_dimension.map_or("Infinity".to_string(), |max| max.to_string()),
+        mime = serde_json::to_string(target.mime())?,
+        quality = JPEG_QUALITY,
+    );
+
+    let tab = browser.new_tab()?;
+    let result = tab.evaluate(&script, true);
+    let _ = tab.close(false);
+    let encoded = result?
+        .value
+        .and_then(|value| value.as_str().map(str::to_string))
+        .ok_or_else(|| anyhow::anyhow!("The browser could not decode the image"))?;
+    Ok(BASE64.decode(encoded)?)
+}
+
+/// Width and height of an image, from its header
+pub fn dimensions(format: ImageFormat, data: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?));
+    let le24 = |at: usize| {
+        let bytes = data.get(at..at + 3)?;
+        Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+    };
+    match format {
+        ImageFormat::Png => {
+            let width = u32::from_be_bytes(data.get(16..20)?.try_into().ok()?);
+            let height = u32::from_be_bytes(data.get(20..24)?.try_into().ok()?);
+            Some((width, height))
+        }
+        ImageFormat::Jpeg => {
+            let mut at = 2;
+            loop {
+                if *data.get(at)? != 0xff {
+                    return None;
+                }
+                let marker = *data.get(at + 1)?;
+                // Start-of-frame markers, but not DHT, JPG, and DAC
+                if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                    return Some((u32::from(be16(at + 7)?), u32::from(be16(at + 5)?)));
+                }
+                at += 2 + usize::from(be16(at + 2)?);
+            }
+        }
+        ImageFormat::Webp => match data.get(12..16)? {
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8 " => {
+                let width = u16::from_le_bytes(data.get(26..28)?.try_into().ok()?);
+                let height = u16::from_le_bytes(data.get(28..30)?.try_into().ok()?);
+                Some((u32::from(width & 0x3fff), u32::from(height & 0x3fff)))
+            }
+            _ => None,
+        },
+    }
+}
+
+/// Remove the metadata segments or chunks of an image, keeping its image data as is
+///
+/// # Errors
+///
+/// Returns an error if the image is truncated or malformed
+pub fn strip_metadata(format: ImageFormat, data: &[u8]) -> Result<Vec<u8>> {
+    let malformed = || anyhow::anyhow!("Malformed {} image", format.extension());
+    let mut stripped = Vec::with_capacity(data.len());
+    match format {
+        ImageFormat::Png => {
+            stripped.extend_from_slice(&data[..8]);
+            let mut at = 8;
+            while at < data.len() {
+                let length = data.get(at..at + 4).ok_or_else(malformed)?;
+                let length = u32::from_be_bytes(length.try_into()?) as usize;
+                // Length, type, data, and CRC
+                let end = at + 12 + length;
+                let chunk = data.get(at..end).ok_or_else(malformed)?;
+                if !PNG_METADATA_CHUNKS.iter().any(|name| &chunk[4..8] == *name) {
+                    stripped.extend_from_slice(chunk);
+                }
+                at = end;
+            }
+        }
+        ImageFormat::Jpeg => {
+            stripped.extend_from_slice(&data[..2]);
+            let mut at = 2;
+            loop {
+                let marker = *data.get(at + 1).ok_or_else(malformed)?;
+                if data[at] != 0xff {
+                    return Err(malformed());
+                }
+                // Entropy-coded data follows the start of scan; keep the rest as is
+                if marker == 0xda {
+                    stripped.extend_from_slice(&data[at..]);
+                    break;
+                }
+                let length = data.get(at + 2..at + 4).ok_or_else(malformed)?;
+                let end = at + 2 + usize::from(u16::from_be_bytes(length.try_into()?));
+                let segment = data.get(at..end).ok_or_else(malformed)?;
+                // APP1 holds EXIF and XMP, APP13 IPTC, and COM comments; APP2 holds the
+                // color profile, which is kept
+                if !matches!(marker, 0xe1 | 0xed | 0xfe) {
+                    stripped.extend_from_slice(segment);
+                }
+                at = end;
+            }
+        }
+        ImageFormat::Webp => {
+            stripped.extend_from_slice(&data[..12]);
+            let mut at = 12;
+            while at < data.len() {
+                let size = data.get(at + 4..at + 8).ok_or_else(malformed)?;
+                let size = u32::from_le_bytes(size.try_into()?) as usize;
+                // Chunks are padded to an even size
+                let end = (at + 8 + size + size % 2).min(data.len());
+                let chunk = data.get(at..end).ok_or_else(malformed)?;
+                if !WEBP_METADATA_CHUNKS.iter().any(|name| &chunk[..4] == *name) {
+                    stripped.extend_from_slice(chunk);
+                }
+                at = end;
+            }
+            // Clear the EXIF and XMP flags of the extended header, and fix the file size
+            if stripped.get(12..16) == Some(b"VP8X") && stripped.len() > 20 {
+                stripped[20] &= !0x0c;
+            }
+            let riff_size = u32::try_from(stripped.len() - 8)?;
+            stripped[4..8].copy_from_slice(&riff_size.to_le_bytes());
+        }
+    }
+    Ok(stripped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png_chunk(name: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = (data.len() as u32).to_be_bytes().to_vec();
+        chunk.extend_from_slice(name);
+        chunk.extend_from_slice(data);
+        chunk.extend_from_slice(&[0; 4]);
+        chunk
+    }
+
+    fn webp_chunk(name: &[u8; 4], data: &[u8]) -> Vec<u8> {
+        let mut chunk = name.to_vec();
+        chunk.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        chunk.extend_from_slice(data);
+        if data.len() % 2 == 1 {
+            chunk.push(0);
+        }
+        chunk
+    }
+
+    #[test]
+    fn test_dimensions_and_strip_metadata() -> Result<()> {
+        let mut ihdr = 640u32.to_be_bytes().to_vec();
+        ihdr.extend_from_slice(&480u32.to_be_bytes());
+        ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+        let png_image = [
+            png_chunk(b"IHDR", &ihdr),
+            png_chunk(b"IDAT", b"pixels"),
+            png_chunk(b"IEND", b""),
+        ]
+        .concat();
+        let png = [
+            b"\x89PNG\r\n\x1a\n".to_vec(),
+            png_chunk(b"IHDR", &ihdr),
+            png_chunk(b"tEXt", b"Author\0Jane"),
+            png_chunk(b"eXIf", b"MM\0*"),
+            png_chunk(b"IDAT", b"pixels"),
+            png_chunk(b"IEND", b""),
+        ]
+        .concat();
+        assert_eq!(ImageFormat::detect(&png), Some(ImageFormat::Png));
+        assert_eq!(dimensions(ImageFormat::Png, &png), Some((640, 480)));
+        assert_eq!(
+            strip_metadata(ImageFormat::Png, &png)?,
+            [b"\x89PNG\r\n\x1a\n".to_vec(), png_image].concat()
+        );
+
+        let jpeg = [
+            &[0xff, 0xd8][..],
+            &[0xff, 0xe0, 0, 4, b'J', b'F'],
+            &[0xff, 0xe1, 0, 8, b'E', b'x', b'i', b'f', 0, 0],
+            &[0xff, 0xc0, 0, 11, 8, 0x01, 0xe0, 0x02, 0x80, 3, 0, 0, 0],
+            &[0xff, 0xda, 0, 2, 0x12, 0xff, 0xe1, 0xff, 0xd9],
+        ]
+        .concat();
+        assert_eq!(ImageFormat::detect(&jpeg), Some(ImageFormat::Jpeg));
+        assert_eq!(dimensions(ImageFormat::Jpeg, &jpeg), Some((640, 480)));
+        let stripped = strip_metadata(ImageFormat::Jpeg, &jpeg)?;
+        assert_eq!(stripped.len(), jpeg.len() - 10);
+        assert!(stripped.ends_with(&[0xff, 0xda, 0, 2, 0x12, 0xff, 0xe1, 0xff, 0xd9]));
+
+        let vp8x = [&[0x0c, 0, 0, 0][..], &[0x7f, 0x02, 0], &[0xdf, 0x01, 0]].concat();
+        let body = [
+            webp_chunk(b"VP8X", &vp8x),
+            webp_chunk(b"VP8L", b"\x2fpixel"),
+            webp_chunk(b"EXIF", b"MM\0*\0"),
+            webp_chunk(b"XMP ", b"<x/>"),
+        ]
+        .concat();
+        let webp = [
+            b"RIFF".to_vec(),
+            (body.len() as u32 + 4).to_le_bytes().to_vec(),
+            b"WEBP".to_vec(),
+            body,
+        ]
+        .concat();
+        assert_eq!(ImageFormat::detect(&webp), Some(ImageFormat::Webp));
+        assert_eq!(dimensions(ImageFormat::Webp, &webp), Some((640, 480)));
+        let stripped = strip_metadata(ImageFormat::Webp, &webp)?;
+        assert_eq!(stripped.len(), 12 + 18 + 14);
+        assert_eq!(stripped[20], 0);
+        assert_eq!(&stripped[4..8], &36u32.to_le_bytes());
+
+        // Stripping alone needs no browser; other data passes through
+        let policy = ImagePolicy::new().with_strip_metadata(true);
+        assert!(!policy.needs_browser());
+        assert_eq!(policy.apply(png.clone())?.data.len(), png.len() - 39);
+        let gif = b"GIF89a".to_vec();
+        assert_eq!(
+            policy.apply(gif.clone())?,
+            PolicyImage {
+                data: gif,
+                format: None
+            }
+        );
+        assert!(
+            ImagePolicy::new()
+                .with_format(ImageFormat::Jpeg)
+                .apply(png)
+                .is_err()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+//! Browsable indexes of output directories
+//!
+//! `index.md` and `index.html` list the pages saved into an output directory, read from
+//! its manifest: the title, source domain, save date, total size, and tags of each page,
+//! with links to its files. The HTML index needs nothing but a browser: rows can be
+//! filtered by text, domain, and tag and sorted by any column, and each page is shown
+//! with a thumbnail of its first image when it has one. Pages saved from search results
+//! are listed with the passages most relevant to the query.
+
+use crate::archive;
+use crate::atomic;
+use crate::manifest::OutputManifest;
+use crate::space::format_size;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::sync::LazyLock;
+use tera::{Context, Tera};
+use url::Url;
+
+/// Name of the Markdown index written into an output directory
+pub const INDEX_MARKDOWN: &str = "index.md";
+
+/// Name of the HTML index written into an output directory
+pub const INDEX_HTML: &str = "index.html";
+
+/// The first image of a Markdown document
+static MARKDOWN_IMAGE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!\[[^\]]*\]\(<?([^)\s>]+)>?\)").expect("valid regex"));
+
+const INDEX_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Saved pages</title>
+<style>
+  body { margin: 1.5em; font-family: system-ui, -apple-system, "Segoe UI", "Noto Sans", sans-serif; color: #111; }
+  .filters { display: flex; flex-wrap: wrap; gap: 0.5em; margin-bottom: 1em; align-items: center; }
+  .filters input { flex: 1; min-width: 12em; }
+  input, select { font: inherit; padding: 0.3em; }
+  table { border-collapse: collapse; width: 100%; }
+  th, td { text-align: left; padding: 0.4em; border-bottom: 1px solid #ddd; vertical-align: top; }
+  th[data-sort] { cursor: pointer; user-select: none; white-space: nowrap; }
+  th[data-order="asc"]::after { content: " ▲"; }
+  th[data-order="desc"]::after { content: " ▼"; }
+  .thumbnail { width: 96px; }
+  .thumbnail img { width: 96px; max-height: 72px; object-fit: cover; border-radius: 4px; }
+  .source, .count { color: #666; font-size: 0.85em; word-break: break-all; }
+  .tag { display: inline-block; background: #eee; border-radius: 3px; padding: 0 0.4em; margin: 0 0.2em 0.2em 0; font-size: 0.85em; }
+  .size { white-space: nowrap; text-align: right; }
+  .snippets { margin: 0.3em 0 0; padding-left: 1.2em; color: #333; font-size: 0.9em; }
+</style>
+</head>
+<body>
+<h1>Saved pages</h1>
+<div class="filters">
+  <input id="search" type="search" placeholder="Filter by title, URL, or tag">
+  <select id="domain"><option value="">All domains</option>{% for domain in domains %}<option>{{ domain }}</option>{% endfor %}</select>
+  {% if tags %}<select id="tag"><option value="">All tags</option>{% for tag in tags %}<option>{{ tag }}</option>{% endfor %}</select>{% endif %}
+  <span class="count" id="count">{{ rows | length }} pages</span>
+</div>
+<table id="captures">
+<thead><tr><th></th><th data-sort="title">Title</th><th data-sort="domain">Domain</th><th data-sort="date" data-order="desc">Saved</th><th data-sort="size">Size</th><th>Tags</th><th>Files</th></tr></thead>
+<tbody>
+{% for row in rows %}<tr data-title="{{ row.entry.title | lower }}" data-domain="{{ row.entry.domain }}" data-date="{{ row.date }}" data-size="{{ row.entry.size }}" data-tags="{{ row.entry.tags | join(sep="|") | lower }}" data-text="{{ row.entry.title | lower }} {{ row.entry.url | lower }} {{ row.entry.tags | join(sep=" ") | lower }} {{ row.entry.snippets | join(sep=" ") | lower }}">
+<td class="thumbnail">{% if row.entry.thumbnail %}<img src="{{ row.entry.thumbnail }}" alt="" loading="lazy" onerror="this.remove()">{% endif %}</td>
+<td><a href="{{ row.entry.url }}">{{ row.entry.title }}</a><div class="source">{{ row.entry.url }}</div>{% if row.entry.snippets %}<ul class="snippets">{% for snippet in row.entry.snippets %}<li>{{ snippet }}</li>{% endfor %}</ul>{% endif %}</td>
+<td>{{ row.entry.domain }}</td>
+<td><time datetime="{{ row.date }}">{{ row.saved }}</time></td>
+<td class="size">{{ row.size }}</td>
+<td>{% for tag in row.entry.tags %}<span class="tag">{{ tag }}</span>{% endfor %}</td>
+<td>{% for file in row.entry.files %}<a href="{{ file }}">{{ file }}</a><br>{% endfor %}</td>
+</tr>
+{% endfor %}</tbody>
+</table>
+<script>
+(() => {
+  const table = document.getElementById('captures');
+  const body = table.tBodies[0];
+  const rows = Array.from(body.rows);
+  const search = document.getElementById('search');
+  const domain = document.getElementById('domain');
+  const tag = document.getElementById('tag');
+  const count = document.getElementById('count');
+
+  const filter = () => {
+    const text = search.value.trim().toLowerCase();
+    const tagValue = tag ? tag.value.toLowerCase() : '';
+    let shown = 0;
+    for (const row of rows) {
+      const visible = row.dataset.text.includes(text) &&
+        (!domain.value || row.dataset.domain === domain.value) &&
+        (!tagValue || row.dataset.tags.split('|').includes(tagValue));
+      row.hidden = !visible;
+      shown += visible ? 1 : 0;
+    }
+    count.textContent = shown === rows.length ? rows.length + ' pages' : shown + ' of ' + rows.length + ' pages';
+  };
+
+  for (const header of table.querySelectorAll('th[data-sort]')) {
+    header.addEventListener('click', () => {
+      const key = header.dataset.sort;
+      const order = header.dataset.order === 'asc' ? 'desc' : 'asc';
+      for (const other of table.querySelectorAll('th[data-sort]')) {
+        delete other.dataset.order;
+      }
+      header.dataset.order = order;
+      const value = (row) => key === 'size' ? Number(row.dataset.size) : row.dataset[key];
+      rows.sort((a, b) => {
+        const x = value(a);
+        const y = value(b);
+        const compared = x < y ? -1 : x > y ? 1 : 0;
+        return order === 'asc' ? compared : -compared;
+      });
+      body.append(...rows);
+    });
+  }
+
+  for (const input of [search, domain, tag]) {
+    if (input) {
+      input.addEventListener('input', filter);
+    }
+  }
+})();
+</script>
+</body>
+</html>
+"#;
+
+/// A saved page listed in an index
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexEntry {
+    /// The page title, or its URL when unknown
+    pub title: String,
+    /// The source URL
+    pub url: String,
+    /// Host of the source URL
+    pub domain: String,
+    /// When the page was saved
+    pub saved_at: DateTime<Utc>,
+    /// Saved files that still exist, relative to the output directory
+    pub files: Vec<String>,
+    /// Total size of the files in bytes
+    pub size: u64,
+    /// Tags the page was saved with
+    pub tags: Vec<String>,
+    /// URL of the first image of the page, if it has one
+    pub thumbnail: Option<String>,
+    /// Passages of the page relevant to the search query it was found with
+    pub snippets: Vec<String>,
+}
+
+/// An entry with the values shown for it in the HTML index
+#[derive(Serialize)]
+struct IndexRow<'a> {
+    entry: &'a IndexEntry,
+    date: String,
+    saved: String,
+    size: String,
+}
+
+/// The pages recorded in an output directory's manifest whose files still exist,
+/// newest first
+pub async fn entries(dir: &Path, manifest: &OutputManifest) -> Vec<IndexEntry> {
+    let mut entries = Vec::new();
+    for saved in &manifest.entries {
+        let mut files = Vec::new();
+        let mut size = 0;
+        let mut markdown = None;
+        for file in &saved.files {
+            let path = dir.join(file);
+            let Ok(metadata) = tokio::fs::metadata(&path).await else {
+                continue;
+            };
+            size += metadata.len();
+            if markdown.is_none() && path.extension().is_some_and(|ext| ext == "md") {
+                markdown = tokio::fs::read_to_string(&path).await.ok();
+            }
+            files.push(file.clone());
+        }
+        if files.is_empty() {
+            continue;
+        }
+
+        let page_url = Url::parse(&saved.url).ok();
+        let thumbnail = markdown
+            .as_deref()
+            .and_then(|markdown| MARKDOWN_IMAGE.captures(markdown))
+            .and_then(|captures| match &page_url {
+                Some(base) => base.join(&captures[1]).ok(),
+                None => Url::parse(&captures[1]).ok(),
+            })
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .map(String::from);
+        entries.push(IndexEntry {
+            title: markdown
+                .as_deref()
+                .and_then(archive::markdown_title)
+                .unwrap_or_else(|| saved.url.clone()),
+            url: saved.url.clone(),
+            domain: page_url
+                .as_ref()
+                .and_then(|url| url.host_str())
+                .unwrap_or_default()
+                .to_string(),
+            saved_at: saved.saved_at,
+            files,
+            size,
+            tags: saved.tags.clone(),
+            thumbnail,
+            snippets: saved.snippets.clone(),
+        });
+    }
+    entries.sort_by(|a, b| b.saved_at.cmp(&a.saved_at));
+    entries
+}
+
+/// Render an index as a Markdown table
+pub fn to_markdown(entries: &[IndexEntry]) -> String {
+    let cell = |text: &str| text.replace('|', "\\|").replace('\n', " ");
+    let mut markdown = String::from("# Saved pages\n\n");
+    markdown.push_str("| Title | Domain | Saved | Size | Tags | Files |\n");
+    markdown.push_str("| --- | --- | --- | --- | --- | --- |\n");
+    for entry in entries {
+        let files: Vec<String> = entry
+            .files
+            .iter()
+            .map(|file| {
+                let name = Path::new(file)
+                    .extension()
+                    .map_or_else(|| file.clone(), |ext| ext.to_string_lossy().into_owned());
+                format!("[{}](<{}>)", cell(&name), file)
+            })
+            .collect();
+        markdown.push_str(&format!(
+            "| [{}]({}) | {} | {} | {} | {} | {} |\n",
+            cell(&entry.title).replace('[', "\\[").replace(']', "\\]"),
+            entry.url,
+            entry.domain,
+            entry.saved_at.format("%Y-%m-%d %H:%M"),
+            format_size(entry.size),
+            cell(&entry.tags.join(", ")),
+            files.join(" "),
+        ));
+    }
+
+    let with_snippets: Vec<&IndexEntry> = entries
+        .iter()
+        .filter(|entry| !entry.snippets.is_empty())
+        .collect();
+    if !with_snippets.is_empty() {
+        markdown.push_str("\n## Relevant passages\n");
+        for entry in with_snippets {
+            markdown.push_str(&format!(
+                "\n### [{}]({})\n\n",
+                entry.title.replace('[', "\\[").replace(']', "\\]"),
+                entry.url
+            ));
+            for snippet in &entry.snippets {
+                markdown.push_str(&format!("> {}\n>\n", snippet));
+            }
+            markdown.truncate(markdown.len() - 2);
+        }
+    }
+    markdown
+}
+
+/// Render an index as an HTML page with filtering and sorting
+///
+/// # Errors
+///
+/// Returns an error if the template fails to render
+pub fn to_html(entries: &[IndexEntry]) -> Result<String> {
+    let rows: Vec<IndexRow> = entries
+        .iter()
+        .map(|entry| IndexRow {
+            entry,
+            date: entry.saved_at.to_rfc3339(),
+            saved: entry.saved_at.format("%Y-%m-%d %H:%M").to_string(),
+            size: format_size(entry.size),
+        })
+        .collect();
+    let domains: BTreeSet<&str> = entries
+        .iter()
+        .map(|entry| entry.domain.as_str())
+        .filter(|domain| !domain.is_empty())
+        .collect();
+    let tags: BTreeSet<String> = entries
+        .iter()
+        .flat_map(|entry| entry.tags.iter().map(|tag| tag.to_lowercase()))
+        .collect();
+
+    let mut context = Context::new();
+    context.insert("rows", &rows);
+    context.insert("domains", &domains);
+    context.insert("tags", &tags);
+    Ok(Tera::one_off(INDEX_TEMPLATE, &context, true)?)
+}
+
+/// Write `index.md` and `index.html` into an output directory
+///
+/// # Returns
+///
+/// Returns the number of pages listed
+///
+/// # Errors
+///
+/// Returns an error if the manifest cannot be read or an index cannot be written
+pub async fn write_index(dir: &Path) -> Result<usize> {
+    let manifest = OutputManifest::load(dir).await?;
+    let entries = entries(dir, &manifest).await;
+    atomic::write(&dir.join(INDEX_MARKDOWN), to_markdown(&entries)).await?;
+    atomic::write(&dir.join(INDEX_HTML), to_html(&entries)?).await?;
+    Ok(entries.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_write_index() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        std::fs::write(
+            dir.join("post.md"),
+            "# Rust <Tips> | 2026\n\n![Chart](/img/chart.png)\n\nBody\n",
+        )?;
+        std::fs::write(dir.join("post.pdf"), vec![0; 2048])?;
+        std::fs::write(dir.join("other.pdf"), "%PDF")?;
+
+        let mut manifest = OutputManifest::default();
+        manifest.record(
+            dir,
+            "https://blog.example.com/post",
+            &[dir.join("post.pdf"), dir.join("post.md")],
+        );
+        manifest.tag("https://blog.example.com/post", &["Rust".to_string()]);
+        manifest.set_snippets(
+            "https://blog.example.com/post",
+            vec!["Rust <tips> for everyone".to_string()],
+        );
+        manifest.record(dir, "https://example.org/other", &[dir.join("other.pdf")]);
+        manifest.record(dir, "https://example.org/gone", &[dir.join("gone.pdf")]);
+        manifest.save(dir).await?;
+
+        assert_eq!(write_index(dir).await?, 2);
+        let entries = entries(dir, &manifest).await;
+        let post = entries
+            .iter()
+            .find(|entry| entry.url == "https://blog.example.com/post")
+            .unwrap();
+        assert_eq!(post.title, "Rust <Tips> | 2026");
+        assert_eq!(post.domain, "blog.example.com");
+        assert_eq!(
+            post.size,
+            2048 + std::fs::metadata(dir.join("post.md"))?.len()
+        );
+        assert_eq!(
+            post.thumbnail.as_deref(),
+            Some("https://blog.example.com/img/chart.png")
+        );
+        let other = entries
+            .iter()
+            .find(|entry| entry.url == "https://example.org/other")
+            .unwrap();
+        assert_eq!(other.title, "https://example.org/other");
+        assert_eq!(other.thumbnail, None);
+
+        let markdown = std::fs::read_to_string(dir.join(INDEX_MARKDOWN))?;
+        assert!(markdown.contains(
+            "| [Rust <Tips> \\| 2026](https://blog.example.com/post) | blog.example.com |"
+        ));
+        assert!(markdown.contains("| Rust | [pdf](<post.pdf>) [md](<post.md>) |"));
+        assert!(markdown.ends_with(
+            "## Relevant passages\n\n### [Rust <Tips> | 2026](https://blog.example.com/post)\n\n\
+             > Rust <tips> for everyone\n"
+        ));
+
+        let html = std::fs::read_to_string(dir.join(INDEX_HTML))?;
+        assert!(html.contains("Rust &lt;Tips&gt; | 2026"));
+        assert!(html.contains(r#"data-domain="blog.example.com""#));
+        assert!(html.contains(r#"data-tags="rust""#));
+        assert!(html.contains(r#"<option>example.org</option>"#));
+        assert!(html.contains("<li>Rust &lt;tips&gt; for everyone</li>"));
+        Ok(())
+    }
+}
@@ -3,29 +3,176 @@
 //! This module provides functionality to search for URLs using the Brave Search API
 //! and then convert those URLs to PDF format.
 
-use crate::markdown::MarkdownGenerator;
-use crate::pdf::PdfGenerator;
-use crate::search::{BraveSearchClient, SearchConfig, SearchType};
+use crate::alttext::AltTextClient;
+use crate::assets::MarkdownImages;
+use crate::atomic;
+use crate::blocklist::DomainBlocklist;
+use crate::branding::Branding;
+use crate::brief::{self, Brief};
+use crate::cache::{PageCache, SearchCache};
+use crate::chunk::{self, ChunkConfig};
+use crate::cookies::BrowserCookies;
+use crate::deterministic::DeterministicRendering;
+use crate::epub::EpubGenerator;
+use crate::events;
+use crate::extract::ExtractConfig;
+use crate::index;
+use crate::manifest::{OutputManifest, OutputSlot};
+use crate::markdown::{self, MarkdownConfig, MarkdownGenerator, MarkdownPage};
+use crate::paper::{Paper, PrintScale};
+use crate::pdf::{self, Media, PdfConfig, PdfGenerator, PdfGeneratorPool, ScreenshotFormat};
+use crate::pii::PiiScanner;
+use crate::places::{PlaceCard, PlaceMaps};
+use crate::printview::PrintViews;
+use crate::proxy::ProxyConfig;
+use crate::reader::ReaderMode;
+use crate::request::RequestOptions;
+use crate::resolver::DnsResolver;
+pub use crate::search::SearchResult;
+use crate::search::{BraveSearchClient, LocalSearchResult, SearchConfig, SearchType};
+use crate::snippets;
+use crate::social::SocialThreads;
+use crate::space;
+use crate::stats::RunStats;
+use crate::timeouts::AdaptiveTimeouts;
+use crate::vcard::{self, Contact};
+use crate::viewport::Viewport;
+use crate::virusscan::{Quarantined, ScanHook};
+use crate::wait::WaitStrategy;
+use crate::wikipedia::Wikipedia;
 use anyhow::Result;
-use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::fs;
 use tracing::{error, info, warn};
 
-/// A search result that can be converted to PDF
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SearchResult {
-    pub title: String,
-    pub url: String,
-    pub description: String,
-}
-
 /// Output format for search results
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum OutputFormat {
     Pdf,
     Markdown,
     Both,
+    /// Full-page screenshots instead of PDFs
+    Screenshot,
+    /// EPUB books of the main content, for e-readers
+    Epub,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputFormat::Pdf => write!(f, "PDF"),
+            OutputFormat::Markdown => write!(f, "Markdown"),
+            OutputFormat::Both => write!(f, "PDF and Markdown"),
+            OutputFormat::Screenshot => write!(f, "screenshot"),
+            OutputFormat::Epub => write!(f, "EPUB"),
+        }
+    }
+}
+
+/// Outcome of converting one page to each requested format
+///
+/// Each format is attempted independently, so a failure in one (say, the PDF) does not
+/// prevent the others from being written.
+#[derive(Debug, Default)]
+pub struct FormatOutcome {
+    /// Files written for the formats that were converted
+    pub files: Vec<PathBuf>,
+    /// Formats that were converted
+    pub converted: Vec<OutputFormat>,
+    /// Formats that failed, with their errors
+    pub failed: Vec<(OutputFormat, anyhow::Error)>,
+}
+
+impl FormatOutcome {
+    /// Record the result of converting to one format
+    pub fn record(&mut self, format: OutputFormat, result: Result<Vec<PathBuf>>) {
+        match result {
+            Ok(files) => {
+                self.files.extend(files);
+                self.converted.push(format);
+            }
+            Err(e) => self.failed.push((format, e)),
+        }
+    }
+
+    /// Run a virus-scan hook over the written files, quarantining infected ones
+    ///
+    /// A format with an infected file, or with a file that could not be scanned, is
+    /// moved from the converted to the failed formats, so its output is not delivered,
+    /// archived, or recorded as saved.
+    ///
+    /// # Returns
+    ///
+    /// Returns the files moved into quarantine
+    pub fn screen(&mut self, hook: &ScanHook) -> Vec<Quarantined> {
+        let mut quarantined = Vec::new();
+        let mut rejected = Vec::new();
+        self.files.retain(|file| {
+            let format = match file.extension().and_then(|ext| ext.to_str()) {
+                Some("pdf") => OutputFormat::Pdf,
+                Some("png" | "jpg" | "webp") => OutputFormat::Screenshot,
+                Some("epub") => OutputFormat::Epub,
+                _ => OutputFormat::Markdown,
+            };
+            if !file.exists() {
+                return true;
+            }
+            match hook.screen(file) {
+                Ok(None) => true,
+                Ok(Some(file)) => {
+                    rejected.push((
+                        format,
+                        anyhow::anyhow!("Quarantined as infected: {}", file.finding),
+                    ));
+                    quarantined.push(file);
+                    false
+                }
+                Err(e) => {
+                    rejected.push((format, e.context("Virus scan failed")));
+                    true
+                }
+            }
+        });
+        for (format, error) in rejected {
+            self.converted.retain(|converted| *converted != format);
+            if !self.failed.iter().any(|(failed, _)| *failed == format) {
+                self.failed.push((format, error));
+            }
+        }
+        quarantined
+    }
+
+    /// Whether every requested format was converted
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Whether some, but not all, requested formats were converted
+    pub fn is_partial(&self) -> bool {
+        !self.converted.is_empty() && !self.failed.is_empty()
+    }
+
+    /// A summary of the failed formats, e.g. `PDF failed (Markdown saved)`
+    pub fn failure_summary(&self) -> String {
+        let failed = join_formats(self.failed.iter().map(|(format, _)| format));
+        match self.converted.is_empty() {
+            true => format!("{} failed", failed),
+            false => format!(
+                "{} failed ({} saved)",
+                failed,
+                join_formats(self.converted.iter())
+            ),
+        }
+    }
+}
+
+fn join_formats<'a>(formats: impl Iterator<Item = &'a OutputFormat>) -> String {
+    formats
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 /// Configuration for search-to-PDF operations
@@ -41,6 +188,62 @@ pub struct SearchToPdfConfig {
     pub naming_strategy: NamingStrategy,
     /// Output format
     pub output_format: OutputFormat,
+    /// Write all Markdown output into a single document named after the query
+    pub merge_markdown: bool,
+    /// Split Markdown output into numbered chunk files instead of one file per document
+    pub chunking: Option<ChunkConfig>,
+    /// Convert results again even if the output directory already has them
+    pub force: bool,
+    /// Check for enough free disk space before converting
+    pub check_space: bool,
+    /// Passages relevant to the query kept per result in the output manifest (0 for none)
+    pub max_snippets: usize,
+    /// Most results converted to PDF at a time
+    pub concurrency: usize,
+    /// Save a compact Markdown brief of each result, and a combined brief named after
+    /// the query, instead of converting the results to `output_format`
+    pub extract_only: bool,
+    /// Capture a full-page screenshot of each result alongside `output_format`
+    pub screenshots: bool,
+    /// Image format of screenshots
+    pub screenshot_format: ScreenshotFormat,
+    /// Save the schema.org events of each result, such as conference sessions, as an
+    /// iCalendar file next to its other output
+    pub calendar: bool,
+    /// Save the places of a local search, and the businesses and people described on
+    /// each result, as vCard files next to the other output
+    pub contacts: bool,
+}
+
+impl SearchToPdfConfig {
+    /// The format results are captured in as screenshots, if they are
+    fn screenshot(&self) -> Option<ScreenshotFormat> {
+        let requested = self.screenshots || self.output_format == OutputFormat::Screenshot;
+        (requested && !self.extract_only).then_some(self.screenshot_format)
+    }
+}
+
+/// The passages of a converted result most relevant to the query, from its Markdown
+/// output, or from its search result description when only a PDF was saved
+async fn relevant_passages(
+    result: &SearchResult,
+    files: &[PathBuf],
+    query: &str,
+    max: usize,
+) -> Vec<String> {
+    let mut documents = Vec::new();
+    for file in files
+        .iter()
+        .filter(|file| file.extension().is_some_and(|ext| ext == "md"))
+    {
+        if let Ok(markdown) = fs::read_to_string(file).await {
+            documents.push(markdown);
+        }
+    }
+    if documents.is_empty() {
+        documents.push(mdka::from_html(&result.description));
+    }
+    snippets::relevant_passages(documents.iter().map(String::as_str), query, max)
 }
 
 /// Strategy for naming PDF files
@@ -64,6 +267,17 @@ impl Default for SearchToPdfConfig {
             include_metadata: true,
             naming_strategy: NamingStrategy::TitleDomain,
             output_format: OutputFormat::Pdf,
+            merge_markdown: false,
+            chunking: None,
+            force: false,
+            check_space: true,
+            max_snippets: snippets::DEFAULT_MAX_SNIPPETS,
+            concurrency: pdf::DEFAULT_CONCURRENCY,
+            extract_only: false,
+            screenshots: false,
+            screenshot_format: ScreenshotFormat::Png,
+            calendar: false,
+            contacts: false,
         }
     }
 }
@@ -73,6 +287,9 @@ pub struct SearchToPdfClient {
     search_client: BraveSearchClient,
     pdf_generator: PdfGenerator,
     markdown_generator: MarkdownGenerator,
+    blocklist: Option<DomainBlocklist>,
+    scan_hook: Option<ScanHook>,
+    place_maps: Option<PlaceMaps>,
 }
 
 impl SearchToPdfClient {
@@ -98,9 +315,303 @@ impl SearchToPdfClient {
             search_client,
             pdf_generator,
             markdown_generator,
+            blocklist: None,
+            scan_hook: None,
+            place_maps: None,
         })
     }
 
+    /// Create a search-to-PDF client whose browser uses a persistent profile directory
+    ///
+    /// Results are loaded in the browser for both PDF and Markdown output, with the
+    /// cookies of the profile (see [`crate::session`]).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search client cannot be initialized or the browser cannot
+    /// be launched on the profile
+    pub async fn new_with_profile(api_key: Option<String>, profile_dir: &Path) -> Result<Self> {
+        let search_client = BraveSearchClient::new(api_key)?;
+        let pdf_generator = PdfGenerator::new_with_profile(profile_dir).await?;
+        let markdown_generator = MarkdownGenerator::new()
+            .await?
+            .with_browser(pdf_generator.browser().clone());
+
+        Ok(Self {
+            search_client,
+            pdf_generator,
+            markdown_generator,
+            blocklist: None,
+            scan_hook: None,
+            place_maps: None,
+        })
+    }
+
+    /// Create a search-to-PDF client that searches and loads results through a proxy,
+    /// optionally with its browser on a persistent profile directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search client cannot be initialized, or the browser or
+    /// HTTP client cannot use the proxy
+    pub async fn new_with_proxy(
+        api_key: Option<String>,
+        profile_dir: Option<&Path>,
+        proxy: ProxyConfig,
+    ) -> Result<Self> {
+        let search_client = BraveSearchClient::new(api_key)?.with_proxy(proxy.clone());
+        let pdf_generator = PdfGenerator::new_with_proxy(profile_dir, proxy.clone()).await?;
+        let mut markdown_generator = MarkdownGenerator::new().await?.with_proxy(proxy)?;
+        if profile_dir.is_some() {
+            markdown_generator = markdown_generator.with_browser(pdf_generator.browser().clone());
+        }
+
+        Ok(Self {
+            search_client,
+            pdf_generator,
+            markdown_generator,
+            blocklist: None,
+            scan_hook: None,
+            place_maps: None,
+        })
+    }
+
+    /// Create a search-to-PDF client that resolves hosts with a custom resolver,
+    /// optionally with its browser on a persistent profile directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search client cannot be initialized, or the browser's
+    /// resolving proxy cannot be started
+    pub async fn new_with_resolver(
+        api_key: Option<String>,
+        profile_dir: Option<&Path>,
+        resolver: DnsResolver,
+    ) -> Result<Self> {
+        let search_client = BraveSearchClient::new(api_key)?.with_resolver(resolver.clone());
+        let pdf_generator =
+            PdfGenerator::new_with_proxy(profile_dir, resolver.browser_proxy()?).await?;
+        let mut markdown_generator = MarkdownGenerator::new().await?.with_resolver(resolver)?;
+        if profile_dir.is_some() {
+            markdown_generator = markdown_generator.with_browser(pdf_generator.browser().clone());
+        }
+
+        Ok(Self {
+            search_client,
+            pdf_generator,
+            markdown_generator,
+            blocklist: None,
+            scan_hook: None,
+            place_maps: None,
+        })
+    }
+
+    /// Serve search responses from an on-disk cache, storing new responses in it
+    pub fn with_search_cache(mut self, cache: SearchCache) -> Self {
+        self.search_client = self.search_client.with_cache(cache);
+        self
+    }
+
+    /// Save a snapshot of every converted page in the given cache
+    pub fn with_page_cache(mut self, cache: PageCache) -> Self {
+        self.pdf_generator = self.pdf_generator.with_page_cache(cache.clone());
+        self.markdown_generator = self.markdown_generator.with_page_cache(cache);
+        self
+    }
+
+    /// Skip results whose domain is on a blocklist, recording them in the run statistics
+    pub fn with_blocklist(mut self, blocklist: DomainBlocklist) -> Self {
+        self.blocklist = Some(blocklist);
+        self
+    }
+
+    /// Save a card with the address and a map of every place found by local searches
+    pub fn with_place_maps(mut self, maps: PlaceMaps) -> Self {
+        self.place_maps = Some(maps);
+        self
+    }
+
+    /// Scan converted files with a virus-scan hook, quarantining infected ones
+    pub fn with_scan_hook(mut self, hook: ScanHook) -> Self {
+        self.scan_hook = Some(hook);
+        self
+    }
+
+    /// Scan the content of every converted page for personal data
+    pub fn with_pii_scanner(mut self, scanner: PiiScanner) -> Self {
+        self.pdf_generator = self.pdf_generator.with_pii_scanner(scanner.clone());
+        self.markdown_generator = self.markdown_generator.with_pii_scanner(scanner);
+        self
+    }
+
+    /// Generate alt text for the images of Markdown pages that have no description
+    pub fn with_alt_text(mut self, client: AltTextClient) -> Self {
+        self.markdown_generator = self.markdown_generator.with_alt_text(client);
+        self
+    }
+
+    /// Write Markdown output with these options, such as front matter
+    pub fn with_markdown_config(mut self, config: MarkdownConfig) -> Self {
+        self.markdown_generator = self.markdown_generator.with_config(config);
+        self
+    }
+
+    /// Download or inline the images of Markdown output
+    pub fn with_markdown_images(mut self, images: MarkdownImages) -> Self {
+        self.markdown_generator = self.markdown_generator.with_images(images);
+        self
+    }
+
+    /// Load pages with per-domain timeouts that grow for slow domains
+    pub fn with_adaptive_timeouts(mut self, timeouts: AdaptiveTimeouts) -> Self {
+        self.pdf_generator = self.pdf_generator.with_adaptive_timeouts(timeouts.clone());
+        self.markdown_generator = self.markdown_generator.with_adaptive_timeouts(timeouts);
+        self
+    }
+
+    /// Follow "next page" links of paginated articles for up to this many further pages,
+    /// stitching all parts into one document
+    pub fn with_follow_pagination(mut self, max_pages: usize) -> Self {
+        self.pdf_generator = self.pdf_generator.with_follow_pagination(max_pages);
+        self.markdown_generator = self.markdown_generator.with_follow_pagination(max_pages);
+        self
+    }
+
+    /// Render pages with JavaScript in the browser before converting them to Markdown,
+    /// so that single-page applications do not produce empty Markdown
+    pub fn with_render_js(mut self, render_js: bool) -> Self {
+        if render_js {
+            self.markdown_generator = self
+                .markdown_generator
+                .with_browser(self.pdf_generator.browser().clone())
+                .with_render_js(true);
+        }
+        self
+    }
+
+    /// Load the print view of pages when one is available
+    pub fn with_print_views(mut self, print_views: PrintViews) -> Self {
+        self.pdf_generator = self.pdf_generator.with_print_views(print_views.clone());
+        self.markdown_generator = self.markdown_generator.with_print_views(print_views);
+        self
+    }
+
+    /// Capture Twitter/X and Mastodon results through the platform APIs
+    pub fn with_social_threads(mut self, social_threads: SocialThreads) -> Self {
+        self.pdf_generator = self
+            .pdf_generator
+            .with_social_threads(social_threads.clone());
+        self.markdown_generator = self.markdown_generator.with_social_threads(social_threads);
+        self
+    }
+
+    /// Export Wikipedia results through the REST API
+    pub fn with_wikipedia(mut self, wikipedia: Wikipedia) -> Self {
+        self.pdf_generator = self.pdf_generator.with_wikipedia(wikipedia.clone());
+        self.markdown_generator = self.markdown_generator.with_wikipedia(wikipedia);
+        self
+    }
+
+    /// Load results with the cookies imported from a browser that apply to them
+    pub fn with_cookies(mut self, cookies: BrowserCookies) -> Self {
+        self.pdf_generator = self.pdf_generator.with_cookies(cookies.clone());
+        self.markdown_generator = self.markdown_generator.with_cookies(cookies);
+        self
+    }
+
+    /// Send extra headers, such as `Authorization`, with the requests for results
+    pub fn with_request_options(mut self, options: RequestOptions) -> Self {
+        self.pdf_generator = self.pdf_generator.with_request_options(options.clone());
+        self.markdown_generator = self.markdown_generator.with_request_options(options);
+        self
+    }
+
+    /// Render PDFs deterministically, so repeated searches give identical PDFs for
+    /// unchanged results
+    pub fn with_deterministic(mut self, deterministic: DeterministicRendering) -> Self {
+        self.pdf_generator = self.pdf_generator.with_deterministic(deterministic);
+        self
+    }
+
+    /// Lay PDFs out for this CSS media type instead of print stylesheets
+    pub fn with_media(mut self, media: Media) -> Self {
+        self.pdf_generator = self.pdf_generator.with_media(media);
+        self
+    }
+
+    /// Render results in a viewport of this size
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.pdf_generator = self.pdf_generator.with_viewport(viewport);
+        self
+    }
+
+    /// Print results mostly taken up by wide tables or code blocks in landscape
+    pub fn with_auto_landscape(mut self, auto_landscape: bool) -> Self {
+        self.pdf_generator = self.pdf_generator.with_auto_landscape(auto_landscape);
+        self
+    }
+
+    /// Print PDFs at a fixed scale, or shrink content wider than the page
+    pub fn with_scale(mut self, scale: PrintScale) -> Self {
+        self.pdf_generator = self.pdf_generator.with_scale(scale);
+        self
+    }
+
+    /// Render PDFs from the extracted main content of pages in a reader template
+    pub fn with_reader(mut self, reader: ReaderMode) -> Self {
+        self.pdf_generator = self.pdf_generator.with_reader(reader);
+        self
+    }
+
+    /// Print PDFs on the page size of a paper preset instead of A4
+    pub fn with_paper(mut self, paper: Paper) -> Self {
+        self.pdf_generator = self.pdf_generator.with_paper(paper);
+        self
+    }
+
+    /// Lay PDFs out with a page size, orientation, margins, scale, header and footer,
+    /// page ranges, and backgrounds
+    pub fn with_pdf_config(mut self, config: PdfConfig) -> Self {
+        self.pdf_generator = self.pdf_generator.with_config(config);
+        self
+    }
+
+    /// Serve searches and conversions only from the caches, never using the network
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.search_client = self.search_client.with_offline(offline);
+        self.pdf_generator = self.pdf_generator.with_offline(offline);
+        self.markdown_generator = self.markdown_generator.with_offline(offline);
+        self
+    }
+
+    /// Set the delay applied after page navigation before printing each PDF
+    ///
+    /// A zero duration disables the extra wait entirely.
+    pub fn with_wait(mut self, wait: Duration) -> Self {
+        self.pdf_generator = self.pdf_generator.with_wait(wait);
+        self
+    }
+
+    /// Wait for a selector or for the network to go idle after page navigation,
+    /// instead of a fixed delay, for PDFs and pages rendered for Markdown
+    pub fn with_wait_strategy(mut self, wait: WaitStrategy) -> Self {
+        self.pdf_generator = self.pdf_generator.with_wait_strategy(wait.clone());
+        self.markdown_generator = self.markdown_generator.with_wait_strategy(wait);
+        self
+    }
+
+    /// Configure how the main content of pages is extracted for Markdown output
+    pub fn with_extraction(mut self, extraction: ExtractConfig) -> Self {
+        self.markdown_generator = self.markdown_generator.with_extraction(extraction);
+        self
+    }
+
+    /// Add a cover page and/or page header to every generated PDF
+    pub fn with_branding(mut self, branding: Branding) -> Self {
+        self.pdf_generator = self.pdf_generator.with_branding(branding);
+        self
+    }
+
     /// Search for URLs and convert them to PDF/Markdown/Both
     ///
     /// # Arguments
@@ -112,7 +623,9 @@ impl SearchToPdfClient {
     ///
     /// # Returns
     ///
-    /// Returns a vector of successfully converted PDF file paths
+    /// Returns a vector of successfully converted PDF file paths. Results already saved in
+    /// the output directory (see [`OutputManifest`]) are skipped unless `force` is set, and
+    /// their existing files are included instead.
     ///
     /// # Errors
     ///
@@ -123,23 +636,88 @@ impl SearchToPdfClient {
         query: &str,
         search_config: Option<SearchConfig>,
         pdf_config: SearchToPdfConfig,
+    ) -> Result<Vec<PathBuf>> {
+        self.search_and_convert_with_stats(
+            search_type,
+            query,
+            search_config,
+            pdf_config,
+            &mut RunStats::new(),
+        )
+        .await
+    }
+
+    /// Search for URLs and convert them like [`Self::search_and_convert_to_pdf`], recording
+    /// the outcome of each conversion in `stats`
+    ///
+    /// The statistics are recorded even when the operation fails part-way.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or if critical PDF conversion errors occur
+    pub async fn search_and_convert_with_stats(
+        &self,
+        search_type: SearchType,
+        query: &str,
+        search_config: Option<SearchConfig>,
+        pdf_config: SearchToPdfConfig,
+        stats: &mut RunStats,
     ) -> Result<Vec<PathBuf>> {
         info!(
             "Starting search-to-PDF operation: {} search for '{}'",
             search_type, query
         );
 
-        // Perform search
-        let search_results = self
-            .search_client
-            .search(search_type, query, search_config)
-            .await?;
+        // Places without a web page only get a card, so they are looked up separately
+        let places = match search_type {
+            SearchType::Local if self.place_maps.is_some() || pdf_config.contacts => self
+                .search_client
+                .places(query, search_config.clone())
+                .await
+                .inspect_err(|e| warn!("Failed to look up places for '{}': {}", query, e))
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        };
 
-        // Extract URLs from search results
-        let urls = self.extract_urls_from_results(&search_results)?;
+        let urls = match self
+            .search_client
+            .search_structured(search_type, query, search_config.clone())
+            .await
+        {
+            Ok(urls) => urls,
+            // Searches cached before typed results existed are only kept as text
+            Err(e) if self.search_client.is_offline() => match self
+                .search_client
+                .search(search_type, query, search_config)
+                .await
+            {
+                Ok(search_results) => extract_urls_from_results(&search_results)?,
+                Err(_) => return Err(e),
+            },
+            Err(e) => return Err(e),
+        };
 
         info!("Found {} URLs from search results", urls.len());
 
+        // Blocked results are dropped before the limit, so they do not take its places
+        let urls: Vec<_> = match &self.blocklist {
+            Some(blocklist) => urls
+                .into_iter()
+                .filter(|result| match blocklist.check(&result.url) {
+                    Some(blocked) => {
+                        warn!(
+                            "Skipping {}: {} is on blocklist {}",
+                            result.url, blocked.domain, blocked.list
+                        );
+                        stats.record_blocked(blocked);
+                        false
+                    }
+                    None => true,
+                })
+                .collect(),
+            None => urls,
+        };
+
         // Limit the number of results to process
         let urls_to_process: Vec<_> = urls.into_iter().take(pdf_config.max_results).collect();
         let total_urls = urls_to_process.len();
@@ -148,32 +726,237 @@ impl SearchToPdfClient {
 
         // Create output directory if it doesn't exist
         fs::create_dir_all(&pdf_config.output_dir).await?;
+        if pdf_config.check_space {
+            space::ensure_space(
+                &pdf_config.output_dir,
+                space::estimate(
+                    total_urls,
+                    // Books embed their images, so they are estimated like PDFs
+                    pdf_config.screenshot().is_some()
+                        || !pdf_config.extract_only
+                            && matches!(
+                                pdf_config.output_format,
+                                OutputFormat::Pdf | OutputFormat::Both | OutputFormat::Epub
+                            ),
+                    pdf_config.extract_only
+                        || matches!(
+                            pdf_config.output_format,
+                            OutputFormat::Markdown | OutputFormat::Both
+                        ),
+                ),
+            )?;
+        }
+
+        // Pages for a merged document are always converted again
+        let output_dir = &pdf_config.output_dir;
+        let mut manifest = match pdf_config.merge_markdown && !pdf_config.extract_only {
+            true => None,
+            false => Some(OutputManifest::load(output_dir).await?),
+        };
 
-        // Convert URLs to specified format
-        let mut converted_files = Vec::new();
+        // Output stems are decided first, so that PDFs can be generated concurrently
+        let mut converted_files = self.save_places(&places, &pdf_config).await;
+        let mut merged_pages = Vec::new();
+        let mut briefs = Vec::new();
+        let mut used_stems = HashSet::new();
+        let mut pending = Vec::new();
         for (index, result) in urls_to_process.into_iter().enumerate() {
-            match self.convert_url(&result, index, &pdf_config).await {
-                Ok(file_paths) => {
-                    for file_path in file_paths {
-                        info!(
-                            "Successfully converted: {} -> {}",
-                            result.url,
-                            file_path.display()
-                        );
-                        converted_files.push(file_path);
-                    }
-                }
+            let base = match self.generate_stem(&result, index, &pdf_config) {
+                Ok(base) => base,
                 Err(e) => {
                     error!("Failed to convert {}: {}", result.url, e);
-                    // Continue with other URLs instead of failing completely
+                    stats.record_failure(&result.url, Duration::ZERO, &e);
+                    continue;
                 }
+            };
+            let stem = match &manifest {
+                Some(manifest) => match manifest.resolve_output(
+                    output_dir,
+                    &result.url,
+                    &base,
+                    pdf_config.force,
+                    |stem| self.output_files(stem, &pdf_config),
+                    &mut used_stems,
+                ) {
+                    Ok(OutputSlot::Saved(files)) => {
+                        info!("Skipping {}: already saved", result.url);
+                        if pdf_config.extract_only {
+                            for file in &files {
+                                if let Ok(brief) = fs::read_to_string(file).await {
+                                    briefs.push((index, brief));
+                                }
+                            }
+                        }
+                        converted_files.extend(files);
+                        continue;
+                    }
+                    Ok(OutputSlot::Free(stem)) => stem,
+                    Err(e) => {
+                        error!("Failed to convert {}: {}", result.url, e);
+                        stats.record_failure(&result.url, Duration::ZERO, &e);
+                        continue;
+                    }
+                },
+                None => base,
+            };
+            pending.push((index, result, stem));
+        }
+
+        let mut pdfs = self
+            .convert_to_pdfs(&pending, &pdf_config)
+            .await
+            .into_iter();
+        let mut screenshots = self
+            .capture_screenshots(&pending, &pdf_config)
+            .await
+            .into_iter();
+        for (index, result, stem) in pending {
+            let started = Instant::now();
+            let pdf = pdfs.next();
+            let screenshot = screenshots.next();
+            let pdf_elapsed = [&pdf, &screenshot]
+                .into_iter()
+                .flatten()
+                .map(|(_, elapsed)| *elapsed)
+                .sum::<Duration>();
+            let mut outcome = match pdf_config.extract_only {
+                true => {
+                    let brief = self.extract_brief(&result, &stem, query, &pdf_config).await;
+                    let mut outcome = FormatOutcome::default();
+                    outcome.record(
+                        OutputFormat::Markdown,
+                        brief.map(|(path, brief)| {
+                            briefs.push((index, brief));
+                            vec![path]
+                        }),
+                    );
+                    outcome
+                }
+                false => {
+                    self.convert_url(
+                        &result,
+                        &stem,
+                        &pdf_config,
+                        pdf.map(|(pdf, _)| pdf),
+                        screenshot.map(|(screenshot, _)| screenshot),
+                        &mut merged_pages,
+                    )
+                    .await
+                }
+            };
+
+            if let Some(hook) = &self.scan_hook {
+                stats.record_quarantined(outcome.screen(hook));
+            }
+            // Calendars and contacts are generated rather than downloaded, so they are
+            // not scanned
+            if pdf_config.calendar && !outcome.converted.is_empty() {
+                match self.save_events(&result, &stem, &pdf_config).await {
+                    Ok(Some(path)) => outcome.files.push(path),
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to save the events of {}: {}", result.url, e),
+                }
+            }
+            if pdf_config.contacts && !outcome.converted.is_empty() {
+                match self.save_contacts(&result, &stem, &pdf_config).await {
+                    Ok(Some(path)) => outcome.files.push(path),
+                    Ok(None) => {}
+                    Err(e) => warn!("Failed to save the contacts of {}: {}", result.url, e),
+                }
+            }
+            stats.record_outcome(&result.url, started.elapsed() + pdf_elapsed, &outcome);
+
+            // Partially converted results are not recorded, so they are converted again
+            // next time
+            if let Some(manifest) = manifest
+                .as_mut()
+                .filter(|_| outcome.is_complete() && !outcome.files.is_empty())
+            {
+                manifest.record(output_dir, &result.url, &outcome.files);
+                if pdf_config.max_snippets > 0 {
+                    let passages =
+                        relevant_passages(&result, &outcome.files, query, pdf_config.max_snippets)
+                            .await;
+                    manifest.set_snippets(&result.url, passages);
+                }
+                if let Err(e) = manifest.save(output_dir).await {
+                    warn!("Failed to update the output manifest: {}", e);
+                }
+            }
+            for file_path in std::mem::take(&mut outcome.files) {
+                info!(
+                    "Successfully converted: {} -> {}",
+                    result.url,
+                    file_path.display()
+                );
+                converted_files.push(file_path);
+            }
+            // Continue with other URLs instead of failing completely
+            for (format, e) in &outcome.failed {
+                error!("Failed to convert {} to {}: {}", result.url, format, e);
+            }
+            if let Some((_, e)) = outcome
+                .failed
+                .into_iter()
+                .find(|(_, e)| space::is_disk_full(e))
+            {
+                return Err(e.context(format!(
+                    "Disk full after processing {} of {} URLs",
+                    index, total_urls
+                )));
             }
         }
 
+        if !merged_pages.is_empty() {
+            let merged_path = pdf_config
+                .output_dir
+                .join(format!("{}.md", query_stem(query)));
+            let merged = markdown::merge_pages(&merged_pages);
+            info!(
+                "Merged {} pages into {}",
+                merged_pages.len(),
+                merged_path.display()
+            );
+            match &pdf_config.chunking {
+                Some(chunking) => {
+                    let chunk_paths =
+                        chunk::write_chunks(&merged_path, &merged, Some(query), None, chunking)
+                            .await?;
+                    converted_files.extend(chunk_paths);
+                }
+                None => {
+                    atomic::write(&merged_path, merged).await?;
+                    converted_files.push(merged_path);
+                }
+            }
+        }
+
+        if !briefs.is_empty() {
+            // Results saved before come first in `briefs`
+            briefs.sort_by_key(|(index, _)| *index);
+            let briefs: Vec<String> = briefs.into_iter().map(|(_, brief)| brief).collect();
+            let combined_path = output_path(output_dir, &query_stem(query), "brief.md");
+            info!(
+                "Combined {} briefs into {}",
+                briefs.len(),
+                combined_path.display()
+            );
+            atomic::write(&combined_path, brief::combine(query, &briefs)).await?;
+            converted_files.push(combined_path);
+        }
+
         if converted_files.is_empty() {
-            return Err(anyhow::anyhow!(
-                "No URLs were successfully converted"
-            ));
+            return Err(anyhow::anyhow!("No URLs were successfully converted"));
+        }
+
+        // An index of the directory is kept up to date, with the new results' passages
+        let has_index = [index::INDEX_MARKDOWN, index::INDEX_HTML]
+            .iter()
+            .any(|name| output_dir.join(name).exists());
+        if manifest.is_some() && has_index {
+            if let Err(e) = index::write_index(output_dir).await {
+                warn!("Failed to update the index: {}", e);
+            }
         }
 
         info!(
@@ -184,168 +967,327 @@ impl SearchToPdfClient {
         Ok(converted_files)
     }
 
-    /// Extract URLs from search results
+    /// Convert a single URL to the specified format(s)
     ///
     /// # Arguments
     ///
-    /// * `search_results` - The raw search results string from Brave API
+    /// * `result` - The search result containing URL and metadata
+    /// * `index` - The index of this result (for sequential naming)
+    /// * `config` - Configuration for conversion
+    /// * `pdf` - The outcome of converting the URL to PDF, done beforehand with the other
+    ///   results (see [`Self::convert_to_pdfs`])
+    /// * `merged_pages` - Collects Markdown pages instead of writing them when merging
     ///
     /// # Returns
     ///
-    /// Returns a vector of SearchResult objects containing URLs and metadata
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the search results cannot be parsed
-    fn extract_urls_from_results(&self, search_results: &str) -> Result<Vec<SearchResult>> {
-        // The search results are typically in a human-readable format
-        // We need to extract URLs from the text
-        let mut results = Vec::new();
-
-        // Split by lines and look for URLs
-        let lines: Vec<&str> = search_results.lines().collect();
-        let mut current_title = String::new();
-        let mut current_url = String::new();
-        let mut current_description = String::new();
-
-        for line in lines {
-            let line = line.trim();
-
-            // Skip empty lines and separators
-            if line.is_empty() || line.starts_with("=") || line.starts_with("-") {
-                continue;
-            }
-
-            // Check if this line contains a URL
-            if line.starts_with("http://") || line.starts_with("https://") {
-                current_url = line.to_string();
-            } else if line.starts_with("URL:") {
-                current_url = line.replace("URL:", "").trim().to_string();
-            } else if line.starts_with("Title:") {
-                current_title = line.replace("Title:", "").trim().to_string();
-            } else if line.starts_with("Description:") {
-                current_description = line.replace("Description:", "").trim().to_string();
-            } else if !current_url.is_empty() && current_title.is_empty() {
-                // If we have a URL but no title, this might be the title
-                current_title = line.to_string();
-            } else if !current_url.is_empty()
-                && !current_title.is_empty()
-                && current_description.is_empty()
-            {
-                // If we have URL and title but no description, this might be the description
-                current_description = line.to_string();
-            }
+    /// Returns the paths of the generated files and the formats that failed. Each format
+    /// is attempted even if another one failed.
+    async fn convert_url(
+        &self,
+        result: &SearchResult,
+        stem: &str,
+        config: &SearchToPdfConfig,
+        pdf: Option<Result<PathBuf>>,
+        screenshot: Option<Result<PathBuf>>,
+        merged_pages: &mut Vec<MarkdownPage>,
+    ) -> FormatOutcome {
+        let mut outcome = FormatOutcome::default();
 
-            // If we have all three components, add to results
-            if !current_url.is_empty() && !current_title.is_empty() {
-                results.push(SearchResult {
-                    title: current_title.clone(),
-                    url: current_url.clone(),
-                    description: current_description.clone(),
-                });
-
-                // Reset for next result
-                current_title.clear();
-                current_url.clear();
-                current_description.clear();
-            }
+        if let Some(pdf_path) = pdf {
+            outcome.record(OutputFormat::Pdf, pdf_path.map(|path| vec![path]));
+        }
+        if let Some(screenshot_path) = screenshot {
+            outcome.record(
+                OutputFormat::Screenshot,
+                screenshot_path.map(|path| vec![path]),
+            );
+        }
+        if matches!(
+            config.output_format,
+            OutputFormat::Markdown | OutputFormat::Both
+        ) {
+            let md_paths = if config.merge_markdown {
+                info!("Converting {} to Markdown for merging", result.url);
+                self.markdown_generator
+                    .url_to_page(&result.url)
+                    .await
+                    .map(|page| {
+                        merged_pages.push(page);
+                        Vec::new()
+                    })
+            } else {
+                self.convert_to_markdown(result, stem, config).await
+            };
+            outcome.record(OutputFormat::Markdown, md_paths);
+        }
+        if config.output_format == OutputFormat::Epub {
+            let epub_path = output_path(&config.output_dir, stem, "epub");
+            info!("Converting {} to {}", result.url, epub_path.display());
+            let epub = EpubGenerator::new(self.markdown_generator.clone())
+                .url_to_epub(&result.url, Some(&epub_path))
+                .await;
+            outcome.record(OutputFormat::Epub, epub.map(|_| vec![epub_path]));
         }
 
-        // Alternative approach: use regex to find URLs if the above doesn't work well
-        if results.is_empty() {
-            warn!("No structured results found, attempting regex URL extraction");
-            let url_regex = regex::Regex::new(r"https?://[^\s]+").unwrap();
+        outcome
+    }
 
-            for (index, url_match) in url_regex.find_iter(search_results).enumerate() {
-                let url = url_match.as_str().to_string();
-                results.push(SearchResult {
-                    title: format!("Search Result {}", index + 1),
-                    url,
-                    description: String::new(),
-                });
-            }
-        }
+    /// Save the brief of a single URL: its title, the passages most relevant to the query,
+    /// and its key facts
+    ///
+    /// # Returns
+    ///
+    /// Returns the path of the brief and its Markdown
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be loaded or the brief cannot be written
+    async fn extract_brief(
+        &self,
+        result: &SearchResult,
+        stem: &str,
+        query: &str,
+        config: &SearchToPdfConfig,
+    ) -> Result<(PathBuf, String)> {
+        let brief_path = output_path(&config.output_dir, stem, "brief.md");
+
+        info!(
+            "Extracting a brief of {} to {}",
+            result.url,
+            brief_path.display()
+        );
 
-        info!("Extracted {} URLs from search results", results.len());
-        Ok(results)
+        let page = self.markdown_generator.url_to_page(&result.url).await?;
+        let brief = Brief::from_page(&page, query, config.max_snippets, brief::DEFAULT_MAX_FACTS)
+            .with_description(mdka::from_html(&result.description));
+        let markdown = brief.to_markdown();
+        atomic::write(&brief_path, &markdown).await?;
+        Ok((brief_path, markdown))
     }
 
-    /// Convert a single URL to the specified format(s)
+    /// Save the schema.org events of a result as an iCalendar file
     ///
-    /// # Arguments
+    /// # Returns
     ///
-    /// * `result` - The search result containing URL and metadata
-    /// * `index` - The index of this result (for sequential naming)
-    /// * `config` - Configuration for conversion
+    /// Returns the path of the file, or None if the page describes no events
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be loaded or the file cannot be written
+    async fn save_events(
+        &self,
+        result: &SearchResult,
+        stem: &str,
+        config: &SearchToPdfConfig,
+    ) -> Result<Option<PathBuf>> {
+        let html_content = self.markdown_generator.url_to_html(&result.url).await?;
+        let events = events::extract_events(&html_content, Some(&result.url));
+        if events.is_empty() {
+            return Ok(None);
+        }
+        let ics_path = output_path(&config.output_dir, stem, "ics");
+        info!(
+            "Saving {} event(s) of {} to {}",
+            events.len(),
+            result.url,
+            ics_path.display()
+        );
+        atomic::write(&ics_path, events::to_ics(&events, chrono::Utc::now())).await?;
+        Ok(Some(ics_path))
+    }
+
+    /// Save the organizations and people described on a result as a vCard file
     ///
     /// # Returns
     ///
-    /// Returns a vector of paths to the generated files
+    /// Returns the path of the file, or None if the page gives no contact details
     ///
     /// # Errors
     ///
-    /// Returns an error if conversion fails
-    async fn convert_url(
+    /// Returns an error if the page cannot be loaded or the file cannot be written
+    async fn save_contacts(
         &self,
         result: &SearchResult,
-        index: usize,
+        stem: &str,
         config: &SearchToPdfConfig,
-    ) -> Result<Vec<PathBuf>> {
-        let mut file_paths = Vec::new();
+    ) -> Result<Option<PathBuf>> {
+        let html_content = self.markdown_generator.url_to_html(&result.url).await?;
+        let contacts = vcard::extract_contacts(&html_content, Some(&result.url));
+        if contacts.is_empty() {
+            return Ok(None);
+        }
+        let vcf_path = output_path(&config.output_dir, stem, "vcf");
+        info!(
+            "Saving {} contact(s) of {} to {}",
+            contacts.len(),
+            result.url,
+            vcf_path.display()
+        );
+        atomic::write(&vcf_path, vcard::to_vcf(&contacts)).await?;
+        Ok(Some(vcf_path))
+    }
 
-        match config.output_format {
-            OutputFormat::Pdf => {
-                let pdf_path = self.convert_to_pdf(result, index, config).await?;
-                file_paths.push(pdf_path);
+    /// Save each place, up to `config.max_results`, as a card in the requested formats
+    /// if place cards are enabled, and as a vCard if `config.contacts` is set
+    ///
+    /// Markdown cards show their map from an SVG file next to them, and PDF cards have
+    /// it inline. Places that cannot be saved are skipped with a warning.
+    ///
+    /// # Returns
+    ///
+    /// Returns the paths of the saved files
+    async fn save_places(
+        &self,
+        places: &[LocalSearchResult],
+        config: &SearchToPdfConfig,
+    ) -> Vec<PathBuf> {
+        let offline = self.search_client.is_offline();
+        let mut files = Vec::new();
+        let mut used_stems = HashSet::new();
+        for (index, place) in places.iter().take(config.max_results).enumerate() {
+            let base = match sanitize_filename(&place.title) {
+                title if title.is_empty() => format!("place_{}", index + 1),
+                title => title,
+            };
+            let mut stem = format!("{}.card", base);
+            let mut suffix = 1;
+            while !used_stems.insert(stem.clone()) {
+                suffix += 1;
+                stem = format!("{}_{}.card", base, suffix);
             }
-            OutputFormat::Markdown => {
-                let md_path = self.convert_to_markdown(result, index, config).await?;
-                file_paths.push(md_path);
+
+            if let Some(maps) = &self.place_maps {
+                info!("Saving the card of {}", place.title);
+                let card = maps.card(place, offline).await;
+                match self.write_place_card(&card, &stem, config).await {
+                    Ok(paths) => files.extend(paths),
+                    Err(e) => warn!("Failed to save the card of {}: {}", place.title, e),
+                }
             }
-            OutputFormat::Both => {
-                let pdf_path = self.convert_to_pdf(result, index, config).await?;
-                file_paths.push(pdf_path);
-                let md_path = self.convert_to_markdown(result, index, config).await?;
-                file_paths.push(md_path);
+            // Named like the cards, so that they cannot collide with the vCards of results
+            if config.contacts {
+                let vcf_path = output_path(&config.output_dir, &stem, "vcf");
+                let vcard = Contact::from_place(place).to_vcard();
+                match atomic::write(&vcf_path, vcard).await {
+                    Ok(()) => files.push(vcf_path),
+                    Err(e) => warn!("Failed to save the contact of {}: {}", place.title, e),
+                }
             }
         }
-
-        Ok(file_paths)
+        files
     }
 
-    /// Convert a single URL to PDF
+    /// Write a place card as Markdown with its map next to it, and/or as PDF
     ///
-    /// # Arguments
+    /// # Errors
     ///
-    /// * `result` - The search result containing URL and metadata
-    /// * `index` - The index of this result (for sequential naming)
-    /// * `config` - Configuration for PDF conversion
+    /// Returns an error if a file cannot be written or the PDF cannot be generated
+    async fn write_place_card(
+        &self,
+        card: &PlaceCard,
+        stem: &str,
+        config: &SearchToPdfConfig,
+    ) -> Result<Vec<PathBuf>> {
+        let mut files = Vec::new();
+        let markdown = config.extract_only
+            || matches!(
+                config.output_format,
+                OutputFormat::Markdown | OutputFormat::Both
+            );
+        if markdown {
+            let map_name = format!("{}.map.svg", stem);
+            if let Some(map) = &card.map {
+                let map_path = config.output_dir.join(&map_name);
+                atomic::write(&map_path, map).await?;
+                files.push(map_path);
+            }
+            let md_path = output_path(&config.output_dir, stem, "md");
+            atomic::write(&md_path, card.to_markdown(Some(&map_name))).await?;
+            files.push(md_path);
+        }
+        if !config.extract_only
+            && matches!(config.output_format, OutputFormat::Pdf | OutputFormat::Both)
+        {
+            let pdf_path = output_path(&config.output_dir, stem, "pdf");
+            self.pdf_generator
+                .html_to_pdf(&card.to_html(), Some(&pdf_path))
+                .await?;
+            files.push(pdf_path);
+        }
+        if !config.extract_only && config.output_format == OutputFormat::Epub {
+            let epub_path = output_path(&config.output_dir, stem, "epub");
+            EpubGenerator::new(self.markdown_generator.clone())
+                .html_to_epub(&card.to_html(), None, Some(&epub_path))
+                .await?;
+            files.push(epub_path);
+        }
+        if let Some(format) = config.screenshot() {
+            let image_path = output_path(&config.output_dir, stem, format.extension());
+            self.pdf_generator
+                .html_to_screenshot(&card.to_html(), format, Some(&image_path))
+                .await?;
+            files.push(image_path);
+        }
+        Ok(files)
+    }
+
+    /// Convert results to PDF, up to `config.concurrency` at a time
     ///
     /// # Returns
     ///
-    /// Returns the path to the generated PDF file
+    /// Returns the path of each result's PDF, or the error converting it, with the time
+    /// the conversion took, in the order of `pending`; nothing unless PDFs are requested
+    async fn convert_to_pdfs(
+        &self,
+        pending: &[(usize, SearchResult, String)],
+        config: &SearchToPdfConfig,
+    ) -> Vec<(Result<PathBuf>, Duration)> {
+        if config.extract_only
+            || !matches!(config.output_format, OutputFormat::Pdf | OutputFormat::Both)
+        {
+            return Vec::new();
+        }
+        let jobs = pending
+            .iter()
+            .map(|(_, result, stem)| {
+                (
+                    result.url.clone(),
+                    output_path(&config.output_dir, stem, "pdf"),
+                )
+            })
+            .collect();
+        PdfGeneratorPool::new(self.pdf_generator.clone(), config.concurrency)
+            .urls_to_pdf(jobs)
+            .await
+    }
+
+    /// Capture full-page screenshots of results, up to `config.concurrency` at a time
     ///
-    /// # Errors
+    /// # Returns
     ///
-    /// Returns an error if PDF conversion fails
-    async fn convert_to_pdf(
+    /// Returns the path of each result's screenshot, or the error capturing it, with the
+    /// time the capture took, in the order of `pending`; nothing unless screenshots are
+    /// requested
+    async fn capture_screenshots(
         &self,
-        result: &SearchResult,
-        index: usize,
+        pending: &[(usize, SearchResult, String)],
         config: &SearchToPdfConfig,
-    ) -> Result<PathBuf> {
-        // Generate filename based on naming strategy
-        let filename = self.generate_filename(result, index, config, "pdf")?;
-        let pdf_path = config.output_dir.join(filename);
-
-        info!("Converting {} to {}", result.url, pdf_path.display());
-
-        // Convert URL to PDF
-        self.pdf_generator
-            .url_to_pdf(&result.url, Some(&pdf_path))
-            .await?;
-
-        Ok(pdf_path)
+    ) -> Vec<(Result<PathBuf>, Duration)> {
+        let Some(format) = config.screenshot() else {
+            return Vec::new();
+        };
+        let jobs = pending
+            .iter()
+            .map(|(_, result, stem)| {
+                (
+                    result.url.clone(),
+                    output_path(&config.output_dir, stem, format.extension()),
+                )
+            })
+            .collect();
+        PdfGeneratorPool::new(self.pdf_generator.clone(), config.concurrency)
+            .urls_to_screenshots(jobs, format)
+            .await
     }
 
     /// Convert a single URL to Markdown
@@ -353,12 +1295,12 @@ impl SearchToPdfClient {
     /// # Arguments
     ///
     /// * `result` - The search result containing URL and metadata
-    /// * `index` - The index of this result (for sequential naming)
+    /// * `stem` - The output filename without extension
     /// * `config` - Configuration for Markdown conversion
     ///
     /// # Returns
     ///
-    /// Returns the path to the generated Markdown file
+    /// Returns the path to the generated Markdown file, or the chunk files when chunking
     ///
     /// # Errors
     ///
@@ -366,54 +1308,88 @@ impl SearchToPdfClient {
     async fn convert_to_markdown(
         &self,
         result: &SearchResult,
-        index: usize,
+        stem: &str,
         config: &SearchToPdfConfig,
-    ) -> Result<PathBuf> {
-        // Generate filename based on naming strategy
-        let filename = self.generate_filename(result, index, config, "md")?;
-        let md_path = config.output_dir.join(filename);
+    ) -> Result<Vec<PathBuf>> {
+        let md_path = output_path(&config.output_dir, stem, "md");
 
         info!("Converting {} to {}", result.url, md_path.display());
 
         // Convert URL to Markdown
+        if let Some(chunking) = &config.chunking {
+            let page = self.markdown_generator.url_to_page(&result.url).await?;
+            return chunk::write_chunks(
+                &md_path,
+                &page.to_document(),
+                Some(&page.title),
+                page.url.as_deref(),
+                chunking,
+            )
+            .await;
+        }
+
         self.markdown_generator
             .url_to_markdown(&result.url, Some(&md_path))
             .await?;
 
-        Ok(md_path)
+        Ok(vec![md_path])
     }
 
-    /// Generate a filename based on the naming strategy
+    /// The files written for a result with the given filename stem, used to detect
+    /// results that were already saved
+    fn output_files(&self, stem: &str, config: &SearchToPdfConfig) -> Vec<PathBuf> {
+        if config.extract_only {
+            return vec![output_path(&config.output_dir, stem, "brief.md")];
+        }
+        let mut files = Vec::new();
+        if matches!(config.output_format, OutputFormat::Pdf | OutputFormat::Both) {
+            files.push(output_path(&config.output_dir, stem, "pdf"));
+        }
+        if matches!(
+            config.output_format,
+            OutputFormat::Markdown | OutputFormat::Both
+        ) {
+            let md_path = output_path(&config.output_dir, stem, "md");
+            files.push(match config.chunking {
+                Some(_) => chunk::chunk_path(&md_path, 1),
+                None => md_path,
+            });
+        }
+        if config.output_format == OutputFormat::Epub {
+            files.push(output_path(&config.output_dir, stem, "epub"));
+        }
+        if let Some(format) = config.screenshot() {
+            files.push(output_path(&config.output_dir, stem, format.extension()));
+        }
+        files
+    }
+
+    /// Generate a filename stem (without extension) based on the naming strategy
     ///
     /// # Arguments
     ///
     /// * `result` - The search result containing URL and metadata
     /// * `index` - The index of this result (for sequential naming)
     /// * `config` - Configuration containing the naming strategy
-    /// * `extension` - File extension (e.g., "pdf", "md")
     ///
     /// # Returns
     ///
-    /// Returns a sanitized filename
+    /// Returns a sanitized filename stem
     ///
     /// # Errors
     ///
     /// Returns an error if filename generation fails
-    fn generate_filename(
+    fn generate_stem(
         &self,
         result: &SearchResult,
         index: usize,
         config: &SearchToPdfConfig,
-        extension: &str,
     ) -> Result<String> {
         let filename = match config.naming_strategy {
-            NamingStrategy::Title => {
-                if result.title.is_empty() {
-                    format!("search_result_{}", index + 1)
-                } else {
-                    sanitize_filename(&result.title)
-                }
-            }
+            NamingStrategy::Title => match sanitize_filename(&result.title) {
+                title if title.is_empty() => format!("search_result_{}", index + 1),
+                title => title,
+            },
             NamingStrategy::Domain => {
                 let url = url::Url::parse(&result.url)?;
                 let domain = url.host_str().unwrap_or("unknown");
@@ -425,31 +1401,274 @@ impl SearchToPdfClient {
             NamingStrategy::TitleDomain => {
                 let url = url::Url::parse(&result.url)?;
                 let domain = url.host_str().unwrap_or("unknown");
-                let title = if result.title.is_empty() {
-                    format!("result_{}", index + 1)
-                } else {
-                    sanitize_filename(&result.title)
+                let title = match sanitize_filename(&result.title) {
+                    title if title.is_empty() => format!("result_{}", index + 1),
+                    title => title,
                 };
                 format!("{}_{}", title, sanitize_filename(domain))
             }
         };
 
-        Ok(format!("{}.{}", filename, extension))
+        ensure_contained(&config.output_dir, &self.output_files(&filename, config))?;
+        Ok(filename)
+    }
+}
+
+/// Extract URLs from search results
+///
+/// # Arguments
+///
+/// * `search_results` - The raw search results string from Brave API
+///
+/// # Returns
+///
+/// Returns a vector of SearchResult objects containing URLs and metadata
+///
+/// # Errors
+///
+/// Returns an error if the search results cannot be parsed
+pub(crate) fn extract_urls_from_results(search_results: &str) -> Result<Vec<SearchResult>> {
+    // The search results are typically in a human-readable format
+    // We need to extract URLs from the text
+    let mut results = Vec::new();
+
+    // Split by lines and look for URLs
+    let lines: Vec<&str> = search_results.lines().collect();
+    let mut current_title = String::new();
+    let mut current_url = String::new();
+    let mut current_description = String::new();
+
+    for line in lines {
+        let line = line.trim();
+
+        // Skip empty lines and separators
+        if line.is_empty() || line.starts_with("=") || line.starts_with("-") {
+            continue;
+        }
+
+        // Check if this line contains a URL
+        let url_line = if line.starts_with("http://") || line.starts_with("https://") {
+            Some(line)
+        } else {
+            line.strip_prefix("URL:")
+        };
+        if let Some(candidate) = url_line {
+            match clean_url(candidate) {
+                Some(url) => current_url = url,
+                None => {
+                    // The title of a result without an address must not go to the next one
+                    warn!(
+                        "Skipping invalid URL in search results: {}",
+                        candidate.trim()
+                    );
+                    current_title.clear();
+                    current_description.clear();
+                }
+            }
+        } else if line.starts_with("Title:") {
+            current_title = line.replace("Title:", "").trim().to_string();
+        } else if line.starts_with("Description:") {
+            current_description = line.replace("Description:", "").trim().to_string();
+        } else if !current_url.is_empty() && current_title.is_empty() {
+            // If we have a URL but no title, this might be the title
+            current_title = line.to_string();
+        } else if !current_url.is_empty()
+            && !current_title.is_empty()
+            && current_description.is_empty()
+        {
+            // If we have URL and title but no description, this might be the description
+            current_description = line.to_string();
+        }
+
+        // If we have all three components, add to results
+        if !current_url.is_empty() && !current_title.is_empty() {
+            results.push(SearchResult {
+                title: current_title.clone(),
+                url: current_url.clone(),
+                description: current_description.clone(),
+            });
+
+            // Reset for next result
+            current_title.clear();
+            current_url.clear();
+            current_description.clear();
+        }
+    }
+
+    // Alternative approach: use regex to find URLs if the above doesn't work well
+    if results.is_empty() {
+        warn!("No structured results found, attempting regex URL extraction");
+        // Quotes, angle brackets, and square brackets end a URL in text and Markdown
+        let url_regex = regex::Regex::new(r#"https?://[^\s<>"`\[\]]+"#).unwrap();
+
+        let mut seen = HashSet::new();
+        let urls = url_regex
+            .find_iter(search_results)
+            .filter_map(|url_match| clean_url(url_match.as_str()))
+            .filter(|url| seen.insert(url.clone()));
+        for (index, url) in urls.enumerate() {
+            results.push(SearchResult {
+                title: format!("Search Result {}", index + 1),
+                url,
+                description: String::new(),
+            });
+        }
+    }
+
+    info!("Extracted {} URLs from search results", results.len());
+    Ok(results)
+}
+
+/// A URL found in text without the punctuation and Markdown around it, or None unless
+/// what is left is an HTTP(S) address with a host
+///
+/// Trailing sentence punctuation, quotes, and emphasis are removed, as are closing
+/// brackets the URL does not open itself, so `(see https://example.com/a_(b)).` keeps
+/// the parentheses of the path but not the one closing the sentence.
+fn clean_url(candidate: &str) -> Option<String> {
+    let candidate = candidate.split_whitespace().next()?;
+    let mut url = candidate.trim_start_matches(['<', '(', '[', '"', '\'', '*', '_', '`']);
+    loop {
+        let trimmed =
+            url.trim_end_matches(['.', ',', ';', ':', '!', '?', '"', '\'', '>', '*', '_', '`']);
+        let trimmed = match trimmed.chars().next_back() {
+            Some(close @ (')' | ']' | '}')) => {
+                let open = match close {
+                    ')' => '(',
+                    ']' => '[',
+                    _ => '{',
+                };
+                match trimmed.matches(open).count() < trimmed.matches(close).count() {
+                    true => &trimmed[..trimmed.len() - 1],
+                    false => trimmed,
+                }
+            }
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            break;
+        }
+        url = trimmed;
+    }
+
+    let parsed = url::Url::parse(url).ok()?;
+    let valid = matches!(parsed.scheme(), "http" | "https")
+        && parsed.host_str().is_some_and(|host| !host.is_empty());
+    valid.then(|| url.to_string())
+}
+
+/// The path of an output file in a directory, keeping any dots in the stem
+fn output_path(dir: &Path, stem: &str, extension: &str) -> PathBuf {
+    dir.join(format!("{}.{}", stem, extension))
+}
+
+/// The filename stem of the documents combining the results of a query, or `search` if
+/// nothing of the query is left to name them after
+fn query_stem(query: &str) -> String {
+    match sanitize_filename(query) {
+        stem if stem.is_empty() => "search".to_string(),
+        stem => stem,
     }
 }
 
+/// Names Windows reserves for devices, whatever the extension
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
 /// Sanitize a filename by removing invalid characters
-fn sanitize_filename(filename: &str) -> String {
-    filename
-        .chars()
-        .map(|c| match c {
-            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
-            c if c.is_control() => '_',
-            c => c,
+///
+/// The result is always a single path component. The path components of the name are
+/// joined with `_`, leaving out `.`, `..`, and other dot-only components, and leading
+/// and trailing dots are removed so that the name is neither hidden nor changed by
+/// Windows. Names of Windows devices such as `CON` get a leading `_`. The result is
+/// empty if nothing of the name is left.
+pub(crate) fn sanitize_filename(filename: &str) -> String {
+    let sanitized = filename
+        .split(['/', '\\'])
+        .filter(|component| !component.trim().chars().all(|c| c == '.'))
+        .map(|component| {
+            component
+                .chars()
+                .map(|c| match c {
+                    ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+                    c if c.is_control() => '_',
+                    c => c,
+                })
+                .collect::<String>()
         })
-        .collect::<String>()
-        .trim()
-        .to_string()
+        .collect::<Vec<_>>()
+        .join("_");
+    let sanitized = sanitized.trim_matches(|c: char| c == '.' || c.is_whitespace());
+
+    let device = sanitized.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(device))
+    {
+        return format!("_{}", sanitized);
+    }
+    sanitized.to_string()
+}
+
+/// Fail unless every path names a file directly in the output directory
+///
+/// The directory and the parent of each path are compared after canonicalization, and
+/// files that already exist are canonicalized too, so that a symlink left in the
+/// directory cannot redirect a write outside of it.
+///
+/// # Errors
+///
+/// Returns an error if a path lies outside the directory or the directory cannot be read
+fn ensure_contained(dir: &Path, paths: &[PathBuf]) -> Result<()> {
+    let dir = dir.canonicalize()?;
+    for path in paths {
+        let contained = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(_)) => {
+                parent.canonicalize()? == dir
+                    && match path.canonicalize() {
+                        // An existing file, or the target of a symlink
+                        Ok(resolved) => resolved.parent() == Some(dir.as_path()),
+                        Err(_) => true,
+                    }
+            }
+            _ => false,
+        };
+        if !contained {
+            anyhow::bail!(
+                "Refusing to write {} outside of the output directory {}",
+                path.display(),
+                dir.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Build a file stem for a URL from its host and path segments
+///
+/// `https://example.com/blog/post` becomes `example.com_blog_post`. The stem is
+/// sanitized so it can be used directly as a filename.
+///
+/// # Errors
+///
+/// Returns an error if the URL cannot be parsed
+pub fn filename_from_url(url: &str) -> Result<String> {
+    let parsed = url::Url::parse(url)?;
+    let host = parsed.host_str().unwrap_or("unknown");
+    let segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+
+    let stem = if segments.is_empty() {
+        host.to_string()
+    } else {
+        format!("{}_{}", host, segments.join("_"))
+    };
+
+    Ok(sanitize_filename(&stem))
 }
 
 #[cfg(test)]
@@ -462,6 +1681,98 @@ mod tests {
         assert_eq!(sanitize_filename("test/file.txt"), "test_file.txt");
         assert_eq!(sanitize_filename("test:file*.txt"), "test_file_.txt");
         assert_eq!(sanitize_filename("test<file>?.txt"), "test_file__.txt");
+        assert_eq!(sanitize_filename("../../etc/passwd"), "etc_passwd");
+        assert_eq!(
+            sanitize_filename("..\\..\\Windows\\win.ini"),
+            "Windows_win.ini"
+        );
+        assert_eq!(sanitize_filename("/etc/./passwd"), "etc_passwd");
+        assert_eq!(sanitize_filename("C:\\boot.ini"), "C__boot.ini");
+        assert_eq!(sanitize_filename(" ..hidden. "), "hidden");
+        assert_eq!(sanitize_filename("con.txt"), "_con.txt");
+        assert_eq!(sanitize_filename("Console"), "Console");
+        assert_eq!(sanitize_filename(".."), "");
+    }
+
+    #[test]
+    fn test_query_stem() {
+        assert_eq!(query_stem("rust async"), "rust async");
+        assert_eq!(query_stem("a/b"), "a_b");
+        assert_eq!(query_stem(".."), "search");
+        assert_eq!(query_stem(" / "), "search");
+    }
+
+    #[test]
+    fn test_ensure_contained() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let out = dir.path().join("out");
+        std::fs::create_dir(&out)?;
+
+        ensure_contained(&out, &[out.join("page.md"), out.join("...pdf")])?;
+        assert!(ensure_contained(&out, &[out.join("../page.md")]).is_err());
+        assert!(ensure_contained(&out, &[out.join("..")]).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.path().join("outside.md"), out.join("link.md"))?;
+            std::fs::write(dir.path().join("outside.md"), "")?;
+            assert!(ensure_contained(&out, &[out.join("link.md")]).is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_urls_from_results() -> Result<()> {
+        let text = "Top results: [Rust](https://www.rust-lang.org/),\n\
+                    see \"https://doc.rust-lang.org/book/\".\n\
+                    Also (https://en.wikipedia.org/wiki/Rust_(programming_language)),\n\
+                    **https://crates.io**, <https://www.rust-lang.org/> and https://.,";
+        let urls: Vec<String> = extract_urls_from_results(text)?
+            .into_iter()
+            .map(|result| result.url)
+            .collect();
+        assert_eq!(
+            urls,
+            [
+                "https://www.rust-lang.org/",
+                "https://doc.rust-lang.org/book/",
+                "https://en.wikipedia.org/wiki/Rust_(programming_language)",
+                "https://crates.io",
+            ]
+        );
+
+        let text = "Title: Rust\nURL: <https://www.rust-lang.org/>.\n\n\
+                    Title: Broken\nURL: not a url\n\n\
+                    https://crates.io/,\nCrates\n";
+        let results: Vec<(String, String)> = extract_urls_from_results(text)?
+            .into_iter()
+            .map(|result| (result.title, result.url))
+            .collect();
+        assert_eq!(
+            results,
+            [
+                ("Rust".to_string(), "https://www.rust-lang.org/".to_string()),
+                ("Crates".to_string(), "https://crates.io/".to_string()),
+            ]
+        );
+        assert_eq!(
+            clean_url("https://example.com:8080/a?b=c;"),
+            Some("https://example.com:8080/a?b=c".to_string())
+        );
+        assert_eq!(clean_url("ftp://example.com/file"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filename_from_url() -> Result<()> {
+        assert_eq!(filename_from_url("https://example.com")?, "example.com");
+        assert_eq!(filename_from_url("https://example.com/")?, "example.com");
+        assert_eq!(
+            filename_from_url("https://example.com/blog/post/")?,
+            "example.com_blog_post"
+        );
+        assert!(filename_from_url("not a url").is_err());
+        Ok(())
     }
 
     #[test]
@@ -472,6 +1783,34 @@ mod tests {
         assert!(config.include_metadata);
         assert_eq!(config.naming_strategy, NamingStrategy::TitleDomain);
         assert_eq!(config.output_format, OutputFormat::Pdf);
+        assert_eq!(config.screenshot(), None);
+
+        let config = SearchToPdfConfig {
+            output_format: OutputFormat::Screenshot,
+            screenshot_format: ScreenshotFormat::Webp,
+            ..Default::default()
+        };
+        assert_eq!(config.screenshot(), Some(ScreenshotFormat::Webp));
+        let config = SearchToPdfConfig {
+            screenshots: true,
+            extract_only: true,
+            ..Default::default()
+        };
+        assert_eq!(config.screenshot(), None);
+    }
+
+    #[test]
+    fn test_format_outcome() {
+        let mut outcome = FormatOutcome::default();
+        outcome.record(OutputFormat::Pdf, Err(anyhow::anyhow!("Navigation failed")));
+        assert!(!outcome.is_partial());
+        assert_eq!(outcome.failure_summary(), "PDF failed");
+
+        outcome.record(OutputFormat::Markdown, Ok(vec![PathBuf::from("page.md")]));
+        assert!(outcome.is_partial());
+        assert!(!outcome.is_complete());
+        assert_eq!(outcome.files, vec![PathBuf::from("page.md")]);
+        assert_eq!(outcome.failure_summary(), "PDF failed (Markdown saved)");
     }
 
     #[test]
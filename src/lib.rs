@@ -16,10 +16,19 @@
 //!
 //! ```bash
 //! # Convert a URL to PDF
-//! webpage-save https://example.com -o output.pdf
+//! webpage-save convert https://example.com -o output.pdf
 //!
 //! # Convert a URL to Markdown
-//! webpage-save https://example.com -f markdown -o output.md
+//! webpage-save convert https://example.com -f markdown -o output.md
+//!
+//! # The `convert` subcommand may be omitted for a single URL
+//! webpage-save https://example.com
+//!
+//! # Re-render saved HTML/MHTML snapshots
+//! webpage-save render ./archive -f markdown -o ./markdown
+//!
+//! # Store the Brave API key in the OS keyring
+//! webpage-save auth set-key
 //!
 //! # Perform a Brave search
 //! webpage-save search web "rust programming"
@@ -43,3 +52,218 @@ pub mod search;
 
 /// Integration utilities for combining search and PDF conversion functionality
 pub mod integration;
+
+/// API key storage in the operating system keyring
+pub mod auth;
+
+/// On-disk caching of search responses and page snapshots
+pub mod cache;
+
+/// Utilities for re-rendering saved HTML snapshots
+pub mod render;
+
+/// Cover pages and page headers for branded PDF output
+pub mod branding;
+
+/// Splitting Markdown into overlapping chunks for LLM context windows
+pub mod chunk;
+
+/// Text embedding generation for converted Markdown
+pub mod embedding;
+
+/// Single-file SQLite archive of captured pages
+pub mod archive;
+
+/// Portable bundles for moving capture archives between machines
+pub mod bundle;
+
+/// Record of the URLs converted into an output directory
+pub mod manifest;
+
+/// Crash-safe output file writes
+pub mod atomic;
+
+/// Disk space checks for batch conversions
+pub mod space;
+
+/// Versioned captures with a stable `latest` pointer
+pub mod versions;
+
+/// Per-domain statistics for batch runs
+pub mod stats;
+
+/// Adaptive per-domain page load timeouts
+pub mod timeouts;
+
+/// Page readiness strategies: a delay, a selector, or network idle
+pub mod wait;
+
+/// Main-content extraction settings and simple CSS selectors
+pub mod extract;
+
+/// Front matter of Markdown output for note-taking apps and static site generators
+pub mod frontmatter;
+
+/// Comment thread extraction for Markdown appendices
+pub mod comments;
+
+/// Pagination detection for multi-page articles
+pub mod pagination;
+
+/// Per-domain site profiles
+pub mod profiles;
+
+/// Print-view URL preference
+pub mod printview;
+
+/// Reader-mode PDFs rendered from the extracted main content
+pub mod reader;
+
+/// Fonts for reader-mode PDFs
+pub mod fonts;
+
+/// Page-size presets for PDFs, including e-reader screens
+pub mod paper;
+
+/// Delivery of PDFs to e-readers
+pub mod delivery;
+
+/// Read-it-later service integration (Readwise Reader, Instapaper, Pocket exports)
+pub mod readlater;
+
+/// Bookmark service integration (Pinboard, Raindrop.io)
+pub mod bookmarks;
+
+/// Hacker News and Reddit thread archiving
+pub mod threads;
+
+/// Twitter/X and Mastodon thread capture
+pub mod social;
+
+/// Newsletter archive listings (Substack, Ghost, Buttondown)
+pub mod newsletter;
+
+/// Documentation site crawling (mdBook, Docusaurus, Sphinx, rustdoc)
+pub mod crawl;
+
+/// Wikipedia article export through the REST API
+pub mod wikipedia;
+
+/// Persistent browser profiles for capturing pages behind a login
+pub mod session;
+
+/// Cookie import from locally installed browsers (Chrome, Firefox)
+pub mod cookies;
+
+/// Extra HTTP headers sent with page requests
+pub mod request;
+
+/// Proxies for the requests of conversions and searches
+pub mod proxy;
+
+/// Custom DNS resolvers and DNS-over-HTTPS for fetches
+pub mod resolver;
+
+/// Recording and replay of the HTTP responses of a conversion
+pub mod recording;
+
+/// Deterministic rendering for reproducible PDF captures
+pub mod deterministic;
+
+/// Still frames of animations and videos before printing
+pub mod motion;
+
+/// Viewport sizes pages are rendered at
+pub mod viewport;
+
+/// Syntax highlighting of code blocks in reader-mode PDFs
+pub mod highlight;
+
+/// Redaction of sensitive text in saved pages
+pub mod redact;
+
+/// Detection of personal data in captured pages
+pub mod pii;
+
+/// Domain blocklists screening URLs before conversion
+pub mod blocklist;
+
+/// Security posture of captured sites
+pub mod security;
+
+/// Virus scanning of saved files through an external command
+pub mod virusscan;
+
+/// Alt text of images in Markdown output
+pub mod alttext;
+
+/// Local copies of the images of Markdown output
+pub mod assets;
+
+/// Downloads of files linked from captured pages
+pub mod attachments;
+
+/// Concurrent conversion of URL lists
+pub mod batch;
+
+/// Compact Markdown briefs of search results
+pub mod brief;
+
+/// Monitoring of changelog and release notes pages for new versions
+pub mod changelog;
+
+/// Visual diffs between two captures of a page
+pub mod diff;
+
+/// DNS resolution snapshots of captured hosts
+pub mod dns;
+
+/// EPUB books of articles for e-readers
+pub mod epub;
+
+/// Calendar export of schema.org events on captured pages
+pub mod events;
+
+/// Evidence captures with a chain-of-custody report
+pub mod evidence;
+
+/// Language of command-line messages and generated dates
+pub mod i18n;
+
+/// Format and size policy for downloaded images
+pub mod images;
+
+/// Browsable indexes of output directories
+pub mod index;
+
+/// Place cards of local search results, with geocoding and map snapshots
+pub mod places;
+
+/// Product prices read from the structured data of shop pages
+pub mod products;
+
+/// Recipes read from the structured data of recipe pages
+pub mod recipe;
+
+/// Static websites published from output directories
+pub mod site;
+
+/// Passages of saved pages relevant to a search query
+pub mod snippets;
+
+/// RFC 3161 trusted timestamping of captures
+pub mod timestamp;
+
+/// Machine translation of Markdown output
+pub mod translate;
+
+/// vCard export of local businesses and contact pages
+pub mod vcard;
+
+/// WARC files of the HTTP exchanges of captures
+pub mod warc;
+
+/// Entry points of private parsers for the fuzz targets in `fuzz/`
+#[cfg(feature = "fuzzing")]
+#[doc(hidden)]
+pub mod fuzz;
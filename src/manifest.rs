@@ -0,0 +1,510 @@
+//! Record of the URLs converted into an output directory
+//!
+//! Each output directory keeps a small JSON manifest mapping source URLs to the files
+//! written for them. Re-running a conversion into the same directory consults it to
+//! skip URLs that were already saved, and to avoid reusing a filename that belongs to
+//! a different URL. Product pages also keep the history of their price here, one
+//! point per capture in which the price or availability changed, and changelog pages
+//! the versions seen on them.
+
+use crate::atomic;
+use crate::dns::DnsSnapshot;
+use crate::products::PricePoint;
+use crate::security::SecurityPosture;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+/// Most stems tried for a URL, `base` and `base_2` up to `base_<MAX>`, before giving up
+pub const MAX_STEM_CANDIDATES: usize = 1000;
+
+/// Name of the manifest file kept in each output directory
+pub const MANIFEST_FILE: &str = ".webpage-save.json";
+
+/// The files saved for one URL
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The source URL
+    pub url: String,
+    /// Output files, relative to the output directory
+    pub files: Vec<String>,
+    /// When the files were last written
+    pub saved_at: DateTime<Utc>,
+    /// Tags the URL was saved with, e.g. from a bookmark service
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Passages of the page most relevant to the search query it was found with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<String>,
+    /// The product price at each capture where it changed, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub prices: Vec<PricePoint>,
+    /// The versions seen on a changelog page
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<String>,
+    /// The security posture of the page's origin at the last capture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<SecurityPosture>,
+    /// How the page's host resolved at the last capture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dns: Option<DnsSnapshot>,
+}
+
+/// Where the output for a URL goes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputSlot {
+    /// The URL was already saved to these files
+    Saved(Vec<PathBuf>),
+    /// Write the output using this filename stem
+    Free(String),
+}
+
+/// The manifest of an output directory
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OutputManifest {
+    /// Saved URLs, in the order they were first saved
+    #[serde(default)]
+    pub entries: Vec<ManifestEntry>,
+}
+
+impl OutputManifest {
+    /// Load the manifest of a directory, or an empty manifest if it has none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest exists but cannot be read or parsed
+    pub async fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(MANIFEST_FILE);
+        match fs::read(&path).await {
+            Ok(data) => serde_json::from_slice(&data)
+                .map_err(|e| anyhow::anyhow!("Invalid manifest {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the manifest into a directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the manifest cannot be written
+    pub async fn save(&self, dir: &Path) -> Result<()> {
+        atomic::write(&dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// The files previously saved for a URL, if they all still exist
+    pub fn saved_files(&self, dir: &Path, url: &str) -> Option<Vec<PathBuf>> {
+        let entry = self.entries.iter().find(|entry| entry.url == url)?;
+        let files: Vec<PathBuf> = entry.files.iter().map(|file| dir.join(file)).collect();
+        (!files.is_empty() && files.iter().all(|file| file.exists())).then_some(files)
+    }
+
+    /// The URL a file in the directory was saved for, if recorded
+    pub fn owner(&self, dir: &Path, path: &Path) -> Option<&str> {
+        let file = relative_name(dir, path);
+        self.entries
+            .iter()
+            .find(|entry| entry.files.contains(&file))
+            .map(|entry| entry.url.as_str())
+    }
+
+    /// Record the files saved for a URL, replacing any earlier record but its tags, price
+    /// history, and changelog versions
+    pub fn record(&mut self, dir: &Path, url: &str, files: &[PathBuf]) {
+        let files: Vec<String> = files.iter().map(|file| relative_name(dir, file)).collect();
+        let (tags, prices, versions) = self
+            .entries
+            .iter()
+            .find(|entry| entry.url == url)
+            .map(|entry| {
+                (
+                    entry.tags.clone(),
+                    entry.prices.clone(),
+                    entry.versions.clone(),
+                )
+            })
+            .unwrap_or_default();
+        for entry in &mut self.entries {
+            entry.files.retain(|file| !files.contains(file));
+        }
+        self.entries
+            .retain(|entry| entry.url != url && !entry.files.is_empty());
+        self.entries.push(ManifestEntry {
+            url: url.to_string(),
+            files,
+            saved_at: Utc::now(),
+            tags,
+            snippets: Vec::new(),
+            prices,
+            versions,
+            security: None,
+            dns: None,
+        });
+    }
+
+    /// The versions seen on a changelog page at earlier checks
+    pub fn versions(&self, url: &str) -> &[String] {
+        self.entries
+            .iter()
+            .find(|entry| entry.url == url)
+            .map_or(&[], |entry| entry.versions.as_slice())
+    }
+
+    /// Add versions seen on a saved changelog page, ignoring those already recorded
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the URL is recorded
+    pub fn add_versions(&mut self, url: &str, versions: &[String]) -> bool {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.url == url) else {
+            return false;
+        };
+        for version in versions {
+            if !entry.versions.contains(version) {
+                entry.versions.push(version.clone());
+            }
+        }
+        true
+    }
+
+    /// Add the price of a saved URL at a capture to its history, unless the price,
+    /// currency, and availability are the same as at the last capture
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the price was added
+    pub fn record_price(&mut self, url: &str, point: PricePoint) -> bool {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.url == url) else {
+            return false;
+        };
+        if entry
+            .prices
+            .last()
+            .is_some_and(|last| last.same_offer(&point))
+        {
+            return false;
+        }
+        entry.prices.push(point);
+        true
+    }
+
+    /// Set the security posture of a saved URL at its last capture
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the URL is recorded
+    pub fn set_security(&mut self, url: &str, posture: SecurityPosture) -> bool {
+        match self.entries.iter_mut().find(|entry| entry.url == url) {
+            Some(entry) => {
+                entry.security = Some(posture);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set how the host of a saved URL resolved at its last capture
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the URL is recorded
+    pub fn set_dns(&mut self, url: &str, snapshot: DnsSnapshot) -> bool {
+        match self.entries.iter_mut().find(|entry| entry.url == url) {
+            Some(entry) => {
+                entry.dns = Some(snapshot);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the passages relevant to a search query of a saved URL
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the URL is recorded
+    pub fn set_snippets(&mut self, url: &str, snippets: Vec<String>) -> bool {
+        match self.entries.iter_mut().find(|entry| entry.url == url) {
+            Some(entry) => {
+                entry.snippets = snippets;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add tags to a saved URL, ignoring tags it already has in any case
+    ///
+    /// # Returns
+    ///
+    /// Returns whether any tag was added
+    pub fn tag(&mut self, url: &str, tags: &[String]) -> bool {
+        let Some(entry) = self.entries.iter_mut().find(|entry| entry.url == url) else {
+            return false;
+        };
+        let mut added = false;
+        for tag in tags
+            .iter()
+            .map(|tag| tag.trim())
+            .filter(|tag| !tag.is_empty())
+        {
+            if !entry
+                .tags
+                .iter()
+                .any(|known| known.eq_ignore_ascii_case(tag))
+            {
+                entry.tags.push(tag.to_string());
+                added = true;
+            }
+        }
+        added
+    }
+
+    /// Decide where the output for a URL goes
+    ///
+    /// `outputs` maps a candidate stem to the files that would be written for it. A URL
+    /// counts as saved when the manifest lists it, or when all its output files already
+    /// exist and are not recorded for another URL (output written before the directory
+    /// had a manifest). Otherwise the first stem, starting from `base`, that is neither
+    /// in `reserved` nor used by another URL's files is reserved and returned. With
+    /// `force`, saved output is ignored and overwritten.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if none of the first [`MAX_STEM_CANDIDATES`] stems is free
+    pub fn resolve_output(
+        &self,
+        dir: &Path,
+        url: &str,
+        base: &str,
+        force: bool,
+        outputs: impl Fn(&str) -> Vec<PathBuf>,
+        reserved: &mut HashSet<String>,
+    ) -> Result<OutputSlot> {
+        if !force {
+            if let Some(files) = self.saved_files(dir, url) {
+                return Ok(OutputSlot::Saved(files));
+            }
+        }
+
+        for counter in 1..=MAX_STEM_CANDIDATES {
+            let stem = match counter {
+                1 => base.to_string(),
+                counter => format!("{}_{}", base, counter),
+            };
+            let files = outputs(&stem);
+            let taken = reserved.contains(&stem)
+                || files.iter().any(|file| {
+                    self.owner(dir, file)
+                        .is_some_and(|owner| owner != url && file.exists())
+                });
+            if !taken {
+                if !force && !files.is_empty() && files.iter().all(|file| file.exists()) {
+                    return Ok(OutputSlot::Saved(files));
+                }
+                reserved.insert(stem.clone());
+                return Ok(OutputSlot::Free(stem));
+            }
+        }
+        anyhow::bail!(
+            "No free output name for {} in {}: {} to {}_{} are all taken",
+            url,
+            dir.display(),
+            base,
+            base,
+            MAX_STEM_CANDIDATES
+        )
+    }
+}
+
+fn relative_name(dir: &Path, path: &Path) -> String {
+    path.strip_prefix(dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn pdf_outputs(dir: &Path) -> impl Fn(&str) -> Vec<PathBuf> + '_ {
+        move |stem| vec![dir.join(format!("{}.pdf", stem))]
+    }
+
+    #[tokio::test]
+    async fn test_load_save_roundtrip() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        assert_eq!(
+            OutputManifest::load(temp_dir.path()).await?,
+            OutputManifest::default()
+        );
+
+        let mut manifest = OutputManifest::default();
+        manifest.record(
+            temp_dir.path(),
+            "https://a.example",
+            &[temp_dir.path().join("a.pdf")],
+        );
+        assert!(manifest.tag(
+            "https://a.example",
+            &["rust".to_string(), "Rust".to_string()]
+        ));
+        assert!(!manifest.tag("https://b.example", &["rust".to_string()]));
+        assert!(manifest.set_snippets("https://a.example", vec!["About Rust".to_string()]));
+        let point = PricePoint {
+            checked_at: Utc::now(),
+            name: Some("Widget".to_string()),
+            price: 19.99,
+            currency: Some("USD".to_string()),
+            availability: Some("InStock".to_string()),
+        };
+        assert!(manifest.record_price("https://a.example", point.clone()));
+        assert!(!manifest.record_price("https://a.example", point.clone()));
+        assert!(manifest.record_price(
+            "https://a.example",
+            PricePoint {
+                price: 17.99,
+                ..point
+            }
+        ));
+        assert!(manifest.add_versions("https://a.example", &["1.0.0".to_string()]));
+        assert!(manifest.set_dns(
+            "https://a.example",
+            DnsSnapshot {
+                host: "a.example".to_string(),
+                resolved_at: Utc::now(),
+                nameserver: None,
+                cnames: vec!["a.cdn.example".to_string()],
+                ipv4: vec!["192.0.2.1".parse()?],
+                ipv6: Vec::new(),
+            }
+        ));
+        manifest.save(temp_dir.path()).await?;
+        assert_eq!(OutputManifest::load(temp_dir.path()).await?, manifest);
+        assert_eq!(manifest.entries[0].files, vec!["a.pdf"]);
+
+        // Converting again keeps the tags, the price history, and the versions
+        manifest.record(
+            temp_dir.path(),
+            "https://a.example",
+            &[temp_dir.path().join("a.md")],
+        );
+        assert_eq!(manifest.entries[0].tags, vec!["rust"]);
+        assert_eq!(manifest.entries[0].prices.len(), 2);
+        assert_eq!(manifest.versions("https://a.example"), ["1.0.0"]);
+        assert!(manifest.entries[0].snippets.is_empty());
+        assert!(manifest.entries[0].dns.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_saved_url() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let mut manifest = OutputManifest::default();
+        std::fs::write(dir.join("a.pdf"), "")?;
+        manifest.record(dir, "https://a.example", &[dir.join("a.pdf")]);
+
+        let mut reserved = HashSet::new();
+        assert_eq!(
+            manifest.resolve_output(
+                dir,
+                "https://a.example",
+                "other",
+                false,
+                pdf_outputs(dir),
+                &mut reserved
+            )?,
+            OutputSlot::Saved(vec![dir.join("a.pdf")])
+        );
+        assert_eq!(
+            manifest.resolve_output(
+                dir,
+                "https://a.example",
+                "a",
+                true,
+                pdf_outputs(dir),
+                &mut reserved
+            )?,
+            OutputSlot::Free("a".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_existing_file_and_collision() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let mut manifest = OutputManifest::default();
+        std::fs::write(dir.join("page.pdf"), "")?;
+
+        // An unrecorded file with the expected name counts as saved
+        let mut reserved = HashSet::new();
+        assert_eq!(
+            manifest.resolve_output(
+                dir,
+                "https://a.example",
+                "page",
+                false,
+                pdf_outputs(dir),
+                &mut reserved
+            )?,
+            OutputSlot::Saved(vec![dir.join("page.pdf")])
+        );
+
+        // A file recorded for another URL is never reused
+        manifest.record(dir, "https://b.example", &[dir.join("page.pdf")]);
+        assert_eq!(
+            manifest.resolve_output(
+                dir,
+                "https://a.example",
+                "page",
+                false,
+                pdf_outputs(dir),
+                &mut reserved
+            )?,
+            OutputSlot::Free("page_2".to_string())
+        );
+        assert_eq!(
+            manifest.resolve_output(
+                dir,
+                "https://c.example",
+                "page",
+                false,
+                pdf_outputs(dir),
+                &mut reserved
+            )?,
+            OutputSlot::Free("page_3".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_without_free_stem() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path();
+        let mut manifest = OutputManifest::default();
+        std::fs::write(dir.join("page.pdf"), "")?;
+        manifest.record(dir, "https://b.example", &[dir.join("page.pdf")]);
+
+        // Every stem maps to the file of another URL
+        let error = manifest
+            .resolve_output(
+                dir,
+                "https://a.example",
+                "page",
+                false,
+                |_| vec![dir.join("page.pdf")],
+                &mut HashSet::new(),
+            )
+            .unwrap_err();
+        assert!(error.to_string().contains("page_1000 are all taken"));
+        Ok(())
+    }
+}
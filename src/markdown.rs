@@ -3,18 +3,209 @@
 //! This module provides functionality to convert web pages to Markdown documents
 //! using HTML parsing and content extraction.
 
+use crate::alttext::{self, AltTextClient};
+use crate::assets::MarkdownImages;
+use crate::atomic;
+use crate::cache::PageCache;
+use crate::comments;
+use crate::cookies::BrowserCookies;
+use crate::extract::{self, ContentMode, ExtractConfig, Selector};
+use crate::frontmatter::{self, FrontMatterFormat, PageMetadata};
+use crate::pagination;
+use crate::pdf::OpenTab;
+use crate::pii::PiiScanner;
+use crate::printview::PrintViews;
+use crate::proxy::ProxyConfig;
+use crate::recipe::{self, Recipe};
+use crate::recording::{HttpRecorder, RecordedResponse, RecordingMode};
+use crate::request::RequestOptions;
+use crate::resolver::DnsResolver;
+use crate::session;
+use crate::social::SocialThreads;
+use crate::timeouts::{self, AdaptiveTimeouts};
+use crate::wait::WaitStrategy;
+use crate::wikipedia::Wikipedia;
 use anyhow::Result;
-use reqwest::Client;
+use chrono::Utc;
+use headless_chrome::{Browser, Tab};
+use regex::Regex;
+use reqwest::header::COOKIE;
+use reqwest::{Client, ClientBuilder, RequestBuilder, Response};
 use select::document::Document;
-use select::predicate::{Attr, Name};
+use select::predicate::Name;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::time::Duration;
-use tokio::fs;
+use std::sync::{LazyLock, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 use url::Url;
 
+/// How often the DOM of a page rendered with JavaScript is checked for changes
+const RENDER_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Longest wait for the DOM of a page rendered with JavaScript to stop changing
+const MAX_RENDER_WAIT: Duration = Duration::from_secs(10);
+
+/// Attributes of the main content holding URLs, including lazy-loaded image sources,
+/// with double-quoted, single-quoted, or unquoted values
+static URL_ATTR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?i)\s(href|src|srcset|data-src|data-lazy-src|data-original|poster)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'=<>`]+))"#,
+    )
+    .expect("valid regex")
+});
+
+/// Opening and closing tags of links
+static LINK_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)</?a(?:\s[^>]*)?>").expect("valid regex"));
+
+/// A page converted to Markdown, with its title and source kept apart from the content
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkdownPage {
+    /// The page title
+    pub title: String,
+    /// The URL the page came from, if known
+    pub url: Option<String>,
+    /// The Markdown of the page's main content
+    pub content: String,
+    /// Author, description, and other metadata of the page, for front matter
+    pub metadata: PageMetadata,
+}
+
+impl MarkdownPage {
+    /// Render the page as a standalone document
+    ///
+    /// When the source URL is known, the content is preceded by the title and a source link.
+    pub fn to_document(&self) -> String {
+        match &self.url {
+            Some(url) => format!(
+                "# {}\n\n*Source: [{}]({})*\n\n---\n\n{}",
+                self.title, url, url, self.content
+            ),
+            None => self.content.clone(),
+        }
+    }
+}
+
+/// Options of the Markdown documents a generator saves
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MarkdownConfig {
+    /// Front matter written at the top of each document
+    pub front_matter: FrontMatterFormat,
+    /// Keep the text of links but not their targets
+    pub strip_links: bool,
+}
+
+/// Merge pages into a single document with a table of contents
+///
+/// Each page becomes an H1 section followed by its source link. Headings inside the
+/// pages are demoted one level so that only the section titles are H1.
+pub fn merge_pages(pages: &[MarkdownPage]) -> String {
+    let mut used_anchors = HashSet::new();
+    let anchors: Vec<String> = pages
+        .iter()
+        .map(|page| unique_anchor(&page.title, &mut used_anchors))
+        .collect();
+
+    let mut merged = String::from("# Contents\n\n");
+    for (index, (page, anchor)) in pages.iter().zip(&anchors).enumerate() {
+        merged.push_str(&format!("{}. [{}](#{})", index + 1, page.title, anchor));
+        if let Some(url) = &page.url {
+            merged.push_str(&format!(" — <{}>", url));
+        }
+        merged.push('\n');
+    }
+
+    for page in pages {
+        merged.push_str(&format!("\n---\n\n# {}\n\n", page.title));
+        if let Some(url) = &page.url {
+            merged.push_str(&format!("*Source: [{}]({})*\n\n", url, url));
+        }
+        merged.push_str(demote_headings(strip_title_heading(&page.content, &page.title)).trim());
+        merged.push('\n');
+    }
+
+    merged
+}
+
+/// Build a GitHub-style heading anchor, made unique within a document
+fn unique_anchor(title: &str, used_anchors: &mut HashSet<String>) -> String {
+    let base: String = title
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect();
+
+    let mut anchor = base.clone();
+    let mut counter = 1;
+    while !used_anchors.insert(anchor.clone()) {
+        anchor = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+    anchor
+}
+
+/// Drop a leading heading that repeats the page title, which the section heading replaces
+fn strip_title_heading<'a>(markdown: &'a str, title: &str) -> &'a str {
+    let content = markdown.trim_start();
+    let (first_line, rest) = content.split_once('\n').unwrap_or((content, ""));
+    let heading = first_line.trim_start_matches('#');
+    if heading.len() < first_line.len() && heading.trim() == title.trim() {
+        rest
+    } else {
+        markdown
+    }
+}
+
+/// Demote ATX headings one level (H6 stays H6), leaving fenced code blocks untouched
+fn demote_headings(markdown: &str) -> String {
+    let mut in_code_block = false;
+    let mut demoted = String::with_capacity(markdown.len());
+    for line in markdown.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+
+        let level = line.chars().take_while(|&c| c == '#').count();
+        let is_heading = (1..6).contains(&level) && line[level..].starts_with(' ');
+        if !in_code_block && is_heading {
+            demoted.push('#');
+        }
+        demoted.push_str(line);
+        demoted.push('\n');
+    }
+    demoted
+}
+
 /// Markdown generator that fetches URLs and converts HTML to Markdown
+#[derive(Clone)]
 pub struct MarkdownGenerator {
     client: Client,
+    page_cache: Option<PageCache>,
+    offline: bool,
+    timeouts: Option<AdaptiveTimeouts>,
+    extraction: ExtractConfig,
+    follow_pagination: usize,
+    print_views: Option<PrintViews>,
+    social_threads: Option<SocialThreads>,
+    wikipedia: Option<Wikipedia>,
+    browser: Option<Browser>,
+    render_js: bool,
+    wait: Option<WaitStrategy>,
+    launched_browser: OnceLock<Browser>,
+    cookies: Option<BrowserCookies>,
+    request_options: Option<RequestOptions>,
+    proxy: Option<ProxyConfig>,
+    resolver: Option<DnsResolver>,
+    recorder: Option<HttpRecorder>,
+    pii_scanner: Option<PiiScanner>,
+    alt_text: Option<AltTextClient>,
+    images: Option<MarkdownImages>,
+    config: MarkdownConfig,
 }
 
 impl MarkdownGenerator {
@@ -24,12 +215,207 @@ impl MarkdownGenerator {
     ///
     /// Returns an error if the HTTP client cannot be created
     pub async fn new() -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(30))
-            .user_agent("webpage-save-markdown-generator/1.0")
-            .build()?;
+        let client = client_builder().build()?;
+
+        Ok(Self {
+            client,
+            page_cache: None,
+            offline: false,
+            timeouts: None,
+            extraction: ExtractConfig::default(),
+            follow_pagination: 0,
+            print_views: None,
+            social_threads: None,
+            wikipedia: None,
+            browser: None,
+            render_js: false,
+            wait: None,
+            launched_browser: OnceLock::new(),
+            cookies: None,
+            request_options: None,
+            proxy: None,
+            resolver: None,
+            recorder: None,
+            pii_scanner: None,
+            alt_text: None,
+            images: None,
+            config: MarkdownConfig::default(),
+        })
+    }
+
+    /// Save a snapshot of every fetched page in the given cache
+    pub fn with_page_cache(mut self, cache: PageCache) -> Self {
+        self.page_cache = Some(cache);
+        self
+    }
 
-        Ok(Self { client })
+    /// Convert URLs only from cached snapshots, never fetching them
+    ///
+    /// URLs that are not in the page cache fail. Requires a page cache (see `with_page_cache`).
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Fetch pages with per-domain timeouts that grow for slow domains
+    ///
+    /// A fetch that times out is retried if its domain's timeout was extended. Without
+    /// this, every fetch uses a fixed 30 second timeout.
+    pub fn with_adaptive_timeouts(mut self, timeouts: AdaptiveTimeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Configure how the main content is extracted from pages
+    pub fn with_extraction(mut self, extraction: ExtractConfig) -> Self {
+        self.extraction = extraction;
+        self
+    }
+
+    /// Follow "next page" links of paginated articles for up to this many further pages,
+    /// stitching all parts into one document
+    pub fn with_follow_pagination(mut self, max_pages: usize) -> Self {
+        self.follow_pagination = max_pages;
+        self
+    }
+
+    /// Fetch the print view of pages when one is available
+    pub fn with_print_views(mut self, print_views: PrintViews) -> Self {
+        self.print_views = Some(print_views);
+        self
+    }
+
+    /// Capture Twitter/X and Mastodon posts through the platform APIs, converting the
+    /// thread instead of the page
+    pub fn with_social_threads(mut self, social_threads: SocialThreads) -> Self {
+        self.social_threads = Some(social_threads);
+        self
+    }
+
+    /// Export Wikipedia articles through the REST API, converting the article with its
+    /// references as footnotes instead of the page
+    pub fn with_wikipedia(mut self, wikipedia: Wikipedia) -> Self {
+        self.wikipedia = Some(wikipedia);
+        self
+    }
+
+    /// Load pages in this browser instead of fetching them over HTTP
+    ///
+    /// Used with a browser on a persistent profile (see [`crate::session`]), so pages
+    /// behind a login are converted as the logged-in user.
+    pub fn with_browser(mut self, browser: Browser) -> Self {
+        self.browser = Some(browser);
+        self
+    }
+
+    /// Render pages with JavaScript in a browser and convert the resulting DOM, so that
+    /// single-page applications that build their content in the browser do not produce
+    /// empty Markdown
+    ///
+    /// Pages are loaded in the browser given with [`Self::with_browser`], or else in a
+    /// headless Chrome launched on first use.
+    pub fn with_render_js(mut self, render_js: bool) -> Self {
+        self.render_js = render_js;
+        self
+    }
+
+    /// Wait for a selector or for the network to go idle before reading pages loaded
+    /// in a browser
+    pub fn with_wait_strategy(mut self, wait: WaitStrategy) -> Self {
+        self.wait = Some(wait);
+        self
+    }
+
+    /// Send the cookies imported from a browser that apply to each fetched page
+    pub fn with_cookies(mut self, cookies: BrowserCookies) -> Self {
+        self.cookies = Some(cookies);
+        self
+    }
+
+    /// Fetch pages through a proxy, also when they are loaded in a browser this
+    /// generator launches
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created with the proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Self> {
+        self.proxy = Some(proxy);
+        self.client = self.build_client()?;
+        Ok(self)
+    }
+
+    /// Resolve the hosts of fetched pages with a custom resolver, also when they are
+    /// loaded in a browser this generator launches without a proxy
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn with_resolver(mut self, resolver: DnsResolver) -> Result<Self> {
+        self.resolver = Some(resolver);
+        self.client = self.build_client()?;
+        Ok(self)
+    }
+
+    /// An HTTP client through the proxy and with the resolver, if any
+    fn build_client(&self) -> Result<Client> {
+        let mut builder = client_builder();
+        if let Some(proxy) = &self.proxy {
+            builder = proxy.apply(builder)?;
+        }
+        if let Some(resolver) = &self.resolver {
+            builder = resolver.apply(builder);
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Send extra headers, such as `Authorization`, with each fetched page
+    ///
+    /// Headers given here replace imported cookies and the client's own headers of
+    /// the same name.
+    pub fn with_request_options(mut self, options: RequestOptions) -> Self {
+        self.request_options = Some(options);
+        self
+    }
+
+    /// Record every fetched page, or answer fetches from a recording instead of the
+    /// network
+    pub fn with_recorder(mut self, recorder: HttpRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Scan the content of every converted page for personal data
+    pub fn with_pii_scanner(mut self, scanner: PiiScanner) -> Self {
+        self.pii_scanner = Some(scanner);
+        self
+    }
+
+    /// Generate alt text for the images of fetched pages that have no description
+    pub fn with_alt_text(mut self, client: AltTextClient) -> Self {
+        self.alt_text = Some(client);
+        self
+    }
+
+    /// Download the images of saved Markdown files and point their links at the copies
+    pub fn with_images(mut self, images: MarkdownImages) -> Self {
+        self.images = Some(images);
+        self
+    }
+
+    /// Write saved documents with these options, such as front matter
+    pub fn with_config(mut self, config: MarkdownConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The HTTP client pages are fetched with
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Whether pages are read only from the page cache
+    pub(crate) fn is_offline(&self) -> bool {
+        self.offline
     }
 
     /// Convert a URL to Markdown
@@ -43,6 +429,10 @@ impl MarkdownGenerator {
     ///
     /// Returns the Markdown content as a String
     ///
+    /// The document starts with front matter when configured with
+    /// [`Self::with_config`], and its images are downloaded next to the file or inlined
+    /// when set with [`Self::with_images`].
+    ///
     /// # Errors
     ///
     /// Returns an error if:
@@ -51,25 +441,381 @@ impl MarkdownGenerator {
     /// - HTML parsing fails
     /// - File I/O operations fail
     pub async fn url_to_markdown(&self, url: &str, output_path: Option<&Path>) -> Result<String> {
+        let (page, recipe) = self.capture_page(url).await?;
+        let mut markdown_content = self.document(&page)?;
+        if let Some(images) = self.images.as_ref().filter(|_| !self.offline) {
+            markdown_content = images
+                .localize(&self.client, &markdown_content, Some(url), output_path)
+                .await?;
+        }
+
+        // Save to file if output path is provided, with the JSON of a recipe next to it
+        if let Some(path) = output_path {
+            atomic::write(path, &markdown_content).await?;
+            if let Some(recipe) = recipe {
+                atomic::write(&recipe::json_path(path), recipe.to_json()).await?;
+            }
+        }
+
+        Ok(markdown_content)
+    }
+
+    /// Convert a URL to a Markdown page, keeping the title and content separate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The URL is invalid or cannot be accessed
+    /// - The HTTP request fails
+    /// - HTML parsing fails
+    pub async fn url_to_page(&self, url: &str) -> Result<MarkdownPage> {
+        Ok(self.capture_page(url).await?.0)
+    }
+
+    /// Convert exactly the part of a URL's page matching a CSS selector to Markdown
+    ///
+    /// The selector replaces the configured content selectors, also on recipe pages, and
+    /// supports the subset
+    /// of CSS described in [`crate::extract`], e.g. `article.post` or `#main .body`;
+    /// a comma-separated list converts the first element matching any of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the selector is invalid, no element of the page matches it,
+    /// or the page cannot be fetched or the file written
+    pub async fn url_to_markdown_with_selector(
+        &self,
+        url: &str,
+        selector: &str,
+        output_path: Option<&Path>,
+    ) -> Result<String> {
+        let mut generator = self.clone();
+        generator.extraction.content_selectors = Selector::parse_list(selector)?;
+        generator.extraction.require_content = true;
+        generator.extraction.mode = ContentMode::Page;
+        generator.url_to_markdown(url, output_path).await
+    }
+
+    /// A page as a standalone document, after front matter if configured
+    fn document(&self, page: &MarkdownPage) -> Result<String> {
+        let front_matter = frontmatter::front_matter(
+            self.config.front_matter,
+            &page.title,
+            page.url.as_deref(),
+            Utc::now(),
+            &page.metadata,
+            frontmatter::word_count(&page.content),
+        )?;
+        Ok(format!("{}{}", front_matter, page.to_document()))
+    }
+
+    /// Convert a URL to a Markdown page, with the recipe it was converted from if it
+    /// was converted as a recipe, scanning the page for personal data
+    async fn capture_page(&self, url: &str) -> Result<(MarkdownPage, Option<Recipe>)> {
+        let (page, recipe) = self.convert_url(url).await?;
+        if let Some(scanner) = &self.pii_scanner {
+            scanner.scan(url, &page.content);
+        }
+        Ok((page, recipe))
+    }
+
+    /// The HTML of a URL, preferring the snapshot saved in the page cache when it was
+    /// captured
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page is not cached and cannot be fetched
+    pub async fn url_to_html(&self, url: &str) -> Result<String> {
+        if let Some(html_content) = match &self.page_cache {
+            Some(cache) => cache.get(url).await,
+            None => None,
+        } {
+            return Ok(html_content);
+        }
+        self.fetch_html(url).await
+    }
+
+    /// Convert a URL to a Markdown page, from a platform API, the network, or the cache
+    async fn convert_url(&self, url: &str) -> Result<(MarkdownPage, Option<Recipe>)> {
         // Validate URL
         let parsed_url = Url::parse(url)?;
         if !matches!(parsed_url.scheme(), "http" | "https") {
             return Err(anyhow::anyhow!("Only HTTP and HTTPS URLs are supported"));
         }
 
-        // Fetch HTML content
-        let response = self.client.get(url).send().await?;
-        let html_content = response.text().await?;
+        let captured = match self.offline {
+            true => None,
+            false => self.capture(url).await?,
+        };
+        if let Some((html_content, page)) = captured {
+            if let Some(cache) = &self.page_cache {
+                if let Err(e) = cache.put(url, &html_content).await {
+                    warn!("Failed to save page snapshot for {}: {}", url, e);
+                }
+            }
+            return Ok((page, None));
+        }
+
+        // Fetch HTML content, with the following parts of paginated articles
+        let parts = self.fetch_parts(url).await?;
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        if let Some(recipe) = self.find_recipe(parts[0], Some(url))? {
+            return Ok((recipe.to_page(), Some(recipe)));
+        }
+        let main_content = self.main_content(&parts, Some(url))?;
+        let alt_texts = match &self.alt_text {
+            Some(client) => client.describe_images(&main_content, Some(url)).await,
+            None => HashMap::new(),
+        };
 
         // Convert HTML to Markdown
-        let markdown_content = self.html_to_markdown(&html_content, Some(url)).await?;
+        let page = self.content_to_page(&parts, &main_content, Some(url), &alt_texts)?;
+        Ok((page, None))
+    }
 
-        // Save to file if output path is provided
-        if let Some(path) = output_path {
-            fs::write(path, &markdown_content).await?;
+    /// The recipe a page is converted as, depending on the content mode
+    ///
+    /// # Errors
+    ///
+    /// Returns an error in recipe mode if the page has no recipe
+    fn find_recipe(&self, html_content: &str, url: Option<&str>) -> Result<Option<Recipe>> {
+        if self.extraction.mode == ContentMode::Page {
+            return Ok(None);
+        }
+        match recipe::extract_recipe(html_content, url) {
+            None if self.extraction.mode == ContentMode::Recipe => Err(anyhow::anyhow!(
+                "No schema.org recipe found on {}",
+                url.unwrap_or("the page")
+            )),
+            recipe => Ok(recipe),
         }
+    }
 
-        Ok(markdown_content)
+    /// The document and Markdown page of a social thread or Wikipedia article read
+    /// through the platform's API, or None if the URL is neither or cannot be read that way
+    async fn capture(&self, url: &str) -> Result<Option<(String, MarkdownPage)>> {
+        if let Some(social_threads) = &self.social_threads {
+            if let Some(thread) = social_threads.capture(url).await {
+                let page = MarkdownPage {
+                    title: thread.title(),
+                    url: Some(url.to_string()),
+                    content: thread.to_markdown(),
+                    metadata: PageMetadata::default(),
+                };
+                return Ok(Some((thread.to_html()?, page)));
+            }
+        }
+        if let Some(wikipedia) = &self.wikipedia {
+            if let Some(article) = wikipedia.capture(url).await {
+                let page = MarkdownPage {
+                    title: article.title.clone(),
+                    url: Some(url.to_string()),
+                    content: article.to_markdown(),
+                    metadata: PageMetadata::default(),
+                };
+                return Ok(Some((article.to_html()?, page)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Fetch the HTML of a URL and of up to `follow_pagination` pages following it
+    ///
+    /// Pagination stops at the first part that cannot be fetched, keeping the parts
+    /// fetched so far.
+    async fn fetch_parts(&self, url: &str) -> Result<Vec<String>> {
+        let mut parts = vec![self.fetch_html(url).await?];
+        let mut current = url.to_string();
+        let mut visited = HashSet::from([current.clone()]);
+        while parts.len() <= self.follow_pagination {
+            let Some(next) = pagination::next_page_url(&parts[parts.len() - 1], &current) else {
+                break;
+            };
+            if !visited.insert(next.clone()) {
+                break;
+            }
+            match self.fetch_html(&next).await {
+                Ok(html_content) => {
+                    info!("Stitching page {} of {}: {}", parts.len() + 1, url, next);
+                    parts.push(html_content);
+                    current = next;
+                }
+                Err(e) => {
+                    warn!("Failed to fetch page {} of {}: {}", parts.len() + 1, url, e);
+                    break;
+                }
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Fetch the HTML for a URL, from the page cache in offline mode or the network otherwise
+    async fn fetch_html(&self, url: &str) -> Result<String> {
+        if self.offline {
+            let cache = self
+                .page_cache
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Offline mode requires the page cache"))?;
+            return cache.require(url).await;
+        }
+
+        let html_content = match &self.print_views {
+            Some(print_views) => self.fetch_remote(&print_views.resolve(url).await).await?,
+            None => self.fetch_remote(url).await?,
+        };
+
+        // Snapshots are kept under the page URL, even when its print view was fetched
+        if let Some(cache) = &self.page_cache {
+            if let Err(e) = cache.put(url, &html_content).await {
+                warn!("Failed to save page snapshot for {}: {}", url, e);
+            }
+        }
+
+        Ok(html_content)
+    }
+
+    /// Fetch the HTML of a URL from the network, adapting the timeout to the domain
+    async fn fetch_remote(&self, url: &str) -> Result<String> {
+        if let Some(recorder) = self
+            .recorder
+            .as_ref()
+            .filter(|r| r.mode() == RecordingMode::Replay)
+        {
+            let response = recorder
+                .lookup("GET", url)
+                .ok_or_else(|| anyhow::anyhow!("Not in the recording: {}", url))?;
+            return Ok(String::from_utf8_lossy(&response.body_bytes()?).into_owned());
+        }
+        if let Some(browser) = &self.browser {
+            return self.load_in_browser(browser, url).await;
+        }
+        if self.render_js {
+            let browser = match self.launched_browser.get() {
+                Some(browser) => browser,
+                None => {
+                    let proxy = match (&self.proxy, &self.resolver) {
+                        (None, Some(resolver)) => Some(resolver.browser_proxy()?),
+                        (proxy, _) => proxy.clone(),
+                    };
+                    // Another fetch may have launched one meanwhile, which is then kept
+                    let _ = self.launched_browser.set(session::launch_browser(
+                        true,
+                        None,
+                        proxy.as_ref(),
+                    )?);
+                    self.launched_browser
+                        .get()
+                        .ok_or_else(|| anyhow::anyhow!("Failed to launch Chrome"))?
+                }
+            };
+            return self.load_in_browser(browser, url).await;
+        }
+        let Some(timeouts) = &self.timeouts else {
+            let response = self.request(url).send().await?;
+            return Ok(self.read_response(url, response).await?);
+        };
+
+        loop {
+            let started = Instant::now();
+            let request = self.request(url).timeout(timeouts.timeout_for(url));
+            let result = match request.send().await {
+                Ok(response) => self.read_response(url, response).await,
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(html_content) => {
+                    timeouts.record(url, started.elapsed(), false);
+                    return Ok(html_content);
+                }
+                Err(e) if e.is_timeout() => match timeouts.record(url, started.elapsed(), true) {
+                    Some(timeout) => warn!(
+                        "Timed out fetching {}; retrying with a {}s timeout",
+                        url,
+                        timeout.as_secs()
+                    ),
+                    None => return Err(e.into()),
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    /// Read the text of a response, adding it to the recording when recording
+    async fn read_response(&self, url: &str, response: Response) -> reqwest::Result<String> {
+        let Some(recorder) = &self.recorder else {
+            return response.text().await;
+        };
+        let status = response.status().as_u16().into();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
+        let html_content = response.text().await?;
+        recorder.push(RecordedResponse::new(
+            "GET",
+            url,
+            status,
+            headers,
+            html_content.as_bytes(),
+        ));
+        Ok(html_content)
+    }
+
+    /// A GET request for a URL, carrying the imported cookies that apply to it and the
+    /// extra headers
+    fn request(&self, url: &str) -> RequestBuilder {
+        let mut request = self.client.get(url);
+        if let Some(header) = self
+            .cookies
+            .as_ref()
+            .and_then(|cookies| cookies.header(url))
+        {
+            request = request.header(COOKIE, header);
+        }
+        match &self.request_options {
+            Some(options) => request.headers(options.headers.clone()),
+            None => request,
+        }
+    }
+
+    /// Load a URL in a new browser tab and return its rendered HTML
+    async fn load_in_browser(&self, browser: &Browser, url: &str) -> Result<String> {
+        let tab = OpenTab(browser.new_tab()?);
+        if let Some(proxy) = &self.proxy {
+            proxy.authenticate(&tab)?;
+        }
+        if let Some(recorder) = &self.recorder {
+            recorder.attach(&tab)?;
+        }
+        if let Some(options) = &self.request_options {
+            options.apply(&tab)?;
+        }
+        if let Some(timeouts) = &self.timeouts {
+            tab.set_default_timeout(timeouts.timeout_for(url));
+        }
+        let watch = self
+            .wait
+            .as_ref()
+            .map(|wait| wait.watch(&tab))
+            .transpose()?;
+        let loaded = tab
+            .navigate_to(url)
+            .and_then(|tab| tab.wait_until_navigated());
+        if let (Ok(_), Some(watch)) = (&loaded, &watch) {
+            watch.wait().await;
+        }
+        drop(watch);
+        match loaded {
+            Ok(tab) if self.render_js => rendered_html(tab).await,
+            Ok(tab) => tab.get_content(),
+            Err(e) => Err(e),
+        }
     }
 
     /// Convert HTML content to Markdown
@@ -93,31 +839,90 @@ impl MarkdownGenerator {
         html_content: &str,
         base_url: Option<&str>,
     ) -> Result<String> {
-        // Extract main content from HTML
-        let main_content = self.extract_main_content(html_content)?;
+        self.document(&self.html_to_page(html_content, base_url)?)
+    }
 
-        // Convert HTML to Markdown using mdka
-        let markdown_content = mdka::from_html(&main_content);
+    /// Convert HTML content to a Markdown page, keeping the title and content separate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if HTML parsing fails
+    pub fn html_to_page(&self, html_content: &str, url: Option<&str>) -> Result<MarkdownPage> {
+        if let Some(recipe) = self.find_recipe(html_content, url)? {
+            return Ok(recipe.to_page());
+        }
+        self.parts_to_page(&[html_content], url)
+    }
 
-        // Add metadata header if base_url is provided
-        let final_content = if let Some(url) = base_url {
-            format!(
-                "# {}\n\n*Source: [{}]({})*\n\n---\n\n{}",
-                self.extract_title(html_content)
-                    .unwrap_or_else(|| "Untitled".to_string()),
-                url,
-                url,
-                markdown_content
-            )
-        } else {
-            markdown_content
+    /// Convert the parts of a paginated page to one Markdown page
+    ///
+    /// The main content of every part is converted as one continuous document, titled
+    /// after the first part.
+    fn parts_to_page(&self, parts: &[&str], url: Option<&str>) -> Result<MarkdownPage> {
+        let main_content = self.main_content(parts, url)?;
+        self.content_to_page(parts, &main_content, url, &HashMap::new())
+    }
+
+    /// The main content of every part of a page, as one HTML document
+    ///
+    /// Relative links and image sources are resolved against the page's base URL, and
+    /// links are removed when configured to strip them.
+    fn main_content(&self, parts: &[&str], url: Option<&str>) -> Result<String> {
+        let content = parts
+            .iter()
+            .map(|part| self.extract_main_content(part))
+            .collect::<Result<Vec<_>>>()?
+            .join("\n");
+        let content = match base_url(parts[0], url) {
+            Some(base) => resolve_links(&content, &base),
+            None => content,
         };
+        Ok(match self.config.strip_links {
+            true => LINK_TAG.replace_all(&content, "").into_owned(),
+            false => content,
+        })
+    }
+
+    /// Convert the main content of a page to a Markdown page, with generated alt text
+    /// for the images that have no description
+    fn content_to_page(
+        &self,
+        parts: &[&str],
+        main_content: &str,
+        url: Option<&str>,
+        alt_texts: &HashMap<String, String>,
+    ) -> Result<MarkdownPage> {
+        // Convert HTML to Markdown using mdka, with images it would drop rewritten
+        let mut content = mdka::from_html(&alttext::normalize_images(main_content, alt_texts));
+        if self.extraction.include_comments {
+            let comments: Vec<_> = parts
+                .iter()
+                .flat_map(|part| comments::extract_comments(&Document::from(*part)))
+                .collect();
+            if !comments.is_empty() {
+                content = format!(
+                    "{}\n\n{}",
+                    content.trim_end(),
+                    comments::comments_markdown(&comments)
+                );
+            }
+        }
 
-        Ok(final_content)
+        Ok(MarkdownPage {
+            title: self
+                .extract_title(parts[0])
+                .unwrap_or_else(|| "Untitled".to_string()),
+            url: url.map(|url| url.to_string()),
+            content,
+            metadata: PageMetadata::from_html(parts[0], url),
+        })
     }
 
     /// Extract main content from HTML using various strategies
     ///
+    /// The configured content selectors are tried before the built-in ones, and elements
+    /// matching the configured strip selectors are removed from the result.
+    ///
     /// # Arguments
     ///
     /// * `html_content` - The HTML content to extract from
@@ -129,47 +934,12 @@ impl MarkdownGenerator {
     /// # Errors
     ///
     /// Returns an error if HTML parsing fails
-    fn extract_main_content(&self, html_content: &str) -> Result<String> {
+    pub(crate) fn extract_main_content(&self, html_content: &str) -> Result<String> {
         let document = Document::from(html_content);
-
-        // Try common content selectors in order of preference
-        let tag_selectors = ["main", "article", "body"];
-        let class_selectors = [
-            "main-content",
-            "content",
-            "post-content",
-            "entry-content",
-            "article-content",
-        ];
-        let id_selectors = ["content"];
-
-        // Try tag selectors first
-        for &selector in &tag_selectors {
-            if let Some(element) = document.find(Name(selector)).next() {
-                return Ok(element.html());
-            }
-        }
-
-        // Try class selectors
-        for &class_name in &class_selectors {
-            if let Some(element) = document.find(Attr("class", class_name)).next() {
-                return Ok(element.html());
-            }
-        }
-
-        // Try ID selectors
-        for &id_name in &id_selectors {
-            if let Some(element) = document.find(Attr("id", id_name)).next() {
-                return Ok(element.html());
-            }
-        }
-
-        // Fallback to body content
-        if let Some(body) = document.find(Name("body")).next() {
-            Ok(body.html())
-        } else {
+        match self.extraction.require_main(&document)? {
+            Some(element) => Ok(self.extraction.html(&element)),
             // Last resort: return the entire document
-            Ok(html_content.to_string())
+            None => Ok(html_content.to_string()),
         }
     }
 
@@ -182,49 +952,113 @@ impl MarkdownGenerator {
     /// # Returns
     ///
     /// Returns the extracted title as an Option<String>
-    fn extract_title(&self, html_content: &str) -> Option<String> {
-        let document = Document::from(html_content);
+    pub(crate) fn extract_title(&self, html_content: &str) -> Option<String> {
+        extract::find_title(&Document::from(html_content))
+    }
+}
 
-        // Try various title selectors
-        let tag_selectors = ["h1", "title"];
-        let class_selectors = ["title", "post-title", "entry-title", "article-title"];
+/// The URL relative links of a page resolve against: its `<base href>`, itself relative
+/// to the page URL, or else the page URL
+fn base_url(html: &str, url: Option<&str>) -> Option<Url> {
+    let page = url.and_then(|url| Url::parse(url).ok());
+    Document::from(html)
+        .find(Name("base"))
+        .find_map(|base| base.attr("href"))
+        .map(str::trim)
+        .filter(|href| !href.is_empty())
+        .and_then(|href| match &page {
+            Some(page) => page.join(href).ok(),
+            None => Url::parse(href).ok(),
+        })
+        .or(page)
+}
 
-        // Try tag selectors first
-        for &selector in &tag_selectors {
-            if let Some(element) = document.find(Name(selector)).next() {
-                let text = element.text().trim().to_string();
-                if !text.is_empty() {
-                    return Some(text);
-                }
-            }
-        }
+/// Resolve the links and image sources of HTML against a base URL
+///
+/// Links within the page and `data:` and `javascript:` URLs are kept as they are, and
+/// the candidates of a `srcset` are resolved one by one. Resolved values are written
+/// double-quoted, whatever their quoting was.
+fn resolve_links(html: &str, base: &Url) -> String {
+    URL_ATTR
+        .replace_all(html, |captures: &regex::Captures| {
+            let attr = captures[1].to_ascii_lowercase();
+            let value = (2..=4)
+                .find_map(|group| captures.get(group))
+                .map_or("", |value| value.as_str())
+                .replace("&amp;", "&");
+            let resolved = match attr.as_str() {
+                // Data URIs contain commas
+                "srcset" if !value.contains("data:") => value
+                    .split(',')
+                    .map(
+                        |candidate| match candidate.trim().split_once(char::is_whitespace) {
+                            Some((url, descriptor)) => {
+                                format!("{} {}", resolve_url(base, url), descriptor.trim())
+                            }
+                            None => resolve_url(base, candidate),
+                        },
+                    )
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                _ => resolve_url(base, &value),
+            };
+            format!(
+                " {}=\"{}\"",
+                attr,
+                resolved.replace('&', "&amp;").replace('"', "&quot;")
+            )
+        })
+        .into_owned()
+}
 
-        // Try Open Graph meta tag
-        if let Some(element) = document.find(Attr("property", "og:title")).next() {
-            if let Some(content) = element.attr("content") {
-                return Some(content.to_string());
-            }
-        }
+/// A URL resolved against a base, unless it points within the page or is a `data:` or
+/// `javascript:` URL
+fn resolve_url(base: &Url, value: &str) -> String {
+    let value = value.trim();
+    if value.is_empty()
+        || value.starts_with('#')
+        || value.starts_with("data:")
+        || value.starts_with("javascript:")
+    {
+        return value.to_string();
+    }
+    base.join(value)
+        .map_or_else(|_| value.to_string(), String::from)
+}
 
-        // Try Twitter meta tag
-        if let Some(element) = document.find(Attr("name", "twitter:title")).next() {
-            if let Some(content) = element.attr("content") {
-                return Some(content.to_string());
-            }
-        }
+/// An HTTP client builder with the generator's timeout and user agent
+fn client_builder() -> ClientBuilder {
+    Client::builder()
+        .timeout(timeouts::DEFAULT_TIMEOUT)
+        .user_agent("webpage-save-markdown-generator/1.0")
+}
 
-        // Try class selectors
-        for &class_name in &class_selectors {
-            if let Some(element) = document.find(Attr("class", class_name)).next() {
-                let text = element.text().trim().to_string();
-                if !text.is_empty() {
-                    return Some(text);
-                }
-            }
+/// The HTML of the DOM of a loaded page, once its scripts have stopped changing it
+///
+/// Single-page applications keep building their content after the load event, so the
+/// DOM is read until it has not changed for one poll interval, or [`MAX_RENDER_WAIT`]
+/// has passed. The polling sleeps on the async runtime rather than blocking its thread.
+async fn rendered_html(tab: &Tab) -> Result<String> {
+    let outer_html = || -> Result<String> {
+        let result = tab.evaluate("document.documentElement.outerHTML", false)?;
+        Ok(result
+            .value
+            .as_ref()
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string())
+    };
+    let started = Instant::now();
+    let mut html_content = outer_html()?;
+    while started.elapsed() < MAX_RENDER_WAIT {
+        tokio::time::sleep(RENDER_POLL_INTERVAL).await;
+        let current = outer_html()?;
+        if current == html_content {
+            break;
         }
-
-        None
+        html_content = current;
     }
+    Ok(format!("<!DOCTYPE html>\n{}", html_content))
 }
 
 #[cfg(test)]
@@ -257,6 +1091,30 @@ mod tests {
         Ok(())
     }
 
+    /// Representative pages whose conversion is kept in `src/snapshots`
+    const FIXTURES: &[(&str, &str)] = &[
+        ("blog", include_str!("../tests/fixtures/pages/blog.html")),
+        ("docs", include_str!("../tests/fixtures/pages/docs.html")),
+        ("news", include_str!("../tests/fixtures/pages/news.html")),
+        (
+            "tables",
+            include_str!("../tests/fixtures/pages/tables.html"),
+        ),
+        ("code", include_str!("../tests/fixtures/pages/code.html")),
+    ];
+
+    #[tokio::test]
+    async fn test_fixture_snapshots() -> Result<()> {
+        // Review changed conversions with `cargo insta review`
+        let generator = MarkdownGenerator::new().await?;
+        for (name, html) in FIXTURES {
+            let url = format!("https://example.com/{}", name);
+            let markdown = generator.html_to_markdown(html, Some(&url)).await?;
+            insta::assert_snapshot!(format!("fixture_{}", name), markdown);
+        }
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_extract_main_content() -> Result<()> {
         let generator = MarkdownGenerator::new().await?;
@@ -281,6 +1139,64 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_extract_with_configured_selectors() -> Result<()> {
+        use crate::extract::Selector;
+
+        let generator = MarkdownGenerator::new().await?.with_extraction(
+            ExtractConfig::new()
+                .with_content_selectors(vec![Selector::parse("div.post-body")?])
+                .with_strip_selectors(Selector::parse_list(".share, aside")?),
+        );
+        let html = r#"
+            <html>
+            <body>
+                <main><p>Site intro</p></main>
+                <div class="post-body">
+                    <p>The article.</p>
+                    <div class="share">Share this</div>
+                    <aside>Related posts</aside>
+                </div>
+            </body>
+            </html>
+        "#;
+
+        let main_content = generator.extract_main_content(html)?;
+        assert!(main_content.contains("The article."));
+        assert!(!main_content.contains("Site intro"));
+        assert!(!main_content.contains("Share this"));
+        assert!(!main_content.contains("Related posts"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_include_comments() -> Result<()> {
+        let generator = MarkdownGenerator::new()
+            .await?
+            .with_extraction(ExtractConfig::new().with_include_comments(true));
+        let html = r#"
+            <html>
+            <body>
+                <article>
+                    <p>The article text, long enough to be the main content.</p>
+                    <ol><li class="comment">
+                        <b class="comment-author"><span class="fn">Ann</span></b>
+                        <div class="comment-content"><p>Great post</p></div>
+                    </li></ol>
+                </article>
+            </body>
+            </html>
+        "#;
+
+        let page = generator.html_to_page(html, None)?;
+        let (article, appendix) = page.content.split_once("## Comments (1)").unwrap();
+        assert!(article.contains("The article text"));
+        assert!(!article.contains("Great post"));
+        assert!(appendix.contains("- **Ann**"));
+        assert!(appendix.contains("Great post"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_extract_title() -> Result<()> {
         let generator = MarkdownGenerator::new().await?;
@@ -317,6 +1233,75 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_offline_url_to_markdown() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let cache = PageCache::new(temp_dir.path());
+        cache
+            .put(
+                "https://example.com/cached",
+                "<html><body><h1>Cached Page</h1></body></html>",
+            )
+            .await?;
+
+        let generator = MarkdownGenerator::new()
+            .await?
+            .with_page_cache(cache)
+            .with_offline(true);
+        let markdown_content = generator
+            .url_to_markdown("https://example.com/cached", None)
+            .await?;
+        assert!(markdown_content.contains("Cached Page"));
+
+        let result = generator
+            .url_to_markdown("https://example.com/missing", None)
+            .await;
+        assert!(result.is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_follow_pagination() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let cache = PageCache::new(temp_dir.path());
+        for (url, html) in [
+            (
+                "https://example.com/story",
+                r#"<html><body><h1>Story</h1><article><p>Part one.</p></article>
+                <a href="/story?page=2">2</a></body></html>"#,
+            ),
+            (
+                "https://example.com/story?page=2",
+                r#"<html><head><link rel="next" href="/story?page=3"></head>
+                <body><article><p>Part two.</p></article></body></html>"#,
+            ),
+            (
+                "https://example.com/story?page=3",
+                "<html><body><article><p>Part three.</p></article></body></html>",
+            ),
+        ] {
+            cache.put(url, html).await?;
+        }
+
+        let generator = MarkdownGenerator::new()
+            .await?
+            .with_page_cache(cache)
+            .with_offline(true)
+            .with_follow_pagination(1);
+        let page = generator.url_to_page("https://example.com/story").await?;
+        assert_eq!(page.title, "Story");
+        assert!(page.content.contains("Part one."));
+        assert!(page.content.contains("Part two."));
+        assert!(!page.content.contains("Part three."));
+
+        let page = generator
+            .with_follow_pagination(5)
+            .url_to_page("https://example.com/story")
+            .await?;
+        assert!(page.content.find("Part two.") < page.content.find("Part three."));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_html_to_markdown_with_base_url() -> Result<()> {
         let generator = MarkdownGenerator::new().await?;
@@ -339,4 +1324,110 @@ mod tests {
         assert!(markdown_content.contains("# Test Page"));
         Ok(())
     }
+
+    #[test]
+    fn test_resolve_link_quoting() {
+        let base = Url::parse("https://example.com/docs/").unwrap();
+        assert_eq!(
+            resolve_links(
+                r#"<a href="a.html">A</a><a href='b.html?x=1&amp;y="2"'>B</a><a HREF=c.html>C</a>"#,
+                &base
+            ),
+            r#"<a href="https://example.com/docs/a.html">A</a><a href="https://example.com/docs/b.html?x=1&amp;y=%222%22">B</a><a href="https://example.com/docs/c.html">C</a>"#
+        );
+        assert_eq!(
+            resolve_links(r#"<img src = '../img.png' alt="x">"#, &base),
+            r#"<img src="https://example.com/img.png" alt="x">"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_resolve_links() -> Result<()> {
+        let html = r##"
+            <html>
+            <head><title>Guide</title><base href="/docs/v2/"></head>
+            <body><main>
+                <h1>Guide</h1>
+                <p>See <a href="setup.html?a=1&amp;b=2">setup</a> and <a href="#usage">usage</a>
+                or <a href="https://other.example/">elsewhere</a>.</p>
+                <img data-src="img/lazy.png" alt="Lazy">
+                <img srcset="img/small.png 1x, /img/large.png 2x" alt="Sized">
+            </main></body>
+            </html>
+        "##;
+
+        let generator = MarkdownGenerator::new().await?;
+        let page = generator.html_to_page(html, Some("https://example.com/guide"))?;
+        assert!(
+            page.content
+                .contains("[setup](https://example.com/docs/v2/setup.html?a=1")
+        );
+        assert!(page.content.contains("[usage](#usage)"));
+        assert!(page.content.contains("[elsewhere](https://other.example/)"));
+        assert!(
+            page.content
+                .contains("![Lazy](https://example.com/docs/v2/img/lazy.png)")
+        );
+        assert!(
+            page.content
+                .contains("![Sized](https://example.com/docs/v2/img/small.png)")
+        );
+
+        // Without a page URL, an absolute base still applies
+        let html = html.replace("/docs/v2/", "https://cdn.example/docs/");
+        let page = generator.html_to_page(&html, None)?;
+        assert!(
+            page.content
+                .contains("[setup](https://cdn.example/docs/setup.html")
+        );
+
+        let generator = generator.with_config(MarkdownConfig {
+            strip_links: true,
+            ..Default::default()
+        });
+        let page = generator.html_to_page(&html, None)?;
+        assert!(page.content.contains("setup"));
+        assert!(!page.content.contains("[setup]"));
+        assert!(!page.content.contains("other.example"));
+        assert!(
+            page.content
+                .contains("![Lazy](https://cdn.example/docs/img/lazy.png)")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_pages() {
+        let pages = vec![
+            MarkdownPage {
+                title: "Rust Book".to_string(),
+                url: Some("https://example.com/book".to_string()),
+                content: "# Intro\n\nText\n\n```\n# not a heading\n```\n".to_string(),
+                metadata: PageMetadata::default(),
+            },
+            MarkdownPage {
+                title: "Rust Book".to_string(),
+                url: None,
+                content: "Second".to_string(),
+                metadata: PageMetadata::default(),
+            },
+        ];
+
+        let merged = merge_pages(&pages);
+        assert!(merged.starts_with("# Contents\n\n1. [Rust Book](#rust-book) — <https://example.com/book>\n2. [Rust Book](#rust-book-1)\n"));
+        assert!(merged.contains(
+            "# Rust Book\n\n*Source: [https://example.com/book](https://example.com/book)*"
+        ));
+        assert!(merged.contains("## Intro"));
+        assert!(merged.contains("\n# not a heading\n"));
+        assert!(merged.trim_end().ends_with("Second"));
+
+        let page = MarkdownPage {
+            title: "Alpha".to_string(),
+            url: None,
+            content: "# Alpha\n\nBody".to_string(),
+            metadata: PageMetadata::default(),
+        };
+        assert!(merge_pages(&[page]).ends_with("# Alpha\n\nBody\n"));
+    }
 }
@@ -0,0 +1,139 @@
+//! Still frames of moving page content before printing
+//!
+//! A page printed mid-animation keeps whatever frame the animation was on: half-faded
+//! text, carousels between slides, spinners. Videos print as black boxes, or as the
+//! frame an autoplaying video happened to reach. Before a page is printed, finite
+//! animations are finished so elements show their final state, endless ones are
+//! rewound and paused, and every video is stopped and replaced with its poster frame
+//! and a caption linking its source.
+
+use anyhow::Result;
+use headless_chrome::Tab;
+
+/// Script settling animations and replacing videos, returning the number of videos
+/// replaced
+const STILL_FRAMES_SCRIPT: &str = r#"(() => {
+  for (const animation of document.getAnimations()) {
+    try {
+      animation.finish();
+    } catch (e) {
+      // Endless animations cannot finish; show their first frame instead
+      animation.pause();
+      animation.currentTime = 0;
+    }
+  }
+
+  const videos = Array.from(document.querySelectorAll('video'));
+  for (const video of videos) {
+    video.pause();
+    video.autoplay = false;
+
+    const source = video.currentSrc || video.src ||
+      (video.querySelector('source[src]') || {}).src || '';
+    let frame = video.poster;
+    if (!frame && video.readyState >= 2) {
+      // Without a poster, keep the current frame when the video allows reading it
+      try {
+        const canvas = document.createElement('canvas');
+        canvas.width = video.videoWidth;
+        canvas.height = video.videoHeight;
+        canvas.getContext('2d').drawImage(video, 0, 0);
+        frame = canvas.toDataURL('image/jpeg', 0.85);
+      } catch (e) {
+        frame = '';
+      }
+    }
+
+    const width = video.getBoundingClientRect().width;
+    const figure = document.createElement('figure');
+    figure.className = 'webpage-save-video';
+    figure.style.margin = '0 0 1em 0';
+    if (width) {
+      figure.style.width = width + 'px';
+      figure.style.maxWidth = '100%';
+    }
+    if (frame) {
+      const image = document.createElement('img');
+      image.src = frame;
+      image.alt = video.getAttribute('aria-label') || video.title || 'Video';
+      image.style.width = '100%';
+      image.style.height = 'auto';
+      image.style.display = 'block';
+      figure.appendChild(image);
+    }
+    const caption = document.createElement('figcaption');
+    caption.style.fontSize = '0.85em';
+    caption.style.color = '#555';
+    caption.appendChild(document.createTextNode('▶ Video: '));
+    if (source && !source.startsWith('blob:')) {
+      const link = document.createElement('a');
+      link.href = source;
+      link.textContent = source;
+      caption.appendChild(link);
+    } else {
+      caption.appendChild(document.createTextNode(video.title || 'streamed video'));
+    }
+    figure.appendChild(caption);
+    video.replaceWith(figure);
+  }
+
+  // Let posters load before the page is printed
+  const images = Array.from(document.querySelectorAll('figure.webpage-save-video img'));
+  return Promise.all(images.map((image) => image.complete ? null :
+    new Promise((resolve) => {
+      image.onload = resolve;
+      image.onerror = resolve;
+      setTimeout(resolve, 3000);
+    }))).then(() => videos.length);
+})()"#;
+
+/// Settle animations and replace videos with their poster frames in a loaded page
+///
+/// # Returns
+///
+/// Returns the number of videos replaced
+///
+/// # Errors
+///
+/// Returns an error if the script cannot be run in the page
+pub fn still_frames(tab: &Tab) -> Result<usize> {
+    let result = tab.evaluate(STILL_FRAMES_SCRIPT, true)?;
+    Ok(result.value.and_then(|v| v.as_u64()).unwrap_or(0) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session;
+
+    #[test]
+    fn test_still_frames() -> Result<()> {
+        let browser = session::launch_browser(true, None, None)?;
+        let tab = browser.new_tab()?;
+        let html = r#"<html><body>
+            <style>@keyframes fade { from { opacity: 0 } to { opacity: 1 } }</style>
+            <p id="faded" style="animation: fade 60s forwards">Text</p>
+            <video autoplay muted poster="data:image/gif;base64,R0lGODlhAQABAAAAACw=">
+              <source src="https://example.com/clip.mp4" type="video/mp4">
+            </video>
+        </body></html>"#;
+        let file = tempfile::Builder::new().suffix(".html").tempfile()?;
+        std::fs::write(file.path(), html)?;
+        let url = url::Url::from_file_path(file.path())
+            .map_err(|_| anyhow::anyhow!("Invalid temporary file path"))?;
+        tab.navigate_to(url.as_str())?.wait_until_navigated()?;
+
+        assert_eq!(still_frames(&tab)?, 1);
+        let state = tab
+            .evaluate(
+                "[document.querySelectorAll('video').length, \
+                  getComputedStyle(document.getElementById('faded')).opacity, \
+                  document.querySelector('figcaption a').href].join(' ')",
+                false,
+            )?
+            .value
+            .unwrap();
+        assert_eq!(state.as_str(), Some("0 1 https://example.com/clip.mp4"));
+        Ok(())
+    }
+}
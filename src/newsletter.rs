@@ -0,0 +1,492 @@
+//! Newsletter archives
+//!
+//! Substack, Ghost, and Buttondown newsletters publish every issue as a web page, but
+//! only list them page by page in their archives. This module finds all the posts of a
+//! newsletter through each platform's public listing, with their publish dates, so a
+//! whole newsletter can be backed up:
+//!
+//! - **Substack**: the archive API behind the site's archive page.
+//! - **Ghost**: the Content API, with the public key the site's own scripts use, or the
+//!   posts sitemap when the site has no key (dates are then last-modified dates).
+//! - **Buttondown**: the newsletter's RSS feed.
+
+use crate::readlater::{checked, http_client, send_with_retry};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::Client;
+use serde::Deserialize;
+use std::sync::LazyLock;
+use tracing::{debug, info};
+use url::Url;
+
+/// Posts requested per page of a listing
+const PAGE_SIZE: usize = 50;
+
+static GENERATOR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<meta[^>]+name=["']generator["'][^>]+content=["']Ghost"#).unwrap()
+});
+static GHOST_KEY: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"data-key=["']([0-9a-fA-F]{16,})["']"#).unwrap());
+static GHOST_API: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"data-api=["'](https?://[^"']+)["']"#).unwrap());
+static SITEMAP_URL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<url>\s*<loc>([^<]+)</loc>(?:\s*<lastmod>([^<]+)</lastmod>)?").unwrap()
+});
+static RSS_ITEM: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<item\b[^>]*>(.*?)</item>").unwrap());
+
+/// A newsletter platform
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NewsletterPlatform {
+    /// Substack, including custom domains
+    Substack,
+    /// Ghost
+    Ghost,
+    /// Buttondown
+    Buttondown,
+}
+
+impl NewsletterPlatform {
+    /// The platform of a newsletter URL, if its host tells
+    pub fn from_url(url: &Url) -> Option<Self> {
+        let host = url.host_str()?;
+        if host.ends_with(".substack.com") {
+            Some(Self::Substack)
+        } else if matches!(
+            host.trim_start_matches("www."),
+            "buttondown.com" | "buttondown.email"
+        ) {
+            Some(Self::Buttondown)
+        } else {
+            None
+        }
+    }
+
+    /// Name of the platform
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Substack => "Substack",
+            Self::Ghost => "Ghost",
+            Self::Buttondown => "Buttondown",
+        }
+    }
+
+    /// The platform a newsletter's home page is served by, if its markup tells
+    ///
+    /// Buttondown newsletters on custom domains are not recognized, since their pages
+    /// look like any other site linking to Buttondown.
+    pub fn from_html(html: &str) -> Option<Self> {
+        if GENERATOR.is_match(html) {
+            Some(Self::Ghost)
+        } else if html.contains("substackcdn.com") {
+            Some(Self::Substack)
+        } else {
+            None
+        }
+    }
+}
+
+/// A newsletter, resolved to where its posts are listed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Newsletter {
+    /// The platform serving the newsletter
+    pub platform: NewsletterPlatform,
+    /// Base URL of the newsletter, without a trailing slash
+    pub base: String,
+    /// Ghost Content API URL and key, when the site exposes them
+    content_api: Option<(String, String)>,
+}
+
+/// A post of a newsletter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewsletterPost {
+    /// URL of the post
+    pub url: String,
+    /// Title of the post
+    pub title: Option<String>,
+    /// When the post was published
+    pub published: Option<DateTime<Utc>>,
+}
+
+impl NewsletterPost {
+    /// File name for the post, without extension: its publish date and URL slug, such as
+    /// `2024-03-01-spring-update`, so files sort in publishing order
+    pub fn file_stem(&self) -> String {
+        let slug = Url::parse(&self.url)
+            .ok()
+            .and_then(|url| {
+                url.path_segments()?
+                    .filter(|segment| !segment.is_empty())
+                    .next_back()
+                    .map(slugify)
+            })
+            .filter(|slug| !slug.is_empty())
+            .unwrap_or_else(|| "post".to_string());
+        match self.published {
+            Some(published) => format!("{}-{}", published.format("%Y-%m-%d"), slug),
+            None => slug,
+        }
+    }
+}
+
+/// Client for newsletter archive listings
+pub struct NewsletterClient {
+    client: Client,
+}
+
+impl NewsletterClient {
+    /// Create a newsletter client
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+        })
+    }
+
+    /// Resolve a newsletter URL, detecting its platform unless given
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is invalid, or the platform cannot be detected
+    pub async fn resolve(
+        &self,
+        url: &str,
+        platform: Option<NewsletterPlatform>,
+    ) -> Result<Newsletter> {
+        let url = Url::parse(url)?;
+        let origin = url.origin().ascii_serialization();
+        let platform = platform.or_else(|| NewsletterPlatform::from_url(&url));
+
+        // Custom domains are recognized, and Ghost keys found, on the home page
+        let home = match platform {
+            Some(NewsletterPlatform::Substack | NewsletterPlatform::Buttondown) => None,
+            _ => {
+                let response = send_with_retry(|| self.client.get(&origin)).await?;
+                Some(
+                    checked(response, "Newsletter home page")
+                        .await?
+                        .text()
+                        .await?,
+                )
+            }
+        };
+        let platform = match platform {
+            Some(platform) => platform,
+            None => home
+                .as_deref()
+                .and_then(NewsletterPlatform::from_html)
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{} is not a recognized Substack, Ghost, or Buttondown newsletter",
+                        url
+                    )
+                })?,
+        };
+
+        let base = match (platform, NewsletterPlatform::from_url(&url)) {
+            // Buttondown hosts every newsletter under its own path
+            (NewsletterPlatform::Buttondown, Some(NewsletterPlatform::Buttondown)) => {
+                let user = url
+                    .path_segments()
+                    .and_then(|mut segments| segments.find(|s| !s.is_empty()))
+                    .ok_or_else(|| anyhow::anyhow!("Buttondown URL has no newsletter name"))?;
+                format!("{}/{}", origin, user)
+            }
+            _ => origin.clone(),
+        };
+        let content_api = match (platform, &home) {
+            (NewsletterPlatform::Ghost, Some(home)) => ghost_content_api(home, &origin),
+            _ => None,
+        };
+        debug!(
+            "Resolved {} as a {:?} newsletter at {}",
+            url, platform, base
+        );
+        Ok(Newsletter {
+            platform,
+            base,
+            content_api,
+        })
+    }
+
+    /// All posts of a newsletter, newest first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the listing cannot be fetched or parsed
+    pub async fn posts(&self, newsletter: &Newsletter) -> Result<Vec<NewsletterPost>> {
+        let mut posts = match (newsletter.platform, &newsletter.content_api) {
+            (NewsletterPlatform::Substack, _) => self.substack_posts(&newsletter.base).await?,
+            (NewsletterPlatform::Ghost, Some((api, key))) => self.ghost_posts(api, key).await?,
+            (NewsletterPlatform::Ghost, None) => {
+                info!("No Ghost Content API key found, listing posts from the sitemap");
+                let url = format!("{}/sitemap-posts.xml", newsletter.base);
+                parse_sitemap(&self.text(&url, "Ghost sitemap").await?)
+            }
+            (NewsletterPlatform::Buttondown, _) => {
+                let url = format!("{}/rss", newsletter.base);
+                parse_rss(&self.text(&url, "Buttondown feed").await?)
+            }
+        };
+        posts.sort_by(|a, b| b.published.cmp(&a.published));
+        Ok(posts)
+    }
+
+    async fn substack_posts(&self, base: &str) -> Result<Vec<NewsletterPost>> {
+        let url = format!("{}/api/v1/archive", base);
+        let limit = PAGE_SIZE.to_string();
+        let mut posts = Vec::new();
+        loop {
+            let offset = posts.len().to_string();
+            let query = [
+                ("sort", "new"),
+                ("offset", offset.as_str()),
+                ("limit", limit.as_str()),
+            ];
+            let response = send_with_retry(|| self.client.get(&url).query(&query)).await?;
+            let page: Vec<SubstackPost> =
+                checked(response, "Substack archive").await?.json().await?;
+            let received = page.len();
+            posts.extend(page.into_iter().map(|post| NewsletterPost {
+                url: post.canonical_url,
+                title: post.title,
+                published: post.post_date.as_deref().and_then(parse_time),
+            }));
+            // The server may cap the page size below the limit asked for, so only an
+            // empty page ends the archive
+            if received == 0 {
+                return Ok(posts);
+            }
+        }
+    }
+
+    async fn ghost_posts(&self, api: &str, key: &str) -> Result<Vec<NewsletterPost>> {
+        let url = format!("{}/posts/", api.trim_end_matches('/'));
+        let limit = PAGE_SIZE.to_string();
+        let mut posts = Vec::new();
+        let mut page_number = 1;
+        loop {
+            let page_param = page_number.to_string();
+            let query = [
+                ("key", key),
+                ("fields", "title,url,published_at"),
+                ("limit", limit.as_str()),
+                ("page", page_param.as_str()),
+            ];
+            let response = send_with_retry(|| self.client.get(&url).query(&query)).await?;
+            let page: GhostPage = checked(response, "Ghost Content API").await?.json().await?;
+            posts.extend(page.posts.into_iter().map(|post| NewsletterPost {
+                url: post.url,
+                title: post.title,
+                published: post.published_at.as_deref().and_then(parse_time),
+            }));
+            match page.meta.pagination.next {
+                Some(next) => page_number = next,
+                None => return Ok(posts),
+            }
+        }
+    }
+
+    async fn text(&self, url: &str, action: &str) -> Result<String> {
+        let response = send_with_retry(|| self.client.get(url)).await?;
+        Ok(checked(response, action).await?.text().await?)
+    }
+}
+
+#[derive(Deserialize)]
+struct SubstackPost {
+    canonical_url: String,
+    title: Option<String>,
+    post_date: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GhostPage {
+    posts: Vec<GhostPost>,
+    meta: GhostMeta,
+}
+
+#[derive(Deserialize)]
+struct GhostPost {
+    url: String,
+    title: Option<String>,
+    published_at: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GhostMeta {
+    pagination: GhostPagination,
+}
+
+#[derive(Deserialize)]
+struct GhostPagination {
+    next: Option<u32>,
+}
+
+/// The Content API URL and public key of a Ghost site, from the attributes of its
+/// Portal and search scripts
+fn ghost_content_api(html: &str, origin: &str) -> Option<(String, String)> {
+    let key = GHOST_KEY.captures(html)?[1].to_string();
+    let api = GHOST_API
+        .captures(html)
+        .map(|captures| captures[1].to_string())
+        .unwrap_or_else(|| format!("{}/ghost/api/content/", origin));
+    Some((api, key))
+}
+
+/// Posts of a sitemap, dated by their last modification
+fn parse_sitemap(xml: &str) -> Vec<NewsletterPost> {
+    SITEMAP_URL
+        .captures_iter(xml)
+        .map(|captures| NewsletterPost {
+            url: xml_text(&captures[1]),
+            title: None,
+            published: captures
+                .get(2)
+                .and_then(|lastmod| parse_time(lastmod.as_str().trim())),
+        })
+        .collect()
+}
+
+/// Posts of an RSS feed
+fn parse_rss(xml: &str) -> Vec<NewsletterPost> {
+    RSS_ITEM
+        .captures_iter(xml)
+        .filter_map(|captures| {
+            let item = &captures[1];
+            let url = xml_element(item, "link").or_else(|| xml_element(item, "guid"))?;
+            Some(NewsletterPost {
+                url,
+                title: xml_element(item, "title"),
+                published: xml_element(item, "pubDate").and_then(|date| {
+                    DateTime::parse_from_rfc2822(&date)
+                        .ok()
+                        .map(|date| date.with_timezone(&Utc))
+                }),
+            })
+        })
+        .collect()
+}
+
+/// The text of the first element with a name in an XML fragment
+fn xml_element(xml: &str, name: &str) -> Option<String> {
+    let start = [format!("<{}>", name), format!("<{} ", name)]
+        .iter()
+        .filter_map(|tag| xml.find(tag.as_str()))
+        .min()?;
+    let content_start = start + xml[start..].find('>')? + 1;
+    let content_end = content_start + xml[content_start..].find(&format!("</{}>", name))?;
+    Some(xml_text(&xml[content_start..content_end])).filter(|text| !text.is_empty())
+}
+
+/// Text content of XML, unwrapping CDATA and decoding the predefined entities
+fn xml_text(text: &str) -> String {
+    let text = text.trim();
+    match text
+        .strip_prefix("<![CDATA[")
+        .and_then(|text| text.strip_suffix("]]>"))
+    {
+        Some(cdata) => cdata.trim().to_string(),
+        None => text
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&"),
+    }
+}
+
+fn parse_time(time: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(time)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+/// Lowercase letters, digits, and dashes, for file names
+pub(crate) fn slugify(text: &str) -> String {
+    let mut slug = String::new();
+    for c in text.chars().flat_map(char::to_lowercase) {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            slug.push(c);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').chars().take(80).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_platform() {
+        let url = |url| Url::parse(url).unwrap();
+        assert_eq!(
+            NewsletterPlatform::from_url(&url("https://example.substack.com/archive")),
+            Some(NewsletterPlatform::Substack)
+        );
+        assert_eq!(
+            NewsletterPlatform::from_url(&url("https://buttondown.com/jane")),
+            Some(NewsletterPlatform::Buttondown)
+        );
+        assert_eq!(
+            NewsletterPlatform::from_url(&url("https://news.example.com")),
+            None
+        );
+        let ghost = r#"<meta name="generator" content="Ghost 5.82">
+            <script defer src="portal.min.js" data-ghost="https://news.example.com/"
+                data-key="22444f78447824223cefc48062" data-api="https://news.example.com/ghost/api/content/"></script>"#;
+        assert_eq!(
+            NewsletterPlatform::from_html(ghost),
+            Some(NewsletterPlatform::Ghost)
+        );
+        assert_eq!(
+            ghost_content_api(ghost, "https://news.example.com"),
+            Some((
+                "https://news.example.com/ghost/api/content/".to_string(),
+                "22444f78447824223cefc48062".to_string()
+            ))
+        );
+        assert_eq!(
+            NewsletterPlatform::from_html(r#"<link href="https://substackcdn.com/x.css">"#),
+            Some(NewsletterPlatform::Substack)
+        );
+        assert_eq!(NewsletterPlatform::from_html("<p>Blog</p>"), None);
+    }
+
+    #[test]
+    fn test_parse_listings() {
+        let rss = r#"<rss><channel><title>Jane's notes</title>
+            <item><title><![CDATA[Issue #2: Q&A]]></title>
+                <link>https://buttondown.com/jane/archive/issue-2/</link>
+                <pubDate>Tue, 05 Mar 2024 09:00:00 +0000</pubDate></item>
+            <item><title>Issue 1</title><guid>https://buttondown.com/jane/archive/issue-1/</guid></item>
+        </channel></rss>"#;
+        let posts = parse_rss(rss);
+        assert_eq!(posts.len(), 2);
+        assert_eq!(posts[0].title.as_deref(), Some("Issue #2: Q&A"));
+        assert_eq!(posts[0].file_stem(), "2024-03-05-issue-2");
+        assert_eq!(posts[1].url, "https://buttondown.com/jane/archive/issue-1/");
+        assert_eq!(posts[1].file_stem(), "issue-1");
+
+        let sitemap = r#"<urlset>
+            <url><loc>https://news.example.com/spring-update/</loc><lastmod>2024-03-01T10:00:00.000Z</lastmod></url>
+            <url><loc>https://news.example.com/a&amp;b/</loc></url>
+        </urlset>"#;
+        let posts = parse_sitemap(sitemap);
+        assert_eq!(posts[0].file_stem(), "2024-03-01-spring-update");
+        assert_eq!(posts[1].url, "https://news.example.com/a&b/");
+
+        let post = NewsletterPost {
+            url: "https://example.substack.com/p/Why-v1.2-Matters".to_string(),
+            title: None,
+            published: parse_time("2023-12-31T23:00:00-05:00"),
+        };
+        assert_eq!(post.file_stem(), "2024-01-01-why-v1-2-matters");
+    }
+}
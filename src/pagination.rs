@@ -0,0 +1,144 @@
+//! Pagination detection for multi-page articles
+//!
+//! Articles split over several pages link to their next part with `rel="next"` or with
+//! a numbered URL such as `?page=2` or `/page/2/`. [`next_page_url`] finds that link, so
+//! the generators can fetch every part and stitch them into one continuous document.
+
+use select::document::Document;
+use select::node::Node;
+use select::predicate::Name;
+use std::collections::HashSet;
+use url::Url;
+
+/// Query parameters that commonly hold the page number
+pub const PAGE_PARAMS: &[&str] = &["page", "p", "pg", "paged", "pagenum"];
+
+/// The URL of the next part of a paginated page, if it links to one
+///
+/// `rel="next"` links are preferred. Otherwise a link is only followed if its URL is the
+/// current one with the page number incremented (or set to 2 on the first page), so
+/// unrelated numbered links are ignored. Links to other hosts are never followed.
+pub fn next_page_url(html_content: &str, current: &str) -> Option<String> {
+    let current = Url::parse(current).ok()?;
+    let document = Document::from(html_content);
+    let links = |predicate: &dyn Fn(&Node) -> bool| -> Vec<Url> {
+        document
+            .find(|node: &Node| predicate(node))
+            .filter_map(|node| current.join(node.attr("href")?).ok())
+            .filter(|url| url.host_str() == current.host_str() && key(url) != key(&current))
+            .collect()
+    };
+
+    let rel_next = links(&|node| {
+        matches!(node.name(), Some("link" | "a"))
+            && node.attr("rel").is_some_and(|rel| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case("next"))
+            })
+    });
+    if let Some(url) = rel_next.into_iter().next() {
+        return Some(url.to_string());
+    }
+
+    let expected: HashSet<String> = numbered_next(&current).iter().map(key).collect();
+    links(&|node| node.is(Name("a")))
+        .into_iter()
+        .find(|url| expected.contains(&key(url)))
+        .map(|url| url.to_string())
+}
+
+/// Candidate URLs of the page after `current` under the common numbering schemes
+fn numbered_next(current: &Url) -> Vec<Url> {
+    let mut candidates = Vec::new();
+
+    // ?page=N, or ?page=2 when the first page has no number
+    let pairs: Vec<(String, String)> = current.query_pairs().into_owned().collect();
+    let numbered = pairs.iter().position(|(name, value)| {
+        PAGE_PARAMS.contains(&name.as_str()) && value.parse::<u32>().is_ok()
+    });
+    let params: Vec<(&str, u32)> = match numbered {
+        Some(index) => vec![(
+            pairs[index].0.as_str(),
+            pairs[index].1.parse::<u32>().unwrap_or(1) + 1,
+        )],
+        None => PAGE_PARAMS.iter().map(|param| (*param, 2)).collect(),
+    };
+    for (param, page) in params {
+        let mut url = current.clone();
+        url.query_pairs_mut()
+            .clear()
+            .extend_pairs(pairs.iter().filter(|(name, _)| name != param))
+            .append_pair(param, &page.to_string());
+        candidates.push(url);
+    }
+
+    // /page/N/, or /page/2/ below the first page
+    let path = current.path().trim_end_matches('/');
+    let next_path = match path.rsplit_once("/page/") {
+        Some((base, page)) => page
+            .parse::<u32>()
+            .ok()
+            .map(|page| format!("{}/page/{}", base, page + 1)),
+        None => Some(format!("{}/page/2", path)),
+    };
+    if let Some(next_path) = next_path {
+        let mut url = current.clone();
+        url.set_path(&next_path);
+        candidates.push(url);
+    }
+
+    candidates
+}
+
+/// A URL's identity for comparison, ignoring the fragment, a trailing slash, and the
+/// order of query parameters
+fn key(url: &Url) -> String {
+    let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+    pairs.sort();
+    format!(
+        "{}{}?{:?}",
+        url.host_str().unwrap_or_default(),
+        url.path().trim_end_matches('/'),
+        pairs
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rel_next() {
+        let html = r#"<head><link rel="next" href="/story?id=7&part=2"></head>
+            <body><a href="/story?id=7&page=2">2</a></body>"#;
+        assert_eq!(
+            next_page_url(html, "https://news.example/story?id=7"),
+            Some("https://news.example/story?id=7&part=2".to_string())
+        );
+        // Other hosts are never followed
+        let html = r#"<a rel="nofollow next" href="https://other.example/2">Next</a>"#;
+        assert_eq!(next_page_url(html, "https://news.example/story"), None);
+    }
+
+    #[test]
+    fn test_numbered_pages() {
+        let html = r#"<a href="?id=7&page=3">3</a><a href="/archive/2025">2025</a>"#;
+        assert_eq!(
+            next_page_url(html, "https://news.example/story?page=2&id=7"),
+            Some("https://news.example/story?id=7&page=3".to_string())
+        );
+
+        let html = r#"<a href="/post/title/page/2/#top">Next</a>"#;
+        assert_eq!(
+            next_page_url(html, "https://blog.example/post/title/"),
+            Some("https://blog.example/post/title/page/2/#top".to_string())
+        );
+
+        // Numbered links that do not follow the current page are ignored
+        let html = r#"<a href="/post/title/page/5/">5</a><a href="/other?page=2">2</a>"#;
+        assert_eq!(
+            next_page_url(html, "https://blog.example/post/title/"),
+            None
+        );
+    }
+}
@@ -0,0 +1,173 @@
+//! Page-size presets for PDFs
+//!
+//! PDFs are printed on A4 by default. E-ink readers show a PDF page scaled to fit their
+//! screen, so an A4 page ends up with unreadably small text; the e-reader presets match
+//! the page to the screen instead, with narrow margins and slightly enlarged content.
+
+/// A page-size preset
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Paper {
+    /// ISO A4, 210 × 297 mm
+    #[default]
+    A4,
+    /// ISO A5, 148 × 210 mm
+    A5,
+    /// ISO A3, 297 × 420 mm
+    A3,
+    /// US Letter, 8.5 × 11 in
+    Letter,
+    /// US Legal, 8.5 × 14 in
+    Legal,
+    /// The screen of a 6.8" Kindle Paperwhite
+    Kindle,
+    /// The screen of a 6" Kobo Clara
+    Kobo,
+    /// The screen of a 10.3" reMarkable 2
+    Remarkable,
+}
+
+impl Paper {
+    /// Page width and height in inches
+    pub fn size(self) -> (f64, f64) {
+        match self {
+            Self::A4 => (8.27, 11.7),
+            Self::A5 => (5.83, 8.27),
+            Self::A3 => (11.7, 16.54),
+            Self::Letter => (8.5, 11.0),
+            Self::Legal => (8.5, 14.0),
+            Self::Kindle => (4.12, 5.49),
+            Self::Kobo => (3.57, 4.83),
+            Self::Remarkable => (6.21, 8.28),
+        }
+    }
+
+    /// Margin on every side in inches
+    pub fn margin(self) -> f64 {
+        match self {
+            Self::A4 | Self::A3 | Self::Letter | Self::Legal => 0.4,
+            Self::A5 => 0.35,
+            Self::Kindle | Self::Kobo => 0.15,
+            Self::Remarkable => 0.25,
+        }
+    }
+
+    /// Width between the margins in CSS pixels at scale 1, in portrait or landscape
+    pub fn printable_width(self, landscape: bool) -> f64 {
+        self.printable_width_with_margin(landscape, self.margin())
+    }
+
+    /// Width between margins of the given size in CSS pixels at scale 1
+    pub fn printable_width_with_margin(self, landscape: bool, margin: f64) -> f64 {
+        let (width, height) = self.size();
+        let width = if landscape { height } else { width };
+        (width - 2.0 * margin).max(0.0) * CSS_PIXELS_PER_INCH
+    }
+
+    /// Scale of the printed content; above 1 enlarges text for small e-ink screens
+    pub fn scale(self) -> f64 {
+        match self {
+            Self::A4 | Self::A5 | Self::A3 | Self::Letter | Self::Legal | Self::Remarkable => 1.0,
+            Self::Kindle | Self::Kobo => 1.15,
+        }
+    }
+}
+
+/// Smallest scale Chrome prints at
+pub const MIN_SCALE: f64 = 0.1;
+
+/// Largest scale Chrome prints at
+pub const MAX_SCALE: f64 = 2.0;
+
+/// Smallest scale fitting content to the page width shrinks to; narrower content
+/// would be unreadable, so anything wider is still cut off
+pub const MIN_FIT_SCALE: f64 = 0.5;
+
+/// CSS pixels per inch
+const CSS_PIXELS_PER_INCH: f64 = 96.0;
+
+/// How much printed content is scaled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PrintScale {
+    /// A fixed scale, replacing the scale of the paper preset
+    Fixed(f64),
+    /// The scale of the paper preset, reduced when wide tables, code blocks, or images
+    /// would otherwise be cut off at the right margin
+    FitToWidth,
+}
+
+impl PrintScale {
+    /// The scale to print at on a paper preset, given the width of the widest content
+    /// in CSS pixels (only measured for [`PrintScale::FitToWidth`])
+    pub fn resolve(self, paper: Paper, landscape: bool, content_width: Option<f64>) -> f64 {
+        self.resolve_for_width(
+            paper.scale(),
+            paper.printable_width(landscape),
+            content_width,
+        )
+    }
+
+    /// The scale to print at given the scale of the paper preset and the printable
+    /// width, e.g. with margins other than the preset's
+    pub fn resolve_for_width(
+        self,
+        paper_scale: f64,
+        printable_width: f64,
+        content_width: Option<f64>,
+    ) -> f64 {
+        match self {
+            Self::Fixed(scale) => scale.clamp(MIN_SCALE, MAX_SCALE),
+            Self::FitToWidth => {
+                let Some(content_width) = content_width.filter(|w| *w > 0.0) else {
+                    return paper_scale;
+                };
+                (printable_width / content_width).clamp(MIN_FIT_SCALE, paper_scale)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for PrintScale {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> anyhow::Result<Self> {
+        let source = source.trim();
+        if source.eq_ignore_ascii_case("auto") {
+            return Ok(Self::FitToWidth);
+        }
+        match source.parse::<f64>() {
+            Ok(scale) if (MIN_SCALE..=MAX_SCALE).contains(&scale) => Ok(Self::Fixed(scale)),
+            _ => Err(anyhow::anyhow!(
+                "Invalid scale '{}': expected 'auto' or a number from {} to {}",
+                source,
+                MIN_SCALE,
+                MAX_SCALE
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_print_scale() -> anyhow::Result<()> {
+        assert_eq!("0.8".parse::<PrintScale>()?, PrintScale::Fixed(0.8));
+        assert_eq!("Auto".parse::<PrintScale>()?, PrintScale::FitToWidth);
+        assert!("3".parse::<PrintScale>().is_err());
+        assert!("wide".parse::<PrintScale>().is_err());
+
+        // A4 prints 717 CSS pixels wide at scale 1
+        let fit = PrintScale::FitToWidth;
+        assert_eq!(fit.resolve(Paper::A4, false, Some(600.0)), 1.0);
+        assert!((fit.resolve(Paper::A4, false, Some(1434.0)) - 0.5).abs() < 0.01);
+        assert_eq!(fit.resolve(Paper::A4, true, Some(1000.0)), 1.0);
+        assert_eq!(fit.resolve(Paper::A4, false, Some(5000.0)), MIN_FIT_SCALE);
+        assert_eq!(fit.resolve(Paper::Kindle, false, None), 1.15);
+        assert_eq!(
+            PrintScale::Fixed(0.7).resolve(Paper::Kindle, false, None),
+            0.7
+        );
+        Ok(())
+    }
+}
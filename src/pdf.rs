@@ -1,20 +1,379 @@
 //! PDF generation utilities for converting URLs and HTML to PDF format
 //!
 //! This module provides functionality to convert web pages to PDF documents
-//! using headless Chrome browser automation.
+//! using headless Chrome browser automation. Pages can also be captured as full-page
+//! screenshots, loaded and prepared the same way as for printing.
 
+use crate::atomic;
+use crate::branding::{Branding, CaptureMetadata};
+use crate::cache::PageCache;
+use crate::cookies::{BrowserCookies, Cookie};
+use crate::deterministic::DeterministicRendering;
+use crate::evidence::NetworkLog;
+use crate::extract::ExtractConfig;
+use crate::motion;
+use crate::pagination;
+use crate::paper::{Paper, PrintScale};
+use crate::pii::PiiScanner;
+use crate::printview::PrintViews;
+use crate::proxy::ProxyConfig;
+use crate::reader::ReaderMode;
+use crate::recording::HttpRecorder;
+use crate::redact::Redactor;
+use crate::request::RequestOptions;
+use crate::session;
+use crate::social::SocialThreads;
+use crate::timeouts::AdaptiveTimeouts;
+use crate::viewport::Viewport;
+use crate::wait::WaitStrategy;
+use crate::wikipedia::Wikipedia;
 use anyhow::Result;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use headless_chrome::protocol::cdp::Emulation::SetEmulatedMedia;
+use headless_chrome::protocol::cdp::Network::CookieParam;
+use headless_chrome::protocol::cdp::Page;
 use headless_chrome::types::PrintToPdfOptions;
-use headless_chrome::{Browser, LaunchOptions};
-use std::path::Path;
-use std::time::Duration;
+use headless_chrome::{Browser, Tab};
+use regex::Regex;
+use select::document::Document;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
+use std::time::{Duration, Instant};
 use tempfile::NamedTempFile;
 use tokio::fs;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{info, warn};
 use url::Url;
 
+/// Default delay after navigation, giving dynamic content time to render
+pub const DEFAULT_WAIT: Duration = Duration::from_secs(2);
+
+/// Default number of pages a [`PdfGeneratorPool`] converts at a time
+pub const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Share of the page height wide tables and code blocks must take for the page to be
+/// printed in landscape
+const LANDSCAPE_WIDE_SHARE: f64 = 0.3;
+
+/// Quality of JPEG and WebP screenshots, from 0 to 100
+const SCREENSHOT_QUALITY: u32 = 90;
+
+/// Tallest screenshot in CSS pixels; Chrome cannot capture taller surfaces in one image
+const MAX_SCREENSHOT_HEIGHT: f64 = 16384.0;
+
+/// Opening `<head>` and `<html>` tags, after which a `<base>` element is inserted
+static HEAD_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<head(\s[^>]*)?>").expect("valid regex"));
+static HTML_TAG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<html(\s[^>]*)?>").expect("valid regex"));
+
+/// CSS media type pages are laid out for when printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Media {
+    /// Screen stylesheets, so the PDF looks like the page in a browser window
+    Screen,
+    /// Print stylesheets, which some sites use to hide navigation and ads and others
+    /// to hide content
+    Print,
+}
+
+impl Media {
+    /// The CSS media type name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Media::Screen => "screen",
+            Media::Print => "print",
+        }
+    }
+}
+
+/// Image format of full-page screenshots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ScreenshotFormat {
+    /// Lossless, keeping text sharp
+    #[default]
+    Png,
+    /// Lossy and smaller, for pages with many photos
+    Jpeg,
+    /// Lossy and smallest, though not every viewer opens it
+    Webp,
+}
+
+impl ScreenshotFormat {
+    /// The file extension of screenshots in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ScreenshotFormat::Png => "png",
+            ScreenshotFormat::Jpeg => "jpg",
+            ScreenshotFormat::Webp => "webp",
+        }
+    }
+
+    /// The format for the DevTools screenshot command
+    fn capture_format(&self) -> Page::CaptureScreenshotFormatOption {
+        match self {
+            ScreenshotFormat::Png => Page::CaptureScreenshotFormatOption::Png,
+            ScreenshotFormat::Jpeg => Page::CaptureScreenshotFormatOption::Jpeg,
+            ScreenshotFormat::Webp => Page::CaptureScreenshotFormatOption::Webp,
+        }
+    }
+
+    /// The encoding quality, for lossy formats
+    fn quality(&self) -> Option<u32> {
+        match self {
+            ScreenshotFormat::Png => None,
+            ScreenshotFormat::Jpeg | ScreenshotFormat::Webp => Some(SCREENSHOT_QUALITY),
+        }
+    }
+}
+
+impl std::fmt::Display for ScreenshotFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScreenshotFormat::Png => write!(f, "PNG"),
+            ScreenshotFormat::Jpeg => write!(f, "JPEG"),
+            ScreenshotFormat::Webp => write!(f, "WebP"),
+        }
+    }
+}
+
+/// What a loaded page is captured as
+#[derive(Debug, Clone, Copy)]
+enum Capture {
+    Pdf,
+    Screenshot(ScreenshotFormat),
+}
+
+/// Page layout of generated PDFs
+#[derive(Debug, Clone, PartialEq)]
+pub struct PdfConfig {
+    /// Page size preset, which also sets the default margins and scale
+    pub paper: Paper,
+    /// Print every page in landscape
+    pub landscape: bool,
+    /// Print pages mostly taken up by tables or code blocks wider than the page in
+    /// landscape
+    pub auto_landscape: bool,
+    /// Margin on every side in inches, instead of the paper preset's
+    pub margins: Option<f64>,
+    /// Scale of the printed content, instead of the paper preset's
+    pub scale: Option<PrintScale>,
+    /// HTML template of the page header, with Chrome's `date`, `title`, `url`,
+    /// `pageNumber`, and `totalPages` classes; branding replaces it
+    pub header_template: Option<String>,
+    /// HTML template of the page footer
+    pub footer_template: Option<String>,
+    /// Pages to print, such as `1-5,8`, or None for all of them
+    pub page_ranges: Option<String>,
+    /// Print background colors and images
+    pub print_background: bool,
+}
+
+impl Default for PdfConfig {
+    fn default() -> Self {
+        Self {
+            paper: Paper::default(),
+            landscape: false,
+            auto_landscape: false,
+            margins: None,
+            scale: None,
+            header_template: None,
+            footer_template: None,
+            page_ranges: None,
+            print_background: true,
+        }
+    }
+}
+
+impl PdfConfig {
+    /// Create a layout of A4 pages in portrait with backgrounds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Print on the page size, margins, and scale of a paper preset
+    pub fn with_paper(mut self, paper: Paper) -> Self {
+        self.paper = paper;
+        self
+    }
+
+    /// Print every page in landscape
+    pub fn with_landscape(mut self, landscape: bool) -> Self {
+        self.landscape = landscape;
+        self
+    }
+
+    /// Print pages mostly taken up by wide tables or code blocks in landscape
+    pub fn with_auto_landscape(mut self, auto_landscape: bool) -> Self {
+        self.auto_landscape = auto_landscape;
+        self
+    }
+
+    /// Use margins of this many inches on every side
+    pub fn with_margins(mut self, inches: f64) -> Self {
+        self.margins = Some(inches.max(0.0));
+        self
+    }
+
+    /// Print at a fixed scale, or shrink content that is wider than the page
+    pub fn with_scale(mut self, scale: PrintScale) -> Self {
+        self.scale = Some(scale);
+        self
+    }
+
+    /// Print a header on every page from an HTML template
+    pub fn with_header_template(mut self, template: impl Into<String>) -> Self {
+        self.header_template = Some(template.into());
+        self
+    }
+
+    /// Print a footer on every page from an HTML template
+    pub fn with_footer_template(mut self, template: impl Into<String>) -> Self {
+        self.footer_template = Some(template.into());
+        self
+    }
+
+    /// Print only some pages, such as `1-5,8`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page ranges are invalid (see [`parse_page_ranges`])
+    pub fn with_page_ranges(mut self, ranges: &str) -> Result<Self> {
+        self.page_ranges = Some(parse_page_ranges(ranges)?);
+        Ok(self)
+    }
+
+    /// Print background colors and images, or leave them out to save ink
+    pub fn with_print_background(mut self, print_background: bool) -> Self {
+        self.print_background = print_background;
+        self
+    }
+
+    /// Margin on every side in inches
+    pub fn margin(&self) -> f64 {
+        self.margins.unwrap_or_else(|| self.paper.margin())
+    }
+
+    /// Width between the margins in CSS pixels at scale 1, in portrait or landscape
+    pub fn printable_width(&self, landscape: bool) -> f64 {
+        self.paper
+            .printable_width_with_margin(landscape, self.margin())
+    }
+
+    /// Chrome's print options for a page printed in portrait or landscape at a scale,
+    /// with the header rendered by branding if any
+    fn print_options(
+        &self,
+        landscape: bool,
+        scale: f64,
+        branded_header: Option<String>,
+        prefer_css_page_size: bool,
+    ) -> PrintToPdfOptions {
+        let (paper_width, paper_height) = self.paper.size();
+        let margin = self.margin();
+        let mut header_template = branded_header.or_else(|| self.header_template.clone());
+        let mut footer_template = self.footer_template.clone();
+        let header_footer = header_template.is_some() || footer_template.is_some();
+        // An empty template keeps Chrome's default date/URL header or footer off the page
+        if header_footer {
+            header_template.get_or_insert_with(|| "<span></span>".to_string());
+            footer_template.get_or_insert_with(|| "<span></span>".to_string());
+        }
+        PrintToPdfOptions {
+            landscape: Some(landscape),
+            display_header_footer: Some(header_footer),
+            print_background: Some(self.print_background),
+            scale: Some(scale),
+            paper_width: Some(paper_width),
+            paper_height: Some(paper_height),
+            margin_top: Some(margin),
+            margin_bottom: Some(margin),
+            margin_left: Some(margin),
+            margin_right: Some(margin),
+            page_ranges: self.page_ranges.clone(),
+            ignore_invalid_page_ranges: Some(false),
+            footer_template,
+            header_template,
+            prefer_css_page_size: Some(prefer_css_page_size),
+            transfer_mode: None,
+            generate_document_outline: Some(false),
+            generate_tagged_pdf: Some(false),
+        }
+    }
+}
+
+/// Validate page ranges such as `1-5, 8, 10-`, returning them without spaces
+///
+/// # Errors
+///
+/// Returns an error if a range is not a page number, or two page numbers separated by
+/// a dash in ascending order (either may be omitted for the first or last page)
+pub fn parse_page_ranges(source: &str) -> Result<String> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid page ranges '{}': expected pages such as 1-5,8,10-",
+            source
+        )
+    };
+    let page = |number: &str| -> Result<Option<u32>> {
+        match number.trim() {
+            "" => Ok(None),
+            number => match number.parse::<u32>() {
+                Ok(page) if page > 0 => Ok(Some(page)),
+                _ => Err(invalid()),
+            },
+        }
+    };
+    let mut ranges = Vec::new();
+    for range in source.split(',') {
+        let range = range.trim();
+        let (first, last) = match range.split_once('-') {
+            Some((first, last)) => (page(first)?, page(last)?),
+            None => {
+                let page = page(range)?.ok_or_else(invalid)?;
+                (Some(page), Some(page))
+            }
+        };
+        match (first, last) {
+            (Some(first), Some(last)) if first > last => return Err(invalid()),
+            (None, None) => return Err(invalid()),
+            _ => {}
+        }
+        ranges.push(range.split_whitespace().collect::<String>());
+    }
+    Ok(ranges.join(","))
+}
+
 /// PDF generator that uses headless Chrome to convert URLs and HTML to PDF
+///
+/// Clones share the browser, each conversion opening its own tab.
+#[derive(Clone)]
 pub struct PdfGenerator {
     browser: Browser,
+    wait: WaitStrategy,
+    page_cache: Option<PageCache>,
+    offline: bool,
+    branding: Option<Branding>,
+    timeouts: Option<AdaptiveTimeouts>,
+    follow_pagination: usize,
+    print_views: Option<PrintViews>,
+    social_threads: Option<SocialThreads>,
+    wikipedia: Option<Wikipedia>,
+    cookies: Option<BrowserCookies>,
+    request_options: Option<RequestOptions>,
+    proxy: Option<ProxyConfig>,
+    recorder: Option<HttpRecorder>,
+    network_log: Option<NetworkLog>,
+    deterministic: Option<DeterministicRendering>,
+    media: Option<Media>,
+    viewport: Option<Viewport>,
+    reader: Option<ReaderMode>,
+    config: PdfConfig,
+    redactor: Option<Redactor>,
+    pii_scanner: Option<PiiScanner>,
 }
 
 impl PdfGenerator {
@@ -24,15 +383,246 @@ impl PdfGenerator {
     ///
     /// Returns an error if the browser cannot be launched
     pub async fn new() -> Result<Self> {
-        let browser = Browser::new(
-            LaunchOptions::default_builder()
-                .headless(true)
-                .sandbox(false)
-                .build()
-                .map_err(|e| anyhow::anyhow!("Failed to build launch options: {}", e))?,
-        )?;
+        Ok(Self::from_browser(session::launch_browser(
+            true, None, None,
+        )?))
+    }
+
+    /// Create a PDF generator whose browser uses a persistent profile directory
+    ///
+    /// Pages are loaded with the cookies of the profile, so sites logged into with
+    /// [`session::LoginWindow`] are captured as the logged-in user.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the browser cannot be launched, e.g. because another Chrome
+    /// is already running on the profile
+    pub async fn new_with_profile(profile_dir: &Path) -> Result<Self> {
+        Ok(Self::from_browser(session::launch_browser(
+            true,
+            Some(profile_dir),
+            None,
+        )?))
+    }
+
+    /// Create a PDF generator whose browser loads pages through a proxy, optionally on a
+    /// persistent profile directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if Chrome cannot use the proxy or the browser cannot be launched
+    pub async fn new_with_proxy(profile_dir: Option<&Path>, proxy: ProxyConfig) -> Result<Self> {
+        let mut generator =
+            Self::from_browser(session::launch_browser(true, profile_dir, Some(&proxy))?);
+        generator.proxy = Some(proxy);
+        Ok(generator)
+    }
+
+    /// Create a PDF generator around a launched browser
+    fn from_browser(browser: Browser) -> Self {
+        Self {
+            browser,
+            wait: WaitStrategy::Delay(DEFAULT_WAIT),
+            page_cache: None,
+            offline: false,
+            branding: None,
+            timeouts: None,
+            follow_pagination: 0,
+            print_views: None,
+            social_threads: None,
+            wikipedia: None,
+            cookies: None,
+            request_options: None,
+            proxy: None,
+            recorder: None,
+            network_log: None,
+            deterministic: None,
+            media: None,
+            viewport: None,
+            reader: None,
+            config: PdfConfig::default(),
+            redactor: None,
+            pii_scanner: None,
+        }
+    }
+
+    /// The browser pages are loaded in, e.g. to share its profile with other captures
+    pub fn browser(&self) -> &Browser {
+        &self.browser
+    }
+
+    /// Save a snapshot of the rendered HTML of every HTTP(S) page in the given cache
+    pub fn with_page_cache(mut self, cache: PageCache) -> Self {
+        self.page_cache = Some(cache);
+        self
+    }
+
+    /// Render HTTP(S) URLs only from cached snapshots, never loading them from the network
+    ///
+    /// URLs that are not in the page cache fail. Requires a page cache (see `with_page_cache`).
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Add a cover page and/or page header to every generated PDF
+    pub fn with_branding(mut self, branding: Branding) -> Self {
+        self.branding = Some(branding);
+        self
+    }
 
-        Ok(Self { browser })
+    /// Load HTTP(S) pages with per-domain timeouts that grow for slow domains
+    ///
+    /// A page that times out is loaded again if its domain's timeout was extended.
+    /// Without this, Chrome's default timeout applies to every page.
+    pub fn with_adaptive_timeouts(mut self, timeouts: AdaptiveTimeouts) -> Self {
+        self.timeouts = Some(timeouts);
+        self
+    }
+
+    /// Follow "next page" links of paginated HTTP(S) articles for up to this many further
+    /// pages, appending the main content of each to the first page before printing
+    pub fn with_follow_pagination(mut self, max_pages: usize) -> Self {
+        self.follow_pagination = max_pages;
+        self
+    }
+
+    /// Load the print view of HTTP(S) pages when one is available
+    pub fn with_print_views(mut self, print_views: PrintViews) -> Self {
+        self.print_views = Some(print_views);
+        self
+    }
+
+    /// Capture Twitter/X and Mastodon posts through the platform APIs, printing the
+    /// thread instead of the page
+    pub fn with_social_threads(mut self, social_threads: SocialThreads) -> Self {
+        self.social_threads = Some(social_threads);
+        self
+    }
+
+    /// Export Wikipedia articles through the REST API, printing the article with its
+    /// references and image credits instead of the page
+    pub fn with_wikipedia(mut self, wikipedia: Wikipedia) -> Self {
+        self.wikipedia = Some(wikipedia);
+        self
+    }
+
+    /// Load remote pages with the cookies imported from a browser that apply to them
+    pub fn with_cookies(mut self, cookies: BrowserCookies) -> Self {
+        self.cookies = Some(cookies);
+        self
+    }
+
+    /// Send extra headers, such as `Authorization`, with every request of remote pages
+    pub fn with_request_options(mut self, options: RequestOptions) -> Self {
+        self.request_options = Some(options);
+        self
+    }
+
+    /// Record every response received while loading remote pages, or answer their
+    /// requests from a recording instead of the network
+    pub fn with_recorder(mut self, recorder: HttpRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Log how every response was received while loading remote pages: the address,
+    /// protocol, headers, and TLS certificate
+    pub fn with_network_log(mut self, log: NetworkLog) -> Self {
+        self.network_log = Some(log);
+        self
+    }
+
+    /// Render pages on a frozen clock, with seeded randomness and without animations
+    /// or dynamic widgets, and date the PDFs with the frozen time, so repeated captures
+    /// of the same content give identical PDFs
+    pub fn with_deterministic(mut self, deterministic: DeterministicRendering) -> Self {
+        self.deterministic = Some(deterministic);
+        self
+    }
+
+    /// Lay pages out for this CSS media type when printing them, instead of Chrome's
+    /// default of print stylesheets
+    pub fn with_media(mut self, media: Media) -> Self {
+        self.media = Some(media);
+        self
+    }
+
+    /// Render pages in a viewport of this size instead of Chrome's default window size
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    /// Render the extracted main content of pages in a reader template instead of
+    /// printing the pages as they are laid out
+    pub fn with_reader(mut self, reader: ReaderMode) -> Self {
+        self.reader = Some(reader);
+        self
+    }
+
+    /// Print on the page size, margins, and scale of a paper preset instead of A4
+    pub fn with_paper(mut self, paper: Paper) -> Self {
+        self.config.paper = paper;
+        self
+    }
+
+    /// Lay out PDFs with a page size, orientation, margins, scale, header and footer,
+    /// page ranges, and backgrounds, replacing any paper, scale, or landscape set before
+    pub fn with_config(mut self, config: PdfConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// The page layout of generated PDFs
+    pub fn config(&self) -> &PdfConfig {
+        &self.config
+    }
+
+    /// Black out the text matched by a redactor before printing pages
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = Some(redactor);
+        self
+    }
+
+    /// Scan the text of every loaded page for personal data
+    pub fn with_pii_scanner(mut self, scanner: PiiScanner) -> Self {
+        self.pii_scanner = Some(scanner);
+        self
+    }
+
+    /// Print pages mostly taken up by tables or code blocks wider than the page in
+    /// landscape, instead of warning about them
+    pub fn with_auto_landscape(mut self, auto_landscape: bool) -> Self {
+        self.config.auto_landscape = auto_landscape;
+        self
+    }
+
+    /// Print at a fixed scale, or shrink content that is wider than the page, instead
+    /// of the scale of the paper preset
+    pub fn with_scale(mut self, scale: PrintScale) -> Self {
+        self.config.scale = Some(scale);
+        self
+    }
+
+    /// Set the delay applied after navigation before the PDF is printed
+    ///
+    /// A zero duration disables the extra wait entirely.
+    pub fn with_wait(mut self, wait: Duration) -> Self {
+        self.wait = WaitStrategy::Delay(wait);
+        self
+    }
+
+    /// Wait for a selector or for the network to go idle after navigation, instead of
+    /// a fixed delay
+    pub fn with_wait_strategy(mut self, wait: WaitStrategy) -> Self {
+        self.wait = wait;
+        self
+    }
+
+    /// When a loaded page is printed
+    pub fn wait(&self) -> &WaitStrategy {
+        &self.wait
     }
 
     /// Convert a URL to PDF
@@ -54,6 +644,46 @@ impl PdfGenerator {
     /// - PDF generation fails
     /// - File I/O operations fail
     pub async fn url_to_pdf(&self, url: &str, output_path: Option<&Path>) -> Result<Vec<u8>> {
+        self.save_url(url, Capture::Pdf, output_path).await
+    }
+
+    /// Capture a full-page screenshot of a URL
+    ///
+    /// The page is loaded and prepared as for [`PdfGenerator::url_to_pdf`], including
+    /// snapshots, branding covers, and redactions, and captured beyond the viewport down
+    /// to the end of the page.
+    ///
+    /// # Arguments
+    ///
+    /// * `url` - The URL to capture
+    /// * `format` - The image format
+    /// * `output_path` - Optional output file path. If None, returns the image without saving
+    ///
+    /// # Returns
+    ///
+    /// Returns the image data as bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be loaded, the screenshot fails, or the file
+    /// cannot be written
+    pub async fn url_to_screenshot(
+        &self,
+        url: &str,
+        format: ScreenshotFormat,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        self.save_url(url, Capture::Screenshot(format), output_path)
+            .await
+    }
+
+    /// Capture a URL as a PDF or screenshot, and save it if an output path is provided
+    async fn save_url(
+        &self,
+        url: &str,
+        capture: Capture,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
         // Validate URL
         let parsed_url = Url::parse(url)?;
         if !matches!(parsed_url.scheme(), "http" | "https" | "file") {
@@ -62,56 +692,160 @@ impl PdfGenerator {
             ));
         }
 
-        // Create new tab
-        let tab = self.browser.new_tab()?;
+        let is_remote = matches!(parsed_url.scheme(), "http" | "https");
+        let captured = match is_remote && !self.offline {
+            true => self.capture(url).await?,
+            false => None,
+        };
+        let pdf_data = if let Some(html_content) = captured {
+            // The captured document is cached as the page snapshot for offline re-rendering
+            if let Some(cache) = &self.page_cache {
+                if let Err(e) = cache.put(url, &html_content).await {
+                    warn!("Failed to save page snapshot for {}: {}", url, e);
+                }
+            }
+            self.render_html(&html_content, Some(url), capture).await?
+        } else if self.offline && is_remote {
+            let cache = self
+                .page_cache
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Offline mode requires the page cache"))?;
+            let html_content = cache.require(url).await?;
+            match &self.reader {
+                Some(reader) => {
+                    let html_content = reader.render(&[html_content], Some(url), Some(url))?;
+                    self.render_html(&html_content, Some(url), capture).await?
+                }
+                None => self.render_html(&html_content, Some(url), capture).await?,
+            }
+        } else {
+            let target = match (&self.print_views, is_remote) {
+                (Some(print_views), true) => print_views.resolve(url).await,
+                _ => url.to_string(),
+            };
+            match &self.reader {
+                Some(reader) => {
+                    self.render_reader(reader, &target, is_remote, url, capture)
+                        .await?
+                }
+                None => {
+                    self.render_url(&target, is_remote, Some(url), capture)
+                        .await?
+                }
+            }
+        };
 
-        // Navigate to URL
-        tab.navigate_to(url)?;
+        // Save to file if output path is provided
+        if let Some(path) = output_path {
+            atomic::write(path, &pdf_data).await?;
+        }
 
-        // Wait for page to load
-        tab.wait_until_navigated()?;
+        Ok(pdf_data)
+    }
 
-        // Wait a bit more for dynamic content to load
-        tokio::time::sleep(Duration::from_millis(2000)).await;
+    /// The document of a social thread or Wikipedia article read through the platform's
+    /// API, or None if the URL is neither or cannot be read that way
+    async fn capture(&self, url: &str) -> Result<Option<String>> {
+        if let Some(social_threads) = &self.social_threads {
+            if let Some(thread) = social_threads.capture(url).await {
+                return Ok(Some(thread.to_html()?));
+            }
+        }
+        if let Some(wikipedia) = &self.wikipedia {
+            if let Some(article) = wikipedia.capture(url).await {
+                return Ok(Some(article.to_html()?));
+            }
+        }
+        Ok(None)
+    }
 
-        // Configure PDF options
-        let pdf_options = PrintToPdfOptions {
-            landscape: Some(false),
-            display_header_footer: Some(false),
-            print_background: Some(true),
-            scale: Some(1.0),
-            paper_width: Some(8.27),  // A4 width in inches
-            paper_height: Some(11.7), // A4 height in inches
-            margin_top: Some(0.4),
-            margin_bottom: Some(0.4),
-            margin_left: Some(0.4),
-            margin_right: Some(0.4),
-            page_ranges: None,
-            ignore_invalid_page_ranges: Some(false),
-            header_template: None,
-            footer_template: None,
-            prefer_css_page_size: Some(false),
-            transfer_mode: None,
-            generate_document_outline: Some(false),
-            generate_tagged_pdf: Some(false),
-        };
+    /// Convert HTML content to PDF
+    ///
+    /// # Arguments
+    ///
+    /// * `html_content` - The HTML content to convert to PDF
+    /// * `output_path` - Optional output file path. If None, returns PDF data without saving
+    ///
+    /// # Returns
+    ///
+    /// Returns the PDF data as bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// - The browser fails to load the HTML content
+    /// - PDF generation fails
+    /// - File I/O operations fail
+    pub async fn html_to_pdf(
+        &self,
+        html_content: &str,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        self.save_html(html_content, Capture::Pdf, output_path)
+            .await
+    }
 
-        // Generate PDF
-        let pdf_data = tab.print_to_pdf(Some(pdf_options))?;
+    /// Capture a full-page screenshot of HTML content
+    ///
+    /// # Arguments
+    ///
+    /// * `html_content` - The HTML content to capture
+    /// * `format` - The image format
+    /// * `output_path` - Optional output file path. If None, returns the image without saving
+    ///
+    /// # Returns
+    ///
+    /// Returns the image data as bytes
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the browser fails to load the HTML content, the screenshot
+    /// fails, or the file cannot be written
+    pub async fn html_to_screenshot(
+        &self,
+        html_content: &str,
+        format: ScreenshotFormat,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        self.save_html(html_content, Capture::Screenshot(format), output_path)
+            .await
+    }
+
+    /// Capture HTML content as a PDF or screenshot, and save it if an output path is
+    /// provided
+    async fn save_html(
+        &self,
+        html_content: &str,
+        capture: Capture,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        let pdf_data = match &self.reader {
+            Some(reader) => {
+                let html_content = reader.render(&[html_content.to_string()], None, None)?;
+                self.render_html(&html_content, None, capture).await?
+            }
+            None => self.render_html(html_content, None, capture).await?,
+        };
 
         // Save to file if output path is provided
         if let Some(path) = output_path {
-            fs::write(path, &pdf_data).await?;
+            atomic::write(path, &pdf_data).await?;
         }
 
         Ok(pdf_data)
     }
 
-    /// Convert HTML content to PDF
+    /// Convert HTML content to PDF, resolving relative references against a base URL
+    ///
+    /// `html_to_pdf` loads the HTML from a temporary file, so relative image, stylesheet,
+    /// and script references would otherwise resolve against the temporary directory.
+    /// A `<base href>` pointing at `base_url` is inserted unless the document already has
+    /// one. For HTML read from disk, pass the `file://` URL of the original file.
     ///
     /// # Arguments
     ///
     /// * `html_content` - The HTML content to convert to PDF
+    /// * `base_url` - The URL relative references are resolved against
     /// * `output_path` - Optional output file path. If None, returns PDF data without saving
     ///
     /// # Returns
@@ -121,13 +855,29 @@ impl PdfGenerator {
     /// # Errors
     ///
     /// Returns an error if:
+    /// - The base URL is invalid
     /// - The browser fails to load the HTML content
     /// - PDF generation fails
     /// - File I/O operations fail
-    pub async fn html_to_pdf(
+    pub async fn html_to_pdf_with_base(
         &self,
         html_content: &str,
+        base_url: &str,
         output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        let base_url = Url::parse(base_url)?;
+        let html_content = insert_base_href(html_content, base_url.as_str());
+        self.html_to_pdf(&html_content, output_path).await
+    }
+
+    /// Render HTML content to PDF or image bytes through a temporary file
+    ///
+    /// `source_url` is the page the HTML came from, shown in branding templates.
+    async fn render_html(
+        &self,
+        html_content: &str,
+        source_url: Option<&str>,
+        capture: Capture,
     ) -> Result<Vec<u8>> {
         // Create a temporary HTML file
         let temp_file = NamedTempFile::new()?;
@@ -136,7 +886,613 @@ impl PdfGenerator {
 
         // Convert file URL to PDF
         let file_url = format!("file://{}", temp_path.display());
-        self.url_to_pdf(&file_url, output_path).await
+        self.render_url(&file_url, false, source_url, capture).await
+    }
+
+    /// Load a page and its following parts, and print or capture their main content in
+    /// the reader template
+    ///
+    /// The page's rendered HTML is saved in the page cache under `source_url`.
+    async fn render_reader(
+        &self,
+        reader: &ReaderMode,
+        url: &str,
+        remote: bool,
+        source_url: &str,
+        capture: Capture,
+    ) -> Result<Vec<u8>> {
+        let html_content = self.load_content(url, remote).await?;
+        if let (Some(cache), true) = (&self.page_cache, remote) {
+            if let Err(e) = cache.put(source_url, &html_content).await {
+                warn!("Failed to save page snapshot for {}: {}", source_url, e);
+            }
+        }
+
+        let mut parts = match remote {
+            true => self.load_following_parts(&html_content, url).await,
+            false => Vec::new(),
+        };
+        parts.insert(0, html_content);
+        let reader_html = reader.render(&parts, Some(url), Some(source_url))?;
+        self.render_html(&reader_html, Some(source_url), capture)
+            .await
+    }
+
+    /// Load a URL in a new tab and print it to PDF bytes, or capture it as an image
+    ///
+    /// When `snapshot` is set and a page cache is configured, the rendered HTML is saved
+    /// so the page can be re-rendered offline later. `source_url` overrides the URL shown
+    /// in branding templates and the URL the snapshot is saved under.
+    async fn render_url(
+        &self,
+        url: &str,
+        snapshot: bool,
+        source_url: Option<&str>,
+        capture: Capture,
+    ) -> Result<Vec<u8>> {
+        // Create new tab and load the URL; it is closed however rendering ends
+        let tab = OpenTab(self.browser.new_tab()?);
+        if let Some(media) = self.media {
+            tab.call_method(SetEmulatedMedia {
+                media: Some(media.as_str().to_string()),
+                features: None,
+            })?;
+        }
+        let watch = self.wait.watch(&tab)?;
+        self.navigate(&tab, url, snapshot)?;
+
+        // Wait for dynamic content to load
+        watch.wait().await;
+        drop(watch);
+
+        // Keep a snapshot of the rendered page for offline re-rendering
+        if let (Some(cache), true) = (&self.page_cache, snapshot) {
+            match tab.get_content() {
+                Ok(html_content) => {
+                    if let Err(e) = cache.put(source_url.unwrap_or(url), &html_content).await {
+                        warn!("Failed to save page snapshot for {}: {}", url, e);
+                    }
+                }
+                Err(e) => warn!("Failed to read rendered HTML for {}: {}", url, e),
+            }
+        }
+
+        // Stitch the following parts of paginated articles after snapshotting as well
+        if snapshot && self.follow_pagination > 0 {
+            self.stitch_pages(&tab, url).await?;
+        }
+
+        // Print still frames instead of mid-animation states and black video boxes,
+        // leaving cached snapshots with the videos
+        match motion::still_frames(&tab) {
+            Ok(0) => {}
+            Ok(videos) => info!("Replaced {} video(s) with their poster frames", videos),
+            Err(e) => warn!("Failed to settle animations and videos for {}: {}", url, e),
+        }
+
+        // Scan the content of captured pages before branding is added
+        if let (Some(scanner), Some(source_url)) = (&self.pii_scanner, source_url) {
+            match tab.evaluate("document.body ? document.body.innerText : ''", false) {
+                Ok(text) => {
+                    let text = text.value.as_ref().and_then(|v| v.as_str()).unwrap_or("");
+                    scanner.scan(source_url, text);
+                }
+                Err(e) => warn!("Failed to scan {} for personal data: {}", url, e),
+            }
+        }
+
+        // Prepend the cover page and render the page header after snapshotting,
+        // so cached snapshots stay unbranded
+        let mut header_template = None;
+        if let Some(branding) = &self.branding {
+            let mut capture = CaptureMetadata::new(
+                source_url.unwrap_or(url),
+                tab.get_title().unwrap_or_default(),
+            );
+            if let Some(deterministic) = &self.deterministic {
+                capture.captured_at = deterministic.time();
+            }
+            if let Some(cover) = branding.render_cover(&capture)? {
+                tab.evaluate(
+                    &format!(
+                        "document.body.insertAdjacentHTML('afterbegin', {})",
+                        serde_json::to_string(&cover)?
+                    ),
+                    false,
+                )?;
+            }
+            header_template = branding.render_header(&capture)?;
+        }
+
+        // Burn redactions in last, so they also cover the cover page
+        if let Some(redactor) = &self.redactor {
+            let redacted = redactor.burn_in(&tab)?;
+            if redacted > 0 {
+                info!("Redacted {} match(es) in {}", redacted, url);
+            }
+        }
+
+        if let Capture::Screenshot(format) = capture {
+            return capture_full_page(&tab, format, url);
+        }
+
+        // Print pages dominated by wide tables or code in landscape, and shrink wide
+        // content to fit between the margins
+        let config = &self.config;
+        let paper_scale = config.paper.scale();
+        let layout = measure_content(&tab, config.printable_width(config.landscape) / paper_scale)
+            .inspect_err(|e| warn!("Failed to measure content width of {}: {}", url, e))
+            .ok();
+        let wide = layout.is_some_and(|layout| layout.wide_share >= LANDSCAPE_WIDE_SHARE);
+        let landscape = config.landscape || (wide && config.auto_landscape);
+        if landscape && !config.landscape {
+            info!("Printing {} in landscape for its wide tables or code", url);
+        } else if wide && !landscape && config.scale != Some(PrintScale::FitToWidth) {
+            warn!(
+                "{} is mostly tables or code wider than the page, which will be cut off; \
+                 landscape or a smaller scale would fit more",
+                url
+            );
+        }
+        let printable_width = config.printable_width(landscape);
+        let scale = match config.scale {
+            Some(PrintScale::FitToWidth) => {
+                let widest = layout.map(|layout| layout.widest);
+                let scale =
+                    PrintScale::FitToWidth.resolve_for_width(paper_scale, printable_width, widest);
+                if scale < paper_scale {
+                    info!("Printing {} at scale {:.2} to fit its width", url, scale);
+                }
+                scale
+            }
+            Some(scale) => scale.resolve_for_width(paper_scale, printable_width, None),
+            None => paper_scale,
+        };
+
+        // Reader templates may set their own page size (see `Typography::page_size`)
+        let pdf_options =
+            config.print_options(landscape, scale, header_template, self.reader.is_some());
+
+        // Generate PDF
+        let mut pdf = tab.print_to_pdf(Some(pdf_options))?;
+        if let Some(deterministic) = &self.deterministic {
+            deterministic.normalize_pdf(&mut pdf);
+        }
+        Ok(pdf)
+    }
+
+    /// Append the main content of up to `follow_pagination` following pages to the page
+    /// loaded in a tab
+    async fn stitch_pages(&self, tab: &Tab, url: &str) -> Result<()> {
+        let extraction = ExtractConfig::new();
+        for part in self.load_following_parts(&tab.get_content()?, url).await {
+            let document = Document::from(part.as_str());
+            let Some(main) = extraction.find_main(&document) else {
+                continue;
+            };
+            tab.evaluate(
+                &format!(
+                    "(document.querySelector('main, article') || document.body)\
+                     .insertAdjacentHTML('beforeend', {})",
+                    serde_json::to_string(&extraction.html(&main))?
+                ),
+                false,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Load the rendered HTML of up to `follow_pagination` pages following a page
+    ///
+    /// Each part is loaded in its own tab. Loading stops at the first part that cannot
+    /// be loaded, keeping the parts loaded so far.
+    async fn load_following_parts(&self, html_content: &str, url: &str) -> Vec<String> {
+        let mut parts: Vec<String> = Vec::new();
+        let mut current = url.to_string();
+        let mut visited = HashSet::from([current.clone()]);
+        while parts.len() < self.follow_pagination {
+            let previous = parts.last().map_or(html_content, String::as_str);
+            let Some(next) = pagination::next_page_url(previous, &current) else {
+                break;
+            };
+            if !visited.insert(next.clone()) {
+                break;
+            }
+            match self.load_content(&next, true).await {
+                Ok(part) => {
+                    info!("Stitching page {} of {}: {}", parts.len() + 2, url, next);
+                    parts.push(part);
+                    current = next;
+                }
+                Err(e) => {
+                    warn!("Failed to load page {} of {}: {}", parts.len() + 2, url, e);
+                    break;
+                }
+            }
+        }
+        parts
+    }
+
+    /// Load a URL in a new tab as for a PDF, without printing it, and return its
+    /// rendered HTML
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the page cannot be loaded
+    pub async fn load_url(&self, url: &str) -> Result<String> {
+        self.load_content(url, true).await
+    }
+
+    /// Load a URL in a new tab and return its rendered HTML
+    async fn load_content(&self, url: &str, remote: bool) -> Result<String> {
+        let tab = OpenTab(self.browser.new_tab()?);
+        let watch = self.wait.watch(&tab)?;
+        self.navigate(&tab, url, remote)?;
+        watch.wait().await;
+        tab.get_content()
+    }
+
+    /// Prepare a tab (viewport, proxy, recording, network log, cookies, and headers),
+    /// navigate it to a URL, and wait for the page to load
+    fn navigate(&self, tab: &Arc<Tab>, url: &str, remote: bool) -> Result<()> {
+        if let Some(viewport) = &self.viewport {
+            viewport.apply(tab)?;
+        }
+        if let Some(deterministic) = &self.deterministic {
+            deterministic.prepare(tab)?;
+        }
+        if let (Some(proxy), true) = (&self.proxy, remote) {
+            proxy.authenticate(tab)?;
+        }
+        if let (Some(recorder), true) = (&self.recorder, remote) {
+            recorder.attach(tab)?;
+        }
+        if let (Some(log), true) = (&self.network_log, remote) {
+            log.attach(tab)?;
+        }
+        if let (Some(cookies), true) = (&self.cookies, remote) {
+            let params: Vec<CookieParam> =
+                cookies.for_url(url).into_iter().map(cookie_param).collect();
+            if !params.is_empty() {
+                tab.set_cookies(params)?;
+            }
+        }
+        if let (Some(options), true) = (&self.request_options, remote) {
+            options.apply(tab)?;
+        }
+
+        self.load(tab, url, remote)?;
+        if let (Some(log), true) = (&self.network_log, remote) {
+            log.fetch_certificates(tab);
+        }
+        Ok(())
+    }
+
+    /// Navigate a tab to a URL and wait for the page to load
+    ///
+    /// With adaptive timeouts, remote pages use their domain's timeout and are loaded
+    /// again when a timeout leads to a longer one.
+    fn load(&self, tab: &Tab, url: &str, remote: bool) -> Result<()> {
+        let Some(timeouts) = self.timeouts.as_ref().filter(|_| remote) else {
+            tab.navigate_to(url)?.wait_until_navigated()?;
+            return Ok(());
+        };
+
+        loop {
+            tab.set_default_timeout(timeouts.timeout_for(url));
+            let started = Instant::now();
+            match tab
+                .navigate_to(url)
+                .and_then(|tab| tab.wait_until_navigated())
+            {
+                Ok(_) => {
+                    timeouts.record(url, started.elapsed(), false);
+                    return Ok(());
+                }
+                Err(e) if e.is::<headless_chrome::util::Timeout>() => {
+                    match timeouts.record(url, started.elapsed(), true) {
+                        Some(timeout) => warn!(
+                            "Timed out loading {}; retrying with a {}s timeout",
+                            url,
+                            timeout.as_secs()
+                        ),
+                        None => return Err(e),
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// The DevTools parameters setting an imported cookie
+///
+/// Host-only cookies are set by URL, since a domain would extend them to subdomains.
+fn cookie_param(cookie: &Cookie) -> CookieParam {
+    let (url, domain) = match cookie.domain.starts_with('.') {
+        true => (None, Some(cookie.domain.clone())),
+        false => {
+            let scheme = if cookie.secure { "https" } else { "http" };
+            (
+                Some(format!("{}://{}{}", scheme, cookie.domain, cookie.path)),
+                None,
+            )
+        }
+    };
+    CookieParam {
+        name: cookie.name.clone(),
+        value: cookie.value.clone(),
+        url,
+        domain,
+        path: Some(cookie.path.clone()),
+        secure: Some(cookie.secure),
+        http_only: Some(cookie.http_only),
+        same_site: None,
+        expires: cookie.expires.map(|expires| expires as f64),
+        priority: None,
+        same_party: None,
+        source_scheme: None,
+        source_port: None,
+        partition_key: None,
+    }
+}
+
+/// A browser tab that is closed when dropped, so that failed conversions do not leave
+/// tabs open in a browser shared by many of them
+pub(crate) struct OpenTab(pub(crate) Arc<Tab>);
+
+impl std::ops::Deref for OpenTab {
+    type Target = Arc<Tab>;
+
+    fn deref(&self) -> &Arc<Tab> {
+        &self.0
+    }
+}
+
+impl Drop for OpenTab {
+    fn drop(&mut self) {
+        let _ = self.0.close(false);
+    }
+}
+
+/// Capture the whole page loaded in a tab as an image, beyond the viewport
+///
+/// Pages taller than [`MAX_SCREENSHOT_HEIGHT`] are cut off there.
+fn capture_full_page(tab: &Tab, format: ScreenshotFormat, url: &str) -> Result<Vec<u8>> {
+    let size = tab
+        .call_method(Page::GetLayoutMetrics(None))?
+        .css_content_size;
+    if size.height > MAX_SCREENSHOT_HEIGHT {
+        warn!(
+            "{} is {:.0}px tall; the screenshot is cut off at {:.0}px",
+            url, size.height, MAX_SCREENSHOT_HEIGHT
+        );
+    }
+    let screenshot = tab.call_method(Page::CaptureScreenshot {
+        format: Some(format.capture_format()),
+        quality: format.quality(),
+        clip: Some(Page::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: size.width.max(1.0).ceil(),
+            height: size.height.clamp(1.0, MAX_SCREENSHOT_HEIGHT).ceil(),
+            scale: 1.0,
+        }),
+        from_surface: Some(true),
+        capture_beyond_viewport: Some(true),
+        optimize_for_speed: None,
+    })?;
+    Ok(BASE64.decode(screenshot.data)?)
+}
+
+/// Measurements of the content of a page that may not fit the page width
+#[derive(Debug, Clone, Copy)]
+struct ContentLayout {
+    /// Width in CSS pixels of the widest content that does not shrink to the page:
+    /// tables, code blocks, and media without a maximum width
+    widest: f64,
+    /// Share of the page height taken by tables and code blocks wider than the page
+    wide_share: f64,
+}
+
+/// Measure the content of a page loaded in a tab, counting tables and code blocks as
+/// wide when they are wider than `page_width` CSS pixels
+fn measure_content(tab: &Tab, page_width: f64) -> Result<ContentLayout> {
+    let result = tab.evaluate(
+        &format!(
+            r#"(() => {{
+                let widest = 0;
+                let wideHeight = 0;
+                for (const element of document.querySelectorAll('table, pre')) {{
+                    widest = Math.max(widest, element.scrollWidth);
+                    if (element.scrollWidth > {page_width} && !element.parentElement.closest('table, pre')) {{
+                        wideHeight += element.getBoundingClientRect().height;
+                    }}
+                }}
+                for (const element of document.querySelectorAll('img, svg, canvas, iframe')) {{
+                    if (getComputedStyle(element).maxWidth === 'none') {{
+                        widest = Math.max(widest, element.getBoundingClientRect().width);
+                    }}
+                }}
+                const height = Math.max(document.documentElement.scrollHeight, 1);
+                return JSON.stringify([widest, Math.min(wideHeight / height, 1)]);
+            }})()"#
+        ),
+        false,
+    )?;
+    let json = result
+        .value
+        .and_then(|value| value.as_str().map(str::to_string))
+        .ok_or_else(|| anyhow::anyhow!("No content measurements returned"))?;
+    let (widest, wide_share): (f64, f64) = serde_json::from_str(&json)?;
+    Ok(ContentLayout { widest, wide_share })
+}
+
+/// Insert a `<base href>` element into an HTML document unless it already has one
+fn insert_base_href(html_content: &str, base_url: &str) -> String {
+    let lower = html_content.to_lowercase();
+    if lower.contains("<base ") || lower.contains("<base>") {
+        return html_content.to_string();
+    }
+
+    let base_tag = format!(
+        "<base href=\"{}\">",
+        base_url.replace('&', "&amp;").replace('"', "&quot;")
+    );
+
+    // Place the element right after the opening <head> (or <html>) tag
+    for opening_tag in [&*HEAD_TAG, &*HTML_TAG] {
+        if let Some(found) = opening_tag.find(html_content) {
+            return format!(
+                "{}{}{}",
+                &html_content[..found.end()],
+                base_tag,
+                &html_content[found.end()..]
+            );
+        }
+    }
+
+    format!("{}{}", base_tag, html_content)
+}
+
+/// Converts URLs to PDF concurrently, in up to a fixed number of tabs of one browser
+///
+/// Clones share the browser and the concurrency limit.
+#[derive(Clone)]
+pub struct PdfGeneratorPool {
+    generator: Arc<PdfGenerator>,
+    permits: Arc<Semaphore>,
+    concurrency: usize,
+}
+
+impl PdfGeneratorPool {
+    /// Create a pool converting with `generator`, at most `concurrency` pages at a time
+    pub fn new(generator: PdfGenerator, concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        Self {
+            generator: Arc::new(generator),
+            permits: Arc::new(Semaphore::new(concurrency)),
+            concurrency,
+        }
+    }
+
+    /// The most pages converted at a time
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    /// Convert a URL to PDF like [`PdfGenerator::url_to_pdf`], waiting for a free tab
+    ///
+    /// The browser is driven from a blocking thread, so conversions started from
+    /// separate tasks run in parallel.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the conversion fails
+    pub async fn url_to_pdf(&self, url: &str, output_path: Option<&Path>) -> Result<Vec<u8>> {
+        self.save_url(url, Capture::Pdf, output_path).await
+    }
+
+    /// Capture a full-page screenshot of a URL like [`PdfGenerator::url_to_screenshot`],
+    /// waiting for a free tab
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the capture fails
+    pub async fn url_to_screenshot(
+        &self,
+        url: &str,
+        format: ScreenshotFormat,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        self.save_url(url, Capture::Screenshot(format), output_path)
+            .await
+    }
+
+    /// Capture a URL in a free tab, from a blocking thread
+    async fn save_url(
+        &self,
+        url: &str,
+        capture: Capture,
+        output_path: Option<&Path>,
+    ) -> Result<Vec<u8>> {
+        let _permit = self.permits.acquire().await?;
+        let generator = self.generator.clone();
+        let url = url.to_string();
+        let output_path = output_path.map(Path::to_path_buf);
+        let runtime = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || {
+            runtime.block_on(generator.save_url(&url, capture, output_path.as_deref()))
+        })
+        .await?
+    }
+
+    /// Convert URLs to PDF files, up to the concurrency limit at a time
+    ///
+    /// # Returns
+    ///
+    /// Returns the outcome of each conversion and the time it took, in the order of
+    /// `jobs`
+    pub async fn urls_to_pdf(
+        &self,
+        jobs: Vec<(String, PathBuf)>,
+    ) -> Vec<(Result<PathBuf>, Duration)> {
+        self.save_urls(jobs, Capture::Pdf).await
+    }
+
+    /// Capture full-page screenshots of URLs, up to the concurrency limit at a time
+    ///
+    /// # Returns
+    ///
+    /// Returns the outcome of each capture and the time it took, in the order of `jobs`
+    pub async fn urls_to_screenshots(
+        &self,
+        jobs: Vec<(String, PathBuf)>,
+        format: ScreenshotFormat,
+    ) -> Vec<(Result<PathBuf>, Duration)> {
+        self.save_urls(jobs, Capture::Screenshot(format)).await
+    }
+
+    /// Capture URLs into files, up to the concurrency limit at a time
+    async fn save_urls(
+        &self,
+        jobs: Vec<(String, PathBuf)>,
+        capture: Capture,
+    ) -> Vec<(Result<PathBuf>, Duration)> {
+        let count = jobs.len();
+        let mut tasks = JoinSet::new();
+        for (index, (url, path)) in jobs.into_iter().enumerate() {
+            let pool = self.clone();
+            tasks.spawn(async move {
+                info!("Converting {} to {}", url, path.display());
+                let started = Instant::now();
+                let result = pool
+                    .save_url(&url, capture, Some(&path))
+                    .await
+                    .map(|_| path);
+                (index, result, started.elapsed())
+            });
+        }
+
+        let mut outcomes: Vec<Option<(Result<PathBuf>, Duration)>> =
+            (0..count).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result, elapsed)) => outcomes[index] = Some((result, elapsed)),
+                Err(e) => warn!("Conversion task failed: {}", e),
+            }
+        }
+        outcomes
+            .into_iter()
+            .map(|outcome| {
+                outcome.unwrap_or_else(|| {
+                    (
+                        Err(anyhow::anyhow!("Conversion was interrupted")),
+                        Duration::ZERO,
+                    )
+                })
+            })
+            .collect()
     }
 }
 
@@ -151,6 +1507,39 @@ mod tests {
     use super::*;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn test_pdf_config() -> Result<()> {
+        assert_eq!(parse_page_ranges("1-5, 8, 10-")?, "1-5,8,10-");
+        assert_eq!(parse_page_ranges("-3")?, "-3");
+        assert!(parse_page_ranges("5-2").is_err());
+        assert!(parse_page_ranges("0").is_err());
+        assert!(parse_page_ranges("1,,2").is_err());
+        assert!(parse_page_ranges("-").is_err());
+
+        let config = PdfConfig::new()
+            .with_paper(Paper::Letter)
+            .with_margins(0.2)
+            .with_footer_template("<span class=\"pageNumber\"></span>")
+            .with_page_ranges("2-3")?
+            .with_print_background(false);
+        assert_eq!(config.margin(), 0.2);
+        assert!(config.printable_width(true) > config.printable_width(false));
+
+        let options = config.print_options(true, 0.9, None, false);
+        assert_eq!(options.paper_width, Some(8.5));
+        assert_eq!(options.margin_left, Some(0.2));
+        assert_eq!(options.landscape, Some(true));
+        assert_eq!(options.print_background, Some(false));
+        assert_eq!(options.page_ranges.as_deref(), Some("2-3"));
+        assert_eq!(options.display_header_footer, Some(true));
+        assert_eq!(options.header_template.as_deref(), Some("<span></span>"));
+
+        let options = PdfConfig::new().print_options(false, 1.0, None, false);
+        assert_eq!(options.display_header_footer, Some(false));
+        assert_eq!(options.margin_top, Some(Paper::A4.margin()));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_html_to_pdf() -> Result<()> {
         let generator = PdfGenerator::new().await?;
@@ -172,6 +1561,89 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_html_to_screenshot() -> Result<()> {
+        assert_eq!(ScreenshotFormat::default().extension(), "png");
+        assert_eq!(ScreenshotFormat::Jpeg.extension(), "jpg");
+
+        let generator = PdfGenerator::new().await?.with_wait(Duration::ZERO);
+        let html = r#"<html><body><div style="height: 3000px">Tall page</div></body></html>"#;
+        let png = generator
+            .html_to_screenshot(html, ScreenshotFormat::Png, None)
+            .await?;
+        assert!(png.starts_with(b"\x89PNG"));
+        // The image is as tall as the page, not the viewport
+        let height = u32::from_be_bytes([png[20], png[21], png[22], png[23]]);
+        assert!(height >= 3000);
+
+        let jpeg = generator
+            .html_to_screenshot(html, ScreenshotFormat::Jpeg, None)
+            .await?;
+        assert!(jpeg.starts_with(&[0xFF, 0xD8]));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_html_to_pdf_without_wait() -> Result<()> {
+        let generator = PdfGenerator::new().await?.with_wait(Duration::ZERO);
+        assert_eq!(generator.wait(), &WaitStrategy::Delay(Duration::ZERO));
+
+        let html = r#"<html><body><h1>No Wait</h1></body></html>"#;
+        let pdf_data = generator.html_to_pdf(html, None).await?;
+        assert!(pdf_data.starts_with(b"%PDF"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_url_to_pdf_from_recording() -> Result<()> {
+        use crate::recording::RecordedResponse;
+
+        // A recorded page replays without network access, even on a domain that
+        // cannot resolve
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("recording.json");
+        let recorder = HttpRecorder::record(&path);
+        let headers = vec![("Content-Type".to_string(), "text/html".to_string())];
+        recorder.push(RecordedResponse::new(
+            "GET",
+            "https://recorded.invalid/",
+            200,
+            headers,
+            b"<html><body><h1>Recorded</h1></body></html>",
+        ));
+        recorder.save()?;
+
+        let generator = PdfGenerator::new()
+            .await?
+            .with_wait(Duration::ZERO)
+            .with_recorder(HttpRecorder::replay(&path)?);
+        let pdf_data = generator
+            .url_to_pdf("https://recorded.invalid/", None)
+            .await?;
+        assert!(pdf_data.starts_with(b"%PDF"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_base_href() {
+        let base = "file:///docs/guide/intro.html";
+        assert_eq!(
+            insert_base_href("<html><head><title>T</title></head></html>", base),
+            "<html><head><base href=\"file:///docs/guide/intro.html\"><title>T</title></head></html>"
+        );
+        assert_eq!(
+            insert_base_href("<html lang=\"en\"><header>H</header></html>", base),
+            "<html lang=\"en\"><base href=\"file:///docs/guide/intro.html\"><header>H</header></html>"
+        );
+        assert_eq!(
+            insert_base_href("<p>fragment</p>", base),
+            "<base href=\"file:///docs/guide/intro.html\"><p>fragment</p>"
+        );
+
+        let with_base = r#"<html><head><base href="https://example.com/"></head></html>"#;
+        assert_eq!(insert_base_href(with_base, base), with_base);
+    }
+
     #[tokio::test]
     async fn test_url_to_pdf_invalid_url() -> Result<()> {
         let generator = PdfGenerator::new().await?;
@@ -205,6 +1677,35 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_pool_converts_in_order() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let pool = PdfGeneratorPool::new(PdfGenerator::new().await?.with_wait(Duration::ZERO), 2);
+        assert_eq!(pool.concurrency(), 2);
+
+        let mut jobs = Vec::new();
+        for index in 0..3 {
+            let page = dir.path().join(format!("page{}.html", index));
+            std::fs::write(
+                &page,
+                format!("<html><body><h1>Page {}</h1></body></html>", index),
+            )?;
+            let url = format!("file://{}", page.display());
+            jobs.push((url, dir.path().join(format!("page{}.pdf", index))));
+        }
+        jobs.push(("ftp://example.com".to_string(), dir.path().join("ftp.pdf")));
+
+        let outcomes = pool.urls_to_pdf(jobs).await;
+        assert_eq!(outcomes.len(), 4);
+        for (index, (result, _)) in outcomes.iter().take(3).enumerate() {
+            let path = result.as_ref().expect("converted");
+            assert_eq!(path, &dir.path().join(format!("page{}.pdf", index)));
+            assert!(std::fs::read(path)?.starts_with(b"%PDF"));
+        }
+        assert!(outcomes[3].0.is_err());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_unsupported_scheme() -> Result<()> {
         let generator = PdfGenerator::new().await?;
@@ -0,0 +1,284 @@
+//! Detection of personal data in captured pages
+//!
+//! Before sharing an archive, it helps to know which captures contain personal data.
+//! A [`PiiScanner`] looks for likely email addresses, phone numbers, and national ID
+//! numbers (US Social Security and UK National Insurance numbers) in the text of every
+//! captured page and records the findings per document for the run report. Matches are
+//! only reported, with masked examples, never changed; use redaction to remove them.
+//!
+//! The same [`PiiScanner`] can be shared by the PDF and Markdown generators; findings
+//! for a page captured in both formats are merged.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// Masked examples kept per document
+const MAX_EXAMPLES: usize = 5;
+
+static NATIONAL_ID: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        // US Social Security numbers
+        r"\b\d{3}-\d{2}-\d{4}\b",
+        // UK National Insurance numbers
+        r"|\b[A-CEGHJ-PR-TW-Z][A-CEGHJ-NPR-TW-Z] ?\d{2} ?\d{2} ?\d{2} ?[A-D]\b",
+    ))
+    .unwrap()
+});
+static EMAIL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}\b").unwrap()
+});
+static PHONE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:\+\d{1,3}[\s.-]?)?(?:\(\d{1,4}\)|\b\d{2,4})[\s.-]\d{3,4}[\s.-]\d{3,4}\b")
+        .unwrap()
+});
+
+/// Kind of personal data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiKind {
+    /// National ID numbers
+    NationalId,
+    /// Email addresses
+    Email,
+    /// Phone numbers
+    Phone,
+}
+
+impl PiiKind {
+    /// Kinds in the order they are matched; text matched as one kind is not matched
+    /// again as a later one
+    const ALL: [PiiKind; 3] = [PiiKind::NationalId, PiiKind::Email, PiiKind::Phone];
+
+    fn regex(self) -> &'static Regex {
+        match self {
+            PiiKind::NationalId => &NATIONAL_ID,
+            PiiKind::Email => &EMAIL,
+            PiiKind::Phone => &PHONE,
+        }
+    }
+
+    /// Whether a match is plausible beyond its pattern
+    fn accepts(self, text: &str) -> bool {
+        match self {
+            // SSNs never start with 000, 666, or 9, and have no all-zero group
+            PiiKind::NationalId if text.as_bytes()[0].is_ascii_digit() => {
+                let groups: Vec<&str> = text.split('-').collect();
+                !matches!(groups[0], "000" | "666")
+                    && !groups[0].starts_with('9')
+                    && groups[1] != "00"
+                    && groups[2] != "0000"
+            }
+            // Phone numbers have 9 to 15 digits
+            PiiKind::Phone => (9..=15).contains(&text.chars().filter(char::is_ascii_digit).count()),
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for PiiKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PiiKind::NationalId => "national ID",
+            PiiKind::Email => "email",
+            PiiKind::Phone => "phone",
+        })
+    }
+}
+
+/// Personal data found in one document
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentPii {
+    /// URL of the document
+    pub source: String,
+    /// Number of matches per kind
+    pub counts: BTreeMap<PiiKind, usize>,
+    /// A few of the matches, masked
+    pub examples: Vec<String>,
+}
+
+impl DocumentPii {
+    /// Total number of matches
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// One-line summary of the counts, e.g. `2 email, 1 phone`
+    pub fn summary(&self) -> String {
+        self.counts
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Merge the findings of another scan of the same document, keeping the higher
+    /// count of each kind
+    fn merge(&mut self, other: DocumentPii) {
+        for (kind, count) in other.counts {
+            let entry = self.counts.entry(kind).or_default();
+            *entry = (*entry).max(count);
+        }
+        for example in other.examples {
+            if self.examples.len() < MAX_EXAMPLES && !self.examples.contains(&example) {
+                self.examples.push(example);
+            }
+        }
+    }
+}
+
+/// Find the personal data in a text
+pub fn scan_text(source: &str, text: &str) -> DocumentPii {
+    let mut taken: Vec<(usize, usize)> = Vec::new();
+    let mut findings = DocumentPii {
+        source: source.to_string(),
+        ..DocumentPii::default()
+    };
+    for kind in PiiKind::ALL {
+        for m in kind.regex().find_iter(text) {
+            let overlaps = taken
+                .iter()
+                .any(|&(start, end)| m.start() < end && start < m.end());
+            if overlaps || !kind.accepts(m.as_str()) {
+                continue;
+            }
+            taken.push((m.start(), m.end()));
+            *findings.counts.entry(kind).or_default() += 1;
+            let example = mask(kind, m.as_str());
+            if findings.examples.len() < MAX_EXAMPLES && !findings.examples.contains(&example) {
+                findings.examples.push(example);
+            }
+        }
+    }
+    findings
+}
+
+/// Mask a match so the report does not repeat the personal data it flags
+fn mask(kind: PiiKind, text: &str) -> String {
+    match kind {
+        PiiKind::Email => match text.split_once('@') {
+            Some((user, domain)) => {
+                format!("{}***@{}", user.chars().next().unwrap_or('*'), domain)
+            }
+            None => "***".to_string(),
+        },
+        // Keep the last characters, as receipts and statements do
+        PiiKind::NationalId | PiiKind::Phone => {
+            let chars: Vec<char> = text.chars().collect();
+            let keep = chars.len().saturating_sub(4);
+            chars
+                .iter()
+                .enumerate()
+                .map(
+                    |(index, &c)| match index < keep && c.is_ascii_alphanumeric() {
+                        true => '*',
+                        false => c,
+                    },
+                )
+                .collect()
+        }
+    }
+}
+
+/// Records the personal data found in the pages captured during a run
+///
+/// Clones share the same findings.
+#[derive(Debug, Clone, Default)]
+pub struct PiiScanner {
+    documents: Arc<Mutex<BTreeMap<String, DocumentPii>>>,
+}
+
+impl PiiScanner {
+    /// Create a scanner without findings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Scan the text of a captured document, recording what was found
+    ///
+    /// # Returns
+    ///
+    /// Returns the number of matches found in this text
+    pub fn scan(&self, source: &str, text: &str) -> usize {
+        let findings = scan_text(source, text);
+        let total = findings.total();
+        if total > 0 {
+            let mut documents = self
+                .documents
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            match documents.get_mut(source) {
+                Some(document) => document.merge(findings),
+                None => {
+                    documents.insert(source.to_string(), findings);
+                }
+            }
+        }
+        total
+    }
+
+    /// The documents with findings, sorted by source
+    pub fn findings(&self) -> Vec<DocumentPii> {
+        self.documents
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_text() {
+        let text = "Write to jane.doe@example.com or call +1 (555) 010-0199.\n\
+                    SSN 123-45-6789, NI number AB 12 34 56 C, office 020 7946 0958.\n\
+                    Not PII: 2024-01-15, version 1.2.3, order 000-12-3456, id 12345.";
+        let findings = scan_text("https://example.com/", text);
+        assert_eq!(
+            findings.counts,
+            BTreeMap::from([
+                (PiiKind::NationalId, 2),
+                (PiiKind::Email, 1),
+                (PiiKind::Phone, 2),
+            ])
+        );
+        assert_eq!(findings.summary(), "2 national ID, 1 email, 2 phone");
+        assert_eq!(
+            findings.examples,
+            vec![
+                "***-**-6789",
+                "** ** ** 56 C",
+                "j***@example.com",
+                "+* (***) ***-0199",
+                "*** **** 0958",
+            ]
+        );
+        assert_eq!(scan_text("x", "Nothing personal here.").total(), 0);
+    }
+
+    #[test]
+    fn test_scanner_merges_formats() {
+        let scanner = PiiScanner::new();
+        let shared = scanner.clone();
+        assert_eq!(scanner.scan("https://a.example/", "a@example.com"), 1);
+        assert_eq!(
+            shared.scan("https://a.example/", "a@example.com b@example.com"),
+            2
+        );
+        assert_eq!(scanner.scan("https://b.example/", "no findings"), 0);
+
+        let findings = scanner.findings();
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].counts[&PiiKind::Email], 2);
+        assert_eq!(
+            findings[0].examples,
+            vec!["a***@example.com", "b***@example.com"]
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Place cards of local search results, with geocoding and map snapshots
+//!
+//! The web pages of a local search say little about where the places are, and many
+//! places have no web page at all. Every place found is instead saved as a card with
+//! its name, address, links, and a small street map around it, so that local-search
+//! archives stay useful offline.
+//!
+//! Places the search did not give coordinates for are geocoded from their address
+//! with a Nominatim server, at most one request per second as its usage policy asks.
+//! The map is an SVG of the OpenStreetMap tiles around the place, embedded as images,
+//! with a marker at the place and the map data attribution.
+
+use crate::extract::escape;
+use crate::readlater::{checked, http_client, send_with_retry};
+use crate::search::LocalSearchResult;
+use anyhow::Result;
+use base64::Engine;
+use reqwest::Client;
+use serde::Deserialize;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Default Nominatim server addresses are geocoded with
+pub const DEFAULT_GEOCODER_ENDPOINT: &str = "https://nominatim.openstreetmap.org";
+
+/// Default URL template of map tiles, with `{z}`, `{x}`, and `{y}` placeholders
+pub const DEFAULT_TILE_URL: &str = "https://tile.openstreetmap.org/{z}/{x}/{y}.png";
+
+/// Default zoom level of maps, showing the surrounding streets
+pub const DEFAULT_MAP_ZOOM: u8 = 16;
+
+/// Highest zoom level of OpenStreetMap tiles
+pub const MAX_MAP_ZOOM: u8 = 19;
+
+/// Attribution required on maps made of OpenStreetMap tiles
+pub const MAP_ATTRIBUTION: &str = "© OpenStreetMap contributors";
+
+/// Width and height of a map tile in pixels
+const TILE_SIZE: f64 = 256.0;
+
+/// Size of map snapshots in pixels
+const MAP_WIDTH: f64 = 480.0;
+const MAP_HEIGHT: f64 = 240.0;
+
+/// Least time between geocoding requests, as the Nominatim usage policy asks
+const GEOCODE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A position on the earth
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    /// Latitude in degrees, positive to the north
+    pub latitude: f64,
+    /// Longitude in degrees, positive to the east
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// The position in pixels on the Web Mercator world map at a zoom level
+    pub fn pixel(self, zoom: u8) -> (f64, f64) {
+        let size = TILE_SIZE * f64::from(1u32 << zoom);
+        let x = (self.longitude + 180.0) / 360.0 * size;
+        let latitude = self.latitude.clamp(-85.0511, 85.0511).to_radians();
+        let y = (1.0 - (latitude.tan() + 1.0 / latitude.cos()).ln() / PI) / 2.0 * size;
+        (x, y)
+    }
+
+    /// The place on openstreetmap.org
+    pub fn osm_url(self, zoom: u8) -> String {
+        format!(
+            "https://www.openstreetmap.org/?mlat={lat:.5}&mlon={lon:.5}#map={zoom}/{lat:.5}/{lon:.5}",
+            lat = self.latitude,
+            lon = self.longitude,
+        )
+    }
+}
+
+impl From<[f64; 2]> for Coordinates {
+    /// Coordinates given as `[latitude, longitude]`, as local search results have them
+    fn from([latitude, longitude]: [f64; 2]) -> Self {
+        Self {
+            latitude,
+            longitude,
+        }
+    }
+}
+
+/// A place of a local search with where it is and a map around it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlaceCard {
+    /// The place as found by the search
+    pub place: LocalSearchResult,
+    /// Where the place is, if known
+    pub coordinates: Option<Coordinates>,
+    /// The map around the place as SVG, if one was made
+    pub map: Option<String>,
+    /// Zoom level of the map and of the openstreetmap.org link
+    pub zoom: u8,
+}
+
+impl PlaceCard {
+    /// Render the card as Markdown, showing the map from a file next to it
+    pub fn to_markdown(&self, map_file: Option<&str>) -> String {
+        let place = &self.place;
+        let mut markdown = format!("# {}\n\n", place.title);
+        if let Some(address) = place.address() {
+            markdown.push_str(&format!("- **Address:** {}\n", address));
+        }
+        if let Some(url) = &place.url {
+            markdown.push_str(&format!("- **Web site:** <{}>\n", url));
+        }
+        if let Some(url) = &place.provider_url {
+            markdown.push_str(&format!("- **Details:** <{}>\n", url));
+        }
+        if let Some(at) = self.coordinates {
+            markdown.push_str(&format!(
+                "- **Coordinates:** [{:.5}, {:.5}]({})\n",
+                at.latitude,
+                at.longitude,
+                at.osm_url(self.zoom)
+            ));
+        }
+        if let Some(description) = place.description.as_deref().filter(|d| !d.is_empty()) {
+            markdown.push_str(&format!("\n{}\n", mdka::from_html(description).trim()));
+        }
+        if let Some(map_file) = map_file.filter(|_| self.map.is_some()) {
+            markdown.push_str(&format!(
+                "\n![Map of {}]({})\n\n*Map data {}*\n",
+                place.title, map_file, MAP_ATTRIBUTION
+            ));
+        }
+        markdown
+    }
+
+    /// Render the card as an HTML document with the map inline, for printing
+    pub fn to_html(&self) -> String {
+        let place = &self.place;
+        let mut details = String::new();
+        let mut row = |label: &str, value: String| {
+            details.push_str(&format!("<dt>{}</dt><dd>{}</dd>", label, value));
+        };
+        if let Some(address) = place.address() {
+            row("Address", escape(address, false));
+        }
+        for (label, url) in [("Web site", &place.url), ("Details", &place.provider_url)] {
+            if let Some(url) = url {
+                row(
+                    label,
+                    format!(
+                        "<a href=\"{}\">{}</a>",
+                        escape(url, true),
+                        escape(url, false)
+                    ),
+                );
+            }
+        }
+        if let Some(at) = self.coordinates {
+            row(
+                "Coordinates",
+                format!(
+                    "<a href=\"{}\">{:.5}, {:.5}</a>",
+                    escape(&at.osm_url(self.zoom), true),
+                    at.latitude,
+                    at.longitude
+                ),
+            );
+        }
+        let description = place
+            .description
+            .as_deref()
+            .map(|description| format!("<p>{}</p>", escape(&mdka::from_html(description), false)))
+            .unwrap_or_default();
+        let map = self
+            .map
+            .as_deref()
+            .map(|map| format!("<figure>{}</figure>", map))
+            .unwrap_or_default();
+        format!(
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{title}</title>\
+             <style>body{{font-family:sans-serif;margin:2em}}dl{{display:grid;\
+             grid-template-columns:max-content auto;gap:.3em 1em}}dt{{font-weight:bold}}\
+             dd{{margin:0}}figure{{margin:1.5em 0}}</style></head>\
+             <body><h1>{title}</h1><dl>{details}</dl>{description}{map}</body></html>",
+            title = escape(&place.title, false),
+        )
+    }
+}
+
+/// Geocoder and map tile source for place cards
+#[derive(Clone)]
+pub struct PlaceMaps {
+    client: Client,
+    geocoder: String,
+    tile_url: String,
+    zoom: u8,
+    last_geocode: Arc<Mutex<Option<Instant>>>,
+}
+
+#[derive(Deserialize)]
+struct GeocodedPlace {
+    lat: String,
+    lon: String,
+}
+
+impl PlaceMaps {
+    /// Create place maps using OpenStreetMap's Nominatim server and tiles
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            geocoder: DEFAULT_GEOCODER_ENDPOINT.to_string(),
+            tile_url: DEFAULT_TILE_URL.to_string(),
+            zoom: DEFAULT_MAP_ZOOM,
+            last_geocode: Arc::new(Mutex::new(None)),
+        })
+    }
+
+    /// Geocode addresses with another Nominatim-compatible server
+    pub fn with_geocoder(mut self, endpoint: impl Into<String>) -> Self {
+        self.geocoder = endpoint.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Make maps from another tile server, given as a URL template with `{z}`, `{x}`,
+    /// and `{y}` placeholders
+    pub fn with_tile_url(mut self, template: impl Into<String>) -> Self {
+        self.tile_url = template.into();
+        self
+    }
+
+    /// Make maps at another zoom level, up to [`MAX_MAP_ZOOM`]
+    pub fn with_zoom(mut self, zoom: u8) -> Self {
+        self.zoom = zoom.min(MAX_MAP_ZOOM);
+        self
+    }
+
+    /// Make the card of a place, geocoding its address if the search did not give its
+    /// coordinates, and rendering the map around it
+    ///
+    /// Without a network connection (`offline`), the card has only what the search gave.
+    /// A failed map leaves the card without one.
+    pub async fn card(&self, place: &LocalSearchResult, offline: bool) -> PlaceCard {
+        let mut coordinates = place.coordinates.map(Coordinates::from);
+        if let (None, Some(address), false) = (coordinates, place.address(), offline) {
+            coordinates = self
+                .geocode(address)
+                .await
+                .inspect_err(|e| tracing::warn!("Failed to geocode {}: {}", address, e))
+                .ok()
+                .flatten();
+        }
+        let mut map = None;
+        if let (Some(at), false) = (coordinates, offline) {
+            map = self
+                .map(at)
+                .await
+                .inspect_err(|e| tracing::warn!("Failed to make a map of {}: {}", place.title, e))
+                .ok();
+        }
+        PlaceCard {
+            place: place.clone(),
+            coordinates,
+            map,
+            zoom: self.zoom,
+        }
+    }
+
+    /// Find the coordinates of an address, if the geocoder knows it
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the geocoder cannot be reached or its response is invalid
+    pub async fn geocode(&self, address: &str) -> Result<Option<Coordinates>> {
+        {
+            let mut last = self.last_geocode.lock().await;
+            if let Some(wait) = last.and_then(|last| GEOCODE_INTERVAL.checked_sub(last.elapsed())) {
+                tokio::time::sleep(wait).await;
+            }
+            *last = Some(Instant::now());
+        }
+        let url = format!("{}/search", self.geocoder);
+        let response = send_with_retry(|| {
+            self.client
+                .get(&url)
+                .query(&[("q", address), ("format", "jsonv2"), ("limit", "1")])
+        })
+        .await?;
+        let places: Vec<GeocodedPlace> = checked(response, "Geocoding").await?.json().await?;
+        Ok(places.first().and_then(|place| {
+            Some(Coordinates {
+                latitude: place.lat.parse().ok()?,
+                longitude: place.lon.parse().ok()?,
+            })
+        }))
+    }
+
+    /// Render a map centered on a position as SVG, with the tiles embedded
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a tile cannot be downloaded
+    pub async fn map(&self, at: Coordinates) -> Result<String> {
+        let mut images = String::new();
+        for (x, y, left, top) in map_tiles(at, self.zoom) {
+            let url = self
+                .tile_url
+                .replace("{z}", &self.zoom.to_string())
+                .replace("{x}", &x.to_string())
+                .replace("{y}", &y.to_string());
+            let response = send_with_retry(|| self.client.get(&url)).await?;
+            let tile = checked(response, "Downloading a map tile")
+                .await?
+                .bytes()
+                .await?;
+            images.push_str(&format!(
+                "<image x=\"{:.1}\" y=\"{:.1}\" width=\"{size}\" height=\"{size}\" \
+                 href=\"data:image/png;base64,{}\"/>",
+                left,
+                top,
+                base64::engine::general_purpose::STANDARD.encode(&tile),
+                size = TILE_SIZE,
+            ));
+        }
+        Ok(format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+             viewBox=\"0 0 {w} {h}\">{images}\
+             <circle cx=\"{cx}\" cy=\"{cy}\" r=\"8\" fill=\"#d33\" stroke=\"#fff\" \
+             stroke-width=\"3\"/>\
+             <rect x=\"{rx}\" y=\"{ry}\" width=\"160\" height=\"16\" fill=\"#fff\" \
+             fill-opacity=\"0.8\"/>\
+             <text x=\"{tx}\" y=\"{ty}\" font-family=\"sans-serif\" font-size=\"10\" \
+             text-anchor=\"end\">{attribution}</text></svg>\n",
+            w = MAP_WIDTH,
+            h = MAP_HEIGHT,
+            cx = MAP_WIDTH / 2.0,
+            cy = MAP_HEIGHT / 2.0,
+            rx = MAP_WIDTH - 160.0,
+            ry = MAP_HEIGHT - 16.0,
+            tx = MAP_WIDTH - 4.0,
+            ty = MAP_HEIGHT - 4.0,
+            attribution = MAP_ATTRIBUTION,
+        ))
+    }
+}
+
+/// The tiles covering a map centered on a position: their tile numbers, and where
+/// their top left corner is on the map
+fn map_tiles(at: Coordinates, zoom: u8) -> Vec<(u32, u32, f64, f64)> {
+    let (x, y) = at.pixel(zoom);
+    let (left, top) = (x - MAP_WIDTH / 2.0, y - MAP_HEIGHT / 2.0);
+    let count = 1i64 << zoom;
+    let first_x = (left / TILE_SIZE).floor() as i64;
+    let last_x = ((left + MAP_WIDTH) / TILE_SIZE).ceil() as i64;
+    let first_y = (top / TILE_SIZE).floor() as i64;
+    let last_y = ((top + MAP_HEIGHT) / TILE_SIZE).ceil() as i64;
+    let mut tiles = Vec::new();
+    for tile_y in (first_y..last_y).filter(|tile_y| (0..count).contains(tile_y)) {
+        for tile_x in first_x..last_x {
+            tiles.push((
+                // The map wraps around at the antimeridian
+                tile_x.rem_euclid(count) as u32,
+                tile_y as u32,
+                tile_x as f64 * TILE_SIZE - left,
+                tile_y as f64 * TILE_SIZE - top,
+            ));
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::PostalAddress;
+
+    #[test]
+    fn test_map_tiles_and_card() {
+        let origin = Coordinates {
+            latitude: 0.0,
+            longitude: 0.0,
+        };
+        assert_eq!(origin.pixel(0), (128.0, 128.0));
+        // A map at zoom 1 centered on the origin shows parts of all four tiles
+        let tiles = map_tiles(origin, 1);
+        assert_eq!(tiles.len(), 4);
+        assert_eq!(tiles[0], (0, 0, -16.0, -136.0));
+
+        let tokyo = Coordinates::from([35.6812, 139.7671]);
+        let (x, y) = tokyo.pixel(DEFAULT_MAP_ZOOM);
+        assert_eq!(
+            ((x / TILE_SIZE) as u32, (y / TILE_SIZE) as u32),
+            (58211, 25806)
+        );
+        assert!(map_tiles(tokyo, DEFAULT_MAP_ZOOM).len() <= 6);
+
+        let card = PlaceCard {
+            place: LocalSearchResult {
+                title: "Cafe".to_string(),
+                url: None,
+                provider_url: Some("https://maps.example/cafe".to_string()),
+                description: Some("Coffee &amp; cake".to_string()),
+                postal_address: Some(PostalAddress {
+                    display_address: Some("1 Main St".to_string()),
+                    ..Default::default()
+                }),
+                coordinates: Some([35.6812, 139.7671]),
+                contact: None,
+            },
+            coordinates: Some(tokyo),
+            map: Some("<svg></svg>".to_string()),
+            zoom: DEFAULT_MAP_ZOOM,
+        };
+        let markdown = card.to_markdown(Some("Cafe.map.svg"));
+        assert!(markdown.starts_with("# Cafe\n\n- **Address:** 1 Main St\n"));
+        assert!(markdown.contains("- **Details:** <https://maps.example/cafe>\n"));
+        assert!(markdown.contains("[35.68120, 139.76710](https://www.openstreetmap.org/"));
+        assert!(markdown.contains("Coffee & cake"));
+        assert!(markdown.contains("![Map of Cafe](Cafe.map.svg)"));
+        let html = card.to_html();
+        assert!(html.contains("<figure><svg></svg></figure>"));
+        assert!(html.contains("Coffee &amp; cake"));
+    }
+}
@@ -0,0 +1,288 @@
+//! Print-view URL preference
+//!
+//! Many sites serve a cleaner print version of their pages, without navigation, ads, or
+//! comment widgets, at a variant of the page URL such as `?print=1` or `/print/`.
+//! [`PrintViews`] loads the print view of a page instead of the page when one exists:
+//! the variant from the site's profile or, with the heuristic enabled, one of the common
+//! [`HEURISTIC_VARIANTS`]. A variant is only used if it responds successfully with HTML
+//! on the same site; otherwise the original URL is used. Heuristic variants that fail on
+//! a domain are not tried again there during the run.
+
+use crate::profiles::SiteProfiles;
+use anyhow::Result;
+use reqwest::Client;
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Duration;
+use tracing::{debug, info};
+use url::Url;
+
+/// Print-view variants tried by the heuristic, in order
+pub const HEURISTIC_VARIANTS: &[&str] = &["?print=1", "/print/"];
+
+/// How long to wait for a print view to respond before using the original URL
+const PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HEURISTIC: LazyLock<Vec<PrintView>> = LazyLock::new(|| {
+    HEURISTIC_VARIANTS
+        .iter()
+        .map(|variant| variant.parse().expect("built-in variants are valid"))
+        .collect()
+});
+
+/// How to turn a page URL into its print view
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum PrintView {
+    /// Never use a print view (`off`)
+    Off,
+    /// Set query parameters, e.g. `?print=1`
+    Query(Vec<(String, String)>),
+    /// Append path segments, e.g. `/print/`
+    Path(String),
+}
+
+impl PrintView {
+    /// The print-view URL of a page, or `None` if print views are off
+    pub fn apply(&self, url: &str) -> Option<String> {
+        let mut url = Url::parse(url).ok()?;
+        match self {
+            Self::Off => return None,
+            Self::Query(params) => {
+                let pairs: Vec<(String, String)> = url
+                    .query_pairs()
+                    .into_owned()
+                    .filter(|(name, _)| params.iter().all(|(param, _)| param != name))
+                    .collect();
+                url.query_pairs_mut()
+                    .clear()
+                    .extend_pairs(pairs)
+                    .extend_pairs(params);
+            }
+            Self::Path(segments) => {
+                let path = format!("{}{}", url.path().trim_end_matches('/'), segments);
+                url.set_path(&path);
+            }
+        }
+        Some(url.to_string())
+    }
+}
+
+impl FromStr for PrintView {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        match source.trim() {
+            "off" => Ok(Self::Off),
+            query if query.len() > 1 && query.starts_with('?') => Ok(Self::Query(
+                url::form_urlencoded::parse(query[1..].as_bytes())
+                    .into_owned()
+                    .collect(),
+            )),
+            path if path.len() > 1 && path.starts_with('/') => Ok(Self::Path(path.to_string())),
+            _ => Err(anyhow::anyhow!(
+                "Invalid print view '{}': expected 'off', '?param=value', or '/path/'",
+                source
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for PrintView {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        source.parse()
+    }
+}
+
+impl fmt::Display for PrintView {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Off => f.write_str("off"),
+            Self::Query(params) => {
+                let query = url::form_urlencoded::Serializer::new(String::new())
+                    .extend_pairs(params)
+                    .finish();
+                write!(f, "?{}", query)
+            }
+            Self::Path(path) => f.write_str(path),
+        }
+    }
+}
+
+impl From<PrintView> for String {
+    fn from(print_view: PrintView) -> Self {
+        print_view.to_string()
+    }
+}
+
+/// Picks the print view of pages, falling back to the original URL
+///
+/// Clones share the record of heuristic variants that failed.
+#[derive(Debug, Clone)]
+pub struct PrintViews {
+    profiles: SiteProfiles,
+    heuristic: bool,
+    client: Client,
+    failed: Arc<Mutex<HashSet<(String, String)>>>,
+}
+
+impl PrintViews {
+    /// Use the print views configured in site profiles and, if `heuristic` is set, try
+    /// the common variants on other sites
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new(profiles: SiteProfiles, heuristic: bool) -> Result<Self> {
+        let client = Client::builder()
+            .timeout(PROBE_TIMEOUT)
+            .user_agent("webpage-save/1.0")
+            .build()?;
+        Ok(Self {
+            profiles,
+            heuristic,
+            client,
+            failed: Arc::default(),
+        })
+    }
+
+    /// Print-view URLs to try for a page, in order
+    pub fn candidates(&self, url: &str) -> Vec<String> {
+        self.variants(url)
+            .0
+            .iter()
+            .filter_map(|variant| variant.apply(url))
+            .collect()
+    }
+
+    /// The URL to load for a page: its first working print view, or the page itself
+    pub async fn resolve(&self, url: &str) -> String {
+        let (variants, from_profile) = self.variants(url);
+        for variant in variants {
+            let Some(candidate) = variant.apply(url) else {
+                continue;
+            };
+            if self.probe(&candidate).await {
+                info!("Using print view {} for {}", candidate, url);
+                return candidate;
+            }
+            debug!("Print view {} of {} is not available", candidate, url);
+            if !from_profile {
+                self.failed
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .insert((domain_of(url), variant.to_string()));
+            }
+        }
+        url.to_string()
+    }
+
+    /// Print-view variants to try for a page, and whether they come from its site profile
+    fn variants(&self, url: &str) -> (Vec<PrintView>, bool) {
+        let profile = self
+            .profiles
+            .for_url(url)
+            .and_then(|profile| profile.print_view.as_ref());
+        match profile {
+            Some(print_view) => (vec![print_view.clone()], true),
+            None if self.heuristic => {
+                let domain = domain_of(url);
+                let failed = self.failed.lock().unwrap_or_else(|e| e.into_inner());
+                let variants = HEURISTIC
+                    .iter()
+                    .filter(|variant| !failed.contains(&(domain.clone(), variant.to_string())))
+                    .cloned()
+                    .collect();
+                (variants, false)
+            }
+            None => (Vec::new(), false),
+        }
+    }
+
+    /// Whether a print-view URL responds successfully with HTML without leaving its site
+    async fn probe(&self, candidate: &str) -> bool {
+        let Ok(response) = self.client.get(candidate).send().await else {
+            return false;
+        };
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_none_or(|value| value.contains("html"));
+        response.status().is_success()
+            && is_html
+            && response.url().host_str()
+                == Url::parse(candidate).ok().as_ref().and_then(Url::host_str)
+    }
+}
+
+fn domain_of(url: &str) -> String {
+    Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::SiteProfile;
+
+    #[test]
+    fn test_apply_print_view() {
+        let query: PrintView = "?print=1".parse().unwrap();
+        assert_eq!(
+            query
+                .apply("https://example.com/a?id=3&print=0#top")
+                .as_deref(),
+            Some("https://example.com/a?id=3&print=1#top")
+        );
+        let path: PrintView = "/print/".parse().unwrap();
+        assert_eq!(
+            path.apply("https://example.com/story/").as_deref(),
+            Some("https://example.com/story/print/")
+        );
+        assert_eq!(PrintView::Off.apply("https://example.com/"), None);
+        assert!("print".parse::<PrintView>().is_err());
+        assert_eq!(query.to_string(), "?print=1");
+    }
+
+    #[test]
+    fn test_candidates() -> Result<()> {
+        let profiles = SiteProfiles::new()
+            .with_profile(
+                "news.example",
+                SiteProfile {
+                    print_view: Some("/print".parse()?),
+                },
+            )
+            .with_profile(
+                "blog.example",
+                SiteProfile {
+                    print_view: Some(PrintView::Off),
+                },
+            );
+        let print_views = PrintViews::new(profiles.clone(), true)?;
+        assert_eq!(
+            print_views.candidates("https://news.example/a"),
+            vec!["https://news.example/a/print"]
+        );
+        assert!(print_views.candidates("https://blog.example/a").is_empty());
+        assert_eq!(
+            print_views.candidates("https://other.example/a"),
+            vec![
+                "https://other.example/a?print=1",
+                "https://other.example/a/print/"
+            ]
+        );
+
+        // Without the heuristic, only profiles are used
+        let print_views = PrintViews::new(profiles, false)?;
+        assert!(print_views.candidates("https://other.example/a").is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,261 @@
+//! Product prices on captured pages
+//!
+//! Shop pages describe their products for search engines as schema.org `Product` items
+//! with `Offer`s in JSON-LD, or with the Open Graph `product:price` tags. The price,
+//! currency, and availability found there are recorded in the output manifest every
+//! time a product page is captured, which builds up the price history of the product
+//! when its page is captured on a schedule.
+
+use chrono::{DateTime, Utc};
+use select::document::Document;
+use select::predicate::{Attr, Name};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// The price of a product when its page was captured
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PricePoint {
+    /// When the page was captured
+    pub checked_at: DateTime<Utc>,
+    /// The product name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The price, the lowest one if the product has several offers
+    pub price: f64,
+    /// ISO 4217 currency code, e.g. `USD`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
+    /// The schema.org availability, e.g. `InStock` or `OutOfStock`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub availability: Option<String>,
+}
+
+impl PricePoint {
+    /// Whether another point has the same price, currency, and availability
+    pub fn same_offer(&self, other: &PricePoint) -> bool {
+        self.price == other.price
+            && self.currency == other.currency
+            && self.availability == other.availability
+    }
+
+    /// The price with its currency, e.g. `19.99 USD`
+    pub fn display_price(&self) -> String {
+        match &self.currency {
+            Some(currency) => format!("{:.2} {}", self.price, currency),
+            None => format!("{:.2}", self.price),
+        }
+    }
+}
+
+/// The product price on a page, from schema.org data or Open Graph tags
+///
+/// Of several offers, or an `AggregateOffer`, the lowest price is taken.
+pub fn extract_price(html_content: &str, checked_at: DateTime<Utc>) -> Option<PricePoint> {
+    let document = Document::from(html_content);
+    let from_json_ld = document
+        .find(Attr("type", "application/ld+json"))
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text()).ok())
+        .find_map(|value| find_product(&value, checked_at));
+    from_json_ld.or_else(|| open_graph_price(&document, checked_at))
+}
+
+fn find_product(value: &Value, checked_at: DateTime<Utc>) -> Option<PricePoint> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .find_map(|value| find_product(value, checked_at)),
+        Value::Object(object) => {
+            let is_product = match object.get("@type") {
+                Some(Value::String(kind)) => is_product_type(kind),
+                Some(Value::Array(kinds)) => kinds
+                    .iter()
+                    .any(|kind| kind.as_str().is_some_and(is_product_type)),
+                _ => false,
+            };
+            if is_product {
+                let offer = object.get("offers").and_then(lowest_offer);
+                if let Some((price, currency, availability)) = offer {
+                    return Some(PricePoint {
+                        checked_at,
+                        name: object
+                            .get("name")
+                            .and_then(Value::as_str)
+                            .map(|name| name.trim().to_string())
+                            .filter(|name| !name.is_empty()),
+                        price,
+                        currency,
+                        availability,
+                    });
+                }
+            }
+            object
+                .values()
+                .find_map(|value| find_product(value, checked_at))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a schema.org type is a product, such as `Product`, `Book`, or `Car`
+fn is_product_type(kind: &str) -> bool {
+    let kind = kind.rsplit('/').next().unwrap_or(kind);
+    matches!(
+        kind,
+        "Product" | "ProductGroup" | "IndividualProduct" | "Book" | "Car" | "Vehicle"
+    )
+}
+
+/// The lowest price of an `Offer`, an `AggregateOffer`, or a list of offers, with its
+/// currency and availability
+fn lowest_offer(value: &Value) -> Option<(f64, Option<String>, Option<String>)> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .filter_map(lowest_offer)
+            .min_by(|a, b| a.0.total_cmp(&b.0)),
+        Value::Object(object) => {
+            let price = ["price", "lowPrice"]
+                .iter()
+                .find_map(|key| object.get(*key).and_then(price_value))
+                .or_else(|| {
+                    object
+                        .get("priceSpecification")
+                        .and_then(|spec| spec.get("price"))
+                        .and_then(price_value)
+                });
+            let Some(price) = price else {
+                return object.get("offers").and_then(lowest_offer);
+            };
+            let currency = object
+                .get("priceCurrency")
+                .or_else(|| {
+                    object
+                        .get("priceSpecification")
+                        .and_then(|spec| spec.get("priceCurrency"))
+                })
+                .and_then(Value::as_str)
+                .map(|currency| currency.trim().to_uppercase())
+                .filter(|currency| !currency.is_empty());
+            let availability = object
+                .get("availability")
+                .and_then(Value::as_str)
+                .map(|availability| {
+                    let availability = availability.trim();
+                    availability
+                        .rsplit('/')
+                        .next()
+                        .unwrap_or(availability)
+                        .to_string()
+                })
+                .filter(|availability| !availability.is_empty());
+            Some((price, currency, availability))
+        }
+        _ => None,
+    }
+}
+
+/// A price given as a number or as text
+fn price_value(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => parse_price(text),
+        _ => None,
+    }
+}
+
+/// Parse a price written with either `.` or `,` as the decimal separator, such as
+/// `1,299.00`, `1.299,00`, or `19,99`
+pub fn parse_price(text: &str) -> Option<f64> {
+    let number: String = text
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+        .collect();
+    // The last separator is the decimal one if both kinds are used, or if it is the
+    // only one and not followed by exactly three digits, which group thousands
+    let mixed = number.contains('.') && number.contains(',');
+    let separators = number.matches(['.', ',']).count();
+    let decimal = number
+        .rfind(['.', ','])
+        .filter(|at| mixed || (separators == 1 && number.len() - at - 1 != 3));
+    let normalized: String = number
+        .char_indices()
+        .filter_map(|(i, c)| match c {
+            '.' | ',' if Some(i) == decimal => Some('.'),
+            '.' | ',' => None,
+            c => Some(c),
+        })
+        .collect();
+    normalized
+        .parse()
+        .ok()
+        .filter(|price: &f64| price.is_finite())
+}
+
+/// The price in the Open Graph `product:price:amount` and `product:price:currency` tags
+fn open_graph_price(document: &Document, checked_at: DateTime<Utc>) -> Option<PricePoint> {
+    let meta = |property: &str| {
+        document
+            .find(Name("meta"))
+            .find(|meta| meta.attr("property") == Some(property))
+            .and_then(|meta| meta.attr("content"))
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+    };
+    let price = meta("product:price:amount")
+        .or_else(|| meta("og:price:amount"))
+        .and_then(|amount| parse_price(&amount))?;
+    Some(PricePoint {
+        checked_at,
+        name: meta("og:title"),
+        price,
+        currency: meta("product:price:currency")
+            .or_else(|| meta("og:price:currency"))
+            .map(|currency| currency.to_uppercase()),
+        availability: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_extract_price() {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 9, 0, 0).unwrap();
+        let html = r#"<script type="application/ld+json">{
+            "@context": "https://schema.org",
+            "@graph": [{"@type": "BreadcrumbList"}, {
+                "@type": "Product",
+                "name": "Ferris Plush",
+                "offers": [
+                    {"@type": "Offer", "price": "24.99", "priceCurrency": "usd",
+                     "availability": "https://schema.org/InStock"},
+                    {"@type": "Offer", "price": 19.5, "priceCurrency": "USD",
+                     "availability": "https://schema.org/OutOfStock"}
+                ]
+            }]
+        }</script>"#;
+        let point = extract_price(html, at).unwrap();
+        assert_eq!(point.name.as_deref(), Some("Ferris Plush"));
+        assert_eq!(point.price, 19.5);
+        assert_eq!(point.availability.as_deref(), Some("OutOfStock"));
+        assert_eq!(point.display_price(), "19.50 USD");
+
+        let aggregate = r#"<script type="application/ld+json">{"@type": "Product", "name": "Desk",
+            "offers": {"@type": "AggregateOffer", "lowPrice": "1.299,00", "priceCurrency": "EUR"}}</script>"#;
+        assert_eq!(extract_price(aggregate, at).unwrap().price, 1299.0);
+
+        let open_graph = r#"<meta property="og:title" content="Mug">
+            <meta property="product:price:amount" content="12.00">
+            <meta property="product:price:currency" content="JPY">"#;
+        let point = extract_price(open_graph, at).unwrap();
+        assert_eq!(point.display_price(), "12.00 JPY");
+        assert!(extract_price("<p>No product</p>", at).is_none());
+
+        assert_eq!(parse_price("$1,299.00"), Some(1299.0));
+        assert_eq!(parse_price("19,99 €"), Some(19.99));
+        assert_eq!(parse_price("1,000"), Some(1000.0));
+        assert_eq!(parse_price("free"), None);
+    }
+}
@@ -0,0 +1,125 @@
+//! Per-domain site profiles
+//!
+//! Some settings only make sense for particular sites. They are kept in a JSON file that
+//! maps domains to profiles:
+//!
+//! ```json
+//! {
+//!   "example.com": { "print_view": "?print=1" },
+//!   "news.example.org": { "print_view": "/print/" },
+//!   "blog.example.net": { "print_view": "off" }
+//! }
+//! ```
+//!
+//! A profile applies to its domain and all of its subdomains; the most specific domain
+//! wins.
+
+use crate::printview::PrintView;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Settings for one site
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteProfile {
+    /// How to turn a page URL into its print view, or `off` to always use the original
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub print_view: Option<PrintView>,
+}
+
+/// Site profiles by domain
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SiteProfiles {
+    domains: HashMap<String, SiteProfile>,
+}
+
+impl SiteProfiles {
+    /// Create an empty set of profiles
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load profiles from a JSON file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid profiles file
+    pub async fn load(path: &Path) -> Result<Self> {
+        let data = tokio::fs::read(path)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let profiles: Self = serde_json::from_slice(&data)
+            .map_err(|e| anyhow::anyhow!("Invalid site profiles {}: {}", path.display(), e))?;
+        Ok(Self {
+            domains: profiles
+                .domains
+                .into_iter()
+                .map(|(domain, profile)| {
+                    (domain.trim_start_matches("www.").to_lowercase(), profile)
+                })
+                .collect(),
+        })
+    }
+
+    /// Set the profile of a domain and its subdomains
+    pub fn with_profile(mut self, domain: &str, profile: SiteProfile) -> Self {
+        self.domains
+            .insert(domain.trim_start_matches("www.").to_lowercase(), profile);
+        self
+    }
+
+    /// The profile for the domain of a URL, if any
+    pub fn for_url(&self, url: &str) -> Option<&SiteProfile> {
+        let host = url::Url::parse(url).ok()?.host_str()?.to_lowercase();
+        let mut domain = host.as_str();
+        loop {
+            if let Some(profile) = self.domains.get(domain) {
+                return Some(profile);
+            }
+            domain = domain.split_once('.')?.1;
+        }
+    }
+
+    /// Whether no profiles are defined
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_load_and_match_profiles() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let path = temp_dir.path().join("sites.json");
+        std::fs::write(
+            &path,
+            r#"{
+                "www.example.com": { "print_view": "?print=1" },
+                "news.example.com": { "print_view": "off" }
+            }"#,
+        )?;
+        let profiles = SiteProfiles::load(&path).await?;
+
+        let print_view = |url| profiles.for_url(url).and_then(|p| p.print_view.clone());
+        assert_eq!(print_view("https://example.com/a"), "?print=1".parse().ok());
+        assert_eq!(
+            print_view("https://blog.example.com/a"),
+            "?print=1".parse().ok()
+        );
+        assert_eq!(
+            print_view("https://news.example.com/a"),
+            Some(PrintView::Off)
+        );
+        assert!(profiles.for_url("https://example.org/").is_none());
+
+        std::fs::write(&path, r#"{"example.com": {"print": "?print=1"}}"#)?;
+        assert!(SiteProfiles::load(&path).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,443 @@
+//! Reader-mode PDFs
+//!
+//! Instead of printing the page as the site lays it out, reader mode extracts the main
+//! content of the page (as for Markdown output), wraps the cleaned HTML in a print
+//! template with article-style typography, and renders that. The result is free of
+//! site navigation, sidebars, and ads. The built-in template can be replaced by a
+//! [Tera](https://keats.github.io/tera/) template using `title`, `url`, `base_url`,
+//! `site`, `captured_at`, `captured` (a phrase such as "Captured March 5, 2026 14:30
+//! UTC" in the configured [`Language`]), `show_title`, `content`, `lang`, the custom
+//! font CSS in `font_faces` and `font_family` (see [`crate::fonts`]), and the
+//! [`Typography`] settings in `typography`.
+
+use crate::extract::{self, ExtractConfig};
+use crate::fonts::{self, FontCss, ReaderFont};
+use crate::highlight::CodeHighlighter;
+use crate::i18n::Language;
+use crate::paper::Paper;
+use anyhow::Result;
+use chrono::Utc;
+use select::document::Document;
+use select::predicate::Name;
+use serde::Serialize;
+use tera::{Context, Tera};
+
+const READER_TEMPLATE: &str = "reader.html";
+
+/// Built-in reader-mode template
+pub const DEFAULT_READER_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html{% if lang %} lang="{{ lang }}"{% endif %}>
+<head>
+<meta charset="utf-8">
+{% if base_url %}<base href="{{ base_url }}">{% endif %}
+<title>{{ title }}</title>
+<style>
+{{ font_faces | safe }}
+  @page { {% if typography.page_size %}size: {{ typography.page_size }}; {% endif %}margin: {{ typography.page_margin }}; }
+  html { font-size: {{ typography.font_size }}pt; }
+  body {
+    margin: 0 auto;
+    max-width: {{ typography.line_width }}em;
+    font-family: {% if font_family %}{{ font_family | safe }}, {% endif %}"Charter", "Bitstream Charter", "Georgia", "Noto Serif", "Times New Roman", serif;
+    line-height: {{ typography.line_height }};
+    text-align: {% if typography.justify %}justify{% else %}left{% endif %};
+    hyphens: {% if typography.hyphenate %}auto{% else %}manual{% endif %};
+    color: #1a1a1a;
+    text-rendering: optimizeLegibility;
+    font-kerning: normal;
+  }
+  header { margin-bottom: 2em; padding-bottom: 1em; border-bottom: 1px solid #ccc; }
+  header h1 { font-size: 2em; line-height: 1.2; margin: 0 0 0.4em; }
+  header .source { font-family: sans-serif; font-size: 0.75em; color: #666; word-break: break-all; }
+  h1, h2, h3, h4, h5, h6 { line-height: 1.25; margin: 1.6em 0 0.5em; break-after: avoid; text-align: left; hyphens: manual; }
+  h2 { font-size: 1.45em; }
+  h3 { font-size: 1.2em; }
+  h4, h5, h6 { font-size: 1em; }
+  p { margin: 0 0 0.9em; orphans: 3; widows: 3; }
+  a { color: inherit; }
+  img, video, svg, figure { max-width: 100%; height: auto; }
+  figure { margin: 1.2em 0; break-inside: avoid; }
+  figcaption { font-size: 0.85em; color: #555; }
+  blockquote { margin: 1em 0; padding-left: 1em; border-left: 3px solid #ccc; color: #444; }
+  pre, code { font-family: "DejaVu Sans Mono", "Menlo", "Consolas", monospace; font-size: 0.85em; }
+  pre { white-space: pre-wrap; word-wrap: break-word; background: #f6f6f6; padding: 0.8em; break-inside: avoid; text-align: left; hyphens: manual; }
+  table { border-collapse: collapse; margin: 1em 0; font-size: 0.9em; text-align: left; }
+  th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; }
+  hr { border: 0; border-top: 1px solid #ccc; margin: 2em 0; }
+</style>
+</head>
+<body>
+<header>
+  {% if show_title %}<h1>{{ title }}</h1>{% endif %}
+  <div class="source">{% if url %}{{ url }} · {% endif %}{{ captured }}</div>
+</header>
+<article>
+{{ content | safe }}
+</article>
+</body>
+</html>"#;
+
+/// Starting points for reader-mode typography
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TypographyPreset {
+    /// Comfortable on-screen reading: ragged right, generous line spacing
+    #[default]
+    Screen,
+    /// Denser text for printing on paper, justified and hyphenated
+    Print,
+    /// Small pages with large text for e-readers and phones
+    Ereader,
+}
+
+/// Typography of reader-mode documents, available to templates as `typography`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Typography {
+    /// Body text size in points
+    pub font_size: f64,
+    /// Maximum line length in ems
+    pub line_width: f64,
+    /// Line height as a multiple of the text size
+    pub line_height: f64,
+    /// Whether body text is justified rather than ragged right
+    pub justify: bool,
+    /// Whether words are hyphenated at line ends (in the page's declared language)
+    pub hyphenate: bool,
+    /// CSS page size such as `90mm 120mm`, or None for the PDF paper size
+    pub page_size: Option<String>,
+    /// CSS page margins
+    pub page_margin: String,
+}
+
+impl Default for Typography {
+    fn default() -> Self {
+        Self::preset(TypographyPreset::default())
+    }
+}
+
+impl Typography {
+    /// The typography of a preset
+    pub fn preset(preset: TypographyPreset) -> Self {
+        match preset {
+            TypographyPreset::Screen => Self {
+                font_size: 11.5,
+                line_width: 36.0,
+                line_height: 1.55,
+                justify: false,
+                hyphenate: false,
+                page_size: None,
+                page_margin: "2cm 2.2cm".to_string(),
+            },
+            TypographyPreset::Print => Self {
+                font_size: 10.5,
+                line_width: 38.0,
+                line_height: 1.45,
+                justify: true,
+                hyphenate: true,
+                page_size: None,
+                page_margin: "2.2cm 2.5cm".to_string(),
+            },
+            TypographyPreset::Ereader => Self {
+                font_size: 14.0,
+                line_width: 30.0,
+                line_height: 1.5,
+                justify: false,
+                hyphenate: true,
+                page_size: Some("90mm 120mm".to_string()),
+                page_margin: "5mm 4mm".to_string(),
+            },
+        }
+    }
+
+    /// Set the body text size in points
+    pub fn with_font_size(mut self, font_size: f64) -> Self {
+        self.font_size = font_size;
+        self
+    }
+
+    /// Set the maximum line length in ems
+    pub fn with_line_width(mut self, line_width: f64) -> Self {
+        self.line_width = line_width;
+        self
+    }
+
+    /// Justify body text or leave it ragged right
+    pub fn with_justify(mut self, justify: bool) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    /// Hyphenate words at line ends or not
+    pub fn with_hyphenate(mut self, hyphenate: bool) -> Self {
+        self.hyphenate = hyphenate;
+        self
+    }
+
+    /// Lay out pages for a paper preset, replacing the preset's own page size and
+    /// margins
+    pub fn with_paper(mut self, paper: Paper) -> Self {
+        let (width, height) = paper.size();
+        self.page_size = Some(format!("{}in {}in", width, height));
+        self.page_margin = format!("{}in", paper.margin());
+        self
+    }
+}
+
+/// Reader-mode rendering: main-content extraction wrapped in a print template
+#[derive(Debug, Clone)]
+pub struct ReaderMode {
+    tera: Tera,
+    extraction: ExtractConfig,
+    fonts: FontCss,
+    typography: Typography,
+    highlighter: Option<CodeHighlighter>,
+    language: Language,
+}
+
+impl Default for ReaderMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReaderMode {
+    /// Create reader mode with the built-in template and extraction
+    pub fn new() -> Self {
+        let mut tera = Tera::default();
+        tera.add_raw_template(READER_TEMPLATE, DEFAULT_READER_TEMPLATE)
+            .expect("built-in reader template is valid");
+        Self {
+            tera,
+            extraction: ExtractConfig::default(),
+            fonts: FontCss::default(),
+            typography: Typography::default(),
+            highlighter: None,
+            language: Language::default(),
+        }
+    }
+
+    /// Use a custom template instead of the built-in one
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template cannot be parsed
+    pub fn with_template(mut self, template: &str) -> Result<Self> {
+        self.tera.add_raw_template(READER_TEMPLATE, template)?;
+        Ok(self)
+    }
+
+    /// Configure how the main content is extracted from pages
+    pub fn with_extraction(mut self, extraction: ExtractConfig) -> Self {
+        self.extraction = extraction;
+        self
+    }
+
+    /// Set the text in these fonts, in order, with later fonts used for characters the
+    /// earlier ones lack (such as a CJK font after a Latin one)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a font file cannot be read, or an installed font is not found
+    pub fn with_fonts(mut self, fonts: &[ReaderFont]) -> Result<Self> {
+        self.fonts = fonts::font_css(fonts)?;
+        Ok(self)
+    }
+
+    /// Set the typography of the rendered document
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text size, line width, or line height is not positive
+    pub fn with_typography(mut self, typography: Typography) -> Result<Self> {
+        for (name, value) in [
+            ("Font size", typography.font_size),
+            ("Line width", typography.line_width),
+            ("Line height", typography.line_height),
+        ] {
+            if !(value.is_finite() && value > 0.0) {
+                return Err(anyhow::anyhow!("{} must be positive, got {}", name, value));
+            }
+        }
+        self.typography = typography;
+        Ok(self)
+    }
+
+    /// Highlight the syntax of code blocks in the extracted content
+    pub fn with_highlighter(mut self, highlighter: CodeHighlighter) -> Self {
+        self.highlighter = Some(highlighter);
+        self
+    }
+
+    /// Write the capture time in this language
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Render the reader-mode HTML document for the parts of a page
+    ///
+    /// The main content of every part is included in order; the title comes from the
+    /// first part. Relative references resolve against `base_url`, or the first part's
+    /// own `<base href>` if not given. `url` is the page URL shown in the document.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there are no parts or the template fails to render
+    pub fn render(
+        &self,
+        parts: &[String],
+        base_url: Option<&str>,
+        url: Option<&str>,
+    ) -> Result<String> {
+        let documents: Vec<Document> = parts
+            .iter()
+            .map(|part| Document::from(part.as_str()))
+            .collect();
+        let first = documents
+            .first()
+            .ok_or_else(|| anyhow::anyhow!("No page content to render"))?;
+
+        let title = extract::find_title(first).unwrap_or_else(|| "Untitled".to_string());
+        let base_url = base_url.map(str::to_string).or_else(|| {
+            first
+                .find(Name("base"))
+                .find_map(|base| base.attr("href").map(str::to_string))
+        });
+        let url = url.map(str::to_string).or_else(|| base_url.clone());
+        // Hyphenation dictionaries are chosen by the document language
+        let lang = first
+            .find(Name("html"))
+            .find_map(|html| html.attr("lang").map(str::trim))
+            .filter(|lang| {
+                !lang.is_empty() && lang.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+            });
+
+        let mut content = String::new();
+        let mut show_title = true;
+        for (index, document) in documents.iter().enumerate() {
+            let Some(main) = self.extraction.require_main(document)? else {
+                continue;
+            };
+            // Skip the template heading when the article starts with its own title
+            if index == 0 {
+                show_title = main
+                    .find(Name("h1"))
+                    .next()
+                    .is_none_or(|heading| heading.text().trim() != title);
+            }
+            content.push_str(&self.extraction.html(&main));
+            content.push('\n');
+        }
+        if let Some(highlighter) = &self.highlighter {
+            content = highlighter.highlight_html(&content);
+        }
+
+        let mut context = Context::new();
+        context.insert("title", &title);
+        context.insert("site", &url.as_deref().and_then(site_of));
+        context.insert("url", &url);
+        context.insert("base_url", &base_url);
+        let now = Utc::now();
+        context.insert("captured_at", &self.language.format_datetime(&now));
+        context.insert("captured", &self.language.captured(&now));
+        context.insert("show_title", &show_title);
+        context.insert("content", &content);
+        context.insert("font_faces", &self.fonts.font_faces);
+        context.insert("font_family", &self.fonts.font_family);
+        context.insert("lang", &lang);
+        context.insert("typography", &self.typography);
+        Ok(self.tera.render(READER_TEMPLATE, &context)?)
+    }
+}
+
+fn site_of(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|host| host.trim_start_matches("www.").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<html><head><title>Story - News</title></head><body>
+        <nav>Home | World</nav>
+        <article><h1>Story</h1><p>First &amp; <img src="a.png"></p>
+        <div class="share-buttons">Share</div></article>
+        <footer>Copyright</footer>
+    </body></html>"#;
+
+    #[test]
+    fn test_render_reader_html() -> Result<()> {
+        let html = ReaderMode::new().render(
+            &[
+                PAGE.to_string(),
+                "<article><p>Second part</p></article>".to_string(),
+            ],
+            Some("https://news.example/story"),
+            None,
+        )?;
+        // Tera escapes the slashes, which browsers decode in attributes
+        assert!(html.contains(r#"<base href="https:&#x2F;&#x2F;news.example&#x2F;story">"#));
+        assert!(html.contains("<title>Story</title>"));
+        assert!(html.contains("<p>First &amp; <img src=\"a.png\"></p>"));
+        assert!(html.contains("Second part"));
+        for chrome in ["Home | World", "Share", "Copyright"] {
+            assert!(!html.contains(chrome), "{}", chrome);
+        }
+        // The article's own heading is kept instead of the template's
+        assert_eq!(html.matches("<h1>Story</h1>").count(), 1);
+        assert!(html.contains("html { font-size: 11.5pt; }"));
+        assert!(html.contains("text-align: left;\n    hyphens: manual;"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_typography() -> Result<()> {
+        let page = PAGE.replace("<html>", "<html lang=\"de-CH\">");
+        let reader = ReaderMode::new().with_typography(
+            Typography::preset(TypographyPreset::Ereader)
+                .with_font_size(15.0)
+                .with_justify(true),
+        )?;
+        let html = reader.render(&[page], None, None)?;
+        assert!(html.contains("<html lang=\"de-CH\">"));
+        assert!(html.contains("@page { size: 90mm 120mm; margin: 5mm 4mm; }"));
+        assert!(html.contains("html { font-size: 15pt; }"));
+        assert!(html.contains("max-width: 30em;"));
+        assert!(html.contains("text-align: justify;\n    hyphens: auto;"));
+
+        let kindle = Typography::preset(TypographyPreset::Ereader).with_paper(Paper::Kindle);
+        let html =
+            ReaderMode::new()
+                .with_typography(kindle)?
+                .render(&[PAGE.to_string()], None, None)?;
+        assert!(html.contains("@page { size: 4.12in 5.49in; margin: 0.15in; }"));
+
+        let invalid = Typography::default().with_line_width(0.0);
+        assert!(ReaderMode::new().with_typography(invalid).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_template() -> Result<()> {
+        let reader = ReaderMode::new().with_template("{{ site }}: {{ title }}")?;
+        let html = reader.render(
+            &[PAGE.to_string()],
+            None,
+            Some("https://www.news.example/s"),
+        )?;
+        assert_eq!(html, "news.example: Story");
+        assert!(ReaderMode::new().with_template("{% if %}").is_err());
+
+        let temp_dir = tempfile::TempDir::new()?;
+        let font = temp_dir.path().join("Reading-Regular.woff2");
+        std::fs::write(&font, b"font")?;
+        let html = ReaderMode::new()
+            .with_fonts(&[ReaderFont::File(font)])?
+            .render(&[PAGE.to_string()], None, None)?;
+        assert!(html.contains("@font-face { font-family: \"Reading\"; src: url(data:font/woff2;"));
+        assert!(html.contains("font-family: \"Reading\", \"Charter\""));
+        Ok(())
+    }
+}
@@ -0,0 +1,427 @@
+//! Recipes read from the structured data of recipe pages
+//!
+//! Recipe sites bury the recipe between life stories, ads, and comment sections, but
+//! almost all of them describe it for search engines as a schema.org `Recipe` in JSON-LD.
+//! That item is read into a [`Recipe`] with its ingredients, steps (grouped into the
+//! sections of `HowToSection`s), times, and yield, and converted to a compact Markdown
+//! page in place of the page itself, and to JSON for meal planners and other tools.
+
+use crate::frontmatter::PageMetadata;
+use crate::markdown::MarkdownPage;
+use regex::Regex;
+use select::document::Document;
+use select::predicate::Attr;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// An ISO 8601 duration such as `PT1H30M` or `P0DT0H45M`
+static DURATION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^P(?:(\d+(?:\.\d+)?)D)?(?:T(?:(\d+(?:\.\d+)?)H)?(?:(\d+(?:\.\d+)?)M)?(?:(\d+(?:\.\d+)?)S)?)?$",
+    )
+    .expect("valid duration pattern")
+});
+
+/// A recipe described on a page
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recipe {
+    /// The name of the dish
+    pub name: String,
+    /// The page of the recipe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// A short description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Who wrote the recipe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// URL of a photo of the dish
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    /// How much the recipe makes, e.g. `4 servings`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub yields: Option<String>,
+    /// Preparation time in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prep_minutes: Option<u32>,
+    /// Cooking time in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cook_minutes: Option<u32>,
+    /// Total time in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_minutes: Option<u32>,
+    /// Ingredients with their amounts, as written on the page
+    pub ingredients: Vec<String>,
+    /// The steps, in sections if the recipe has them
+    pub instructions: Vec<RecipeSection>,
+}
+
+/// A group of steps of a recipe, such as the steps for its sauce
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecipeSection {
+    /// The section name, or None for the steps of a recipe without sections
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The steps, in order
+    pub steps: Vec<String>,
+}
+
+impl Recipe {
+    /// Render the recipe as a Markdown page, with the name as its title
+    pub fn to_page(&self) -> MarkdownPage {
+        let mut content = String::new();
+        if let Some(description) = &self.description {
+            content.push_str(&format!("{}\n\n", description));
+        }
+        if let Some(image) = &self.image {
+            content.push_str(&format!("![{}]({})\n\n", self.name, image));
+        }
+        let facts = [
+            ("Yield", self.yields.clone()),
+            ("Prep time", self.prep_minutes.map(format_minutes)),
+            ("Cook time", self.cook_minutes.map(format_minutes)),
+            ("Total time", self.total_minutes.map(format_minutes)),
+            ("Author", self.author.clone()),
+        ];
+        let facts: Vec<String> = facts
+            .into_iter()
+            .filter_map(|(label, value)| value.map(|value| format!("- **{}:** {}", label, value)))
+            .collect();
+        if !facts.is_empty() {
+            content.push_str(&format!("{}\n\n", facts.join("\n")));
+        }
+        if !self.ingredients.is_empty() {
+            content.push_str("## Ingredients\n\n");
+            for ingredient in &self.ingredients {
+                content.push_str(&format!("- {}\n", ingredient));
+            }
+            content.push('\n');
+        }
+        if !self.instructions.is_empty() {
+            content.push_str("## Instructions\n\n");
+            for section in &self.instructions {
+                if let Some(name) = &section.name {
+                    content.push_str(&format!("### {}\n\n", name));
+                }
+                for (i, step) in section.steps.iter().enumerate() {
+                    content.push_str(&format!("{}. {}\n", i + 1, step));
+                }
+                content.push('\n');
+            }
+        }
+        MarkdownPage {
+            title: self.name.clone(),
+            url: self.url.clone(),
+            content: format!("{}\n", content.trim_end()),
+            metadata: PageMetadata {
+                author: self.author.clone(),
+                description: self.description.clone(),
+                image: self.image.clone(),
+                language: None,
+            },
+        }
+    }
+
+    /// Render the recipe as pretty-printed JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// The path of the JSON file saved next to the Markdown of a recipe, e.g.
+/// `pancakes.recipe.json` for `pancakes.md`
+pub fn json_path(markdown_path: &Path) -> PathBuf {
+    markdown_path.with_extension("recipe.json")
+}
+
+/// The first schema.org recipe in the JSON-LD of a page
+///
+/// Recipes without a name or without both ingredients and steps are skipped.
+/// `page_url` is the URL of a recipe that does not name its own.
+pub fn extract_recipe(html_content: &str, page_url: Option<&str>) -> Option<Recipe> {
+    let document = Document::from(html_content);
+    document
+        .find(Attr("type", "application/ld+json"))
+        .filter_map(|script| serde_json::from_str::<Value>(&script.text()).ok())
+        .find_map(|value| find_recipe(&value, page_url))
+}
+
+fn find_recipe(value: &Value, page_url: Option<&str>) -> Option<Recipe> {
+    match value {
+        Value::Array(values) => values.iter().find_map(|value| find_recipe(value, page_url)),
+        Value::Object(object) => {
+            let is_recipe = match object.get("@type") {
+                Some(Value::String(kind)) => kind.ends_with("Recipe"),
+                Some(Value::Array(kinds)) => kinds
+                    .iter()
+                    .any(|kind| kind.as_str().is_some_and(|kind| kind.ends_with("Recipe"))),
+                _ => false,
+            };
+            if !is_recipe {
+                return object
+                    .values()
+                    .find_map(|value| find_recipe(value, page_url));
+            }
+
+            let text = |key: &str| object.get(key).and_then(first_text);
+            let recipe = Recipe {
+                name: text("name")?,
+                url: text("url").or(page_url.map(str::to_string)),
+                description: text("description"),
+                author: object.get("author").and_then(name_of),
+                image: object.get("image").and_then(image_url),
+                yields: object.get("recipeYield").and_then(yields),
+                prep_minutes: text("prepTime").and_then(|time| parse_minutes(&time)),
+                cook_minutes: text("cookTime").and_then(|time| parse_minutes(&time)),
+                total_minutes: text("totalTime").and_then(|time| parse_minutes(&time)),
+                ingredients: object
+                    .get("recipeIngredient")
+                    .or(object.get("ingredients"))
+                    .map(texts)
+                    .unwrap_or_default(),
+                instructions: object
+                    .get("recipeInstructions")
+                    .map(sections)
+                    .unwrap_or_default(),
+            };
+            (!recipe.ingredients.is_empty() || !recipe.instructions.is_empty()).then_some(recipe)
+        }
+        _ => None,
+    }
+}
+
+/// The steps of `recipeInstructions`: text, a list of texts and `HowToStep`s, or
+/// `HowToSection`s of steps
+fn sections(value: &Value) -> Vec<RecipeSection> {
+    let mut sections = Vec::new();
+    let mut loose = Vec::new();
+    let items = match value {
+        Value::Array(items) => items.clone(),
+        value => vec![value.clone()],
+    };
+    for item in &items {
+        let is_section = item
+            .get("@type")
+            .and_then(Value::as_str)
+            .is_some_and(|kind| kind.ends_with("HowToSection"));
+        if is_section {
+            if !loose.is_empty() {
+                sections.push(RecipeSection {
+                    name: None,
+                    steps: std::mem::take(&mut loose),
+                });
+            }
+            let steps = item.get("itemListElement").map(steps).unwrap_or_default();
+            if !steps.is_empty() {
+                sections.push(RecipeSection {
+                    name: item.get("name").and_then(first_text),
+                    steps,
+                });
+            }
+        } else {
+            loose.extend(steps(item));
+        }
+    }
+    if !loose.is_empty() {
+        sections.push(RecipeSection {
+            name: None,
+            steps: loose,
+        });
+    }
+    sections
+}
+
+/// The steps of text (one per line), a `HowToStep`, or a list of them
+fn steps(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(text) => plain_text(text)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(str::to_string)
+            .collect(),
+        Value::Array(values) => values.iter().flat_map(steps).collect(),
+        Value::Object(object) => object
+            .get("text")
+            .or(object.get("name"))
+            .or(object.get("itemListElement"))
+            .map(steps)
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// The texts of a value that is a text or a list of texts
+fn texts(value: &Value) -> Vec<String> {
+    match value {
+        Value::Array(values) => values.iter().filter_map(first_text).collect(),
+        value => first_text(value).into_iter().collect(),
+    }
+}
+
+/// A text, or the first text of a list, as plain text without markup
+fn first_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(text) => Some(plain_text(text)).filter(|text| !text.is_empty()),
+        Value::Number(number) => Some(number.to_string()),
+        Value::Array(values) => values.iter().find_map(first_text),
+        _ => None,
+    }
+}
+
+/// The name of a `Person` or `Organization`, or of the first of a list of them
+fn name_of(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(object) => object.get("name").and_then(first_text),
+        Value::Array(values) => values.iter().find_map(name_of),
+        value => first_text(value),
+    }
+}
+
+/// The URL of an image given as a URL, an `ImageObject`, or a list of them
+fn image_url(value: &Value) -> Option<String> {
+    match value {
+        Value::Object(object) => object.get("url").and_then(first_text),
+        Value::Array(values) => values.iter().find_map(image_url),
+        value => first_text(value),
+    }
+}
+
+/// The yield of a recipe, preferring a text with a unit (`4 servings`) over a number
+fn yields(value: &Value) -> Option<String> {
+    let yields = texts(value);
+    yields
+        .iter()
+        .find(|text| text.chars().any(char::is_alphabetic))
+        .or(yields.first())
+        .cloned()
+}
+
+/// Text with markup removed, entities decoded, and whitespace collapsed within lines
+fn plain_text(text: &str) -> String {
+    let text = match text.contains('<') || text.contains('&') {
+        true => Document::from(text.replace("<br", "\n<br").as_str())
+            .nth(0)
+            .map(|root| root.text())
+            .unwrap_or_default(),
+        false => text.to_string(),
+    };
+    text.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// The minutes of an ISO 8601 duration such as `PT1H30M`, rounded to the nearest minute
+pub fn parse_minutes(duration: &str) -> Option<u32> {
+    let captures = DURATION.captures(duration.trim())?;
+    let part = |i: usize| {
+        captures
+            .get(i)
+            .and_then(|part| part.as_str().parse::<f64>().ok())
+            .unwrap_or(0.0)
+    };
+    let minutes = part(1) * 24.0 * 60.0 + part(2) * 60.0 + part(3) + part(4) / 60.0;
+    (minutes > 0.0).then(|| minutes.round() as u32)
+}
+
+/// Minutes as hours and minutes, e.g. `1 h 30 min`
+fn format_minutes(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{} min", minutes),
+        (hours, 0) => format!("{} h", hours),
+        (hours, minutes) => format!("{} h {} min", hours, minutes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_recipe() {
+        let html = r#"<html><head><script type="application/ld+json">{
+            "@context": "https://schema.org",
+            "@graph": [
+                {"@type": "WebPage", "name": "Best pancakes ever"},
+                {
+                    "@type": "Recipe",
+                    "name": "Fluffy Pancakes",
+                    "author": [{"@type": "Person", "name": "Ferris"}],
+                    "image": {"@type": "ImageObject", "url": "https://example.com/pancakes.jpg"},
+                    "recipeYield": ["4", "4 servings"],
+                    "prepTime": "PT10M",
+                    "cookTime": "PT20M",
+                    "totalTime": "P0DT1H30M",
+                    "recipeIngredient": ["200 g flour", "2 eggs &amp; milk"],
+                    "recipeInstructions": [
+                        {"@type": "HowToStep", "text": "Mix <b>everything</b>."},
+                        {
+                            "@type": "HowToSection",
+                            "name": "Topping",
+                            "itemListElement": [{"@type": "HowToStep", "text": "Add syrup."}]
+                        }
+                    ]
+                }
+            ]
+        }</script></head><body><p>A long story</p></body></html>"#;
+
+        let recipe = extract_recipe(html, Some("https://example.com/pancakes")).unwrap();
+        assert_eq!(recipe.author.as_deref(), Some("Ferris"));
+        assert_eq!(recipe.yields.as_deref(), Some("4 servings"));
+        assert_eq!(recipe.total_minutes, Some(90));
+        assert_eq!(recipe.ingredients, vec!["200 g flour", "2 eggs & milk"]);
+        assert_eq!(
+            recipe.instructions,
+            vec![
+                RecipeSection {
+                    name: None,
+                    steps: vec!["Mix everything.".to_string()],
+                },
+                RecipeSection {
+                    name: Some("Topping".to_string()),
+                    steps: vec!["Add syrup.".to_string()],
+                },
+            ]
+        );
+
+        let page = recipe.to_page();
+        assert_eq!(page.title, "Fluffy Pancakes");
+        assert_eq!(page.url.as_deref(), Some("https://example.com/pancakes"));
+        assert!(
+            page.content
+                .contains("- **Yield:** 4 servings\n- **Prep time:** 10 min\n")
+        );
+        assert!(page.content.contains("- **Total time:** 1 h 30 min\n"));
+        assert!(page.content.contains("## Ingredients\n\n- 200 g flour\n"));
+        assert!(
+            page.content.contains(
+                "## Instructions\n\n1. Mix everything.\n\n### Topping\n\n1. Add syrup.\n"
+            )
+        );
+
+        let json: Value = serde_json::from_str(&recipe.to_json()).unwrap();
+        assert_eq!(json["cook_minutes"], 20);
+        assert_eq!(json["instructions"][1]["name"], "Topping");
+        assert_eq!(
+            json_path(Path::new("out/pancakes.md")),
+            PathBuf::from("out/pancakes.recipe.json")
+        );
+
+        let text_steps = r#"<script type="application/ld+json">{"@type": "Recipe",
+            "name": "Toast", "recipeInstructions": "Toast the bread.\nButter it."}</script>"#;
+        let recipe = extract_recipe(text_steps, None).unwrap();
+        assert_eq!(
+            recipe.instructions[0].steps,
+            vec!["Toast the bread.", "Butter it."]
+        );
+        assert!(extract_recipe("<p>No recipe</p>", None).is_none());
+        assert_eq!(parse_minutes("PT0.5H"), Some(30));
+        assert_eq!(parse_minutes("soon"), None);
+    }
+}
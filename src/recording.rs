@@ -0,0 +1,393 @@
+//! Recording and replay of the HTTP responses of a conversion
+//!
+//! In record mode, every response Chrome receives while loading pages (documents,
+//! stylesheets, scripts, images, XHRs) is captured through the DevTools Fetch domain,
+//! along with the pages the Markdown generator fetches. The responses are saved to one
+//! JSON file. In replay mode, requests are answered from that file instead of the
+//! network, and requests that were not recorded fail, so a conversion can be
+//! repeated byte for byte for debugging, or run in tests without network access.
+//!
+//! Responses are matched by method and URL. A URL requested several times is answered
+//! with its recorded responses in order, and with the last one after that.
+
+use crate::atomic;
+use anyhow::Result;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use headless_chrome::Tab;
+use headless_chrome::browser::tab::RequestPausedDecision;
+use headless_chrome::browser::transport::{SessionId, Transport};
+use headless_chrome::protocol::cdp::Fetch::events::RequestPausedEvent;
+use headless_chrome::protocol::cdp::Fetch::{
+    FailRequest, FulfillRequest, GetResponseBody, HeaderEntry, RequestPattern, RequestStage,
+};
+use headless_chrome::protocol::cdp::Network::ErrorReason;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, warn};
+
+/// Response headers left out of recordings, since recorded bodies are stored decoded
+/// and replayed whole
+const SKIPPED_HEADERS: &[&str] = &["content-encoding", "content-length", "transfer-encoding"];
+
+/// A recorded HTTP response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    /// Request method
+    pub method: String,
+    /// Request URL
+    pub url: String,
+    /// Response status code
+    pub status: u32,
+    /// Response headers, in order
+    pub headers: Vec<(String, String)>,
+    /// Base64-encoded response body
+    pub body: String,
+    /// Request headers, in order, when recorded from the browser
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub request_headers: Vec<(String, String)>,
+    /// When the response was received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+impl RecordedResponse {
+    /// Create a recorded response from a decoded body
+    ///
+    /// Headers describing the encoding of the body on the wire are left out.
+    pub fn new(
+        method: impl Into<String>,
+        url: impl Into<String>,
+        status: u32,
+        headers: Vec<(String, String)>,
+        body: &[u8],
+    ) -> Self {
+        Self {
+            method: method.into(),
+            url: url.into(),
+            status,
+            headers: headers
+                .into_iter()
+                .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.to_lowercase().as_str()))
+                .collect(),
+            body: base64::engine::general_purpose::STANDARD.encode(body),
+            request_headers: Vec::new(),
+            received_at: Some(Utc::now()),
+        }
+    }
+
+    /// Add the headers the request was sent with
+    pub fn with_request_headers(mut self, headers: Vec<(String, String)>) -> Self {
+        self.request_headers = headers;
+        self
+    }
+
+    /// The decoded response body
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body is not valid base64
+    pub fn body_bytes(&self) -> Result<Vec<u8>> {
+        Ok(base64::engine::general_purpose::STANDARD.decode(&self.body)?)
+    }
+}
+
+/// The contents of a recording file
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// Recorded responses, in the order they were received
+    pub responses: Vec<RecordedResponse>,
+}
+
+/// Whether responses are being recorded or replayed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingMode {
+    /// Load from the network, recording every response
+    Record,
+    /// Answer requests from a recording, without network access
+    Replay,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    recording: Recording,
+    /// Number of responses replayed so far per method and URL
+    replayed: HashMap<(String, String), usize>,
+}
+
+/// Records responses into, or replays them from, a recording file
+///
+/// Clones share the same recording, so the PDF and Markdown generators can record
+/// into one file.
+#[derive(Debug, Clone)]
+pub struct HttpRecorder {
+    mode: RecordingMode,
+    path: PathBuf,
+    state: Arc<Mutex<State>>,
+}
+
+impl HttpRecorder {
+    /// Start a new recording, saved to `path` by [`HttpRecorder::save`]
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self {
+            mode: RecordingMode::Record,
+            path: path.into(),
+            state: Arc::default(),
+        }
+    }
+
+    /// Replay the recording saved at `path`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a recording
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let json = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        let recording: Recording = serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid recording {}: {}", path.display(), e))?;
+        Ok(Self {
+            mode: RecordingMode::Replay,
+            path,
+            state: Arc::new(Mutex::new(State {
+                recording,
+                replayed: HashMap::new(),
+            })),
+        })
+    }
+
+    /// Whether responses are recorded or replayed
+    pub fn mode(&self) -> RecordingMode {
+        self.mode
+    }
+
+    /// The recording file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of responses in the recording
+    pub fn len(&self) -> usize {
+        self.lock().recording.responses.len()
+    }
+
+    /// Whether the recording has no responses
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The recorded responses, in the order they were received
+    pub fn responses(&self) -> Vec<RecordedResponse> {
+        self.lock().recording.responses.clone()
+    }
+
+    /// Add a response to the recording
+    pub fn push(&self, response: RecordedResponse) {
+        debug!("Recorded {} {}", response.method, response.url);
+        self.lock().recording.responses.push(response);
+    }
+
+    /// The next recorded response for a request, or None when it was not recorded
+    pub fn lookup(&self, method: &str, url: &str) -> Option<RecordedResponse> {
+        let mut guard = self.lock();
+        let state = &mut *guard;
+        let matches: Vec<&RecordedResponse> = state
+            .recording
+            .responses
+            .iter()
+            .filter(|response| response.method == method && response.url == url)
+            .collect();
+        let last = matches.len().checked_sub(1)?;
+        let count = state
+            .replayed
+            .entry((method.to_string(), url.to_string()))
+            .or_default();
+        let response = matches[(*count).min(last)].clone();
+        *count += 1;
+        Some(response)
+    }
+
+    /// Record or replay the requests of a browser tab
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if request interception cannot be enabled
+    pub fn attach(&self, tab: &Tab) -> Result<()> {
+        let stage = match self.mode {
+            RecordingMode::Record => RequestStage::Response,
+            RecordingMode::Replay => RequestStage::Request,
+        };
+        tab.enable_fetch(
+            Some(&[RequestPattern {
+                url_pattern: Some("*".to_string()),
+                resource_Type: None,
+                request_stage: Some(stage),
+            }]),
+            // Keeps the proxy's authentication challenges answered (see crate::proxy)
+            Some(true),
+        )?;
+        let recorder = self.clone();
+        tab.enable_request_interception(Arc::new(
+            move |transport: Arc<Transport>, session_id: SessionId, event: RequestPausedEvent| {
+                recorder.intercept(&transport, session_id, event)
+            },
+        ))?;
+        Ok(())
+    }
+
+    /// Save the recording to its file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written, or the recorder is replaying
+    pub fn save(&self) -> Result<()> {
+        if self.mode != RecordingMode::Record {
+            return Err(anyhow::anyhow!("Only recordings can be saved"));
+        }
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_vec(&self.lock().recording)?;
+        atomic::write_blocking(&self.path, &json)
+    }
+
+    /// Handle a paused request: record its response, or answer it from the recording
+    fn intercept(
+        &self,
+        transport: &Transport,
+        session_id: SessionId,
+        event: RequestPausedEvent,
+    ) -> RequestPausedDecision {
+        let params = event.params;
+        let request = params.request;
+        match self.mode {
+            RecordingMode::Record => {
+                // Redirects and failed responses have no body
+                let body = transport
+                    .call_method_on_target(
+                        session_id,
+                        GetResponseBody {
+                            request_id: params.request_id,
+                        },
+                    )
+                    .ok()
+                    .and_then(|body| match body.base_64_encoded {
+                        true => base64::engine::general_purpose::STANDARD
+                            .decode(&body.body)
+                            .ok(),
+                        false => Some(body.body.into_bytes()),
+                    })
+                    .unwrap_or_default();
+                let headers = params
+                    .response_headers
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|header| (header.name, header.value))
+                    .collect();
+                let request_headers = match request.headers.0 {
+                    Some(serde_json::Value::Object(headers)) => headers
+                        .into_iter()
+                        .filter_map(|(name, value)| Some((name, value.as_str()?.to_string())))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                if let Some(status) = params.response_status_code {
+                    self.push(
+                        RecordedResponse::new(request.method, request.url, status, headers, &body)
+                            .with_request_headers(request_headers),
+                    );
+                }
+                RequestPausedDecision::Continue(None)
+            }
+            RecordingMode::Replay => match self.lookup(&request.method, &request.url) {
+                Some(response) => RequestPausedDecision::Fulfill(FulfillRequest {
+                    request_id: params.request_id,
+                    response_code: response.status,
+                    response_headers: Some(
+                        response
+                            .headers
+                            .into_iter()
+                            .map(|(name, value)| HeaderEntry { name, value })
+                            .collect(),
+                    ),
+                    binary_response_headers: None,
+                    body: Some(response.body),
+                    response_phrase: None,
+                }),
+                None => {
+                    warn!("Not in the recording: {} {}", request.method, request.url);
+                    RequestPausedDecision::Fail(FailRequest {
+                        request_id: params.request_id,
+                        error_reason: ErrorReason::InternetDisconnected,
+                    })
+                }
+            },
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_replays_in_order() {
+        let recorder = HttpRecorder::record("unused.json");
+        let url = "https://example.com/feed";
+        recorder.push(RecordedResponse::new("GET", url, 200, vec![], b"first"));
+        recorder.push(RecordedResponse::new("GET", url, 200, vec![], b"second"));
+        recorder.push(RecordedResponse::new("POST", url, 201, vec![], b"posted"));
+
+        let body = |response: Option<RecordedResponse>| response.unwrap().body_bytes().unwrap();
+        assert_eq!(body(recorder.lookup("GET", url)), b"first");
+        assert_eq!(body(recorder.lookup("GET", url)), b"second");
+        assert_eq!(body(recorder.lookup("GET", url)), b"second");
+        assert_eq!(body(recorder.lookup("POST", url)), b"posted");
+        assert!(
+            recorder
+                .lookup("GET", "https://example.com/other")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_save_and_replay() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("run").join("recording.json");
+        let recorder = HttpRecorder::record(&path);
+        let headers = vec![("Content-Type".to_string(), "image/png".to_string())];
+        let wire_headers = [
+            headers.clone(),
+            vec![("Content-Encoding".to_string(), "gzip".to_string())],
+        ]
+        .concat();
+        recorder.push(RecordedResponse::new(
+            "GET",
+            "https://example.com/logo.png",
+            200,
+            wire_headers,
+            &[0, 159, 146, 150],
+        ));
+        recorder.save()?;
+
+        let replay = HttpRecorder::replay(&path)?;
+        assert_eq!(replay.mode(), RecordingMode::Replay);
+        assert_eq!(replay.len(), 1);
+        let response = replay
+            .lookup("GET", "https://example.com/logo.png")
+            .unwrap();
+        assert_eq!(response.headers, headers);
+        assert_eq!(response.body_bytes()?, vec![0, 159, 146, 150]);
+        assert!(replay.save().is_err());
+        Ok(())
+    }
+}
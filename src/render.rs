@@ -0,0 +1,498 @@
+//! Utilities for converting local and previously saved HTML files
+//!
+//! This module locates `.html`/`.htm`/`.mhtml`/`.mht` files, given as paths, directories,
+//! or glob patterns, and reads their HTML so they can be converted to PDF or Markdown
+//! without fetching anything over the network.
+
+use anyhow::Result;
+use base64::Engine;
+use select::document::Document;
+use select::predicate::{Attr, Name};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use url::Url;
+
+/// File extensions recognized as HTML snapshots
+const SNAPSHOT_EXTENSIONS: [&str; 4] = ["html", "htm", "mhtml", "mht"];
+
+/// Whether a path has an HTML or MHTML snapshot extension
+pub fn is_snapshot_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| SNAPSHOT_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Whether a path is an MHTML archive
+pub fn is_mhtml_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| matches!(ext.to_lowercase().as_str(), "mhtml" | "mht"))
+        .unwrap_or(false)
+}
+
+/// Collect snapshot files from a file or directory
+///
+/// A file is returned as-is. A directory is searched recursively for snapshot files,
+/// returned in sorted order.
+///
+/// # Errors
+///
+/// Returns an error if the path does not exist or a directory cannot be read
+pub fn collect_snapshot_files(path: &Path) -> Result<Vec<PathBuf>> {
+    collect_files(path, is_snapshot_file)
+}
+
+/// Collect the files accepted by `filter` from a file or directory
+///
+/// A file is returned as-is. A directory is searched recursively, returning matching
+/// files in sorted order.
+///
+/// # Errors
+///
+/// Returns an error if the path does not exist or a directory cannot be read
+pub fn collect_files(path: &Path, filter: fn(&Path) -> bool) -> Result<Vec<PathBuf>> {
+    if path.is_file() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    if !path.is_dir() {
+        return Err(anyhow::anyhow!("{} does not exist", path.display()));
+    }
+
+    let mut files = Vec::new();
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry_path = entry?.path();
+            if entry_path.is_dir() {
+                pending.push(entry_path);
+            } else if filter(&entry_path) {
+                files.push(entry_path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// A local HTML file matched by a command-line input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalFile {
+    /// Path of the file
+    pub path: PathBuf,
+    /// Path of the file relative to the input root, used to mirror the directory structure
+    pub relative: PathBuf,
+}
+
+/// Whether a command-line input names a local path or glob pattern rather than a URL
+///
+/// Anything that does not parse as an absolute URL is local. Single-letter schemes are
+/// treated as Windows drive letters (`C:\docs\index.html`).
+pub fn is_local_input(input: &str) -> bool {
+    match Url::parse(input) {
+        Ok(url) => url.scheme().len() == 1,
+        Err(_) => true,
+    }
+}
+
+/// Expand a local path, directory, or glob pattern into the HTML files it matches
+///
+/// Directories are searched recursively. Glob patterns support `**` for recursive
+/// matching (e.g. `./docs/**/*.html`) and only snapshot files are kept. Each file's
+/// relative path is taken from the directory (or the non-glob prefix of the pattern).
+///
+/// # Errors
+///
+/// Returns an error if the pattern is invalid, a path does not exist, or nothing matches
+pub fn expand_local_input(input: &str) -> Result<Vec<LocalFile>> {
+    let path = Path::new(input);
+    let files = if is_glob_pattern(input) {
+        let root = glob_root(path);
+        let mut files = Vec::new();
+        for entry in glob::glob(input)? {
+            let entry = entry?;
+            if entry.is_file() && is_snapshot_file(&entry) {
+                files.push(local_file(&root, entry));
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        files
+    } else {
+        local_files(path)?
+    };
+
+    if files.is_empty() {
+        return Err(anyhow::anyhow!("No HTML files match {}", input));
+    }
+    Ok(files)
+}
+
+/// Collect the HTML files under a path, relative to that path
+///
+/// A file is returned relative to its parent directory; a directory is searched recursively.
+///
+/// # Errors
+///
+/// Returns an error if the path does not exist or a directory cannot be read
+pub fn local_files(path: &Path) -> Result<Vec<LocalFile>> {
+    let root = if path.is_file() {
+        path.parent().unwrap_or(Path::new(""))
+    } else {
+        path
+    };
+
+    Ok(collect_snapshot_files(path)?
+        .into_iter()
+        .map(|file| local_file(root, file))
+        .collect())
+}
+
+fn is_glob_pattern(input: &str) -> bool {
+    input.contains(['*', '?', '['])
+}
+
+/// The leading directory components of a glob pattern that contain no wildcards
+fn glob_root(pattern: &Path) -> PathBuf {
+    pattern
+        .components()
+        .take_while(|component| !is_glob_pattern(&component.as_os_str().to_string_lossy()))
+        .collect()
+}
+
+fn local_file(root: &Path, path: PathBuf) -> LocalFile {
+    let relative = path
+        .strip_prefix(root)
+        .ok()
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .or_else(|| path.file_name().map(Path::new))
+        .unwrap_or(&path)
+        .to_path_buf();
+    LocalFile { path, relative }
+}
+
+/// A saved page read from disk
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    /// The page HTML (extracted from the archive for MHTML files)
+    pub html: String,
+    /// The URL the page was originally captured from, if recorded
+    pub source_url: Option<String>,
+}
+
+/// Read a snapshot file, extracting the HTML from MHTML archives
+///
+/// # Errors
+///
+/// Returns an error if the file cannot be read or an MHTML archive has no HTML part
+pub async fn read_snapshot(path: &Path) -> Result<Snapshot> {
+    let data = fs::read(path).await?;
+    let content = String::from_utf8_lossy(&data);
+    let source_url = snapshot_source_url(&content);
+
+    let html = if is_mhtml_file(path) {
+        extract_mhtml_html(&content)?
+    } else {
+        content.into_owned()
+    };
+
+    Ok(Snapshot { html, source_url })
+}
+
+/// Build a `file://` URL for a local path
+///
+/// # Errors
+///
+/// Returns an error if the path cannot be resolved
+pub fn file_url(path: &Path) -> Result<String> {
+    let absolute = std::fs::canonicalize(path)?;
+    Url::from_file_path(&absolute)
+        .map(|url| url.to_string())
+        .map_err(|_| anyhow::anyhow!("Cannot build a file URL for {}", absolute.display()))
+}
+
+/// Find the original URL of a snapshot
+///
+/// Looks at the MHTML `Snapshot-Content-Location` header, then the HTML canonical link
+/// and `og:url` meta tag.
+pub fn snapshot_source_url(content: &str) -> Option<String> {
+    for line in content.lines().take_while(|line| !line.trim().is_empty()) {
+        if let Some(location) = line.strip_prefix("Snapshot-Content-Location:") {
+            return Some(location.trim().to_string());
+        }
+    }
+
+    let document = Document::from(content);
+    if let Some(href) = document
+        .find(Name("link"))
+        .filter(|link| link.attr("rel") == Some("canonical"))
+        .find_map(|link| link.attr("href"))
+    {
+        return Some(href.to_string());
+    }
+
+    document
+        .find(Attr("property", "og:url"))
+        .find_map(|meta| meta.attr("content"))
+        .map(|url| url.to_string())
+}
+
+/// Extract the first `text/html` part from an MHTML archive
+///
+/// # Errors
+///
+/// Returns an error if the archive has no MIME boundary or no HTML part
+pub fn extract_mhtml_html(content: &str) -> Result<String> {
+    let (headers, body) = split_headers(content);
+    let boundary = header_parameter(&headers, "boundary")
+        .ok_or_else(|| anyhow::anyhow!("MHTML archive has no MIME boundary"))?;
+    let delimiter = format!("--{}", boundary);
+
+    for part in body.split(delimiter.as_str()).skip(1) {
+        if part.starts_with("--") {
+            break;
+        }
+
+        let (part_headers, part_body) = split_headers(part.trim_start_matches(['\r', '\n']));
+        let content_type = header_value(&part_headers, "content-type").unwrap_or_default();
+        if !content_type.to_lowercase().starts_with("text/html") {
+            continue;
+        }
+
+        let encoding = header_value(&part_headers, "content-transfer-encoding")
+            .unwrap_or_default()
+            .to_lowercase();
+        let bytes = match encoding.as_str() {
+            "quoted-printable" => decode_quoted_printable(part_body),
+            "base64" => {
+                let compact: String = part_body.split_whitespace().collect();
+                base64::engine::general_purpose::STANDARD.decode(compact)?
+            }
+            _ => part_body.as_bytes().to_vec(),
+        };
+        return Ok(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    Err(anyhow::anyhow!("MHTML archive contains no text/html part"))
+}
+
+/// Split a MIME entity into unfolded header lines and its body
+fn split_headers(content: &str) -> (Vec<String>, &str) {
+    let (raw_headers, body) = match content.find("\r\n\r\n") {
+        Some(index) => (&content[..index], &content[index + 4..]),
+        None => match content.find("\n\n") {
+            Some(index) => (&content[..index], &content[index + 2..]),
+            None => (content, ""),
+        },
+    };
+
+    let mut headers: Vec<String> = Vec::new();
+    for line in raw_headers.lines() {
+        match headers.last_mut() {
+            Some(last) if line.starts_with([' ', '\t']) => {
+                last.push(' ');
+                last.push_str(line.trim());
+            }
+            _ => headers.push(line.trim_end().to_string()),
+        }
+    }
+
+    (headers, body)
+}
+
+/// Look up a header value by case-insensitive name
+fn header_value(headers: &[String], name: &str) -> Option<String> {
+    headers.iter().find_map(|header| {
+        let (key, value) = header.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().to_string())
+    })
+}
+
+/// Look up a `name=value` parameter in any header, e.g. the multipart boundary
+fn header_parameter(headers: &[String], name: &str) -> Option<String> {
+    headers
+        .iter()
+        .flat_map(|header| header.split(';'))
+        .find_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().trim_matches('"').to_string())
+        })
+}
+
+/// Decode a quoted-printable body, handling soft line breaks
+fn decode_quoted_printable(body: &str) -> Vec<u8> {
+    let bytes = body.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        if bytes[index] != b'=' {
+            decoded.push(bytes[index]);
+            index += 1;
+            continue;
+        }
+
+        let rest = &bytes[index + 1..];
+        if rest.starts_with(b"\r\n") {
+            index += 3;
+        } else if rest.starts_with(b"\n") {
+            index += 2;
+        } else if let Some(byte) = rest
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            index += 3;
+        } else {
+            decoded.push(b'=');
+            index += 1;
+        }
+    }
+
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MHTML: &str = "From: <Saved by Blink>\r\n\
+        Snapshot-Content-Location: https://example.com/article\r\n\
+        Subject: Article\r\n\
+        MIME-Version: 1.0\r\n\
+        Content-Type: multipart/related;\r\n\
+        \ttype=\"text/html\";\r\n\
+        \tboundary=\"----MultipartBoundary--abc----\"\r\n\
+        \r\n\
+        ------MultipartBoundary--abc----\r\n\
+        Content-Type: text/html\r\n\
+        Content-Transfer-Encoding: quoted-printable\r\n\
+        \r\n\
+        <html><body><h1 class=3D\"t\">Caf=C3=A9</h1><p>long =\r\n\
+        line</p></body></html>\r\n\
+        ------MultipartBoundary--abc----\r\n\
+        Content-Type: text/css\r\n\
+        \r\n\
+        body {}\r\n\
+        ------MultipartBoundary--abc------\r\n";
+
+    #[test]
+    fn test_extract_mhtml_html() -> Result<()> {
+        let html = extract_mhtml_html(MHTML)?;
+        assert!(html.contains("<h1 class=\"t\">Café</h1>"));
+        assert!(html.contains("long line"));
+        assert!(!html.contains("body {}"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_header_parameter() {
+        let headers = vec![
+            "Content-Type: multipart/related; xboundary=wrong; type=\"text/html\"".to_string(),
+            "X-Title: Ünïcödé; BOUNDARY=\"right\"".to_string(),
+        ];
+        assert_eq!(
+            header_parameter(&headers, "boundary").as_deref(),
+            Some("right")
+        );
+        assert_eq!(header_parameter(&headers, "charset"), None);
+    }
+
+    #[test]
+    fn test_snapshot_source_url() {
+        assert_eq!(
+            snapshot_source_url(MHTML).as_deref(),
+            Some("https://example.com/article")
+        );
+
+        let html =
+            r#"<html><head><link rel="canonical" href="https://example.com/c"></head></html>"#;
+        assert_eq!(
+            snapshot_source_url(html).as_deref(),
+            Some("https://example.com/c")
+        );
+        assert!(snapshot_source_url("<html></html>").is_none());
+    }
+
+    #[test]
+    fn test_collect_snapshot_files() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let nested = temp_dir.path().join("nested");
+        std::fs::create_dir(&nested)?;
+        std::fs::write(temp_dir.path().join("a.html"), "<html></html>")?;
+        std::fs::write(nested.join("b.mhtml"), MHTML)?;
+        std::fs::write(nested.join("notes.txt"), "ignored")?;
+
+        let files = collect_snapshot_files(temp_dir.path())?;
+        assert_eq!(
+            files,
+            vec![temp_dir.path().join("a.html"), nested.join("b.mhtml")]
+        );
+        assert!(collect_snapshot_files(&temp_dir.path().join("missing")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_local_input() {
+        assert!(is_local_input("./docs/**/*.html"));
+        assert!(is_local_input("index.html"));
+        assert!(is_local_input("C:\\docs\\index.html"));
+        assert!(!is_local_input("https://example.com"));
+        assert!(!is_local_input("file:///tmp/index.html"));
+    }
+
+    #[test]
+    fn test_expand_local_input() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let docs = temp_dir.path().join("docs");
+        let guide = docs.join("guide");
+        std::fs::create_dir_all(&guide)?;
+        std::fs::write(docs.join("index.html"), "<html></html>")?;
+        std::fs::write(guide.join("intro.html"), "<html></html>")?;
+        std::fs::write(guide.join("notes.txt"), "ignored")?;
+
+        let pattern = format!("{}/**/*.html", docs.display());
+        let relative: Vec<PathBuf> = expand_local_input(&pattern)?
+            .into_iter()
+            .map(|file| file.relative)
+            .collect();
+        assert_eq!(
+            relative,
+            vec![
+                PathBuf::from("guide/intro.html"),
+                PathBuf::from("index.html")
+            ]
+        );
+
+        let from_dir = expand_local_input(&docs.to_string_lossy())?;
+        assert_eq!(from_dir.len(), 2);
+
+        let single = expand_local_input(&docs.join("index.html").to_string_lossy())?;
+        assert_eq!(single[0].relative, PathBuf::from("index.html"));
+
+        assert!(expand_local_input(&format!("{}/*.pdf", docs.display())).is_err());
+        assert!(expand_local_input(&docs.join("missing.html").to_string_lossy()).is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_snapshot() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let path = temp_dir.path().join("page.mht");
+        std::fs::write(&path, MHTML)?;
+
+        let snapshot = read_snapshot(&path).await?;
+        assert!(snapshot.html.starts_with("<html>"));
+        assert_eq!(
+            snapshot.source_url.as_deref(),
+            Some("https://example.com/article")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! Extra HTTP headers sent with page requests
+//!
+//! Some pages are only served to clients that authenticate with a header instead of a
+//! login form: an `Authorization` header with Basic or Bearer credentials, an API key,
+//! or a cookie copied from a browser session. Headers are given as `Name: value`, like
+//! in curl, and are sent by both the browser that prints PDFs and the HTTP client that
+//! fetches pages for Markdown.
+
+use anyhow::{Context, Result, bail};
+use headless_chrome::Tab;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::collections::HashMap;
+
+/// Options applied to every request for a page
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    /// Headers added to each request, replacing any the client would send itself
+    pub headers: HeaderMap,
+}
+
+impl RequestOptions {
+    /// Options that add no headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a header, keeping earlier values of the same name
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Options with the headers of `Name: value` lines
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a line is not a valid header
+    pub fn from_header_lines(lines: &[String]) -> Result<Self> {
+        lines.iter().try_fold(Self::new(), |options, line| {
+            let (name, value) = parse_header(line)?;
+            Ok(options.with_header(name, value))
+        })
+    }
+
+    /// Whether no headers are added
+    pub fn is_empty(&self) -> bool {
+        self.headers.is_empty()
+    }
+
+    /// Send the headers with every request of a tab, before it loads a page
+    ///
+    /// Chrome sends them to every origin the page loads resources from, not only to
+    /// the page's own.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the DevTools call fails
+    pub fn apply(&self, tab: &Tab) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        // Chrome takes one value per header, so repeated headers are joined as in HTTP
+        let mut headers: HashMap<&str, String> = HashMap::new();
+        for (name, value) in &self.headers {
+            let value = value.to_str().unwrap_or_default();
+            headers
+                .entry(name.as_str())
+                .and_modify(|joined| {
+                    joined.push_str(", ");
+                    joined.push_str(value);
+                })
+                .or_insert_with(|| value.to_string());
+        }
+        let headers = headers
+            .iter()
+            .map(|(name, value)| (*name, value.as_str()))
+            .collect();
+        tab.set_extra_http_headers(headers)
+    }
+}
+
+/// Parse a `Name: value` header line
+///
+/// # Errors
+///
+/// Returns an error if the line has no colon, or the name or value is not valid in a
+/// header
+pub fn parse_header(line: &str) -> Result<(HeaderName, HeaderValue)> {
+    let Some((name, value)) = line.split_once(':') else {
+        bail!("Header '{}' is not of the form 'Name: value'", line);
+    };
+    let name = HeaderName::from_bytes(name.trim().as_bytes())
+        .with_context(|| format!("Invalid header name in '{}'", line))?;
+    let value = HeaderValue::from_str(value.trim())
+        .with_context(|| format!("Invalid header value in '{}'", line))?;
+    Ok((name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header_lines() {
+        let options = RequestOptions::from_header_lines(&[
+            "Authorization: Bearer abc.def".to_string(),
+            "x-api-key:secret".to_string(),
+            "Accept-Language: en".to_string(),
+            "accept-language: de ".to_string(),
+        ])
+        .unwrap();
+        assert_eq!(options.headers["authorization"], "Bearer abc.def");
+        assert_eq!(options.headers["x-api-key"], "secret");
+        let languages: Vec<_> = options.headers.get_all("accept-language").iter().collect();
+        assert_eq!(languages, ["en", "de"]);
+
+        assert!(RequestOptions::new().is_empty());
+        assert!(parse_header("Authorization Bearer abc").is_err());
+        assert!(parse_header("Bad Name: value").is_err());
+        assert!(parse_header("X-Token: line\nbreak").is_err());
+    }
+}
@@ -0,0 +1,642 @@
+//! Custom DNS resolvers for fetches
+//!
+//! Corporate networks and sites with split-horizon DNS answer differently depending
+//! on who asks, so the system's resolver may not see the hosts a capture needs. A
+//! [`DnsResolver`] sends every lookup of a run to a chosen nameserver instead, over
+//! UDP or DNS-over-HTTPS (RFC 8484), and keeps the answers for the rest of the run.
+//! Like a hosts file, it can also map hosts to fixed addresses, to capture a staging
+//! server or a site before its DNS is switched over; other hosts are then resolved as
+//! usual, with the system's nameserver unless another is chosen.
+//!
+//! The HTTP clients use it as their resolver. Chrome's `--host-resolver-rules` only
+//! maps hosts known before it is launched, and a page loads resources from hosts no
+//! one knows in advance, so Chrome is instead pointed at a local SOCKS5 proxy that
+//! resolves the host of each connection with the resolver.
+//!
+//! The same proxy serves hosts mapped to Unix sockets, for dashboards and development
+//! servers that only listen on one. Only the hosts mapped explicitly are served from
+//! sockets; they have no addresses, so the HTTP clients are pointed at the proxy too.
+
+use crate::dns::{self, Answer, DnsSnapshot, TYPE_A, TYPE_AAAA};
+use crate::proxy::ProxyConfig;
+use anyhow::{Context, Result, bail};
+use hyper::client::connect::dns::Name;
+use reqwest::ClientBuilder;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::header::{ACCEPT, CONTENT_TYPE};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tracing::debug;
+use url::Url;
+
+/// Media type of DNS messages sent over HTTPS
+const DNS_MESSAGE: &str = "application/dns-message";
+
+/// Nameserver recorded in the snapshots of hosts mapped to fixed addresses
+pub const OVERRIDE_NAMESERVER: &str = "override";
+
+/// A nameserver to send queries to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DnsServer {
+    /// A nameserver queried over UDP
+    Udp(SocketAddr),
+    /// A DNS-over-HTTPS endpoint, such as `https://1.1.1.1/dns-query`
+    Https(Url),
+}
+
+impl DnsServer {
+    /// A nameserver from an address, `IP` or `IP:port` (port 53 by default), or an
+    /// `https://` DNS-over-HTTPS URL
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the server is neither an IP address nor an HTTPS URL
+    pub fn new(server: &str) -> Result<Self> {
+        let server = server.trim();
+        if server.starts_with("https://") {
+            let url = Url::parse(server)
+                .with_context(|| format!("Invalid DNS-over-HTTPS URL {}", server))?;
+            return Ok(Self::Https(url));
+        }
+        if let Ok(address) = server.parse::<SocketAddr>() {
+            return Ok(Self::Udp(address));
+        }
+        match server
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<IpAddr>()
+        {
+            Ok(address) => Ok(Self::Udp(SocketAddr::new(address, 53))),
+            Err(_) => bail!(
+                "Invalid DNS server {}: give an IP address or an https:// DNS-over-HTTPS URL",
+                server
+            ),
+        }
+    }
+}
+
+impl FromStr for DnsServer {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::new(source)
+    }
+}
+
+impl fmt::Display for DnsServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Udp(address) if address.port() == 53 => write!(f, "{}", address.ip()),
+            Self::Udp(address) => write!(f, "{}", address),
+            Self::Https(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// A resolver sending lookups to one nameserver, shared by the clones of a run
+#[derive(Debug, Clone)]
+pub struct DnsResolver {
+    /// Nameserver queried, or None for the system's
+    server: Option<DnsServer>,
+    /// Fixed addresses of hosts, by lowercase host
+    overrides: HashMap<String, Vec<IpAddr>>,
+    /// Unix sockets serving hosts, by lowercase host
+    sockets: HashMap<String, PathBuf>,
+    timeout: Duration,
+    client: reqwest::Client,
+    /// Snapshots of the hosts resolved so far, by lowercase host
+    cache: Arc<Mutex<HashMap<String, DnsSnapshot>>>,
+    /// Address of the SOCKS5 proxy for Chrome, once started
+    socks: Arc<Mutex<Option<SocketAddr>>>,
+}
+
+impl DnsResolver {
+    /// A resolver querying a nameserver
+    pub fn new(server: DnsServer) -> Self {
+        Self {
+            server: Some(server),
+            ..Self::system()
+        }
+    }
+
+    /// A resolver querying the system's nameserver, as [`DnsSnapshot::resolve`] does,
+    /// for hosts without fixed addresses
+    pub fn system() -> Self {
+        Self {
+            server: None,
+            overrides: HashMap::new(),
+            sockets: HashMap::new(),
+            timeout: dns::DEFAULT_TIMEOUT,
+            client: reqwest::Client::new(),
+            cache: Arc::new(Mutex::new(HashMap::new())),
+            socks: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Wait at most `timeout` for each answer and each connection of the proxy
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Resolve a host to fixed addresses instead of asking a nameserver
+    pub fn with_override(mut self, host: &str, addresses: Vec<IpAddr>) -> Self {
+        self.overrides.insert(host.to_lowercase(), addresses);
+        self
+    }
+
+    /// Serve a host, on any port, from a Unix socket
+    ///
+    /// Connections to the host only reach the socket through [`browser_proxy`], so
+    /// clients fetching it must use that proxy.
+    ///
+    /// [`browser_proxy`]: Self::browser_proxy
+    pub fn with_unix_socket(mut self, host: &str, path: impl Into<PathBuf>) -> Self {
+        self.sockets.insert(host.to_lowercase(), path.into());
+        self
+    }
+
+    /// The nameserver queried, or None for the system's
+    pub fn server(&self) -> Option<&DnsServer> {
+        self.server.as_ref()
+    }
+
+    /// The hosts resolved to fixed addresses, and their addresses
+    pub fn overrides(&self) -> &HashMap<String, Vec<IpAddr>> {
+        &self.overrides
+    }
+
+    /// The hosts served from Unix sockets, and their sockets
+    pub fn unix_sockets(&self) -> &HashMap<String, PathBuf> {
+        &self.sockets
+    }
+
+    /// Resolve a host, recording its aliases and addresses
+    ///
+    /// Hosts already resolved in this run are answered from the cache, so the
+    /// snapshot shows the addresses the fetches used. Hosts with fixed addresses are
+    /// recorded with [`OVERRIDE_NAMESERVER`] as their nameserver.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host does not exist, is served from a Unix socket, or
+    /// the nameserver does not answer
+    pub async fn snapshot(&self, host: &str) -> Result<DnsSnapshot> {
+        let mut snapshot = DnsSnapshot::new(host);
+        if snapshot.host.parse::<IpAddr>().is_ok() {
+            return Ok(snapshot);
+        }
+        let key = snapshot.host.to_lowercase();
+        if let Some(path) = self.sockets.get(&key) {
+            bail!(
+                "{} is served from the Unix socket {} and has no addresses",
+                host,
+                path.display()
+            );
+        }
+        if let Some(addresses) = self.overrides.get(&key) {
+            snapshot.nameserver = Some(OVERRIDE_NAMESERVER.to_string());
+            addresses
+                .iter()
+                .for_each(|address| snapshot.add_address(*address));
+            return Ok(snapshot);
+        }
+        if let Some(cached) = self.cached(&key) {
+            return Ok(cached);
+        }
+
+        match &self.server {
+            Some(server) => {
+                snapshot.nameserver = Some(server.to_string());
+                for record_type in [TYPE_A, TYPE_AAAA] {
+                    snapshot.add_answer(self.query(server, &key, record_type).await?);
+                }
+            }
+            None => {
+                let (host, timeout) = (snapshot.host.clone(), self.timeout);
+                snapshot =
+                    tokio::task::spawn_blocking(move || DnsSnapshot::resolve(&host, timeout))
+                        .await??;
+            }
+        }
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(key, snapshot.clone());
+        }
+        Ok(snapshot)
+    }
+
+    /// The addresses of a host, IPv4 first
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the host cannot be resolved or has no addresses
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let addresses = self.snapshot(host).await?.addresses();
+        if addresses.is_empty() {
+            bail!("No addresses found for {}", host);
+        }
+        Ok(addresses)
+    }
+
+    /// Resolve the hosts of a client's requests with this resolver
+    pub fn apply(&self, builder: ClientBuilder) -> ClientBuilder {
+        builder.dns_resolver(Arc::new(self.clone()))
+    }
+
+    /// A proxy for Chrome that resolves the host of each connection with this
+    /// resolver, started on a local port the first time it is asked for
+    ///
+    /// It is a `socks5h://` proxy, so HTTP clients pass it host names too. The proxy runs on its own thread until the process exits.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the proxy cannot listen on a local port
+    pub fn browser_proxy(&self) -> Result<ProxyConfig> {
+        let mut socks = self
+            .socks
+            .lock()
+            .map_err(|_| anyhow::anyhow!("DNS proxy lock poisoned"))?;
+        let address = match *socks {
+            Some(address) => address,
+            None => *socks.insert(self.start_proxy()?),
+        };
+        ProxyConfig::new(&format!("socks5h://{}", address))
+    }
+
+    fn cached(&self, host: &str) -> Option<DnsSnapshot> {
+        self.cache.lock().ok()?.get(host).cloned()
+    }
+
+    /// Ask the nameserver for the records of one type of a host
+    async fn query(&self, server: &DnsServer, host: &str, record_type: u16) -> Result<Answer> {
+        match server {
+            DnsServer::Udp(server) => {
+                let (server, host, timeout) = (*server, host.to_string(), self.timeout);
+                tokio::task::spawn_blocking(move || dns::query(server, &host, record_type, timeout))
+                    .await?
+            }
+            DnsServer::Https(url) => {
+                // RFC 8484 asks for ID 0, so that caches can share answers
+                let reply = self
+                    .client
+                    .post(url.clone())
+                    .timeout(self.timeout)
+                    .header(CONTENT_TYPE, DNS_MESSAGE)
+                    .header(ACCEPT, DNS_MESSAGE)
+                    .body(dns::request(0, host, record_type)?)
+                    .send()
+                    .await
+                    .with_context(|| format!("No reply from DNS-over-HTTPS server {}", url))?
+                    .error_for_status()?
+                    .bytes()
+                    .await?;
+                dns::parse_reply(&reply, 0, host)
+            }
+        }
+    }
+
+    /// Listen on a local port and serve SOCKS5 connections on a new thread
+    fn start_proxy(&self) -> Result<SocketAddr> {
+        let listener = std::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        // Its own runtime keeps the proxy answering while the caller blocks on Chrome
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?;
+        let resolver = self.clone();
+        std::thread::Builder::new()
+            .name("dns-proxy".to_string())
+            .spawn(move || {
+                runtime.block_on(async move {
+                    let Ok(listener) = TcpListener::from_std(listener) else {
+                        return;
+                    };
+                    loop {
+                        let stream = match listener.accept().await {
+                            Ok((stream, _)) => stream,
+                            Err(e) => {
+                                // Out of file descriptors, most likely: wait for some
+                                debug!("DNS proxy failed to accept a connection: {}", e);
+                                tokio::time::sleep(Duration::from_millis(100)).await;
+                                continue;
+                            }
+                        };
+                        let resolver = resolver.clone();
+                        tokio::spawn(async move {
+                            if let Err(e) = resolver.serve_socks(stream).await {
+                                debug!("DNS proxy connection failed: {:#}", e);
+                            }
+                        });
+                    }
+                })
+            })?;
+        Ok(address)
+    }
+
+    /// Serve one SOCKS5 CONNECT request, without authentication
+    async fn serve_socks(&self, mut client: TcpStream) -> Result<()> {
+        // Greeting: version, then the authentication methods offered
+        let mut greeting = [0u8; 2];
+        client.read_exact(&mut greeting).await?;
+        if greeting[0] != 5 {
+            bail!("Not a SOCKS5 client");
+        }
+        let mut methods = vec![0u8; greeting[1] as usize];
+        client.read_exact(&mut methods).await?;
+        if !methods.contains(&0) {
+            client.write_all(&[5, 0xff]).await?;
+            bail!("SOCKS5 client requires authentication");
+        }
+        client.write_all(&[5, 0]).await?;
+
+        // Request: version, command, reserved, then the destination
+        let mut request = [0u8; 4];
+        client.read_exact(&mut request).await?;
+        if request[1] != 1 {
+            socks_reply(&mut client, 7).await?;
+            bail!("Unsupported SOCKS5 command {}", request[1]);
+        }
+        let host = match request[3] {
+            1 => {
+                let mut address = [0u8; 4];
+                client.read_exact(&mut address).await?;
+                IpAddr::from(address).to_string()
+            }
+            3 => {
+                let mut name = vec![0u8; client.read_u8().await? as usize];
+                client.read_exact(&mut name).await?;
+                String::from_utf8(name).context("Invalid SOCKS5 host name")?
+            }
+            4 => {
+                let mut address = [0u8; 16];
+                client.read_exact(&mut address).await?;
+                IpAddr::from(address).to_string()
+            }
+            address_type => {
+                socks_reply(&mut client, 8).await?;
+                bail!("Unsupported SOCKS5 address type {}", address_type);
+            }
+        };
+        let port = client.read_u16().await?;
+
+        if let Some(path) = self.sockets.get(&host.to_lowercase()) {
+            return self.relay_unix(client, path).await;
+        }
+        let addresses = match self.lookup(&host).await {
+            Ok(addresses) => addresses,
+            Err(e) => {
+                socks_reply(&mut client, 4).await?;
+                return Err(e);
+            }
+        };
+        let mut server = None;
+        for address in addresses {
+            let connect = TcpStream::connect(SocketAddr::new(address, port));
+            if let Ok(Ok(stream)) = tokio::time::timeout(self.timeout, connect).await {
+                server = Some(stream);
+                break;
+            }
+        }
+        let Some(mut server) = server else {
+            socks_reply(&mut client, 5).await?;
+            bail!("Failed to connect to {}:{}", host, port);
+        };
+        socks_reply(&mut client, 0).await?;
+        tokio::io::copy_bidirectional(&mut client, &mut server).await?;
+        Ok(())
+    }
+
+    /// Relay an accepted SOCKS5 connection to a Unix socket
+    #[cfg(unix)]
+    async fn relay_unix(&self, mut client: TcpStream, path: &Path) -> Result<()> {
+        let connect = tokio::net::UnixStream::connect(path);
+        let mut server = match tokio::time::timeout(self.timeout, connect).await {
+            Ok(Ok(stream)) => stream,
+            _ => {
+                socks_reply(&mut client, 5).await?;
+                bail!("Failed to connect to Unix socket {}", path.display());
+            }
+        };
+        socks_reply(&mut client, 0).await?;
+        tokio::io::copy_bidirectional(&mut client, &mut server).await?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    async fn relay_unix(&self, mut client: TcpStream, path: &Path) -> Result<()> {
+        socks_reply(&mut client, 1).await?;
+        bail!(
+            "Cannot connect to {}: Unix sockets are not supported on this platform",
+            path.display()
+        );
+    }
+}
+
+impl Resolve for DnsResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addresses = resolver.lookup(name.as_str()).await?;
+            // reqwest sets the port of the request on each address
+            let addresses: Addrs = Box::new(
+                addresses
+                    .into_iter()
+                    .map(|address| SocketAddr::new(address, 0)),
+            );
+            Ok(addresses)
+        })
+    }
+}
+
+/// Parse a `HOST:IP` override, with several addresses separated by commas
+///
+/// # Errors
+///
+/// Returns an error if the host is missing or an address is not an IP address
+pub fn parse_override(spec: &str) -> Result<(String, Vec<IpAddr>)> {
+    let Some((host, addresses)) = spec.split_once(':') else {
+        bail!("Override '{}' is not of the form HOST:IP", spec);
+    };
+    let host = host.trim();
+    if host.is_empty() || host.contains(char::is_whitespace) {
+        bail!("Invalid host in override '{}'", spec);
+    }
+    let addresses = addresses
+        .split(',')
+        .map(|address| {
+            let address = address.trim();
+            address
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse::<IpAddr>()
+                .with_context(|| {
+                    format!(
+                        "Invalid address {} in override '{}': give HOST:IP, e.g. www.example.com:203.0.113.7",
+                        address, spec
+                    )
+                })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok((host.to_lowercase(), addresses))
+}
+
+/// Parse a `HOST=PATH` mapping of a host to a Unix socket
+///
+/// # Errors
+///
+/// Returns an error if the host or the path is missing
+pub fn parse_unix_socket(spec: &str) -> Result<(String, PathBuf)> {
+    let Some((host, path)) = spec.split_once('=') else {
+        bail!(
+            "Unix socket '{}' is not of the form HOST=PATH, e.g. dashboard.internal=/run/dashboard.sock",
+            spec
+        );
+    };
+    let (host, path) = (host.trim(), path.trim());
+    if host.is_empty() || host.contains(|c: char| c.is_whitespace() || c == ':' || c == '/') {
+        bail!("Invalid host in Unix socket '{}'", spec);
+    }
+    if path.is_empty() {
+        bail!("Missing path in Unix socket '{}'", spec);
+    }
+    Ok((host.to_lowercase(), PathBuf::from(path)))
+}
+
+/// Answer a SOCKS5 request with a status code and an unspecified bound address
+async fn socks_reply(client: &mut TcpStream, code: u8) -> Result<()> {
+    client.write_all(&[5, code, 0, 1, 0, 0, 0, 0, 0, 0]).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_resolve_through_proxy() -> Result<()> {
+        assert_eq!(
+            DnsServer::new("10.0.0.1")?,
+            DnsServer::Udp("10.0.0.1:53".parse()?)
+        );
+        assert_eq!(DnsServer::new("[::1]:5353")?.to_string(), "[::1]:5353");
+        assert_eq!(DnsServer::new("::1")?.to_string(), "::1");
+        assert!(matches!(
+            "https://1.1.1.1/dns-query".parse::<DnsServer>()?,
+            DnsServer::Https(_)
+        ));
+        assert!(DnsServer::new("dns.example").is_err());
+        assert!(DnsServer::new("http://dns.example/dns-query").is_err());
+
+        // Nothing listens on the discard port, so only cached hosts resolve
+        let resolver = DnsResolver::new(DnsServer::new("127.0.0.1:9")?)
+            .with_timeout(Duration::from_millis(500));
+        let mut snapshot = DnsSnapshot::new("127.0.0.1");
+        snapshot.host = "intranet.example".to_string();
+        resolver
+            .cache
+            .lock()
+            .unwrap()
+            .insert(snapshot.host.clone(), snapshot);
+        assert_eq!(
+            resolver.lookup("Intranet.Example").await?,
+            [IpAddr::from([127, 0, 0, 1])]
+        );
+        assert_eq!(
+            resolver.lookup("[::1]").await?,
+            [IpAddr::from(std::net::Ipv6Addr::LOCALHOST)]
+        );
+
+        let (host, addresses) = parse_override("Staging.Example:127.0.0.1, [::1]")?;
+        assert_eq!(host, "staging.example");
+        let resolver = resolver.with_override(&host, addresses);
+        let snapshot = resolver.snapshot("STAGING.example").await?;
+        assert_eq!(snapshot.nameserver.as_deref(), Some(OVERRIDE_NAMESERVER));
+        assert_eq!(snapshot.ipv4, [std::net::Ipv4Addr::LOCALHOST]);
+        assert_eq!(snapshot.ipv6, [std::net::Ipv6Addr::LOCALHOST]);
+        assert!(parse_override("staging.example").is_err());
+        assert!(parse_override("staging.example:443:127.0.0.1").is_err());
+        assert!(parse_override(":127.0.0.1").is_err());
+
+        let echo = TcpListener::bind("127.0.0.1:0").await?;
+        let echo_port = echo.local_addr()?.port();
+        tokio::spawn(async move {
+            if let Ok((mut stream, _)) = echo.accept().await {
+                let (mut reader, mut writer) = stream.split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            }
+        });
+
+        let proxy = resolver.browser_proxy()?;
+        assert_eq!(resolver.browser_proxy()?, proxy);
+        let port = proxy.url.port().unwrap();
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).await?;
+        stream.write_all(&[5, 1, 0]).await?;
+        let mut reply = [0u8; 2];
+        stream.read_exact(&mut reply).await?;
+        assert_eq!(reply, [5, 0]);
+
+        let mut request = vec![5, 1, 0, 3, 16];
+        request.extend_from_slice(b"intranet.example");
+        request.extend_from_slice(&echo_port.to_be_bytes());
+        stream.write_all(&request).await?;
+        let mut reply = [0u8; 10];
+        stream.read_exact(&mut reply).await?;
+        assert_eq!(reply[1], 0);
+
+        stream.write_all(b"ping").await?;
+        let mut echoed = [0u8; 4];
+        stream.read_exact(&mut echoed).await?;
+        assert_eq!(&echoed, b"ping");
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_unix_socket_through_proxy() -> Result<()> {
+        let (host, path) = parse_unix_socket("Dashboard.Internal = /tmp/x.sock")?;
+        assert_eq!(host, "dashboard.internal");
+        assert_eq!(path, PathBuf::from("/tmp/x.sock"));
+        assert!(parse_unix_socket("dashboard.internal").is_err());
+        assert!(parse_unix_socket("=/tmp/x.sock").is_err());
+        assert!(parse_unix_socket("dashboard.internal=").is_err());
+
+        let path = std::env::temp_dir().join(format!("webpage-save-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let echo = tokio::net::UnixListener::bind(&path)?;
+        tokio::spawn(async move {
+            if let Ok((mut stream, _)) = echo.accept().await {
+                let (mut reader, mut writer) = stream.split();
+                let _ = tokio::io::copy(&mut reader, &mut writer).await;
+            }
+        });
+
+        let resolver = DnsResolver::system().with_unix_socket("Dashboard.Internal", &path);
+        assert!(resolver.lookup("dashboard.internal").await.is_err());
+        let proxy = resolver.browser_proxy()?;
+        assert_eq!(proxy.url.scheme(), "socks5h");
+        let mut stream = TcpStream::connect(("127.0.0.1", proxy.url.port().unwrap())).await?;
+        let mut request = vec![5, 1, 0, 5, 1, 0, 3, 18];
+        request.extend_from_slice(b"dashboard.internal");
+        request.extend_from_slice(&80u16.to_be_bytes());
+        stream.write_all(&request).await?;
+        let mut reply = [0u8; 12];
+        stream.read_exact(&mut reply).await?;
+        assert_eq!(reply[..2], [5, 0]);
+        assert_eq!(reply[3], 0);
+
+        stream.write_all(b"ping").await?;
+        let mut echoed = [0u8; 4];
+        stream.read_exact(&mut echoed).await?;
+        assert_eq!(&echoed, b"ping");
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
+}
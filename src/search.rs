@@ -1,12 +1,40 @@
 //! Brave search utilities for performing web, news, and local searches
 //!
 //! This module provides functionality to perform searches using the Brave Search API
-//! through the bravesearch-mcp crate.
+//! through the bravesearch-mcp crate, which returns results as formatted text, and
+//! typed results read from the API's JSON responses directly
+//! ([`BraveSearchClient::search_structured`]).
 
+use crate::auth;
+use crate::cache::SearchCache;
+use crate::proxy::ProxyConfig;
+use crate::resolver::DnsResolver;
 use anyhow::Result;
 use bravesearch_mcp::tools::BraveSearchRouter;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::LazyLock;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Brave Search API endpoint used for direct, lightweight requests
+const BRAVE_WEB_SEARCH_ENDPOINT: &str = "https://api.search.brave.com/res/v1/web/search";
+
+/// Brave Search API endpoint for news searches
+const BRAVE_NEWS_SEARCH_ENDPOINT: &str = "https://api.search.brave.com/res/v1/news/search";
+
+/// An HTTP status named in a search router error, e.g. `status code: 429`
+static ERROR_STATUS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)status(?:\s+code)?\D{0,3}(\d{3})\b").expect("valid regex"));
+
+/// A bare 4xx or 5xx code in a search router error
+static ERROR_BARE_STATUS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b([45]\d{2})\b").expect("valid regex"));
+
+/// A `Retry-After` hint in a search router error, in seconds
+static ERROR_RETRY_AFTER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)retry[-_ ]after\D{0,3}(\d+)").expect("valid regex"));
 
 /// Search types supported by the Brave Search API
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -44,7 +72,7 @@ impl std::str::FromStr for SearchType {
 }
 
 /// Configuration for search operations
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct SearchConfig {
     /// Number of results to return
     pub count: Option<usize>,
@@ -58,9 +86,367 @@ pub struct SearchConfig {
     pub freshness: Option<String>,
 }
 
+/// A search result that can be converted to PDF
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub description: String,
+}
+
+/// A web search result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebSearchResult {
+    /// Page title
+    pub title: String,
+    /// Page URL
+    pub url: String,
+    /// Snippet of the page, with the query terms in `<strong>` tags
+    #[serde(default)]
+    pub description: String,
+    /// How long ago the page was published, e.g. `2 days ago`
+    #[serde(default)]
+    pub age: Option<String>,
+    /// Further snippets of the page
+    #[serde(default)]
+    pub extra_snippets: Vec<String>,
+}
+
+/// A news search result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NewsSearchResult {
+    /// Article title
+    pub title: String,
+    /// Article URL
+    pub url: String,
+    /// Snippet of the article
+    #[serde(default)]
+    pub description: String,
+    /// How long ago the article was published, e.g. `3 hours ago`
+    #[serde(default)]
+    pub age: Option<String>,
+    /// When the article was published, as reported by the site
+    #[serde(default)]
+    pub page_age: Option<String>,
+}
+
+/// A local search result: a business or place
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LocalSearchResult {
+    /// Name of the place
+    pub title: String,
+    /// Web site of the place, if known
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Page about the place at the data provider, if known
+    #[serde(default)]
+    pub provider_url: Option<String>,
+    /// Description of the place
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Postal address of the place
+    #[serde(default)]
+    pub postal_address: Option<PostalAddress>,
+    /// Latitude and longitude of the place, if known
+    #[serde(default)]
+    pub coordinates: Option<[f64; 2]>,
+    /// Phone number and email address of the place, if known
+    #[serde(default)]
+    pub contact: Option<PlaceContact>,
+}
+
+/// Postal address of a local search result
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostalAddress {
+    /// The address on one line
+    #[serde(default, rename = "displayAddress")]
+    pub display_address: Option<String>,
+    /// Street and house number
+    #[serde(default, rename = "streetAddress")]
+    pub street_address: Option<String>,
+    /// City or town
+    #[serde(default, rename = "addressLocality")]
+    pub locality: Option<String>,
+    /// State, prefecture, or province
+    #[serde(default, rename = "addressRegion")]
+    pub region: Option<String>,
+    /// Postal code
+    #[serde(default, rename = "postalCode")]
+    pub postal_code: Option<String>,
+    /// Country
+    #[serde(default)]
+    pub country: Option<String>,
+}
+
+/// Contact details of a local search result
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaceContact {
+    /// Phone number
+    #[serde(default)]
+    pub telephone: Option<String>,
+    /// Email address
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+impl From<WebSearchResult> for SearchResult {
+    fn from(result: WebSearchResult) -> Self {
+        Self {
+            title: result.title,
+            url: result.url,
+            description: result.description,
+        }
+    }
+}
+
+impl From<NewsSearchResult> for SearchResult {
+    fn from(result: NewsSearchResult) -> Self {
+        Self {
+            title: result.title,
+            url: result.url,
+            description: result.description,
+        }
+    }
+}
+
+impl LocalSearchResult {
+    /// The postal address of the place on one line, if known
+    pub fn address(&self) -> Option<&str> {
+        self.postal_address
+            .as_ref()
+            .and_then(|address| address.display_address.as_deref())
+    }
+
+    /// The result as a convertible page: the place's web site, or its page at the data
+    /// provider, with its address as the description
+    pub fn to_search_result(&self) -> Option<SearchResult> {
+        let url = self.url.clone().or_else(|| self.provider_url.clone())?;
+        let address = self.address().map(str::to_string);
+        let description = [self.description.clone(), address]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" — ");
+        Some(SearchResult {
+            title: self.title.clone(),
+            url,
+            description,
+        })
+    }
+}
+
+/// Results of one type in a Brave Search API response
+#[derive(Deserialize)]
+struct ResultList<T> {
+    #[serde(default = "Vec::new")]
+    results: Vec<T>,
+}
+
+/// Response of the web search endpoint
+#[derive(Deserialize)]
+struct WebResponse {
+    #[serde(default)]
+    web: Option<ResultList<WebSearchResult>>,
+    #[serde(default)]
+    locations: Option<ResultList<LocalSearchResult>>,
+}
+
+/// Rate limit and quota information reported by the Brave Search API
+///
+/// Each value is passed through as reported by Brave, typically a comma-separated
+/// pair of per-second and per-month figures (e.g. `1, 15000`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaInfo {
+    /// Request limits (`X-RateLimit-Limit`)
+    pub limit: Option<String>,
+    /// Rate limit policy (`X-RateLimit-Policy`)
+    pub policy: Option<String>,
+    /// Remaining requests (`X-RateLimit-Remaining`)
+    pub remaining: Option<String>,
+    /// Seconds until the limits reset (`X-RateLimit-Reset`)
+    pub reset: Option<String>,
+}
+
+impl QuotaInfo {
+    /// Extract quota information from Brave response headers
+    pub fn from_headers(headers: &reqwest::header::HeaderMap) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.to_string())
+        };
+
+        Self {
+            limit: header("x-ratelimit-limit"),
+            policy: header("x-ratelimit-policy"),
+            remaining: header("x-ratelimit-remaining"),
+            reset: header("x-ratelimit-reset"),
+        }
+    }
+}
+
+/// Outcome of validating an API key against the Brave Search API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyStatus {
+    /// The key was accepted
+    Valid,
+    /// The key was rejected as missing, malformed, or unauthorized
+    InvalidKey,
+    /// The key is valid but its rate limit or monthly quota is exhausted
+    QuotaExceeded,
+    /// Brave returned an unexpected HTTP status
+    Unexpected(u16),
+}
+
+impl KeyStatus {
+    /// Classify an HTTP status code returned by the Brave Search API
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            200..=299 => KeyStatus::Valid,
+            401 | 403 | 422 => KeyStatus::InvalidKey,
+            429 => KeyStatus::QuotaExceeded,
+            other => KeyStatus::Unexpected(other),
+        }
+    }
+}
+
+/// Result of an API key check
+#[derive(Debug, Clone)]
+pub struct KeyCheck {
+    /// Classified outcome of the check
+    pub status: KeyStatus,
+    /// HTTP status code returned by Brave
+    pub http_status: u16,
+    /// Quota information from the response headers
+    pub quota: QuotaInfo,
+}
+
+/// Classified failure reported by the Brave Search API
+///
+/// Search methods return this inside `anyhow::Error`; use
+/// `error.downcast_ref::<BraveSearchError>()` to react to a specific failure.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BraveSearchError {
+    /// The API key was missing or rejected (HTTP 401, 403, 422)
+    Unauthorized(String),
+    /// The subscription plan does not cover the request (HTTP 402)
+    SubscriptionRequired(String),
+    /// The rate limit or monthly quota was exceeded (HTTP 429)
+    RateLimited {
+        /// How long Brave asked us to wait, if it said
+        retry_after: Option<Duration>,
+        /// Error detail reported by the API
+        message: String,
+    },
+    /// Brave failed to handle the request (HTTP 5xx)
+    ServerError {
+        /// HTTP status code
+        status: u16,
+        /// Error detail reported by the API
+        message: String,
+    },
+    /// Any other failure
+    Other(String),
+}
+
+impl BraveSearchError {
+    /// Classify a failure from its HTTP status, `Retry-After` value and message
+    pub fn from_status(status: u16, retry_after: Option<Duration>, message: String) -> Self {
+        match status {
+            401 | 403 | 422 => BraveSearchError::Unauthorized(message),
+            402 => BraveSearchError::SubscriptionRequired(message),
+            429 => BraveSearchError::RateLimited {
+                retry_after,
+                message,
+            },
+            500..=599 => BraveSearchError::ServerError { status, message },
+            _ => BraveSearchError::Other(message),
+        }
+    }
+
+    /// Classify an `Error: ...` string returned by the search router
+    ///
+    /// The HTTP status and any `Retry-After` hint are recovered from the text when present.
+    pub fn from_error_text(text: &str) -> Self {
+        let message = text.trim_start_matches("Error:").trim().to_string();
+
+        let status = ERROR_STATUS
+            .captures(&message)
+            .or_else(|| ERROR_BARE_STATUS.captures(&message))
+            .and_then(|captures| captures[1].parse::<u16>().ok());
+
+        let retry_after = ERROR_RETRY_AFTER
+            .captures(&message)
+            .and_then(|captures| captures[1].parse::<u64>().ok())
+            .map(Duration::from_secs);
+
+        match status {
+            Some(status) => Self::from_status(status, retry_after, message),
+            None => BraveSearchError::Other(message),
+        }
+    }
+
+    /// Whether retrying the same request later may succeed
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            BraveSearchError::RateLimited { .. } | BraveSearchError::ServerError { .. }
+        )
+    }
+
+    /// The delay Brave requested before retrying, if any
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            BraveSearchError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for BraveSearchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BraveSearchError::Unauthorized(message) => {
+                write!(f, "Brave API key was rejected: {}", message)
+            }
+            BraveSearchError::SubscriptionRequired(message) => {
+                write!(
+                    f,
+                    "Brave subscription does not cover this request: {}",
+                    message
+                )
+            }
+            BraveSearchError::RateLimited {
+                retry_after: Some(retry_after),
+                message,
+            } => write!(
+                f,
+                "Brave rate limit exceeded (retry after {}s): {}",
+                retry_after.as_secs(),
+                message
+            ),
+            BraveSearchError::RateLimited { message, .. } => {
+                write!(f, "Brave rate limit exceeded: {}", message)
+            }
+            BraveSearchError::ServerError { status, message } => {
+                write!(f, "Brave server error (HTTP {}): {}", status, message)
+            }
+            BraveSearchError::Other(message) => write!(f, "Search failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for BraveSearchError {}
+
 /// Brave search client for performing various types of searches
 pub struct BraveSearchClient {
     router: BraveSearchRouter,
+    api_key: String,
+    cache: Option<SearchCache>,
+    offline: bool,
+    proxy: Option<ProxyConfig>,
+    resolver: Option<DnsResolver>,
 }
 
 impl BraveSearchClient {
@@ -68,7 +454,8 @@ impl BraveSearchClient {
     ///
     /// # Arguments
     ///
-    /// * `api_key` - Optional API key. If None, attempts to read from BRAVE_API_KEY environment variable
+    /// * `api_key` - Optional API key. If None, attempts to read from BRAVE_API_KEY environment variable,
+    ///   then from the OS keyring (see `webpage-save auth set-key`)
     ///
     /// # Returns
     ///
@@ -76,16 +463,112 @@ impl BraveSearchClient {
     ///
     /// # Errors
     ///
-    /// Returns an error if no API key is provided and BRAVE_API_KEY environment variable is not set
+    /// Returns an error if no API key is provided, BRAVE_API_KEY environment variable is not set,
+    /// and no key is stored in the keyring
     pub fn new(api_key: Option<String>) -> Result<Self> {
         let key = match api_key {
             Some(key) => key,
-            None => env::var("BRAVE_API_KEY")
-                .map_err(|_| anyhow::anyhow!("BRAVE_API_KEY environment variable not set"))?,
+            None => match env::var("BRAVE_API_KEY") {
+                Ok(key) => key,
+                Err(_) => keyring_api_key().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "BRAVE_API_KEY environment variable not set and no API key stored in the keyring"
+                    )
+                })?,
+            },
+        };
+
+        let router = BraveSearchRouter::new(key.clone());
+        Ok(Self {
+            router,
+            api_key: key,
+            cache: None,
+            offline: false,
+            proxy: None,
+            resolver: None,
+        })
+    }
+
+    /// Serve `search` responses only from the cache, regardless of age, never calling the API
+    ///
+    /// Searches that are not cached fail. Requires a cache (see `with_cache`).
+    pub fn with_offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Whether responses are served only from the cache (see `with_offline`)
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Serve `search` responses from an on-disk cache, storing new responses in it
+    pub fn with_cache(mut self, cache: SearchCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Send API requests through a proxy
+    ///
+    /// Only the requests for typed results and key checks go through it: the text
+    /// searches of the search router use reqwest's proxy environment variables.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Resolve the API's host with a custom resolver
+    ///
+    /// Like proxies, this only applies to the requests for typed results and key
+    /// checks.
+    pub fn with_resolver(mut self, resolver: DnsResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    /// An HTTP client for API requests, through the proxy and with the resolver if
+    /// there are any
+    fn http_client(&self) -> Result<reqwest::Client> {
+        let builder = reqwest::Client::builder().timeout(Duration::from_secs(30));
+        let builder = match &self.proxy {
+            Some(proxy) => proxy.apply(builder)?,
+            None => builder,
+        };
+        let builder = match &self.resolver {
+            Some(resolver) => resolver.apply(builder),
+            None => builder,
         };
+        Ok(builder.build()?)
+    }
 
-        let router = BraveSearchRouter::new(key);
-        Ok(Self { router })
+    /// Validate the API key with a minimal search request and report quota headers
+    ///
+    /// # Returns
+    ///
+    /// Returns the classified outcome together with the quota information Brave reported.
+    /// An invalid key or an exhausted quota is reported through `KeyCheck::status`, not as an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the Brave Search API cannot be reached
+    pub async fn check_api_key(&self) -> Result<KeyCheck> {
+        let client = self.http_client()?;
+
+        let response = client
+            .get(BRAVE_WEB_SEARCH_ENDPOINT)
+            .query(&[("q", "test"), ("count", "1")])
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Could not reach the Brave Search API: {}", e))?;
+
+        let http_status = response.status().as_u16();
+        Ok(KeyCheck {
+            status: KeyStatus::from_status(http_status),
+            http_status,
+            quota: QuotaInfo::from_headers(response.headers()),
+        })
     }
 
     /// Perform a web search
@@ -109,11 +592,7 @@ impl BraveSearchClient {
             .brave_web_search(query.to_string(), config.count, config.offset)
             .await;
 
-        if result.starts_with("Error:") {
-            return Err(anyhow::anyhow!("Search failed: {}", result));
-        }
-
-        Ok(result)
+        check_router_result(result)
     }
 
     /// Perform a news search
@@ -144,11 +623,7 @@ impl BraveSearchClient {
             )
             .await;
 
-        if result.starts_with("Error:") {
-            return Err(anyhow::anyhow!("Search failed: {}", result));
-        }
-
-        Ok(result)
+        check_router_result(result)
     }
 
     /// Perform a local search
@@ -172,15 +647,237 @@ impl BraveSearchClient {
             .brave_local_search(query.to_string(), config.count)
             .await;
 
-        if result.starts_with("Error:") {
-            return Err(anyhow::anyhow!("Search failed: {}", result));
+        check_router_result(result)
+    }
+
+    /// Perform a web search, returning typed results
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or the response cannot be parsed
+    pub async fn web_results(
+        &self,
+        query: &str,
+        config: Option<SearchConfig>,
+    ) -> Result<Vec<WebSearchResult>> {
+        let config = config.unwrap_or_default();
+        let mut params = vec![("q", query.to_string())];
+        params.extend(config.count.map(|count| ("count", count.to_string())));
+        params.extend(config.offset.map(|offset| ("offset", offset.to_string())));
+        let response = self.api_request(BRAVE_WEB_SEARCH_ENDPOINT, &params).await?;
+        parse_web_results(&response)
+    }
+
+    /// Perform a news search, returning typed results
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or the response cannot be parsed
+    pub async fn news_results(
+        &self,
+        query: &str,
+        config: Option<SearchConfig>,
+    ) -> Result<Vec<NewsSearchResult>> {
+        let config = config.unwrap_or_default();
+        let mut params = vec![("q", query.to_string())];
+        params.extend(config.count.map(|count| ("count", count.to_string())));
+        params.extend(config.offset.map(|offset| ("offset", offset.to_string())));
+        params.extend(config.country.map(|country| ("country", country)));
+        params.extend(config.language.map(|language| ("search_lang", language)));
+        params.extend(
+            config
+                .freshness
+                .map(|freshness| ("freshness", news_freshness(&freshness))),
+        );
+        let response = self
+            .api_request(BRAVE_NEWS_SEARCH_ENDPOINT, &params)
+            .await?;
+        let response: ResultList<NewsSearchResult> = serde_json::from_str(&response)
+            .map_err(|e| anyhow::anyhow!("Invalid news search response: {}", e))?;
+        Ok(response.results)
+    }
+
+    /// Perform a local search, returning typed results
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or the response cannot be parsed
+    pub async fn local_results(
+        &self,
+        query: &str,
+        config: Option<SearchConfig>,
+    ) -> Result<Vec<LocalSearchResult>> {
+        let config = config.unwrap_or_default();
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("result_filter", "locations".to_string()),
+        ];
+        params.extend(config.count.map(|count| ("count", count.to_string())));
+        params.extend(config.country.map(|country| ("country", country)));
+        let response = self.api_request(BRAVE_WEB_SEARCH_ENDPOINT, &params).await?;
+        parse_local_results(&response)
+    }
+
+    /// Perform a local search, returning every place found, including places without a
+    /// web page
+    ///
+    /// Results are cached like [`Self::search_structured`] does, so that they are
+    /// available in offline mode.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or the response cannot be parsed
+    pub async fn places(
+        &self,
+        query: &str,
+        config: Option<SearchConfig>,
+    ) -> Result<Vec<LocalSearchResult>> {
+        let cache_key = SearchCache::key(&("places", query, &config))?;
+        self.cached(&cache_key, SearchType::Local, query, async {
+            self.local_results(query, config).await
+        })
+        .await
+    }
+
+    /// Perform a search based on the specified type, returning the results as
+    /// [`SearchResult`]s
+    ///
+    /// Local searches that find no places fall back to a web search, and places
+    /// without a web page are left out. Results are cached like [`Self::search`] does,
+    /// separately from its text results.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search fails or the response cannot be parsed
+    pub async fn search_structured(
+        &self,
+        search_type: SearchType,
+        query: &str,
+        config: Option<SearchConfig>,
+    ) -> Result<Vec<SearchResult>> {
+        let cache_key = SearchCache::key(&("structured", search_type, query, &config))?;
+        self.cached(&cache_key, search_type, query, async {
+            Ok(match search_type {
+                SearchType::Web => self
+                    .web_results(query, config)
+                    .await?
+                    .into_iter()
+                    .map(SearchResult::from)
+                    .collect(),
+                SearchType::News => self
+                    .news_results(query, config)
+                    .await?
+                    .into_iter()
+                    .map(SearchResult::from)
+                    .collect(),
+                SearchType::Local => {
+                    let places: Vec<SearchResult> = self
+                        .local_results(query, config.clone())
+                        .await?
+                        .iter()
+                        .filter_map(LocalSearchResult::to_search_result)
+                        .collect();
+                    match places.is_empty() {
+                        true => self
+                            .web_results(query, config)
+                            .await?
+                            .into_iter()
+                            .map(SearchResult::from)
+                            .collect(),
+                        false => places,
+                    }
+                }
+            })
+        })
+        .await
+    }
+
+    /// Serve results from the cache, or fetch and cache them; only from the cache in
+    /// offline mode
+    async fn cached<T: Serialize + serde::de::DeserializeOwned>(
+        &self,
+        cache_key: &str,
+        search_type: SearchType,
+        query: &str,
+        fetch: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        if self.offline {
+            let cache = self
+                .cache
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Offline mode requires the search cache"))?;
+            let cached = cache.get_stale(cache_key).await.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No cached {} search results for '{}' (offline mode)",
+                    search_type,
+                    query
+                )
+            })?;
+            return Ok(serde_json::from_str(&cached)?);
         }
 
-        Ok(result)
+        if let Some(cache) = &self.cache {
+            if let Some(results) = cache
+                .get(cache_key)
+                .await
+                .and_then(|cached| serde_json::from_str(&cached).ok())
+            {
+                debug!(
+                    "Using cached {} search results for '{}'",
+                    search_type, query
+                );
+                return Ok(results);
+            }
+        }
+
+        let results = fetch.await?;
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache
+                .put(cache_key, &serde_json::to_string(&results)?)
+                .await
+            {
+                warn!("Failed to cache search results: {}", e);
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Send a GET request to a Brave Search API endpoint, returning the response body
+    async fn api_request(&self, endpoint: &str, params: &[(&str, String)]) -> Result<String> {
+        let client = self.http_client()?;
+        let response = client
+            .get(endpoint)
+            .query(params)
+            .header("Accept", "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .send()
+            .await
+            .map_err(|e| anyhow::anyhow!("Could not reach the Brave Search API: {}", e))?;
+
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get("retry-after")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await?;
+        if !status.is_success() {
+            let message = body.chars().take(500).collect();
+            return Err(
+                BraveSearchError::from_status(status.as_u16(), retry_after, message).into(),
+            );
+        }
+        Ok(body)
     }
 
     /// Perform a search based on the specified type
     ///
+    /// When a cache is configured (see `with_cache`), fresh cached responses for the same
+    /// search type, query, and configuration are returned without calling the API.
+    ///
     /// # Arguments
     ///
     /// * `search_type` - The type of search to perform
@@ -200,14 +897,94 @@ impl BraveSearchClient {
         query: &str,
         config: Option<SearchConfig>,
     ) -> Result<String> {
-        match search_type {
+        let cache_key = SearchCache::key(&(search_type, query, &config))?;
+        if self.offline {
+            let cache = self
+                .cache
+                .as_ref()
+                .ok_or_else(|| anyhow::anyhow!("Offline mode requires the search cache"))?;
+            return cache.get_stale(&cache_key).await.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "No cached {} search results for '{}' (offline mode)",
+                    search_type,
+                    query
+                )
+            });
+        }
+
+        if let Some(cache) = &self.cache {
+            if let Some(cached) = cache.get(&cache_key).await {
+                debug!(
+                    "Using cached {} search results for '{}'",
+                    search_type, query
+                );
+                return Ok(cached);
+            }
+        }
+
+        let result = match search_type {
             SearchType::Web => self.web_search(query, config).await,
             SearchType::News => self.news_search(query, config).await,
             SearchType::Local => self.local_search(query, config).await,
+        }?;
+
+        if let Some(cache) = &self.cache {
+            if let Err(e) = cache.put(&cache_key, &result).await {
+                warn!("Failed to cache search results: {}", e);
+            }
         }
+
+        Ok(result)
     }
 }
 
+/// Convert a search router response into a result, classifying `Error:` responses
+fn check_router_result(result: String) -> Result<String> {
+    if result.starts_with("Error:") {
+        return Err(BraveSearchError::from_error_text(&result).into());
+    }
+
+    Ok(result)
+}
+
+/// Parse the web results of a web search response
+fn parse_web_results(response: &str) -> Result<Vec<WebSearchResult>> {
+    let response: WebResponse = serde_json::from_str(response)
+        .map_err(|e| anyhow::anyhow!("Invalid web search response: {}", e))?;
+    Ok(response.web.map(|web| web.results).unwrap_or_default())
+}
+
+/// Parse the places of a local search response
+fn parse_local_results(response: &str) -> Result<Vec<LocalSearchResult>> {
+    let response: WebResponse = serde_json::from_str(response)
+        .map_err(|e| anyhow::anyhow!("Invalid local search response: {}", e))?;
+    Ok(response
+        .locations
+        .map(|locations| locations.results)
+        .unwrap_or_default())
+}
+
+/// The news API's value for a freshness filter given as `h`, `d`, `w`, `m`, or `y`
+///
+/// The API has no hourly filter, so `h` is narrowed to the past day.
+fn news_freshness(freshness: &str) -> String {
+    match freshness {
+        "h" | "d" => "pd".to_string(),
+        "w" => "pw".to_string(),
+        "m" => "pm".to_string(),
+        "y" => "py".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Look up the API key in the OS keyring, treating an unavailable keyring as no key
+fn keyring_api_key() -> Option<String> {
+    auth::load_api_key().unwrap_or_else(|e| {
+        debug!("Keyring lookup for Brave API key failed: {}", e);
+        None
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,18 +1015,217 @@ mod tests {
         assert!(config.freshness.is_none());
     }
 
+    #[test]
+    fn test_key_status_from_status() {
+        assert_eq!(KeyStatus::from_status(200), KeyStatus::Valid);
+        assert_eq!(KeyStatus::from_status(401), KeyStatus::InvalidKey);
+        assert_eq!(KeyStatus::from_status(422), KeyStatus::InvalidKey);
+        assert_eq!(KeyStatus::from_status(429), KeyStatus::QuotaExceeded);
+        assert_eq!(KeyStatus::from_status(500), KeyStatus::Unexpected(500));
+    }
+
+    #[test]
+    fn test_quota_info_from_headers() {
+        let mut headers = reqwest::header::HeaderMap::new();
+        headers.insert("X-RateLimit-Limit", "1, 15000".parse().unwrap());
+        headers.insert("X-RateLimit-Remaining", "0, 14999".parse().unwrap());
+
+        let quota = QuotaInfo::from_headers(&headers);
+        assert_eq!(quota.limit.as_deref(), Some("1, 15000"));
+        assert_eq!(quota.remaining.as_deref(), Some("0, 14999"));
+        assert!(quota.policy.is_none());
+        assert!(quota.reset.is_none());
+    }
+
+    #[test]
+    fn test_brave_search_error_from_status() {
+        let error = BraveSearchError::from_status(
+            429,
+            Some(Duration::from_secs(3)),
+            "too many".to_string(),
+        );
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(3)));
+
+        let error = BraveSearchError::from_status(402, None, "plan".to_string());
+        assert_eq!(
+            error,
+            BraveSearchError::SubscriptionRequired("plan".to_string())
+        );
+        assert!(!error.is_retryable());
+
+        let error = BraveSearchError::from_status(503, None, "down".to_string());
+        assert!(error.is_retryable());
+        assert!(error.retry_after().is_none());
+    }
+
+    #[test]
+    fn test_brave_search_error_from_error_text() {
+        let error = BraveSearchError::from_error_text(
+            "Error: request failed with status 429 Too Many Requests, retry-after: 2",
+        );
+        assert!(matches!(error, BraveSearchError::RateLimited { .. }));
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(2)));
+
+        let error = BraveSearchError::from_error_text("Error: 401 Unauthorized");
+        assert!(matches!(error, BraveSearchError::Unauthorized(_)));
+
+        let error = BraveSearchError::from_error_text("Error: connection reset");
+        assert_eq!(
+            error,
+            BraveSearchError::Other("connection reset".to_string())
+        );
+    }
+
+    #[test]
+    fn test_check_router_result() {
+        assert_eq!(
+            check_router_result("results".to_string()).unwrap(),
+            "results"
+        );
+
+        let error = check_router_result("Error: status 500".to_string()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<BraveSearchError>(),
+            Some(BraveSearchError::ServerError { status: 500, .. })
+        ));
+    }
+
     #[tokio::test]
     async fn test_brave_search_client_creation() {
         // Test with explicit API key
         let client = BraveSearchClient::new(Some("test_key".to_string()));
         assert!(client.is_ok());
 
-        // Test without API key and no environment variable
+        // Test without API key, no environment variable and no keyring entry
         unsafe {
             std::env::remove_var("BRAVE_API_KEY");
         }
-        let client = BraveSearchClient::new(None);
-        assert!(client.is_err());
+        if keyring_api_key().is_none() {
+            let client = BraveSearchClient::new(None);
+            assert!(client.is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_served_from_cache() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let cache = SearchCache::new(temp_dir.path(), Duration::from_secs(60));
+        let config = SearchConfig {
+            count: Some(3),
+            ..Default::default()
+        };
+        let key = SearchCache::key(&(SearchType::Web, "rust", &Some(config.clone())))?;
+        cache.put(&key, "cached results").await?;
+
+        let client = BraveSearchClient::new(Some("test_key".to_string()))?.with_cache(cache);
+        let results = client.search(SearchType::Web, "rust", Some(config)).await?;
+        assert_eq!(results, "cached results");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_offline_search_cache_miss() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let cache = SearchCache::new(temp_dir.path(), Duration::from_secs(60));
+        let client = BraveSearchClient::new(Some("test_key".to_string()))?
+            .with_cache(cache)
+            .with_offline(true);
+
+        let error = client
+            .search(SearchType::News, "rust", None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("offline mode"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_structured_results() -> Result<()> {
+        let web = r#"{"type":"search","web":{"results":[
+            {"title":"Rust","url":"https://www.rust-lang.org/","description":"A <strong>language</strong>","age":"2 days ago","extra_snippets":["Fast"],"profile":{"name":"Rust"}},
+            {"title":"No description","url":"https://example.com/"}
+        ]}}"#;
+        let results = parse_web_results(web)?;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].age.as_deref(), Some("2 days ago"));
+        assert_eq!(results[0].extra_snippets, vec!["Fast"]);
+        assert_eq!(
+            SearchResult::from(results[1].clone()),
+            SearchResult {
+                title: "No description".to_string(),
+                url: "https://example.com/".to_string(),
+                description: String::new(),
+            }
+        );
+        assert!(parse_web_results(r#"{"type":"search"}"#)?.is_empty());
+        assert!(parse_web_results("Error: not JSON").is_err());
+
+        let local = r#"{"locations":{"results":[
+            {"title":"Cafe","provider_url":"https://maps.example/cafe","postal_address":{"displayAddress":"1 Main St","postalCode":"100-0005"},"coordinates":[35.68,139.76],"contact":{"telephone":"+81 3-1234-5678"}},
+            {"title":"No page"}
+        ]}}"#;
+        let places: Vec<SearchResult> = parse_local_results(local)?
+            .iter()
+            .filter_map(LocalSearchResult::to_search_result)
+            .collect();
+        assert_eq!(
+            places,
+            vec![SearchResult {
+                title: "Cafe".to_string(),
+                url: "https://maps.example/cafe".to_string(),
+                description: "1 Main St".to_string(),
+            }]
+        );
+        let cafe = &parse_local_results(local)?[0];
+        assert_eq!(cafe.address(), Some("1 Main St"));
+        assert_eq!(cafe.coordinates, Some([35.68, 139.76]));
+        assert_eq!(
+            cafe.postal_address
+                .as_ref()
+                .and_then(|address| address.postal_code.as_deref()),
+            Some("100-0005")
+        );
+        assert_eq!(
+            cafe.contact
+                .as_ref()
+                .and_then(|contact| contact.telephone.as_deref()),
+            Some("+81 3-1234-5678")
+        );
+        assert_eq!(news_freshness("w"), "pw");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_structured_search_served_from_cache() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let cache = SearchCache::new(temp_dir.path(), Duration::from_secs(60));
+        let client = BraveSearchClient::new(Some("test_key".to_string()))?
+            .with_cache(cache)
+            .with_offline(true);
+        let error = client
+            .search_structured(SearchType::Web, "rust", None)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("offline mode"));
+
+        let cache = SearchCache::new(temp_dir.path(), Duration::from_secs(60));
+        let key =
+            SearchCache::key(&("structured", SearchType::Web, "rust", &None::<SearchConfig>))?;
+        let results = vec![SearchResult {
+            title: "Rust".to_string(),
+            url: "https://www.rust-lang.org/".to_string(),
+            description: String::new(),
+        }];
+        cache.put(&key, &serde_json::to_string(&results)?).await?;
+        let client = BraveSearchClient::new(Some("test_key".to_string()))?.with_cache(cache);
+        assert_eq!(
+            client
+                .search_structured(SearchType::Web, "rust", None)
+                .await?,
+            results
+        );
+        Ok(())
     }
 
     #[tokio::test]
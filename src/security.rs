@@ -0,0 +1,374 @@
+//! Security posture of captured sites
+//!
+//! For archived security research, the state of a site's transport security at
+//! capture time is worth keeping with the capture: the TLS certificate chain the origin
+//! presented, its HSTS policy, and which of the common security headers the page was
+//! served with. The details are read from the [`NetworkLog`](crate::evidence::NetworkLog)
+//! of the page load, and certificates that had expired or were not yet valid, insecure
+//! connections, and pages served over plain HTTP are flagged as issues.
+
+use crate::evidence::{NetworkEntry, TlsDetails, origin_of};
+use crate::timestamp::read_value;
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+/// Response headers recorded as security headers, in the order they are listed
+pub const SECURITY_HEADERS: &[&str] = &[
+    "strict-transport-security",
+    "content-security-policy",
+    "x-frame-options",
+    "x-content-type-options",
+    "referrer-policy",
+    "permissions-policy",
+    "cross-origin-opener-policy",
+];
+
+/// DER encodings of the attribute types shown in certificate names
+const NAME_ATTRIBUTES: &[(&[u8], &str)] =
+    &[(&[0x55, 0x04, 0x03], "CN"), (&[0x55, 0x04, 0x0a], "O")];
+
+/// A certificate of a chain
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CertificateInfo {
+    /// Subject, e.g. `CN=example.com, O=Example Inc.`
+    pub subject: String,
+    /// Issuer
+    pub issuer: String,
+    /// Start of the validity period
+    pub not_before: DateTime<Utc>,
+    /// End of the validity period
+    pub not_after: DateTime<Utc>,
+    /// Hex SHA-256 fingerprint of the DER encoding
+    pub sha256: String,
+}
+
+impl CertificateInfo {
+    /// Read the names and validity of a DER-encoded X.509 certificate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the certificate is malformed
+    pub fn parse(der: &[u8]) -> Result<Self> {
+        let (_, certificate, _) = read_value(der)?;
+        let (_, tbs, _) = read_value(certificate)?;
+        let mut fields = Vec::new();
+        let mut rest = tbs;
+        while !rest.is_empty() {
+            let (tag, content, next) = read_value(rest)?;
+            fields.push((tag, content));
+            rest = next;
+        }
+        // The version is an explicit [0] field that is left out for version 1
+        if fields.first().is_some_and(|(tag, _)| *tag == 0xa0) {
+            fields.remove(0);
+        }
+        // Then come the serial number, signature algorithm, issuer, validity, and subject
+        let (Some(&(_, issuer)), Some(&(_, validity)), Some(&(_, subject))) =
+            (fields.get(2), fields.get(3), fields.get(4))
+        else {
+            bail!("Certificate has too few fields");
+        };
+        let (not_before_tag, not_before, rest) = read_value(validity)?;
+        let (not_after_tag, not_after, _) = read_value(rest)?;
+        Ok(Self {
+            subject: name(subject)?,
+            issuer: name(issuer)?,
+            not_before: time(not_before_tag, not_before)?,
+            not_after: time(not_after_tag, not_after)?,
+            sha256: format!("{:x}", Sha256::digest(der)),
+        })
+    }
+}
+
+/// The common name and organization of an X.509 name
+fn name(der: &[u8]) -> Result<String> {
+    let mut parts = Vec::new();
+    let mut sets = der;
+    while !sets.is_empty() {
+        let (_, set, next) = read_value(sets)?;
+        sets = next;
+        let (_, attribute, _) = read_value(set)?;
+        let (_, oid, value) = read_value(attribute)?;
+        let (_, value, _) = read_value(value)?;
+        if let Some((_, label)) = NAME_ATTRIBUTES.iter().find(|(known, _)| *known == oid) {
+            parts.push(format!("{}={}", label, String::from_utf8_lossy(value)));
+        }
+    }
+    Ok(parts.join(", "))
+}
+
+/// A UTCTime (tag 0x17) or GeneralizedTime (0x18)
+fn time(tag: u8, der: &[u8]) -> Result<DateTime<Utc>> {
+    let text = std::str::from_utf8(der)?.trim_end_matches('Z');
+    let text = match tag {
+        // Two-digit years from 50 are in the 1900s
+        0x17 if text.starts_with(['5', '6', '7', '8', '9']) => format!("19{}", text),
+        0x17 => format!("20{}", text),
+        _ => text.to_string(),
+    };
+    Ok(NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%S")?.and_utc())
+}
+
+/// The security posture of an origin at capture time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecurityPosture {
+    /// Origin of the page, e.g. `https://example.com`
+    pub origin: String,
+    /// When the page was received
+    pub checked_at: DateTime<Utc>,
+    /// How Chrome rated the security of the connection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security_state: Option<String>,
+    /// TLS connection and leaf certificate, for HTTPS pages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsDetails>,
+    /// Certificate chain, leaf first, when Chrome provided it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chain: Vec<CertificateInfo>,
+    /// The HSTS policy, if the page was served with one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hsts: Option<String>,
+    /// Security headers the page was served with
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+    /// Security headers the page was served without
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub missing_headers: Vec<String>,
+    /// Problems found, such as an expired certificate
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub issues: Vec<String>,
+}
+
+impl SecurityPosture {
+    /// The posture of the page loaded in a logged page load: its document response, or
+    /// None when the log has no document response for a web page
+    ///
+    /// `chains` are the DER-encoded certificate chains fetched per origin.
+    pub fn from_network(
+        entries: &[NetworkEntry],
+        chains: &HashMap<String, Vec<Vec<u8>>>,
+    ) -> Option<Self> {
+        let document = entries
+            .iter()
+            .filter(|entry| entry.resource_type == "Document")
+            .find(|entry| !(300..400).contains(&entry.status))?;
+        let origin = origin_of(&document.url)?;
+        let chain = chains.get(&origin).map(Vec::as_slice).unwrap_or_default();
+        Some(Self::new(document, origin, chain, Utc::now()))
+    }
+
+    /// The posture of an origin from its document response and certificate chain,
+    /// flagging what was wrong at `now`
+    pub fn new(
+        document: &NetworkEntry,
+        origin: String,
+        chain: &[Vec<u8>],
+        now: DateTime<Utc>,
+    ) -> Self {
+        let header = |name: &str| {
+            document
+                .response_headers
+                .iter()
+                .find(|(header, _)| header.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone())
+        };
+        let mut headers = Vec::new();
+        let mut missing_headers = Vec::new();
+        for name in SECURITY_HEADERS {
+            match header(name) {
+                Some(value) => headers.push((name.to_string(), value)),
+                None => missing_headers.push(name.to_string()),
+            }
+        }
+        let chain: Vec<CertificateInfo> = chain
+            .iter()
+            .filter_map(|der| CertificateInfo::parse(der).ok())
+            .collect();
+
+        let mut issues = Vec::new();
+        let date = |time: DateTime<Utc>| time.format("%Y-%m-%d").to_string();
+        if !origin.starts_with("https:") {
+            issues.push("Served over plain HTTP".to_string());
+        }
+        if let Some(state) = document
+            .security_state
+            .as_deref()
+            .filter(|state| state.starts_with("Insecure"))
+        {
+            issues.push(format!("Chrome rated the connection {}", state));
+        }
+        if let Some(tls) = &document.tls {
+            if let Some(valid_to) = tls.valid_to.filter(|valid_to| *valid_to < now) {
+                issues.push(format!("Certificate expired on {}", date(valid_to)));
+            }
+            if let Some(valid_from) = tls.valid_from.filter(|valid_from| *valid_from > now) {
+                issues.push(format!("Certificate not valid until {}", date(valid_from)));
+            }
+            if tls.certificate_transparency == "NotCompliant" {
+                issues
+                    .push("Certificate does not comply with Certificate Transparency".to_string());
+            }
+        }
+        // The leaf is checked above, from what Chrome reported
+        let intermediates = match document.tls {
+            Some(_) => chain.get(1..).unwrap_or_default(),
+            None => &chain[..],
+        };
+        for certificate in intermediates {
+            if certificate.not_after < now {
+                issues.push(format!(
+                    "Chain certificate {} expired on {}",
+                    certificate.subject,
+                    date(certificate.not_after)
+                ));
+            }
+        }
+
+        Self {
+            origin,
+            checked_at: document.received_at,
+            security_state: document.security_state.clone(),
+            tls: document.tls.clone(),
+            chain,
+            hsts: header("strict-transport-security"),
+            headers,
+            missing_headers,
+            issues,
+        }
+    }
+
+    /// Whether any problem was found
+    pub fn is_flagged(&self) -> bool {
+        !self.issues.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// DER encoding of a value with a short length
+    fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+        [&[tag, content.len() as u8][..], content].concat()
+    }
+
+    fn name_der(common_name: &str) -> Vec<u8> {
+        let attribute = der(
+            0x30,
+            &[
+                der(0x06, &[0x55, 0x04, 0x03]),
+                der(0x0c, common_name.as_bytes()),
+            ]
+            .concat(),
+        );
+        der(0x30, &der(0x31, &attribute))
+    }
+
+    fn certificate_der(subject: &str, issuer: &str, not_after: &[u8]) -> Vec<u8> {
+        let validity = der(
+            0x30,
+            &[der(0x17, b"200101000000Z"), der(0x18, not_after)].concat(),
+        );
+        let tbs = der(
+            0x30,
+            &[
+                der(0xa0, &der(0x02, &[2])),
+                der(0x02, &[7]),
+                der(0x30, &der(0x06, &[0x2a])),
+                name_der(issuer),
+                validity,
+                name_der(subject),
+            ]
+            .concat(),
+        );
+        der(0x30, &tbs)
+    }
+
+    #[test]
+    fn test_posture_flags_expired_certificates() {
+        let leaf = certificate_der("example.com", "Example CA", b"20300101000000Z");
+        let intermediate = certificate_der("Example CA", "Example Root", b"20250101000000Z");
+        let info = CertificateInfo::parse(&leaf).unwrap();
+        assert_eq!(info.subject, "CN=example.com");
+        assert_eq!(info.issuer, "CN=Example CA");
+        assert_eq!(info.not_before.to_rfc3339(), "2020-01-01T00:00:00+00:00");
+        assert_eq!(info.not_after.to_rfc3339(), "2030-01-01T00:00:00+00:00");
+
+        let now = DateTime::parse_from_rfc3339("2026-10-16T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let tls = TlsDetails {
+            protocol: "TLS 1.3".to_string(),
+            cipher: "AES_128_GCM".to_string(),
+            key_exchange: "X25519".to_string(),
+            subject: "example.com".to_string(),
+            issuer: "Example CA".to_string(),
+            san: vec!["example.com".to_string()],
+            valid_from: Some(info.not_before),
+            valid_to: Some(info.not_after),
+            certificate_transparency: "Compliant".to_string(),
+        };
+        let document = NetworkEntry {
+            url: "https://example.com/".to_string(),
+            resource_type: "Document".to_string(),
+            status: 200,
+            status_text: "OK".to_string(),
+            protocol: Some("h2".to_string()),
+            mime_type: "text/html".to_string(),
+            remote_ip: None,
+            remote_port: None,
+            request_headers: Vec::new(),
+            response_headers: vec![
+                (
+                    "Strict-Transport-Security".to_string(),
+                    "max-age=31536000".to_string(),
+                ),
+                ("X-Frame-Options".to_string(), "DENY".to_string()),
+            ],
+            from_cache: false,
+            tls: Some(tls),
+            security_state: Some("Secure".to_string()),
+            received_at: now,
+        };
+        let chains = HashMap::from([(
+            "https://example.com".to_string(),
+            vec![leaf.clone(), intermediate.clone()],
+        )]);
+        let posture =
+            SecurityPosture::from_network(std::slice::from_ref(&document), &chains).unwrap();
+        assert_eq!(posture.origin, "https://example.com");
+        assert_eq!(posture.chain.len(), 2);
+        assert_eq!(posture.hsts.as_deref(), Some("max-age=31536000"));
+        assert_eq!(posture.headers.len(), 2);
+        assert!(
+            posture
+                .missing_headers
+                .contains(&"content-security-policy".to_string())
+        );
+
+        let posture = SecurityPosture::new(
+            &document,
+            posture.origin,
+            &[leaf.clone(), intermediate],
+            now,
+        );
+        assert_eq!(
+            posture.issues,
+            vec!["Chain certificate CN=Example CA expired on 2025-01-01"]
+        );
+        assert!(posture.is_flagged());
+
+        let posture = SecurityPosture::new(
+            &document,
+            "https://example.com".to_string(),
+            &[leaf],
+            DateTime::parse_from_rfc3339("2031-01-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        );
+        assert_eq!(posture.issues, vec!["Certificate expired on 2030-01-01"]);
+    }
+}
@@ -0,0 +1,357 @@
+//! Persistent Chrome profiles for capturing pages behind a login
+//!
+//! Chrome keeps cookies and local storage in its user-data directory. Launching the
+//! capture browser with a fixed directory instead of a fresh temporary one lets a
+//! session started once in a visible window (see [`LoginWindow`]) carry over to
+//! every later headless capture.
+//!
+//! Login sessions are also saved per domain, as the cookies the site set during the
+//! login (see [`SessionStore`]), so later captures of the domain send them without
+//! a profile directory.
+
+use crate::atomic;
+use crate::cookies::Cookie;
+use crate::proxy::ProxyConfig;
+use anyhow::Result;
+use headless_chrome::protocol::cdp::Browser::Close;
+use headless_chrome::protocol::cdp::Network::{self, GetAllCookies};
+use headless_chrome::{Browser, LaunchOptions, Tab};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a visible login window may sit without browser events before it is closed
+const LOGIN_IDLE_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+
+/// How long Chrome may take to write the profile and exit after being asked to close
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Size of the visible login window
+const LOGIN_WINDOW_SIZE: (u32, u32) = (1280, 900);
+
+/// Launch Chrome, optionally with a persistent user-data directory and a proxy
+///
+/// Without a profile directory, Chrome starts with a fresh temporary profile that is
+/// deleted when it exits. The proxy's credentials are not passed to Chrome; tabs answer
+/// its challenges with [`ProxyConfig::authenticate`].
+///
+/// # Errors
+///
+/// Returns an error if the profile directory cannot be created, Chrome cannot use the
+/// proxy, or the browser cannot be launched
+pub fn launch_browser(
+    headless: bool,
+    profile_dir: Option<&Path>,
+    proxy: Option<&ProxyConfig>,
+) -> Result<Browser> {
+    if let Some(dir) = profile_dir {
+        std::fs::create_dir_all(dir)?;
+    }
+    if let Some(proxy) = proxy {
+        proxy.check_browser_support()?;
+    }
+    let proxy_server = proxy.map(|proxy| format!("--proxy-server={}", proxy.server()));
+
+    let mut options = LaunchOptions::default_builder();
+    options
+        .headless(headless)
+        .sandbox(false)
+        .user_data_dir(profile_dir.map(Path::to_path_buf))
+        .args(proxy_server.iter().map(OsStr::new).collect());
+    if !headless {
+        options
+            .window_size(Some(LOGIN_WINDOW_SIZE))
+            .idle_browser_timeout(LOGIN_IDLE_TIMEOUT);
+    }
+    let options = options
+        .build()
+        .map_err(|e| anyhow::anyhow!("Failed to build launch options: {}", e))?;
+    Browser::new(options)
+}
+
+/// A visible Chrome window on a persistent profile, for logging into a site by hand
+pub struct LoginWindow {
+    browser: Browser,
+    tab: Arc<Tab>,
+}
+
+impl LoginWindow {
+    /// Open a visible Chrome window, behind a proxy if one is given, and load the given URL
+    ///
+    /// Without a profile directory, the window uses a fresh temporary profile, and the
+    /// session only outlives it through [`LoginWindow::cookies`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the browser cannot be launched or the page cannot be loaded
+    pub fn open(
+        url: &str,
+        profile_dir: Option<&Path>,
+        proxy: Option<&ProxyConfig>,
+    ) -> Result<Self> {
+        let browser = launch_browser(false, profile_dir, proxy)?;
+        let tab = browser.new_tab()?;
+        if let Some(proxy) = proxy {
+            proxy.authenticate(&tab)?;
+        }
+        tab.navigate_to(url)?;
+        Ok(Self { browser, tab })
+    }
+
+    /// The URL the window currently shows
+    pub fn current_url(&self) -> String {
+        self.tab.get_url()
+    }
+
+    /// Whether the browser is still running, i.e. the user has not closed it
+    pub fn is_open(&self) -> bool {
+        self.browser.get_version().is_ok()
+    }
+
+    /// The cookies the browser holds for a domain, its subdomains, and its parent
+    /// domains
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the browser has closed
+    pub fn cookies(&self, domain: &str) -> Result<Vec<Cookie>> {
+        let cookies = self.tab.call_method(GetAllCookies(None))?.cookies;
+        Ok(cookies
+            .into_iter()
+            .filter(|cookie| cookie_applies(&cookie.domain, domain))
+            .map(from_devtools)
+            .collect())
+    }
+
+    /// Close the window, letting Chrome write the session to the profile
+    ///
+    /// Chrome flushes cookies to disk only on a clean shutdown, so the browser is asked
+    /// to close itself rather than being killed.
+    pub async fn close(self) {
+        // The connection drops as Chrome exits, so the call may not get a reply
+        let _ = self.tab.call_method(Close(None));
+
+        // Dropping the browser kills Chrome, so give it time to exit on its own first
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while Instant::now() < deadline && self.browser.get_version().is_ok() {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+}
+
+/// Login sessions saved per domain, as JSON files of cookies in a directory
+#[derive(Debug, Clone)]
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    /// Create a session store in the given directory
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Open the session store in the platform data directory
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the platform data directory cannot be determined
+    pub fn open_default() -> Result<Self> {
+        let dir = dirs::data_dir()
+            .map(|dir| dir.join("webpage-save").join("sessions"))
+            .ok_or_else(|| anyhow::anyhow!("Could not determine the platform data directory"))?;
+        Ok(Self::new(dir))
+    }
+
+    /// The file the session of a domain is saved in
+    pub fn path(&self, domain: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", domain))
+    }
+
+    /// Save the cookies of a domain's session, replacing any earlier session
+    ///
+    /// The file is only readable by the current user, since it holds live session
+    /// cookies.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub fn save(&self, domain: &str, cookies: &[Cookie]) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.dir)?;
+        let path = self.path(domain);
+        let file = atomic::temp_file_for(&path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.as_file()
+                .set_permissions(std::fs::Permissions::from_mode(0o600))?;
+        }
+        serde_json::to_writer_pretty(file.as_file(), cookies)?;
+        atomic::persist(file, &path)?;
+        Ok(path)
+    }
+
+    /// The domains with a saved session, sorted
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read
+    pub fn domains(&self) -> Result<Vec<String>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let mut domains = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    domains.push(stem.to_string());
+                }
+            }
+        }
+        domains.sort();
+        Ok(domains)
+    }
+
+    /// The saved cookies of a domain's session, or None when there is none
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed
+    pub fn load(&self, domain: &str) -> Result<Option<Vec<Cookie>>> {
+        let path = self.path(domain);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = std::fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&json).map_err(|e| {
+            anyhow::anyhow!("Invalid session file {}: {}", path.display(), e)
+        })?))
+    }
+
+    /// The saved cookies of every domain's session
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a session file cannot be read or parsed
+    pub fn load_all(&self) -> Result<Vec<Cookie>> {
+        let mut cookies = Vec::new();
+        for domain in self.domains()? {
+            cookies.extend(self.load(&domain)?.unwrap_or_default());
+        }
+        Ok(cookies)
+    }
+
+    /// Delete the saved session of a domain
+    ///
+    /// # Returns
+    ///
+    /// Returns `true` if a session was deleted and `false` if none was saved
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be deleted
+    pub fn delete(&self, domain: &str) -> Result<bool> {
+        match std::fs::remove_file(self.path(domain)) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// The domain and start URL of a `login` target, given as a domain or a URL
+///
+/// A leading `www.` is dropped from the domain, so the session covers the whole site.
+///
+/// # Errors
+///
+/// Returns an error if the target is neither a domain nor an HTTP(S) URL
+pub fn login_target(target: &str) -> Result<(String, String)> {
+    let url = match target.contains("://") {
+        true => target.to_string(),
+        false => format!("https://{}/", target.trim_end_matches('/')),
+    };
+    let parsed = url::Url::parse(&url)
+        .map_err(|e| anyhow::anyhow!("Invalid domain or URL {}: {}", target, e))?;
+    let host = parsed
+        .host_str()
+        .filter(|_| matches!(parsed.scheme(), "http" | "https"))
+        .ok_or_else(|| anyhow::anyhow!("Not a domain or HTTP(S) URL: {}", target))?;
+    let domain = host.strip_prefix("www.").unwrap_or(host).to_lowercase();
+    Ok((domain, url))
+}
+
+/// Whether a cookie set for `cookie_domain` belongs to the session of `domain`
+fn cookie_applies(cookie_domain: &str, domain: &str) -> bool {
+    let cookie_domain = cookie_domain.trim_start_matches('.');
+    cookie_domain == domain
+        || cookie_domain.ends_with(&format!(".{}", domain))
+        || domain.ends_with(&format!(".{}", cookie_domain))
+}
+
+/// Convert a cookie read through the DevTools protocol
+fn from_devtools(cookie: Network::Cookie) -> Cookie {
+    Cookie {
+        name: cookie.name,
+        value: cookie.value,
+        domain: cookie.domain,
+        path: cookie.path,
+        secure: cookie.secure,
+        http_only: cookie.http_only,
+        expires: (!cookie.session).then_some(cookie.expires as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_target() -> Result<()> {
+        assert_eq!(
+            login_target("www.example.com")?,
+            (
+                "example.com".to_string(),
+                "https://www.example.com/".to_string()
+            )
+        );
+        assert_eq!(
+            login_target("https://news.example.com/login?next=/")?,
+            (
+                "news.example.com".to_string(),
+                "https://news.example.com/login?next=/".to_string()
+            )
+        );
+        assert!(login_target("ftp://example.com/").is_err());
+        assert!(cookie_applies(".example.com", "news.example.com"));
+        assert!(cookie_applies("login.example.com", "example.com"));
+        assert!(!cookie_applies("accounts.google.com", "example.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_session_store() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let store = SessionStore::new(dir.path().join("sessions"));
+        assert!(store.domains()?.is_empty());
+
+        let cookie = Cookie {
+            name: "sid".to_string(),
+            value: "secret".to_string(),
+            domain: ".example.com".to_string(),
+            path: "/".to_string(),
+            secure: true,
+            http_only: true,
+            expires: None,
+        };
+        store.save("example.com", std::slice::from_ref(&cookie))?;
+        assert_eq!(store.domains()?, vec!["example.com".to_string()]);
+        assert_eq!(store.load("example.com")?, Some(vec![cookie.clone()]));
+        assert_eq!(store.load_all()?, vec![cookie]);
+        assert!(store.delete("example.com")?);
+        assert!(!store.delete("example.com")?);
+        Ok(())
+    }
+}
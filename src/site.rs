@@ -0,0 +1,1186 @@
+//! Static websites published from output directories
+//!
+//! [`publish`] turns the Markdown captures recorded in an output directory's manifest
+//! into a small read-only website: a page per capture, an index of all of them, a page
+//! per tag, and full-text search. Search runs in the browser on an index of terms built
+//! at publish time (`search-index.js`), so the site can be hosted on any static file
+//! server or opened straight from disk.
+//!
+//! Markdown is rendered by a small converter covering what the Markdown output uses:
+//! headings, paragraphs, lists, block quotes, code blocks, tables, and inline emphasis,
+//! code, links, and images. Raw HTML in the Markdown is shown as text. Links between
+//! captured pages point to their copies on the site.
+
+use crate::atomic;
+use crate::extract::escape;
+use crate::frontmatter;
+use crate::index::{self, IndexEntry};
+use crate::manifest::OutputManifest;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use tera::{Context, Tera};
+use tracing::warn;
+use url::Url;
+
+/// Directory of the page files in a published site
+const PAGES_DIR: &str = "pages";
+
+/// Directory of the tag pages in a published site
+const TAGS_DIR: &str = "tags";
+
+/// Longest word added to the search index, in characters
+const MAX_TERM_CHARS: usize = 40;
+
+const STYLE: &str = r#"body { max-width: 46em; margin: 0 auto; padding: 1.5em; font-family: system-ui, -apple-system, "Segoe UI", "Noto Sans", sans-serif; line-height: 1.6; color: #111; }
+nav { margin-bottom: 1.5em; font-size: 0.9em; }
+a { color: #0645ad; }
+img { max-width: 100%; height: auto; }
+pre { background: #f5f5f5; padding: 0.8em; overflow-x: auto; border-radius: 4px; }
+code { font-family: ui-monospace, "SFMono-Regular", Menlo, Consolas, monospace; font-size: 0.9em; }
+blockquote { margin-left: 0; padding-left: 1em; border-left: 3px solid #ccc; color: #444; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ddd; padding: 0.3em 0.6em; }
+.meta { color: #666; font-size: 0.85em; word-break: break-all; }
+.tags { margin: 0.5em 0 1em; }
+.tag { display: inline-block; background: #eee; border-radius: 3px; padding: 0 0.4em; margin: 0 0.3em 0.3em 0; font-size: 0.85em; text-decoration: none; color: #333; }
+.pages { list-style: none; padding: 0; }
+.pages li { margin-bottom: 0.8em; }
+#search { width: 100%; font: inherit; padding: 0.4em; margin-bottom: 1em; box-sizing: border-box; }
+"#;
+
+const PAGE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{ page.title }}</title>
+<link rel="stylesheet" href="../style.css">
+</head>
+<body>
+<nav><a href="../index.html">Saved pages</a></nav>
+<div class="meta"><a href="{{ page.url }}">{{ page.url }}</a> · saved <time datetime="{{ page.date }}">{{ page.saved }}</time></div>
+{% if page.tags %}<div class="tags">{% for tag in page.tags %}<a class="tag" href="../tags/{{ tag.slug }}.html">{{ tag.name }}</a>{% endfor %}</div>{% endif %}
+<article>
+{{ body | safe }}
+</article>
+</body>
+</html>
+"#;
+
+const LIST_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{ heading }}</title>
+<link rel="stylesheet" href="{{ root }}style.css">
+</head>
+<body>
+{% if root %}<nav><a href="{{ root }}index.html">Saved pages</a></nav>{% endif %}
+<h1>{{ heading }}</h1>
+{% if search %}<input id="search" type="search" placeholder="Search {{ pages | length }} pages">{% endif %}
+{% if tags %}<div class="tags">{% for tag in tags %}<a class="tag" href="{{ root }}tags/{{ tag.slug }}.html">{{ tag.name }} ({{ tag.count }})</a>{% endfor %}</div>{% endif %}
+<ul class="pages" id="pages">
+{% for page in pages %}<li data-id="{{ page.id }}"><a href="{{ root }}pages/{{ page.slug }}.html">{{ page.title }}</a><div class="meta">{{ page.domain }} · <time datetime="{{ page.date }}">{{ page.saved }}</time></div></li>
+{% endfor %}</ul>
+{% if search %}<p id="no-results" hidden>No pages match.</p>
+<script src="search-index.js"></script>
+<script>
+(() => {
+  const input = document.getElementById('search');
+  const items = Array.from(document.querySelectorAll('#pages li'));
+  const empty = document.getElementById('no-results');
+  const keys = Object.keys(SEARCH_INDEX);
+  // Split like the index: words, with Chinese, Japanese, and Korean text in pairs of characters
+  const cjk = /[\p{Script=Han}\p{Script=Hiragana}\p{Script=Katakana}\p{Script=Hangul}]/u;
+  const terms = (text) => (text.toLowerCase().match(/[\p{L}\p{N}]+/gu) || []).flatMap((word) => {
+    const chars = Array.from(word);
+    return cjk.test(word) && chars.length > 2 ? chars.slice(1).map((c, i) => chars[i] + c) : [word];
+  });
+
+  input.addEventListener('input', () => {
+    let matches = null;
+    for (const term of terms(input.value)) {
+      const found = new Set();
+      for (const key of keys) {
+        if (key.startsWith(term)) {
+          SEARCH_INDEX[key].forEach((id) => found.add(id));
+        }
+      }
+      matches = matches === null ? found : new Set([...matches].filter((id) => found.has(id)));
+    }
+    let shown = 0;
+    for (const item of items) {
+      item.hidden = matches !== null && !matches.has(Number(item.dataset.id));
+      shown += item.hidden ? 0 : 1;
+    }
+    empty.hidden = shown > 0;
+  });
+})();
+</script>{% endif %}
+</body>
+</html>
+"#;
+
+/// What was written by [`publish`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PublishedSite {
+    /// Number of captured pages on the site
+    pub pages: usize,
+    /// Number of tag pages
+    pub tags: usize,
+    /// Saved pages left out because they have no Markdown capture
+    pub skipped: usize,
+}
+
+/// A tag, with the name of its page and how many pages have it
+#[derive(Debug, Clone, Serialize)]
+struct SiteTag {
+    name: String,
+    slug: String,
+    count: usize,
+}
+
+/// A captured page as shown on the site
+#[derive(Debug, Clone, Serialize)]
+struct SitePage {
+    id: usize,
+    slug: String,
+    title: String,
+    url: String,
+    domain: String,
+    date: String,
+    saved: String,
+    tags: Vec<SiteTag>,
+    #[serde(skip)]
+    markdown: String,
+}
+
+/// Publish the Markdown captures of an output directory as a static website
+///
+/// Pages from an earlier publish into the same site directory that are no longer
+/// captured are removed.
+///
+/// # Errors
+///
+/// Returns an error if the manifest cannot be read or the site cannot be written
+pub async fn publish(dir: &Path, site: &Path) -> Result<PublishedSite> {
+    let manifest = OutputManifest::load(dir).await?;
+    let entries = index::entries(dir, &manifest).await;
+
+    let mut skipped = 0;
+    let mut page_slugs = HashSet::new();
+    let mut tag_slugs = HashMap::new();
+    let mut taken_tags = HashSet::new();
+    let mut pages = Vec::new();
+    for entry in &entries {
+        let Some(markdown) = read_markdown(dir, entry).await else {
+            skipped += 1;
+            continue;
+        };
+        let stem = entry
+            .files
+            .iter()
+            .find(|file| file.ends_with(".md"))
+            .and_then(|file| Path::new(file).file_stem())
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let tags = entry
+            .tags
+            .iter()
+            .map(|tag| {
+                let slug = tag_slugs
+                    .entry(tag.to_lowercase())
+                    .or_insert_with(|| unique_slug(&tag.to_lowercase(), "tag", &mut taken_tags))
+                    .clone();
+                SiteTag {
+                    name: tag.clone(),
+                    slug,
+                    count: 0,
+                }
+            })
+            .collect();
+        pages.push(SitePage {
+            id: pages.len(),
+            slug: unique_slug(&stem, "page", &mut page_slugs),
+            title: entry.title.clone(),
+            url: entry.url.clone(),
+            domain: entry.domain.clone(),
+            date: entry.saved_at.to_rfc3339(),
+            saved: entry.saved_at.format("%Y-%m-%d %H:%M").to_string(),
+            tags,
+            markdown,
+        });
+    }
+
+    // Tags are listed under the name first used for them, most used first
+    let mut tags: BTreeMap<String, SiteTag> = BTreeMap::new();
+    for tag in pages.iter().flat_map(|page| &page.tags) {
+        tags.entry(tag.slug.clone())
+            .or_insert_with(|| tag.clone())
+            .count += 1;
+    }
+    let mut tags: Vec<SiteTag> = tags.into_values().collect();
+    tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+
+    let links: HashMap<String, String> = pages
+        .iter()
+        .map(|page| (page.url.clone(), format!("{}.html", page.slug)))
+        .collect();
+    tokio::fs::create_dir_all(site.join(PAGES_DIR)).await?;
+    tokio::fs::create_dir_all(site.join(TAGS_DIR)).await?;
+    atomic::write(&site.join("style.css"), STYLE).await?;
+
+    let mut written = HashSet::new();
+    for page in &pages {
+        let renderer = MarkdownRenderer {
+            base: Url::parse(&page.url).ok(),
+            links: &links,
+        };
+        let mut context = Context::new();
+        context.insert("page", page);
+        context.insert("body", &renderer.render(&page.markdown));
+        let file = format!("{}/{}.html", PAGES_DIR, page.slug);
+        atomic::write(
+            &site.join(&file),
+            Tera::one_off(PAGE_TEMPLATE, &context, true)?,
+        )
+        .await?;
+        written.insert(file);
+    }
+    for tag in &tags {
+        let tagged: Vec<&SitePage> = pages
+            .iter()
+            .filter(|page| page.tags.iter().any(|page_tag| page_tag.slug == tag.slug))
+            .collect();
+        let html = list_page(
+            &format!("Tagged “{}”", tag.name),
+            "../",
+            &tagged,
+            &[],
+            false,
+        )?;
+        let file = format!("{}/{}.html", TAGS_DIR, tag.slug);
+        atomic::write(&site.join(&file), html).await?;
+        written.insert(file);
+    }
+    let all: Vec<&SitePage> = pages.iter().collect();
+    atomic::write(
+        &site.join("index.html"),
+        list_page("Saved pages", "", &all, &tags, true)?,
+    )
+    .await?;
+    atomic::write(
+        &site.join("search-index.js"),
+        format!(
+            "const SEARCH_INDEX = {};\n",
+            serde_json::to_string(&search_index(&pages))?
+        ),
+    )
+    .await?;
+    remove_stale_pages(site, &written).await;
+
+    Ok(PublishedSite {
+        pages: pages.len(),
+        tags: tags.len(),
+        skipped,
+    })
+}
+
+/// The Markdown capture of a saved page, without front matter
+async fn read_markdown(dir: &Path, entry: &IndexEntry) -> Option<String> {
+    let file = entry.files.iter().find(|file| file.ends_with(".md"))?;
+    let markdown = tokio::fs::read_to_string(dir.join(file)).await.ok()?;
+    Some(
+        frontmatter::split_front_matter(&markdown)
+            .map_or(markdown.as_str(), |(_, body)| body)
+            .to_string(),
+    )
+}
+
+/// Render the index or a tag page
+fn list_page(
+    heading: &str,
+    root: &str,
+    pages: &[&SitePage],
+    tags: &[SiteTag],
+    search: bool,
+) -> Result<String> {
+    let mut context = Context::new();
+    context.insert("heading", heading);
+    context.insert("root", root);
+    context.insert("pages", pages);
+    context.insert("tags", tags);
+    context.insert("search", &search);
+    Ok(Tera::one_off(LIST_TEMPLATE, &context, true)?)
+}
+
+/// Map every search term to the IDs of the pages containing it
+fn search_index(pages: &[SitePage]) -> BTreeMap<String, Vec<usize>> {
+    let mut index: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for page in pages {
+        let tags: Vec<&str> = page.tags.iter().map(|tag| tag.name.as_str()).collect();
+        let text = format!(
+            "{} {} {} {}",
+            page.title,
+            page.url,
+            tags.join(" "),
+            page.markdown
+        );
+        let terms: HashSet<String> = search_terms(&text).collect();
+        for term in terms {
+            index.entry(term).or_default().push(page.id);
+        }
+    }
+    index
+}
+
+/// The lowercase words of a text, with Chinese, Japanese, and Korean text (written
+/// without spaces) split into overlapping pairs of characters
+pub(crate) fn search_terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .flat_map(|word| {
+            let chars: Vec<char> = word.chars().collect();
+            if chars.len() > 2 && chars.iter().copied().any(is_cjk) {
+                chars
+                    .windows(2)
+                    .map(|pair| pair.iter().collect())
+                    .collect::<Vec<String>>()
+            } else if (2..=MAX_TERM_CHARS).contains(&chars.len())
+                || chars.first().copied().is_some_and(is_cjk)
+            {
+                vec![word]
+            } else {
+                Vec::new()
+            }
+        })
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30ff}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4dbf}' // CJK Extension A
+        | '\u{4e00}'..='\u{9fff}' // CJK Unified Ideographs
+        | '\u{ac00}'..='\u{d7af}' // Hangul syllables
+        | '\u{f900}'..='\u{faff}' // CJK Compatibility Ideographs
+    )
+}
+
+/// A file name for a site page, from a name, not yet taken by another page
+fn unique_slug(name: &str, fallback: &str, taken: &mut HashSet<String>) -> String {
+    let base: String = name
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') => c,
+            _ => '-',
+        })
+        .collect();
+    let base = match base.trim_matches(|c| c == '-' || c == '.') {
+        "" => fallback.to_string(),
+        base => base.to_string(),
+    };
+    let mut slug = base.clone();
+    let mut counter = 2;
+    while !taken.insert(slug.to_lowercase()) {
+        slug = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+    slug
+}
+
+/// Remove the page and tag files of an earlier publish that were not written again
+async fn remove_stale_pages(site: &Path, written: &HashSet<String>) {
+    for dir in [PAGES_DIR, TAGS_DIR] {
+        let Ok(mut files) = tokio::fs::read_dir(site.join(dir)).await else {
+            continue;
+        };
+        while let Ok(Some(file)) = files.next_entry().await {
+            let name = format!("{}/{}", dir, file.file_name().to_string_lossy());
+            if name.ends_with(".html") && !written.contains(&name) {
+                if let Err(e) = tokio::fs::remove_file(file.path()).await {
+                    warn!("Failed to remove stale page {}: {}", name, e);
+                }
+            }
+        }
+    }
+}
+
+/// A list item marker: its indentation, whether it is numbered, its number, and the
+/// column the item's content starts at
+struct ListMarker {
+    indent: usize,
+    ordered: bool,
+    start: u64,
+    content: usize,
+}
+
+/// A link or image in Markdown: `[label](destination "title")`
+struct InlineLink {
+    label: String,
+    destination: String,
+    title: Option<String>,
+    /// Index just past the closing parenthesis
+    end: usize,
+}
+
+/// Renders the Markdown of a capture as HTML
+struct MarkdownRenderer<'a> {
+    /// URL of the captured page, for relative links
+    base: Option<Url>,
+    /// Site pages of captured URLs, relative to the page being rendered
+    links: &'a HashMap<String, String>,
+}
+
+impl MarkdownRenderer<'_> {
+    fn render(&self, markdown: &str) -> String {
+        let lines: Vec<String> = markdown
+            .replace("\r\n", "\n")
+            .replace('\t', "    ")
+            .lines()
+            .map(String::from)
+            .collect();
+        self.blocks(&lines)
+    }
+
+    fn blocks(&self, lines: &[String]) -> String {
+        let mut html = String::new();
+        let mut paragraph: Vec<&str> = Vec::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i].as_str();
+            let trimmed = line.trim();
+            if trimmed.is_empty() {
+                self.paragraph(&mut html, &mut paragraph);
+                i += 1;
+                continue;
+            }
+
+            if let Some(fence) = trimmed.chars().next().filter(|c| matches!(c, '`' | '~')) {
+                let length = trimmed.chars().take_while(|c| *c == fence).count();
+                if length >= 3 {
+                    self.paragraph(&mut html, &mut paragraph);
+                    let language = trimmed[length..].split_whitespace().next();
+                    let indent = indentation(line);
+                    let mut code = Vec::new();
+                    i += 1;
+                    while i < lines.len() {
+                        let close = lines[i].trim();
+                        if close.len() >= length && close.chars().all(|c| c == fence) {
+                            i += 1;
+                            break;
+                        }
+                        let strip = indentation(&lines[i]).min(indent);
+                        code.push(&lines[i][strip..]);
+                        i += 1;
+                    }
+                    match language {
+                        Some(language) => html.push_str(&format!(
+                            "<pre><code class=\"language-{}\">",
+                            escape(language, true)
+                        )),
+                        None => html.push_str("<pre><code>"),
+                    }
+                    for line in code {
+                        html.push_str(&escape(line, false));
+                        html.push('\n');
+                    }
+                    html.push_str("</code></pre>\n");
+                    continue;
+                }
+            }
+
+            let hashes = trimmed.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&hashes)
+                && (trimmed.len() == hashes || trimmed[hashes..].starts_with(' '))
+            {
+                self.paragraph(&mut html, &mut paragraph);
+                let mut text = trimmed[hashes..].trim();
+                let unclosed = text.trim_end_matches('#');
+                if unclosed.is_empty() || unclosed.ends_with(' ') {
+                    text = unclosed.trim_end();
+                }
+                html.push_str(&format!("<h{0}>{1}</h{0}>\n", hashes, self.inline(text)));
+                i += 1;
+                continue;
+            }
+
+            // A line of `=` or `-` under a paragraph makes it a heading
+            if !paragraph.is_empty()
+                && (trimmed.chars().all(|c| c == '=') || trimmed.chars().all(|c| c == '-'))
+            {
+                let level = if trimmed.starts_with('=') { 1 } else { 2 };
+                let text = paragraph.join("\n");
+                paragraph.clear();
+                html.push_str(&format!("<h{0}>{1}</h{0}>\n", level, self.inline(&text)));
+                i += 1;
+                continue;
+            }
+
+            if is_rule(trimmed) {
+                self.paragraph(&mut html, &mut paragraph);
+                html.push_str("<hr>\n");
+                i += 1;
+                continue;
+            }
+
+            if trimmed.starts_with('>') {
+                self.paragraph(&mut html, &mut paragraph);
+                let mut quoted = Vec::new();
+                while let Some(rest) = lines.get(i).and_then(|line| line.trim().strip_prefix('>')) {
+                    quoted.push(rest.strip_prefix(' ').unwrap_or(rest).to_string());
+                    i += 1;
+                }
+                html.push_str(&format!(
+                    "<blockquote>\n{}</blockquote>\n",
+                    self.blocks(&quoted)
+                ));
+                continue;
+            }
+
+            if trimmed.starts_with('|')
+                && lines
+                    .get(i + 1)
+                    .is_some_and(|next| is_table_separator(next))
+            {
+                self.paragraph(&mut html, &mut paragraph);
+                i = self.table(&mut html, lines, i);
+                continue;
+            }
+
+            if list_marker(line).is_some() {
+                self.paragraph(&mut html, &mut paragraph);
+                i = self.list(&mut html, lines, i);
+                continue;
+            }
+
+            paragraph.push(line.trim_start());
+            i += 1;
+        }
+        self.paragraph(&mut html, &mut paragraph);
+        html
+    }
+
+    /// Write the pending paragraph lines, if any
+    fn paragraph(&self, html: &mut String, lines: &mut Vec<&str>) {
+        if lines.is_empty() {
+            return;
+        }
+        // Two trailing spaces break the line, like a trailing backslash
+        let text: Vec<String> = lines
+            .iter()
+            .enumerate()
+            .map(|(n, line)| match line.strip_suffix("  ") {
+                Some(line) if n + 1 < lines.len() => format!("{}\\", line.trim_end()),
+                _ => line.trim_end().to_string(),
+            })
+            .collect();
+        html.push_str(&format!("<p>{}</p>\n", self.inline(&text.join("\n"))));
+        lines.clear();
+    }
+
+    /// Write the table starting at a line, returning the index of the line after it
+    fn table(&self, html: &mut String, lines: &[String], start: usize) -> usize {
+        let alignments: Vec<&str> = table_cells(&lines[start + 1])
+            .iter()
+            .map(|cell| {
+                let cell = cell.trim();
+                match (cell.starts_with(':'), cell.ends_with(':')) {
+                    (true, true) => " style=\"text-align: center\"",
+                    (false, true) => " style=\"text-align: right\"",
+                    (true, false) => " style=\"text-align: left\"",
+                    (false, false) => "",
+                }
+            })
+            .collect();
+        let row = |html: &mut String, line: &str, tag: &str| {
+            html.push_str("<tr>");
+            for (n, cell) in table_cells(line).iter().enumerate() {
+                html.push_str(&format!(
+                    "<{0}{1}>{2}</{0}>",
+                    tag,
+                    alignments.get(n).copied().unwrap_or_default(),
+                    self.inline(cell.trim())
+                ));
+            }
+            html.push_str("</tr>\n");
+        };
+
+        html.push_str("<table>\n<thead>\n");
+        row(html, &lines[start], "th");
+        html.push_str("</thead>\n<tbody>\n");
+        let mut i = start + 2;
+        while let Some(line) = lines.get(i).filter(|line| line.trim().starts_with('|')) {
+            row(html, line, "td");
+            i += 1;
+        }
+        html.push_str("</tbody>\n</table>\n");
+        i
+    }
+
+    /// Write the list starting at a line, returning the index of the line after it
+    fn list(&self, html: &mut String, lines: &[String], start: usize) -> usize {
+        let Some(first) = list_marker(&lines[start]) else {
+            return start;
+        };
+        let mut items: Vec<Vec<String>> = Vec::new();
+        let mut content = first.content;
+        let mut loose = false;
+        let mut i = start;
+        while i < lines.len() {
+            let line = lines[i].as_str();
+            if line.trim().is_empty() {
+                // The list goes on after blank lines only if the next line belongs to it
+                let continues = lines[i..]
+                    .iter()
+                    .find(|line| !line.trim().is_empty())
+                    .is_some_and(|next| {
+                        indentation(next) >= content
+                            || list_marker(next).is_some_and(|marker| {
+                                marker.indent < content && marker.ordered == first.ordered
+                            })
+                    });
+                if !continues {
+                    break;
+                }
+                loose = true;
+                if let Some(item) = items.last_mut() {
+                    item.push(String::new());
+                }
+                i += 1;
+                continue;
+            }
+
+            if let Some(marker) = list_marker(line).filter(|marker| marker.indent < content) {
+                if marker.ordered != first.ordered || is_rule(line.trim()) {
+                    break;
+                }
+                content = marker.content;
+                items.push(vec![line.get(content..).unwrap_or_default().to_string()]);
+            } else if indentation(line) >= content {
+                if let Some(item) = items.last_mut() {
+                    item.push(line[content..].to_string());
+                }
+            } else if items
+                .last()
+                .and_then(|item| item.last())
+                .is_some_and(|last| !last.trim().is_empty())
+                && !starts_block(line.trim())
+            {
+                // A paragraph continuing without indentation
+                if let Some(item) = items.last_mut() {
+                    item.push(line.trim_start().to_string());
+                }
+            } else {
+                break;
+            }
+            i += 1;
+        }
+
+        match (first.ordered, first.start) {
+            (true, 1) => html.push_str("<ol>\n"),
+            (true, start) => html.push_str(&format!("<ol start=\"{}\">\n", start)),
+            (false, _) => html.push_str("<ul>\n"),
+        }
+        for item in &items {
+            let mut body = self.blocks(item);
+            // Items of a list without blank lines hold text, not paragraphs
+            if !loose && body.starts_with("<p>") {
+                if let Some(end) = body.find("</p>") {
+                    body.replace_range(end..end + 4, "");
+                    body.replace_range(..3, "");
+                }
+            }
+            html.push_str(&format!("<li>{}</li>\n", body.trim_end()));
+        }
+        html.push_str(if first.ordered { "</ol>\n" } else { "</ul>\n" });
+        i
+    }
+
+    fn inline(&self, text: &str) -> String {
+        let chars: Vec<char> = text.chars().collect();
+        let mut html = String::new();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            match c {
+                '\\' if next == Some('\n') => {
+                    html.push_str("<br>\n");
+                    i += 2;
+                }
+                '\\' if next.is_some_and(|next| next.is_ascii_punctuation()) => {
+                    html.push_str(&escape(&chars[i + 1].to_string(), false));
+                    i += 2;
+                }
+                '`' => {
+                    let run = run_length(&chars, i, c);
+                    match find_run(&chars, i + run, c, run) {
+                        Some(end) => {
+                            let code: String = chars[i + run..end].iter().collect();
+                            let code = code.replace('\n', " ");
+                            let code = match code
+                                .strip_prefix(' ')
+                                .and_then(|code| code.strip_suffix(' '))
+                            {
+                                Some(inner) if !inner.trim().is_empty() => inner,
+                                _ => &code,
+                            };
+                            html.push_str(&format!("<code>{}</code>", escape(code, false)));
+                            i = end + run;
+                        }
+                        None => {
+                            html.extend(std::iter::repeat_n('`', run));
+                            i += run;
+                        }
+                    }
+                }
+                '!' if next == Some('[') => match link_at(&chars, i + 1) {
+                    Some(link) => {
+                        let src = self.resolve(&link.destination);
+                        html.push_str(&format!(
+                            "<img src=\"{}\" alt=\"{}\"",
+                            escape(safe_url(&src, true), true),
+                            escape(&link.label, true)
+                        ));
+                        if let Some(title) = &link.title {
+                            html.push_str(&format!(" title=\"{}\"", escape(title, true)));
+                        }
+                        html.push_str(" loading=\"lazy\">");
+                        i = link.end;
+                    }
+                    None => {
+                        html.push('!');
+                        i += 1;
+                    }
+                },
+                '[' => match link_at(&chars, i) {
+                    Some(link) => {
+                        html.push_str(&format!(
+                            "<a href=\"{}\"",
+                            escape(&self.href(&link.destination), true)
+                        ));
+                        if let Some(title) = &link.title {
+                            html.push_str(&format!(" title=\"{}\"", escape(title, true)));
+                        }
+                        html.push_str(&format!(">{}</a>", self.inline(&link.label)));
+                        i = link.end;
+                    }
+                    None => {
+                        html.push('[');
+                        i += 1;
+                    }
+                },
+                '<' => {
+                    let target: Option<String> = chars[i + 1..]
+                        .iter()
+                        .position(|c| *c == '>')
+                        .map(|end| chars[i + 1..i + 1 + end].iter().collect());
+                    match target.filter(|target| {
+                        !target.contains(char::is_whitespace)
+                            && ["http://", "https://", "mailto:"]
+                                .iter()
+                                .any(|scheme| target.starts_with(scheme))
+                    }) {
+                        Some(target) => {
+                            html.push_str(&format!(
+                                "<a href=\"{}\">{}</a>",
+                                escape(&self.href(&target), true),
+                                escape(target.trim_start_matches("mailto:"), false)
+                            ));
+                            i += target.chars().count() + 2;
+                        }
+                        None => {
+                            html.push_str("&lt;");
+                            i += 1;
+                        }
+                    }
+                }
+                '*' | '_' | '~' => {
+                    let run = run_length(&chars, i, c);
+                    let length = run.min(3);
+                    let opens = chars.get(i + run).is_some_and(|next| !next.is_whitespace())
+                        && (c != '_' || i == 0 || !chars[i - 1].is_alphanumeric())
+                        && (c != '~' || run == 2);
+                    match find_emphasis_end(&chars, i + run, c, length).filter(|_| opens) {
+                        Some(end) => {
+                            let (open, close) = match (c, length) {
+                                ('~', _) => ("<del>", "</del>"),
+                                (_, 1) => ("<em>", "</em>"),
+                                (_, 2) => ("<strong>", "</strong>"),
+                                _ => ("<strong><em>", "</em></strong>"),
+                            };
+                            // Markers beyond three are kept as text
+                            html.extend(std::iter::repeat_n(c, run - length));
+                            let inner: String = chars[i + run..end].iter().collect();
+                            html.push_str(open);
+                            html.push_str(&self.inline(&inner));
+                            html.push_str(close);
+                            i = end + length;
+                        }
+                        None => {
+                            html.extend(std::iter::repeat_n(c, run));
+                            i += run;
+                        }
+                    }
+                }
+                c => {
+                    html.push_str(&escape(&c.to_string(), false));
+                    i += 1;
+                }
+            }
+        }
+        html
+    }
+
+    /// A link destination resolved against the captured page's URL
+    fn resolve(&self, destination: &str) -> String {
+        match &self.base {
+            Some(base) if !destination.starts_with('#') => base
+                .join(destination)
+                .map(String::from)
+                .unwrap_or_else(|_| destination.to_string()),
+            _ => destination.to_string(),
+        }
+    }
+
+    /// The target of a link: the site page of a captured URL, or the URL itself
+    fn href(&self, destination: &str) -> String {
+        let url = self.resolve(destination);
+        let page = url.split_once('#').map_or(url.as_str(), |(page, _)| page);
+        match self.links.get(page) {
+            Some(local) => local.clone(),
+            None => safe_url(&url, false).to_string(),
+        }
+    }
+}
+
+/// The URL, unless its scheme could run script (`javascript:`), in which case `#`
+fn safe_url(url: &str, image: bool) -> &str {
+    let Some((scheme, _)) = url.split_once(':') else {
+        return url;
+    };
+    if scheme.contains(['/', '?', '#']) {
+        return url;
+    }
+    let scheme = scheme.to_ascii_lowercase();
+    let allowed = matches!(scheme.as_str(), "http" | "https" | "mailto")
+        || (image && scheme == "data" && url[5..].trim_start().starts_with("image/"));
+    if allowed { url } else { "#" }
+}
+
+/// Parse `[label](destination "title")` starting at the opening bracket
+fn link_at(chars: &[char], open: usize) -> Option<InlineLink> {
+    let mut depth = 0;
+    let mut close = None;
+    let mut j = open;
+    while j < chars.len() {
+        match chars[j] {
+            '\\' => j += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(j);
+                    break;
+                }
+            }
+            _ => {}
+        }
+        j += 1;
+    }
+    let close = close?;
+    if chars.get(close + 1) != Some(&'(') {
+        return None;
+    }
+
+    let skip_spaces = |mut j: usize| {
+        while chars.get(j).is_some_and(|c| c.is_whitespace()) {
+            j += 1;
+        }
+        j
+    };
+    let mut j = skip_spaces(close + 2);
+    let mut destination = String::new();
+    if chars.get(j) == Some(&'<') {
+        j += 1;
+        while let Some(c) = chars.get(j).filter(|c| **c != '>') {
+            if *c == '\n' {
+                return None;
+            }
+            destination.push(*c);
+            j += 1;
+        }
+        chars.get(j)?;
+        j += 1;
+    } else {
+        let mut parens = 0;
+        while let Some(&c) = chars.get(j) {
+            match c {
+                c if c.is_whitespace() => break,
+                '\\' if chars
+                    .get(j + 1)
+                    .is_some_and(|next| next.is_ascii_punctuation()) =>
+                {
+                    destination.push(chars[j + 1]);
+                    j += 2;
+                    continue;
+                }
+                '(' => parens += 1,
+                ')' if parens == 0 => break,
+                ')' => parens -= 1,
+                _ => {}
+            }
+            destination.push(c);
+            j += 1;
+        }
+    }
+
+    j = skip_spaces(j);
+    let mut title = None;
+    if let Some(&quote @ ('"' | '\'')) = chars.get(j) {
+        let end = j + 1 + chars[j + 1..].iter().position(|c| *c == quote)?;
+        title = Some(chars[j + 1..end].iter().collect());
+        j = skip_spaces(end + 1);
+    }
+    if chars.get(j) != Some(&')') {
+        return None;
+    }
+    Some(InlineLink {
+        label: chars[open + 1..close].iter().collect(),
+        destination,
+        title,
+        end: j + 1,
+    })
+}
+
+/// Number of consecutive `c` starting at `start`
+fn run_length(chars: &[char], start: usize, c: char) -> usize {
+    chars[start..].iter().take_while(|next| **next == c).count()
+}
+
+/// Start of the next run of exactly `length` backticks
+fn find_run(chars: &[char], from: usize, c: char, length: usize) -> Option<usize> {
+    let mut j = from;
+    while j < chars.len() {
+        if chars[j] == c {
+            let run = run_length(chars, j, c);
+            if run == length {
+                return Some(j);
+            }
+            j += run;
+        } else {
+            j += 1;
+        }
+    }
+    None
+}
+
+/// Start of the closing emphasis markers: at least `length` of `c`, after text
+fn find_emphasis_end(chars: &[char], from: usize, c: char, length: usize) -> Option<usize> {
+    let mut j = from + 1;
+    while j < chars.len() {
+        if chars[j] == '\\' {
+            j += 2;
+            continue;
+        }
+        if chars[j] == c && !chars[j - 1].is_whitespace() {
+            let run = run_length(chars, j, c);
+            let word_follows = chars
+                .get(j + run)
+                .is_some_and(|next| next.is_alphanumeric());
+            if run >= length && (c != '_' || !word_follows) {
+                return Some(j);
+            }
+            j += run;
+            continue;
+        }
+        j += 1;
+    }
+    None
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_rule(trimmed: &str) -> bool {
+    let Some(c) = trimmed
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '-' | '*' | '_'))
+    else {
+        return false;
+    };
+    let marks = trimmed.chars().filter(|next| !next.is_whitespace());
+    marks.clone().all(|next| next == c) && marks.count() >= 3
+}
+
+/// Whether a line starts a block that ends a paragraph
+fn starts_block(trimmed: &str) -> bool {
+    ["#", ">", "```", "~~~", "|"]
+        .iter()
+        .any(|start| trimmed.starts_with(start))
+        || is_rule(trimmed)
+}
+
+fn list_marker(line: &str) -> Option<ListMarker> {
+    let indent = indentation(line);
+    let rest = &line[indent..];
+    let (ordered, start, marker) = if rest.starts_with(['-', '*', '+']) {
+        (false, 0, 1)
+    } else {
+        let digits = rest.chars().take_while(char::is_ascii_digit).count();
+        if !(1..=9).contains(&digits) || !rest[digits..].starts_with(['.', ')']) {
+            return None;
+        }
+        (true, rest[..digits].parse().ok()?, digits + 1)
+    };
+    let after = &rest[marker..];
+    let spaces = indentation(after);
+    if spaces == 0 && !after.is_empty() {
+        return None;
+    }
+    // Content indented further than that is code inside the item
+    let spaces = if spaces == 0 || spaces > 4 { 1 } else { spaces };
+    Some(ListMarker {
+        indent,
+        ordered,
+        start,
+        content: indent + marker + spaces,
+    })
+}
+
+/// The cells of a table row, split at unescaped `|`
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(inner) if !inner.ends_with('\\') => inner,
+        _ => line,
+    };
+    let mut cells = vec![String::new()];
+    let mut escaped = false;
+    for c in line.chars() {
+        if c == '|' && !escaped {
+            cells.push(String::new());
+        } else if let Some(cell) = cells.last_mut() {
+            cell.push(c);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+    cells
+}
+
+fn is_table_separator(line: &str) -> bool {
+    line.trim().starts_with('|')
+        && table_cells(line).iter().all(|cell| {
+            let dashes = cell.trim().trim_start_matches(':').trim_end_matches(':');
+            !dashes.is_empty() && dashes.chars().all(|c| c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_render_markdown() {
+        let links = HashMap::from([(
+            "https://example.com/other".to_string(),
+            "other.html".to_string(),
+        )]);
+        let renderer = MarkdownRenderer {
+            base: Url::parse("https://example.com/post").ok(),
+            links: &links,
+        };
+        let markdown = "# Title <b>\n\nSome *em*, **strong**, `a<b`, and [a link](/other#top)\nthat \
+                        [wraps](https://rust-lang.org \"Rust\").  \nBroken [link]( and snake_case_name.\n\n\
+                        - one\n- two\n  - nested\n\n1. first\n2. [x](javascript:alert(1))\n\n\
+                        > quoted\n\n```rust\nfn main() {}\n```\n\n\
+                        | Name | Size |\n| :--- | ---: |\n| a \\| b | 1 |\n\n---\n\n![Chart](img/chart.png)";
+        assert_eq!(
+            renderer.render(markdown),
+            "<h1>Title &lt;b&gt;</h1>\n\
+             <p>Some <em>em</em>, <strong>strong</strong>, <code>a&lt;b</code>, and <a href=\"other.html\">a link</a>\n\
+             that <a href=\"https://rust-lang.org/\" title=\"Rust\">wraps</a>.<br>\n\
+             Broken [link]( and snake_case_name.</p>\n\
+             <ul>\n<li>one</li>\n<li>two\n<ul>\n<li>nested</li>\n</ul></li>\n</ul>\n\
+             <ol>\n<li>first</li>\n<li><a href=\"#\">x</a></li>\n</ol>\n\
+             <blockquote>\n<p>quoted</p>\n</blockquote>\n\
+             <pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n\
+             <table>\n<thead>\n<tr><th style=\"text-align: left\">Name</th><th style=\"text-align: right\">Size</th></tr>\n\
+             </thead>\n<tbody>\n<tr><td style=\"text-align: left\">a | b</td><td style=\"text-align: right\">1</td></tr>\n\
+             </tbody>\n</table>\n\
+             <hr>\n\
+             <p><img src=\"https://example.com/img/chart.png\" alt=\"Chart\" loading=\"lazy\"></p>\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_publish() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let dir = temp_dir.path().join("captures");
+        let site = temp_dir.path().join("site");
+        std::fs::create_dir_all(site.join(PAGES_DIR))?;
+        std::fs::write(site.join(PAGES_DIR).join("removed.html"), "")?;
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(
+            dir.join("tokio.md"),
+            "---\ntitle: \"Tokio\"\n---\n\n# Tokio tutorial\n\nAsync runtime; see [the guide](https://example.com/guide).\n",
+        )?;
+        std::fs::write(
+            dir.join("guide.md"),
+            "# 非同期プログラミング入門\n\nGuide text\n",
+        )?;
+        std::fs::write(dir.join("scan.pdf"), "%PDF")?;
+
+        let mut manifest = OutputManifest::default();
+        manifest.record(&dir, "https://example.com/tokio", &[dir.join("tokio.md")]);
+        manifest.tag(
+            "https://example.com/tokio",
+            &["Rust".to_string(), "async".to_string()],
+        );
+        manifest.record(&dir, "https://example.com/guide", &[dir.join("guide.md")]);
+        manifest.tag("https://example.com/guide", &["Rust".to_string()]);
+        manifest.record(&dir, "https://example.com/scan", &[dir.join("scan.pdf")]);
+        manifest.save(&dir).await?;
+
+        assert_eq!(
+            publish(&dir, &site).await?,
+            PublishedSite {
+                pages: 2,
+                tags: 2,
+                skipped: 1
+            }
+        );
+        let page = std::fs::read_to_string(site.join("pages/tokio.html"))?;
+        assert!(page.contains("<h1>Tokio tutorial</h1>"));
+        assert!(page.contains(r#"<a href="guide.html">the guide</a>"#));
+        assert!(page.contains(r#"<a class="tag" href="../tags/rust.html">Rust</a>"#));
+        assert!(!page.contains("title: "));
+
+        let rust = std::fs::read_to_string(site.join("tags/rust.html"))?;
+        assert!(rust.contains("pages/guide.html") && rust.contains("pages/tokio.html"));
+        let index = std::fs::read_to_string(site.join("index.html"))?;
+        assert!(index.contains(r#"href="tags/rust.html">Rust (2)</a>"#));
+        assert!(index.contains("非同期プログラミング入門"));
+        assert!(!site.join(PAGES_DIR).join("removed.html").exists());
+
+        let search = std::fs::read_to_string(site.join("search-index.js"))?;
+        let terms: BTreeMap<String, Vec<usize>> = serde_json::from_str(
+            search
+                .trim()
+                .strip_prefix("const SEARCH_INDEX = ")
+                .and_then(|json| json.strip_suffix(';'))
+                .unwrap_or_default(),
+        )?;
+        assert_eq!(terms["runtime"].len(), 1);
+        assert_eq!(terms["非同"].len(), 1);
+        assert_eq!(terms["rust"].len(), 2);
+        Ok(())
+    }
+}
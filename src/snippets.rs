@@ -0,0 +1,341 @@
+//! Passages of a page relevant to a search query
+//!
+//! Search results are converted without being read, so each one gets a few short
+//! passages that match the query best, to triage the results without opening every
+//! file. The page text is split into passages (paragraphs, with long ones cut into
+//! windows of sentences), which are ranked against the query terms with BM25. The
+//! sentences of the passages that state figures or dates are the key facts of a result's
+//! brief (see [`crate::brief`]).
+
+use crate::frontmatter;
+use crate::site::search_terms;
+use std::collections::{HashMap, HashSet};
+
+/// Default number of passages kept per result
+pub const DEFAULT_MAX_SNIPPETS: usize = 3;
+
+/// Fewest letters in a key fact, so that dates and figures on their own are skipped
+const MIN_FACT_LETTERS: usize = 10;
+
+/// Longest passage in characters; longer paragraphs are split at sentence ends
+const MAX_PASSAGE_CHARS: usize = 300;
+
+/// BM25 term frequency saturation
+const K1: f64 = 1.2;
+
+/// BM25 document length normalization
+const B: f64 = 0.75;
+
+/// The passages of Markdown documents (a page, or the chunks it was split into) most
+/// relevant to a query, best first
+///
+/// Only passages containing at least one query term are returned, so a page that does
+/// not mention the query has none.
+pub fn relevant_passages<'a>(
+    documents: impl IntoIterator<Item = &'a str>,
+    query: &str,
+    max: usize,
+) -> Vec<String> {
+    let query: HashSet<String> = search_terms(query).collect();
+    if query.is_empty() || max == 0 {
+        return Vec::new();
+    }
+    // Chunks overlap, so the same passage can occur more than once
+    let mut seen = HashSet::new();
+    let passages: Vec<String> = documents
+        .into_iter()
+        .flat_map(passages)
+        .filter(|passage| seen.insert(passage.clone()))
+        .collect();
+    let terms: Vec<Vec<String>> = passages
+        .iter()
+        .map(|passage| search_terms(passage).collect())
+        .collect();
+    let count = passages.len() as f64;
+    let average_len = terms.iter().map(Vec::len).sum::<usize>() as f64 / count.max(1.0);
+
+    let mut document_frequency: HashMap<&str, usize> = HashMap::new();
+    for passage in &terms {
+        let unique: HashSet<&str> = passage
+            .iter()
+            .map(String::as_str)
+            .filter(|term| query.contains(*term))
+            .collect();
+        for term in unique {
+            *document_frequency.entry(term).or_default() += 1;
+        }
+    }
+
+    let mut scored: Vec<(f64, usize)> = terms
+        .iter()
+        .enumerate()
+        .filter_map(|(i, passage)| {
+            let len = passage.len() as f64;
+            let score: f64 = document_frequency
+                .iter()
+                .map(|(term, &frequency)| {
+                    let tf = passage.iter().filter(|t| t == term).count() as f64;
+                    let idf =
+                        ((count - frequency as f64 + 0.5) / (frequency as f64 + 0.5) + 1.0).ln();
+                    idf * tf * (K1 + 1.0) / (tf + K1 * (1.0 - B + B * len / average_len.max(1.0)))
+                })
+                .sum();
+            (score > 0.0).then_some((score, i))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+    scored
+        .into_iter()
+        .take(max)
+        .map(|(_, i)| passages[i].clone())
+        .collect()
+}
+
+/// Sentences of Markdown documents stating key facts, i.e. containing a number such as
+/// a date, amount, or measurement
+///
+/// Sentences mentioning more of the query terms come first, the others in page order.
+pub fn key_facts<'a>(
+    documents: impl IntoIterator<Item = &'a str>,
+    query: &str,
+    max: usize,
+) -> Vec<String> {
+    let query: HashSet<String> = search_terms(query).collect();
+    let mut seen = HashSet::new();
+    let mut facts: Vec<(usize, usize, String)> = documents
+        .into_iter()
+        .flat_map(passages)
+        .flat_map(|passage| {
+            sentences(&passage)
+                .into_iter()
+                .map(|sentence| sentence.trim().to_string())
+                .collect::<Vec<_>>()
+        })
+        .filter(|sentence| {
+            sentence.chars().any(char::is_numeric)
+                && sentence.chars().filter(|c| c.is_alphabetic()).count() >= MIN_FACT_LETTERS
+        })
+        .filter(|sentence| seen.insert(sentence.clone()))
+        .enumerate()
+        .map(|(i, sentence)| {
+            let matched: HashSet<String> = search_terms(&sentence)
+                .filter(|term| query.contains(term))
+                .collect();
+            (matched.len(), i, sentence)
+        })
+        .collect();
+    facts.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+    facts
+        .into_iter()
+        .take(max)
+        .map(|(_, _, sentence)| sentence)
+        .collect()
+}
+
+/// The plain-text passages of a Markdown document: paragraphs, list items, and quotes
+/// without front matter, headings, code, and link targets
+fn passages(markdown: &str) -> Vec<String> {
+    let body = frontmatter::split_front_matter(markdown).map_or(markdown, |(_, body)| body);
+
+    let mut passages = Vec::new();
+    let mut paragraph = String::new();
+    let mut in_code = false;
+    for line in body.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+            flush(&mut paragraph, &mut passages);
+            continue;
+        }
+        if in_code || trimmed.starts_with('#') || trimmed.starts_with('|') {
+            flush(&mut paragraph, &mut passages);
+            continue;
+        }
+        let text = strip_marker(trimmed);
+        let item = text.len() != trimmed.len();
+        if text.is_empty() || item || is_rule(trimmed) {
+            flush(&mut paragraph, &mut passages);
+        }
+        if !text.is_empty() && !is_rule(trimmed) {
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+            paragraph.push_str(&plain_text(text));
+        }
+    }
+    flush(&mut paragraph, &mut passages);
+    passages
+}
+
+/// A line without its quote or list item marker
+fn strip_marker(line: &str) -> &str {
+    if let Some(rest) = line.strip_prefix('>') {
+        return strip_marker(rest.trim_start());
+    }
+    for marker in ["- ", "* ", "+ "] {
+        if let Some(rest) = line.strip_prefix(marker) {
+            return rest.trim_start();
+        }
+    }
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    match line[digits..].strip_prefix(". ") {
+        Some(rest) if digits > 0 => rest.trim_start(),
+        _ => line,
+    }
+}
+
+/// Whether a line is a thematic break such as `---` or `***`
+fn is_rule(line: &str) -> bool {
+    let line: String = line.chars().filter(|c| !c.is_whitespace()).collect();
+    line.len() >= 3
+        && ['-', '*', '_']
+            .iter()
+            .any(|&c| line.chars().all(|x| x == c))
+}
+
+/// Add a paragraph as passages of at most [`MAX_PASSAGE_CHARS`], split at sentence ends
+fn flush(paragraph: &mut String, passages: &mut Vec<String>) {
+    let text = paragraph.split_whitespace().collect::<Vec<_>>().join(" ");
+    paragraph.clear();
+    if !text.chars().any(char::is_alphanumeric) {
+        return;
+    }
+
+    let mut passage = String::new();
+    for sentence in sentences(&text) {
+        if !passage.is_empty()
+            && passage.chars().count() + sentence.chars().count() > MAX_PASSAGE_CHARS
+        {
+            passages.push(std::mem::take(&mut passage).trim().to_string());
+        }
+        passage.push_str(sentence);
+    }
+    passages.push(passage.trim().to_string());
+    // A single sentence can still be too long
+    for passage in passages.iter_mut() {
+        if passage.chars().count() > MAX_PASSAGE_CHARS {
+            let cut: String = passage.chars().take(MAX_PASSAGE_CHARS - 1).collect();
+            *passage = format!("{}…", cut.trim_end());
+        }
+    }
+}
+
+/// The sentences of a text, each with its trailing punctuation and space
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = match c {
+            '。' | '！' | '？' => Some(i + c.len_utf8()),
+            '.' | '!' | '?' if chars.peek().is_some_and(|(_, next)| *next == ' ') => {
+                Some(i + c.len_utf8() + 1)
+            }
+            _ => None,
+        };
+        if let Some(end) = end {
+            sentences.push(&text[start..end]);
+            start = end;
+        }
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
+
+/// A line of Markdown as plain text: images are dropped, links keep their text, and
+/// emphasis and code markers are removed
+fn plain_text(line: &str) -> String {
+    let mut text = String::with_capacity(line.len());
+    let chars: Vec<char> = line.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let image = chars[i] == '!' && chars.get(i + 1) == Some(&'[');
+        if chars[i] == '[' || image {
+            let open = if image { i + 1 } else { i };
+            let close = chars[open..]
+                .iter()
+                .position(|&c| c == ']')
+                .map(|p| open + p);
+            if let Some(close) = close.filter(|&close| chars.get(close + 1) == Some(&'(')) {
+                let end = chars[close..]
+                    .iter()
+                    .position(|&c| c == ')')
+                    .map(|p| close + p);
+                if let Some(end) = end {
+                    if !image {
+                        text.extend(&chars[open + 1..close]);
+                    }
+                    i = end + 1;
+                    continue;
+                }
+            }
+        }
+        if !matches!(chars[i], '*' | '_' | '`' | '\\') {
+            text.push(chars[i]);
+        }
+        i += 1;
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relevant_passages() {
+        let markdown = "---\ntitle: Rust borrow checker\n---\n\n# The borrow checker\n\n\
+            ![Diagram](borrow.png)\n\n\
+            This page is about **programming languages** in general.\n\n\
+            The [borrow checker](https://doc.rust-lang.org) enforces ownership rules. \
+            Each value has one owner.\n\n\
+            ```rust\nlet borrow = &checker;\n```\n\n\
+            - Lifetimes help the checker reason about scopes\n\
+            - Unrelated list item\n";
+
+        assert_eq!(
+            relevant_passages([markdown], "borrow checker", 3),
+            vec![
+                "The borrow checker enforces ownership rules. Each value has one owner.",
+                "Lifetimes help the checker reason about scopes",
+            ]
+        );
+        assert!(relevant_passages([markdown], "python", 3).is_empty());
+        assert_eq!(relevant_passages([markdown], "borrow", 1).len(), 1);
+
+        let long = "Rust is fast. ".repeat(40);
+        let passages = relevant_passages([long.as_str()], "rust", 5);
+        assert!(passages.len() > 1);
+        assert!(
+            passages
+                .iter()
+                .all(|p| p.chars().count() <= MAX_PASSAGE_CHARS)
+        );
+
+        assert_eq!(
+            relevant_passages(["東京の天気は晴れです。\n\n大阪は雨です。"], "東京", 3),
+            vec!["東京の天気は晴れです。"]
+        );
+    }
+
+    #[test]
+    fn test_key_facts() {
+        let markdown = "# Release notes\n\nThe project is maintained by volunteers. \
+            Version 2 was released on March 5, 2026. \
+            The borrow checker got 30% faster in version 2.\n\n\
+            - 2026\n\
+            - Adopted by 120 companies worldwide\n";
+
+        assert_eq!(
+            key_facts([markdown], "borrow checker", 5),
+            vec![
+                "The borrow checker got 30% faster in version 2.",
+                "Version 2 was released on March 5, 2026.",
+                "Adopted by 120 companies worldwide",
+            ]
+        );
+        assert_eq!(key_facts([markdown], "borrow", 1).len(), 1);
+    }
+}
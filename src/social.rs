@@ -0,0 +1,780 @@
+//! Social post thread capture
+//!
+//! Twitter/X and Mastodon pages show little without a login and render posts with
+//! scripts, so printing them gives a login wall or a half-loaded timeline. Post URLs are
+//! instead read through the platforms' public APIs, and the thread is rendered as a
+//! clean document: every post with its author, time, text, and media.
+//!
+//! - **Mastodon**: the post, the conversation it replies to, and the author's replies
+//!   continuing the thread, from the instance's public status API.
+//! - **Twitter/X**: the post and the chain of posts it replies to, from the syndication
+//!   API used by embedded posts. Replies after the post are not available there, so
+//!   link the last post of a thread to capture all of it.
+
+use crate::extract::escape;
+use crate::readlater::{checked, http_client, send_with_retry};
+use anyhow::Result;
+use chrono::DateTime;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use tera::{Context, Tera};
+use tracing::{info, warn};
+use url::Url;
+
+/// Twitter/X syndication API, which serves embedded posts without a login
+pub const TWITTER_SYNDICATION_API: &str = "https://cdn.syndication.twimg.com";
+
+/// Most posts followed up a reply chain
+const MAX_THREAD_POSTS: usize = 100;
+
+const THREAD_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{ title }}</title>
+<style>
+  body { margin: 0 auto; max-width: 36em; font-family: system-ui, -apple-system, "Segoe UI", "Noto Sans", sans-serif; line-height: 1.5; color: #111; }
+  h1 { font-size: 1.4em; margin-bottom: 0.2em; }
+  .source, .handle, time, figcaption, .cw { color: #666; }
+  .source { font-size: 0.85em; word-break: break-all; }
+  article { padding: 0.8em 0; border-bottom: 1px solid #ddd; page-break-inside: avoid; }
+  .author { font-weight: bold; }
+  .cw { font-style: italic; }
+  a { color: inherit; }
+  figure { margin: 0.5em 0; }
+  img { max-width: 100%; max-height: 60vh; border-radius: 4px; }
+  figcaption { font-size: 0.85em; }
+</style>
+</head>
+<body>
+<h1>{{ title }}</h1>
+<p class="source"><a href="{{ url }}">{{ url }}</a></p>
+{% for post in posts %}<article>
+<header><span class="author">{{ post.author }}</span> <span class="handle">{{ post.handle }}</span>{% if post.posted %} · <a href="{{ post.url }}"><time>{{ post.posted }}</time></a>{% endif %}</header>
+{% if post.content_warning %}<p class="cw">Content warning: {{ post.content_warning }}</p>{% endif %}
+<div class="content">{{ post.content | safe }}</div>
+{% for media in post.media %}<figure>{% if media.kind == "image" %}<img src="{{ media.url }}" alt="{{ media.description }}">{% elif media.preview %}<a href="{{ media.url }}"><img src="{{ media.preview }}" alt="{{ media.description }}"></a>{% else %}<a href="{{ media.url }}">{{ media.kind }}</a>{% endif %}{% if media.description %}<figcaption>{{ media.description }}</figcaption>{% endif %}</figure>
+{% endfor %}</article>
+{% endfor %}</body>
+</html>
+"#;
+
+/// A post on a social platform, identified by its URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocialPostUrl {
+    /// A Twitter/X post, by id
+    Twitter(u64),
+    /// A Mastodon post, by instance host and status id
+    Mastodon {
+        /// Host of the instance the URL points to
+        host: String,
+        /// The status id on that instance
+        id: String,
+    },
+}
+
+impl SocialPostUrl {
+    /// Recognize a Twitter/X post URL (`x.com/USER/status/ID`) or a Mastodon post URL
+    /// (`INSTANCE/@USER/ID` or `INSTANCE/users/USER/statuses/ID`)
+    ///
+    /// Mastodon URLs are recognized by their shape, so they may point to another kind of
+    /// site; capturing them then fails and the page is printed as usual.
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return None;
+        }
+        let host = url.host_str()?;
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        let is_numeric = |id: &str| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit());
+        let twitter = matches!(
+            host.trim_start_matches("www.")
+                .trim_start_matches("mobile."),
+            "twitter.com" | "x.com"
+        );
+        match segments.as_slice() {
+            [_, "status", id, ..] if twitter => id.parse().ok().map(Self::Twitter),
+            _ if twitter => None,
+            [user, id] | ["users", user, "statuses", id]
+                if is_numeric(id) && (segments.len() == 4 || user.starts_with('@')) =>
+            {
+                Some(Self::Mastodon {
+                    host: host.to_string(),
+                    id: id.to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Kind of media attached to a post
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MediaKind {
+    /// A picture
+    Image,
+    /// A video or animated GIF
+    Video,
+    /// An audio clip
+    Audio,
+}
+
+/// Media attached to a post
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SocialMedia {
+    /// What the media is
+    pub kind: MediaKind,
+    /// URL of the media file
+    pub url: String,
+    /// URL of a still image of a video
+    pub preview: Option<String>,
+    /// Alt text
+    pub description: Option<String>,
+}
+
+/// A post in a thread
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SocialPost {
+    /// Display name of the author
+    pub author: String,
+    /// Handle of the author, such as `@user` or `@user@instance`
+    pub handle: String,
+    /// URL of the post
+    pub url: String,
+    /// When the post was made, as `YYYY-MM-DD HH:MM UTC`
+    pub posted: Option<String>,
+    /// Content warning shown before the post
+    pub content_warning: Option<String>,
+    /// The text of the post as HTML
+    pub content: String,
+    /// Attached media
+    pub media: Vec<SocialMedia>,
+}
+
+impl SocialPost {
+    fn to_markdown(&self) -> String {
+        let mut markdown = format!("**{}** ({})", self.author, self.handle);
+        match &self.posted {
+            Some(posted) => markdown.push_str(&format!(" · [{}]({})\n", posted, self.url)),
+            None => markdown.push_str(&format!(" · <{}>\n", self.url)),
+        }
+        if let Some(warning) = &self.content_warning {
+            markdown.push_str(&format!("\n*Content warning: {}*\n", warning));
+        }
+        let content = mdka::from_html(&self.content);
+        if !content.trim().is_empty() {
+            markdown.push_str(&format!("\n{}\n", content.trim()));
+        }
+        for media in &self.media {
+            let description = media.description.as_deref().unwrap_or_default();
+            match media.kind {
+                MediaKind::Image => {
+                    markdown.push_str(&format!("\n![{}]({})\n", description, media.url))
+                }
+                MediaKind::Video | MediaKind::Audio => {
+                    let label = match media.kind {
+                        MediaKind::Video => "Video",
+                        _ => "Audio",
+                    };
+                    match description {
+                        "" => markdown.push_str(&format!("\n[{}]({})\n", label, media.url)),
+                        _ => markdown
+                            .push_str(&format!("\n[{}: {}]({})\n", label, description, media.url)),
+                    }
+                }
+            }
+        }
+        markdown
+    }
+}
+
+/// A captured thread, in the order the posts were made
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocialThread {
+    /// The URL the thread was captured from
+    pub url: String,
+    /// The posts of the thread
+    pub posts: Vec<SocialPost>,
+    /// Index of the post the URL points to
+    pub focus: usize,
+}
+
+impl SocialThread {
+    /// Title of the thread, after the author of the linked post
+    pub fn title(&self) -> String {
+        let kind = match self.posts.len() {
+            1 => "Post",
+            _ => "Thread",
+        };
+        match self.posts.get(self.focus) {
+            Some(post) => format!("{} by {} ({})", kind, post.author, post.handle),
+            None => kind.to_string(),
+        }
+    }
+
+    /// Render the thread as an HTML document for printing
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template fails to render
+    pub fn to_html(&self) -> Result<String> {
+        let mut context = Context::new();
+        context.insert("title", &self.title());
+        context.insert("url", &self.url);
+        context.insert("posts", &self.posts);
+        Ok(Tera::one_off(THREAD_TEMPLATE, &context, true)?)
+    }
+
+    /// Render the posts of the thread as Markdown, separated by rules
+    pub fn to_markdown(&self) -> String {
+        self.posts
+            .iter()
+            .map(SocialPost::to_markdown)
+            .collect::<Vec<_>>()
+            .join("\n---\n\n")
+    }
+}
+
+/// Captures social post URLs through the platform APIs
+#[derive(Debug, Clone)]
+pub struct SocialThreads {
+    client: Client,
+    twitter_endpoint: String,
+}
+
+impl SocialThreads {
+    /// Create a thread capturer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            twitter_endpoint: TWITTER_SYNDICATION_API.to_string(),
+        })
+    }
+
+    /// The thread of a social post URL, or None if the URL is not a post or the thread
+    /// cannot be read through the API, in which case the page should be printed instead
+    pub async fn capture(&self, url: &str) -> Option<SocialThread> {
+        let post = SocialPostUrl::parse(url)?;
+        let result = match &post {
+            SocialPostUrl::Twitter(id) => self.twitter_thread(*id).await,
+            SocialPostUrl::Mastodon { host, id } => self.mastodon_thread(host, id).await,
+        };
+        match result {
+            Ok((posts, focus)) => {
+                info!("Captured {} posts of the thread at {}", posts.len(), url);
+                Some(SocialThread {
+                    url: url.to_string(),
+                    posts,
+                    focus,
+                })
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to capture the thread at {}, printing the page instead: {}",
+                    url, e
+                );
+                None
+            }
+        }
+    }
+
+    /// The ancestors of a Mastodon post, the post, and the author's replies continuing it
+    async fn mastodon_thread(&self, host: &str, id: &str) -> Result<(Vec<SocialPost>, usize)> {
+        let status_url = format!("https://{}/api/v1/statuses/{}", host, id);
+        let response = send_with_retry(|| self.client.get(&status_url)).await?;
+        let status: MastodonStatus = checked(response, "Mastodon status").await?.json().await?;
+        let context_url = format!("{}/context", status_url);
+        let response = send_with_retry(|| self.client.get(&context_url)).await?;
+        let context: MastodonContext = checked(response, "Mastodon context").await?.json().await?;
+
+        // Descendants come depth-first, so a reply follows the post it answers
+        let mut thread_ids = HashSet::from([status.id.clone()]);
+        let continuation: Vec<MastodonStatus> = context
+            .descendants
+            .into_iter()
+            .filter(|reply| {
+                let continues = reply.account.id == status.account.id
+                    && reply
+                        .in_reply_to_id
+                        .as_ref()
+                        .is_some_and(|parent| thread_ids.contains(parent));
+                if continues {
+                    thread_ids.insert(reply.id.clone());
+                }
+                continues
+            })
+            .collect();
+
+        let focus = context.ancestors.len();
+        let posts = context
+            .ancestors
+            .into_iter()
+            .chain(std::iter::once(status))
+            .chain(continuation)
+            .map(|status| status.into_post(host))
+            .collect();
+        Ok((posts, focus))
+    }
+
+    /// A Twitter/X post and the chain of posts it replies to
+    async fn twitter_thread(&self, id: u64) -> Result<(Vec<SocialPost>, usize)> {
+        let mut posts = vec![self.tweet(id).await?];
+        let mut parent = posts[0].1;
+        while let Some(parent_id) = parent.filter(|_| posts.len() < MAX_THREAD_POSTS) {
+            match self.tweet(parent_id).await {
+                Ok(post) => {
+                    parent = post.1;
+                    posts.push(post);
+                }
+                Err(e) => {
+                    warn!("Failed to load post {} of the thread: {}", parent_id, e);
+                    break;
+                }
+            }
+        }
+        posts.reverse();
+        let focus = posts.len() - 1;
+        Ok((posts.into_iter().map(|(post, _)| post).collect(), focus))
+    }
+
+    /// A Twitter/X post, with the id of the post it replies to
+    async fn tweet(&self, id: u64) -> Result<(SocialPost, Option<u64>)> {
+        let url = format!("{}/tweet-result", self.twitter_endpoint);
+        let id_param = id.to_string();
+        let token = syndication_token(id);
+        let query = [("id", id_param.as_str()), ("token", token.as_str())];
+        let response = send_with_retry(|| self.client.get(&url).query(&query)).await?;
+        let body = checked(response, "Twitter post").await?.text().await?;
+        let tweet: Tweet = serde_json::from_str(&body)
+            .map_err(|_| anyhow::anyhow!("Post {} is not available", id))?;
+        let parent = tweet
+            .in_reply_to_status_id_str
+            .as_deref()
+            .and_then(|parent| parent.parse().ok());
+        Ok((tweet.into_post(), parent))
+    }
+}
+
+#[derive(Deserialize)]
+struct MastodonStatus {
+    id: String,
+    url: Option<String>,
+    uri: String,
+    created_at: String,
+    in_reply_to_id: Option<String>,
+    content: String,
+    #[serde(default)]
+    spoiler_text: String,
+    account: MastodonAccount,
+    #[serde(default)]
+    media_attachments: Vec<MastodonMedia>,
+}
+
+#[derive(Deserialize)]
+struct MastodonAccount {
+    id: String,
+    username: String,
+    acct: String,
+    #[serde(default)]
+    display_name: String,
+}
+
+#[derive(Deserialize)]
+struct MastodonMedia {
+    #[serde(rename = "type")]
+    kind: String,
+    url: Option<String>,
+    preview_url: Option<String>,
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct MastodonContext {
+    ancestors: Vec<MastodonStatus>,
+    descendants: Vec<MastodonStatus>,
+}
+
+impl MastodonStatus {
+    fn into_post(self, host: &str) -> SocialPost {
+        // Accounts of the instance itself have no domain in `acct`
+        let handle = match self.account.acct.contains('@') {
+            true => format!("@{}", self.account.acct),
+            false => format!("@{}@{}", self.account.acct, host),
+        };
+        let media = self
+            .media_attachments
+            .into_iter()
+            .filter_map(|media| {
+                let kind = match media.kind.as_str() {
+                    "image" => MediaKind::Image,
+                    "video" | "gifv" => MediaKind::Video,
+                    "audio" => MediaKind::Audio,
+                    _ => return None,
+                };
+                Some(SocialMedia {
+                    kind,
+                    url: media.url?,
+                    preview: media.preview_url.filter(|_| kind != MediaKind::Image),
+                    description: media.description.filter(|text| !text.trim().is_empty()),
+                })
+            })
+            .collect();
+        SocialPost {
+            author: non_empty(self.account.display_name).unwrap_or(self.account.username),
+            handle,
+            url: self.url.unwrap_or(self.uri),
+            posted: format_time(&self.created_at),
+            content_warning: non_empty(self.spoiler_text),
+            content: self.content,
+            media,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Tweet {
+    id_str: String,
+    text: String,
+    created_at: Option<String>,
+    display_text_range: Option<[usize; 2]>,
+    user: TweetUser,
+    #[serde(default)]
+    entities: TweetEntities,
+    #[serde(default, rename = "mediaDetails")]
+    media_details: Vec<TweetMedia>,
+    in_reply_to_status_id_str: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TweetUser {
+    name: String,
+    screen_name: String,
+}
+
+#[derive(Default, Deserialize)]
+struct TweetEntities {
+    #[serde(default)]
+    urls: Vec<TweetLink>,
+    #[serde(default)]
+    media: Vec<TweetLink>,
+}
+
+#[derive(Deserialize)]
+struct TweetLink {
+    url: String,
+    #[serde(default)]
+    expanded_url: String,
+    #[serde(default)]
+    display_url: String,
+}
+
+#[derive(Deserialize)]
+struct TweetMedia {
+    #[serde(rename = "type")]
+    kind: String,
+    media_url_https: String,
+    ext_alt_text: Option<String>,
+    video_info: Option<TweetVideo>,
+}
+
+#[derive(Deserialize)]
+struct TweetVideo {
+    variants: Vec<TweetVideoVariant>,
+}
+
+#[derive(Deserialize)]
+struct TweetVideoVariant {
+    content_type: String,
+    url: String,
+    #[serde(default)]
+    bitrate: u64,
+}
+
+impl Tweet {
+    fn into_post(self) -> SocialPost {
+        // The displayed range leaves out the mentions of reply chains and trailing media
+        // links; it counts characters, not bytes. A reversed range is ignored.
+        let text: String = match self.display_text_range {
+            Some([start, end]) if start <= end => {
+                self.text.chars().skip(start).take(end - start).collect()
+            }
+            _ => self.text.clone(),
+        };
+        let mut content = escape(&text, false);
+        for media in &self.entities.media {
+            content = content.replace(&media.url, "");
+        }
+        for link in &self.entities.urls {
+            let display = match link.display_url.is_empty() {
+                true => &link.expanded_url,
+                false => &link.display_url,
+            };
+            content = content.replace(
+                &link.url,
+                &format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape(&link.expanded_url, true),
+                    escape(display, false)
+                ),
+            );
+        }
+        let content = format!("<p>{}</p>", content.trim().replace('\n', "<br>"));
+
+        let media = self
+            .media_details
+            .into_iter()
+            .map(|media| {
+                let video = media.video_info.and_then(|video| {
+                    video
+                        .variants
+                        .into_iter()
+                        .filter(|variant| variant.content_type == "video/mp4")
+                        .max_by_key(|variant| variant.bitrate)
+                });
+                let description = media.ext_alt_text.filter(|text| !text.trim().is_empty());
+                match (media.kind.as_str(), video) {
+                    ("video" | "animated_gif", Some(video)) => SocialMedia {
+                        kind: MediaKind::Video,
+                        url: video.url,
+                        preview: Some(media.media_url_https),
+                        description,
+                    },
+                    _ => SocialMedia {
+                        kind: MediaKind::Image,
+                        url: media.media_url_https,
+                        preview: None,
+                        description,
+                    },
+                }
+            })
+            .collect();
+
+        SocialPost {
+            author: self.user.name,
+            url: format!(
+                "https://x.com/{}/status/{}",
+                self.user.screen_name, self.id_str
+            ),
+            handle: format!("@{}", self.user.screen_name),
+            posted: self.created_at.as_deref().and_then(format_time),
+            content_warning: None,
+            content,
+            media,
+        }
+    }
+}
+
+/// The token the syndication API expects with a post id, computed as embedded posts do:
+/// `((id / 1e15) * π).toString(36)` without zeros and the decimal point
+fn syndication_token(id: u64) -> String {
+    let value = (id as f64 / 1e15) * std::f64::consts::PI;
+    js_radix_string(value, 36).replace(['0', '.'], "")
+}
+
+/// A positive number in a radix, with the fraction digits JavaScript's
+/// `Number.prototype.toString` gives (the shortest that read back as the same number)
+fn js_radix_string(value: f64, radix: u32) -> String {
+    const DIGITS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let base = f64::from(radix);
+    let mut integer = value.floor();
+    let mut fraction = value - integer;
+    // Half the distance to the next representable number bounds the digits needed
+    let mut delta = (0.5 * (f64::from_bits(value.to_bits() + 1) - value)).max(f64::from_bits(1));
+    let mut fraction_digits: Vec<u32> = Vec::new();
+    if fraction >= delta {
+        loop {
+            fraction *= base;
+            delta *= base;
+            let digit = fraction as u32;
+            fraction_digits.push(digit);
+            fraction -= f64::from(digit);
+            if (fraction > 0.5 || (fraction == 0.5 && digit % 2 == 1)) && fraction + delta > 1.0 {
+                // Round up, carrying into the digits before
+                loop {
+                    match fraction_digits.pop() {
+                        Some(digit) if digit + 1 < radix => {
+                            fraction_digits.push(digit + 1);
+                            break;
+                        }
+                        Some(_) => {}
+                        None => {
+                            integer += 1.0;
+                            break;
+                        }
+                    }
+                }
+                break;
+            }
+            if fraction < delta {
+                break;
+            }
+        }
+    }
+
+    let mut integer_digits = Vec::new();
+    loop {
+        let remainder = integer % base;
+        integer_digits.push(remainder as u32);
+        integer = (integer - remainder) / base;
+        if integer <= 0.0 {
+            break;
+        }
+    }
+    let mut result: String = integer_digits
+        .iter()
+        .rev()
+        .map(|digit| char::from(DIGITS[*digit as usize]))
+        .collect();
+    if !fraction_digits.is_empty() {
+        result.push('.');
+        result.extend(
+            fraction_digits
+                .iter()
+                .map(|digit| char::from(DIGITS[*digit as usize])),
+        );
+    }
+    result
+}
+
+fn format_time(timestamp: &str) -> Option<String> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+}
+
+fn non_empty(text: String) -> Option<String> {
+    Some(text).filter(|text| !text.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_social_post_url() {
+        assert_eq!(
+            SocialPostUrl::parse("https://x.com/jack/status/20?s=20"),
+            Some(SocialPostUrl::Twitter(20))
+        );
+        assert_eq!(
+            SocialPostUrl::parse("https://mobile.twitter.com/jack/status/20/photo/1"),
+            Some(SocialPostUrl::Twitter(20))
+        );
+        let mastodon = Some(SocialPostUrl::Mastodon {
+            host: "mastodon.social".to_string(),
+            id: "109".to_string(),
+        });
+        assert_eq!(
+            SocialPostUrl::parse("https://mastodon.social/@Gargron/109"),
+            mastodon
+        );
+        assert_eq!(
+            SocialPostUrl::parse("https://mastodon.social/users/Gargron/statuses/109"),
+            mastodon
+        );
+        assert_eq!(SocialPostUrl::parse("https://x.com/jack"), None);
+        assert_eq!(SocialPostUrl::parse("https://example.com/blog/109"), None);
+        assert_eq!(
+            SocialPostUrl::parse("https://medium.com/@user/post-1a2b"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_syndication_token() {
+        // Computed with JavaScript
+        assert_eq!(syndication_token(20), "6dq1a2xwd93");
+        assert_eq!(syndication_token(1585341984679469056), "3uchycv2wqc");
+        assert_eq!(syndication_token(1683920951807971329), "42y6zv7ufp");
+        assert_eq!(syndication_token(463440424141459456), "14fxvks611f");
+    }
+
+    #[test]
+    fn test_tweet_post() -> Result<()> {
+        let tweet: Tweet = serde_json::from_value(json!({
+            "id_str": "2", "created_at": "2023-05-01T12:30:00.000Z",
+            "text": "@a Read <this> https://t.co/x https://t.co/m",
+            "display_text_range": [3, 29],
+            "user": {"name": "Jack", "screen_name": "jack"},
+            "entities": {
+                "urls": [{"url": "https://t.co/x", "expanded_url": "https://example.com/a?b=1&c=2", "display_url": "example.com/a"}],
+                "media": [{"url": "https://t.co/m"}]
+            },
+            "mediaDetails": [
+                {"type": "photo", "media_url_https": "https://pbs.twimg.com/media/p.jpg", "ext_alt_text": "A cat"},
+                {"type": "video", "media_url_https": "https://pbs.twimg.com/v.jpg", "video_info": {"variants": [
+                    {"content_type": "video/mp4", "url": "https://video.twimg.com/low.mp4", "bitrate": 100},
+                    {"content_type": "video/mp4", "url": "https://video.twimg.com/high.mp4", "bitrate": 900},
+                    {"content_type": "application/x-mpegURL", "url": "https://video.twimg.com/v.m3u8"}
+                ]}}
+            ]
+        }))?;
+        let post = tweet.into_post();
+        assert_eq!(post.url, "https://x.com/jack/status/2");
+        assert_eq!(post.posted.as_deref(), Some("2023-05-01 12:30 UTC"));
+        assert_eq!(
+            post.content,
+            "<p>Read &lt;this&gt; <a href=\"https://example.com/a?b=1&amp;c=2\">example.com/a</a></p>"
+        );
+        assert_eq!(post.media[1].url, "https://video.twimg.com/high.mp4");
+
+        let thread = SocialThread {
+            url: "https://x.com/jack/status/2".to_string(),
+            posts: vec![post],
+            focus: 0,
+        };
+        assert_eq!(thread.title(), "Post by Jack (@jack)");
+        let markdown = thread.to_markdown();
+        assert!(markdown.starts_with(
+            "**Jack** (@jack) · [2023-05-01 12:30 UTC](https://x.com/jack/status/2)\n"
+        ));
+        assert!(markdown.contains("\n![A cat](https://pbs.twimg.com/media/p.jpg)\n"));
+        assert!(markdown.contains("\n[Video](https://video.twimg.com/high.mp4)\n"));
+        let html = thread.to_html()?;
+        assert!(html.contains("<title>Post by Jack (@jack)</title>"));
+        assert!(html.contains("<figcaption>A cat</figcaption>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tweet_reversed_text_range() -> Result<()> {
+        let tweet: Tweet = serde_json::from_value(json!({
+            "id_str": "3", "text": "Hello there", "display_text_range": [6, 2],
+            "user": {"name": "Jack", "screen_name": "jack"}
+        }))?;
+        assert_eq!(tweet.into_post().content, "<p>Hello there</p>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_mastodon_post() -> Result<()> {
+        let status: MastodonStatus = serde_json::from_value(json!({
+            "id": "1", "uri": "https://example.social/users/ann/statuses/1",
+            "url": "https://example.social/@ann/1", "created_at": "2024-02-03T04:05:06.000Z",
+            "in_reply_to_id": null, "content": "<p>Hello</p>", "spoiler_text": "",
+            "account": {"id": "7", "username": "ann", "acct": "ann", "display_name": ""},
+            "media_attachments": [{"type": "gifv", "url": "https://files/a.mp4", "preview_url": "https://files/a.png", "description": ""}]
+        }))?;
+        let post = status.into_post("example.social");
+        assert_eq!(post.author, "ann");
+        assert_eq!(post.handle, "@ann@example.social");
+        assert_eq!(post.content_warning, None);
+        assert_eq!(
+            post.media,
+            vec![SocialMedia {
+                kind: MediaKind::Video,
+                url: "https://files/a.mp4".to_string(),
+                preview: Some("https://files/a.png".to_string()),
+                description: None,
+            }]
+        );
+        Ok(())
+    }
+}
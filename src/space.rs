@@ -0,0 +1,133 @@
+//! Disk space checks for batch conversions
+//!
+//! Before a batch run, the free space in the output directory is compared against a
+//! rough estimate of the output size so that runs fail early with a clear error. During
+//! a run, write errors caused by a full disk are recognized so the batch can stop instead
+//! of attempting every remaining conversion.
+
+use anyhow::Result;
+use std::path::Path;
+
+/// Estimated size of one PDF, in bytes
+pub const PDF_ESTIMATE: u64 = 5 * 1024 * 1024;
+
+/// Estimated size of one Markdown document, in bytes
+pub const MARKDOWN_ESTIMATE: u64 = 256 * 1024;
+
+/// Free space kept in reserve on top of the estimated output size, in bytes
+pub const RESERVE: u64 = 50 * 1024 * 1024;
+
+/// Estimated space needed to convert `count` pages, including the reserve
+pub fn estimate(count: usize, pdf: bool, markdown: bool) -> u64 {
+    let mut per_page = 0;
+    if pdf {
+        per_page += PDF_ESTIMATE;
+    }
+    if markdown {
+        per_page += MARKDOWN_ESTIMATE;
+    }
+    per_page
+        .saturating_mul(count as u64)
+        .saturating_add(RESERVE)
+}
+
+/// Free space available to the current user on the filesystem holding `path`
+///
+/// The path does not need to exist yet; its nearest existing ancestor is used.
+///
+/// # Errors
+///
+/// Returns an error if the free space cannot be determined
+pub fn available_space(path: &Path) -> Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.exists())
+        .unwrap_or_else(|| Path::new("."));
+    Ok(fs2::available_space(existing)?)
+}
+
+/// Fail if the filesystem holding `path` has less than `required` bytes free
+///
+/// # Errors
+///
+/// Returns an error if there is not enough free space, or it cannot be determined
+pub fn ensure_space(path: &Path, required: u64) -> Result<()> {
+    let available = available_space(path)?;
+    if available < required {
+        return Err(anyhow::anyhow!(
+            "Not enough disk space in {}: about {} needed, {} available",
+            path.display(),
+            format_size(required),
+            format_size(available)
+        ));
+    }
+    Ok(())
+}
+
+/// Whether an error was caused by the disk (or quota) being full
+pub fn is_disk_full(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        cause
+            .downcast_ref::<std::io::Error>()
+            .is_some_and(|e| e.kind() == std::io::ErrorKind::StorageFull)
+    })
+}
+
+/// Format a byte count for messages, e.g. `1.5 GB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_estimate() {
+        assert_eq!(estimate(0, true, true), RESERVE);
+        assert_eq!(
+            estimate(2, true, true),
+            2 * (PDF_ESTIMATE + MARKDOWN_ESTIMATE) + RESERVE
+        );
+        assert_eq!(estimate(usize::MAX, true, false), u64::MAX);
+    }
+
+    #[test]
+    fn test_ensure_space() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let missing = temp_dir.path().join("not/yet/created");
+        assert!(available_space(&missing)? > 0);
+        ensure_space(&missing, 1)?;
+
+        let error = ensure_space(&missing, u64::MAX).unwrap_err();
+        assert!(error.to_string().contains("Not enough disk space"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_disk_full() {
+        let full = anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::StorageFull))
+            .context("Failed to write page.pdf");
+        assert!(is_disk_full(&full));
+        assert!(!is_disk_full(&anyhow::anyhow!("Navigation failed")));
+    }
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1536), "1.5 KB");
+        assert_eq!(format_size(5 * 1024 * 1024), "5.0 MB");
+    }
+}
@@ -0,0 +1,507 @@
+//! Per-domain statistics for batch runs
+//!
+//! Batch conversions record the outcome of every page by domain: whether it succeeded,
+//! how long it took, how much output it produced, and what kind of error it failed with.
+//! The resulting report is printed as a table after the run and can be saved as JSON,
+//! which makes it easy to spot the sites that need a longer wait or other tuning.
+
+use crate::blocklist::BlockedUrl;
+use crate::integration::FormatOutcome;
+use crate::pii::{DocumentPii, PiiScanner};
+use crate::security::SecurityPosture;
+use crate::timeouts::AdaptiveTimeouts;
+use crate::virusscan::Quarantined;
+use crate::{atomic, space};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Domain under which local files are recorded
+pub const LOCAL_DOMAIN: &str = "(local files)";
+
+/// Accumulated outcomes of the pages of one domain
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct DomainTally {
+    successes: u64,
+    partial: u64,
+    failures: u64,
+    render_time: Duration,
+    output_bytes: u64,
+    errors: BTreeMap<String, u64>,
+}
+
+/// Outcomes of the pages converted in one run, grouped by domain
+#[derive(Debug, Clone, Default)]
+pub struct RunStats {
+    domains: BTreeMap<String, DomainTally>,
+    blocked: Vec<BlockedUrl>,
+    quarantined: Vec<Quarantined>,
+    security: Vec<SecurityPosture>,
+}
+
+/// Statistics for one domain in a [`StatsReport`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomainStats {
+    /// The domain, or [`LOCAL_DOMAIN`] for local files
+    pub domain: String,
+    /// Pages attempted
+    pub attempts: u64,
+    /// Pages converted successfully to every requested format
+    pub successes: u64,
+    /// Pages converted to some, but not all, requested formats
+    #[serde(default)]
+    pub partial: u64,
+    /// Pages that failed
+    pub failures: u64,
+    /// Fraction of attempts that succeeded completely, from 0 to 1
+    pub success_rate: f64,
+    /// Average time spent converting a page, successful or not, in milliseconds
+    pub avg_render_ms: u64,
+    /// Average size of the files written for a successful or partially converted page,
+    /// in bytes
+    pub avg_output_bytes: u64,
+    /// Number of failures per error kind
+    pub errors: BTreeMap<String, u64>,
+    /// Page load timeout the domain ended the run with, in seconds, if it was extended
+    /// because the domain was slow
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extended_timeout_secs: Option<u64>,
+}
+
+/// Statistics report of a run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsReport {
+    /// When the report was generated
+    pub generated_at: DateTime<Utc>,
+    /// Per-domain statistics, sorted by domain
+    pub domains: Vec<DomainStats>,
+    /// URLs skipped because their domain is on a blocklist
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub blocked: Vec<BlockedUrl>,
+    /// Files moved into quarantine because the virus scan found them infected
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quarantined: Vec<Quarantined>,
+    /// Documents found to contain personal data, when pages were scanned for it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pii: Vec<DocumentPii>,
+    /// Origins whose security posture was flagged, when it was recorded
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub security: Vec<SecurityPosture>,
+}
+
+impl RunStats {
+    /// Create empty statistics
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether no pages have been recorded
+    pub fn is_empty(&self) -> bool {
+        self.domains.is_empty()
+    }
+
+    /// Record a URL skipped because its domain is on a blocklist
+    pub fn record_blocked(&mut self, blocked: BlockedUrl) {
+        self.blocked.push(blocked);
+    }
+
+    /// Record files moved into quarantine by the virus scan
+    pub fn record_quarantined(&mut self, files: Vec<Quarantined>) {
+        self.quarantined.extend(files);
+    }
+
+    /// Record the security posture of a captured origin, keeping it for the report when
+    /// it was flagged
+    pub fn record_security(&mut self, posture: &SecurityPosture) {
+        if posture.is_flagged() && !self.security.iter().any(|p| p.origin == posture.origin) {
+            self.security.push(posture.clone());
+        }
+    }
+
+    /// Record a page converted successfully in `elapsed`, writing `output_bytes` bytes
+    pub fn record_success(&mut self, source: &str, elapsed: Duration, output_bytes: u64) {
+        let tally = self.tally(source);
+        tally.successes += 1;
+        tally.render_time += elapsed;
+        tally.output_bytes += output_bytes;
+    }
+
+    /// Record the outcome of converting a page to each requested format
+    ///
+    /// Errors are grouped by format and kind, e.g. `PDF: timeout`.
+    pub fn record_outcome(&mut self, source: &str, elapsed: Duration, outcome: &FormatOutcome) {
+        let tally = self.tally(source);
+        tally.render_time += elapsed;
+        tally.output_bytes += files_size(&outcome.files);
+        match (outcome.converted.is_empty(), outcome.failed.is_empty()) {
+            (_, true) => tally.successes += 1,
+            (false, false) => tally.partial += 1,
+            (true, false) => tally.failures += 1,
+        }
+        for (format, error) in &outcome.failed {
+            *tally
+                .errors
+                .entry(format!("{}: {}", format, error_kind(error)))
+                .or_default() += 1;
+        }
+    }
+
+    /// Record a page that failed after `elapsed` with `error`
+    pub fn record_failure(&mut self, source: &str, elapsed: Duration, error: &anyhow::Error) {
+        let tally = self.tally(source);
+        tally.failures += 1;
+        tally.render_time += elapsed;
+        *tally.errors.entry(error_kind(error)).or_default() += 1;
+    }
+
+    fn tally(&mut self, source: &str) -> &mut DomainTally {
+        self.domains.entry(domain_of(source)).or_default()
+    }
+
+    /// Summarize the recorded outcomes
+    pub fn report(&self) -> StatsReport {
+        let domains = self
+            .domains
+            .iter()
+            .map(|(domain, tally)| {
+                let attempts = tally.successes + tally.partial + tally.failures;
+                DomainStats {
+                    domain: domain.clone(),
+                    attempts,
+                    successes: tally.successes,
+                    partial: tally.partial,
+                    failures: tally.failures,
+                    success_rate: tally.successes as f64 / attempts.max(1) as f64,
+                    avg_render_ms: (tally.render_time.as_millis() / u128::from(attempts.max(1)))
+                        as u64,
+                    avg_output_bytes: tally.output_bytes / (tally.successes + tally.partial).max(1),
+                    errors: tally.errors.clone(),
+                    extended_timeout_secs: None,
+                }
+            })
+            .collect();
+        StatsReport {
+            generated_at: Utc::now(),
+            domains,
+            blocked: self.blocked.clone(),
+            quarantined: self.quarantined.clone(),
+            pii: Vec::new(),
+            security: self.security.clone(),
+        }
+    }
+}
+
+impl StatsReport {
+    /// Record the domains whose page load timeout was extended during the run
+    pub fn with_timeouts(mut self, timeouts: &AdaptiveTimeouts) -> Self {
+        let extended = timeouts.extended();
+        for stats in &mut self.domains {
+            stats.extended_timeout_secs = extended.get(&stats.domain).map(Duration::as_secs);
+        }
+        self
+    }
+
+    /// Record the personal data found in the pages of the run
+    pub fn with_pii(mut self, scanner: &PiiScanner) -> Self {
+        self.pii = scanner.findings();
+        self
+    }
+
+    /// List the documents found to contain personal data, one per line with masked
+    /// examples
+    pub fn pii_summary(&self) -> String {
+        let mut summary = String::new();
+        for document in &self.pii {
+            writeln!(summary, "{}: {}", document.source, document.summary())
+                .expect("writing to a String cannot fail");
+            if !document.examples.is_empty() {
+                writeln!(summary, "  e.g. {}", document.examples.join(", "))
+                    .expect("writing to a String cannot fail");
+            }
+        }
+        summary
+    }
+
+    /// List the origins whose security posture was flagged, with their issues, and the
+    /// domains whose pages failed on certificate or TLS errors, one per line; empty when
+    /// there are neither
+    pub fn security_summary(&self) -> String {
+        let mut summary = String::new();
+        for posture in &self.security {
+            writeln!(summary, "{}: {}", posture.origin, posture.issues.join("; "))
+                .expect("writing to a String cannot fail");
+        }
+        for stats in &self.domains {
+            let failures: Vec<String> = stats
+                .errors
+                .iter()
+                .filter(|(kind, _)| {
+                    kind.contains("net::ERR_CERT_") || kind.contains("net::ERR_SSL_")
+                })
+                .map(|(kind, count)| format!("{} ×{}", kind, count))
+                .collect();
+            if !failures.is_empty() {
+                writeln!(summary, "{}: {}", stats.domain, failures.join(", "))
+                    .expect("writing to a String cannot fail");
+            }
+        }
+        summary
+    }
+
+    /// Render the report as a plain-text table, one row per domain
+    pub fn to_table(&self) -> String {
+        let header = [
+            "Domain", "OK", "Partial", "Failed", "Success", "Avg time", "Avg size", "Timeout",
+            "Errors",
+        ];
+        let rows: Vec<[String; 9]> = self
+            .domains
+            .iter()
+            .map(|stats| {
+                let errors = stats
+                    .errors
+                    .iter()
+                    .map(|(kind, count)| format!("{} ×{}", kind, count))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                [
+                    stats.domain.clone(),
+                    stats.successes.to_string(),
+                    stats.partial.to_string(),
+                    stats.failures.to_string(),
+                    format!("{:.0}%", stats.success_rate * 100.0),
+                    format!("{:.1}s", stats.avg_render_ms as f64 / 1000.0),
+                    match stats.successes + stats.partial {
+                        0 => "-".to_string(),
+                        _ => space::format_size(stats.avg_output_bytes),
+                    },
+                    match stats.extended_timeout_secs {
+                        Some(secs) => format!("{}s", secs),
+                        None => "-".to_string(),
+                    },
+                    if errors.is_empty() {
+                        "-".to_string()
+                    } else {
+                        errors
+                    },
+                ]
+            })
+            .collect();
+
+        let mut widths = header.map(|title| title.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut table = String::new();
+        let header = header.map(str::to_string);
+        for row in std::iter::once(&header).chain(&rows) {
+            let mut line = String::new();
+            for (index, (cell, width)) in row.iter().zip(widths).enumerate() {
+                // Text columns are left-aligned, numeric columns right-aligned
+                match index {
+                    0 | 8 => write!(line, "{:<width$}  ", cell, width = width),
+                    _ => write!(line, "{:>width$}  ", cell, width = width),
+                }
+                .expect("writing to a String cannot fail");
+            }
+            table.push_str(line.trim_end());
+            table.push('\n');
+        }
+        table
+    }
+
+    /// Save the report as pretty-printed JSON
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be written
+    pub async fn write_json(&self, path: &Path) -> Result<()> {
+        atomic::write(path, serde_json::to_vec_pretty(self)?).await
+    }
+}
+
+/// Domain of a URL, or [`LOCAL_DOMAIN`] for anything that is not a URL with a host
+pub fn domain_of(source: &str) -> String {
+    url::Url::parse(source)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_else(|| LOCAL_DOMAIN.to_string())
+}
+
+/// Short description of the kind of an error, used to group failures
+///
+/// Browser network errors keep their Chrome error code (e.g. `net::ERR_NAME_NOT_RESOLVED`)
+/// and HTTP errors their status code.
+pub fn error_kind(error: &anyhow::Error) -> String {
+    if space::is_disk_full(error) {
+        return "disk full".to_string();
+    }
+    for cause in error.chain() {
+        if let Some(e) = cause.downcast_ref::<reqwest::Error>() {
+            if e.is_timeout() {
+                return "timeout".to_string();
+            }
+            if let Some(status) = e.status() {
+                return format!("HTTP {}", status.as_u16());
+            }
+            if e.is_connect() {
+                return "connection failed".to_string();
+            }
+        }
+    }
+
+    let message = format!("{:#}", error);
+    if let Some(start) = message.find("net::ERR_") {
+        let code: String = message[start..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | ':'))
+            .collect();
+        return code;
+    }
+    let lower = message.to_lowercase();
+    if lower.contains("timed out")
+        || lower.contains("timeout")
+        || lower.contains("event waited for never came")
+    {
+        "timeout".to_string()
+    } else if lower.contains("quarantined as infected") {
+        "quarantined".to_string()
+    } else if lower.contains("virus scan failed") {
+        "virus scan failed".to_string()
+    } else if lower.contains("not in the page cache") {
+        "not cached".to_string()
+    } else if lower.contains("supported") || lower.contains("relative url") {
+        "invalid URL".to_string()
+    } else {
+        "other".to_string()
+    }
+}
+
+/// Total size of the given files, ignoring files that do not exist
+pub fn files_size(files: &[PathBuf]) -> u64 {
+    files
+        .iter()
+        .filter_map(|file| std::fs::metadata(file).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integration::OutputFormat;
+
+    #[test]
+    fn test_report_per_domain() {
+        let mut stats = RunStats::new();
+        stats.record_success("https://a.example/one", Duration::from_millis(1000), 300);
+        stats.record_success("https://a.example/two", Duration::from_millis(2000), 100);
+        stats.record_failure(
+            "https://b.example/",
+            Duration::from_millis(500),
+            &anyhow::anyhow!("Navigate failed: net::ERR_NAME_NOT_RESOLVED"),
+        );
+        stats.record_success("/tmp/page.html", Duration::from_millis(10), 5);
+        let mut outcome = FormatOutcome::default();
+        outcome.record(
+            OutputFormat::Pdf,
+            Err(anyhow::anyhow!("Navigate failed: net::ERR_TIMED_OUT")),
+        );
+        outcome.record(OutputFormat::Markdown, Ok(Vec::new()));
+        stats.record_outcome("https://c.example/", Duration::from_millis(100), &outcome);
+
+        let report = stats.report();
+        let domains: Vec<_> = report.domains.iter().map(|d| d.domain.as_str()).collect();
+        assert_eq!(
+            domains,
+            vec![LOCAL_DOMAIN, "a.example", "b.example", "c.example"]
+        );
+
+        let a = &report.domains[1];
+        assert_eq!((a.attempts, a.successes, a.failures), (2, 2, 0));
+        assert_eq!(a.success_rate, 1.0);
+        assert_eq!(a.avg_render_ms, 1500);
+        assert_eq!(a.avg_output_bytes, 200);
+
+        let b = &report.domains[2];
+        assert_eq!(b.success_rate, 0.0);
+        assert_eq!(b.avg_output_bytes, 0);
+        assert_eq!(b.errors.get("net::ERR_NAME_NOT_RESOLVED"), Some(&1));
+
+        let timeouts = AdaptiveTimeouts::new(Duration::from_secs(10), Duration::from_secs(40));
+        timeouts.record("https://b.example/", Duration::from_secs(10), true);
+        let report = report.with_timeouts(&timeouts);
+        assert_eq!(report.domains[1].extended_timeout_secs, None);
+        assert_eq!(report.domains[2].extended_timeout_secs, Some(20));
+
+        let table = report.to_table();
+        assert!(table.starts_with("Domain"));
+        assert!(table.contains("20s  net::ERR_NAME_NOT_RESOLVED ×1"));
+        assert_eq!(table.lines().count(), 5);
+
+        let c = &report.domains[3];
+        assert_eq!((c.successes, c.partial, c.failures), (0, 1, 0));
+        assert_eq!(c.errors.get("PDF: net::ERR_TIMED_OUT"), Some(&1));
+
+        stats.record_blocked(BlockedUrl {
+            url: "https://bad.example/".to_string(),
+            domain: "bad.example".to_string(),
+            list: "malware.txt".to_string(),
+        });
+        assert_eq!(stats.report().blocked.len(), 1);
+        assert_eq!(stats.report().domains.len(), 4);
+
+        let scanner = PiiScanner::new();
+        scanner.scan("https://a.example/one", "Contact jane@a.example");
+        let report = report.with_pii(&scanner);
+        assert_eq!(
+            report.pii_summary(),
+            "https://a.example/one: 1 email\n  e.g. j***@a.example\n"
+        );
+        assert_eq!(report.security_summary(), "");
+
+        stats.record_failure(
+            "https://d.example/",
+            Duration::from_millis(50),
+            &anyhow::anyhow!("Navigate failed: net::ERR_CERT_DATE_INVALID"),
+        );
+        assert_eq!(
+            stats.report().security_summary(),
+            "d.example: net::ERR_CERT_DATE_INVALID ×1\n"
+        );
+    }
+
+    #[test]
+    fn test_error_kind() {
+        assert_eq!(
+            error_kind(&anyhow::anyhow!("The event waited for never came")),
+            "timeout"
+        );
+        assert_eq!(
+            error_kind(&anyhow::anyhow!(
+                "https://a.example is not in the page cache; fetch it once without --offline first"
+            )),
+            "not cached"
+        );
+        assert_eq!(
+            error_kind(&anyhow::Error::new(std::io::Error::from(
+                std::io::ErrorKind::StorageFull
+            ))),
+            "disk full"
+        );
+        assert_eq!(
+            error_kind(&anyhow::anyhow!(
+                "Quarantined as infected: Eicar-Signature FOUND"
+            )),
+            "quarantined"
+        );
+        assert_eq!(error_kind(&anyhow::anyhow!("Something broke")), "other");
+    }
+}
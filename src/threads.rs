@@ -0,0 +1,387 @@
+//! Discussion thread archiving
+//!
+//! Hacker News items and Reddit posts are read through their JSON APIs instead of being
+//! printed as pages, so the whole discussion is archived, including comments that the
+//! page collapses or splits over several pages. The thread is written as Markdown with
+//! the post followed by the comments as nested lists, and the article the post links to
+//! can be archived next to it.
+
+use crate::comments::{Comment, comments_markdown};
+use crate::readlater::{checked, http_client, send_with_retry};
+use anyhow::Result;
+use chrono::DateTime;
+use reqwest::Client;
+use serde_json::Value;
+use tracing::debug;
+use url::Url;
+
+/// Hacker News item API (Algolia), which returns an item with its whole comment tree
+pub const HACKER_NEWS_API: &str = "https://hn.algolia.com/api/v1";
+
+/// Reddit base URL for the JSON listings of posts
+pub const REDDIT_API: &str = "https://www.reddit.com";
+
+/// Text Reddit shows in place of deleted comments
+const DELETED: &str = "[deleted]";
+
+/// A discussion thread, identified by its URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThreadSource {
+    /// A Hacker News item, by id
+    HackerNews(u64),
+    /// A Reddit post, by its base-36 id
+    Reddit(String),
+}
+
+impl ThreadSource {
+    /// Recognize a Hacker News item URL (`news.ycombinator.com/item?id=...`) or a Reddit
+    /// post URL (`reddit.com/r/.../comments/ID/...` or `redd.it/ID`)
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.trim_start_matches("www.");
+        let segments: Vec<&str> = url.path_segments()?.filter(|s| !s.is_empty()).collect();
+        match host {
+            "news.ycombinator.com" if segments == ["item"] => url
+                .query_pairs()
+                .find(|(key, _)| key == "id")
+                .and_then(|(_, id)| id.parse().ok())
+                .map(Self::HackerNews),
+            "redd.it" => match segments.as_slice() {
+                [id] => Self::reddit_id(id),
+                _ => None,
+            },
+            host if host == "reddit.com" || host.ends_with(".reddit.com") => segments
+                .iter()
+                .position(|segment| *segment == "comments")
+                .and_then(|index| segments.get(index + 1))
+                .and_then(|id| Self::reddit_id(id)),
+            _ => None,
+        }
+    }
+
+    fn reddit_id(id: &str) -> Option<Self> {
+        let valid = !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric());
+        valid.then(|| Self::Reddit(id.to_ascii_lowercase()))
+    }
+
+    /// Output file name of the thread, without extension
+    pub fn file_stem(&self) -> String {
+        match self {
+            Self::HackerNews(id) => format!("hn-{}", id),
+            Self::Reddit(id) => format!("reddit-{}", id),
+        }
+    }
+
+    /// The canonical URL of the discussion
+    pub fn url(&self) -> String {
+        match self {
+            Self::HackerNews(id) => format!("https://news.ycombinator.com/item?id={}", id),
+            Self::Reddit(id) => format!("https://www.reddit.com/comments/{}", id),
+        }
+    }
+}
+
+/// A discussion thread with its comments
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Thread {
+    /// Where the thread was read from
+    pub source: ThreadSource,
+    /// Title of the post
+    pub title: String,
+    /// The article the post links to, None for text posts
+    pub link: Option<String>,
+    /// Name of the poster
+    pub author: Option<String>,
+    /// Points (Hacker News) or score (Reddit) of the post
+    pub score: Option<i64>,
+    /// When the post was made, as a Unix timestamp
+    pub created: Option<i64>,
+    /// Subreddit of a Reddit post
+    pub community: Option<String>,
+    /// Text of the post as Markdown, empty for link posts
+    pub text: String,
+    /// The comments in thread order
+    pub comments: Vec<Comment>,
+}
+
+impl Thread {
+    /// Render the thread as a Markdown document
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n\n", self.title.trim());
+        markdown.push_str(&format!("- Discussion: <{}>\n", self.source.url()));
+        if let Some(link) = &self.link {
+            markdown.push_str(&format!("- Article: <{}>\n", link));
+        }
+        if let Some(community) = &self.community {
+            markdown.push_str(&format!("- Subreddit: r/{}\n", community));
+        }
+        let mut posted = Vec::new();
+        if let Some(author) = &self.author {
+            posted.push(format!("by **{}**", author));
+        }
+        if let Some(created) = self.created.and_then(|ts| DateTime::from_timestamp(ts, 0)) {
+            posted.push(format!("on {}", created.format("%Y-%m-%d %H:%M UTC")));
+        }
+        if let Some(score) = self.score {
+            posted.push(format!("{} points", score));
+        }
+        if !posted.is_empty() {
+            markdown.push_str(&format!("- Posted {}\n", posted.join(", ")));
+        }
+        if !self.text.trim().is_empty() {
+            markdown.push_str(&format!("\n{}\n", self.text.trim()));
+        }
+        let comments = comments_markdown(&self.comments);
+        if !comments.is_empty() {
+            markdown.push_str(&format!("\n{}", comments));
+        }
+        markdown
+    }
+}
+
+/// Client for the Hacker News and Reddit APIs
+pub struct ThreadClient {
+    client: Client,
+    hacker_news_endpoint: String,
+    reddit_endpoint: String,
+}
+
+impl ThreadClient {
+    /// Create a thread client
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+            hacker_news_endpoint: HACKER_NEWS_API.to_string(),
+            reddit_endpoint: REDDIT_API.to_string(),
+        })
+    }
+
+    /// Fetch a thread with all of its comments
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the API cannot be reached, or the thread does not exist
+    pub async fn fetch(&self, source: &ThreadSource) -> Result<Thread> {
+        let thread = match source {
+            ThreadSource::HackerNews(id) => {
+                let url = format!("{}/items/{}", self.hacker_news_endpoint, id);
+                let response = send_with_retry(|| self.client.get(&url)).await?;
+                let item: Value = checked(response, "Hacker News item").await?.json().await?;
+                hacker_news_thread(*id, &item)?
+            }
+            ThreadSource::Reddit(id) => {
+                let url = format!("{}/comments/{}.json", self.reddit_endpoint, id);
+                let query = [("raw_json", "1"), ("limit", "500")];
+                let response = send_with_retry(|| self.client.get(&url).query(&query)).await?;
+                let listings: Value = checked(response, "Reddit post").await?.json().await?;
+                reddit_thread(id, &listings)?
+            }
+        };
+        debug!(
+            "Fetched {} with {} comments",
+            source.url(),
+            thread.comments.len()
+        );
+        Ok(thread)
+    }
+}
+
+/// A thread from a Hacker News item of the Algolia API
+fn hacker_news_thread(id: u64, item: &Value) -> Result<Thread> {
+    let title = item["title"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Hacker News item {} is not a story", id))?;
+    let mut comments = Vec::new();
+    for child in item["children"].as_array().into_iter().flatten() {
+        collect_hacker_news(child, 0, &mut comments);
+    }
+    Ok(Thread {
+        source: ThreadSource::HackerNews(id),
+        title: title.to_string(),
+        link: non_empty(&item["url"]),
+        author: non_empty(&item["author"]),
+        score: item["points"].as_i64(),
+        created: item["created_at_i"].as_i64(),
+        community: None,
+        text: item["text"]
+            .as_str()
+            .map(|html| mdka::from_html(html).trim().to_string())
+            .unwrap_or_default(),
+        comments,
+    })
+}
+
+fn collect_hacker_news(item: &Value, depth: usize, comments: &mut Vec<Comment>) {
+    let replies = item["children"].as_array().map_or(&[][..], Vec::as_slice);
+    let content = item["text"]
+        .as_str()
+        .map(|html| mdka::from_html(html).trim().to_string())
+        .filter(|content| !content.is_empty());
+    // Deleted comments are kept as placeholders only while they have replies
+    if content.is_none() && replies.is_empty() {
+        return;
+    }
+    comments.push(Comment {
+        author: non_empty(&item["author"]),
+        content: content.unwrap_or_else(|| DELETED.to_string()),
+        depth,
+    });
+    for reply in replies {
+        collect_hacker_news(reply, depth + 1, comments);
+    }
+}
+
+/// A thread from the post and comment listings of a Reddit post
+fn reddit_thread(id: &str, listings: &Value) -> Result<Thread> {
+    let post = &listings[0]["data"]["children"][0]["data"];
+    let title = post["title"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Reddit post {} not found", id))?;
+    let mut comments = Vec::new();
+    collect_reddit(&listings[1], 0, &mut comments);
+    let is_self = post["is_self"].as_bool().unwrap_or(false);
+    Ok(Thread {
+        source: ThreadSource::Reddit(id.to_string()),
+        title: title.to_string(),
+        link: (!is_self).then(|| non_empty(&post["url"])).flatten(),
+        author: non_empty(&post["author"]).filter(|author| author != DELETED),
+        score: post["score"].as_i64(),
+        created: post["created_utc"].as_f64().map(|ts| ts as i64),
+        community: non_empty(&post["subreddit"]),
+        text: post["selftext"].as_str().unwrap_or_default().to_string(),
+        comments,
+    })
+}
+
+/// Collect the comments of a Reddit listing, skipping "load more" stubs
+fn collect_reddit(listing: &Value, depth: usize, comments: &mut Vec<Comment>) {
+    for child in listing["data"]["children"].as_array().into_iter().flatten() {
+        if child["kind"] != "t1" {
+            continue;
+        }
+        let data = &child["data"];
+        comments.push(Comment {
+            author: non_empty(&data["author"]).filter(|author| author != DELETED),
+            content: data["body"].as_str().unwrap_or(DELETED).trim().to_string(),
+            depth,
+        });
+        // Comments without replies have an empty string instead of a listing
+        collect_reddit(&data["replies"], depth + 1, comments);
+    }
+}
+
+fn non_empty(value: &Value) -> Option<String> {
+    value
+        .as_str()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_thread_source() {
+        assert_eq!(
+            ThreadSource::parse("https://news.ycombinator.com/item?id=8863"),
+            Some(ThreadSource::HackerNews(8863))
+        );
+        assert_eq!(
+            ThreadSource::parse("https://old.reddit.com/r/rust/comments/AbC12/title/"),
+            Some(ThreadSource::Reddit("abc12".to_string()))
+        );
+        assert_eq!(
+            ThreadSource::parse("https://redd.it/abc12"),
+            Some(ThreadSource::Reddit("abc12".to_string()))
+        );
+        assert_eq!(
+            ThreadSource::parse("https://news.ycombinator.com/news"),
+            None
+        );
+        assert_eq!(ThreadSource::parse("https://example.com/item?id=1"), None);
+    }
+
+    #[test]
+    fn test_hacker_news_thread() -> Result<()> {
+        let item = json!({
+            "title": "My YC app", "url": "http://www.getdropbox.com/u/2/screencast.html",
+            "author": "dhouston", "points": 111, "created_at_i": 1175714200,
+            "children": [
+                {"author": "pg", "text": "<p>Nice <i>demo</i></p>", "children": [
+                    {"author": "dhouston", "text": "Thanks", "children": []}
+                ]},
+                {"author": null, "text": null, "children": [
+                    {"author": "dang", "text": "Reply to a deleted comment", "children": []}
+                ]},
+                {"author": null, "text": null, "children": []}
+            ]
+        });
+        let thread = hacker_news_thread(8863, &item)?;
+        assert_eq!(thread.comments.len(), 4);
+        assert_eq!(thread.comments[1].depth, 1);
+        assert_eq!(thread.comments[2].content, DELETED);
+
+        let markdown = thread.to_markdown();
+        assert!(markdown.starts_with("# My YC app\n\n- Discussion: <https://news.ycombinator.com/item?id=8863>\n- Article: <http://www.getdropbox.com/u/2/screencast.html>\n"));
+        assert!(
+            markdown.contains("- Posted by **dhouston**, on 2007-04-04 19:16 UTC, 111 points\n")
+        );
+        assert!(markdown.contains("## Comments (4)\n\n- **pg**\n\n  Nice _demo_\n"));
+        assert!(markdown.contains("\n  - **dhouston**\n\n    Thanks\n"));
+
+        assert!(hacker_news_thread(1, &json!({"text": "a comment"})).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reddit_thread() -> Result<()> {
+        let listings = json!([
+            {"data": {"children": [{"kind": "t3", "data": {
+                "title": "Ask: favorite crate?", "is_self": true, "selftext": "**Go**",
+                "url": "https://www.reddit.com/r/rust/comments/abc12/", "author": "ferris",
+                "score": 42, "created_utc": 1700000000.0, "subreddit": "rust"
+            }}]}},
+            {"data": {"children": [
+                {"kind": "t1", "data": {"author": "a", "body": "serde", "replies": {
+                    "data": {"children": [
+                        {"kind": "t1", "data": {"author": "[deleted]", "body": "[deleted]", "replies": ""}},
+                        {"kind": "more", "data": {"count": 3}}
+                    ]}
+                }}},
+                {"kind": "t1", "data": {"author": "b", "body": "tokio", "replies": ""}}
+            ]}}
+        ]);
+        let thread = reddit_thread("abc12", &listings)?;
+        assert_eq!(thread.link, None);
+        assert_eq!(thread.text, "**Go**");
+        assert_eq!(
+            thread.comments,
+            vec![
+                Comment {
+                    author: Some("a".into()),
+                    content: "serde".into(),
+                    depth: 0
+                },
+                Comment {
+                    author: None,
+                    content: DELETED.into(),
+                    depth: 1
+                },
+                Comment {
+                    author: Some("b".into()),
+                    content: "tokio".into(),
+                    depth: 0
+                },
+            ]
+        );
+        assert!(thread.to_markdown().contains("- Subreddit: r/rust\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,174 @@
+//! Adaptive per-domain page load timeouts
+//!
+//! During a run, the load time of every page is recorded by domain. When a domain is
+//! consistently slow (its recent pages all used more than half of the timeout) or a page
+//! times out, the timeout for that domain is doubled, up to a maximum. A page that timed
+//! out is retried with the longer timeout instead of failing outright.
+//!
+//! The same [`AdaptiveTimeouts`] can be shared by the PDF and Markdown generators, so
+//! both learn from each other's page loads.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Page load timeout used for domains that have not been found slow
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Longest timeout a slow domain can be given by default
+pub const DEFAULT_MAX_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Number of recent page loads considered when deciding whether a domain is slow
+const HISTORY: usize = 3;
+
+/// Load time taking more than timeout / SLOW_DIVISOR counts as slow
+const SLOW_DIVISOR: u32 = 2;
+
+#[derive(Debug, Clone)]
+struct DomainTimeout {
+    timeout: Duration,
+    recent: VecDeque<Duration>,
+}
+
+/// Per-domain page load timeouts that grow for slow domains
+///
+/// Clones share the same state.
+#[derive(Debug, Clone)]
+pub struct AdaptiveTimeouts {
+    base: Duration,
+    max: Duration,
+    domains: Arc<Mutex<HashMap<String, DomainTimeout>>>,
+}
+
+impl Default for AdaptiveTimeouts {
+    fn default() -> Self {
+        Self::new(DEFAULT_TIMEOUT, DEFAULT_MAX_TIMEOUT)
+    }
+}
+
+impl AdaptiveTimeouts {
+    /// Create timeouts starting at `base` for every domain and never exceeding `max`
+    ///
+    /// A `max` below `base` is raised to `base`, which disables adaptation.
+    pub fn new(base: Duration, max: Duration) -> Self {
+        Self {
+            base,
+            max: max.max(base),
+            domains: Arc::default(),
+        }
+    }
+
+    /// The timeout for domains that have not been found slow
+    pub fn base(&self) -> Duration {
+        self.base
+    }
+
+    /// The current timeout for the domain of a URL
+    pub fn timeout_for(&self, url: &str) -> Duration {
+        self.lock()
+            .get(&domain_key(url))
+            .map_or(self.base, |domain| domain.timeout)
+    }
+
+    /// Record how long loading a URL took, and whether it timed out
+    ///
+    /// Returns the new timeout of the URL's domain if it was extended.
+    pub fn record(&self, url: &str, elapsed: Duration, timed_out: bool) -> Option<Duration> {
+        let mut domains = self.lock();
+        let domain = domains
+            .entry(domain_key(url))
+            .or_insert_with(|| DomainTimeout {
+                timeout: self.base,
+                recent: VecDeque::with_capacity(HISTORY),
+            });
+
+        if !timed_out {
+            if domain.recent.len() == HISTORY {
+                domain.recent.pop_front();
+            }
+            domain.recent.push_back(elapsed);
+        }
+        let slow = domain.recent.len() == HISTORY
+            && domain
+                .recent
+                .iter()
+                .all(|elapsed| *elapsed > domain.timeout / SLOW_DIVISOR);
+        if !(timed_out || slow) || domain.timeout >= self.max {
+            return None;
+        }
+
+        domain.timeout = (domain.timeout * 2).min(self.max);
+        domain.recent.clear();
+        Some(domain.timeout)
+    }
+
+    /// Domains whose timeout was extended during the run, with their current timeout
+    pub fn extended(&self) -> BTreeMap<String, Duration> {
+        self.lock()
+            .iter()
+            .filter(|(_, domain)| domain.timeout > self.base)
+            .map(|(name, domain)| (name.clone(), domain.timeout))
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, DomainTimeout>> {
+        self.domains
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn domain_key(url: &str) -> String {
+    url::Url::parse(url)
+        .ok()
+        .and_then(|url| url.host_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BASE: Duration = Duration::from_secs(10);
+    const MAX: Duration = Duration::from_secs(30);
+
+    #[test]
+    fn test_timeout_extends_for_timed_out_domain() {
+        let timeouts = AdaptiveTimeouts::new(BASE, MAX);
+        let url = "https://slow.example/page";
+        assert_eq!(timeouts.timeout_for(url), BASE);
+
+        assert_eq!(timeouts.record(url, BASE, true), Some(BASE * 2));
+        assert_eq!(timeouts.timeout_for("https://slow.example/other"), BASE * 2);
+        assert_eq!(timeouts.record(url, BASE * 2, true), Some(MAX));
+        // At the cap, timeouts are final
+        assert_eq!(timeouts.record(url, MAX, true), None);
+
+        assert_eq!(timeouts.timeout_for("https://fast.example/"), BASE);
+        assert_eq!(
+            timeouts.extended(),
+            BTreeMap::from([("slow.example".to_string(), MAX)])
+        );
+    }
+
+    #[test]
+    fn test_timeout_extends_for_consistently_slow_domain() {
+        let timeouts = AdaptiveTimeouts::new(BASE, MAX);
+        let url = "https://slow.example/";
+        let slow = Duration::from_secs(6);
+
+        assert_eq!(timeouts.record(url, slow, false), None);
+        assert_eq!(timeouts.record(url, Duration::from_secs(1), false), None);
+        assert_eq!(timeouts.record(url, slow, false), None);
+        assert_eq!(timeouts.record(url, slow, false), None);
+        assert_eq!(timeouts.record(url, slow, false), Some(BASE * 2));
+        assert_eq!(timeouts.timeout_for(url), BASE * 2);
+    }
+
+    #[test]
+    fn test_max_below_base_disables_adaptation() {
+        let timeouts = AdaptiveTimeouts::new(BASE, Duration::from_secs(1));
+        assert_eq!(timeouts.record("https://a.example/", BASE, true), None);
+        assert!(timeouts.extended().is_empty());
+    }
+}
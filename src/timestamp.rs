@@ -0,0 +1,320 @@
+//! Trusted timestamping of captures (RFC 3161)
+//!
+//! A timestamp authority (TSA) signs a statement that a SHA-256 digest existed at a
+//! given time, so a capture timestamped when it was saved can later be shown not to
+//! have been made or altered after that time. The digest of each saved file is sent to
+//! the authority as a `TimeStampReq`, and its `TimeStampResp` is kept whole, the form
+//! `openssl ts` reads:
+//!
+//! ```text
+//! openssl ts -reply -in page.pdf.tsr -text
+//! openssl ts -verify -data page.pdf -in page.pdf.tsr -CAfile tsa-ca.pem
+//! ```
+//!
+//! The reply is checked for a granted status and for the digest and nonce of the
+//! request; verifying the authority's signature is left to such tools.
+
+use crate::atomic;
+use anyhow::{Result, bail};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Extension appended to the name of a timestamped file for its timestamp reply
+pub const REPLY_EXTENSION: &str = "tsr";
+
+/// DER encoding of the object identifier of SHA-256 (2.16.840.1.101.3.4.2.1)
+const SHA256_OID: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x01];
+
+const TAG_BOOLEAN: u8 = 0x01;
+const TAG_INTEGER: u8 = 0x02;
+const TAG_OCTET_STRING: u8 = 0x04;
+const TAG_NULL: u8 = 0x05;
+const TAG_OID: u8 = 0x06;
+const TAG_GENERALIZED_TIME: u8 = 0x18;
+const TAG_SEQUENCE: u8 = 0x30;
+
+/// A timestamp granted by an authority
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimestampToken {
+    /// Hex SHA-256 digest that was timestamped
+    pub sha256: String,
+    /// The DER-encoded `TimeStampResp`, as returned by the authority
+    pub reply: Vec<u8>,
+    /// Time the authority put on the timestamp, when it could be read
+    pub gen_time: Option<DateTime<Utc>>,
+}
+
+/// Client of an RFC 3161 timestamp authority
+#[derive(Debug, Clone)]
+pub struct TimestampAuthority {
+    client: Client,
+    url: String,
+}
+
+impl TimestampAuthority {
+    /// Create a client for the authority at `url`, such as `https://freetsa.org/tsr`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not http(s) or the HTTP client cannot be created
+    pub fn new(url: &str) -> Result<Self> {
+        let parsed = url::Url::parse(url)?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            bail!("Timestamp authority URL must be http(s): {}", url);
+        }
+        let client = Client::builder()
+            .timeout(Duration::from_secs(30))
+            .user_agent("webpage-save-timestamp/1.0")
+            .build()?;
+        Ok(Self {
+            client,
+            url: url.to_string(),
+        })
+    }
+
+    /// The authority's URL
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Timestamp the SHA-256 digest of `data`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request fails, or the authority does not grant the
+    /// timestamp or answers for another digest
+    pub async fn timestamp(&self, data: &[u8]) -> Result<TimestampToken> {
+        let digest: [u8; 32] = Sha256::digest(data).into();
+        let nonce = nonce(&digest);
+        let response = self
+            .client
+            .post(&self.url)
+            .header("Content-Type", "application/timestamp-query")
+            .header("Accept", "application/timestamp-reply")
+            .body(request(&digest, &nonce))
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            bail!("Timestamp authority {} returned {}", self.url, status);
+        }
+        let reply = response.bytes().await?.to_vec();
+        parse_reply(reply, &digest, &nonce)
+    }
+
+    /// Timestamp a saved file, writing the reply next to it as `<file>.tsr`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, the timestamp is not granted, or
+    /// the reply cannot be written
+    pub async fn timestamp_file(&self, path: &Path) -> Result<(PathBuf, TimestampToken)> {
+        let data = tokio::fs::read(path).await?;
+        let token = self.timestamp(&data).await?;
+        let reply_path = reply_path(path);
+        atomic::write(&reply_path, &token.reply).await?;
+        Ok((reply_path, token))
+    }
+}
+
+/// Where the timestamp reply of a file is written
+pub fn reply_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(REPLY_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// A request nonce, unique to the digest and the time of the request
+fn nonce(digest: &[u8; 32]) -> [u8; 8] {
+    let mut hasher = Sha256::new();
+    hasher.update(digest);
+    hasher.update(
+        Utc::now()
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_be_bytes(),
+    );
+    let mut nonce: [u8; 8] = hasher.finalize()[..8]
+        .try_into()
+        .expect("SHA-256 digests are longer than 8 bytes");
+    // Keep the INTEGER positive without a leading zero byte
+    nonce[0] &= 0x7f;
+    nonce[0] |= 0x01;
+    nonce
+}
+
+/// DER encoding of a value with its tag and length
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut encoded = vec![tag];
+    let len = content.len();
+    match len {
+        0..0x80 => encoded.push(len as u8),
+        0x80..0x100 => encoded.extend([0x81, len as u8]),
+        _ => encoded.extend([0x82, (len >> 8) as u8, len as u8]),
+    }
+    encoded.extend_from_slice(content);
+    encoded
+}
+
+/// A `TimeStampReq` for a SHA-256 digest, asking for the authority's certificate
+fn request(digest: &[u8; 32], nonce: &[u8; 8]) -> Vec<u8> {
+    let algorithm = der(
+        TAG_SEQUENCE,
+        &[der(TAG_OID, SHA256_OID), der(TAG_NULL, &[])].concat(),
+    );
+    let imprint = der(
+        TAG_SEQUENCE,
+        &[algorithm, der(TAG_OCTET_STRING, digest)].concat(),
+    );
+    der(
+        TAG_SEQUENCE,
+        &[
+            der(TAG_INTEGER, &[1]),
+            imprint,
+            der(TAG_INTEGER, nonce),
+            der(TAG_BOOLEAN, &[0xff]),
+        ]
+        .concat(),
+    )
+}
+
+/// Split the first DER value off `data`: its tag, its content, and what follows
+pub(crate) fn read_value(data: &[u8]) -> Result<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Truncated DER value"))?;
+    let (&first, rest) = rest
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("Truncated DER value"))?;
+    let (len, rest) = match first {
+        0..=0x7f => (first as usize, rest),
+        0x81..=0x84 => {
+            let octets = (first & 0x7f) as usize;
+            if rest.len() < octets {
+                bail!("Truncated DER value");
+            }
+            let len = rest[..octets]
+                .iter()
+                .fold(0usize, |len, &byte| (len << 8) | byte as usize);
+            (len, &rest[octets..])
+        }
+        _ => bail!("Unsupported DER length"),
+    };
+    if rest.len() < len {
+        bail!("Truncated DER value");
+    }
+    Ok((tag, &rest[..len], &rest[len..]))
+}
+
+/// Check a `TimeStampResp` and read the time off its token
+fn parse_reply(reply: Vec<u8>, digest: &[u8; 32], nonce: &[u8; 8]) -> Result<TimestampToken> {
+    let (tag, response, _) = read_value(&reply)?;
+    if tag != TAG_SEQUENCE {
+        bail!("Timestamp reply is not a TimeStampResp");
+    }
+    let (_, status_info, token) = read_value(response)?;
+    let (tag, status, _) = read_value(status_info)?;
+    if tag != TAG_INTEGER || status.len() != 1 {
+        bail!("Timestamp reply has no status");
+    }
+    match status[0] {
+        0 | 1 => {}
+        2 => bail!("Timestamp authority rejected the request"),
+        3 => bail!("Timestamp authority asked to wait and try again"),
+        4 | 5 => bail!("Timestamp authority reported a revoked certificate"),
+        other => bail!("Timestamp authority returned status {}", other),
+    }
+    if token.is_empty() {
+        bail!("Timestamp reply has no token");
+    }
+    let contains = |needle: &[u8]| token.windows(needle.len()).any(|window| window == needle);
+    if !contains(&der(TAG_OCTET_STRING, digest)) {
+        bail!("Timestamp token is for another digest");
+    }
+    if !contains(&der(TAG_INTEGER, nonce)) {
+        bail!("Timestamp token does not answer this request");
+    }
+    let gen_time = generalized_time(token);
+    Ok(TimestampToken {
+        sha256: digest.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        }),
+        reply,
+        gen_time,
+    })
+}
+
+/// The first GeneralizedTime of a token, which is the `genTime` of its `TSTInfo`: the
+/// signed content comes before the certificates and signer information
+fn generalized_time(token: &[u8]) -> Option<DateTime<Utc>> {
+    token.windows(2).enumerate().find_map(|(at, header)| {
+        let len = header[1] as usize;
+        if header[0] != TAG_GENERALIZED_TIME || !(15..=23).contains(&len) {
+            return None;
+        }
+        let text = std::str::from_utf8(token.get(at + 2..at + 2 + len)?).ok()?;
+        let seconds = text.strip_suffix('Z')?.split('.').next()?;
+        NaiveDateTime::parse_from_str(seconds, "%Y%m%d%H%M%S")
+            .ok()
+            .map(|time| time.and_utc())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_and_reply() {
+        let digest: [u8; 32] = Sha256::digest(b"capture").into();
+        let nonce = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0];
+        let request = request(&digest, &nonce);
+        let expected_start = [
+            0x30, 0x43, 0x02, 0x01, 0x01, 0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48,
+            0x01, 0x65, 0x03, 0x04, 0x02, 0x01, 0x05, 0x00, 0x04, 0x20,
+        ];
+        assert_eq!(&request[..24], &expected_start);
+        assert!(request.ends_with(&[
+            0x02, 0x08, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0x01, 0x01, 0xff
+        ]));
+
+        // A granted reply whose token holds the TSTInfo fields the reply is checked for
+        let tst_info = der(
+            TAG_SEQUENCE,
+            &[
+                der(TAG_INTEGER, &[1]),
+                request[5..5 + 0x33].to_vec(),
+                der(TAG_GENERALIZED_TIME, b"20261016093000.25Z"),
+                der(TAG_INTEGER, &nonce),
+            ]
+            .concat(),
+        );
+        let token = der(TAG_SEQUENCE, &der(TAG_OCTET_STRING, &tst_info));
+        let status = der(TAG_SEQUENCE, &der(TAG_INTEGER, &[0]));
+        let reply = der(TAG_SEQUENCE, &[status, token.clone()].concat());
+        let granted = parse_reply(reply.clone(), &digest, &nonce).unwrap();
+        assert_eq!(granted.reply, reply);
+        assert_eq!(
+            granted.gen_time.unwrap().to_rfc3339(),
+            "2026-10-16T09:30:00+00:00"
+        );
+        assert_eq!(granted.sha256, format!("{:x}", Sha256::digest(b"capture")));
+
+        let other: [u8; 32] = Sha256::digest(b"other").into();
+        assert!(parse_reply(reply.clone(), &other, &nonce).is_err());
+        assert!(parse_reply(reply, &digest, &[1; 8]).is_err());
+        let rejected = der(TAG_SEQUENCE, &der(TAG_SEQUENCE, &der(TAG_INTEGER, &[2])));
+        assert!(parse_reply(rejected, &digest, &nonce).is_err());
+
+        assert_eq!(
+            reply_path(Path::new("out/page.pdf")),
+            PathBuf::from("out/page.pdf.tsr")
+        );
+    }
+}
@@ -0,0 +1,463 @@
+//! Machine translation of Markdown output
+//!
+//! A [`Translator`] writes a translated variant of a saved Markdown file next to the
+//! original, with the target language in its name (`article.md` becomes
+//! `article.de.md`). The document is translated block by block: YAML front matter,
+//! fenced code, and blocks without any letters are kept as they are, so code, metadata,
+//! and the document structure survive the translation.
+//!
+//! Two kinds of endpoints are supported: the DeepL API, and OpenAI-compatible
+//! `/chat/completions` endpoints, which are asked to translate a batch of blocks given
+//! as a JSON array.
+
+use crate::atomic;
+use anyhow::Result;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::info;
+
+/// Default DeepL API base URL (the free plan; the paid plan uses `https://api.deepl.com`)
+pub const DEFAULT_DEEPL_ENDPOINT: &str = "https://api-free.deepl.com";
+
+/// Default OpenAI-compatible API base URL
+pub const DEFAULT_OPENAI_ENDPOINT: &str = "https://api.openai.com/v1";
+
+/// Default chat model for OpenAI-compatible endpoints
+pub const DEFAULT_TRANSLATION_MODEL: &str = "gpt-4o-mini";
+
+/// Most texts DeepL accepts in one request
+const MAX_BATCH_TEXTS: usize = 50;
+
+/// Most characters sent in one request, well below the request size limits
+const MAX_BATCH_CHARS: usize = 20_000;
+
+/// A machine translation service
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TranslationService {
+    /// The DeepL API
+    #[default]
+    DeepL,
+    /// An OpenAI-compatible chat completions endpoint
+    OpenAi,
+}
+
+impl TranslationService {
+    /// The API base URL used unless another is given
+    pub fn default_endpoint(self) -> &'static str {
+        match self {
+            Self::DeepL => DEFAULT_DEEPL_ENDPOINT,
+            Self::OpenAi => DEFAULT_OPENAI_ENDPOINT,
+        }
+    }
+}
+
+/// Client translating Markdown documents into one language
+pub struct Translator {
+    client: Client,
+    service: TranslationService,
+    endpoint: String,
+    target_lang: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct DeepLResponse {
+    translations: Vec<DeepLTranslation>,
+}
+
+#[derive(Deserialize)]
+struct DeepLTranslation {
+    text: String,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatMessage {
+    content: Option<String>,
+}
+
+/// A run of lines of a Markdown document
+struct Block {
+    text: String,
+    translate: bool,
+}
+
+impl Translator {
+    /// Create a translator into `target_lang` (a language code such as `de` or
+    /// `pt-BR`) using the API at `endpoint`
+    ///
+    /// `endpoint` is the API base URL; `/v2/translate` (DeepL) or `/chat/completions`
+    /// (OpenAI-compatible) is appended unless it is already present.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the language code is empty or the HTTP client cannot be
+    /// created
+    pub fn new(service: TranslationService, endpoint: &str, target_lang: &str) -> Result<Self> {
+        let target_lang = target_lang.trim();
+        if target_lang.is_empty()
+            || !target_lang
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(anyhow::anyhow!("Invalid language code: {:?}", target_lang));
+        }
+
+        let client = Client::builder()
+            .timeout(Duration::from_secs(120))
+            .user_agent("webpage-save-translate/1.0")
+            .build()?;
+
+        let path = match service {
+            TranslationService::DeepL => "/v2/translate",
+            TranslationService::OpenAi => "/chat/completions",
+        };
+        let endpoint = endpoint.trim_end_matches('/');
+        let endpoint = if endpoint.ends_with(path) {
+            endpoint.to_string()
+        } else {
+            format!("{}{}", endpoint, path)
+        };
+
+        Ok(Self {
+            client,
+            service,
+            endpoint,
+            target_lang: target_lang.replace('_', "-"),
+            model: DEFAULT_TRANSLATION_MODEL.to_string(),
+            api_key: None,
+        })
+    }
+
+    /// Authenticate requests with an API key
+    pub fn with_api_key(mut self, api_key: Option<String>) -> Self {
+        self.api_key = api_key;
+        self
+    }
+
+    /// Use this chat model with OpenAI-compatible endpoints
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    /// Translate a Markdown document, keeping front matter and code as they are
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a request fails or a response cannot be used
+    pub async fn translate(&self, markdown: &str) -> Result<String> {
+        let mut blocks = split_blocks(markdown);
+        let pending: Vec<usize> = (0..blocks.len()).filter(|&i| blocks[i].translate).collect();
+
+        let mut start = 0;
+        while start < pending.len() {
+            let mut end = start;
+            let mut chars = 0;
+            while end < pending.len() && end - start < MAX_BATCH_TEXTS {
+                let len = blocks[pending[end]].text.len();
+                if end > start && chars + len > MAX_BATCH_CHARS {
+                    break;
+                }
+                chars += len;
+                end += 1;
+            }
+
+            let texts: Vec<&str> = pending[start..end]
+                .iter()
+                .map(|&i| blocks[i].text.as_str())
+                .collect();
+            let translated = self.translate_texts(&texts).await?;
+            if translated.len() != texts.len() {
+                return Err(anyhow::anyhow!(
+                    "The translation response has {} texts instead of {}",
+                    translated.len(),
+                    texts.len()
+                ));
+            }
+            for (&i, text) in pending[start..end].iter().zip(translated) {
+                blocks[i].text = text.trim_end().to_string();
+            }
+            start = end;
+        }
+
+        Ok(blocks
+            .into_iter()
+            .map(|block| block.text)
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+
+    /// Write the translation of a Markdown file next to it
+    ///
+    /// # Returns
+    ///
+    /// Returns the path of the translated file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or written, or translating it fails
+    pub async fn translate_file(&self, md_path: &Path) -> Result<PathBuf> {
+        let markdown = tokio::fs::read_to_string(md_path).await?;
+        let translated = self.translate(&markdown).await?;
+        let path = translated_path(md_path, &self.target_lang);
+        atomic::write(&path, translated).await?;
+        info!("Translated {} into {}", md_path.display(), self.target_lang);
+        Ok(path)
+    }
+
+    /// Translate a batch of texts, in order
+    async fn translate_texts(&self, texts: &[&str]) -> Result<Vec<String>> {
+        match self.service {
+            TranslationService::DeepL => {
+                let body = json!({
+                    "text": texts,
+                    "target_lang": self.target_lang.to_ascii_uppercase(),
+                    "preserve_formatting": true,
+                });
+                let mut request = self.client.post(&self.endpoint).json(&body);
+                if let Some(api_key) = &self.api_key {
+                    request =
+                        request.header("Authorization", format!("DeepL-Auth-Key {}", api_key));
+                }
+                let response = send(request).await?.json::<DeepLResponse>().await?;
+                Ok(response
+                    .translations
+                    .into_iter()
+                    .map(|translation| translation.text)
+                    .collect())
+            }
+            TranslationService::OpenAi => {
+                let prompt = format!(
+                    "Translate each Markdown string of the JSON array below into the language \
+                     with the code \"{}\". Keep the Markdown formatting, link targets, image \
+                     sources, and inline code unchanged. Reply with only a JSON array of the \
+                     translated strings, in the same order.",
+                    self.target_lang
+                );
+                let body = json!({
+                    "model": self.model,
+                    "messages": [
+                        { "role": "system", "content": prompt },
+                        { "role": "user", "content": serde_json::to_string(texts)? },
+                    ],
+                });
+                let mut request = self.client.post(&self.endpoint).json(&body);
+                if let Some(api_key) = &self.api_key {
+                    request = request.bearer_auth(api_key);
+                }
+                let response = send(request).await?.json::<ChatResponse>().await?;
+                let content = response
+                    .choices
+                    .into_iter()
+                    .find_map(|choice| choice.message.content)
+                    .unwrap_or_default();
+                // Models sometimes wrap the array in a code fence
+                let content = content.trim();
+                let content = content
+                    .strip_prefix("```json")
+                    .or_else(|| content.strip_prefix("```"))
+                    .and_then(|rest| rest.strip_suffix("```"))
+                    .unwrap_or(content);
+                serde_json::from_str(content.trim()).map_err(|e| {
+                    anyhow::anyhow!("The translation response is not a JSON array: {}", e)
+                })
+            }
+        }
+    }
+}
+
+/// Send a request, turning error statuses into errors
+async fn send(request: RequestBuilder) -> Result<reqwest::Response> {
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(anyhow::anyhow!(
+            "Translation request failed with status {}: {}",
+            status,
+            body.chars().take(500).collect::<String>()
+        ));
+    }
+    Ok(response)
+}
+
+/// The path of the translation of a Markdown file, e.g. `article.de.md` for
+/// `article.md`
+pub fn translated_path(md_path: &Path, target_lang: &str) -> PathBuf {
+    let extension = md_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "md".to_string());
+    md_path.with_extension(format!(
+        "{}.{}",
+        target_lang.to_ascii_lowercase(),
+        extension
+    ))
+}
+
+/// Split a document into blocks of lines that are joined again with newlines: front
+/// matter, fenced code, blank lines, and paragraphs, of which only paragraphs with
+/// letters are translated
+fn split_blocks(markdown: &str) -> Vec<Block> {
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    let flush = |paragraph: &mut Vec<&str>, blocks: &mut Vec<Block>| {
+        if !paragraph.is_empty() {
+            let text = paragraph.join("\n");
+            let translate = text.chars().any(char::is_alphabetic);
+            blocks.push(Block { text, translate });
+            paragraph.clear();
+        }
+    };
+    let keep = |lines: &[&str], blocks: &mut Vec<Block>| {
+        blocks.push(Block {
+            text: lines.join("\n"),
+            translate: false,
+        });
+    };
+
+    let lines: Vec<&str> = markdown.split('\n').collect();
+    let mut i = 0;
+    // YAML front matter between `---` lines, or TOML between `+++` lines
+    let fence = lines
+        .first()
+        .map(|line| line.trim_end())
+        .filter(|line| matches!(*line, "---" | "+++"));
+    if let Some(fence) = fence {
+        if let Some(end) = lines
+            .iter()
+            .skip(1)
+            .position(|line| line.trim_end() == fence)
+        {
+            keep(&lines[..end + 2], &mut blocks);
+            i = end + 2;
+        }
+    }
+    while i < lines.len() {
+        let line = lines[i];
+        let trimmed = line.trim_start();
+        let fence = ["```", "~~~"]
+            .into_iter()
+            .find(|fence| trimmed.starts_with(fence));
+        if let Some(fence) = fence {
+            flush(&mut paragraph, &mut blocks);
+            let end = lines[i + 1..]
+                .iter()
+                .position(|line| line.trim_start().starts_with(fence))
+                .map_or(lines.len(), |end| i + end + 2);
+            keep(&lines[i..end], &mut blocks);
+            i = end;
+        } else if line.trim().is_empty() {
+            flush(&mut paragraph, &mut blocks);
+            keep(&[line], &mut blocks);
+            i += 1;
+        } else {
+            paragraph.push(line);
+            i += 1;
+        }
+    }
+    flush(&mut paragraph, &mut blocks);
+    blocks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use tempfile::TempDir;
+
+    const MARKDOWN: &str = "---\ntitle: Hallo\n---\n\n# Guten Tag\n\nEin [Link](https://example.com)\nzweite Zeile\n\n```rust\nfn main() {}\n```\n\n---\n";
+
+    #[test]
+    fn test_split_blocks() {
+        let blocks = split_blocks(MARKDOWN);
+        let translated: Vec<&str> = blocks
+            .iter()
+            .filter(|block| block.translate)
+            .map(|block| block.text.as_str())
+            .collect();
+        assert_eq!(
+            translated,
+            vec![
+                "# Guten Tag",
+                "Ein [Link](https://example.com)\nzweite Zeile"
+            ]
+        );
+        let joined: Vec<&str> = blocks.iter().map(|block| block.text.as_str()).collect();
+        assert_eq!(joined.join("\n"), MARKDOWN);
+
+        assert_eq!(
+            translated_path(Path::new("out/article.md"), "pt-BR"),
+            PathBuf::from("out/article.pt-br.md")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translate_file_with_deepl() -> Result<()> {
+        // Minimal DeepL-compatible server answering a single request
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let endpoint = format!("http://{}", listener.local_addr()?);
+        let server = std::thread::spawn(move || -> std::io::Result<String> {
+            let (mut stream, _) = listener.accept()?;
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            loop {
+                let read = stream.read(&mut buffer)?;
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+                    let length = headers
+                        .lines()
+                        .find_map(|line| line.strip_prefix("content-length: "))
+                        .and_then(|length| length.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+            }
+
+            let body = r##"{"translations":[{"text":"# Good day"},{"text":"A [link](https://example.com)\nsecond line\n"}]}"##;
+            write!(
+                stream,
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )?;
+            Ok(String::from_utf8_lossy(&request).into_owned())
+        });
+
+        let temp_dir = TempDir::new()?;
+        let md_path = temp_dir.path().join("article.md");
+        std::fs::write(&md_path, MARKDOWN)?;
+        let translator = Translator::new(TranslationService::DeepL, &endpoint, "en")?
+            .with_api_key(Some("secret".to_string()));
+        let path = translator.translate_file(&md_path).await?;
+        assert_eq!(path, temp_dir.path().join("article.en.md"));
+        assert_eq!(
+            std::fs::read_to_string(&path)?,
+            "---\ntitle: Hallo\n---\n\n# Good day\n\nA [link](https://example.com)\nsecond line\n\n```rust\nfn main() {}\n```\n\n---\n"
+        );
+
+        let request = server.join().unwrap()?;
+        assert!(request.starts_with("POST /v2/translate"));
+        assert!(request.contains("authorization: DeepL-Auth-Key secret"));
+        assert!(request.contains(r#""target_lang":"EN""#));
+        Ok(())
+    }
+}
@@ -0,0 +1,406 @@
+//! Contact cards of local businesses and contact pages
+//!
+//! The places of a local search, and pages describing an organization or a person
+//! with schema.org items in JSON-LD, are turned into [`Contact`]s with their name,
+//! phone numbers, email addresses, postal address, and web site. These are written as
+//! vCard 3.0 (`.vcf`) files, which address books and phones import directly. Pages
+//! without structured data still give a contact if they link phone numbers or email
+//! addresses with `tel:` and `mailto:` links.
+
+use crate::events::{escape, fold};
+use crate::search::{LocalSearchResult, PostalAddress};
+use select::document::Document;
+use select::predicate::{Attr, Name};
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// A person or organization that can be contacted
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Contact {
+    /// The name of the business or person
+    pub name: String,
+    /// Whether the contact is a person rather than an organization
+    pub person: bool,
+    /// Phone numbers, as written on the page
+    pub telephones: Vec<String>,
+    /// Email addresses
+    pub emails: Vec<String>,
+    /// The postal address
+    pub address: Option<PostalAddress>,
+    /// The web site
+    pub url: Option<String>,
+    /// Latitude and longitude
+    pub coordinates: Option<[f64; 2]>,
+    /// A note, such as the description of a place
+    pub note: Option<String>,
+}
+
+impl Contact {
+    /// The contact of a local search result
+    pub fn from_place(place: &LocalSearchResult) -> Self {
+        let contact = place.contact.as_ref();
+        Self {
+            name: place.title.trim().to_string(),
+            person: false,
+            telephones: contact
+                .and_then(|contact| contact.telephone.clone())
+                .into_iter()
+                .collect(),
+            emails: contact
+                .and_then(|contact| contact.email.clone())
+                .into_iter()
+                .collect(),
+            address: place.postal_address.clone(),
+            url: place.url.clone().or(place.provider_url.clone()),
+            coordinates: place.coordinates,
+            note: place
+                .description
+                .as_deref()
+                .map(|description| mdka::from_html(description).trim().to_string())
+                .filter(|note| !note.is_empty()),
+        }
+    }
+
+    /// Render the contact as a vCard 3.0 with CRLF line endings
+    pub fn to_vcard(&self) -> String {
+        let mut lines = vec![
+            "BEGIN:VCARD".to_string(),
+            "VERSION:3.0".to_string(),
+            "PRODID:-//webpage-save//EN".to_string(),
+            format!("FN:{}", escape(&self.name)),
+        ];
+        if self.person {
+            // The last word is taken as the family name, as most pages write names
+            // in Western order
+            let (given, family) = self.name.rsplit_once(' ').unwrap_or(("", &self.name));
+            lines.push(format!("N:{};{};;;", escape(family), escape(given.trim())));
+        } else {
+            lines.push("N:;;;;".to_string());
+            lines.push(format!("ORG:{}", escape(&self.name)));
+            lines.push("X-ABShowAs:COMPANY".to_string());
+        }
+        for telephone in &self.telephones {
+            lines.push(format!("TEL;TYPE=WORK,VOICE:{}", escape(telephone)));
+        }
+        for email in &self.emails {
+            lines.push(format!("EMAIL;TYPE=INTERNET:{}", escape(email)));
+        }
+        if let Some(address) = &self.address {
+            let structured = [
+                &address.street_address,
+                &address.locality,
+                &address.region,
+                &address.postal_code,
+                &address.country,
+            ];
+            let parts: Vec<String> = match structured.iter().any(|part| part.is_some()) {
+                true => structured
+                    .iter()
+                    .map(|part| escape(part.as_deref().unwrap_or("").trim()))
+                    .collect(),
+                // An address on one line is kept whole as the street
+                false => vec![
+                    escape(address.display_address.as_deref().unwrap_or("").trim()),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ],
+            };
+            if parts.iter().any(|part| !part.is_empty()) {
+                lines.push(format!("ADR;TYPE=WORK:;;{}", parts.join(";")));
+            }
+        }
+        if let Some(url) = &self.url {
+            lines.push(format!("URL:{}", url));
+        }
+        if let Some([latitude, longitude]) = self.coordinates {
+            lines.push(format!("GEO:{:.6};{:.6}", latitude, longitude));
+        }
+        if let Some(note) = &self.note {
+            lines.push(format!("NOTE:{}", escape(note)));
+        }
+        lines.push("END:VCARD".to_string());
+        lines.iter().map(|line| fold(line)).collect()
+    }
+}
+
+/// Write contacts as one vCard file
+pub fn to_vcf(contacts: &[Contact]) -> String {
+    contacts.iter().map(Contact::to_vcard).collect()
+}
+
+/// The contacts described on a page, in page order
+///
+/// These are the schema.org items in JSON-LD with a name and a phone number or email
+/// address, their own or that of a `contactPoint`. A page without such items gives
+/// one contact named after the site if it has `tel:` or `mailto:` links. `page_url` is
+/// the web site of contacts that do not name their own.
+pub fn extract_contacts(html_content: &str, page_url: Option<&str>) -> Vec<Contact> {
+    let document = Document::from(html_content);
+    let mut contacts = Vec::new();
+    for script in document.find(Attr("type", "application/ld+json")) {
+        if let Ok(value) = serde_json::from_str::<Value>(&script.text()) {
+            collect_contacts(&value, page_url, &mut contacts);
+        }
+    }
+    if contacts.is_empty() {
+        contacts.extend(linked_contact(&document, page_url));
+    }
+    let mut seen = HashSet::new();
+    contacts.retain(|contact| seen.insert((contact.name.clone(), contact.telephones.clone())));
+    contacts
+}
+
+fn collect_contacts(value: &Value, page_url: Option<&str>, contacts: &mut Vec<Contact>) {
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                collect_contacts(value, page_url, contacts);
+            }
+        }
+        Value::Object(object) => {
+            let text = |key: &str| {
+                object
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty())
+            };
+            let mut telephones = Vec::new();
+            let mut emails = Vec::new();
+            collect_reachable(value, &mut telephones, &mut emails);
+            if let Some(points) = object.get("contactPoint") {
+                collect_reachable(points, &mut telephones, &mut emails);
+                if let Value::Array(points) = points {
+                    for point in points {
+                        collect_reachable(point, &mut telephones, &mut emails);
+                    }
+                }
+            }
+            if let Some(name) =
+                text("name").filter(|_| !(telephones.is_empty() && emails.is_empty()))
+            {
+                let person = match object.get("@type") {
+                    Some(Value::String(kind)) => kind.ends_with("Person"),
+                    Some(Value::Array(kinds)) => kinds
+                        .iter()
+                        .any(|kind| kind.as_str().is_some_and(|kind| kind.ends_with("Person"))),
+                    _ => false,
+                };
+                contacts.push(Contact {
+                    name: name.to_string(),
+                    person,
+                    telephones,
+                    emails,
+                    address: object.get("address").and_then(address),
+                    url: text("url").or(page_url).map(str::to_string),
+                    coordinates: object.get("geo").and_then(coordinates),
+                    note: text("description").map(str::to_string),
+                });
+            }
+            for (key, value) in object {
+                if !matches!(key.as_str(), "address" | "contactPoint" | "geo") {
+                    collect_contacts(value, page_url, contacts);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Add the `telephone` and `email` of a schema.org item, without duplicates
+fn collect_reachable(value: &Value, telephones: &mut Vec<String>, emails: &mut Vec<String>) {
+    for (key, found, prefix) in [
+        ("telephone", &mut *telephones, "tel:"),
+        ("email", &mut *emails, "mailto:"),
+    ] {
+        let values = match value.get(key) {
+            Some(Value::String(text)) => vec![text.as_str()],
+            Some(Value::Array(values)) => values.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        for text in values {
+            let text = text.trim();
+            let text = text.strip_prefix(prefix).unwrap_or(text).trim();
+            if !text.is_empty() && !found.iter().any(|known| known == text) {
+                found.push(text.to_string());
+            }
+        }
+    }
+}
+
+/// A schema.org `PostalAddress`, or an address as text
+fn address(value: &Value) -> Option<PostalAddress> {
+    match value {
+        Value::String(text) => Some(PostalAddress {
+            display_address: Some(text.trim().to_string()).filter(|text| !text.is_empty()),
+            ..Default::default()
+        }),
+        Value::Array(values) => values.iter().find_map(address),
+        Value::Object(object) => {
+            let field = |key: &str| {
+                object
+                    .get(key)
+                    .and_then(Value::as_str)
+                    .map(str::trim)
+                    .filter(|text| !text.is_empty())
+                    .map(str::to_string)
+            };
+            let country = match object.get("addressCountry") {
+                Some(Value::Object(country)) => country
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(|name| name.trim().to_string()),
+                _ => field("addressCountry"),
+            };
+            Some(PostalAddress {
+                display_address: None,
+                street_address: field("streetAddress"),
+                locality: field("addressLocality"),
+                region: field("addressRegion"),
+                postal_code: field("postalCode"),
+                country,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The latitude and longitude of a schema.org `GeoCoordinates`, given as numbers or text
+fn coordinates(value: &Value) -> Option<[f64; 2]> {
+    let number = |key: &str| match value.get(key)? {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    };
+    Some([number("latitude")?, number("longitude")?])
+}
+
+/// The contact of a page from its `tel:` and `mailto:` links, named after the site
+fn linked_contact(document: &Document, page_url: Option<&str>) -> Option<Contact> {
+    let mut telephones = Vec::new();
+    let mut emails = Vec::new();
+    for href in document
+        .find(Name("a"))
+        .filter_map(|link| link.attr("href"))
+    {
+        let (found, target) = match href.trim() {
+            href if href.starts_with("tel:") => (&mut telephones, &href[4..]),
+            href if href.starts_with("mailto:") => (&mut emails, &href[7..]),
+            _ => continue,
+        };
+        // Email links can carry a subject or body
+        let target = target
+            .split('?')
+            .next()
+            .unwrap_or(target)
+            .trim()
+            .to_string();
+        if !target.is_empty() && !found.contains(&target) {
+            found.push(target);
+        }
+    }
+    if telephones.is_empty() && emails.is_empty() {
+        return None;
+    }
+    let name = document
+        .find(Attr("property", "og:site_name"))
+        .find_map(|meta| meta.attr("content").map(str::to_string))
+        .or_else(|| {
+            document
+                .find(Name("title"))
+                .next()
+                .map(|title| title.text())
+        })
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            page_url
+                .and_then(|url| url::Url::parse(url).ok())
+                .and_then(|url| url.host_str().map(str::to_string))
+        })?;
+    Some(Contact {
+        name,
+        telephones,
+        emails,
+        url: page_url.map(str::to_string),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::PlaceContact;
+
+    #[test]
+    fn test_contacts_to_vcard() {
+        let html = r#"<html><head><script type="application/ld+json">{
+            "@context": "https://schema.org",
+            "@type": "Restaurant",
+            "name": "Cafe Rust, Tokyo",
+            "telephone": "+81 3-1234-5678",
+            "address": {
+                "@type": "PostalAddress",
+                "streetAddress": "1-1 Marunouchi",
+                "addressLocality": "Chiyoda",
+                "postalCode": "100-0005",
+                "addressCountry": {"@type": "Country", "name": "JP"}
+            },
+            "geo": {"@type": "GeoCoordinates", "latitude": "35.6812", "longitude": 139.7671},
+            "contactPoint": [{"@type": "ContactPoint", "email": "mailto:info@cafe.example"}],
+            "founder": {"@type": "Person", "name": "Ferris Crab", "email": "ferris@cafe.example"}
+        }</script></head><body></body></html>"#;
+
+        let contacts = extract_contacts(html, Some("https://cafe.example/"));
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].emails, vec!["info@cafe.example"]);
+        assert_eq!(contacts[0].coordinates, Some([35.6812, 139.7671]));
+        assert!(contacts[1].person);
+
+        let vcf = to_vcf(&contacts);
+        assert!(vcf.starts_with("BEGIN:VCARD\r\nVERSION:3.0\r\n"));
+        assert!(vcf.contains("\r\nFN:Cafe Rust\\, Tokyo\r\nN:;;;;\r\nORG:Cafe Rust\\, Tokyo\r\n"));
+        assert!(vcf.contains("\r\nTEL;TYPE=WORK,VOICE:+81 3-1234-5678\r\n"));
+        assert!(vcf.contains("\r\nADR;TYPE=WORK:;;1-1 Marunouchi;Chiyoda;;100-0005;JP\r\n"));
+        assert!(vcf.contains("\r\nURL:https://cafe.example/\r\n"));
+        assert!(vcf.contains("\r\nGEO:35.681200;139.767100\r\n"));
+        assert!(
+            vcf.contains("\r\nN:Crab;Ferris;;;\r\nEMAIL;TYPE=INTERNET:ferris@cafe.example\r\n")
+        );
+        assert_eq!(vcf.matches("END:VCARD\r\n").count(), 2);
+
+        let linked = extract_contacts(
+            r#"<title>Example Inc.</title><a href="tel:+1-555-0100">Call</a>
+               <a href="mailto:hello@example.com?subject=Hi">Mail</a><a href="/about">About</a>"#,
+            Some("https://example.com/contact"),
+        );
+        assert_eq!(linked.len(), 1);
+        assert_eq!(linked[0].name, "Example Inc.");
+        assert_eq!(linked[0].telephones, vec!["+1-555-0100"]);
+        assert_eq!(linked[0].emails, vec!["hello@example.com"]);
+        assert!(extract_contacts("<p>No contacts</p>", None).is_empty());
+
+        let place = LocalSearchResult {
+            title: "Cafe".to_string(),
+            url: None,
+            provider_url: Some("https://maps.example/cafe".to_string()),
+            description: Some("<p>Good coffee</p>".to_string()),
+            postal_address: Some(PostalAddress {
+                display_address: Some("1 Main St, Springfield".to_string()),
+                ..Default::default()
+            }),
+            coordinates: None,
+            contact: Some(PlaceContact {
+                telephone: Some("555-0100".to_string()),
+                email: None,
+            }),
+        };
+        let vcard = Contact::from_place(&place).to_vcard();
+        assert!(vcard.contains("\r\nTEL;TYPE=WORK,VOICE:555-0100\r\n"));
+        assert!(vcard.contains("\r\nADR;TYPE=WORK:;;1 Main St\\, Springfield;;;;\r\n"));
+        assert!(vcard.contains("\r\nURL:https://maps.example/cafe\r\n"));
+        assert!(vcard.contains("\r\nNOTE:Good coffee\r\n"));
+    }
+}
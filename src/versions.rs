@@ -0,0 +1,168 @@
+//! Versioned captures with a stable `latest` pointer
+//!
+//! In versioned mode each capture of a URL goes into its own timestamped directory
+//! under a per-URL directory, and a `latest` entry next to them always refers to the
+//! newest capture, so downstream tools can use a stable path:
+//!
+//! ```text
+//! out/example.com_blog/20250101T120000Z/example.com_blog.pdf
+//! out/example.com_blog/20250102T120000Z/example.com_blog.pdf
+//! out/example.com_blog/latest -> 20250102T120000Z
+//! ```
+//!
+//! On Unix `latest` is a relative symlink that is replaced atomically. Elsewhere it is
+//! a copy of the newest capture directory.
+
+use anyhow::Result;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// Name of the pointer to the newest capture in each per-URL directory
+pub const LATEST: &str = "latest";
+
+/// Format of capture directory names
+pub const VERSION_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// Create a new capture directory for a URL, returning its path
+///
+/// Captures taken within the same second get a numeric suffix.
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be created
+pub fn create_version_dir(url_dir: &Path, captured_at: DateTime<Utc>) -> Result<PathBuf> {
+    std::fs::create_dir_all(url_dir)?;
+    let base = captured_at.format(VERSION_FORMAT).to_string();
+    let mut name = base.clone();
+    let mut counter = 2;
+    loop {
+        let dir = url_dir.join(&name);
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                name = format!("{}_{}", base, counter);
+                counter += 1;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// Point the `latest` entry of a per-URL directory at one of its capture directories
+///
+/// # Errors
+///
+/// Returns an error if the capture directory is not inside a per-URL directory, or the
+/// pointer cannot be updated
+pub fn update_latest(version_dir: &Path) -> Result<PathBuf> {
+    let (Some(url_dir), Some(version)) = (version_dir.parent(), version_dir.file_name()) else {
+        return Err(anyhow::anyhow!(
+            "Invalid capture directory: {}",
+            version_dir.display()
+        ));
+    };
+    let latest = url_dir.join(LATEST);
+    point_latest(url_dir, Path::new(version), &latest)?;
+    Ok(latest)
+}
+
+/// The capture directories of a per-URL directory with their capture times, oldest first
+///
+/// # Errors
+///
+/// Returns an error if the directory cannot be read
+pub fn list_captures(url_dir: &Path) -> Result<Vec<(DateTime<Utc>, PathBuf)>> {
+    let mut captures = Vec::new();
+    for entry in std::fs::read_dir(url_dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        // Captures within the same second have a `_2`, `_3`, ... suffix
+        let (time, counter) = match name.split_once('_') {
+            Some((time, counter)) => (time, counter.parse::<u32>().ok()),
+            None => (name.as_str(), Some(1)),
+        };
+        let (Ok(time), Some(counter)) =
+            (NaiveDateTime::parse_from_str(time, VERSION_FORMAT), counter)
+        else {
+            continue;
+        };
+        if entry.file_type()?.is_dir() {
+            captures.push((time.and_utc(), counter, entry.path()));
+        }
+    }
+    captures.sort();
+    Ok(captures
+        .into_iter()
+        .map(|(time, _, path)| (time, path))
+        .collect())
+}
+
+#[cfg(unix)]
+fn point_latest(url_dir: &Path, version: &Path, latest: &Path) -> Result<()> {
+    // Create the new link under a temporary name and rename it over the old one
+    let temp = url_dir.join(format!(".{}.{}.tmp", LATEST, std::process::id()));
+    let _ = std::fs::remove_file(&temp);
+    std::os::unix::fs::symlink(version, &temp)?;
+    if let Err(e) = std::fs::rename(&temp, latest) {
+        let _ = std::fs::remove_file(&temp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn point_latest(url_dir: &Path, version: &Path, latest: &Path) -> Result<()> {
+    if latest.exists() {
+        std::fs::remove_dir_all(latest)?;
+    }
+    copy_dir(&url_dir.join(version), latest)
+}
+
+#[cfg(not(unix))]
+fn copy_dir(from: &Path, to: &Path) -> Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            std::fs::copy(entry.path(), target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_version_dirs_and_latest() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let url_dir = temp_dir.path().join("example.com");
+        let captured_at = Utc.with_ymd_and_hms(2025, 1, 2, 3, 4, 5).unwrap();
+
+        let first = create_version_dir(&url_dir, captured_at)?;
+        let second = create_version_dir(&url_dir, captured_at)?;
+        assert_eq!(first, url_dir.join("20250102T030405Z"));
+        assert_eq!(second, url_dir.join("20250102T030405Z_2"));
+
+        std::fs::write(first.join("page.md"), "first")?;
+        std::fs::write(second.join("page.md"), "second")?;
+
+        let latest = update_latest(&first)?;
+        assert_eq!(std::fs::read_to_string(latest.join("page.md"))?, "first");
+        update_latest(&second)?;
+        assert_eq!(std::fs::read_to_string(latest.join("page.md"))?, "second");
+
+        let captures = list_captures(&url_dir)?;
+        assert_eq!(
+            captures,
+            vec![(captured_at, first.clone()), (captured_at, second.clone())]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+//! Viewport sizes pages are rendered at
+//!
+//! Responsive pages lay out differently at different widths: a sidebar may collapse
+//! into a menu, images may switch sources, and scripts may render other layouts.
+//! Without a viewport, headless Chrome renders at its default window size. A viewport
+//! is given as `WIDTHxHEIGHT` in CSS pixels or as a preset name.
+
+use anyhow::Result;
+use headless_chrome::Tab;
+use headless_chrome::protocol::cdp::Emulation::SetDeviceMetricsOverride;
+use std::str::FromStr;
+
+/// Viewport presets: name, width, height, and whether it is a mobile device
+pub const PRESETS: &[(&str, u32, u32, bool)] = &[
+    ("desktop", 1920, 1080, false),
+    ("laptop", 1366, 768, false),
+    ("tablet", 768, 1024, true),
+    ("mobile", 390, 844, true),
+];
+
+/// The size of the window pages are rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Viewport {
+    /// Width in CSS pixels
+    pub width: u32,
+    /// Height in CSS pixels
+    pub height: u32,
+    /// Whether pages see a mobile device, with touch-sized layouts and the meta
+    /// viewport tag applied
+    pub mobile: bool,
+}
+
+impl Viewport {
+    /// A desktop viewport of the given size
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            mobile: false,
+        }
+    }
+
+    /// Resize the viewport of a tab, before it loads a page
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the DevTools call fails
+    pub fn apply(&self, tab: &Tab) -> Result<()> {
+        tab.call_method(SetDeviceMetricsOverride {
+            width: self.width,
+            height: self.height,
+            device_scale_factor: 1.0,
+            mobile: self.mobile,
+            scale: None,
+            screen_width: Some(self.width),
+            screen_height: Some(self.height),
+            position_x: None,
+            position_y: None,
+            dont_set_visible_size: None,
+            screen_orientation: None,
+            viewport: None,
+            display_feature: None,
+            device_posture: None,
+        })?;
+        Ok(())
+    }
+}
+
+impl FromStr for Viewport {
+    type Err = anyhow::Error;
+
+    fn from_str(source: &str) -> Result<Self> {
+        let source = source.trim().to_lowercase();
+        if let Some(&(_, width, height, mobile)) = PRESETS.iter().find(|(name, ..)| *name == source)
+        {
+            return Ok(Self {
+                width,
+                height,
+                mobile,
+            });
+        }
+
+        let invalid = || {
+            let presets: Vec<&str> = PRESETS.iter().map(|(name, ..)| *name).collect();
+            anyhow::anyhow!(
+                "Invalid viewport '{}': expected WIDTHxHEIGHT (e.g. 1920x1080) or one of {}",
+                source,
+                presets.join(", ")
+            )
+        };
+        let (width, height) = source.split_once('x').ok_or_else(invalid)?;
+        let (width, height) = match (width.trim().parse(), height.trim().parse()) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(invalid()),
+        };
+        Ok(Self::new(width, height))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_viewport() -> Result<()> {
+        assert_eq!("1920x1080".parse::<Viewport>()?, Viewport::new(1920, 1080));
+        assert_eq!(" 800X600 ".parse::<Viewport>()?, Viewport::new(800, 600));
+        assert_eq!(
+            "Mobile".parse::<Viewport>()?,
+            Viewport {
+                width: 390,
+                height: 844,
+                mobile: true
+            }
+        );
+        assert!("1920".parse::<Viewport>().is_err());
+        assert!("0x600".parse::<Viewport>().is_err());
+        assert!("watch".parse::<Viewport>().is_err());
+        Ok(())
+    }
+}
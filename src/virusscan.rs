@@ -0,0 +1,241 @@
+//! Virus scanning of saved files through an external command
+//!
+//! Files fetched from the web are scanned with a user-configured command, such as
+//! `clamdscan --no-summary` or `clamscan --no-summary`, before they are delivered or
+//! stored in the archive. The command is run once per file, with the file path in
+//! place of a `{}` argument or appended to the command. Following the convention of
+//! ClamAV, exit status 0 means clean, 1 means infected, and anything else is an error.
+//! Infected files are moved into a quarantine directory instead of being kept with the
+//! other output.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::warn;
+
+/// Name of the quarantine directory created in the output directory unless another
+/// is given
+pub const DEFAULT_QUARANTINE_DIR: &str = "quarantine";
+
+/// Exit status of the scan command for an infected file
+const INFECTED_STATUS: i32 = 1;
+
+/// A file moved into quarantine
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Quarantined {
+    /// Where the file was saved
+    pub file: PathBuf,
+    /// Where the file is now
+    pub quarantined: PathBuf,
+    /// What the scanner reported, e.g. `Eicar-Signature FOUND`
+    pub finding: String,
+}
+
+/// Result of scanning a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanVerdict {
+    /// Nothing was found
+    Clean,
+    /// The scanner found something, with its report
+    Infected(String),
+}
+
+/// A scan command run over saved files
+#[derive(Debug, Clone)]
+pub struct ScanHook {
+    program: String,
+    args: Vec<String>,
+    quarantine_dir: PathBuf,
+}
+
+impl ScanHook {
+    /// Create a hook from a command line, such as `clamdscan --no-summary {}`, which
+    /// quarantines into [`DEFAULT_QUARANTINE_DIR`] in the current directory
+    ///
+    /// Arguments are split on whitespace; wrap anything more involved in a script.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command line is empty
+    pub fn new(command: &str) -> Result<Self> {
+        let mut words = command.split_whitespace().map(str::to_string);
+        let program = words
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("The virus scan command is empty"))?;
+        Ok(Self {
+            program,
+            args: words.collect(),
+            quarantine_dir: PathBuf::from(DEFAULT_QUARANTINE_DIR),
+        })
+    }
+
+    /// Move infected files into this directory
+    pub fn with_quarantine_dir(mut self, dir: PathBuf) -> Self {
+        self.quarantine_dir = dir;
+        self
+    }
+
+    /// The directory infected files are moved into
+    pub fn quarantine_dir(&self) -> &Path {
+        &self.quarantine_dir
+    }
+
+    /// Scan a file
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command cannot be run or exits with a status other than
+    /// clean or infected
+    pub fn scan(&self, path: &Path) -> Result<ScanVerdict> {
+        let path_arg = path.as_os_str();
+        let mut command = Command::new(&self.program);
+        let mut placed = false;
+        for arg in &self.args {
+            match arg.as_str() {
+                "{}" => {
+                    command.arg(path_arg);
+                    placed = true;
+                }
+                _ => {
+                    command.arg(arg);
+                }
+            }
+        }
+        if !placed {
+            command.arg(path_arg);
+        }
+        let output = command
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| anyhow::anyhow!("Failed to run {}: {}", self.program, e))?;
+
+        match output.status.code() {
+            Some(0) => Ok(ScanVerdict::Clean),
+            Some(INFECTED_STATUS) => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                Ok(ScanVerdict::Infected(finding(path, &stdout, &stderr)))
+            }
+            _ => Err(anyhow::anyhow!(
+                "{} failed on {} ({}): {}",
+                self.program,
+                path.display(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )),
+        }
+    }
+
+    /// Scan a file and move it into quarantine if it is infected
+    ///
+    /// # Returns
+    ///
+    /// Returns the quarantined file, or None if the file is clean
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be scanned, or is infected but cannot be
+    /// moved
+    pub fn screen(&self, path: &Path) -> Result<Option<Quarantined>> {
+        let ScanVerdict::Infected(finding) = self.scan(path)? else {
+            return Ok(None);
+        };
+        warn!("{} is infected: {}", path.display(), finding);
+        let quarantined = self.quarantine(path)?;
+        Ok(Some(Quarantined {
+            file: path.to_path_buf(),
+            quarantined,
+            finding,
+        }))
+    }
+
+    /// Move a file into the quarantine directory under a name not taken yet
+    fn quarantine(&self, path: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(&self.quarantine_dir)?;
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow::anyhow!("{} is not a file", path.display()))?;
+        let mut target = self.quarantine_dir.join(name);
+        let mut counter = 1;
+        while target.exists() {
+            target = self
+                .quarantine_dir
+                .join(format!("{}.{}", name.to_string_lossy(), counter));
+            counter += 1;
+        }
+        // Renaming fails across file systems; copy and remove the original then
+        if std::fs::rename(path, &target).is_err() {
+            std::fs::copy(path, &target)?;
+            std::fs::remove_file(path)?;
+        }
+        Ok(target)
+    }
+}
+
+/// The scanner's report on a file: its first output line, without the file path
+/// ClamAV starts it with
+fn finding(path: &Path, stdout: &str, stderr: &str) -> String {
+    let line = stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("infected");
+    let prefix = format!("{}:", path.display());
+    line.strip_prefix(&prefix)
+        .unwrap_or(line)
+        .trim()
+        .to_string()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_screen_files() -> Result<()> {
+        let temp_dir = tempfile::TempDir::new()?;
+        // A scanner flagging files that contain the EICAR marker, like clamscan
+        let scanner = temp_dir.path().join("scan.sh");
+        std::fs::write(
+            &scanner,
+            "#!/bin/sh\n\
+             [ -f \"$2\" ] || exit 2\n\
+             grep -q EICAR \"$2\" || exit 0\n\
+             echo \"$2: Eicar-Signature FOUND\"\n\
+             exit 1\n",
+        )?;
+        std::fs::set_permissions(&scanner, std::fs::Permissions::from_mode(0o755))?;
+        let quarantine_dir = temp_dir.path().join("quarantine");
+        let hook = ScanHook::new(&format!("{} --no-summary {{}}", scanner.display()))?
+            .with_quarantine_dir(quarantine_dir.clone());
+
+        let clean = temp_dir.path().join("clean.pdf");
+        std::fs::write(&clean, "%PDF-1.4")?;
+        assert_eq!(hook.screen(&clean)?, None);
+        assert!(clean.exists());
+
+        let infected = temp_dir.path().join("page.pdf");
+        for _ in 0..2 {
+            std::fs::write(&infected, "X5O!P%@AP EICAR")?;
+            hook.screen(&infected)?;
+        }
+        assert!(!infected.exists());
+        assert!(quarantine_dir.join("page.pdf").exists());
+        std::fs::write(&infected, "X5O!P%@AP EICAR")?;
+        assert_eq!(
+            hook.screen(&infected)?,
+            Some(Quarantined {
+                file: infected.clone(),
+                quarantined: quarantine_dir.join("page.pdf.2"),
+                finding: "Eicar-Signature FOUND".to_string(),
+            })
+        );
+
+        assert!(hook.scan(&temp_dir.path().join("missing.pdf")).is_err());
+        assert!(ScanHook::new("  ").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+//! Page readiness strategies
+//!
+//! A fixed sleep after the load event is too long for static pages and too short for
+//! heavy single-page applications. A [`WaitStrategy`] instead waits until an element
+//! the page builds is present, or until the page has stopped loading resources, up to
+//! a timeout. The PDF generator and the Markdown generator's browser fetches share it.
+
+use anyhow::Result;
+use headless_chrome::Tab;
+use headless_chrome::browser::tab::EventListener;
+use headless_chrome::protocol::cdp::Network;
+use headless_chrome::protocol::cdp::Network::ResourceType;
+use headless_chrome::protocol::cdp::types::Event;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Default longest wait for a selector or for the network to go idle
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long no request may be in flight for the network to count as idle
+pub const NETWORK_IDLE_TIME: Duration = Duration::from_millis(500);
+
+/// Interval between checks of the page
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// When a loaded page is ready to be captured
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitStrategy {
+    /// After a fixed delay; a zero delay captures the page at its load event
+    Delay(Duration),
+    /// Once an element matches a CSS selector, or the timeout has passed
+    Selector {
+        /// CSS selector of an element the page shows when ready, e.g. `#content`
+        selector: String,
+        /// Longest wait
+        timeout: Duration,
+    },
+    /// Once no request has been in flight for [`NETWORK_IDLE_TIME`], or the timeout
+    /// has passed
+    NetworkIdle {
+        /// Longest wait
+        timeout: Duration,
+    },
+}
+
+impl WaitStrategy {
+    /// Wait until an element matching `selector` is present
+    pub fn selector(selector: &str, timeout: Duration) -> Self {
+        Self::Selector {
+            selector: selector.to_string(),
+            timeout,
+        }
+    }
+
+    /// Wait until the network is idle
+    pub fn network_idle(timeout: Duration) -> Self {
+        Self::NetworkIdle { timeout }
+    }
+
+    /// Start watching a tab, before it loads a page
+    ///
+    /// Requests are counted from here, so the network is only known to be idle when
+    /// the tab was watched before it started loading.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the DevTools calls fail
+    pub fn watch(&self, tab: &Arc<Tab>) -> Result<PageWatch> {
+        let mut watch = PageWatch {
+            strategy: self.clone(),
+            tab: tab.clone(),
+            activity: Arc::new(Mutex::new(NetworkActivity::new())),
+            listener: None,
+        };
+        if let Self::NetworkIdle { .. } = self {
+            tab.call_method(Network::Enable {
+                max_total_buffer_size: None,
+                max_resource_buffer_size: None,
+                max_post_data_size: None,
+            })?;
+            let activity = watch.activity.clone();
+            watch.listener = Some(tab.add_event_listener(Arc::new(move |event: &Event| {
+                let Ok(mut activity) = activity.lock() else {
+                    return;
+                };
+                match event {
+                    Event::NetworkRequestWillBeSent(event) => activity.started(
+                        &event.params.request_id,
+                        event.params.Type.as_ref(),
+                        Instant::now(),
+                    ),
+                    Event::NetworkLoadingFinished(event) => {
+                        activity.finished(&event.params.request_id, Instant::now())
+                    }
+                    Event::NetworkLoadingFailed(event) => {
+                        activity.finished(&event.params.request_id, Instant::now())
+                    }
+                    _ => {}
+                }
+            }))?);
+        }
+        Ok(watch)
+    }
+}
+
+/// A tab watched for readiness, from before it loads a page until it is ready
+pub struct PageWatch {
+    strategy: WaitStrategy,
+    tab: Arc<Tab>,
+    activity: Arc<Mutex<NetworkActivity>>,
+    listener: Option<Weak<dyn EventListener<Event> + Send + Sync>>,
+}
+
+impl PageWatch {
+    /// Wait until the loaded page is ready, returning whether it was before the
+    /// timeout
+    ///
+    /// A page that is not ready in time is still captured, as it is then, with a
+    /// warning.
+    pub async fn wait(&self) -> bool {
+        let (timeout, what) = match &self.strategy {
+            WaitStrategy::Delay(delay) => {
+                if !delay.is_zero() {
+                    tokio::time::sleep(*delay).await;
+                }
+                return true;
+            }
+            WaitStrategy::Selector { selector, timeout } => (*timeout, format!("'{}'", selector)),
+            WaitStrategy::NetworkIdle { timeout } => (*timeout, "network idle".to_string()),
+        };
+        let started = Instant::now();
+        while started.elapsed() < timeout {
+            if self.is_ready() {
+                return true;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+        let ready = self.is_ready();
+        if !ready {
+            let url = self.tab.get_url();
+            warn!(
+                "Timed out after {}s waiting for {} on {}; capturing the page as it is",
+                timeout.as_secs(),
+                what,
+                url
+            );
+        }
+        ready
+    }
+
+    fn is_ready(&self) -> bool {
+        match &self.strategy {
+            WaitStrategy::Delay(_) => true,
+            WaitStrategy::Selector { selector, .. } => {
+                let expression = format!(
+                    "document.querySelector({}) !== null",
+                    serde_json::to_string(selector).unwrap_or_default()
+                );
+                self.tab
+                    .evaluate(&expression, false)
+                    .ok()
+                    .and_then(|result| result.value)
+                    .and_then(|value| value.as_bool())
+                    .unwrap_or(false)
+            }
+            WaitStrategy::NetworkIdle { .. } => self
+                .activity
+                .lock()
+                .is_ok_and(|activity| activity.is_idle(Instant::now())),
+        }
+    }
+}
+
+impl Drop for PageWatch {
+    fn drop(&mut self) {
+        if let Some(listener) = &self.listener {
+            let _ = self.tab.remove_event_listener(listener);
+        }
+    }
+}
+
+/// Requests in flight in a tab, and when that last changed
+#[derive(Debug)]
+struct NetworkActivity {
+    in_flight: HashSet<String>,
+    changed_at: Instant,
+}
+
+impl NetworkActivity {
+    fn new() -> Self {
+        Self {
+            in_flight: HashSet::new(),
+            changed_at: Instant::now(),
+        }
+    }
+
+    /// A request was sent; redirects keep the ID of the request they follow
+    fn started(&mut self, id: &str, resource_type: Option<&ResourceType>, now: Instant) {
+        // WebSockets and event streams stay open as long as the page does
+        if matches!(
+            resource_type,
+            Some(ResourceType::WebSocket | ResourceType::EventSource)
+        ) {
+            return;
+        }
+        self.in_flight.insert(id.to_string());
+        self.changed_at = now;
+    }
+
+    /// A request finished loading or failed
+    fn finished(&mut self, id: &str, now: Instant) {
+        if self.in_flight.remove(id) {
+            self.changed_at = now;
+        }
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        self.in_flight.is_empty() && now.duration_since(self.changed_at) >= NETWORK_IDLE_TIME
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_network_activity() {
+        let start = Instant::now();
+        let mut activity = NetworkActivity::new();
+        activity.changed_at = start;
+        assert!(!activity.is_idle(start));
+        assert!(activity.is_idle(start + NETWORK_IDLE_TIME));
+
+        activity.started("1", Some(&ResourceType::Document), start);
+        activity.started("2", None, start);
+        activity.started("3", Some(&ResourceType::WebSocket), start);
+        activity.finished("1", start + Duration::from_millis(100));
+        assert!(!activity.is_idle(start + Duration::from_secs(5)));
+
+        let last = start + Duration::from_millis(300);
+        activity.finished("2", last);
+        activity.finished("2", start + Duration::from_secs(1));
+        assert!(!activity.is_idle(last + Duration::from_millis(499)));
+        assert!(activity.is_idle(last + NETWORK_IDLE_TIME));
+
+        assert_eq!(
+            WaitStrategy::selector("#content", DEFAULT_WAIT_TIMEOUT),
+            WaitStrategy::Selector {
+                selector: "#content".to_string(),
+                timeout: DEFAULT_WAIT_TIMEOUT,
+            }
+        );
+    }
+}
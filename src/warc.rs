@@ -0,0 +1,335 @@
+//! WARC files of captures
+//!
+//! For digital preservation, the HTTP exchanges of a capture (the page and every
+//! stylesheet, script, image, font, and XHR Chrome fetched to render it) can be saved
+//! as a WARC file (ISO 28500, version 1.1), the format web archives store and replay
+//! pages from. The exchanges are recorded from the DevTools Fetch domain by an
+//! [`HttpRecorder`](crate::recording::HttpRecorder), and each is written as a `response`
+//! record with the `request` record it answered, after a `warcinfo` record describing
+//! the file. Files named `.gz` are compressed record by record, as the format
+//! recommends, so readers can seek to any record.
+//!
+//! Chrome hands over response bodies decoded, so the HTTP headers of the response
+//! records leave out `Content-Encoding` and `Transfer-Encoding`, and give the decoded
+//! length.
+
+use crate::atomic;
+use crate::recording::RecordedResponse;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::Path;
+
+/// WARC version written in every record
+pub const WARC_VERSION: &str = "WARC/1.1";
+
+/// Response headers describing the body on the wire, replaced or left out
+const WIRE_HEADERS: &[&str] = &["content-encoding", "content-length", "transfer-encoding"];
+
+/// Builds a WARC file in memory, record by record
+pub struct WarcWriter {
+    data: Vec<u8>,
+    gzip: bool,
+    file_name: String,
+    warcinfo_id: String,
+    records: u64,
+}
+
+impl WarcWriter {
+    /// Start a WARC file with its `warcinfo` record, compressed if `gzip` is set
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if compressing the record fails
+    pub fn new(file_name: &str, gzip: bool) -> Result<Self> {
+        let mut writer = Self {
+            data: Vec::new(),
+            gzip,
+            file_name: file_name.to_string(),
+            warcinfo_id: String::new(),
+            records: 0,
+        };
+        writer.warcinfo_id = writer.record_id();
+        let fields = format!(
+            "software: webpage-save/{}\r\nformat: WARC File Format 1.1\r\n\
+             conformsTo: https://iipc.github.io/warc-specifications/specifications/warc-format/warc-1.1/\r\n",
+            env!("CARGO_PKG_VERSION")
+        );
+        let headers = vec![
+            ("WARC-Type", "warcinfo".to_string()),
+            ("WARC-Record-ID", writer.warcinfo_id.clone()),
+            ("WARC-Date", warc_date(Utc::now())),
+            ("WARC-Filename", header_value(file_name)),
+            ("Content-Type", "application/warc-fields".to_string()),
+        ];
+        writer.write_record(headers, fields.as_bytes())?;
+        Ok(writer)
+    }
+
+    /// Add a recorded HTTP exchange as a `response` record and its `request` record
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the exchange was added; responses to URLs other than HTTP(S),
+    /// such as `data:` URLs, are skipped
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the recorded body is not valid base64, or compressing a
+    /// record fails
+    pub fn add_exchange(&mut self, response: &RecordedResponse) -> Result<bool> {
+        let Ok(url) = url::Url::parse(&response.url) else {
+            return Ok(false);
+        };
+        if !matches!(url.scheme(), "http" | "https") {
+            return Ok(false);
+        }
+        let body = response.body_bytes()?;
+        let date = warc_date(response.received_at.unwrap_or_else(Utc::now));
+        let target = header_value(url.as_str());
+
+        let mut block = format!(
+            "HTTP/1.1 {} {}\r\n",
+            response.status,
+            reqwest::StatusCode::from_u16(response.status as u16)
+                .ok()
+                .and_then(|status| status.canonical_reason())
+                .unwrap_or("")
+        );
+        for (name, value) in &response.headers {
+            if !WIRE_HEADERS.contains(&name.to_lowercase().as_str()) {
+                block.push_str(&format!("{}: {}\r\n", name, header_value(value)));
+            }
+        }
+        block.push_str(&format!("Content-Length: {}\r\n\r\n", body.len()));
+        let mut block = block.into_bytes();
+        block.extend_from_slice(&body);
+
+        let response_id = self.record_id();
+        self.write_record(
+            vec![
+                ("WARC-Type", "response".to_string()),
+                ("WARC-Record-ID", response_id.clone()),
+                ("WARC-Date", date.clone()),
+                ("WARC-Target-URI", target.clone()),
+                ("WARC-Warcinfo-ID", self.warcinfo_id.clone()),
+                ("WARC-Payload-Digest", sha1_digest(&body)),
+                (
+                    "Content-Type",
+                    "application/http;msgtype=response".to_string(),
+                ),
+            ],
+            &block,
+        )?;
+
+        let path = match url.query() {
+            Some(query) => format!("{}?{}", url.path(), query),
+            None => url.path().to_string(),
+        };
+        let mut request = format!("{} {} HTTP/1.1\r\n", response.method, path);
+        if !response
+            .request_headers
+            .iter()
+            .any(|(name, _)| name.eq_ignore_ascii_case("host"))
+        {
+            let host = url.host_str().unwrap_or_default();
+            match url.port() {
+                Some(port) => request.push_str(&format!("Host: {}:{}\r\n", host, port)),
+                None => request.push_str(&format!("Host: {}\r\n", host)),
+            }
+        }
+        for (name, value) in &response.request_headers {
+            request.push_str(&format!("{}: {}\r\n", name, header_value(value)));
+        }
+        request.push_str("\r\n");
+        let request_id = self.record_id();
+        self.write_record(
+            vec![
+                ("WARC-Type", "request".to_string()),
+                ("WARC-Record-ID", request_id),
+                ("WARC-Date", date),
+                ("WARC-Target-URI", target),
+                ("WARC-Warcinfo-ID", self.warcinfo_id.clone()),
+                ("WARC-Concurrent-To", response_id),
+                (
+                    "Content-Type",
+                    "application/http;msgtype=request".to_string(),
+                ),
+            ],
+            request.as_bytes(),
+        )?;
+        Ok(true)
+    }
+
+    /// The WARC file
+    pub fn finish(self) -> Vec<u8> {
+        self.data
+    }
+
+    /// Append a record with its block digest and length
+    fn write_record(&mut self, headers: Vec<(&str, String)>, block: &[u8]) -> Result<()> {
+        let mut record = format!("{}\r\n", WARC_VERSION);
+        for (name, value) in headers {
+            record.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        record.push_str(&format!(
+            "WARC-Block-Digest: {}\r\nContent-Length: {}\r\n\r\n",
+            sha1_digest(block),
+            block.len()
+        ));
+        let mut record = record.into_bytes();
+        record.extend_from_slice(block);
+        record.extend_from_slice(b"\r\n\r\n");
+        match self.gzip {
+            true => {
+                let mut encoder = GzEncoder::new(&mut self.data, Compression::default());
+                encoder.write_all(&record)?;
+                encoder.finish()?;
+            }
+            false => self.data.extend_from_slice(&record),
+        }
+        self.records += 1;
+        Ok(())
+    }
+
+    /// A new record ID, a UUID URN in angle brackets
+    fn record_id(&self) -> String {
+        let nanos = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+        let seed = format!(
+            "{}\n{}\n{}\n{}",
+            self.file_name,
+            self.records,
+            nanos,
+            std::process::id()
+        );
+        format!("<{}>", uuid_urn(&seed))
+    }
+}
+
+/// A random-looking (version 4) UUID URN derived from a seed, e.g.
+/// `urn:uuid:0f5c4d2e-8c1b-4a4e-9b1d-3e6f2a7c9d10`
+pub(crate) fn uuid_urn(seed: &str) -> String {
+    let mut bytes: [u8; 16] = Sha256::digest(seed.as_bytes())[..16]
+        .try_into()
+        .expect("SHA-256 digests are longer than 16 bytes");
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = format!("{:032x}", u128::from_be_bytes(bytes));
+    format!(
+        "urn:uuid:{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// Write recorded HTTP exchanges as a WARC file, compressed if its name ends in `.gz`
+///
+/// # Returns
+///
+/// Returns the number of exchanges written
+///
+/// # Errors
+///
+/// Returns an error if a recorded body is invalid, or the file cannot be written
+pub async fn write_warc(path: &Path, responses: &[RecordedResponse]) -> Result<usize> {
+    let file_name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "capture.warc".to_string());
+    let gzip = file_name.to_lowercase().ends_with(".gz");
+    let mut writer = WarcWriter::new(&file_name, gzip)?;
+    let mut written = 0;
+    for response in responses {
+        if writer.add_exchange(response)? {
+            written += 1;
+        }
+    }
+    atomic::write(path, writer.finish()).await?;
+    Ok(written)
+}
+
+/// A WARC date, in UTC to the second
+fn warc_date(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
+
+/// A header value on one line
+fn header_value(value: &str) -> String {
+    value.replace(['\r', '\n'], " ")
+}
+
+/// A SHA-1 digest as WARC labels it, e.g. `sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ`
+fn sha1_digest(data: &[u8]) -> String {
+    format!("sha1:{}", base32(&Sha1::digest(data)))
+}
+
+/// RFC 4648 base32, without padding
+fn base32(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+    let mut encoded = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        buffer = (buffer << 8) | u32::from(byte);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            encoded.push(char::from(ALPHABET[((buffer >> bits) & 31) as usize]));
+        }
+    }
+    if bits > 0 {
+        encoded.push(char::from(ALPHABET[((buffer << (5 - bits)) & 31) as usize]));
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[tokio::test]
+    async fn test_write_warc() -> Result<()> {
+        assert_eq!(sha1_digest(b""), "sha1:3I42H3S6NNFQ2MSVX7XZKYAYSCX5QBYJ");
+
+        let page = RecordedResponse::new(
+            "GET",
+            "https://example.com/a?b=1",
+            200,
+            vec![
+                ("Content-Type".to_string(), "text/html".to_string()),
+                ("Content-Encoding".to_string(), "br".to_string()),
+            ],
+            b"<p>Hello</p>",
+        )
+        .with_request_headers(vec![("Accept".to_string(), "text/html".to_string())]);
+        let inline = RecordedResponse::new("GET", "data:image/png;base64,AA==", 200, vec![], b"");
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("capture.warc.gz");
+        assert_eq!(write_warc(&path, &[page, inline]).await?, 1);
+
+        // Every record is a gzip member of its own
+        let mut warc = String::new();
+        flate2::read::MultiGzDecoder::new(std::fs::File::open(&path)?).read_to_string(&mut warc)?;
+        assert_eq!(warc.matches("WARC/1.1\r\n").count(), 3);
+        assert!(warc.starts_with("WARC/1.1\r\nWARC-Type: warcinfo\r\n"));
+        assert!(warc.contains("WARC-Target-URI: https://example.com/a?b=1\r\n"));
+        assert!(warc.contains(
+            "\r\n\r\nHTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: 12\r\n\r\n<p>Hello</p>\r\n\r\n"
+        ));
+        assert!(!warc.contains("Content-Encoding"));
+        assert!(warc.contains(
+            "\r\n\r\nGET /a?b=1 HTTP/1.1\r\nHost: example.com\r\nAccept: text/html\r\n\r\n"
+        ));
+        assert!(!warc.contains("data:image"));
+        Ok(())
+    }
+}
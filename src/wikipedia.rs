@@ -0,0 +1,713 @@
+//! Wikipedia article export
+//!
+//! Printed Wikipedia pages carry the site chrome, collapsed sections, and footnote
+//! markers that point nowhere once the page is a PDF. Article URLs are instead read
+//! through the wiki's REST API, which returns the article body as clean HTML, and the
+//! article is rendered as a document of its own:
+//!
+//! - **Footnotes** link to their references within the document, and become Markdown
+//!   footnotes in Markdown output.
+//! - **Infoboxes** and other tables are kept, styled as on the site.
+//! - **Images** are credited in an image credits section, with the author and license
+//!   of every file from the wiki's file descriptions.
+//! - **"See also"** links are listed, so the articles they point to can be saved too.
+//!
+//! Navigation boxes, maintenance notices, and hatnotes are left out.
+
+use crate::extract::{self, Selector};
+use crate::readlater::{checked, http_client, send_with_retry};
+use anyhow::Result;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use reqwest::Client;
+use select::document::Document;
+use select::node::Node;
+use select::predicate::{Attr, Name};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+use tera::{Context, Tera};
+use tracing::{info, warn};
+use url::Url;
+
+/// Most files described per image credits request
+const CREDITS_BATCH_SIZE: usize = 50;
+
+/// Ids of the "See also" heading in the larger Wikipedias
+const SEE_ALSO_IDS: [&str; 8] = [
+    "See_also",
+    "Siehe_auch",
+    "Voir_aussi",
+    "Véase_también",
+    "Vedi_anche",
+    "Zie_ook",
+    "Veja_também",
+    "関連項目",
+];
+
+/// Namespaces of links that are not articles
+const NON_ARTICLE_PREFIXES: [&str; 8] = [
+    "File:",
+    "Image:",
+    "Category:",
+    "Portal:",
+    "Help:",
+    "Wikipedia:",
+    "Template:",
+    "Special:",
+];
+
+static SKIPPED: LazyLock<Vec<Selector>> = LazyLock::new(|| {
+    Selector::parse_list(
+        "script, meta, link, .noprint, .navbox, .navbox-styles, .vertical-navbox, .metadata, \
+         .ambox, .hatnote, .sistersitebox, .portalbox, .mw-empty-elt, .mw-editsection",
+    )
+    .expect("built-in selectors are valid")
+});
+
+static LINK_ATTR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\s(href|src|srcset|data-mw|about|typeof|resource)="([^"]*)""#)
+        .expect("valid regex")
+});
+
+static FOOTNOTE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r##"(?s)<sup[^>]*class="[^"]*\b(?:mw-ref|reference)\b[^"]*"[^>]*>.*?href="#(cite_note-[^"]*)".*?</sup>"##)
+        .expect("valid regex")
+});
+
+static REFERENCE_LIST: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?s)<ol[^>]*class="[^"]*\breferences\b[^"]*"[^>]*>.*?</ol>"#)
+        .expect("valid regex")
+});
+
+static STYLE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<style[^>]*>.*?</style>").expect("valid regex"));
+
+static IMAGE_LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<a [^>]*>\s*(<img [^>]*>)\s*</a>").expect("valid regex"));
+
+static FOOTNOTE_MARK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"WPFOOTNOTE(\d+)END").expect("valid regex"));
+
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]*>").expect("valid regex"));
+
+const ARTICLE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>{{ title }}</title>
+<style>
+  body { margin: 0 auto; max-width: 46em; font-family: "Linux Libertine", Georgia, "Noto Serif", serif; line-height: 1.5; color: #111; }
+  h1, h2 { font-weight: normal; border-bottom: 1px solid #aaa; }
+  h2, h3, h4 { font-family: system-ui, -apple-system, "Segoe UI", "Noto Sans", sans-serif; }
+  .source { color: #666; font-size: 0.85em; word-break: break-all; font-family: system-ui, sans-serif; }
+  a { color: #0645ad; text-decoration: none; }
+  table.infobox { float: right; clear: right; width: 22em; margin: 0 0 1em 1em; border: 1px solid #a2a9b1; background: #f8f9fa; font-size: 0.85em; border-collapse: collapse; }
+  table.infobox th, table.infobox td { padding: 0.2em 0.4em; vertical-align: top; text-align: left; }
+  table.infobox caption { font-weight: bold; font-size: 1.2em; }
+  table.wikitable { border-collapse: collapse; background: #f8f9fa; }
+  table.wikitable th, table.wikitable td { border: 1px solid #a2a9b1; padding: 0.2em 0.4em; }
+  figure { margin: 0.5em 0 1em 1em; float: right; clear: right; max-width: 22em; border: 1px solid #c8ccd1; padding: 3px; background: #f8f9fa; page-break-inside: avoid; }
+  figcaption { font-size: 0.85em; }
+  img { max-width: 100%; height: auto; }
+  h2 { clear: both; }
+  sup { line-height: 0; }
+  ol.references { font-size: 0.9em; }
+  .credits { clear: both; font-size: 0.85em; }
+</style>
+</head>
+<body>
+<h1>{{ title }}</h1>
+<p class="source"><a href="{{ url }}">{{ url }}</a></p>
+<div class="mw-parser-output">
+{{ content | safe }}
+</div>
+{% if credits %}<section class="credits">
+<h2>Image credits</h2>
+<ul>
+{% for credit in credits %}<li><a href="{{ credit.url }}">{{ credit.file }}</a>{% if credit.artist %} by {{ credit.artist }}{% endif %}{% if credit.license %}, {% if credit.license_url %}<a href="{{ credit.license_url }}">{{ credit.license }}</a>{% else %}{{ credit.license }}{% endif %}{% endif %}</li>
+{% endfor %}</ul>
+</section>
+{% endif %}</body>
+</html>
+"#;
+
+/// A Wikipedia article URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikipediaUrl {
+    /// Host of the wiki, such as `en.wikipedia.org`
+    pub host: String,
+    /// Title of the article, with underscores for spaces
+    pub title: String,
+    /// Revision of an old version of the article
+    pub revision: Option<u64>,
+}
+
+impl WikipediaUrl {
+    /// Parse a `/wiki/Title` or `/w/index.php?title=Title` URL of a Wikipedia, or None
+    /// if the URL is not an article
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?.to_ascii_lowercase();
+        let (language, rest) = host.split_once('.')?;
+        let rest = rest.strip_prefix("m.").unwrap_or(rest);
+        if rest != "wikipedia.org" || language == "www" {
+            return None;
+        }
+        let query: HashMap<_, _> = url.query_pairs().collect();
+        let title = match url.path().strip_prefix("/wiki/") {
+            Some(title) => percent_decode_str(title).decode_utf8_lossy().into_owned(),
+            None if url.path() == "/w/index.php" => query.get("title")?.to_string(),
+            None => return None,
+        };
+        let title = title.replace(' ', "_");
+        if title.is_empty() || NON_ARTICLE_PREFIXES.iter().any(|p| title.starts_with(p)) {
+            return None;
+        }
+        Some(Self {
+            host: format!("{}.wikipedia.org", language),
+            title,
+            revision: query.get("oldid").and_then(|id| id.parse().ok()),
+        })
+    }
+
+    /// URL of the article on the site
+    pub fn page_url(&self) -> String {
+        title_url(&self.host, "/wiki/", &self.title)
+    }
+
+    /// URL of the article body in the REST API
+    fn html_url(&self) -> String {
+        let mut url = title_url(&self.host, "/api/rest_v1/page/html/", &self.title);
+        if let Some(revision) = self.revision {
+            url.push_str(&format!("/{}", revision));
+        }
+        url
+    }
+}
+
+/// The author and license of an image in an article
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImageCredit {
+    /// Title of the file, such as `File:Example.jpg`
+    pub file: String,
+    /// URL of the file's description page
+    pub url: String,
+    /// Author of the image, as plain text
+    pub artist: Option<String>,
+    /// Short name of the license, such as `CC BY-SA 4.0`
+    pub license: Option<String>,
+    /// URL of the license
+    pub license_url: Option<String>,
+}
+
+/// An exported Wikipedia article
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WikipediaArticle {
+    /// The URL the article was exported from
+    pub url: String,
+    /// Title of the article
+    pub title: String,
+    /// The article body as HTML, with links made absolute and footnotes linked within it
+    pub content: String,
+    /// The references of the article, as note ids and HTML, in order
+    pub references: Vec<(String, String)>,
+    /// Credits of the images in the article
+    pub credits: Vec<ImageCredit>,
+    /// URLs of the articles in the "See also" section
+    pub see_also: Vec<String>,
+}
+
+impl WikipediaArticle {
+    /// Render the article as an HTML document for printing
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template fails to render
+    pub fn to_html(&self) -> Result<String> {
+        let mut context = Context::new();
+        context.insert("title", &self.title);
+        context.insert("url", &self.url);
+        context.insert("content", &self.content);
+        context.insert("credits", &self.credits);
+        Ok(Tera::one_off(ARTICLE_TEMPLATE, &context, true)?)
+    }
+
+    /// Render the article as Markdown, with its references as footnotes and the image
+    /// credits at the end
+    pub fn to_markdown(&self) -> String {
+        let numbers: HashMap<&str, usize> = self
+            .references
+            .iter()
+            .enumerate()
+            .map(|(index, (id, _))| (id.as_str(), index + 1))
+            .collect();
+        // Footnote marks pass through the conversion as plain words
+        let content = STYLE.replace_all(&self.content, "");
+        let content = REFERENCE_LIST.replace_all(&content, "");
+        // Images inside links would be converted as empty links
+        let content = IMAGE_LINK.replace_all(&content, "$1");
+        let content = FOOTNOTE.replace_all(&content, |captures: &regex::Captures| {
+            match numbers.get(&captures[1]) {
+                Some(number) => format!("WPFOOTNOTE{}END", number),
+                None => String::new(),
+            }
+        });
+        let mut markdown = FOOTNOTE_MARK
+            .replace_all(&mdka::from_html(&content), "[^$1]")
+            .trim()
+            .to_string();
+
+        for (index, (_, html)) in self.references.iter().enumerate() {
+            let text = mdka::from_html(html);
+            let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            markdown.push_str(&format!("\n\n[^{}]: {}", index + 1, text));
+        }
+        if !self.credits.is_empty() {
+            markdown.push_str("\n\n## Image credits\n");
+            for credit in &self.credits {
+                markdown.push_str(&format!("\n- [{}]({})", credit.file, credit.url));
+                if let Some(artist) = &credit.artist {
+                    markdown.push_str(&format!(" by {}", artist));
+                }
+                match (&credit.license, &credit.license_url) {
+                    (Some(license), Some(url)) => {
+                        markdown.push_str(&format!(", [{}]({})", license, url))
+                    }
+                    (Some(license), None) => markdown.push_str(&format!(", {}", license)),
+                    _ => {}
+                }
+            }
+        }
+        markdown.push('\n');
+        markdown
+    }
+}
+
+/// Exports Wikipedia article URLs through the REST API
+#[derive(Debug, Clone)]
+pub struct Wikipedia {
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct CreditsResponse {
+    #[serde(default)]
+    query: Option<CreditsQuery>,
+}
+
+#[derive(Deserialize)]
+struct CreditsQuery {
+    #[serde(default)]
+    pages: Vec<CreditsPage>,
+}
+
+#[derive(Deserialize)]
+struct CreditsPage {
+    title: String,
+    #[serde(default)]
+    imageinfo: Vec<ImageInfo>,
+}
+
+#[derive(Deserialize)]
+struct ImageInfo {
+    descriptionurl: Option<String>,
+    #[serde(default)]
+    extmetadata: HashMap<String, MetadataValue>,
+}
+
+#[derive(Deserialize)]
+struct MetadataValue {
+    value: serde_json::Value,
+}
+
+impl Wikipedia {
+    /// Create an article exporter
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the HTTP client cannot be created
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            client: http_client()?,
+        })
+    }
+
+    /// The article at a Wikipedia URL, or None if the URL is not an article or the
+    /// article cannot be read through the API, in which case the page should be printed
+    /// instead
+    pub async fn capture(&self, url: &str) -> Option<WikipediaArticle> {
+        let article_url = WikipediaUrl::parse(url)?;
+        match self.export(url, &article_url).await {
+            Ok(article) => {
+                info!(
+                    "Exported {} with {} references",
+                    article.title,
+                    article.references.len()
+                );
+                Some(article)
+            }
+            Err(e) => {
+                warn!(
+                    "Failed to export the article at {}, printing the page instead: {}",
+                    url, e
+                );
+                None
+            }
+        }
+    }
+
+    /// The articles linked from the "See also" section of the article at a URL
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the URL is not a Wikipedia article or the article cannot be
+    /// fetched
+    pub async fn see_also(&self, url: &str) -> Result<Vec<String>> {
+        let article_url = WikipediaUrl::parse(url)
+            .ok_or_else(|| anyhow::anyhow!("{} is not a Wikipedia article", url))?;
+        let html = self.fetch_html(&article_url).await?;
+        Ok(see_also_links(&Document::from(html.as_str()), &article_url))
+    }
+
+    async fn fetch_html(&self, article_url: &WikipediaUrl) -> Result<String> {
+        let api_url = article_url.html_url();
+        let response = send_with_retry(|| self.client.get(&api_url)).await?;
+        Ok(checked(response, "Wikipedia article").await?.text().await?)
+    }
+
+    async fn export(&self, url: &str, article_url: &WikipediaUrl) -> Result<WikipediaArticle> {
+        let html = self.fetch_html(article_url).await?;
+        let mut article = parse_article(&html, url, article_url);
+        let files = image_files(&Document::from(html.as_str()));
+        match self.credits(&article_url.host, &files).await {
+            Ok(credits) => article.credits = credits,
+            Err(e) => warn!("Failed to look up the image credits of {}: {}", url, e),
+        }
+        Ok(article)
+    }
+
+    /// The authors and licenses of files, from their description pages
+    async fn credits(&self, host: &str, files: &[String]) -> Result<Vec<ImageCredit>> {
+        let api_url = format!("https://{}/w/api.php", host);
+        let mut credits: HashMap<String, ImageCredit> = HashMap::new();
+        for batch in files.chunks(CREDITS_BATCH_SIZE) {
+            let titles = batch.join("|");
+            let query = [
+                ("action", "query"),
+                ("format", "json"),
+                ("formatversion", "2"),
+                ("prop", "imageinfo"),
+                ("iiprop", "extmetadata|url"),
+                ("iiextmetadatafilter", "Artist|LicenseShortName|LicenseUrl"),
+                ("titles", titles.as_str()),
+            ];
+            let response = send_with_retry(|| self.client.get(&api_url).query(&query)).await?;
+            let response: CreditsResponse = checked(response, "Wikipedia image credits")
+                .await?
+                .json()
+                .await?;
+            for page in response.query.map(|query| query.pages).unwrap_or_default() {
+                let Some(info) = page.imageinfo.into_iter().next() else {
+                    continue;
+                };
+                let metadata = |key: &str| {
+                    info.extmetadata
+                        .get(key)
+                        .and_then(|value| value.value.as_str())
+                        .map(plain_text)
+                        .filter(|value| !value.is_empty())
+                };
+                let credit = ImageCredit {
+                    url: info.descriptionurl.clone().unwrap_or_else(|| {
+                        title_url(host, "/wiki/", &page.title.replace(' ', "_"))
+                    }),
+                    artist: metadata("Artist"),
+                    license: metadata("LicenseShortName"),
+                    license_url: metadata("LicenseUrl"),
+                    file: page.title.clone(),
+                };
+                credits.insert(page.title.replace('_', " "), credit);
+            }
+        }
+        // Credits follow the order the images appear in
+        Ok(files
+            .iter()
+            .filter_map(|file| credits.remove(&file.replace('_', " ")))
+            .collect())
+    }
+}
+
+/// The article body, references, and "See also" links of the REST API's article HTML
+fn parse_article(html: &str, url: &str, article_url: &WikipediaUrl) -> WikipediaArticle {
+    let document = Document::from(html);
+    let title = document
+        .find(Name("title"))
+        .next()
+        .map(|title| plain_text(&title.text()))
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| article_url.title.replace('_', " "));
+    let page_url = article_url.page_url();
+
+    let mut content = String::new();
+    if let Some(body) = document.find(Name("body")).next() {
+        for child in body.children() {
+            extract::write_html(&child, &is_skipped, &mut content);
+        }
+    }
+
+    let references_selector =
+        Selector::parse("ol.references > li").expect("built-in selectors are valid");
+    let text_selector =
+        Selector::parse(".mw-reference-text").expect("built-in selectors are valid");
+    let references = document
+        .find(|node: &Node| references_selector.matches(node))
+        .filter_map(|item| {
+            let id = item.attr("id")?.to_string();
+            let text = item
+                .descendants()
+                .find(|node| text_selector.matches(node))
+                .unwrap_or(item);
+            let mut html = String::new();
+            for child in text.children() {
+                extract::write_html(&child, &is_skipped, &mut html);
+            }
+            Some((id, rewrite_links(&html, &page_url)))
+        })
+        .collect();
+
+    WikipediaArticle {
+        url: url.to_string(),
+        title,
+        content: rewrite_links(&content, &page_url),
+        references,
+        credits: Vec::new(),
+        see_also: see_also_links(&document, article_url),
+    }
+}
+
+fn is_skipped(node: &Node) -> bool {
+    SKIPPED.iter().any(|selector| selector.matches(node))
+}
+
+/// Whether a node is inside a skipped element
+fn in_skipped(node: &Node) -> bool {
+    let mut ancestor = node.parent();
+    while let Some(node) = ancestor {
+        if is_skipped(&node) {
+            return true;
+        }
+        ancestor = node.parent();
+    }
+    false
+}
+
+/// The article links of the "See also" section, as article URLs
+fn see_also_links(document: &Document, article_url: &WikipediaUrl) -> Vec<String> {
+    let Some(section) = document
+        .find(Name("h2"))
+        .find(|heading| {
+            heading
+                .attr("id")
+                .is_some_and(|id| SEE_ALSO_IDS.contains(&id))
+        })
+        .and_then(|heading| heading.parent())
+    else {
+        return Vec::new();
+    };
+    let Ok(base) = Url::parse(&article_url.page_url()) else {
+        return Vec::new();
+    };
+    let mut seen = HashSet::new();
+    section
+        .find(Attr("rel", "mw:WikiLink"))
+        .filter(|link| !in_skipped(link))
+        .filter_map(|link| base.join(link.attr("href")?).ok())
+        .filter_map(|mut link| {
+            link.set_fragment(None);
+            let linked = WikipediaUrl::parse(link.as_str())?;
+            (linked.title != article_url.title).then(|| linked.page_url())
+        })
+        .filter(|link| seen.insert(link.clone()))
+        .collect()
+}
+
+/// Titles of the files shown in an article, in order
+fn image_files(document: &Document) -> Vec<String> {
+    let mut seen = HashSet::new();
+    document
+        .find(Name("img"))
+        .filter(|image| !in_skipped(image))
+        .filter_map(|image| image.attr("resource"))
+        .map(|resource| {
+            percent_decode_str(resource.trim_start_matches("./"))
+                .decode_utf8_lossy()
+                .into_owned()
+        })
+        .filter(|file| seen.insert(file.clone()))
+        .collect()
+}
+
+/// Make links absolute, point footnote links within the document, and drop Parsoid's
+/// bookkeeping attributes
+fn rewrite_links(html: &str, page_url: &str) -> String {
+    let Ok(base) = Url::parse(page_url) else {
+        return html.to_string();
+    };
+    LINK_ATTR
+        .replace_all(html, |captures: &regex::Captures| {
+            let attr = &captures[1];
+            if !matches!(attr, "href" | "src") {
+                return String::new();
+            }
+            let value = captures[2].replace("&amp;", "&");
+            let rewritten = match base.join(&value) {
+                Ok(target) if value.starts_with('#') => {
+                    target.fragment().map(|f| format!("#{}", f))
+                }
+                Ok(target) => match target.fragment() {
+                    Some(fragment) if attr == "href" && target.path() == base.path() => {
+                        Some(format!("#{}", fragment))
+                    }
+                    _ => Some(target.to_string()),
+                },
+                Err(_) => None,
+            };
+            let rewritten = rewritten.unwrap_or(value);
+            format!(" {}=\"{}\"", attr, rewritten.replace('&', "&amp;"))
+        })
+        .into_owned()
+}
+
+/// Text of an HTML snippet, without tags and with collapsed whitespace
+fn plain_text(html: &str) -> String {
+    let text = TAG.replace_all(html, " ");
+    let text = text
+        .replace("&amp;", "&")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// URL of a title under a path of a wiki, with slashes in the title escaped
+fn title_url(host: &str, path: &str, title: &str) -> String {
+    let mut url = Url::parse(&format!("https://{}{}", host, path)).expect("valid wiki URL");
+    if let Ok(mut segments) = url.path_segments_mut() {
+        segments.pop_if_empty().push(title);
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wikipedia_url() {
+        assert_eq!(
+            WikipediaUrl::parse("https://en.m.wikipedia.org/wiki/AC%2FDC#History"),
+            Some(WikipediaUrl {
+                host: "en.wikipedia.org".to_string(),
+                title: "AC/DC".to_string(),
+                revision: None,
+            })
+        );
+        let url =
+            WikipediaUrl::parse("https://de.wikipedia.org/w/index.php?title=C%2B%2B&oldid=123")
+                .unwrap();
+        assert_eq!((url.title.as_str(), url.revision), ("C++", Some(123)));
+        assert_eq!(
+            url.html_url(),
+            "https://de.wikipedia.org/api/rest_v1/page/html/C++/123"
+        );
+        assert_eq!(
+            WikipediaUrl::parse("https://en.wikipedia.org/wiki/Star_Wars:_Episode_IV")
+                .unwrap()
+                .page_url(),
+            "https://en.wikipedia.org/wiki/Star_Wars:_Episode_IV"
+        );
+        assert_eq!(
+            WikipediaUrl::parse("https://en.wikipedia.org/wiki/Special:Random"),
+            None
+        );
+        assert_eq!(WikipediaUrl::parse("https://www.wikipedia.org/"), None);
+        assert_eq!(WikipediaUrl::parse("https://example.org/wiki/Rust"), None);
+    }
+
+    const ARTICLE: &str = r##"<html><head><title>Rust (programming language)</title>
+        <base href="//en.wikipedia.org/wiki/"></head>
+        <body>
+        <section data-mw-section-id="0">
+            <div class="hatnote">For the fungus, see Rust (fungus).</div>
+            <table class="infobox"><tr><th>Paradigm</th><td><a rel="mw:WikiLink" href="./Multi-paradigm">Multi-paradigm</a></td></tr></table>
+            <p data-mw='{"x":1}'>Rust is a language.<sup class="mw-ref reference" id="cite_ref-1" typeof="mw:Extension/ref"><a href="./Rust_(programming_language)#cite_note-1"><span class="mw-reflink-text">[1]</span></a></sup></p>
+            <figure typeof="mw:File/Thumb"><a href="./File:Ferris.svg"><img resource="./File:Ferris.svg" src="//upload.wikimedia.org/ferris.png" srcset="//upload.wikimedia.org/ferris2.png 2x"></a><figcaption>Ferris</figcaption></figure>
+        </section>
+        <section data-mw-section-id="1"><h2 id="See_also">See also</h2>
+            <ul><li><a rel="mw:WikiLink" href="./Go_(programming_language)">Go</a></li>
+            <li><a rel="mw:WikiLink" href="./Rust_(programming_language)#History">History</a></li></ul>
+            <div class="portalbox"><a rel="mw:WikiLink" href="./Portal:Computer_programming">Portal</a></div>
+        </section>
+        <section data-mw-section-id="2"><h2 id="References">References</h2>
+            <ol class="mw-references references"><li id="cite_note-1"><span class="mw-cite-backlink"><a href="./Rust_(programming_language)#cite_ref-1">↑</a></span> <span class="mw-reference-text">Klabnik, <i>The Book</i>.</span></li></ol>
+        </section>
+        <div class="navbox">Programming languages</div>
+        </body></html>"##;
+
+    #[test]
+    fn test_parse_article() {
+        let url = "https://en.wikipedia.org/wiki/Rust_(programming_language)";
+        let article = parse_article(ARTICLE, url, &WikipediaUrl::parse(url).unwrap());
+        assert_eq!(article.title, "Rust (programming language)");
+        assert!(article.content.contains(r##"<a href="#cite_note-1">"##));
+        assert!(article.content.contains(r##"<a href="#cite_ref-1">"##));
+        assert!(
+            article
+                .content
+                .contains(r#"href="https://en.wikipedia.org/wiki/Multi-paradigm""#)
+        );
+        assert!(
+            article
+                .content
+                .contains(r#"src="https://upload.wikimedia.org/ferris.png""#)
+        );
+        assert!(article.content.contains(r#"<table class="infobox">"#));
+        for removed in [
+            "data-mw=",
+            "srcset",
+            "typeof",
+            "fungus",
+            "Programming languages",
+        ] {
+            assert!(!article.content.contains(removed), "{}", removed);
+        }
+        assert_eq!(
+            article.references,
+            [(
+                "cite_note-1".to_string(),
+                "Klabnik, <i>The Book</i>.".to_string()
+            )]
+        );
+        assert_eq!(
+            article.see_also,
+            ["https://en.wikipedia.org/wiki/Go_(programming_language)"]
+        );
+        assert_eq!(image_files(&Document::from(ARTICLE)), ["File:Ferris.svg"]);
+
+        let markdown = article.to_markdown();
+        assert!(markdown.contains("Rust is a language.[^1]"), "{}", markdown);
+        assert!(
+            markdown.contains("![](https://upload.wikimedia.org/ferris.png)"),
+            "{}",
+            markdown
+        );
+        assert!(
+            markdown.contains("[^1]: Klabnik, _The Book_"),
+            "{}",
+            markdown
+        );
+    }
+}